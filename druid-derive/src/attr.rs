@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! parsing #[druid(attributes)]
+//! parsing #[druid(attributes)], and #[data(attributes)]
 
 use proc_macro2::{Ident, Literal, Span, TokenStream, TokenTree};
 use quote::{quote, quote_spanned};
@@ -20,6 +20,8 @@ use syn::spanned::Spanned;
 use syn::{Error, ExprPath, Meta, NestedMeta};
 
 const BASE_ATTR_PATH: &str = "druid";
+/// An alternative to `druid` for the attributes of `derive(Data)`.
+const DATA_ATTR_PATH: &str = "data";
 const IGNORE_ATTR_PATH: &str = "ignore";
 const SAME_FN_ATTR_PATH: &str = "same_fn";
 const LENS_NAME_OVERRIDE_ATTR_PATH: &str = "lens_name";
@@ -83,10 +85,6 @@ impl Fields {
         Ok(Fields { kind, fields })
     }
 
-    pub fn len(&self) -> usize {
-        self.fields.len()
-    }
-
     pub fn iter(&self) -> impl Iterator<Item = &Field> {
         self.fields.iter()
     }
@@ -108,8 +106,9 @@ impl Field {
         for attr in field
             .attrs
             .iter()
-            .filter(|attr| attr.path.is_ident(BASE_ATTR_PATH))
+            .filter(|attr| attr.path.is_ident(BASE_ATTR_PATH) || attr.path.is_ident(DATA_ATTR_PATH))
         {
+            let is_data_attr = attr.path.is_ident(DATA_ATTR_PATH);
             match attr.parse_meta()? {
                 Meta::List(meta) => {
                     for nested in meta.nested.iter() {
@@ -133,7 +132,8 @@ impl Field {
                                 same_fn = Some(path);
                             }
                             NestedMeta::Meta(Meta::NameValue(meta))
-                                if meta.path.is_ident(LENS_NAME_OVERRIDE_ATTR_PATH) =>
+                                if meta.path.is_ident(LENS_NAME_OVERRIDE_ATTR_PATH)
+                                    && !is_data_attr =>
                             {
                                 if lens_name_override.is_some() {
                                     return Err(Error::new(meta.span(), "Duplicate attribute"));
@@ -146,12 +146,10 @@ impl Field {
                         }
                     }
                 }
-                other => {
-                    return Err(Error::new(
-                        other.span(),
-                        "Expected attribute list (the form #[druid(one, two)])",
-                    ))
-                }
+                other => return Err(Error::new(
+                    other.span(),
+                    "Expected attribute list (the form #[druid(one, two)] or #[data(one, two)])",
+                )),
             }
         }
         Ok(Field {
@@ -14,10 +14,10 @@
 
 //! The implementation for #[derive(Data)]
 
-use crate::attr::{Field, FieldKind, Fields};
+use crate::attr::{Field, Fields};
 
-use quote::{quote, quote_spanned};
-use syn::{spanned::Spanned, Data, DataEnum, DataStruct};
+use quote::quote;
+use syn::{parse_quote, spanned::Spanned, Data, DataEnum, DataStruct};
 
 pub(crate) fn derive_data_impl(
     input: syn::DeriveInput,
@@ -36,13 +36,13 @@ fn derive_struct(
     input: &syn::DeriveInput,
     s: &DataStruct,
 ) -> Result<proc_macro2::TokenStream, syn::Error> {
-    let generics_bounds = generics_bounds(&input.generics);
-    let generics = &input.generics;
+    let generics = generics_with_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let ty = &input.ident;
     let fields = Fields::parse_ast(&s.fields)?;
 
-    let diff = if fields.len() > 0 {
+    let diff = if fields.iter().any(|f| !f.ignore) {
         let same_fns = fields
             .iter()
             .filter(|f| !f.ignore)
@@ -54,7 +54,7 @@ fn derive_struct(
     };
 
     let res = quote! {
-        impl<#generics_bounds> druid::Data for #ty #generics {
+        impl #impl_generics druid::Data for #ty #ty_generics #where_clause {
             fn same(&self, other: &Self) -> bool {
                 #diff
             }
@@ -81,13 +81,12 @@ fn derive_enum(
     s: &DataEnum,
 ) -> Result<proc_macro2::TokenStream, syn::Error> {
     let ty = &input.ident;
+    let generics = generics_with_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     if is_c_style_enum(&s) {
-        let generics_bounds = generics_bounds(&input.generics);
-        let generics = &input.generics;
-
         let res = quote! {
-            impl<#generics_bounds> ::druid::Data for #ty #generics {
+            impl #impl_generics ::druid::Data for #ty #ty_generics #where_clause {
                 fn same(&self, other: &Self) -> bool { self == other }
             }
         };
@@ -99,6 +98,7 @@ fn derive_enum(
         .iter()
         .map(|variant| {
             let fields = Fields::parse_ast(&variant.fields)?;
+            let variant_fields = &variant.fields;
             let variant = &variant.ident;
 
             // the various inner `same()` calls, to the right of the match arm.
@@ -112,13 +112,29 @@ fn derive_enum(
                     quote!( #same_fn(#var_left, #var_right) )
                 })
                 .collect();
+            let same = if tests.is_empty() {
+                quote!(true)
+            } else {
+                quote!( #( #tests )&&* )
+            };
+
+            // ignored fields are bound to `_`, so they don't need to be used
+            let binding = |field: &Field, prefix: &str| {
+                if field.ignore {
+                    quote!(_)
+                } else {
+                    let var = ident_from_str(&format!("{}{}", prefix, field.ident_string()));
+                    quote!( #var )
+                }
+            };
 
-            if let FieldKind::Named = fields.kind {
+            match variant_fields {
+                syn::Fields::Named(_) => {
                 let lefts: Vec<_> = fields
                     .iter()
                     .map(|field| {
                         let ident = field.ident_tokens();
-                        let var = ident_from_str(&format!("__self_{}", field.ident_string()));
+                        let var = binding(field, "__self_");
                         quote!( #ident: #var )
                     })
                     .collect();
@@ -126,46 +142,42 @@ fn derive_enum(
                     .iter()
                     .map(|field| {
                         let ident = field.ident_tokens();
-                        let var = ident_from_str(&format!("__other_{}", field.ident_string()));
+                        let var = binding(field, "__other_");
                         quote!( #ident: #var )
                     })
                     .collect();
 
                 Ok(quote! {
                     (#ty :: #variant { #( #lefts ),* }, #ty :: #variant { #( #rights ),* }) => {
-                        #( #tests )&&*
+                        #same
                     }
                 })
-            } else {
+                }
+                syn::Fields::Unnamed(_) => {
                 let vars_left: Vec<_> = fields
                     .iter()
-                    .map(|field| ident_from_str(&format!("__self_{}", field.ident_string())))
+                    .map(|field| binding(field, "__self_"))
                     .collect();
                 let vars_right: Vec<_> = fields
                     .iter()
-                    .map(|field| ident_from_str(&format!("__other_{}", field.ident_string())))
+                    .map(|field| binding(field, "__other_"))
                     .collect();
 
-                if fields.iter().filter(|field| !field.ignore).count() > 0 {
-                    Ok(quote! {
-                        ( #ty :: #variant( #(#vars_left),* ),  #ty :: #variant( #(#vars_right),* )) => {
-                            #( #tests )&&*
-                        }
-                    })
-                } else {
-                    Ok(quote! {
-                       ( #ty :: #variant ,  #ty :: #variant ) => { true }
-                    })
+                Ok(quote! {
+                    ( #ty :: #variant( #(#vars_left),* ),  #ty :: #variant( #(#vars_right),* )) => {
+                        #same
+                    }
+                })
                 }
+                syn::Fields::Unit => Ok(quote! {
+                   ( #ty :: #variant ,  #ty :: #variant ) => { true }
+                }),
             }
         })
         .collect::<Result<Vec<proc_macro2::TokenStream>, syn::Error>>()?;
 
-    let generics_bounds = generics_bounds(&input.generics);
-    let generics = &input.generics;
-
     let res = quote! {
-        impl<#generics_bounds> ::druid::Data for #ty #generics {
+        impl #impl_generics ::druid::Data for #ty #ty_generics #where_clause {
             fn same(&self, other: &Self) -> bool {
                 match (self, other) {
                     #( #cases ),*
@@ -178,15 +190,11 @@ fn derive_enum(
     Ok(res)
 }
 
-fn generics_bounds(generics: &syn::Generics) -> proc_macro2::TokenStream {
-    let res = generics.params.iter().map(|gp| {
-        use syn::GenericParam::*;
-        match gp {
-            Type(ty) => quote_spanned!(ty.span()=> #ty : ::druid::Data),
-            Lifetime(lf) => quote!(#lf),
-            Const(cst) => quote!(#cst),
-        }
-    });
-
-    quote!( #( #res, )* )
+/// The generics, with a `Data` bound added to each type parameter.
+fn generics_with_bounds(generics: &syn::Generics) -> syn::Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::druid::Data));
+    }
+    generics
 }
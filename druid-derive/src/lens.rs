@@ -73,17 +73,27 @@ fn derive_struct(input: &syn::DeriveInput) -> Result<proc_macro2::TokenStream, s
         }
     });
 
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // The lens types themselves are not generic; any type parameters of the
+    // struct appear in the `Lens` trait parameters instead.
     let impls = fields.iter().map(|f| {
         let field_name = &f.ident.unwrap_named();
         let field_ty = &f.ty;
 
         quote! {
-            impl druid::Lens<#ty, #field_ty> for #twizzled_name::#field_name {
-                fn with<V, F: FnOnce(&#field_ty) -> V>(&self, data: &#ty, f: F) -> V {
+            impl #impl_generics druid::Lens<#ty #ty_generics, #field_ty>
+                for #twizzled_name::#field_name #where_clause
+            {
+                fn with<V, F: FnOnce(&#field_ty) -> V>(&self, data: &#ty #ty_generics, f: F) -> V {
                     f(&data.#field_name)
                 }
 
-                fn with_mut<V, F: FnOnce(&mut #field_ty) -> V>(&self, data: &mut #ty, f: F) -> V {
+                fn with_mut<V, F: FnOnce(&mut #field_ty) -> V>(
+                    &self,
+                    data: &mut #ty #ty_generics,
+                    f: F,
+                ) -> V {
                     f(&mut data.#field_name)
                 }
             }
@@ -100,8 +110,6 @@ fn derive_struct(input: &syn::DeriveInput) -> Result<proc_macro2::TokenStream, s
         }
     });
 
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
-
     let expanded = quote! {
         pub mod #twizzled_name {
             #(#defs)*
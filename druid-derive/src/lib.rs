@@ -24,7 +24,38 @@ use proc_macro::TokenStream;
 use syn::parse_macro_input;
 
 /// Generates implementations of the `Data` trait.
-#[proc_macro_derive(Data, attributes(druid))]
+///
+/// Structs are compared field by field, and enums by variant and then field
+/// by field. Fields can be annotated with `#[data(ignore)]` to skip them, and
+/// with `#[data(same_fn = "path")]` to compare them with a custom function;
+/// these are also accepted in a `#[druid(...)]` attribute.
+///
+/// Each type parameter of the type is required to be `Data`.
+///
+/// `Data` can't be derived for unions:
+///
+/// ```compile_fail
+/// use druid::Data;
+///
+/// #[derive(Clone, Copy, Data)]
+/// union Number {
+///     int: u32,
+///     float: f32,
+/// }
+/// ```
+///
+/// The lens name attribute only applies in a `#[druid(...)]` attribute:
+///
+/// ```compile_fail
+/// use druid::Data;
+///
+/// #[derive(Clone, Data)]
+/// struct Item {
+///     #[data(lens_name = "item_name")]
+///     name: String,
+/// }
+/// ```
+#[proc_macro_derive(Data, attributes(druid, data))]
 pub fn derive_data(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as syn::DeriveInput);
     data::derive_data_impl(input)
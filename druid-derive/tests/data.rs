@@ -71,3 +71,74 @@ fn test_data_derive_same() {
         })
     );
 }
+
+#[test]
+fn generics() {
+    #[derive(Clone, Data)]
+    struct Wrapper<T> {
+        inner: T,
+    }
+
+    #[derive(Clone, Data)]
+    struct Bounded<T: Default>
+    where
+        T: std::fmt::Debug,
+    {
+        inner: T,
+        count: usize,
+    }
+
+    #[derive(Clone, Data)]
+    enum Either<L, R> {
+        Left(L),
+        Right { value: R },
+    }
+
+    let one = Wrapper { inner: 1u8 };
+    assert!(one.same(&Wrapper { inner: 1 }));
+    assert!(!one.same(&Wrapper { inner: 2 }));
+
+    let one = Bounded {
+        inner: "a".to_string(),
+        count: 1,
+    };
+    assert!(one.same(&one.clone()));
+    assert!(!one.same(&Bounded {
+        inner: "b".to_string(),
+        count: 1,
+    }));
+
+    let left: Either<u8, bool> = Either::Left(1);
+    let right: Either<u8, bool> = Either::Right { value: true };
+    assert!(left.same(&Either::Left(1)));
+    assert!(!left.same(&right));
+    assert!(right.same(&Either::Right { value: true }));
+    assert!(!right.same(&Either::Right { value: false }));
+}
+
+#[test]
+fn enum_variants_without_compared_fields() {
+    #[derive(Clone, Data)]
+    enum Mixed {
+        Unit,
+        EmptyTuple(),
+        EmptyNamed {},
+        Ignored(#[data(ignore)] String),
+        IgnoredNamed {
+            #[data(ignore)]
+            name: String,
+        },
+        Value(u32),
+    }
+
+    assert!(Mixed::Unit.same(&Mixed::Unit));
+    assert!(Mixed::EmptyTuple().same(&Mixed::EmptyTuple()));
+    assert!(Mixed::EmptyNamed {}.same(&Mixed::EmptyNamed {}));
+    assert!(!Mixed::Unit.same(&Mixed::EmptyTuple()));
+    assert!(Mixed::Ignored("a".into()).same(&Mixed::Ignored("b".into())));
+    let named = Mixed::IgnoredNamed { name: "a".into() };
+    assert!(named.same(&Mixed::IgnoredNamed { name: "b".into() }));
+    assert!(!named.same(&Mixed::Ignored("a".into())));
+    assert!(Mixed::Value(1).same(&Mixed::Value(1)));
+    assert!(!Mixed::Value(1).same(&Mixed::Value(2)));
+}
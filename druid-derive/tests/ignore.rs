@@ -58,3 +58,30 @@ fn enums() {
     assert!(name_one.same(&name_two));
     assert!(tuple_one.same(&tuple_two));
 }
+
+#[test]
+fn data_attribute() {
+    #[derive(Clone, Data)]
+    struct Point {
+        x: f64,
+        #[data(ignore)]
+        y: f64,
+    }
+    let p1 = Point { x: 0.0, y: 1.0 };
+    let p2 = Point { x: 0.0, y: 9.0 };
+    assert!(p1.same(&p2));
+    assert!(!p1.same(&Point { x: 1.0, y: 1.0 }));
+}
+
+#[test]
+fn all_fields_ignored() {
+    #[derive(Clone, Data)]
+    struct Cache {
+        #[druid(ignore)]
+        hits: usize,
+        #[data(ignore)]
+        misses: usize,
+    }
+    let one = Cache { hits: 1, misses: 2 };
+    assert!(one.same(&Cache { hits: 3, misses: 4 }));
+}
@@ -0,0 +1,102 @@
+//! testing the Lens derive on generic structs, and composing derived lenses
+
+use druid::{Lens, LensExt};
+
+#[test]
+fn generics() {
+    #[derive(Lens)]
+    struct Wrapper<T> {
+        x: T,
+        count: usize,
+    }
+
+    let mut wrap = Wrapper {
+        x: String::from("hello"),
+        count: 2,
+    };
+    assert_eq!(Wrapper::<String>::x.get(&wrap), "hello");
+
+    Wrapper::<String>::count.put(&mut wrap, 5);
+    assert_eq!(wrap.count, 5);
+    assert_eq!(wrap.x, "hello");
+}
+
+#[test]
+fn generics_with_bounds() {
+    #[derive(Lens)]
+    struct Bounded<T: Clone>
+    where
+        T: Default,
+    {
+        value: T,
+    }
+
+    let bounded = Bounded { value: 42u32 };
+    assert_eq!(Bounded::<u32>::value.get(&bounded), 42);
+}
+
+#[test]
+fn lifetimes() {
+    #[derive(Lens)]
+    struct Borrowed<'a> {
+        name: &'a str,
+    }
+
+    let borrowed = Borrowed { name: "druid" };
+    assert_eq!(Borrowed::name.get(&borrowed), "druid");
+}
+
+#[test]
+fn method_like_names() {
+    #[derive(Lens)]
+    struct Shadow {
+        #[druid(lens_name = "len_lens")]
+        len: usize,
+    }
+
+    impl Shadow {
+        fn len(&self) -> usize {
+            self.len
+        }
+    }
+
+    let shadow = Shadow { len: 3 };
+    assert_eq!(Shadow::len_lens.get(&shadow), shadow.len());
+}
+
+#[test]
+fn composition() {
+    #[derive(Lens)]
+    struct Inner {
+        flag: bool,
+        values: Vec<u32>,
+    }
+
+    #[derive(Lens)]
+    struct Outer {
+        inner: Inner,
+        boxed: Box<u32>,
+    }
+
+    let mut outer = Outer {
+        inner: Inner {
+            flag: false,
+            values: vec![1, 2, 3],
+        },
+        boxed: Box::new(3),
+    };
+
+    let flag = Outer::inner.then(Inner::flag);
+    flag.put(&mut outer, true);
+    assert!(outer.inner.flag);
+
+    let second = Outer::inner.then(Inner::values).index(1);
+    assert_eq!(second.get(&outer), 2);
+    second.put(&mut outer, 20);
+    assert_eq!(outer.inner.values, vec![1, 20, 3]);
+
+    let doubled = Outer::boxed.deref().map(|x| x * 2, |x, y| *x = y / 2);
+    assert_eq!(doubled.get(&outer), 6);
+    doubled.put(&mut outer, 10);
+    assert_eq!(*outer.boxed, 5);
+}
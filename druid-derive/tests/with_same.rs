@@ -70,3 +70,21 @@ fn enums() {
 fn same_sign(one: &f64, two: &f64) -> bool {
     one.signum() == two.signum()
 }
+
+#[test]
+fn data_attribute() {
+    #[derive(Clone, Data)]
+    struct Bits {
+        #[data(same_fn = "same_bits")]
+        value: f64,
+    }
+
+    // positive and negative zero are equal, but don't have the same bits
+    let zero = Bits { value: 0.0 };
+    assert!(!zero.same(&Bits { value: -0.0 }));
+    assert!(zero.same(&Bits { value: 0.0 }));
+}
+
+fn same_bits(one: &f64, two: &f64) -> bool {
+    one.to_bits() == two.to_bits()
+}
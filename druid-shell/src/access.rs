@@ -0,0 +1,188 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The accessibility tree of a window, which screen readers present.
+//!
+//! The application builds the tree and gives it to the window with
+//! [`WindowHandle::update_access_tree`]; each platform exposes it through its
+//! own accessibility API. This is UI Automation on Windows; the other
+//! platforms don't expose the tree yet.
+//!
+//! [`WindowHandle::update_access_tree`]: ../struct.WindowHandle.html#method.update_access_tree
+
+use crate::kurbo::{Point, Rect};
+
+/// What a node in the accessibility tree is, which tells screen readers how
+/// to present it and what can be done with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// The window itself, at the root of the tree.
+    Window,
+    /// A group of other nodes, with no meaning of its own.
+    Group,
+    /// Text that can't be edited.
+    Label,
+    /// Something that does an action when it is pressed.
+    Button,
+    /// Something that is on or off, with its state in `toggled`.
+    CheckBox,
+    /// Editable text, with the text in `value`.
+    TextBox,
+}
+
+/// A node in the accessibility tree.
+#[derive(Clone, Debug)]
+pub struct AccessNode {
+    /// Identifies the node from one version of the tree to the next; it is
+    /// unique within the window.
+    pub id: u64,
+    /// What the node is.
+    pub role: Role,
+    /// The name of the node, which screen readers read out.
+    pub label: Option<String>,
+    /// The value of the node, such as the text of a text box.
+    pub value: Option<String>,
+    /// Whether the node is on, for nodes that can be toggled.
+    pub toggled: Option<bool>,
+    /// Whether the node can take the keyboard focus.
+    pub focusable: bool,
+    /// Whether the node has the keyboard focus.
+    pub focused: bool,
+    /// Where the node is, in px units, relative to the window's content area.
+    pub rect: Rect,
+    /// The nodes inside this one, in the order they are read.
+    pub children: Vec<AccessNode>,
+}
+
+/// A change to the accessibility tree that screen readers should be told
+/// about, as they do not watch the whole tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessEvent {
+    /// The node with this id got the keyboard focus.
+    FocusChanged(u64),
+    /// The label of the node with this id changed.
+    LabelChanged(u64),
+    /// The value of the node with this id changed.
+    ValueChanged(u64),
+    /// The node with this id was toggled.
+    ToggleChanged(u64),
+    /// Nodes were added to or removed from the tree.
+    StructureChanged,
+}
+
+impl AccessNode {
+    /// Create a node with no label, value or children.
+    pub fn new(id: u64, role: Role, rect: Rect) -> AccessNode {
+        AccessNode {
+            id,
+            role,
+            label: None,
+            value: None,
+            toggled: None,
+            focusable: false,
+            focused: false,
+            rect,
+            children: Vec::new(),
+        }
+    }
+
+    /// This node and all of its descendants, each before its children.
+    pub fn nodes(&self) -> Vec<&AccessNode> {
+        let mut nodes = vec![self];
+        let mut idx = 0;
+        // each node's children go right after it
+        while idx < nodes.len() {
+            let node = nodes[idx];
+            idx += 1;
+            for (i, child) in node.children.iter().enumerate() {
+                nodes.insert(idx + i, child);
+            }
+        }
+        nodes
+    }
+
+    /// The node with this id, if it is this node or one of its descendants.
+    pub fn find(&self, id: u64) -> Option<&AccessNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+
+    /// The parent of the node with this id, if it is a descendant of this
+    /// node.
+    pub fn parent_of(&self, id: u64) -> Option<&AccessNode> {
+        if self.children.iter().any(|child| child.id == id) {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.parent_of(id))
+    }
+
+    /// The deepest node at `point`, if it is in this node.
+    pub fn node_at(&self, point: Point) -> Option<&AccessNode> {
+        if !self.rect.contains(point) {
+            return None;
+        }
+        // later children are painted over earlier ones
+        let child = self.children.iter().rev().find_map(|c| c.node_at(point));
+        Some(child.unwrap_or(self))
+    }
+
+    /// The node that has the keyboard focus, if there is one.
+    pub fn focused(&self) -> Option<&AccessNode> {
+        if self.focused {
+            return Some(self);
+        }
+        self.children.iter().find_map(AccessNode::focused)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> AccessNode {
+        let mut ok = AccessNode::new(2, Role::Button, Rect::new(10., 10., 60., 30.));
+        ok.label = Some("OK".into());
+        ok.focused = true;
+        let label = AccessNode::new(4, Role::Label, Rect::new(10., 40., 60., 60.));
+        let mut group = AccessNode::new(3, Role::Group, Rect::new(0., 40., 100., 100.));
+        group.children.push(label);
+        let mut root = AccessNode::new(1, Role::Window, Rect::new(0., 0., 100., 100.));
+        root.children = vec![ok, group];
+        root
+    }
+
+    #[test]
+    fn nodes_are_listed_depth_first() {
+        let root = tree();
+        let ids: Vec<_> = root.nodes().iter().map(|node| node.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(root.find(4).map(|node| node.role), Some(Role::Label));
+        assert_eq!(root.parent_of(4).map(|node| node.id), Some(3));
+        assert!(root.parent_of(1).is_none());
+        assert_eq!(root.focused().map(|node| node.id), Some(2));
+    }
+
+    #[test]
+    fn node_at_finds_the_deepest_node() {
+        let root = tree();
+        let at = |x, y| root.node_at(Point::new(x, y)).map(|node| node.id);
+        assert_eq!(at(20., 20.), Some(2));
+        assert_eq!(at(20., 50.), Some(4));
+        assert_eq!(at(80., 50.), Some(3));
+        assert_eq!(at(80., 20.), Some(1));
+        assert_eq!(at(200., 20.), None);
+    }
+}
@@ -14,8 +14,11 @@
 
 //! The top-level application type.
 
+use std::time::Instant;
+
 use crate::clipboard::Clipboard;
 use crate::platform::application as platform;
+use crate::window::{IdleToken, TimerToken};
 
 /// A top-level handler that is not associated with any window.
 ///
@@ -24,16 +27,55 @@ use crate::platform::application as platform;
 ///
 /// # Note
 ///
-/// This is currently very limited in its functionality, and is currently
-/// designed to address a single case, which is handling menu commands when
-/// no window is open.
+/// This is currently very limited in its functionality. It handles menu
+/// commands when no window is open, and the timers and idle callbacks that
+/// are requested from the [`Application`] rather than from a window; these
+/// work before any window has been created.
 ///
 /// It is possible that this will expand to cover additional functionality
 /// in the future.
+///
+/// [`Application`]: struct.Application.html
 pub trait AppHandler {
     /// Called when a menu item is selected.
     #[allow(unused_variables)]
     fn command(&mut self, id: u32) {}
+
+    /// Called when a timer requested with [`Application::request_timer`]
+    /// fires.
+    ///
+    /// [`Application::request_timer`]: struct.Application.html#method.request_timer
+    #[allow(unused_variables)]
+    fn timer(&mut self, token: TimerToken) {}
+
+    /// Called when an idle token is requested with
+    /// [`AppIdleHandle::schedule_idle`].
+    ///
+    /// [`AppIdleHandle::schedule_idle`]: struct.AppIdleHandle.html#method.schedule_idle
+    #[allow(unused_variables)]
+    fn idle(&mut self, token: IdleToken) {}
+}
+
+/// A handle that can be moved between threads, and used to wake the
+/// [`AppHandler`] from the run loop.
+///
+/// Unlike a window's [`IdleHandle`], this exists as soon as the
+/// [`Application`] does.
+///
+/// [`AppHandler`]: trait.AppHandler.html
+/// [`IdleHandle`]: struct.IdleHandle.html
+/// [`Application`]: struct.Application.html
+#[derive(Clone)]
+pub struct AppIdleHandle(platform::AppIdleHandle);
+
+impl AppIdleHandle {
+    /// Request a callback from the run loop. Your `AppHandler::idle` method
+    /// will be called with the `token` that was passed in.
+    ///
+    /// If the run loop has not started yet, the callback happens once it has.
+    pub fn schedule_idle(&mut self, token: IdleToken) {
+        self.0.add_idle_token(token)
+    }
 }
 
 //TODO: we may want to make the user create an instance of this (Application::global()?)
@@ -49,6 +91,10 @@ impl Application {
     /// Start the runloop.
     ///
     /// This will block the current thread until the program has finished executing.
+    /// The application keeps running until [`Application::quit`] is called,
+    /// even if it has no windows.
+    ///
+    /// [`Application::quit`]: #method.quit
     pub fn run(&mut self) {
         self.0.run()
     }
@@ -58,6 +104,28 @@ impl Application {
         platform::Application::quit()
     }
 
+    /// Returns a handle for waking the [`AppHandler`] from other threads.
+    ///
+    /// This must be called on the main thread, and returns `None` if the
+    /// application has not been created yet.
+    ///
+    /// [`AppHandler`]: trait.AppHandler.html
+    pub fn get_idle_handle() -> Option<AppIdleHandle> {
+        platform::Application::get_idle_handle().map(AppIdleHandle)
+    }
+
+    /// Request a timer that is not associated with any window.
+    ///
+    /// When the deadline passes, the [`AppHandler`]'s `timer` method is
+    /// called with the returned token. This must be called on the main thread,
+    /// after the application has been created; it can be called before the
+    /// run loop starts.
+    ///
+    /// [`AppHandler`]: trait.AppHandler.html
+    pub fn request_timer(deadline: Instant) -> TimerToken {
+        platform::Application::request_timer(deadline)
+    }
+
     // TODO: do these two go in some kind of PlatformExt trait?
     /// Hide the application this window belongs to. (cmd+H)
     pub fn hide() {
@@ -84,4 +152,17 @@ impl Application {
     pub fn get_locale() -> String {
         platform::Application::get_locale()
     }
+
+    /// Returns `true` if the user has asked the system to reduce motion in
+    /// the user interface.
+    ///
+    /// This is "Show animations in Windows" on Windows, "Reduce motion" on
+    /// macOS, and the `gtk-enable-animations` setting with GTK. When it
+    /// changes, each window's handler is told with
+    /// [`WinHandler::system_settings_changed`].
+    ///
+    /// [`WinHandler::system_settings_changed`]: trait.WinHandler.html#method.system_settings_changed
+    pub fn prefers_reduced_motion() -> bool {
+        platform::Application::prefers_reduced_motion()
+    }
 }
@@ -13,12 +13,17 @@
 // limitations under the License.
 
 //! Interacting with the system pasteboard/clipboard.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
 pub use crate::platform::clipboard as platform;
 
 /// A handle to the system clipboard.
 ///
 /// To get access to the global clipboard, call [`Application::clipboard()`].
-///
+/// A clipboard that is private to the application, such as for tests, can
+/// be created with [`Clipboard::in_memory`].
 ///
 /// # Working with text
 ///
@@ -119,6 +124,7 @@ pub use crate::platform::clipboard as platform;
 /// ```
 ///
 /// [`Application::clipboard()`]: struct.Application.html#method.clipboard
+/// [`Clipboard::in_memory`]: struct.Clipboard.html#method.in_memory
 /// [`Clipboard::put_string`]: struct.Clipboard.html#method.put_string
 /// [`Clipboard::get_string`]: struct.Clipboard.html#method.get_string
 /// [`FormatId`]: type.FormatId.html
@@ -126,28 +132,65 @@ pub use crate::platform::clipboard as platform;
 /// [MIME types]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types
 /// [`ClipboardFormat`]: struct.ClipboardFormat.html
 #[derive(Debug, Clone)]
-pub struct Clipboard(platform::Clipboard);
+pub struct Clipboard(Backend);
+
+#[derive(Debug, Clone)]
+enum Backend {
+    System(platform::Clipboard),
+    /// The contents of an in-memory clipboard, shared by its clones.
+    Memory(Rc<RefCell<Vec<ClipboardFormat>>>),
+}
 
 impl Clipboard {
+    /// Create a clipboard that holds its contents in memory, instead of
+    /// using the system clipboard.
+    ///
+    /// The contents are shared between the clones of the returned
+    /// `Clipboard`, and are not visible to other applications.
+    pub fn in_memory() -> Clipboard {
+        Clipboard(Backend::Memory(Default::default()))
+    }
+
     /// Put a string onto the system clipboard.
     pub fn put_string(&mut self, s: impl AsRef<str>) {
-        self.0.put_string(s);
+        match &mut self.0 {
+            Backend::System(clipboard) => clipboard.put_string(s),
+            Backend::Memory(contents) => {
+                *contents.borrow_mut() = vec![ClipboardFormat::from(s.as_ref())];
+            }
+        }
     }
 
     /// Put multi-format data on the system clipboard.
     pub fn put_formats(&mut self, formats: &[ClipboardFormat]) {
-        self.0.put_formats(formats)
+        match &mut self.0 {
+            Backend::System(clipboard) => clipboard.put_formats(formats),
+            Backend::Memory(contents) => *contents.borrow_mut() = formats.to_owned(),
+        }
     }
 
     /// Get a string from the system clipboard, if one is available.
     pub fn get_string(&self) -> Option<String> {
-        self.0.get_string()
+        match &self.0 {
+            Backend::System(clipboard) => clipboard.get_string(),
+            Backend::Memory(_) => self
+                .get_format(ClipboardFormat::TEXT)
+                .and_then(|data| String::from_utf8(data).ok()),
+        }
     }
 
     /// Given a list of supported clipboard types, returns the supported type which has
     /// highest priority on the system clipboard, or `None` if no types are supported.
     pub fn preferred_format(&self, formats: &[FormatId]) -> Option<FormatId> {
-        self.0.preferred_format(formats)
+        match &self.0 {
+            Backend::System(clipboard) => clipboard.preferred_format(formats),
+            // the formats were put on the clipboard in order of priority
+            Backend::Memory(contents) => contents
+                .borrow()
+                .iter()
+                .find_map(|item| formats.iter().find(|id| **id == item.identifier))
+                .copied(),
+        }
     }
 
     /// Return data in a given format, if available.
@@ -158,14 +201,28 @@ impl Clipboard {
     /// [`Clipboard::preferred_format`]: struct.Clipboard.html#method.preferred_format
     /// [`FormatId`]: type.FormatId.html
     pub fn get_format(&self, format: FormatId) -> Option<Vec<u8>> {
-        self.0.get_format(format)
+        match &self.0 {
+            Backend::System(clipboard) => clipboard.get_format(format),
+            Backend::Memory(contents) => contents
+                .borrow()
+                .iter()
+                .find(|item| item.identifier == format)
+                .map(|item| item.data.clone()),
+        }
     }
 
     /// For debugging: print the resolved identifiers for each type currently
     /// on the clipboard.
     #[doc(hidden)]
     pub fn available_type_names(&self) -> Vec<String> {
-        self.0.available_type_names()
+        match &self.0 {
+            Backend::System(clipboard) => clipboard.available_type_names(),
+            Backend::Memory(contents) => contents
+                .borrow()
+                .iter()
+                .map(|item| item.identifier.to_string())
+                .collect(),
+        }
     }
 }
 
@@ -178,7 +235,7 @@ impl Clipboard {
 pub type FormatId = &'static str;
 
 /// Data coupled with a type identifier.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ClipboardFormat {
     pub(crate) identifier: FormatId,
     pub(crate) data: Vec<u8>,
@@ -210,7 +267,7 @@ impl From<&str> for ClipboardFormat {
 
 impl From<platform::Clipboard> for Clipboard {
     fn from(src: platform::Clipboard) -> Clipboard {
-        Clipboard(src)
+        Clipboard(Backend::System(src))
     }
 }
 
@@ -17,6 +17,12 @@
 use std::any::Any;
 use std::num::NonZeroU64;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::kurbo::{Point, Rect, Size};
+use crate::piet::{Color, Device, ImageFormat, Piet, RenderContext};
+use crate::window::CapturedImage;
 
 /// Strip the access keys from the menu string.
 ///
@@ -39,6 +45,68 @@ pub fn strip_access_key(raw_menu_text: &str) -> String {
     result
 }
 
+/// The dpi at which one px is exactly one physical pixel.
+const NOMINAL_DPI: f64 = 96.0;
+
+/// Convert a size in px units to physical pixels, at the given dpi.
+pub(crate) fn size_to_pixels(size: Size, dpi: f64) -> Size {
+    let scale = dpi / NOMINAL_DPI;
+    Size::new(size.width * scale, size.height * scale)
+}
+
+/// Convert a size in physical pixels to px units, at the given dpi.
+pub(crate) fn size_to_px(size: Size, dpi: f64) -> Size {
+    let scale = NOMINAL_DPI / dpi;
+    Size::new(size.width * scale, size.height * scale)
+}
+
+/// Convert a point in px units to physical pixels, at the given dpi.
+pub(crate) fn point_to_pixels(point: Point, dpi: f64) -> Point {
+    let scale = dpi / NOMINAL_DPI;
+    Point::new(point.x * scale, point.y * scale)
+}
+
+/// Convert a point in physical pixels to px units, at the given dpi.
+pub(crate) fn point_to_px(point: Point, dpi: f64) -> Point {
+    let scale = NOMINAL_DPI / dpi;
+    Point::new(point.x * scale, point.y * scale)
+}
+
+/// Convert a rect in physical pixels to px units, at the given dpi.
+pub(crate) fn rect_to_px(rect: Rect, dpi: f64) -> Rect {
+    let max = Point::new(rect.x1, rect.y1);
+    Rect::from_points(point_to_px(rect.origin(), dpi), point_to_px(max, dpi))
+}
+
+/// Paint an image of `width` by `height` pixels in memory, with piet's
+/// bitmap device, which is what draws windows on GTK and macOS, and on the
+/// GPU on Windows.
+pub(crate) fn render_device_image(
+    width: usize,
+    height: usize,
+    scale: f64,
+    paint: impl FnOnce(&mut Piet),
+) -> Result<CapturedImage, Error> {
+    let mut device = Device::new().map_err(|_| Error::Other("there is no device to paint with"))?;
+    let mut target = device
+        .bitmap_target(width, height, scale)
+        .map_err(|_| Error::Other("failed to create a bitmap target"))?;
+    {
+        let mut piet = target.render_context();
+        paint(&mut piet);
+        piet.finish()
+            .map_err(|_| Error::Other("failed to paint the image"))?;
+    }
+    let pixels = target
+        .into_raw_pixels(ImageFormat::RgbaPremul)
+        .map_err(|_| Error::Other("failed to read the painted pixels"))?;
+    Ok(CapturedImage {
+        pixels,
+        width,
+        height,
+    })
+}
+
 /// A trait for implementing the boxed callback hack.
 pub(crate) trait IdleCallback: Send {
     fn call(self: Box<Self>, a: &dyn Any);
@@ -50,6 +118,108 @@ impl<F: FnOnce(&dyn Any) + Send> IdleCallback for F {
     }
 }
 
+/// Defers showing a window until its first paint has completed, so that it
+/// doesn't appear without its content for a frame or two.
+///
+/// When the window is asked to show, the backend starts a paint of the
+/// hidden window and a timer of `FIRST_PAINT_TIMEOUT`; the window is shown
+/// when the paint completes, or when the timer fires, whichever is first.
+#[cfg_attr(all(target_os = "macos", not(feature = "use_gtk")), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeferredShow {
+    /// The window has not been asked to show.
+    Hidden,
+    /// The window has been asked to show, and is waiting for its first paint.
+    WaitingForPaint,
+    /// The window has been shown.
+    Shown,
+}
+
+/// How long a window waits for its first paint before it is shown anyway.
+#[cfg_attr(all(target_os = "macos", not(feature = "use_gtk")), allow(dead_code))]
+pub(crate) const FIRST_PAINT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[cfg_attr(all(target_os = "macos", not(feature = "use_gtk")), allow(dead_code))]
+impl DeferredShow {
+    /// The window was asked to show.
+    ///
+    /// Returns `true` if the backend should start the first paint and the
+    /// timeout; this is only the case the first time.
+    pub(crate) fn request_show(&mut self) -> bool {
+        let first = *self == DeferredShow::Hidden;
+        if first {
+            *self = DeferredShow::WaitingForPaint;
+        }
+        first
+    }
+
+    /// A paint completed.
+    ///
+    /// Returns `true` if the window should be shown now.
+    pub(crate) fn paint_completed(&mut self) -> bool {
+        self.show_if_waiting()
+    }
+
+    /// The first paint timeout fired.
+    ///
+    /// Returns `true` if the window should be shown now.
+    pub(crate) fn timed_out(&mut self) -> bool {
+        self.show_if_waiting()
+    }
+
+    fn show_if_waiting(&mut self) -> bool {
+        let waiting = *self == DeferredShow::WaitingForPaint;
+        if waiting {
+            *self = DeferredShow::Shown;
+        }
+        waiting
+    }
+}
+
+impl Default for DeferredShow {
+    fn default() -> Self {
+        DeferredShow::Hidden
+    }
+}
+
+/// The red, green, blue and alpha components of a color, each from 0 to 1.
+#[cfg_attr(all(target_os = "windows", not(feature = "use_gtk")), allow(dead_code))]
+pub(crate) fn color_components(color: &Color) -> (f64, f64, f64, f64) {
+    let rgba = color.as_rgba_u32();
+    let component = |shift: u32| f64::from((rgba >> shift) & 0xff) / 255.;
+    (component(24), component(16), component(8), component(0))
+}
+
+/// A color as a Windows `COLORREF`, which is `0x00bbggrr`. The alpha is
+/// ignored, since window backgrounds are opaque.
+#[cfg_attr(any(not(target_os = "windows"), feature = "use_gtk"), allow(dead_code))]
+pub(crate) fn color_to_colorref(color: &Color) -> u32 {
+    let rgba = color.as_rgba_u32();
+    let (r, g, b) = ((rgba >> 24) & 0xff, (rgba >> 16) & 0xff, (rgba >> 8) & 0xff);
+    (b << 16) | (g << 8) | r
+}
+
+/// The offset in bytes of an offset in UTF-16 code units, which is how
+/// Windows and macOS count the text of a composition. An offset inside a
+/// character is moved to its end, and one past the text is the end.
+#[cfg_attr(
+    any(
+        feature = "use_gtk",
+        not(any(target_os = "windows", target_os = "macos"))
+    ),
+    allow(dead_code)
+)]
+pub(crate) fn utf16_to_utf8_offset(text: &str, utf16_offset: usize) -> usize {
+    let mut units = 0;
+    for (offset, c) in text.char_indices() {
+        if units >= utf16_offset {
+            return offset;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
 /// An incrementing counter for generating unique ids.
 ///
 /// This can be used safely from multiple threads.
@@ -85,3 +255,144 @@ impl Counter {
         unsafe { NonZeroU64::new_unchecked(self.0.fetch_add(1, Ordering::Relaxed)) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nominal_dpi_is_identity() {
+        let size = Size::new(640., 480.);
+        let point = Point::new(-20., 35.5);
+        assert_eq!(size_to_pixels(size, 96.), size);
+        assert_eq!(size_to_px(size, 96.), size);
+        assert_eq!(point_to_pixels(point, 96.), point);
+        assert_eq!(point_to_px(point, 96.), point);
+    }
+
+    #[test]
+    fn scaled_dpi() {
+        // 144 dpi is a scale factor of 1.5
+        assert_eq!(
+            size_to_pixels(Size::new(400., 300.), 144.),
+            Size::new(600., 450.)
+        );
+        assert_eq!(
+            size_to_px(Size::new(600., 450.), 144.),
+            Size::new(400., 300.)
+        );
+        assert_eq!(
+            point_to_pixels(Point::new(10., -8.), 144.),
+            Point::new(15., -12.)
+        );
+        assert_eq!(
+            point_to_px(Point::new(15., -12.), 144.),
+            Point::new(10., -8.)
+        );
+    }
+
+    /// A stand-in for a backend, recording the calls it makes to the platform.
+    #[derive(Default)]
+    struct MockWindow {
+        deferred: DeferredShow,
+        calls: Vec<&'static str>,
+    }
+
+    impl MockWindow {
+        fn show(&mut self) {
+            if self.deferred.request_show() {
+                self.calls.push("start-timeout");
+                self.calls.push("start-paint");
+            }
+        }
+
+        fn paint_completed(&mut self) {
+            self.calls.push("paint-completed");
+            if self.deferred.paint_completed() {
+                self.calls.push("show");
+            }
+        }
+
+        fn timed_out(&mut self) {
+            if self.deferred.timed_out() {
+                self.calls.push("show");
+            }
+        }
+    }
+
+    #[test]
+    fn show_after_first_paint() {
+        let mut window = MockWindow::default();
+        // painting before the window is asked to show doesn't show it
+        window.paint_completed();
+        window.show();
+        assert_eq!(window.deferred, DeferredShow::WaitingForPaint);
+        window.paint_completed();
+        window.timed_out();
+        // asking again does nothing
+        window.show();
+        window.paint_completed();
+        assert_eq!(
+            window.calls,
+            &[
+                "paint-completed",
+                "start-timeout",
+                "start-paint",
+                "paint-completed",
+                "show",
+                "paint-completed",
+            ]
+        );
+        assert_eq!(window.deferred, DeferredShow::Shown);
+    }
+
+    #[test]
+    fn show_on_first_paint_timeout() {
+        let mut window = MockWindow::default();
+        // the timeout is only armed by showing
+        window.timed_out();
+        window.show();
+        window.timed_out();
+        window.paint_completed();
+        assert_eq!(
+            window.calls,
+            &["start-timeout", "start-paint", "show", "paint-completed"]
+        );
+        assert_eq!(window.deferred, DeferredShow::Shown);
+    }
+
+    #[test]
+    fn color_conversions() {
+        let color = Color::rgba8(0x12, 0x34, 0x56, 0x78);
+        assert_eq!(color_to_colorref(&color), 0x56_34_12);
+        let (r, g, b, a) = color_components(&color);
+        let expected = [0x12, 0x34, 0x56, 0x78];
+        for (component, expected) in [r, g, b, a].iter().zip(expected.iter()) {
+            assert!((component - f64::from(*expected) / 255.).abs() < 1e-9);
+        }
+        assert_eq!(color_components(&Color::WHITE), (1., 1., 1., 1.));
+    }
+
+    #[test]
+    fn utf16_offsets() {
+        let text = "aé日𝄞b";
+        let offsets: Vec<_> = (0..8).map(|i| utf16_to_utf8_offset(text, i)).collect();
+        // the clef takes two units, and four bytes
+        assert_eq!(offsets, &[0, 1, 3, 6, 10, 10, 11, 11]);
+        assert_eq!(utf16_to_utf8_offset("", 3), 0);
+    }
+
+    #[test]
+    fn round_trip() {
+        for &dpi in &[72., 96., 120., 144., 192.] {
+            let size = Size::new(500., 400.);
+            let back = size_to_px(size_to_pixels(size, dpi), dpi);
+            assert!((back.width - size.width).abs() < 1e-9);
+            assert!((back.height - size.height).abs() < 1e-9);
+
+            let point = Point::new(123., 45.);
+            let back = point_to_px(point_to_pixels(point, dpi), dpi);
+            assert!((back - point).hypot() < 1e-9);
+        }
+    }
+}
@@ -17,9 +17,14 @@
 use std::path::{Path, PathBuf};
 
 /// Information about a file to be opened or saved.
-#[derive(Debug, Clone)]
+///
+/// If the dialog allowed [`multi_selection`], this can hold several paths.
+///
+/// [`multi_selection`]: struct.FileDialogOptions.html#method.multi_selection
+#[derive(Debug, Clone, PartialEq)]
 pub struct FileInfo {
-    pub(crate) path: PathBuf,
+    /// The chosen paths; never empty.
+    pub(crate) paths: Vec<PathBuf>,
 }
 
 /// Type of file dialog.
@@ -38,6 +43,9 @@ pub struct FileDialogOptions {
     pub default_type: Option<FileSpec>,
     pub select_directories: bool,
     pub multi_selection: bool,
+    pub default_name: Option<String>,
+    pub starting_directory: Option<PathBuf>,
+    pub title: Option<String>,
     // we don't want a library user to be able to construct this type directly
     __non_exhaustive: (),
 }
@@ -68,9 +76,30 @@ pub struct FileSpec {
 }
 
 impl FileInfo {
-    /// The file's path.
+    /// Create a `FileInfo` for a single path.
+    pub fn new(path: impl Into<PathBuf>) -> FileInfo {
+        FileInfo {
+            paths: vec![path.into()],
+        }
+    }
+
+    /// Create a `FileInfo` for several paths, or `None` if there are none.
+    pub fn from_paths(paths: Vec<PathBuf>) -> Option<FileInfo> {
+        if paths.is_empty() {
+            None
+        } else {
+            Some(FileInfo { paths })
+        }
+    }
+
+    /// The file's path. If several files were chosen, this is the first.
     pub fn path(&self) -> &Path {
-        &self.path
+        &self.paths[0]
+    }
+
+    /// The paths of all of the chosen files.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
     }
 }
 
@@ -104,6 +133,26 @@ impl FileDialogOptions {
         self
     }
 
+    /// Set the file name that the dialog starts with, such as the current name
+    /// of a document that is being saved.
+    pub fn default_name(mut self, default_name: impl Into<String>) -> Self {
+        self.default_name = Some(default_name.into());
+        self
+    }
+
+    /// Set the directory that the dialog starts in.
+    pub fn starting_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.starting_directory = Some(directory.into());
+        self
+    }
+
+    /// Set the title of the dialog. If this is not set, the platform's
+    /// default title is used.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
     /// Set the default file type.
     /// If it's `None` or not present in [`allowed_types`](#method.allowed_types)
     /// then the first entry in [`allowed_types`](#method.allowed_types) will be used as default.
@@ -125,3 +174,51 @@ impl FileSpec {
         FileSpec { name, extensions }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_builder() {
+        let options = FileDialogOptions::new()
+            .select_directories()
+            .multi_selection()
+            .default_name("notes.txt")
+            .starting_directory("/home/druid")
+            .title("Pick some notes")
+            .allowed_types(vec![FileSpec::TEXT])
+            .default_type(FileSpec::TEXT);
+
+        assert!(options.select_directories);
+        assert!(options.multi_selection);
+        assert!(!options.show_hidden);
+        assert_eq!(options.default_name.as_deref(), Some("notes.txt"));
+        assert_eq!(
+            options.starting_directory.as_deref(),
+            Some(Path::new("/home/druid"))
+        );
+        assert_eq!(options.title.as_deref(), Some("Pick some notes"));
+        assert_eq!(options.allowed_types, Some(vec![FileSpec::TEXT]));
+        assert_eq!(options.default_type, Some(FileSpec::TEXT));
+
+        let defaults = FileDialogOptions::new();
+        assert!(defaults.default_name.is_none());
+        assert!(defaults.starting_directory.is_none());
+        assert!(defaults.title.is_none());
+    }
+
+    #[test]
+    fn file_info_paths() {
+        let single = FileInfo::new("a.txt");
+        assert_eq!(single.path(), Path::new("a.txt"));
+        assert_eq!(single.paths(), &[PathBuf::from("a.txt")]);
+
+        let paths = vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")];
+        let several = FileInfo::from_paths(paths.clone()).unwrap();
+        assert_eq!(several.path(), Path::new("a.txt"));
+        assert_eq!(several.paths(), paths.as_slice());
+
+        assert!(FileInfo::from_paths(Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dragging things onto a window.
+//!
+//! Once a window is created, it is a drop target: when something is dragged
+//! over it, from another application or from the file manager, its handler
+//! is told with [`WinHandler::drag_enter`] and the methods next to it.
+//!
+//! [`WinHandler::drag_enter`]: trait.WinHandler.html#method.drag_enter
+
+use std::path::PathBuf;
+
+use crate::clipboard::{ClipboardFormat, FormatId};
+use crate::keyboard::KeyModifiers;
+use crate::kurbo::Point;
+
+/// What is being dragged: files, data in some formats, or both.
+///
+/// The formats are identified the same way as on the clipboard; see
+/// [`ClipboardFormat`]. Text is in the `ClipboardFormat::TEXT` format.
+///
+/// [`ClipboardFormat`]: struct.ClipboardFormat.html
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DragPayload {
+    files: Vec<PathBuf>,
+    formats: Vec<ClipboardFormat>,
+}
+
+/// Something being dragged over a window, and where it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DragEvent {
+    /// The position of the pointer, in px units, relative to the window's
+    /// content area.
+    pub pos: Point,
+    /// The keyboard modifiers, which some platforms use to choose between
+    /// copying and moving.
+    pub mods: KeyModifiers,
+    /// What is being dragged.
+    pub payload: DragPayload,
+}
+
+impl DragPayload {
+    /// A payload with nothing in it.
+    pub fn new() -> DragPayload {
+        Default::default()
+    }
+
+    /// A payload of files.
+    pub fn files(files: impl IntoIterator<Item = PathBuf>) -> DragPayload {
+        DragPayload::new().with_files(files)
+    }
+
+    /// A payload of text.
+    pub fn text(text: impl Into<String>) -> DragPayload {
+        DragPayload::new().with_text(text)
+    }
+
+    /// Builder-style method to add files to the payload.
+    pub fn with_files(mut self, files: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.files.extend(files);
+        self
+    }
+
+    /// Builder-style method to add text to the payload.
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        self.with_data(ClipboardFormat::TEXT, text.into().into_bytes())
+    }
+
+    /// Builder-style method to add data in a format to the payload.
+    ///
+    /// Data that was already in the payload in this format is replaced.
+    pub fn with_data(mut self, format: FormatId, data: impl Into<Vec<u8>>) -> Self {
+        self.formats.retain(|item| item.identifier != format);
+        self.formats.push(ClipboardFormat::new(format, data));
+        self
+    }
+
+    /// The files in the payload.
+    pub fn get_files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// The text in the payload, if there is some.
+    pub fn get_text(&self) -> Option<&str> {
+        self.get(ClipboardFormat::TEXT)
+            .and_then(|data| std::str::from_utf8(data).ok())
+    }
+
+    /// The data in the payload in `format`, if there is some.
+    pub fn get(&self, format: FormatId) -> Option<&[u8]> {
+        self.formats
+            .iter()
+            .find(|item| item.identifier == format)
+            .map(|item| item.data.as_slice())
+    }
+
+    /// Whether the payload has data in `format`.
+    pub fn has_format(&self, format: FormatId) -> bool {
+        self.get(format).is_some()
+    }
+
+    /// The formats of the data in the payload, in the order it was added.
+    pub fn formats(&self) -> impl Iterator<Item = FormatId> + '_ {
+        self.formats.iter().map(|item| item.identifier)
+    }
+
+    /// Whether there is nothing in the payload.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.formats.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_holds_files_and_formats() {
+        let payload = DragPayload::files(vec![PathBuf::from("a.txt")])
+            .with_text("hello")
+            .with_data("x-custom", vec![1, 2])
+            .with_data("x-custom", vec![3]);
+        assert_eq!(payload.get_files(), &[PathBuf::from("a.txt")]);
+        assert_eq!(payload.get_text(), Some("hello"));
+        assert_eq!(payload.get("x-custom"), Some(&[3][..]));
+        assert!(!payload.has_format(ClipboardFormat::PDF));
+        let formats: Vec<_> = payload.formats().collect();
+        assert_eq!(formats, vec![ClipboardFormat::TEXT, "x-custom"]);
+        assert!(DragPayload::new().is_empty());
+    }
+}
@@ -18,8 +18,8 @@ use std::borrow::Borrow;
 
 use log::warn;
 
-use crate::keyboard::{KeyEvent, KeyModifiers};
-use crate::keycodes::KeyCode;
+use crate::keyboard::{KbKey, KeyEvent, KeyModifiers};
+use crate::keycodes::Code;
 
 /// A description of a keyboard shortcut.
 ///
@@ -31,83 +31,97 @@ use crate::keycodes::KeyCode;
 /// [`SysMods`] matches the Command key on macOS and Ctrl elsewhere:
 ///
 /// ```
-/// use druid_shell::{HotKey, KeyEvent, KeyCode, RawMods, SysMods};
+/// use druid_shell::{HotKey, KeyEvent, Code, RawMods, SysMods};
 ///
 /// let hotkey = HotKey::new(SysMods::Cmd, "a");
 ///
 /// #[cfg(target_os = "macos")]
-/// assert!(hotkey.matches(KeyEvent::for_test(RawMods::Meta, "a", KeyCode::KeyA)));
+/// assert!(hotkey.matches(KeyEvent::for_test(RawMods::Meta, "a", Code::KeyA)));
 ///
 /// #[cfg(target_os = "windows")]
-/// assert!(hotkey.matches(KeyEvent::for_test(RawMods::Ctrl, "a", KeyCode::KeyA)));
+/// assert!(hotkey.matches(KeyEvent::for_test(RawMods::Ctrl, "a", Code::KeyA)));
 /// ```
 ///
-/// `None` matches only the key without modifiers:
+/// `None` matches the key with any modifiers, and [`SysMods::None`] matches
+/// only the key without modifiers:
 ///
 /// ```
-/// use druid_shell::{HotKey, KeyEvent, KeyCode, RawMods, SysMods};
+/// use druid_shell::{Code, HotKey, KbKey, KeyEvent, RawMods, SysMods};
 ///
-/// let hotkey = HotKey::new(None, KeyCode::ArrowLeft);
+/// let any = HotKey::new(None, KbKey::ArrowLeft);
+/// let bare = HotKey::new(SysMods::None, KbKey::ArrowLeft);
 ///
-/// assert!(hotkey.matches(KeyEvent::for_test(RawMods::None, "", KeyCode::ArrowLeft)));
-/// assert!(!hotkey.matches(KeyEvent::for_test(RawMods::Ctrl, "", KeyCode::ArrowLeft)));
+/// assert!(any.matches(KeyEvent::for_test(RawMods::Ctrl, "", Code::ArrowLeft)));
+/// assert!(bare.matches(KeyEvent::for_test(RawMods::None, "", Code::ArrowLeft)));
+/// assert!(!bare.matches(KeyEvent::for_test(RawMods::Ctrl, "", Code::ArrowLeft)));
 /// ```
 ///
 /// [`SysMods`]: enum.SysMods.html
+/// [`SysMods::None`]: enum.SysMods.html#variant.None
 #[derive(Debug, Clone)]
 pub struct HotKey {
-    pub(crate) mods: RawMods,
+    /// The modifiers, or `None` if any modifiers are accepted.
+    pub(crate) mods: Option<RawMods>,
     pub(crate) key: KeyCompare,
 }
 
 /// Something that can be compared with a keyboard key.
 #[derive(Debug, Clone, PartialEq)]
 pub enum KeyCompare {
-    Code(KeyCode),
-    Text(&'static str),
+    /// The physical key, whatever the layout.
+    Code(Code),
+    /// A key that doesn't type text, such as an arrow.
+    Key(KbKey),
+    /// The text that the key types.
+    Text(String),
 }
 
 impl HotKey {
     /// Create a new hotkey.
     ///
     /// The first argument describes the keyboard modifiers. This can be `None`,
-    /// or an instance of either [`SysMods`], or [`RawMods`]. [`SysMods`] unify the
-    /// 'Command' key on macOS with the 'Ctrl' key on other platforms.
+    /// which accepts any modifiers, or an instance of either [`SysMods`], or
+    /// [`RawMods`], which must match exactly. [`SysMods`] unify the 'Command'
+    /// key on macOS with the 'Ctrl' key on other platforms.
     ///
-    /// The second argument describes the non-modifier key. This can be either
-    /// a `&'static str` or a [`KeyCode`]. If it is a `&str`, it will be compared
-    /// against the [`unmodified text`] for a given key event; if it is a [`KeyCode`]
-    /// it will be compared against the event's key code.
+    /// The second argument describes the non-modifier key. This can be a
+    /// `&'static str`, a [`KbKey`] or a [`Code`]. A `&str` is compared with
+    /// the [`key`] of an event that types text, ignoring the case that shift
+    /// gives it; a [`KbKey`] is compared with the key, and a [`Code`] with the
+    /// physical key, whatever the layout.
     ///
-    /// In general, [`KeyCode`] should be preferred for non-printing keys, like
+    /// Text that has no lowercase letters, such as `"+"`, may need shift to
+    /// be typed, depending on the keyboard layout; unless the modifiers include
+    /// shift, whether it is held doesn't matter for such text.
+    ///
+    /// In general, [`KbKey`] should be preferred for non-printing keys, like
     /// the arrows or backspace.
     ///
     /// # Examples
     /// ```
-    /// use druid_shell::{HotKey, KeyEvent, KeyCode, RawMods, SysMods};
+    /// use druid_shell::{HotKey, KbKey, RawMods, SysMods};
     ///
     /// let select_all = HotKey::new(SysMods::Cmd, "a");
-    /// let esc = HotKey::new(None, KeyCode::Escape);
+    /// let esc = HotKey::new(None, KbKey::Escape);
     /// let macos_fullscreen = HotKey::new(RawMods::CtrlMeta, "f");
     /// ```
     ///
-    /// [`KeyCode`]: enum.KeyCode.html
+    /// [`Code`]: enum.Code.html
+    /// [`KbKey`]: enum.KbKey.html
+    /// [`key`]: struct.KeyEvent.html#structfield.key
     /// [`SysMods`]: enum.SysMods.html
     /// [`RawMods`]: enum.RawMods.html
-    /// [`unmodified text`]: struct.KeyEvent.html#method.unmod_text
     pub fn new(mods: impl Into<Option<RawMods>>, key: impl Into<KeyCompare>) -> Self {
         HotKey {
-            mods: mods.into().unwrap_or(RawMods::None),
+            mods: mods.into(),
             key: key.into(),
         }
         .warn_if_needed()
     }
 
-    //TODO: figure out if we need to be normalizing case or something? This requires
-    //correctly documenting the expected behaviour of `unmod_text`.
     fn warn_if_needed(self) -> Self {
-        if let KeyCompare::Text(s) = self.key {
-            let km: KeyModifiers = self.mods.into();
+        if let KeyCompare::Text(s) = &self.key {
+            let km: KeyModifiers = self.raw_mods().into();
             if km.shift && s.chars().any(|c| c.is_uppercase()) {
                 warn!(
                     "warning: HotKey {:?} includes shift, but text is lowercase. \
@@ -119,16 +133,38 @@ impl HotKey {
         self
     }
 
+    /// The modifiers, where accepting any modifiers is treated as none.
+    ///
+    /// This is for menu accelerators, which need a specific combination.
+    pub(crate) fn raw_mods(&self) -> RawMods {
+        self.mods.unwrap_or(RawMods::None)
+    }
+
     /// Returns `true` if this [`KeyEvent`] matches this `HotKey`.
     ///
     /// [`KeyEvent`]: struct.KeyEvent.html
     pub fn matches(&self, event: impl Borrow<KeyEvent>) -> bool {
         let event = event.borrow();
-        self.mods == event.mods
-            && match self.key {
-                KeyCompare::Code(code) => code == event.key_code,
-                KeyCompare::Text(text) => Some(text) == event.text(),
+        let shift_implied = match (&self.key, &event.key) {
+            (KeyCompare::Code(code), _) if *code == event.code => false,
+            (KeyCompare::Key(key), _) if *key == event.key => false,
+            (KeyCompare::Text(text), KbKey::Character(typed))
+                if typed == text || (event.mods.shift && typed.to_lowercase() == *text) =>
+            {
+                !text.chars().any(char::is_lowercase)
+            }
+            _ => return false,
+        };
+        match self.mods {
+            None => true,
+            Some(mods) => {
+                let mut mods: KeyModifiers = mods.into();
+                if shift_implied && !mods.shift {
+                    mods.shift = event.mods.shift;
+                }
+                mods == event.mods
             }
+        }
     }
 }
 
@@ -239,35 +275,162 @@ impl From<SysMods> for Option<RawMods> {
 
 impl From<SysMods> for RawMods {
     fn from(src: SysMods) -> RawMods {
-        #[cfg(target_os = "macos")]
-        match src {
-            SysMods::None => RawMods::None,
-            SysMods::Shift => RawMods::Shift,
-            SysMods::Cmd => RawMods::Meta,
-            SysMods::AltCmd => RawMods::AltMeta,
-            SysMods::CmdShift => RawMods::MetaShift,
-            SysMods::AltCmdShift => RawMods::AltMetaShift,
-        }
-        #[cfg(not(target_os = "macos"))]
-        match src {
-            SysMods::None => RawMods::None,
-            SysMods::Shift => RawMods::Shift,
-            SysMods::Cmd => RawMods::Ctrl,
-            SysMods::AltCmd => RawMods::AltCtrl,
-            SysMods::CmdShift => RawMods::CtrlShift,
-            SysMods::AltCmdShift => RawMods::AltCtrlShift,
+        src.to_raw(cfg!(target_os = "macos"))
+    }
+}
+
+impl SysMods {
+    /// The raw modifiers, on macOS or elsewhere.
+    pub(crate) fn to_raw(self, macos: bool) -> RawMods {
+        match (self, macos) {
+            (SysMods::None, _) => RawMods::None,
+            (SysMods::Shift, _) => RawMods::Shift,
+            (SysMods::Cmd, true) => RawMods::Meta,
+            (SysMods::AltCmd, true) => RawMods::AltMeta,
+            (SysMods::CmdShift, true) => RawMods::MetaShift,
+            (SysMods::AltCmdShift, true) => RawMods::AltMetaShift,
+            (SysMods::Cmd, false) => RawMods::Ctrl,
+            (SysMods::AltCmd, false) => RawMods::AltCtrl,
+            (SysMods::CmdShift, false) => RawMods::CtrlShift,
+            (SysMods::AltCmdShift, false) => RawMods::AltCtrlShift,
         }
     }
 }
 
-impl From<KeyCode> for KeyCompare {
-    fn from(src: KeyCode) -> KeyCompare {
+impl From<Code> for KeyCompare {
+    fn from(src: Code) -> KeyCompare {
         KeyCompare::Code(src)
     }
 }
 
 impl From<&'static str> for KeyCompare {
     fn from(src: &'static str) -> KeyCompare {
-        KeyCompare::Text(src)
+        KeyCompare::Text(src.to_string())
+    }
+}
+
+impl From<KbKey> for KeyCompare {
+    fn from(src: KbKey) -> KeyCompare {
+        match src {
+            KbKey::Character(text) => KeyCompare::Text(text),
+            key => KeyCompare::Key(key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::KeyState;
+
+    fn mods(raw: RawMods) -> KeyModifiers {
+        raw.into()
+    }
+
+    fn key(raw: RawMods, code: Code) -> KeyEvent {
+        KeyEvent::for_test(raw, "", code)
+    }
+
+    #[test]
+    fn none_accepts_any_mods() {
+        let hotkey = HotKey::new(None, Code::Home);
+        assert!(hotkey.matches(key(RawMods::None, Code::Home)));
+        assert!(hotkey.matches(key(RawMods::CtrlShift, Code::Home)));
+        assert!(hotkey.matches(key(RawMods::AltCtrlMetaShift, Code::Home)));
+        assert!(!hotkey.matches(key(RawMods::None, Code::End)));
+    }
+
+    #[test]
+    fn mods_match_exactly() {
+        let bare = HotKey::new(SysMods::None, Code::Home);
+        assert!(bare.matches(key(RawMods::None, Code::Home)));
+        assert!(!bare.matches(key(RawMods::Shift, Code::Home)));
+
+        let ctrl = HotKey::new(RawMods::Ctrl, Code::Home);
+        assert!(ctrl.matches(key(RawMods::Ctrl, Code::Home)));
+        assert!(!ctrl.matches(key(RawMods::None, Code::Home)));
+        assert!(!ctrl.matches(key(RawMods::CtrlShift, Code::Home)));
+        assert!(!ctrl.matches(key(RawMods::CtrlMeta, Code::Home)));
+    }
+
+    #[test]
+    fn text_matches_unmodified_text() {
+        let hotkey = HotKey::new(RawMods::Ctrl, "a");
+        // ctrl+a produces a control character on some platforms
+        let event = KeyEvent::new(
+            KeyState::Down,
+            Code::KeyA,
+            false,
+            mods(RawMods::Ctrl),
+            "\u{1}",
+            "a",
+        );
+        assert!(hotkey.matches(event));
+        assert!(!hotkey.matches(KeyEvent::for_test(RawMods::Ctrl, "b", Code::KeyA)));
+
+        // a key code is compared regardless of the text
+        let hotkey = HotKey::new(RawMods::Ctrl, Code::KeyA);
+        assert!(hotkey.matches(KeyEvent::for_test(RawMods::Ctrl, "b", Code::KeyA)));
+    }
+
+    #[test]
+    fn key_matches_whatever_the_code() {
+        let enter = HotKey::new(SysMods::None, KbKey::Enter);
+        assert!(enter.matches(key(RawMods::None, Code::Return)));
+        assert!(enter.matches(key(RawMods::None, Code::NumpadEnter)));
+        assert!(!enter.matches(key(RawMods::None, Code::Tab)));
+
+        // a character key is the same as its text
+        let a = HotKey::new(RawMods::Ctrl, KbKey::Character("a".into()));
+        assert_eq!(a.key, KeyCompare::Text("a".into()));
+        assert!(a.matches(KeyEvent::for_test(RawMods::Ctrl, "a", Code::KeyQ)));
+    }
+
+    #[test]
+    fn shift_implied_by_text() {
+        let plus = HotKey::new(RawMods::Ctrl, "+");
+        assert!(plus.matches(KeyEvent::for_test(RawMods::Ctrl, "+", Code::NumpadAdd)));
+        assert!(plus.matches(KeyEvent::for_test(RawMods::CtrlShift, "+", Code::Equals)));
+        assert!(!plus.matches(KeyEvent::for_test(RawMods::AltCtrl, "+", Code::Equals)));
+
+        // letters have case, so shift is significant for them
+        let a = HotKey::new(RawMods::Ctrl, "a");
+        assert!(!a.matches(KeyEvent::for_test(RawMods::CtrlShift, "a", Code::KeyA)));
+        let shift_a = HotKey::new(RawMods::CtrlShift, "a");
+        assert!(shift_a.matches(KeyEvent::for_test(RawMods::CtrlShift, "a", Code::KeyA)));
+        // with shift, the key types the uppercase letter
+        assert!(shift_a.matches(KeyEvent::for_test(RawMods::CtrlShift, "A", Code::KeyA)));
+    }
+
+    #[test]
+    fn cmd_depends_on_platform() {
+        assert_eq!(mods(SysMods::Cmd.to_raw(true)), mods(RawMods::Meta));
+        assert_eq!(mods(SysMods::Cmd.to_raw(false)), mods(RawMods::Ctrl));
+        assert_eq!(
+            mods(SysMods::AltCmdShift.to_raw(true)),
+            mods(RawMods::AltMetaShift)
+        );
+        assert_eq!(
+            mods(SysMods::AltCmdShift.to_raw(false)),
+            mods(RawMods::AltCtrlShift)
+        );
+        assert_eq!(mods(SysMods::Shift.to_raw(true)), mods(RawMods::Shift));
+
+        assert!(mods(RawMods::Meta).command_for(true));
+        assert!(!mods(RawMods::Ctrl).command_for(true));
+        assert!(mods(RawMods::Ctrl).command_for(false));
+        assert!(!mods(RawMods::Meta).command_for(false));
+
+        let native: KeyModifiers = RawMods::from(SysMods::Cmd).into();
+        assert!(native.command());
+    }
+
+    #[test]
+    fn modifier_predicates() {
+        assert!(mods(RawMods::None).is_empty());
+        assert!(!mods(RawMods::Shift).is_empty());
+        assert!(mods(RawMods::Shift).only_shift());
+        assert!(!mods(RawMods::CtrlShift).only_shift());
+        assert!(!mods(RawMods::None).only_shift());
     }
 }
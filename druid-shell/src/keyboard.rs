@@ -13,81 +13,251 @@
 // limitations under the License.
 
 //! Keyboard event types and helpers
+//!
+//! The types follow the [W3C UI Events] model: a key event has both the
+//! meaning of the key in the keyboard layout, its [`KbKey`], and the physical
+//! key, its [`Code`]. Text that is made of more than one key press, such as a
+//! letter with an accent that is typed with a dead key, or text from an
+//! input method, arrives in composition events.
+//!
+//! [W3C UI Events]: https://w3c.github.io/uievents/
+//! [`KbKey`]: enum.KbKey.html
+//! [`Code`]: enum.Code.html
 
-use super::keycodes::KeyCode;
+use super::keycodes::Code;
 use std::fmt;
+use std::ops::Range;
 
 /// A keyboard event, generated on every key press and key release.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct KeyEvent {
-    /// The platform independent keycode.
-    pub key_code: KeyCode,
-    /// Whether or not this event is a repeat (the key was held down)
-    pub is_repeat: bool,
+    /// Whether the key was pressed or released.
+    pub state: KeyState,
+    /// The meaning of the key, given the keyboard layout and the modifiers.
+    pub key: KbKey,
+    /// The physical key, whatever the keyboard layout.
+    pub code: Code,
     /// The modifiers for this event.
     pub mods: KeyModifiers,
-    // these are exposed via methods, below. The rationale for this approach is
-    // that a key might produce more than a single 'char' of input, but we don't
-    // want to need a heap allocation in the trivial case. This gives us 15 bytes
-    // of string storage, which... might be enough?
-    text: TinyStr,
-    /// The 'unmodified text' is the text that would be produced by this keystroke
-    /// in the absence of ctrl+alt modifiers or preceding dead keys.
-    unmodified_text: TinyStr,
-    //TODO: add time
+    /// Whether or not this event is a repeat (the key was held down)
+    pub repeat: bool,
+    /// The key was pressed during a composition, such as after a dead key.
+    ///
+    /// The text of the composition is sent in composition events; a key
+    /// press during one should not be typed.
+    pub is_composing: bool,
+}
+
+/// Whether a key was pressed or released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
+    Down,
+    Up,
+}
+
+/// The meaning of a key, as the W3C `key` attribute of a keyboard event.
+///
+/// This depends on the keyboard layout: the key that is `a` with a QWERTY
+/// layout is `q` with AZERTY. For the physical key, see [`Code`].
+///
+/// [`Code`]: enum.Code.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KbKey {
+    /// A key that types text, such as a letter or a digit.
+    ///
+    /// This is the text that the key types with the layout and shift, such
+    /// as `A` for shift+a; with ctrl, it is the text without ctrl.
+    Character(String),
+    /// A dead key, which adds an accent to the key that follows it.
+    Dead,
+    /// A key that the platform doesn't identify.
+    Unidentified,
+
+    Alt,
+    CapsLock,
+    Control,
+    Meta,
+    NumLock,
+    ScrollLock,
+    Shift,
+
+    Enter,
+    Tab,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    End,
+    Home,
+    PageDown,
+    PageUp,
+
+    Backspace,
+    Delete,
+    Insert,
+    Escape,
+    Pause,
+    PrintScreen,
+
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+}
+
+/// A change in a composition, which is text that takes more than one key
+/// press to type, such as `ö` typed as `¨` followed by `o`, or text that is
+/// typed with an input method, such as Japanese.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompositionEvent {
+    /// A composition started.
+    Start,
+    /// The text of the composition so far, which is not typed yet, and the
+    /// range of its cursor, in bytes; the range is empty when the cursor is
+    /// a caret.
+    ///
+    /// This text, sometimes called the preedit, should be shown where it
+    /// will be typed, marked as not typed yet, such as with an underline.
+    Update(String, Range<usize>),
+    /// The composition ended, and this text should be typed. It is empty if
+    /// the composition was cancelled.
+    End(String),
 }
 
 impl KeyEvent {
     /// Create a new `KeyEvent` struct. This accepts either &str or char for the last
     /// two arguments.
+    ///
+    /// The key is found from the code for keys that don't type text, and
+    /// otherwise from the text; `unmodified_text`, which is the text without
+    /// ctrl, is for when the text is a control character.
     pub(crate) fn new(
-        key_code: impl Into<KeyCode>,
-        is_repeat: bool,
+        state: KeyState,
+        code: impl Into<Code>,
+        repeat: bool,
         mods: KeyModifiers,
         text: impl Into<StrOrChar>,
         unmodified_text: impl Into<StrOrChar>,
     ) -> Self {
-        let text = match text.into() {
-            StrOrChar::Char(c) => c.into(),
-            StrOrChar::Str(s) => TinyStr::new(s),
-        };
-        let unmodified_text = match unmodified_text.into() {
-            StrOrChar::Char(c) => c.into(),
-            StrOrChar::Str(s) => TinyStr::new(s),
-        };
-
+        let code = code.into();
+        let key = text.into().with_str(|text| {
+            unmodified_text
+                .into()
+                .with_str(|unmodified| key_for(code, mods, text, unmodified))
+        });
         KeyEvent {
-            key_code: key_code.into(),
-            is_repeat,
+            state,
+            key,
+            code,
             mods,
-            text,
-            unmodified_text,
+            repeat,
+            is_composing: false,
         }
     }
 
-    /// The resolved input text for this event. This takes into account modifiers,
-    /// e.g. the `chars` on macOS for opt+s is 'ß'.
+    /// The text that this key types, if it is a [`Character`].
+    ///
+    /// [`Character`]: enum.KbKey.html#variant.Character
     pub fn text(&self) -> Option<&str> {
-        if self.text.len == 0 {
-            None
-        } else {
-            Some(self.text.as_str())
+        match &self.key {
+            KbKey::Character(text) => Some(text),
+            _ => None,
         }
     }
 
-    /// The unmodified input text for this event. On macOS, for opt+s, this is 's'.
-    pub fn unmod_text(&self) -> Option<&str> {
-        if self.unmodified_text.len == 0 {
-            None
+    /// For creating `KeyEvent`s during testing.
+    #[doc(hidden)]
+    pub fn for_test(mods: impl Into<KeyModifiers>, text: &'static str, code: Code) -> Self {
+        KeyEvent::new(KeyState::Down, code, false, mods.into(), text, text)
+    }
+}
+
+/// The key for a key event, given its code and text.
+fn key_for(code: Code, mods: KeyModifiers, text: &str, unmodified_text: &str) -> KbKey {
+    let types_text = |s: &str| !s.is_empty() && !s.chars().any(char::is_control);
+    if let Some(key) = code.named_key() {
+        key
+    } else if types_text(text) {
+        KbKey::Character(text.to_string())
+    } else if types_text(unmodified_text) {
+        KbKey::Character(unmodified_text.to_string())
+    } else if let Some(c) = code.qwerty_char() {
+        let text = if mods.shift {
+            c.to_uppercase().collect()
         } else {
-            Some(self.unmodified_text.as_str())
+            c.to_string()
+        };
+        KbKey::Character(text)
+    } else {
+        KbKey::Unidentified
+    }
+}
+
+impl KbKey {
+    /// Returns `true` for a modifier key, such as shift.
+    pub fn is_modifier(&self) -> bool {
+        match self {
+            KbKey::Alt
+            | KbKey::CapsLock
+            | KbKey::Control
+            | KbKey::Meta
+            | KbKey::NumLock
+            | KbKey::ScrollLock
+            | KbKey::Shift => true,
+            _ => false,
         }
     }
 
-    /// For creating `KeyEvent`s during testing.
-    #[doc(hidden)]
-    pub fn for_test(mods: impl Into<KeyModifiers>, text: &'static str, code: KeyCode) -> Self {
-        KeyEvent::new(code, false, mods.into(), text, text)
+    /// The physical key that usually produces this key, for platform menu
+    /// accelerators, which are described that way.
+    pub(crate) fn code(&self) -> Option<Code> {
+        let code = match self {
+            KbKey::Alt => Code::LeftAlt,
+            KbKey::CapsLock => Code::CapsLock,
+            KbKey::Control => Code::LeftControl,
+            KbKey::Meta => Code::LeftMeta,
+            KbKey::NumLock => Code::NumLock,
+            KbKey::ScrollLock => Code::ScrollLock,
+            KbKey::Shift => Code::LeftShift,
+            KbKey::Enter => Code::Return,
+            KbKey::Tab => Code::Tab,
+            KbKey::ArrowDown => Code::ArrowDown,
+            KbKey::ArrowLeft => Code::ArrowLeft,
+            KbKey::ArrowRight => Code::ArrowRight,
+            KbKey::ArrowUp => Code::ArrowUp,
+            KbKey::End => Code::End,
+            KbKey::Home => Code::Home,
+            KbKey::PageDown => Code::PageDown,
+            KbKey::PageUp => Code::PageUp,
+            KbKey::Backspace => Code::Backspace,
+            KbKey::Delete => Code::Delete,
+            KbKey::Insert => Code::Insert,
+            KbKey::Escape => Code::Escape,
+            KbKey::Pause => Code::Pause,
+            KbKey::PrintScreen => Code::PrintScreen,
+            KbKey::F1 => Code::F1,
+            KbKey::F2 => Code::F2,
+            KbKey::F3 => Code::F3,
+            KbKey::F4 => Code::F4,
+            KbKey::F5 => Code::F5,
+            KbKey::F6 => Code::F6,
+            KbKey::F7 => Code::F7,
+            KbKey::F8 => Code::F8,
+            KbKey::F9 => Code::F9,
+            KbKey::F10 => Code::F10,
+            KbKey::F11 => Code::F11,
+            KbKey::F12 => Code::F12,
+            KbKey::Character(_) | KbKey::Dead | KbKey::Unidentified => return None,
+        };
+        Some(code)
     }
 }
 
@@ -104,71 +274,33 @@ pub struct KeyModifiers {
     pub meta: bool,
 }
 
-/// Should realistically be (8 * N) - 1; we need one byte for the length.
-const TINY_STR_CAPACITY: usize = 15;
-
-/// A stack allocated string with a fixed capacity.
-#[derive(Clone, Copy)]
-struct TinyStr {
-    len: u8,
-    buf: [u8; TINY_STR_CAPACITY],
-}
-
-impl TinyStr {
-    fn new<S: AsRef<str>>(s: S) -> Self {
-        let s = s.as_ref();
-        let len = match s.len() {
-            l @ 0..=15 => l,
-            more => {
-                // If some user has weird unicode bound to a key, it's better to
-                // mishandle that input then to crash the client application?
-                debug_assert!(
-                    false,
-                    "Err 101, Invalid Assumptions: TinyStr::new \
-                     called with {} (len {}).",
-                    s, more
-                );
-                #[cfg(test)]
-                {
-                    // we still want to fail when testing a release build
-                    assert!(
-                        false,
-                        "Err 101, Invalid Assumptions: TinyStr::new \
-                         called with {} (len {}).",
-                        s, more
-                    );
-                }
-
-                // rups. find last valid codepoint offset
-                let mut len = 15;
-                while !s.is_char_boundary(len) {
-                    len -= 1;
-                }
-                len
-            }
-        };
-        let mut buf = [0; 15];
-        buf[..len].copy_from_slice(&s.as_bytes()[..len]);
-        TinyStr {
-            len: len as u8,
-            buf,
-        }
+impl KeyModifiers {
+    /// Returns `true` if no modifier is held.
+    pub fn is_empty(&self) -> bool {
+        !(self.shift || self.alt || self.ctrl || self.meta)
     }
 
-    fn as_str(&self) -> &str {
-        unsafe { std::str::from_utf8_unchecked(&self.buf[..self.len as usize]) }
+    /// Returns `true` if shift is held, and no other modifier is.
+    pub fn only_shift(&self) -> bool {
+        self.shift && !(self.alt || self.ctrl || self.meta)
     }
-}
 
-impl From<char> for TinyStr {
-    fn from(src: char) -> TinyStr {
-        let len = src.len_utf8();
-        let mut buf = [0; 15];
-        src.encode_utf8(&mut buf);
+    /// Returns `true` if the platform's command modifier is held.
+    ///
+    /// This is Command (meta) on macOS, and Ctrl on other platforms; it is
+    /// the modifier that [`SysMods::Cmd`] stands for.
+    ///
+    /// [`SysMods::Cmd`]: enum.SysMods.html#variant.Cmd
+    pub fn command(&self) -> bool {
+        self.command_for(cfg!(target_os = "macos"))
+    }
 
-        TinyStr {
-            len: len as u8,
-            buf,
+    /// The command modifier, on macOS or elsewhere.
+    pub(crate) fn command_for(&self, macos: bool) -> bool {
+        if macos {
+            self.meta
+        } else {
+            self.ctrl
         }
     }
 }
@@ -180,6 +312,15 @@ pub enum StrOrChar {
     Str(&'static str),
 }
 
+impl StrOrChar {
+    fn with_str<R>(self, f: impl FnOnce(&str) -> R) -> R {
+        match self {
+            StrOrChar::Char(c) => f(c.encode_utf8(&mut [0; 4])),
+            StrOrChar::Str(s) => f(s),
+        }
+    }
+}
+
 impl From<&'static str> for StrOrChar {
     fn from(src: &'static str) -> Self {
         StrOrChar::Str(src)
@@ -201,24 +342,6 @@ impl From<Option<char>> for StrOrChar {
     }
 }
 
-impl fmt::Display for TinyStr {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.as_str())
-    }
-}
-
-impl fmt::Debug for TinyStr {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "TinyStr(\"{}\")", self.as_str())
-    }
-}
-
-impl Default for TinyStr {
-    fn default() -> Self {
-        TinyStr::new("")
-    }
-}
-
 #[allow(clippy::useless_let_if_seq)]
 impl fmt::Debug for KeyModifiers {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -259,16 +382,54 @@ impl fmt::Debug for KeyModifiers {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hotkey::RawMods;
+
+    fn character(text: &str) -> KbKey {
+        KbKey::Character(text.to_string())
+    }
+
+    #[test]
+    fn key_from_code_and_text() {
+        let key = |mods, text, unmodified, code| {
+            KeyEvent::new(KeyState::Down, code, false, mods, text, unmodified).key
+        };
+        let none = KeyModifiers::default();
+        let ctrl: KeyModifiers = RawMods::Ctrl.into();
+        let ctrl_shift: KeyModifiers = RawMods::CtrlShift.into();
+
+        assert_eq!(key(none, "a", "a", Code::KeyA), character("a"));
+        // the layout decides the text, not the code
+        assert_eq!(key(none, "q", "q", Code::KeyA), character("q"));
+        assert_eq!(key(none, " ", " ", Code::Space), character(" "));
+        // named keys are found from the code, whatever their text
+        assert_eq!(key(none, "\t", "\t", Code::Tab), KbKey::Tab);
+        assert_eq!(key(none, "\r", "\r", Code::Return), KbKey::Enter);
+        assert_eq!(key(none, "\r", "\r", Code::NumpadEnter), KbKey::Enter);
+        assert_eq!(key(none, "", "", Code::ArrowLeft), KbKey::ArrowLeft);
+        assert_eq!(key(none, "", "", Code::LeftShift), KbKey::Shift);
+        // ctrl+a types a control character on some platforms
+        assert_eq!(key(ctrl, "\u{1}", "a", Code::KeyA), character("a"));
+        assert_eq!(key(ctrl, "\u{1}", "\u{1}", Code::KeyA), character("a"));
+        assert_eq!(
+            key(ctrl_shift, "\u{1}", "\u{1}", Code::KeyA),
+            character("A")
+        );
+        assert_eq!(key(none, "", "", Code::Unknown(0)), KbKey::Unidentified);
+    }
+
     #[test]
-    #[should_panic(expected = "Invalid Assumptions")]
-    fn smallstr() {
-        let smol = TinyStr::new("hello");
-        assert_eq!(smol.as_str(), "hello");
-        let smol = TinyStr::new("");
-        assert_eq!(smol.as_str(), "");
-        let s_16 = "😍🥰😘😗";
-        assert_eq!(s_16.len(), 16);
-        assert!(!s_16.is_char_boundary(15));
-        let _too_big = TinyStr::new("😍🥰😘😗");
+    fn repeat_and_state_are_kept() {
+        let event = KeyEvent::new(
+            KeyState::Up,
+            Code::KeyX,
+            true,
+            RawMods::None.into(),
+            'x',
+            'x',
+        );
+        assert_eq!(event.state, KeyState::Up);
+        assert!(event.repeat);
+        assert!(!event.is_composing);
+        assert_eq!(event.text(), Some("x"));
     }
 }
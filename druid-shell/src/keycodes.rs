@@ -14,6 +14,7 @@
 
 //! Platform-independent key codes.
 
+use crate::keyboard::KbKey;
 use crate::platform::keycodes as platform;
 
 //NOTE: This was mostly taken from makepad, which I'm sure took it from somewhere else.
@@ -21,11 +22,15 @@ use crate::platform::keycodes as platform;
 // I know of for this is probably the MDN keyboard event docs:
 // https://developer.mozilla.org/en-US/docs/Web/API/KeyboardEvent/code
 
-/// A platform-independent key identifier.
+/// The physical key of a key event, as the W3C `code` attribute.
 ///
-/// This ignores things like the user's keyboard layout.
+/// This ignores things like the user's keyboard layout: the names are those
+/// of the keys in a US QWERTY layout. For the meaning of the key in the
+/// user's layout, see [`KbKey`].
+///
+/// [`KbKey`]: enum.KbKey.html
 #[derive(Clone, Copy, PartialEq, Debug)]
-pub enum KeyCode {
+pub enum Code {
     Escape,
 
     Backtick,
@@ -149,9 +154,9 @@ pub enum KeyCode {
     Unknown(platform::RawKeyCode),
 }
 
-impl KeyCode {
+impl Code {
     pub fn is_printable(self) -> bool {
-        use KeyCode::*;
+        use Code::*;
         match self {
             Backtick | Key0 | Key1 | Key2 | Key3 | Key4 | Key5 | Key6 | Key7 | Key8 | Key9
             | Minus | Equals | Tab | KeyQ | KeyW | KeyE | KeyR | KeyT | KeyY | KeyU | KeyI
@@ -164,4 +169,96 @@ impl KeyCode {
             _ => false,
         }
     }
+
+    /// The key for a code that doesn't type text, whatever the layout.
+    pub(crate) fn named_key(self) -> Option<KbKey> {
+        use Code::*;
+        let key = match self {
+            Escape => KbKey::Escape,
+            Backspace => KbKey::Backspace,
+            Tab => KbKey::Tab,
+            Return | NumpadEnter => KbKey::Enter,
+            LeftControl | RightControl => KbKey::Control,
+            LeftAlt | RightAlt => KbKey::Alt,
+            LeftShift | RightShift => KbKey::Shift,
+            LeftMeta | RightMeta => KbKey::Meta,
+            CapsLock => KbKey::CapsLock,
+            F1 => KbKey::F1,
+            F2 => KbKey::F2,
+            F3 => KbKey::F3,
+            F4 => KbKey::F4,
+            F5 => KbKey::F5,
+            F6 => KbKey::F6,
+            F7 => KbKey::F7,
+            F8 => KbKey::F8,
+            F9 => KbKey::F9,
+            F10 => KbKey::F10,
+            F11 => KbKey::F11,
+            F12 => KbKey::F12,
+            PrintScreen => KbKey::PrintScreen,
+            ScrollLock => KbKey::ScrollLock,
+            Pause => KbKey::Pause,
+            Insert => KbKey::Insert,
+            Delete => KbKey::Delete,
+            Home => KbKey::Home,
+            End => KbKey::End,
+            PageUp => KbKey::PageUp,
+            PageDown => KbKey::PageDown,
+            NumLock => KbKey::NumLock,
+            ArrowUp => KbKey::ArrowUp,
+            ArrowDown => KbKey::ArrowDown,
+            ArrowLeft => KbKey::ArrowLeft,
+            ArrowRight => KbKey::ArrowRight,
+            _ => return None,
+        };
+        Some(key)
+    }
+
+    /// The letter or digit on this key in a US QWERTY layout.
+    ///
+    /// This is for when the platform gives a control character as the text,
+    /// as it does for ctrl+a on some platforms.
+    pub(crate) fn qwerty_char(self) -> Option<char> {
+        use Code::*;
+        let c = match self {
+            KeyA => 'a',
+            KeyB => 'b',
+            KeyC => 'c',
+            KeyD => 'd',
+            KeyE => 'e',
+            KeyF => 'f',
+            KeyG => 'g',
+            KeyH => 'h',
+            KeyI => 'i',
+            KeyJ => 'j',
+            KeyK => 'k',
+            KeyL => 'l',
+            KeyM => 'm',
+            KeyN => 'n',
+            KeyO => 'o',
+            KeyP => 'p',
+            KeyQ => 'q',
+            KeyR => 'r',
+            KeyS => 's',
+            KeyT => 't',
+            KeyU => 'u',
+            KeyV => 'v',
+            KeyW => 'w',
+            KeyX => 'x',
+            KeyY => 'y',
+            KeyZ => 'z',
+            Key0 | Numpad0 => '0',
+            Key1 | Numpad1 => '1',
+            Key2 | Numpad2 => '2',
+            Key3 | Numpad3 => '3',
+            Key4 | Numpad4 => '4',
+            Key5 | Numpad5 => '5',
+            Key6 | Numpad6 => '6',
+            Key7 | Numpad7 => '7',
+            Key8 | Numpad8 => '8',
+            Key9 | Numpad9 => '9',
+            _ => return None,
+        };
+        Some(c)
+    }
 }
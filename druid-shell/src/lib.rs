@@ -36,10 +36,12 @@ extern crate objc;
 #[macro_use]
 extern crate lazy_static;
 
+mod access;
 mod application;
 mod clipboard;
 mod common_util;
 mod dialog;
+mod drag;
 mod error;
 mod hotkey;
 mod keyboard;
@@ -47,18 +49,26 @@ mod keycodes;
 mod menu;
 mod mouse;
 mod platform;
+mod scale;
+mod screen;
+mod timer;
 mod window;
 
-pub use application::{AppHandler, Application};
+pub use access::{AccessEvent, AccessNode, Role};
+pub use application::{AppHandler, AppIdleHandle, Application};
 pub use clipboard::{Clipboard, ClipboardFormat, FormatId};
 pub use common_util::Counter;
 pub use dialog::{FileDialogOptions, FileInfo, FileSpec};
+pub use drag::{DragEvent, DragPayload};
 pub use error::Error;
 pub use hotkey::{HotKey, KeyCompare, RawMods, SysMods};
-pub use keyboard::{KeyEvent, KeyModifiers};
-pub use keycodes::KeyCode;
+pub use keyboard::{CompositionEvent, KbKey, KeyEvent, KeyModifiers, KeyState};
+pub use keycodes::Code;
 pub use menu::Menu;
-pub use mouse::{Cursor, MouseButton, MouseEvent};
+pub use mouse::{Cursor, MouseButton, MouseButtons, MouseEvent, ScrollPhase};
+pub use scale::{Scalable, Scale};
+pub use screen::{Monitor, Screen};
 pub use window::{
-    IdleHandle, IdleToken, Text, TimerToken, WinHandler, WindowBuilder, WindowHandle,
+    CapturedImage, ChildSurface, IdleHandle, IdleToken, RenderBackend, Text, TimerToken,
+    WinHandler, WindowBuilder, WindowHandle, WindowLevel, WindowState,
 };
@@ -33,6 +33,14 @@ pub struct MouseEvent {
     /// The currently pressed button in the case of a move or click event,
     /// or the released button in the case of a mouse-up event.
     pub button: MouseButton,
+    /// The buttons that are held down once this event has happened.
+    ///
+    /// For a click this includes `button`, and for a mouse-up it doesn't.
+    pub buttons: MouseButtons,
+    /// Whether this click also brought the window into focus.
+    ///
+    /// This is only reported on Windows; elsewhere it is always `false`.
+    pub focus: bool,
 }
 
 /// An indicator of which mouse button was pressed.
@@ -62,13 +70,119 @@ impl MouseButton {
     pub fn is_right(self) -> bool {
         self == MouseButton::Right
     }
+
+    fn bit(self) -> u8 {
+        match self {
+            MouseButton::Left => 1,
+            MouseButton::Middle => 1 << 1,
+            MouseButton::Right => 1 << 2,
+            MouseButton::X1 => 1 << 3,
+            MouseButton::X2 => 1 << 4,
+        }
+    }
+}
+
+/// A set of [`MouseButton`]s, such as the buttons that are held down.
+///
+/// [`MouseButton`]: enum.MouseButton.html
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub struct MouseButtons(u8);
+
+impl MouseButtons {
+    /// An empty set.
+    pub fn new() -> MouseButtons {
+        MouseButtons(0)
+    }
+
+    /// This set, with `button` added.
+    pub fn with(mut self, button: MouseButton) -> MouseButtons {
+        self.insert(button);
+        self
+    }
+
+    /// Add `button` to the set.
+    pub fn insert(&mut self, button: MouseButton) {
+        self.0 |= button.bit();
+    }
+
+    /// Remove `button` from the set.
+    pub fn remove(&mut self, button: MouseButton) {
+        self.0 &= !button.bit();
+    }
+
+    /// Returns `true` if the set contains `button`.
+    pub fn contains(self, button: MouseButton) -> bool {
+        self.0 & button.bit() != 0
+    }
+
+    /// Returns `true` if the set is empty.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if the set contains the left mouse button.
+    pub fn has_left(self) -> bool {
+        self.contains(MouseButton::Left)
+    }
+
+    /// Returns `true` if the set contains the right mouse button.
+    pub fn has_right(self) -> bool {
+        self.contains(MouseButton::Right)
+    }
+
+    /// Returns `true` if the set contains the middle mouse button.
+    pub fn has_middle(self) -> bool {
+        self.contains(MouseButton::Middle)
+    }
+
+    /// The buttons in the set.
+    pub fn iter(self) -> impl Iterator<Item = MouseButton> {
+        const ALL: [MouseButton; 5] = [
+            MouseButton::Left,
+            MouseButton::Middle,
+            MouseButton::Right,
+            MouseButton::X1,
+            MouseButton::X2,
+        ];
+        ALL.iter()
+            .cloned()
+            .filter(move |button| self.contains(*button))
+    }
+}
+
+impl From<MouseButton> for MouseButtons {
+    fn from(button: MouseButton) -> MouseButtons {
+        MouseButtons::new().with(button)
+    }
+}
+
+impl std::fmt::Debug for MouseButtons {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Where a wheel event is in a scrolling gesture, on devices that report it,
+/// such as trackpads.
+///
+/// A mouse wheel has no gestures; each of its events is `Changed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollPhase {
+    /// The first event of the gesture, such as fingers touching the
+    /// trackpad.
+    Started,
+    /// The gesture continues.
+    Changed,
+    /// The last event of the gesture, such as fingers leaving the trackpad.
+    /// It may have no delta.
+    Ended,
 }
 
 //NOTE: this currently only contains cursors that are included by default on
 //both Windows and macOS. We may want to provide polyfills for various additional cursors,
 //and we will also want to add some mechanism for adding custom cursors.
 /// Mouse cursors.
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Cursor {
     /// The default arrow cursor.
     Arrow,
@@ -80,3 +194,27 @@ pub enum Cursor {
     ResizeLeftRight,
     ResizeUpDown,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_sets() {
+        let mut buttons = MouseButtons::new().with(MouseButton::Left);
+        assert!(buttons.has_left() && !buttons.has_right());
+        buttons.insert(MouseButton::X2);
+        assert_eq!(
+            buttons.iter().collect::<Vec<_>>(),
+            vec![MouseButton::Left, MouseButton::X2]
+        );
+        buttons.remove(MouseButton::Left);
+        assert_eq!(buttons, MouseButton::X2.into());
+        buttons.remove(MouseButton::X2);
+        assert!(buttons.is_empty());
+        assert_eq!(
+            format!("{:?}", MouseButtons::from(MouseButton::Right)),
+            "{Right}"
+        );
+    }
+}
@@ -15,26 +15,38 @@
 //! GTK implementation of features at the application scope.
 
 use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use gio::prelude::ApplicationExtManual;
 use gio::{ApplicationExt, ApplicationFlags, Cancellable};
-use gtk::{Application as GtkApplication, GtkApplicationExt};
+use gtk::{Application as GtkApplication, GtkApplicationExt, SettingsExt};
 
 use super::clipboard::Clipboard;
 use super::util;
+use super::window::threads_add_idle;
 use crate::application::AppHandler;
+use crate::window::{IdleToken, TimerToken};
 
 // XXX: The application needs to be global because WindowBuilder::build wants
 // to construct an ApplicationWindow, which needs the application, but
 // WindowBuilder::build does not get the RunLoop
 thread_local!(
     static GTK_APPLICATION: RefCell<Option<GtkApplication>> = RefCell::new(None);
+    static APP_HANDLER: RefCell<Option<Box<dyn AppHandler>>> = RefCell::new(None);
+    static IDLE_QUEUE: Arc<Mutex<Vec<IdleToken>>> = Arc::new(Mutex::new(Vec::new()));
 );
 
 pub struct Application;
 
+#[derive(Clone)]
+pub struct AppIdleHandle {
+    idle_queue: Arc<Mutex<Vec<IdleToken>>>,
+}
+
 impl Application {
-    pub fn new(_handler: Option<Box<dyn AppHandler>>) -> Application {
+    pub fn new(handler: Option<Box<dyn AppHandler>>) -> Application {
         // TODO: we should give control over the application ID to the user
         let application = GtkApplication::new(
             Some("com.github.xi-editor.druid"),
@@ -55,7 +67,12 @@ impl Application {
             .register(None as Option<&Cancellable>)
             .expect("Could not register GTK application");
 
+        // GTK would stop the application when it has no windows; like the other
+        // platforms, we keep running until we are asked to quit.
+        application.hold();
+
         GTK_APPLICATION.with(move |x| *x.borrow_mut() = Some(application));
+        APP_HANDLER.with(move |x| *x.borrow_mut() = handler);
         Application
     }
 
@@ -73,17 +90,39 @@ impl Application {
 
     pub fn quit() {
         util::assert_main_thread();
-        with_application(|app| {
-            match app.get_active_window() {
-                None => {
-                    // no application is running, main is not running
-                }
-                Some(_) => {
-                    // we still have an active window, close the run loop
-                    app.quit();
-                }
+        // if the run loop has not started yet, it returns as soon as it does
+        with_application(|app| app.quit());
+    }
+
+    pub fn get_idle_handle() -> Option<AppIdleHandle> {
+        util::assert_main_thread();
+        if GTK_APPLICATION.with(|x| x.borrow().is_none()) {
+            return None;
+        }
+        let idle_queue = IDLE_QUEUE.with(|queue| queue.clone());
+        Some(AppIdleHandle { idle_queue })
+    }
+
+    pub fn request_timer(deadline: Instant) -> TimerToken {
+        util::assert_main_thread();
+        let interval = deadline
+            .checked_duration_since(Instant::now())
+            .unwrap_or_default()
+            .as_millis();
+        let interval = match u32::try_from(interval) {
+            Ok(iv) => iv,
+            Err(_) => {
+                log::warn!("timer duration exceeds gtk max of 2^32 millis");
+                u32::max_value()
             }
+        };
+
+        let token = TimerToken::next();
+        // if the handler is busy, we try again on the next iteration
+        gdk::threads_add_timeout(interval, move || {
+            !with_handler(|handler| handler.timer(token))
         });
+        token
     }
 
     pub fn clipboard() -> Clipboard {
@@ -94,6 +133,58 @@ impl Application {
         //TODO ahem
         "en-US".into()
     }
+
+    pub fn prefers_reduced_motion() -> bool {
+        // this can be called before the application is created
+        if !gtk::is_initialized() && gtk::init().is_err() {
+            return false;
+        }
+        gtk::Settings::get_default()
+            .map(|settings| !settings.get_property_gtk_enable_animations())
+            .unwrap_or(false)
+    }
+}
+
+impl AppIdleHandle {
+    pub fn add_idle_token(&self, token: IdleToken) {
+        let mut queue = self.idle_queue.lock().unwrap();
+        if queue.is_empty() {
+            let idle_queue = self.idle_queue.clone();
+            threads_add_idle(move || run_idle(&idle_queue));
+        }
+        queue.push(token);
+    }
+}
+
+fn run_idle(idle_queue: &Arc<Mutex<Vec<IdleToken>>>) -> bool {
+    util::assert_main_thread();
+    let mut queue: Vec<_> = std::mem::replace(&mut idle_queue.lock().unwrap(), Vec::new());
+    let handled = with_handler(|handler| {
+        for token in &queue {
+            handler.idle(*token);
+        }
+    });
+    if !handled {
+        // the handler is busy; put the tokens back and try again
+        let mut current = idle_queue.lock().unwrap();
+        queue.append(&mut current);
+        *current = queue;
+    }
+    !handled
+}
+
+/// Call `f` with the app handler, if there is one and it is not already
+/// borrowed. Returns `false` if it was borrowed.
+fn with_handler(f: impl FnOnce(&mut dyn AppHandler)) -> bool {
+    APP_HANDLER.with(|handler| match handler.try_borrow_mut() {
+        Ok(mut handler) => {
+            if let Some(handler) = handler.as_mut() {
+                f(handler.as_mut());
+            }
+            true
+        }
+        Err(_) => false,
+    })
 }
 
 #[inline]
@@ -21,19 +21,20 @@ use gtk::{FileChooserAction, FileChooserExt, NativeDialogExt, ResponseType, Wind
 
 use crate::Error;
 
-pub(crate) fn get_file_dialog_path(
+pub(crate) fn get_file_dialog_paths(
     window: &Window,
     ty: FileDialogType,
     options: FileDialogOptions,
-) -> Result<OsString, Error> {
+) -> Result<Vec<OsString>, Error> {
     // TODO: support message localization
 
-    let (title, action) = match (ty, options.select_directories) {
+    let (title, action) = match (&ty, options.select_directories) {
         (FileDialogType::Open, false) => ("Open File", FileChooserAction::Open),
         (FileDialogType::Open, true) => ("Open Folder", FileChooserAction::SelectFolder),
         (FileDialogType::Save, _) => ("Save File", FileChooserAction::Save),
     };
 
+    let title = options.title.as_deref().unwrap_or(title);
     let dialog = gtk::FileChooserNativeBuilder::new()
         .transient_for(window)
         .title(title)
@@ -45,13 +46,32 @@ pub(crate) fn get_file_dialog_path(
 
     dialog.set_select_multiple(options.multi_selection);
 
+    if let Some(directory) = &options.starting_directory {
+        dialog.set_current_folder(directory);
+    }
+
+    if let Some(name) = &options.default_name {
+        match (&ty, &options.starting_directory) {
+            (FileDialogType::Save, _) => dialog.set_current_name(name),
+            // there is no name to suggest when opening; select the file instead
+            (FileDialogType::Open, Some(directory)) => {
+                dialog.set_filename(directory.join(name));
+            }
+            (FileDialogType::Open, None) => (),
+        }
+    }
+
     let result = dialog.run();
 
     let result = match result {
-        ResponseType::Accept => match dialog.get_filename() {
-            Some(path) => Ok(path.into_os_string()),
-            None => Err(Error::Other("No path received for filename")),
-        },
+        ResponseType::Accept => {
+            let paths = dialog.get_filenames();
+            if paths.is_empty() {
+                Err(Error::Other("No path received for filename"))
+            } else {
+                Ok(paths.into_iter().map(|p| p.into_os_string()).collect())
+            }
+        }
         ResponseType::Cancel => Err(Error::Other("Dialog was deleted")),
         _ => {
             log::warn!("Unhandled dialog result: {:?}", result);
@@ -16,260 +16,259 @@
 
 use gdk::enums::key::*;
 
-use crate::keycodes::KeyCode;
+use crate::keycodes::Code;
 
 pub type RawKeyCode = u32;
 
-impl From<u32> for KeyCode {
+impl From<u32> for Code {
     #[allow(clippy::just_underscores_and_digits, non_upper_case_globals)]
-    fn from(raw: u32) -> KeyCode {
+    fn from(raw: u32) -> Code {
         match raw {
-            Escape => KeyCode::Escape,
-            grave => KeyCode::Backtick,
-            _0 => KeyCode::Key0,
-            _1 => KeyCode::Key1,
-            _2 => KeyCode::Key2,
-            _3 => KeyCode::Key3,
-            _4 => KeyCode::Key4,
-            _5 => KeyCode::Key5,
-            _6 => KeyCode::Key6,
-            _7 => KeyCode::Key7,
-            _8 => KeyCode::Key8,
-            _9 => KeyCode::Key9,
-            minus => KeyCode::Minus,
-            equal => KeyCode::Equals,
-            BackSpace => KeyCode::Backspace,
+            Escape => Code::Escape,
+            grave => Code::Backtick,
+            _0 => Code::Key0,
+            _1 => Code::Key1,
+            _2 => Code::Key2,
+            _3 => Code::Key3,
+            _4 => Code::Key4,
+            _5 => Code::Key5,
+            _6 => Code::Key6,
+            _7 => Code::Key7,
+            _8 => Code::Key8,
+            _9 => Code::Key9,
+            minus => Code::Minus,
+            equal => Code::Equals,
+            BackSpace => Code::Backspace,
 
-            Tab => KeyCode::Tab,
-            q | Q => KeyCode::KeyQ,
-            w | W => KeyCode::KeyW,
-            e | E => KeyCode::KeyE,
-            r | R => KeyCode::KeyR,
-            t | T => KeyCode::KeyT,
-            y | Y => KeyCode::KeyY,
-            u | U => KeyCode::KeyU,
-            i | I => KeyCode::KeyI,
-            o | O => KeyCode::KeyO,
-            p | P => KeyCode::KeyP,
-            bracketleft => KeyCode::LeftBracket,
-            bracketright => KeyCode::RightBracket,
-            Return => KeyCode::Return,
+            Tab => Code::Tab,
+            q | Q => Code::KeyQ,
+            w | W => Code::KeyW,
+            e | E => Code::KeyE,
+            r | R => Code::KeyR,
+            t | T => Code::KeyT,
+            y | Y => Code::KeyY,
+            u | U => Code::KeyU,
+            i | I => Code::KeyI,
+            o | O => Code::KeyO,
+            p | P => Code::KeyP,
+            bracketleft => Code::LeftBracket,
+            bracketright => Code::RightBracket,
+            Return => Code::Return,
 
-            a | A => KeyCode::KeyA,
-            s | S => KeyCode::KeyS,
-            d | D => KeyCode::KeyD,
-            f | F => KeyCode::KeyF,
-            g | G => KeyCode::KeyG,
-            h | H => KeyCode::KeyH,
-            j | J => KeyCode::KeyJ,
-            k | K => KeyCode::KeyK,
-            l | L => KeyCode::KeyL,
-            semicolon => KeyCode::Semicolon,
-            quoteright => KeyCode::Quote,
-            backslash => KeyCode::Backslash,
+            a | A => Code::KeyA,
+            s | S => Code::KeyS,
+            d | D => Code::KeyD,
+            f | F => Code::KeyF,
+            g | G => Code::KeyG,
+            h | H => Code::KeyH,
+            j | J => Code::KeyJ,
+            k | K => Code::KeyK,
+            l | L => Code::KeyL,
+            semicolon => Code::Semicolon,
+            quoteright => Code::Quote,
+            backslash => Code::Backslash,
 
-            z | Z => KeyCode::KeyZ,
-            x | X => KeyCode::KeyX,
-            c | C => KeyCode::KeyC,
-            v | V => KeyCode::KeyV,
-            b | B => KeyCode::KeyB,
-            n | N => KeyCode::KeyN,
-            m | M => KeyCode::KeyM,
-            comma => KeyCode::Comma,
-            period => KeyCode::Period,
-            slash => KeyCode::Slash,
+            z | Z => Code::KeyZ,
+            x | X => Code::KeyX,
+            c | C => Code::KeyC,
+            v | V => Code::KeyV,
+            b | B => Code::KeyB,
+            n | N => Code::KeyN,
+            m | M => Code::KeyM,
+            comma => Code::Comma,
+            period => Code::Period,
+            slash => Code::Slash,
 
-            Control_L => KeyCode::LeftControl,
-            Control_R => KeyCode::RightControl,
-            Alt_L => KeyCode::LeftAlt,
-            Alt_R => KeyCode::RightAlt,
-            Shift_L => KeyCode::LeftShift,
-            Shift_R => KeyCode::RightShift,
-            Super_L => KeyCode::LeftMeta,
-            Super_R => KeyCode::RightMeta,
+            Control_L => Code::LeftControl,
+            Control_R => Code::RightControl,
+            Alt_L => Code::LeftAlt,
+            Alt_R => Code::RightAlt,
+            Shift_L => Code::LeftShift,
+            Shift_R => Code::RightShift,
+            Super_L => Code::LeftMeta,
+            Super_R => Code::RightMeta,
 
-            space => KeyCode::Space,
-            Caps_Lock => KeyCode::CapsLock,
-            F1 => KeyCode::F1,
-            F2 => KeyCode::F2,
-            F3 => KeyCode::F3,
-            F4 => KeyCode::F4,
-            F5 => KeyCode::F5,
-            F6 => KeyCode::F6,
-            F7 => KeyCode::F7,
-            F8 => KeyCode::F8,
-            F9 => KeyCode::F9,
-            F10 => KeyCode::F10,
-            F11 => KeyCode::F11,
-            F12 => KeyCode::F12,
+            space => Code::Space,
+            Caps_Lock => Code::CapsLock,
+            F1 => Code::F1,
+            F2 => Code::F2,
+            F3 => Code::F3,
+            F4 => Code::F4,
+            F5 => Code::F5,
+            F6 => Code::F6,
+            F7 => Code::F7,
+            F8 => Code::F8,
+            F9 => Code::F9,
+            F10 => Code::F10,
+            F11 => Code::F11,
+            F12 => Code::F12,
 
-            Print => KeyCode::PrintScreen,
-            Scroll_Lock => KeyCode::ScrollLock,
+            Print => Code::PrintScreen,
+            Scroll_Lock => Code::ScrollLock,
             // Pause/Break not audio.
-            Pause => KeyCode::Pause,
+            Pause => Code::Pause,
 
-            Insert => KeyCode::Insert,
-            Delete => KeyCode::Delete,
-            Home => KeyCode::Home,
-            End => KeyCode::End,
-            Page_Up => KeyCode::PageUp,
-            Page_Down => KeyCode::PageDown,
+            Insert => Code::Insert,
+            Delete => Code::Delete,
+            Home => Code::Home,
+            End => Code::End,
+            Page_Up => Code::PageUp,
+            Page_Down => Code::PageDown,
 
-            KP_0 => KeyCode::Numpad0,
-            KP_1 => KeyCode::Numpad1,
-            KP_2 => KeyCode::Numpad2,
-            KP_3 => KeyCode::Numpad3,
-            KP_4 => KeyCode::Numpad4,
-            KP_5 => KeyCode::Numpad5,
-            KP_6 => KeyCode::Numpad6,
-            KP_7 => KeyCode::Numpad7,
-            KP_8 => KeyCode::Numpad8,
-            KP_9 => KeyCode::Numpad9,
+            KP_0 => Code::Numpad0,
+            KP_1 => Code::Numpad1,
+            KP_2 => Code::Numpad2,
+            KP_3 => Code::Numpad3,
+            KP_4 => Code::Numpad4,
+            KP_5 => Code::Numpad5,
+            KP_6 => Code::Numpad6,
+            KP_7 => Code::Numpad7,
+            KP_8 => Code::Numpad8,
+            KP_9 => Code::Numpad9,
 
-            KP_Equal => KeyCode::NumpadEquals,
-            KP_Subtract => KeyCode::NumpadSubtract,
-            KP_Add => KeyCode::NumpadAdd,
-            KP_Decimal => KeyCode::NumpadDecimal,
-            KP_Multiply => KeyCode::NumpadMultiply,
-            KP_Divide => KeyCode::NumpadDivide,
-            Num_Lock => KeyCode::NumLock,
-            KP_Enter => KeyCode::NumpadEnter,
+            KP_Equal => Code::NumpadEquals,
+            KP_Subtract => Code::NumpadSubtract,
+            KP_Add => Code::NumpadAdd,
+            KP_Decimal => Code::NumpadDecimal,
+            KP_Multiply => Code::NumpadMultiply,
+            KP_Divide => Code::NumpadDivide,
+            Num_Lock => Code::NumLock,
+            KP_Enter => Code::NumpadEnter,
 
-            Up => KeyCode::ArrowUp,
-            Down => KeyCode::ArrowDown,
-            Left => KeyCode::ArrowLeft,
-            Right => KeyCode::ArrowRight,
+            Up => Code::ArrowUp,
+            Down => Code::ArrowDown,
+            Left => Code::ArrowLeft,
+            Right => Code::ArrowRight,
             _ => {
                 log::warn!("Warning: unknown keyval {}", raw);
-                KeyCode::Unknown(raw)
+                Code::Unknown(raw)
             }
         }
     }
 }
 
-impl From<KeyCode> for u32 {
+impl From<Code> for u32 {
     #[allow(non_upper_case_globals)]
-    fn from(src: KeyCode) -> u32 {
+    fn from(src: Code) -> u32 {
         match src {
-            KeyCode::Escape => Escape,
-            KeyCode::Backtick => grave,
-            KeyCode::Key0 => _0,
-            KeyCode::Key1 => _1,
-            KeyCode::Key2 => _2,
-            KeyCode::Key3 => _3,
-            KeyCode::Key4 => _4,
-            KeyCode::Key5 => _5,
-            KeyCode::Key6 => _6,
-            KeyCode::Key7 => _7,
-            KeyCode::Key8 => _8,
-            KeyCode::Key9 => _9,
-            KeyCode::Minus => minus,
-            KeyCode::Equals => equal,
-            KeyCode::Backspace => BackSpace,
+            Code::Escape => Escape,
+            Code::Backtick => grave,
+            Code::Key0 => _0,
+            Code::Key1 => _1,
+            Code::Key2 => _2,
+            Code::Key3 => _3,
+            Code::Key4 => _4,
+            Code::Key5 => _5,
+            Code::Key6 => _6,
+            Code::Key7 => _7,
+            Code::Key8 => _8,
+            Code::Key9 => _9,
+            Code::Minus => minus,
+            Code::Equals => equal,
+            Code::Backspace => BackSpace,
 
-            KeyCode::Tab => Tab,
-            KeyCode::KeyQ => q | Q,
-            KeyCode::KeyW => w | W,
-            KeyCode::KeyE => e | E,
-            KeyCode::KeyR => r | R,
-            KeyCode::KeyT => t | T,
-            KeyCode::KeyY => y | Y,
-            KeyCode::KeyU => u | U,
-            KeyCode::KeyI => i | I,
-            KeyCode::KeyO => o | O,
-            KeyCode::KeyP => p | P,
-            KeyCode::LeftBracket => bracketleft,
-            KeyCode::RightBracket => bracketright,
-            KeyCode::Return => Return,
+            Code::Tab => Tab,
+            Code::KeyQ => q | Q,
+            Code::KeyW => w | W,
+            Code::KeyE => e | E,
+            Code::KeyR => r | R,
+            Code::KeyT => t | T,
+            Code::KeyY => y | Y,
+            Code::KeyU => u | U,
+            Code::KeyI => i | I,
+            Code::KeyO => o | O,
+            Code::KeyP => p | P,
+            Code::LeftBracket => bracketleft,
+            Code::RightBracket => bracketright,
+            Code::Return => Return,
 
-            KeyCode::KeyA => a | A,
-            KeyCode::KeyS => s | S,
-            KeyCode::KeyD => d | D,
-            KeyCode::KeyF => f | F,
-            KeyCode::KeyG => g | G,
-            KeyCode::KeyH => h | H,
-            KeyCode::KeyJ => j | J,
-            KeyCode::KeyK => k | K,
-            KeyCode::KeyL => l | L,
-            KeyCode::Semicolon => semicolon,
-            KeyCode::Quote => quoteright,
-            KeyCode::Backslash => backslash,
+            Code::KeyA => a | A,
+            Code::KeyS => s | S,
+            Code::KeyD => d | D,
+            Code::KeyF => f | F,
+            Code::KeyG => g | G,
+            Code::KeyH => h | H,
+            Code::KeyJ => j | J,
+            Code::KeyK => k | K,
+            Code::KeyL => l | L,
+            Code::Semicolon => semicolon,
+            Code::Quote => quoteright,
+            Code::Backslash => backslash,
 
-            KeyCode::KeyZ => z | Z,
-            KeyCode::KeyX => x | X,
-            KeyCode::KeyC => c | C,
-            KeyCode::KeyV => v | V,
-            KeyCode::KeyB => b | B,
-            KeyCode::KeyN => n | N,
-            KeyCode::KeyM => m | M,
-            KeyCode::Comma => comma,
-            KeyCode::Period => period,
-            KeyCode::Slash => slash,
+            Code::KeyZ => z | Z,
+            Code::KeyX => x | X,
+            Code::KeyC => c | C,
+            Code::KeyV => v | V,
+            Code::KeyB => b | B,
+            Code::KeyN => n | N,
+            Code::KeyM => m | M,
+            Code::Comma => comma,
+            Code::Period => period,
+            Code::Slash => slash,
 
-            KeyCode::LeftControl => Control_L,
-            KeyCode::RightControl => Control_R,
-            KeyCode::LeftAlt => Alt_L,
-            KeyCode::RightAlt => Alt_R,
-            KeyCode::LeftShift => Shift_L,
-            KeyCode::RightShift => Shift_R,
-            KeyCode::LeftMeta => Super_L,
-            KeyCode::RightMeta => Super_R,
+            Code::LeftControl => Control_L,
+            Code::RightControl => Control_R,
+            Code::LeftAlt => Alt_L,
+            Code::RightAlt => Alt_R,
+            Code::LeftShift => Shift_L,
+            Code::RightShift => Shift_R,
+            Code::LeftMeta => Super_L,
+            Code::RightMeta => Super_R,
 
-            KeyCode::Space => space,
-            KeyCode::CapsLock => Caps_Lock,
-            KeyCode::F1 => F1,
-            KeyCode::F2 => F2,
-            KeyCode::F3 => F3,
-            KeyCode::F4 => F4,
-            KeyCode::F5 => F5,
-            KeyCode::F6 => F6,
-            KeyCode::F7 => F7,
-            KeyCode::F8 => F8,
-            KeyCode::F9 => F9,
-            KeyCode::F10 => F10,
-            KeyCode::F11 => F11,
-            KeyCode::F12 => F12,
+            Code::Space => space,
+            Code::CapsLock => Caps_Lock,
+            Code::F1 => F1,
+            Code::F2 => F2,
+            Code::F3 => F3,
+            Code::F4 => F4,
+            Code::F5 => F5,
+            Code::F6 => F6,
+            Code::F7 => F7,
+            Code::F8 => F8,
+            Code::F9 => F9,
+            Code::F10 => F10,
+            Code::F11 => F11,
+            Code::F12 => F12,
 
-            KeyCode::PrintScreen => Print,
-            KeyCode::ScrollLock => Scroll_Lock,
+            Code::PrintScreen => Print,
+            Code::ScrollLock => Scroll_Lock,
             // Pause/Break not audio.
-            KeyCode::Pause => Pause,
+            Code::Pause => Pause,
 
-            KeyCode::Insert => Insert,
-            KeyCode::Delete => Delete,
-            KeyCode::Home => Home,
-            KeyCode::End => End,
-            KeyCode::PageUp => Page_Up,
-            KeyCode::PageDown => Page_Down,
+            Code::Insert => Insert,
+            Code::Delete => Delete,
+            Code::Home => Home,
+            Code::End => End,
+            Code::PageUp => Page_Up,
+            Code::PageDown => Page_Down,
 
-            KeyCode::Numpad0 => KP_0,
-            KeyCode::Numpad1 => KP_1,
-            KeyCode::Numpad2 => KP_2,
-            KeyCode::Numpad3 => KP_3,
-            KeyCode::Numpad4 => KP_4,
-            KeyCode::Numpad5 => KP_5,
-            KeyCode::Numpad6 => KP_6,
-            KeyCode::Numpad7 => KP_7,
-            KeyCode::Numpad8 => KP_8,
-            KeyCode::Numpad9 => KP_9,
+            Code::Numpad0 => KP_0,
+            Code::Numpad1 => KP_1,
+            Code::Numpad2 => KP_2,
+            Code::Numpad3 => KP_3,
+            Code::Numpad4 => KP_4,
+            Code::Numpad5 => KP_5,
+            Code::Numpad6 => KP_6,
+            Code::Numpad7 => KP_7,
+            Code::Numpad8 => KP_8,
+            Code::Numpad9 => KP_9,
 
-            KeyCode::NumpadEquals => KP_Equal,
-            KeyCode::NumpadSubtract => KP_Subtract,
-            KeyCode::NumpadAdd => KP_Add,
-            KeyCode::NumpadDecimal => KP_Decimal,
-            KeyCode::NumpadMultiply => KP_Multiply,
-            KeyCode::NumpadDivide => KP_Divide,
-            KeyCode::NumLock => Num_Lock,
-            KeyCode::NumpadEnter => KP_Enter,
+            Code::NumpadEquals => KP_Equal,
+            Code::NumpadSubtract => KP_Subtract,
+            Code::NumpadAdd => KP_Add,
+            Code::NumpadDecimal => KP_Decimal,
+            Code::NumpadMultiply => KP_Multiply,
+            Code::NumpadDivide => KP_Divide,
+            Code::NumLock => Num_Lock,
+            Code::NumpadEnter => KP_Enter,
 
-            KeyCode::ArrowUp => Up,
-            KeyCode::ArrowDown => Down,
-            KeyCode::ArrowLeft => Left,
-            KeyCode::ArrowRight => Right,
-            KeyCode::Unknown(_) => unreachable!(
-                "Unreachable: converting unknown KeyCode {:?} to a keyval",
-                src
-            ),
+            Code::ArrowUp => Up,
+            Code::ArrowDown => Down,
+            Code::ArrowLeft => Left,
+            Code::ArrowRight => Right,
+            Code::Unknown(_) => {
+                unreachable!("Unreachable: converting unknown Code {:?} to a keyval", src)
+            }
         }
     }
 }
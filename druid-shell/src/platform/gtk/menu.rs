@@ -127,8 +127,15 @@ impl Menu {
 }
 
 fn register_accelerator(item: &GtkMenuItem, accel_group: &AccelGroup, menu_key: HotKey) {
-    let wc = match menu_key.key {
-        KeyCompare::Code(key_code) => key_code.into(),
+    let wc = match &menu_key.key {
+        KeyCompare::Code(code) => (*code).into(),
+        KeyCompare::Key(key) => match key.code() {
+            Some(code) => code.into(),
+            None => {
+                log::warn!("no accelerator for {:?}", key);
+                return;
+            }
+        },
         KeyCompare::Text(text) => text.chars().next().unwrap() as u32,
     };
 
@@ -136,7 +143,7 @@ fn register_accelerator(item: &GtkMenuItem, accel_group: &AccelGroup, menu_key:
         "activate",
         accel_group,
         gdk::unicode_to_keyval(wc),
-        modifiers_to_gdk_modifier_type(menu_key.mods),
+        modifiers_to_gdk_modifier_type(menu_key.raw_mods()),
         gtk::AccelFlags::VISIBLE,
     );
 }
@@ -0,0 +1,58 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! GTK monitors.
+
+use gdk::{Display, Rectangle};
+
+use crate::kurbo::Rect;
+use crate::scale::Scale;
+use crate::screen::Monitor;
+
+fn rect(rect: Rectangle) -> Rect {
+    Rect::from_origin_size(
+        (f64::from(rect.x), f64::from(rect.y)),
+        (f64::from(rect.width), f64::from(rect.height)),
+    )
+}
+
+/// The monitors of the default display.
+///
+/// Their geometry is in GDK's application pixels, which windows are moved in;
+/// those are scaled by the resolution of the screen, like window positions.
+pub(crate) fn get_monitors() -> Vec<Monitor> {
+    // this can be called before the application is created
+    if !gtk::is_initialized() && gtk::init().is_err() {
+        return Vec::new();
+    }
+    let display = match Display::get_default() {
+        Some(display) => display,
+        None => return Vec::new(),
+    };
+    let resolution = display.get_default_screen().get_resolution();
+    let dpi = if resolution > 0.0 { resolution } else { 96.0 };
+    (0..display.get_n_monitors())
+        .filter_map(|idx| display.get_monitor(idx))
+        .map(|monitor| {
+            let factor = f64::from(monitor.get_scale_factor());
+            Monitor::from_pixels(
+                monitor.is_primary(),
+                rect(monitor.get_geometry()),
+                rect(monitor.get_workarea()),
+                Scale::new(factor, factor),
+                dpi,
+            )
+        })
+        .collect()
+}
@@ -16,7 +16,6 @@
 
 use std::any::Any;
 use std::cell::{Cell, RefCell};
-use std::convert::TryFrom;
 use std::ffi::c_void;
 use std::ffi::OsString;
 use std::os::raw::{c_int, c_uint};
@@ -25,24 +24,32 @@ use std::slice;
 use std::sync::{Arc, Mutex, Weak};
 use std::time::Instant;
 
+use gdk::prelude::GdkContextExt;
 use gdk::{EventKey, EventMask, ModifierType, ScrollDirection, WindowExt};
 use gio::ApplicationExt;
 use gtk::prelude::*;
 use gtk::{AccelGroup, ApplicationWindow};
 
-use crate::kurbo::{Point, Size, Vec2};
-use crate::piet::{Piet, RenderContext};
+use crate::access::{AccessEvent, AccessNode};
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::piet::{Color, Piet, RenderContext};
 
 use super::application::with_application;
 use super::dialog;
 use super::menu::Menu;
 use super::util::assert_main_thread;
 
-use crate::common_util::IdleCallback;
+use crate::common_util::{
+    color_components, point_to_pixels, point_to_px, render_device_image, size_to_pixels,
+    size_to_px, DeferredShow, IdleCallback, FIRST_PAINT_TIMEOUT,
+};
 use crate::dialog::{FileDialogOptions, FileDialogType, FileInfo};
-use crate::keyboard;
-use crate::mouse::{Cursor, MouseButton, MouseEvent};
-use crate::window::{IdleToken, Text, TimerToken, WinHandler};
+use crate::drag::{DragEvent, DragPayload};
+use crate::keyboard::{self, KbKey, KeyState};
+use crate::mouse::{Cursor, MouseButton, MouseButtons, MouseEvent, ScrollPhase};
+use crate::scale::Scale;
+use crate::timer;
+use crate::window::{self, IdleToken, Text, TimerToken, WinHandler};
 use crate::Error;
 
 /// Taken from https://gtk-rs.org/docs-src/tutorial/closures
@@ -86,8 +93,14 @@ pub struct WindowBuilder {
     title: String,
     menu: Option<Menu>,
     size: Size,
+    min_size: Option<Size>,
+    position: Option<Point>,
+    state: Option<window::WindowState>,
     resizable: bool,
     show_titlebar: bool,
+    transparent: bool,
+    level: window::WindowLevel,
+    background: Option<Color>,
 }
 
 #[derive(Clone)]
@@ -100,13 +113,175 @@ pub struct IdleHandle {
 enum IdleKind {
     Callback(Box<dyn IdleCallback>),
     Token(IdleToken),
+    /// A timer that is due, with its deadline.
+    Timer(TimerToken, Instant),
 }
 
+/// The width of the edges that resize a window without decorations, in pixels.
+const RESIZE_BORDER: f64 = 8.0;
+
 pub(crate) struct WindowState {
     window: ApplicationWindow,
     pub(crate) handler: RefCell<Box<dyn WinHandler>>,
     idle_queue: Arc<Mutex<Vec<IdleKind>>>,
-    current_keyval: RefCell<Option<u32>>,
+    key_repeat: KeyRepeat,
+    /// The input method, which composes text such as Japanese, and the
+    /// accents of dead keys.
+    im_context: gtk::IMMulticontext,
+    ime: ImeState,
+    /// The window is transparent until its first paint.
+    deferred_show: Cell<DeferredShow>,
+    /// The color the window is filled with before it is painted, if it is set.
+    background: RefCell<Option<Color>>,
+    /// Holds the drawing area, and any child surfaces over it.
+    overlay: gtk::Overlay,
+    /// Something that is dragged over the window.
+    drag: RefCell<DragState>,
+}
+
+/// Tells the presses of a key that is held down from the first.
+#[derive(Default)]
+struct KeyRepeat {
+    /// The keyval of the key that is down, if one is.
+    keyval: Cell<Option<u32>>,
+}
+
+/// What the window knows about the composition of the input method.
+#[derive(Default)]
+struct ImeState {
+    /// Whether the handler has been told that a composition started.
+    composing: Cell<bool>,
+    /// The preedit ended during a key press; the composition ends when the
+    /// key has been handled, unless the input method commits its text.
+    ending: Cell<bool>,
+    /// Whether a key press is being given to the input method.
+    in_key_press: Cell<bool>,
+    /// The text that the input method committed for the key press that is
+    /// being handled, outside of a composition; the key types it.
+    key_text: RefCell<Option<String>>,
+}
+
+/// What the window knows about something that is dragged over it.
+#[derive(Default)]
+struct DragState {
+    /// What is dragged, once its data is received; the handler is told it
+    /// entered then.
+    payload: Option<DragPayload>,
+    /// The data has been asked for.
+    requested: bool,
+    /// It was dropped before its data was received.
+    dropping: bool,
+    /// It left the window, and the handler will be told on idle unless it
+    /// is dropped first.
+    leaving: bool,
+    /// Where the pointer last was.
+    pos: Point,
+}
+
+/// A `GLArea` over a window's drawing area.
+pub struct ChildSurface {
+    area: gtk::GLArea,
+    overlay: gtk::Overlay,
+    window: WindowHandle,
+}
+
+impl KeyRepeat {
+    /// Whether a press of `keyval` repeats the key that is down.
+    fn press(&self, keyval: u32) -> bool {
+        self.keyval.replace(Some(keyval)) == Some(keyval)
+    }
+
+    /// A key was released, so the next press doesn't repeat.
+    fn release(&self) {
+        self.keyval.set(None);
+    }
+}
+
+impl ImeState {
+    /// Send a key press to the handler, once `filter` has given it to the
+    /// input method, which may compose it.
+    ///
+    /// The input method tells us what it does with the key through the
+    /// other methods, which are called from within `filter`.
+    fn key_down(
+        &self,
+        handler: &RefCell<Box<dyn WinHandler>>,
+        mut key_event: keyboard::KeyEvent,
+        dead: bool,
+        filter: impl FnOnce() -> bool,
+    ) -> bool {
+        if dead {
+            key_event.key = KbKey::Dead;
+        }
+        self.in_key_press.set(true);
+        let filtered = filter();
+        self.in_key_press.set(false);
+        if self.ending.replace(false) {
+            self.end_composition(handler, "");
+        }
+        match self.key_text.borrow_mut().take() {
+            // the key is part of a composition
+            None if filtered => key_event.is_composing = true,
+            Some(text) => key_event.key = KbKey::Character(text),
+            None => (),
+        }
+        handler.borrow_mut().key_down(key_event)
+    }
+
+    /// Tell the handler that the input method started a composition,
+    /// unless it has been told already.
+    fn start_composition(&self, handler: &RefCell<Box<dyn WinHandler>>) {
+        if !self.composing.replace(true) {
+            handler.borrow_mut().composition_start();
+        }
+    }
+
+    /// The text of the composition changed, with the cursor `cursor`
+    /// characters into it.
+    fn preedit_changed(&self, handler: &RefCell<Box<dyn WinHandler>>, text: &str, cursor: i32) {
+        if text.is_empty() && !self.composing.get() {
+            return;
+        }
+        self.start_composition(handler);
+        let cursor = text
+            .char_indices()
+            .nth(cursor.max(0) as usize)
+            .map_or(text.len(), |(offset, _)| offset);
+        handler
+            .borrow_mut()
+            .composition_update(text, cursor..cursor);
+    }
+
+    /// The input method stopped showing the composition.
+    fn preedit_end(&self, handler: &RefCell<Box<dyn WinHandler>>) {
+        // the text is usually committed right after this
+        if self.in_key_press.get() {
+            self.ending.set(true);
+        } else {
+            self.end_composition(handler, "");
+        }
+    }
+
+    /// The input method typed `text`.
+    fn commit(&self, handler: &RefCell<Box<dyn WinHandler>>, text: &str) {
+        if self.composing.get() {
+            self.ending.set(false);
+            self.end_composition(handler, text);
+        } else if self.in_key_press.get() {
+            *self.key_text.borrow_mut() = Some(text.to_string());
+        } else {
+            // such as from an on-screen keyboard
+            self.start_composition(handler);
+            self.end_composition(handler, text);
+        }
+    }
+
+    /// End the input method's composition, if there is one, typing `text`.
+    fn end_composition(&self, handler: &RefCell<Box<dyn WinHandler>>, text: &str) {
+        if self.composing.replace(false) {
+            handler.borrow_mut().composition_end(text);
+        }
+    }
 }
 
 impl WindowBuilder {
@@ -116,8 +291,14 @@ impl WindowBuilder {
             title: String::new(),
             menu: None,
             size: Size::new(500.0, 400.0),
+            min_size: None,
+            position: None,
+            state: None,
             resizable: true,
             show_titlebar: true,
+            transparent: false,
+            level: window::WindowLevel::AppWindow,
+            background: None,
         }
     }
 
@@ -129,6 +310,18 @@ impl WindowBuilder {
         self.size = size;
     }
 
+    pub fn set_min_size(&mut self, size: Size) {
+        self.min_size = Some(size);
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.position = Some(position);
+    }
+
+    pub fn set_window_state(&mut self, state: window::WindowState) {
+        self.state = Some(state);
+    }
+
     pub fn resizable(&mut self, resizable: bool) {
         self.resizable = resizable;
     }
@@ -137,14 +330,30 @@ impl WindowBuilder {
         self.show_titlebar = show_titlebar;
     }
 
+    pub fn transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
+    pub fn set_level(&mut self, level: window::WindowLevel) {
+        self.level = level;
+    }
+
     pub fn set_title(&mut self, title: impl Into<String>) {
         self.title = title.into();
     }
 
+    pub fn set_background_color(&mut self, color: Color) {
+        self.background = Some(color);
+    }
+
     pub fn set_menu(&mut self, menu: Menu) {
         self.menu = Some(menu);
     }
 
+    pub fn set_render_backend(&mut self, _backend: window::RenderBackend) {
+        // drawing is always done in software
+    }
+
     pub fn build(self) -> Result<WindowHandle, Error> {
         assert_main_thread();
 
@@ -158,16 +367,70 @@ impl WindowBuilder {
         window.set_resizable(self.resizable);
         window.set_decorated(self.show_titlebar);
 
-        let dpi_scale = window
+        if self.level.is_popup() {
+            window.set_decorated(false);
+            window.set_type_hint(match self.level {
+                window::WindowLevel::Tooltip => gdk::WindowTypeHint::Tooltip,
+                _ => gdk::WindowTypeHint::DropdownMenu,
+            });
+            window.set_skip_taskbar_hint(true);
+            window.set_skip_pager_hint(true);
+            window.set_accept_focus(false);
+            window.set_keep_above(true);
+        }
+
+        if self.transparent {
+            match window
+                .get_screen()
+                .and_then(|screen| screen.get_rgba_visual())
+            {
+                Some(visual) => window.set_visual(Some(&visual)),
+                None => log::warn!("the display doesn't support transparent windows"),
+            }
+            // don't draw the theme's background behind ours
+            window.set_app_paintable(true);
+        }
+
+        let dpi = window
             .get_display()
             .map(|c| c.get_default_screen().get_resolution() as f64)
-            .unwrap_or(96.0)
-            / 96.0;
+            .unwrap_or(96.0);
+
+        let size = size_to_pixels(self.size, dpi);
+        window.set_default_size(size.width as i32, size.height as i32);
+
+        if let Some(min_size) = self.min_size {
+            let min_size = size_to_pixels(min_size, dpi);
+            let geometry = gdk::Geometry {
+                min_width: min_size.width.ceil() as i32,
+                min_height: min_size.height.ceil() as i32,
+                max_width: 0,
+                max_height: 0,
+                base_width: 0,
+                base_height: 0,
+                width_inc: 0,
+                height_inc: 0,
+                min_aspect: 0.0,
+                max_aspect: 0.0,
+                win_gravity: gdk::Gravity::NorthWest,
+            };
+            window.set_geometry_hints(
+                None::<&gtk::Widget>,
+                Some(&geometry),
+                gdk::WindowHints::MIN_SIZE,
+            );
+        }
 
-        window.set_default_size(
-            (self.size.width * dpi_scale) as i32,
-            (self.size.height * dpi_scale) as i32,
-        );
+        if let Some(position) = self.position {
+            let position = point_to_pixels(position, dpi);
+            window.move_(position.x as i32, position.y as i32);
+        }
+
+        match self.state {
+            Some(window::WindowState::Maximized) => window.maximize(),
+            Some(window::WindowState::Minimized) => window.iconify(),
+            Some(window::WindowState::Restored) | None => (),
+        }
 
         let accel_group = AccelGroup::new();
         window.add_accel_group(&accel_group);
@@ -179,7 +442,13 @@ impl WindowBuilder {
             window,
             handler: RefCell::new(handler),
             idle_queue: Arc::new(Mutex::new(vec![])),
-            current_keyval: RefCell::new(None),
+            key_repeat: Default::default(),
+            im_context: gtk::IMMulticontext::new(),
+            ime: Default::default(),
+            deferred_show: Default::default(),
+            background: RefCell::new(self.background),
+            overlay: gtk::Overlay::new(),
+            drag: Default::default(),
         });
 
         with_application(|app| {
@@ -212,7 +481,8 @@ impl WindowBuilder {
                 | EventMask::ENTER_NOTIFY_MASK
                 | EventMask::KEY_RELEASE_MASK
                 | EventMask::SCROLL_MASK
-                | EventMask::SMOOTH_SCROLL_MASK,
+                | EventMask::SMOOTH_SCROLL_MASK
+                | EventMask::FOCUS_CHANGE_MASK,
         );
 
         drawing_area.set_can_focus(true);
@@ -229,13 +499,16 @@ impl WindowBuilder {
         drawing_area.connect_draw(clone!(handle => move |widget, context| {
             if let Some(state) = handle.state.upgrade() {
 
+                // only the invalidated part of the window is painted, so the
+                // clip isn't the size of the window
                 let extents = context.clip_extents();
+                let invalid_rect = Rect::new(extents.0, extents.1, extents.2, extents.3);
                 let dpi_scale = state.window.get_window()
                     .map(|w| w.get_display().get_default_screen().get_resolution())
                     .unwrap_or(96.0) / 96.0;
                 let size = (
-                    ((extents.2 - extents.0) * dpi_scale) as u32,
-                    ((extents.3 - extents.1) * dpi_scale) as u32,
+                    (f64::from(widget.get_allocated_width()) * dpi_scale) as u32,
+                    (f64::from(widget.get_allocated_height()) * dpi_scale) as u32,
                 );
 
                 if last_size.get() != size {
@@ -243,13 +516,19 @@ impl WindowBuilder {
                     state.handler.borrow_mut().size(size.0, size.1);
                 }
 
+                if let Some(color) = state.background.borrow().as_ref() {
+                    let (r, g, b, a) = color_components(color);
+                    context.set_source_rgba(r, g, b, a);
+                    context.paint();
+                }
+
                 // For some reason piet needs a mutable context, so give it one I guess.
                 let mut context = context.clone();
                 let mut piet_context = Piet::new(&mut context);
 
                 if let Ok(mut handler_borrow) = state.handler.try_borrow_mut() {
                     let anim = handler_borrow
-                        .paint(&mut piet_context);
+                        .paint(&mut piet_context, invalid_rect);
                     if let Err(e) = piet_context.finish() {
                         eprintln!("piet error on render: {:?}", e);
                     }
@@ -257,6 +536,11 @@ impl WindowBuilder {
                     if anim {
                         widget.queue_draw();
                     }
+                    let mut deferred = state.deferred_show.get();
+                    if deferred.paint_completed() {
+                        state.window.set_opacity(1.0);
+                    }
+                    state.deferred_show.set(deferred);
                 }
 
             }
@@ -264,15 +548,39 @@ impl WindowBuilder {
             Inhibit(false)
         }));
 
-        drawing_area.connect_button_press_event(clone!(handle => move |_widget, button| {
+        drawing_area.connect_button_press_event(clone!(handle => move |widget, button| {
             if let Some(state) = handle.state.upgrade() {
 
+                // without decorations, the edges of the window resize it
+                if !state.window.get_decorated() && state.window.get_resizable() {
+                    let size = Size::new(
+                        widget.get_allocated_width() as f64,
+                        widget.get_allocated_height() as f64,
+                    );
+                    let edge = resize_edge(size, Point::from(button.get_position()), RESIZE_BORDER);
+                    if let Some(edge) = edge {
+                        let (x, y) = button.get_root();
+                        state.window.begin_resize_drag(
+                            edge,
+                            button.get_button() as i32,
+                            x as i32,
+                            y as i32,
+                            button.get_time(),
+                        );
+                        return Inhibit(true);
+                    }
+                }
+
+                // the state is from before the press
+                let pressed = get_mouse_button(button.get_button());
                 state.handler.borrow_mut().mouse_down(
                     &MouseEvent {
                         pos: Point::from(button.get_position()),
                         count: get_mouse_click_count(button.get_event_type()),
                         mods: get_modifiers(button.get_state()),
-                        button: get_mouse_button(button.get_button()),
+                        button: pressed,
+                        buttons: get_mouse_buttons(button.get_state()).with(pressed),
+                        focus: false,
                     },
                 );
             }
@@ -283,12 +591,17 @@ impl WindowBuilder {
         drawing_area.connect_button_release_event(clone!(handle => move |_widget, button| {
             if let Some(state) = handle.state.upgrade() {
 
+                let released = get_mouse_button(button.get_button());
+                let mut buttons = get_mouse_buttons(button.get_state());
+                buttons.remove(released);
                 state.handler.borrow_mut().mouse_up(
                     &MouseEvent {
                         pos: Point::from(button.get_position()),
                         mods: get_modifiers(button.get_state()),
                         count: 0,
-                        button: get_mouse_button(button.get_button()),
+                        button: released,
+                        buttons,
+                        focus: false,
                     },
                 );
             }
@@ -305,6 +618,8 @@ impl WindowBuilder {
                     mods: get_modifiers(motion.get_state()),
                     count: 0,
                     button: get_mouse_button_from_modifiers(motion.get_state()),
+                    buttons: get_mouse_buttons(motion.get_state()),
+                    focus: false,
                 };
 
                 state
@@ -325,6 +640,8 @@ impl WindowBuilder {
                     mods: get_modifiers(crossing.get_state()),
                     count: 0,
                     button: get_mouse_button_from_modifiers(crossing.get_state()),
+                    buttons: get_mouse_buttons(crossing.get_state()),
+                    focus: false,
                 };
 
                 state
@@ -336,6 +653,122 @@ impl WindowBuilder {
             Inhibit(true)
         }));
 
+        // the seat grab of `set_mouse_capture` is broken when another
+        // application grabs the seat, or when the window is hidden
+        drawing_area.connect_grab_broken_event(clone!(handle => move |_widget, _grab| {
+            if let Some(state) = handle.state.upgrade() {
+                state.handler.borrow_mut().mouse_capture_lost();
+            }
+            Inhibit(true)
+        }));
+
+        // the window takes files and text
+        drawing_area.drag_dest_set(gtk::DestDefaults::empty(), &[], gdk::DragAction::COPY);
+        drawing_area.drag_dest_add_uri_targets();
+        drawing_area.drag_dest_add_text_targets();
+
+        drawing_area.connect_drag_motion(clone!(handle => move |widget, context, x, y, time| {
+            if let Some(state) = handle.state.upgrade() {
+                let pos = Point::new(x as f64, y as f64);
+                let mut drag = state.drag.borrow_mut();
+                drag.leaving = false;
+                drag.pos = pos;
+                let accepted = match drag.payload.clone() {
+                    Some(payload) => {
+                        let event = DragEvent { pos, mods: drag_modifiers(), payload };
+                        state.handler.borrow_mut().drag_over(&event)
+                    }
+                    // the handler is told once the data is received
+                    None => {
+                        if !drag.requested {
+                            drag.requested = true;
+                            if let Some(target) = widget.drag_dest_find_target(context, None) {
+                                widget.drag_get_data(context, &target, time);
+                            }
+                        }
+                        false
+                    }
+                };
+                context.drag_status(drag_action(accepted), time);
+            }
+
+            Inhibit(true)
+        }));
+
+        drawing_area.connect_drag_data_received(
+            clone!(handle => move |_widget, context, _x, _y, data, _info, time| {
+                if let Some(state) = handle.state.upgrade() {
+                    let mut drag = state.drag.borrow_mut();
+                    let payload = drag_payload(data);
+                    drag.payload = Some(payload.clone());
+                    let event = DragEvent { pos: drag.pos, mods: drag_modifiers(), payload };
+                    let mut handler = state.handler.borrow_mut();
+                    let accepted = handler.drag_enter(&event);
+                    if drag.dropping {
+                        let accepted = handler.drag_drop(&event);
+                        context.drag_finish(accepted, false, time);
+                        *drag = Default::default();
+                    } else {
+                        context.drag_status(drag_action(accepted), time);
+                    }
+                }
+            }),
+        );
+
+        drawing_area.connect_drag_leave(clone!(handle => move |_widget, _context, _time| {
+            if let Some(state) = handle.state.upgrade() {
+                state.drag.borrow_mut().leaving = true;
+                // GTK leaves before it drops, so the handler is only told
+                // if no drop follows
+                let handle = handle.clone();
+                glib::idle_add_local(move || {
+                    if let Some(state) = handle.state.upgrade() {
+                        let mut drag = state.drag.borrow_mut();
+                        if drag.leaving {
+                            if drag.payload.is_some() {
+                                state.handler.borrow_mut().drag_leave();
+                            }
+                            *drag = Default::default();
+                        }
+                    }
+                    glib::Continue(false)
+                });
+            }
+        }));
+
+        drawing_area.connect_drag_drop(clone!(handle => move |widget, context, x, y, time| {
+            if let Some(state) = handle.state.upgrade() {
+                let pos = Point::new(x as f64, y as f64);
+                let mut drag = state.drag.borrow_mut();
+                drag.leaving = false;
+                drag.pos = pos;
+                match drag.payload.clone() {
+                    Some(payload) => {
+                        let event = DragEvent { pos, mods: drag_modifiers(), payload };
+                        let accepted = state.handler.borrow_mut().drag_drop(&event);
+                        context.drag_finish(accepted, false, time);
+                        *drag = Default::default();
+                    }
+                    None => match widget.drag_dest_find_target(context, None) {
+                        // the drop is done once the data is received
+                        Some(target) => {
+                            drag.dropping = true;
+                            if !drag.requested {
+                                drag.requested = true;
+                                widget.drag_get_data(context, &target, time);
+                            }
+                        }
+                        None => {
+                            context.drag_finish(false, false, time);
+                            *drag = Default::default();
+                        }
+                    },
+                }
+            }
+
+            Inhibit(true)
+        }));
+
         drawing_area.connect_scroll_event(clone!(handle => move |_widget, scroll| {
             if let Some(state) = handle.state.upgrade() {
 
@@ -362,7 +795,15 @@ impl WindowBuilder {
                         let (mut delta_x, mut delta_y) = scroll.get_delta();
                         delta_x *= 120.;
                         delta_y *= 120.;
-                        handler.wheel(Vec2::from((delta_x, delta_y)), modifiers)
+                        // a touchpad ends its scrolling with a stop event; GTK
+                        // doesn't report a start, or momentum
+                        let phase = if scroll.get_is_stop() {
+                            ScrollPhase::Ended
+                        } else {
+                            ScrollPhase::Changed
+                        };
+                        let delta = Vec2::from((delta_x, delta_y));
+                        handler.wheel_with_phase(delta, modifiers, phase, None)
                     }
                     e => {
                         eprintln!(
@@ -378,14 +819,13 @@ impl WindowBuilder {
 
         drawing_area.connect_key_press_event(clone!(handle => move |_widget, key| {
             if let Some(state) = handle.state.upgrade() {
-
-                let mut current_keyval = state.current_keyval.borrow_mut();
-                let repeat = *current_keyval == Some(key.get_keyval());
-
-                *current_keyval = Some(key.get_keyval());
-
-                let key_event = make_key_event(key, repeat);
-                state.handler.borrow_mut().key_down(key_event);
+                let keyval = key.get_keyval();
+                let repeat = state.key_repeat.press(keyval);
+                let key_event = make_key_event(key, repeat, KeyState::Down);
+                // the input method sees the key first
+                state.ime.key_down(&state.handler, key_event, is_dead_key(keyval), || {
+                    state.im_context.filter_keypress(key)
+                });
             }
 
             Inhibit(true)
@@ -394,22 +834,101 @@ impl WindowBuilder {
         drawing_area.connect_key_release_event(clone!(handle => move |_widget, key| {
             if let Some(state) = handle.state.upgrade() {
 
-                *(state.current_keyval.borrow_mut()) = None;
+                state.key_repeat.release();
+                // some input methods follow the releases as well
+                state.im_context.filter_keypress(key);
 
-                let key_event = make_key_event(key, false);
+                let key_event = make_key_event(key, false, KeyState::Up);
                 state.handler.borrow_mut().key_up(key_event);
             }
 
             Inhibit(true)
         }));
 
+        drawing_area.connect_focus_in_event(clone!(handle => move |_widget, _focus| {
+            if let Some(state) = handle.state.upgrade() {
+                state.im_context.focus_in();
+            }
+            Inhibit(false)
+        }));
+
+        drawing_area.connect_focus_out_event(clone!(handle => move |_widget, _focus| {
+            if let Some(state) = handle.state.upgrade() {
+                state.im_context.focus_out();
+            }
+            Inhibit(false)
+        }));
+
+        drawing_area.connect_realize(clone!(handle => move |widget| {
+            if let Some(state) = handle.state.upgrade() {
+                state.im_context.set_client_window(widget.get_window().as_ref());
+            }
+        }));
+
+        drawing_area.connect_unrealize(clone!(handle => move |_widget| {
+            if let Some(state) = handle.state.upgrade() {
+                state.im_context.set_client_window(None::<&gdk::Window>);
+            }
+        }));
+
+        win_state
+            .im_context
+            .connect_preedit_start(clone!(handle => move |_| {
+                if let Some(state) = handle.state.upgrade() {
+                    state.ime.start_composition(&state.handler);
+                }
+            }));
+
+        win_state
+            .im_context
+            .connect_preedit_changed(clone!(handle => move |im_context| {
+                if let Some(state) = handle.state.upgrade() {
+                    let (text, _attributes, cursor) = im_context.get_preedit_string();
+                    state.ime.preedit_changed(&state.handler, &text, cursor);
+                }
+            }));
+
+        win_state
+            .im_context
+            .connect_preedit_end(clone!(handle => move |_| {
+                if let Some(state) = handle.state.upgrade() {
+                    state.ime.preedit_end(&state.handler);
+                }
+            }));
+
+        win_state
+            .im_context
+            .connect_commit(clone!(handle => move |_, text| {
+                if let Some(state) = handle.state.upgrade() {
+                    state.ime.commit(&state.handler, text);
+                }
+            }));
+
+        win_state
+            .window
+            .connect_property_scale_factor_notify(clone!(handle => move |_| {
+                if let Some(state) = handle.state.upgrade() {
+                    let scale = handle.get_scale();
+                    state.handler.borrow_mut().scale(scale);
+                }
+            }));
+
+        if let Some(settings) = gtk::Settings::get_default() {
+            settings.connect_property_gtk_enable_animations_notify(clone!(handle => move |_| {
+                if let Some(state) = handle.state.upgrade() {
+                    state.handler.borrow_mut().system_settings_changed();
+                }
+            }));
+        }
+
         drawing_area.connect_destroy(clone!(handle => move |_widget| {
             if let Some(state) = handle.state.upgrade() {
                 state.handler.borrow_mut().destroy();
             }
         }));
 
-        vbox.pack_end(&drawing_area, true, true, 0);
+        win_state.overlay.add(&drawing_area);
+        vbox.pack_end(&win_state.overlay, true, true, 0);
 
         win_state
             .handler
@@ -421,8 +940,31 @@ impl WindowBuilder {
 }
 
 impl WindowHandle {
+    /// Show the window, once it has been painted.
+    ///
+    /// The window is mapped transparent, as unmapped windows aren't drawn,
+    /// and becomes opaque when its first paint completes, or after
+    /// `FIRST_PAINT_TIMEOUT` if that takes too long.
     pub fn show(&self) {
         if let Some(state) = self.state.upgrade() {
+            let mut deferred = state.deferred_show.get();
+            if deferred.request_show() {
+                state.window.set_opacity(0.0);
+                let handle = self.clone();
+                let timeout = FIRST_PAINT_TIMEOUT.as_millis() as u32;
+                gdk::threads_add_timeout(timeout, move || {
+                    if let Some(state) = handle.state.upgrade() {
+                        let mut deferred = state.deferred_show.get();
+                        if deferred.timed_out() {
+                            log::warn!("first paint timed out, showing the window anyway");
+                            state.window.set_opacity(1.0);
+                        }
+                        state.deferred_show.set(deferred);
+                    }
+                    false
+                });
+            }
+            state.deferred_show.set(deferred);
             state.window.show_all();
         }
     }
@@ -439,6 +981,25 @@ impl WindowHandle {
         }
     }
 
+    pub fn begin_move_drag(&self) {
+        if let Some(state) = self.state.upgrade() {
+            // this is called while the press is being handled
+            let event = match gtk::get_current_event() {
+                Some(event) => event,
+                None => return,
+            };
+            match (event.get_button(), event.get_root_coords()) {
+                (Some(button), Some((x, y))) => state.window.begin_move_drag(
+                    button as i32,
+                    x as i32,
+                    y as i32,
+                    event.get_time(),
+                ),
+                _ => log::warn!("begin_move_drag called outside of a mouse press"),
+            }
+        }
+    }
+
     /// Close the window.
     pub fn close(&self) {
         if let Some(state) = self.state.upgrade() {
@@ -454,6 +1015,71 @@ impl WindowHandle {
         log::warn!("bring_to_front_and_focus not yet implemented for gtk");
     }
 
+    pub fn set_size(&self, size: Size) {
+        if let Some(state) = self.state.upgrade() {
+            let size = size_to_pixels(size, self.get_dpi() as f64);
+            state.window.resize(size.width as i32, size.height as i32);
+        }
+    }
+
+    pub fn get_size(&self) -> Size {
+        if let Some(state) = self.state.upgrade() {
+            let (width, height) = state.window.get_size();
+            size_to_px(
+                Size::new(width as f64, height as f64),
+                self.get_dpi() as f64,
+            )
+        } else {
+            Size::ZERO
+        }
+    }
+
+    pub fn set_position(&self, position: Point) {
+        if let Some(state) = self.state.upgrade() {
+            let position = point_to_pixels(position, self.get_dpi() as f64);
+            state.window.move_(position.x as i32, position.y as i32);
+        }
+    }
+
+    pub fn get_position(&self) -> Point {
+        if let Some(state) = self.state.upgrade() {
+            let (x, y) = state.window.get_position();
+            point_to_px(Point::new(x as f64, y as f64), self.get_dpi() as f64)
+        } else {
+            Point::ZERO
+        }
+    }
+
+    pub fn to_screen(&self, point: Point) -> Point {
+        let state = match self.state.upgrade() {
+            Some(state) => state,
+            None => return point,
+        };
+        // the overlay shares the gdk window of the toplevel, under the menu
+        match state.overlay.get_window() {
+            Some(gdk_window) => {
+                let (_, x, y) = gdk_window.get_origin();
+                let allocation = state.overlay.get_allocation();
+                let origin = Point::new(f64::from(x + allocation.x), f64::from(y + allocation.y));
+                point_to_px(origin, self.get_dpi() as f64) + point.to_vec2()
+            }
+            None => point,
+        }
+    }
+
+    pub fn set_window_state(&self, window_state: window::WindowState) {
+        if let Some(state) = self.state.upgrade() {
+            match window_state {
+                window::WindowState::Maximized => state.window.maximize(),
+                window::WindowState::Minimized => state.window.iconify(),
+                window::WindowState::Restored => {
+                    state.window.unmaximize();
+                    state.window.deiconify();
+                }
+            }
+        }
+    }
+
     // Request invalidation of the entire window contents.
     pub fn invalidate(&self) {
         if let Some(state) = self.state.upgrade() {
@@ -461,35 +1087,30 @@ impl WindowHandle {
         }
     }
 
+    pub fn invalidate_rect(&self, rect: Rect) {
+        if let Some(state) = self.state.upgrade() {
+            // the drawing area fills the overlay, so they share coordinates
+            let rect = rect.expand();
+            state.overlay.queue_draw_area(
+                rect.x0 as i32,
+                rect.y0 as i32,
+                rect.width() as i32,
+                rect.height() as i32,
+            );
+        }
+    }
+
     pub fn text(&self) -> Text {
         Text::new()
     }
 
     pub fn request_timer(&self, deadline: Instant) -> TimerToken {
-        let interval = deadline
-            .checked_duration_since(Instant::now())
-            .unwrap_or_default()
-            .as_millis();
-        let interval = match u32::try_from(interval) {
-            Ok(iv) => iv,
-            Err(_) => {
-                log::warn!("timer duration exceeds gtk max of 2^32 millis");
-                u32::max_value()
-            }
-        };
-
         let token = TimerToken::next();
-        let handle = self.clone();
-
-        gdk::threads_add_timeout(interval, move || {
-            if let Some(state) = handle.state.upgrade() {
-                if let Ok(mut handler_borrow) = state.handler.try_borrow_mut() {
-                    handler_borrow.timer(token);
-                    return false;
-                }
-            }
-            true
-        });
+        if let Some(idle) = self.get_idle_handle() {
+            timer::schedule(token, deadline, move |token, deadline| {
+                idle.add_timer(token, deadline)
+            });
+        }
         token
     }
 
@@ -500,16 +1121,60 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_ime_cursor_area(&self, rect: Rect) {
+        if let Some(state) = self.state.upgrade() {
+            // the drawing area is the input method's client window
+            let rect = rect.expand();
+            state.im_context.set_cursor_location(&gdk::Rectangle {
+                x: rect.x0 as i32,
+                y: rect.y0 as i32,
+                width: rect.width() as i32,
+                height: rect.height() as i32,
+            });
+        }
+    }
+
+    pub fn set_mouse_capture(&self, capture: bool) {
+        let state = match self.state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        // the events go to the drawing area, so that is what grabs them
+        let gdk_window = match state.overlay.get_child().and_then(|w| w.get_window()) {
+            Some(gdk_window) => gdk_window,
+            None => return,
+        };
+        let seat = match gdk_window.get_display().get_default_seat() {
+            Some(seat) => seat,
+            None => return,
+        };
+        if capture {
+            let status = seat.grab(
+                &gdk_window,
+                gdk::SeatCapabilities::ALL_POINTING,
+                true,
+                None,
+                None,
+                None,
+            );
+            if status != gdk::GrabStatus::Success {
+                log::warn!("could not capture the mouse: {:?}", status);
+            }
+        } else {
+            seat.ungrab();
+        }
+    }
+
     pub fn open_file_sync(&mut self, options: FileDialogOptions) -> Option<FileInfo> {
         self.file_dialog(FileDialogType::Open, options)
             .ok()
-            .map(|s| FileInfo { path: s.into() })
+            .and_then(|paths| FileInfo::from_paths(paths.into_iter().map(Into::into).collect()))
     }
 
     pub fn save_as_sync(&mut self, options: FileDialogOptions) -> Option<FileInfo> {
         self.file_dialog(FileDialogType::Save, options)
             .ok()
-            .map(|s| FileInfo { path: s.into() })
+            .and_then(|paths| FileInfo::from_paths(paths.into_iter().map(Into::into).collect()))
     }
 
     /// Get a handle that can be used to schedule an idle task.
@@ -520,6 +1185,81 @@ impl WindowHandle {
         })
     }
 
+    pub fn render_backend(&self) -> window::RenderBackend {
+        window::RenderBackend::Software
+    }
+
+    pub fn create_child_surface(&self) -> Result<ChildSurface, Error> {
+        let state = self
+            .state
+            .upgrade()
+            .ok_or(Error::Other("the window has been closed"))?;
+        let area = gtk::GLArea::new();
+        area.set_halign(gtk::Align::Start);
+        area.set_valign(gtk::Align::Start);
+        // it is shown with `set_visible`, not with the rest of the window
+        area.set_no_show_all(true);
+        state.overlay.add_overlay(&area);
+        Ok(ChildSurface {
+            area,
+            overlay: state.overlay.clone(),
+            window: self.clone(),
+        })
+    }
+
+    /// The tree isn't exposed with AT-SPI yet.
+    pub fn update_access_tree(&self, _tree: AccessNode, _events: &[AccessEvent]) {}
+
+    pub fn capture_image(&self) -> Result<window::CapturedImage, Error> {
+        let state = self
+            .state
+            .upgrade()
+            .ok_or(Error::Other("the window has been closed"))?;
+        let gdk_window = state
+            .overlay
+            .get_window()
+            .ok_or(Error::Other("the window has not been shown"))?;
+        let allocation = state.overlay.get_allocation();
+        let factor = state.window.get_scale_factor();
+        let width = allocation.width * factor;
+        let height = allocation.height * factor;
+
+        let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)
+            .map_err(|_| Error::Other("failed to create an image surface"))?;
+        {
+            // the overlay shares the gdk window of the toplevel
+            let cr = cairo::Context::new(&surface);
+            cr.scale(f64::from(factor), f64::from(factor));
+            cr.set_source_window(
+                &gdk_window,
+                -f64::from(allocation.x),
+                -f64::from(allocation.y),
+            );
+            cr.paint();
+        }
+        surface.flush();
+
+        let stride = surface.get_stride() as usize;
+        let (width, height) = (width as usize, height as usize);
+        let data = surface
+            .get_data()
+            .map_err(|_| Error::Other("failed to read the image surface"))?;
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for row in data.chunks(stride).take(height) {
+            for px in row[..width * 4].chunks(4) {
+                // cairo pixels are premultiplied native-endian ARGB words
+                let argb = u32::from_ne_bytes([px[0], px[1], px[2], px[3]]);
+                let [a, r, g, b] = argb.to_be_bytes();
+                pixels.extend_from_slice(&[r, g, b, a]);
+            }
+        }
+        Ok(window::CapturedImage {
+            pixels,
+            width,
+            height,
+        })
+    }
+
     /// Get the dpi of the window.
     ///
     /// TODO: we want to migrate this from dpi (with 96 as nominal) to a scale
@@ -532,6 +1272,17 @@ impl WindowHandle {
             .unwrap_or(96.0)
     }
 
+    pub fn get_scale(&self) -> Scale {
+        // cairo takes care of the rest of the dpi scaling
+        self.state
+            .upgrade()
+            .map(|s| {
+                let factor = f64::from(s.window.get_scale_factor());
+                Scale::new(factor, factor)
+            })
+            .unwrap_or_default()
+    }
+
     // TODO: the following methods are cut'n'paste code. A good way to DRY
     // would be to have a platform-independent trait with these as methods with
     // default implementations.
@@ -599,13 +1350,20 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_background_color(&self, color: Color) {
+        if let Some(state) = self.state.upgrade() {
+            state.background.replace(Some(color));
+            state.window.queue_draw();
+        }
+    }
+
     fn file_dialog(
         &self,
         ty: FileDialogType,
         options: FileDialogOptions,
-    ) -> Result<OsString, Error> {
+    ) -> Result<Vec<OsString>, Error> {
         if let Some(state) = self.state.upgrade() {
-            dialog::get_file_dialog_path(state.window.upcast_ref(), ty, options)
+            dialog::get_file_dialog_paths(state.window.upcast_ref(), ty, options)
         } else {
             Err(Error::Other(
                 "Cannot upgrade state from weak pointer to arc",
@@ -619,6 +1377,37 @@ unsafe impl Send for IdleHandle {}
 unsafe impl Send for WindowState {}
 unsafe impl Sync for WindowState {}
 
+impl ChildSurface {
+    pub fn set_frame(&self, frame: Rect) {
+        let dpi = self.window.get_dpi() as f64;
+        let origin = point_to_pixels(frame.origin(), dpi);
+        let size = size_to_pixels(frame.size(), dpi);
+        self.area.set_margin_start(origin.x.round() as i32);
+        self.area.set_margin_top(origin.y.round() as i32);
+        self.area
+            .set_size_request(size.width.round() as i32, size.height.round() as i32);
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        self.area.set_visible(visible);
+    }
+
+    pub fn set_passthrough(&self, passthrough: bool) {
+        self.overlay
+            .set_overlay_pass_through(&self.area, passthrough);
+    }
+
+    pub fn gl_area(&self) -> &gtk::GLArea {
+        &self.area
+    }
+}
+
+impl Drop for ChildSurface {
+    fn drop(&mut self) {
+        self.overlay.remove(&self.area);
+    }
+}
+
 impl IdleHandle {
     /// Add an idle handler, which is called (once) when the message loop
     /// is empty. The idle handler will be run from the main UI thread, and
@@ -652,6 +1441,19 @@ impl IdleHandle {
             }
         }
     }
+
+    /// Deliver a timer that is due, from the timer thread.
+    fn add_timer(&self, token: TimerToken, deadline: Instant) {
+        let mut queue = self.idle_queue.lock().unwrap();
+        if let Some(state) = self.state.upgrade() {
+            if queue.is_empty() {
+                queue.push(IdleKind::Timer(token, deadline));
+                threads_add_idle(move || run_idle(&state));
+            } else {
+                queue.push(IdleKind::Timer(token, deadline));
+            }
+        }
+    }
 }
 
 // FIXME: delete when https://github.com/gtk-rs/gdk/issues/304 is resolved
@@ -672,6 +1474,18 @@ pub fn threads_add_idle<P: Fn() -> bool + Send + Sync + 'static>(function: P) ->
     unsafe { gdk_sys::gdk_threads_add_idle(function, Box::into_raw(super_callback0) as *mut _) }
 }
 
+/// Paint an image in memory. GTK always draws in software, so this is the
+/// same for every backend.
+pub(crate) fn render_image(
+    _backend: window::RenderBackend,
+    width: usize,
+    height: usize,
+    scale: f64,
+    paint: impl FnOnce(&mut Piet),
+) -> Result<window::CapturedImage, Error> {
+    render_device_image(width, height, scale, paint)
+}
+
 fn run_idle(state: &Arc<WindowState>) -> bool {
     assert_main_thread();
     let mut handler = state.handler.borrow_mut();
@@ -682,6 +1496,7 @@ fn run_idle(state: &Arc<WindowState>) -> bool {
         match item {
             IdleKind::Callback(it) => it.call(handler.as_any()),
             IdleKind::Token(it) => handler.idle(it),
+            IdleKind::Timer(token, deadline) => timer::deliver(&mut **handler, token, deadline),
         }
     }
     false
@@ -728,6 +1543,22 @@ fn get_mouse_button_from_modifiers(modifiers: gdk::ModifierType) -> MouseButton
     }
 }
 
+fn get_mouse_buttons(modifiers: gdk::ModifierType) -> MouseButtons {
+    let mut buttons = MouseButtons::new();
+    for &(mask, button) in &[
+        (ModifierType::BUTTON1_MASK, MouseButton::Left),
+        (ModifierType::BUTTON2_MASK, MouseButton::Middle),
+        (ModifierType::BUTTON3_MASK, MouseButton::Right),
+        (ModifierType::BUTTON4_MASK, MouseButton::X1),
+        (ModifierType::BUTTON5_MASK, MouseButton::X2),
+    ] {
+        if modifiers.contains(mask) {
+            buttons.insert(button);
+        }
+    }
+    buttons
+}
+
 fn get_mouse_click_count(event_type: gdk::EventType) -> u32 {
     match event_type {
         gdk::EventType::ButtonPress => 1,
@@ -737,6 +1568,35 @@ fn get_mouse_click_count(event_type: gdk::EventType) -> u32 {
     }
 }
 
+/// The modifiers that are held down, as the drag signals don't say.
+fn drag_modifiers() -> keyboard::KeyModifiers {
+    let state = gdk::Keymap::get_default().map_or(0, |keymap| keymap.get_modifier_state());
+    get_modifiers(ModifierType::from_bits_truncate(state))
+}
+
+fn drag_action(accepted: bool) -> gdk::DragAction {
+    if accepted {
+        gdk::DragAction::COPY
+    } else {
+        gdk::DragAction::empty()
+    }
+}
+
+/// Read the files and the text that are dragged.
+fn drag_payload(data: &gtk::SelectionData) -> DragPayload {
+    let files = data
+        .get_uris()
+        .iter()
+        .filter_map(|uri| glib::filename_from_uri(uri).ok())
+        .map(|(path, _)| path)
+        .collect::<Vec<_>>();
+    let payload = DragPayload::files(files);
+    match data.get_text() {
+        Some(text) => payload.with_text(text.as_str()),
+        None => payload,
+    }
+}
+
 fn get_modifiers(modifiers: gdk::ModifierType) -> keyboard::KeyModifiers {
     keyboard::KeyModifiers {
         shift: modifiers.contains(ModifierType::SHIFT_MASK),
@@ -746,7 +1606,7 @@ fn get_modifiers(modifiers: gdk::ModifierType) -> keyboard::KeyModifiers {
     }
 }
 
-fn make_key_event(key: &EventKey, repeat: bool) -> keyboard::KeyEvent {
+fn make_key_event(key: &EventKey, repeat: bool, state: KeyState) -> keyboard::KeyEvent {
     let keyval = key.get_keyval();
     let hardware_keycode = key.get_hardware_keycode();
 
@@ -754,7 +1614,15 @@ fn make_key_event(key: &EventKey, repeat: bool) -> keyboard::KeyEvent {
 
     let text = gdk::keyval_to_unicode(keyval);
 
-    keyboard::KeyEvent::new(keycode, repeat, get_modifiers(key.get_state()), text, text)
+    let mods = get_modifiers(key.get_state());
+    keyboard::KeyEvent::new(state, keycode, repeat, mods, text, text)
+}
+
+/// Whether a keysym is a dead key, such as `dead_acute`.
+///
+/// These are the keysyms from `0xfe50` to `0xfe8f` in `gdkkeysyms.h`.
+fn is_dead_key(keyval: u32) -> bool {
+    (0xfe50..=0xfe8f).contains(&keyval)
 }
 
 /// Map a hardware keycode to a keyval by performing a lookup in the keymap and finding the
@@ -798,3 +1666,161 @@ fn hardware_keycode_to_keyval(keycode: u16) -> Option<u32> {
         }
     }
 }
+
+/// The edge of a window of `size` that resizes it from `pos`, within
+/// `border` of the edge.
+fn resize_edge(size: Size, pos: Point, border: f64) -> Option<gdk::WindowEdge> {
+    let left = pos.x < border;
+    let right = pos.x >= size.width - border;
+    let top = pos.y < border;
+    let bottom = pos.y >= size.height - border;
+    match (left, right, top, bottom) {
+        (true, _, true, _) => Some(gdk::WindowEdge::NorthWest),
+        (_, true, true, _) => Some(gdk::WindowEdge::NorthEast),
+        (true, _, _, true) => Some(gdk::WindowEdge::SouthWest),
+        (_, true, _, true) => Some(gdk::WindowEdge::SouthEast),
+        (true, ..) => Some(gdk::WindowEdge::West),
+        (_, true, ..) => Some(gdk::WindowEdge::East),
+        (_, _, true, _) => Some(gdk::WindowEdge::North),
+        (.., true) => Some(gdk::WindowEdge::South),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keycodes::Code;
+    use std::rc::Rc;
+
+    /// Writes down the keyboard events that it is sent.
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+
+    impl WinHandler for Recorder {
+        fn connect(&mut self, _: &window::WindowHandle) {}
+
+        fn paint(&mut self, _: &mut Piet, _: Rect) -> bool {
+            false
+        }
+
+        fn key_down(&mut self, event: keyboard::KeyEvent) -> bool {
+            let composing = if event.is_composing { " composing" } else { "" };
+            let repeat = if event.repeat { " repeat" } else { "" };
+            let down = format!("down {:?}{}{}", event.key, composing, repeat);
+            self.0.borrow_mut().push(down);
+            true
+        }
+
+        fn composition_start(&mut self) {
+            self.0.borrow_mut().push("start".to_string());
+        }
+
+        fn composition_update(&mut self, text: &str, cursor: std::ops::Range<usize>) {
+            let update = format!("update {:?} {:?}", text, cursor);
+            self.0.borrow_mut().push(update);
+        }
+
+        fn composition_end(&mut self, text: &str) {
+            self.0.borrow_mut().push(format!("end {:?}", text));
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn recorder() -> (RefCell<Box<dyn WinHandler>>, Rc<RefCell<Vec<String>>>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        (RefCell::new(Box::new(Recorder(log.clone()))), log)
+    }
+
+    fn key(text: &'static str, code: Code, repeat: bool) -> keyboard::KeyEvent {
+        let mut key_event =
+            keyboard::KeyEvent::for_test(keyboard::KeyModifiers::default(), text, code);
+        key_event.repeat = repeat;
+        key_event
+    }
+
+    #[test]
+    fn dead_keys_are_composed_by_the_input_method() {
+        let (handler, log) = recorder();
+        let ime = ImeState::default();
+        // the input method shows the accent of the dead key
+        ime.key_down(&handler, key("", Code::Quote, false), true, || {
+            ime.start_composition(&handler);
+            ime.preedit_changed(&handler, "¨", 1);
+            true
+        });
+        // and types it on the next key
+        ime.key_down(&handler, key("o", Code::KeyO, false), false, || {
+            ime.preedit_changed(&handler, "", 0);
+            ime.preedit_end(&handler);
+            ime.commit(&handler, "ö");
+            true
+        });
+        assert_eq!(
+            *log.borrow(),
+            [
+                "start",
+                "update \"¨\" 2..2",
+                "down Dead composing",
+                "update \"\" 0..0",
+                "end \"ö\"",
+                "down Character(\"o\") composing",
+            ]
+        );
+    }
+
+    #[test]
+    fn text_committed_by_a_key_press_is_its_key() {
+        let (handler, log) = recorder();
+        let ime = ImeState::default();
+        ime.key_down(&handler, key("x", Code::KeyX, false), false, || {
+            ime.commit(&handler, "x");
+            true
+        });
+        ime.key_down(&handler, key("", Code::Escape, false), false, || false);
+        assert_eq!(*log.borrow(), ["down Character(\"x\")", "down Escape"]);
+    }
+
+    #[test]
+    fn text_committed_outside_a_key_press_is_a_composition() {
+        let (handler, log) = recorder();
+        let ime = ImeState::default();
+        ime.commit(&handler, "ü");
+        // there's nothing to end
+        ime.preedit_end(&handler);
+        assert_eq!(*log.borrow(), ["start", "end \"ü\""]);
+    }
+
+    #[test]
+    fn held_keys_repeat_until_released() {
+        let (handler, log) = recorder();
+        let ime = ImeState::default();
+        let key_repeat = KeyRepeat::default();
+        let a = gdk::enums::key::a;
+        let b = gdk::enums::key::b;
+        for &(keyval, code) in &[
+            (a, Code::KeyA),
+            (a, Code::KeyA),
+            (b, Code::KeyB),
+            (b, Code::KeyB),
+        ] {
+            let repeat = key_repeat.press(keyval);
+            let text = if keyval == a { "a" } else { "b" };
+            ime.key_down(&handler, key(text, code, repeat), false, || false);
+        }
+        key_repeat.release();
+        assert!(!key_repeat.press(b));
+        assert!(key_repeat.press(b));
+        assert_eq!(
+            *log.borrow(),
+            [
+                "down Character(\"a\")",
+                "down Character(\"a\") repeat",
+                "down Character(\"b\")",
+                "down Character(\"b\") repeat",
+            ]
+        );
+    }
+}
@@ -17,13 +17,18 @@
 #![allow(non_upper_case_globals)]
 
 use std::ffi::c_void;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use super::clipboard::Clipboard;
 use super::util;
+use super::window::time_interval_from_deadline;
 use crate::application::AppHandler;
+use crate::window::{IdleToken, TimerToken};
 
 use cocoa::appkit::{NSApp, NSApplication, NSApplicationActivationPolicyRegular};
-use cocoa::base::{id, nil, YES};
+use cocoa::base::{id, nil, BOOL, NO, YES};
 use cocoa::foundation::NSAutoreleasePool;
 use objc::declare::ClassDecl;
 use objc::runtime::{Class, Object, Sel};
@@ -34,6 +39,13 @@ pub struct Application {
     ns_app: id,
 }
 
+#[derive(Clone)]
+pub struct AppIdleHandle {
+    /// The app delegate, which is never deallocated.
+    delegate: id,
+    idle_queue: Arc<Mutex<Vec<IdleToken>>>,
+}
+
 impl Application {
     pub fn new(handler: Option<Box<dyn AppHandler>>) -> Application {
         util::assert_main_thread();
@@ -42,7 +54,10 @@ impl Application {
 
             let delegate: id = msg_send![APP_DELEGATE.0, alloc];
             let () = msg_send![delegate, init];
-            let state = DelegateState { handler };
+            let state = DelegateState {
+                handler,
+                idle_queue: Arc::new(Mutex::new(Vec::new())),
+            };
             let handler_ptr = Box::into_raw(Box::new(state));
             (*delegate).set_ivar(APP_HANDLER_IVAR, handler_ptr as *mut c_void);
             let ns_app = NSApp();
@@ -64,6 +79,37 @@ impl Application {
         }
     }
 
+    pub fn get_idle_handle() -> Option<AppIdleHandle> {
+        util::assert_main_thread();
+        unsafe {
+            let delegate: id = msg_send![NSApp(), delegate];
+            if delegate == nil {
+                return None;
+            }
+            let state = delegate_state(&mut *delegate);
+            Some(AppIdleHandle {
+                delegate,
+                idle_queue: state.idle_queue.clone(),
+            })
+        }
+    }
+
+    pub fn request_timer(deadline: Instant) -> TimerToken {
+        util::assert_main_thread();
+        let ti = time_interval_from_deadline(deadline);
+        let token = TimerToken::next();
+        unsafe {
+            let delegate: id = msg_send![NSApp(), delegate];
+            let nstimer = class!(NSTimer);
+            let nsnumber = class!(NSNumber);
+            let user_info: id = msg_send![nsnumber, numberWithUnsignedInteger: token.into_raw()];
+            let selector = sel!(handleTimer:);
+            let _: id = msg_send![nstimer, scheduledTimerWithTimeInterval: ti target: delegate
+                selector: selector userInfo: user_info repeats: NO];
+        }
+        token
+    }
+
     /// Hide the application this window belongs to. (cmd+H)
     pub fn hide() {
         unsafe {
@@ -96,10 +142,34 @@ impl Application {
             locale
         }
     }
+
+    pub fn prefers_reduced_motion() -> bool {
+        unsafe {
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let reduce_motion: BOOL = msg_send![workspace, accessibilityDisplayShouldReduceMotion];
+            reduce_motion == YES
+        }
+    }
+}
+
+unsafe impl Send for AppIdleHandle {}
+
+impl AppIdleHandle {
+    pub fn add_idle_token(&self, token: IdleToken) {
+        let mut queue = self.idle_queue.lock().expect("queue lock");
+        if queue.is_empty() {
+            unsafe {
+                let () = msg_send!(self.delegate, performSelectorOnMainThread: sel!(runIdle)
+                    withObject: nil waitUntilDone: NO);
+            }
+        }
+        queue.push(token);
+    }
 }
 
 struct DelegateState {
     handler: Option<Box<dyn AppHandler>>,
+    idle_queue: Arc<Mutex<Vec<IdleToken>>>,
 }
 
 impl DelegateState {
@@ -108,6 +178,26 @@ impl DelegateState {
             inner.command(command)
         }
     }
+
+    fn idle(&mut self) {
+        let queue: Vec<_> = mem::replace(&mut self.idle_queue.lock().expect("queue"), Vec::new());
+        if let Some(inner) = self.handler.as_mut() {
+            for token in queue {
+                inner.idle(token);
+            }
+        }
+    }
+
+    fn timer(&mut self, token: TimerToken) {
+        if let Some(inner) = self.handler.as_mut() {
+            inner.timer(token)
+        }
+    }
+}
+
+unsafe fn delegate_state(delegate: &mut Object) -> &mut DelegateState {
+    let inner: *mut c_void = *delegate.get_ivar(APP_HANDLER_IVAR);
+    &mut *(inner as *mut DelegateState)
 }
 
 struct AppDelegate(*const Class);
@@ -128,6 +218,12 @@ lazy_static! {
             sel!(handleMenuItem:),
             handle_menu_item as extern "C" fn(&mut Object, Sel, id),
         );
+
+        decl.add_method(sel!(runIdle), run_idle as extern "C" fn(&mut Object, Sel));
+        decl.add_method(
+            sel!(handleTimer:),
+            handle_timer as extern "C" fn(&mut Object, Sel, id),
+        );
         AppDelegate(decl.register())
     };
 }
@@ -147,3 +243,15 @@ extern "C" fn handle_menu_item(this: &mut Object, _: Sel, item: id) {
         (*inner).command(tag as u32);
     }
 }
+
+extern "C" fn run_idle(this: &mut Object, _: Sel) {
+    unsafe { delegate_state(this) }.idle();
+}
+
+extern "C" fn handle_timer(this: &mut Object, _: Sel, timer: id) {
+    let token = unsafe {
+        let user_info: id = msg_send![timer, userInfo];
+        msg_send![user_info, unsignedIntValue]
+    };
+    unsafe { delegate_state(this) }.timer(TimerToken::from_raw(token));
+}
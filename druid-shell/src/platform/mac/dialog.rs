@@ -27,12 +27,12 @@ use crate::dialog::{FileDialogOptions, FileDialogType};
 const NSModalResponseOK: NSInteger = 1;
 const NSModalResponseCancel: NSInteger = 0;
 
-pub(crate) fn get_file_dialog_path(
+pub(crate) fn get_file_dialog_paths(
     ty: FileDialogType,
     options: FileDialogOptions,
-) -> Option<OsString> {
+) -> Option<Vec<OsString>> {
     unsafe {
-        let panel: id = match ty {
+        let panel: id = match &ty {
             FileDialogType::Open => msg_send![class!(NSOpenPanel), openPanel],
             FileDialogType::Save => msg_send![class!(NSSavePanel), savePanel],
         };
@@ -50,6 +50,22 @@ pub(crate) fn get_file_dialog_path(
             let () = msg_send![panel, setAllowsMultipleSelection: YES];
         }
 
+        // panels don't show a title on recent versions of macOS; the message
+        // is shown at the top of the panel instead
+        if let Some(title) = &options.title {
+            let () = msg_send![panel, setMessage: make_nsstring(title)];
+        }
+
+        if let Some(name) = &options.default_name {
+            let () = msg_send![panel, setNameFieldStringValue: make_nsstring(name)];
+        }
+
+        if let Some(directory) = &options.starting_directory {
+            let path = make_nsstring(&directory.to_string_lossy());
+            let url: id = msg_send![class!(NSURL), fileURLWithPath: path isDirectory: YES];
+            let () = msg_send![panel, setDirectoryURL: url];
+        }
+
         // A vector of NSStrings. this must outlive `nsarray_allowed_types`.
         let allowed_types = options.allowed_types.as_ref().map(|specs| {
             specs
@@ -68,10 +84,22 @@ pub(crate) fn get_file_dialog_path(
         let result: NSInteger = msg_send![panel, runModal];
         match result {
             NSModalResponseOK => {
-                let url: id = msg_send![panel, URL];
-                let path: id = msg_send![url, path];
-                let path: OsString = from_nsstring(path).into();
-                Some(path)
+                let urls: Vec<id> = match ty {
+                    // only an open panel can have several results
+                    FileDialogType::Open => {
+                        let urls: id = msg_send![panel, URLs];
+                        (0..urls.count()).map(|i| urls.objectAtIndex(i)).collect()
+                    }
+                    FileDialogType::Save => vec![msg_send![panel, URL]],
+                };
+                let paths = urls
+                    .into_iter()
+                    .map(|url| {
+                        let path: id = msg_send![url, path];
+                        OsString::from(from_nsstring(path))
+                    })
+                    .collect();
+                Some(paths)
             }
             NSModalResponseCancel => None,
             _ => unreachable!(),
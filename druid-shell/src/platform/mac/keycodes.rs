@@ -14,144 +14,144 @@
 
 //! macOS keycode handling.
 
-use crate::keycodes::KeyCode;
+use crate::keycodes::Code;
 
 pub type RawKeyCode = u16;
 
-impl From<u16> for KeyCode {
-    fn from(raw: u16) -> KeyCode {
+impl From<u16> for Code {
+    fn from(raw: u16) -> Code {
         match raw {
-            0x00 => KeyCode::KeyA,
-            0x01 => KeyCode::KeyS,
-            0x02 => KeyCode::KeyD,
-            0x03 => KeyCode::KeyF,
-            0x04 => KeyCode::KeyH,
-            0x05 => KeyCode::KeyG,
-            0x06 => KeyCode::KeyZ,
-            0x07 => KeyCode::KeyX,
-            0x08 => KeyCode::KeyC,
-            0x09 => KeyCode::KeyV,
+            0x00 => Code::KeyA,
+            0x01 => Code::KeyS,
+            0x02 => Code::KeyD,
+            0x03 => Code::KeyF,
+            0x04 => Code::KeyH,
+            0x05 => Code::KeyG,
+            0x06 => Code::KeyZ,
+            0x07 => Code::KeyX,
+            0x08 => Code::KeyC,
+            0x09 => Code::KeyV,
             //0x0a => World 1,
-            0x0b => KeyCode::KeyB,
-            0x0c => KeyCode::KeyQ,
-            0x0d => KeyCode::KeyW,
-            0x0e => KeyCode::KeyE,
-            0x0f => KeyCode::KeyR,
-            0x10 => KeyCode::KeyY,
-            0x11 => KeyCode::KeyT,
-            0x12 => KeyCode::Key1,
-            0x13 => KeyCode::Key2,
-            0x14 => KeyCode::Key3,
-            0x15 => KeyCode::Key4,
-            0x16 => KeyCode::Key6,
-            0x17 => KeyCode::Key5,
-            0x18 => KeyCode::Equals,
-            0x19 => KeyCode::Key9,
-            0x1a => KeyCode::Key7,
-            0x1b => KeyCode::Minus,
-            0x1c => KeyCode::Key8,
-            0x1d => KeyCode::Key0,
-            0x1e => KeyCode::RightBracket,
-            0x1f => KeyCode::KeyO,
-            0x20 => KeyCode::KeyU,
-            0x21 => KeyCode::LeftBracket,
-            0x22 => KeyCode::KeyI,
-            0x23 => KeyCode::KeyP,
-            0x24 => KeyCode::Return,
-            0x25 => KeyCode::KeyL,
-            0x26 => KeyCode::KeyJ,
-            0x27 => KeyCode::Backtick,
-            0x28 => KeyCode::KeyK,
-            0x29 => KeyCode::Semicolon,
-            0x2a => KeyCode::Backslash,
-            0x2b => KeyCode::Comma,
-            0x2c => KeyCode::Slash,
-            0x2d => KeyCode::KeyN,
-            0x2e => KeyCode::KeyM,
-            0x2f => KeyCode::Period,
-            0x30 => KeyCode::Tab,
-            0x31 => KeyCode::Space,
-            0x32 => KeyCode::Backtick,
-            0x33 => KeyCode::Backspace,
+            0x0b => Code::KeyB,
+            0x0c => Code::KeyQ,
+            0x0d => Code::KeyW,
+            0x0e => Code::KeyE,
+            0x0f => Code::KeyR,
+            0x10 => Code::KeyY,
+            0x11 => Code::KeyT,
+            0x12 => Code::Key1,
+            0x13 => Code::Key2,
+            0x14 => Code::Key3,
+            0x15 => Code::Key4,
+            0x16 => Code::Key6,
+            0x17 => Code::Key5,
+            0x18 => Code::Equals,
+            0x19 => Code::Key9,
+            0x1a => Code::Key7,
+            0x1b => Code::Minus,
+            0x1c => Code::Key8,
+            0x1d => Code::Key0,
+            0x1e => Code::RightBracket,
+            0x1f => Code::KeyO,
+            0x20 => Code::KeyU,
+            0x21 => Code::LeftBracket,
+            0x22 => Code::KeyI,
+            0x23 => Code::KeyP,
+            0x24 => Code::Return,
+            0x25 => Code::KeyL,
+            0x26 => Code::KeyJ,
+            0x27 => Code::Backtick,
+            0x28 => Code::KeyK,
+            0x29 => Code::Semicolon,
+            0x2a => Code::Backslash,
+            0x2b => Code::Comma,
+            0x2c => Code::Slash,
+            0x2d => Code::KeyN,
+            0x2e => Code::KeyM,
+            0x2f => Code::Period,
+            0x30 => Code::Tab,
+            0x31 => Code::Space,
+            0x32 => Code::Backtick,
+            0x33 => Code::Backspace,
             //0x34 => unknown,
-            0x35 => KeyCode::Escape,
-            0x36 => KeyCode::RightMeta,
-            0x37 => KeyCode::LeftMeta,
-            0x38 => KeyCode::LeftShift,
-            0x39 => KeyCode::CapsLock,
-            0x3a => KeyCode::LeftAlt,
-            0x3b => KeyCode::LeftControl,
-            0x3c => KeyCode::RightShift,
-            0x3d => KeyCode::RightAlt,
-            0x3e => KeyCode::RightControl,
+            0x35 => Code::Escape,
+            0x36 => Code::RightMeta,
+            0x37 => Code::LeftMeta,
+            0x38 => Code::LeftShift,
+            0x39 => Code::CapsLock,
+            0x3a => Code::LeftAlt,
+            0x3b => Code::LeftControl,
+            0x3c => Code::RightShift,
+            0x3d => Code::RightAlt,
+            0x3e => Code::RightControl,
             //0x3f => Fn key,
-            //0x40 => KeyCode::F17,
-            0x41 => KeyCode::NumpadDecimal,
+            //0x40 => Code::F17,
+            0x41 => Code::NumpadDecimal,
             //0x42 -> unknown,
-            0x43 => KeyCode::NumpadMultiply,
+            0x43 => Code::NumpadMultiply,
             //0x44 => unknown,
-            0x45 => KeyCode::NumpadAdd,
+            0x45 => Code::NumpadAdd,
             //0x46 => unknown,
-            0x47 => KeyCode::NumLock,
+            0x47 => Code::NumLock,
             //0x48 => KeypadClear,
-            //0x49 => KeyCode::VolumeUp,
-            //0x4a => KeyCode::VolumeDown,
-            0x4b => KeyCode::NumpadDivide,
-            0x4c => KeyCode::NumpadEnter,
-            0x4e => KeyCode::NumpadSubtract,
+            //0x49 => Code::VolumeUp,
+            //0x4a => Code::VolumeDown,
+            0x4b => Code::NumpadDivide,
+            0x4c => Code::NumpadEnter,
+            0x4e => Code::NumpadSubtract,
             //0x4d => unknown,
-            //0x4e => KeyCode::Subtract,
-            //0x4f => KeyCode::F18,
-            //0x50 => KeyCode::F19,
-            0x51 => KeyCode::NumpadEquals,
-            0x52 => KeyCode::Numpad0,
-            0x53 => KeyCode::Numpad1,
-            0x54 => KeyCode::Numpad2,
-            0x55 => KeyCode::Numpad3,
-            0x56 => KeyCode::Numpad4,
-            0x57 => KeyCode::Numpad5,
-            0x58 => KeyCode::Numpad6,
-            0x59 => KeyCode::Numpad7,
-            //0x5a => KeyCode::F20,
-            0x5b => KeyCode::Numpad8,
-            0x5c => KeyCode::Numpad9,
-            //0x5d => KeyCode::Yen,
+            //0x4e => Code::Subtract,
+            //0x4f => Code::F18,
+            //0x50 => Code::F19,
+            0x51 => Code::NumpadEquals,
+            0x52 => Code::Numpad0,
+            0x53 => Code::Numpad1,
+            0x54 => Code::Numpad2,
+            0x55 => Code::Numpad3,
+            0x56 => Code::Numpad4,
+            0x57 => Code::Numpad5,
+            0x58 => Code::Numpad6,
+            0x59 => Code::Numpad7,
+            //0x5a => Code::F20,
+            0x5b => Code::Numpad8,
+            0x5c => Code::Numpad9,
+            //0x5d => Code::Yen,
             //0x5e => JIS Ro,
             //0x5f => unknown,
-            0x60 => KeyCode::F5,
-            0x61 => KeyCode::F6,
-            0x62 => KeyCode::F7,
-            0x63 => KeyCode::F3,
-            0x64 => KeyCode::F8,
-            0x65 => KeyCode::F9,
+            0x60 => Code::F5,
+            0x61 => Code::F6,
+            0x62 => Code::F7,
+            0x63 => Code::F3,
+            0x64 => Code::F8,
+            0x65 => Code::F9,
             //0x66 => JIS Eisuu (macOS),
-            0x67 => KeyCode::F11,
+            0x67 => Code::F11,
             //0x68 => JIS Kana (macOS),
-            0x69 => KeyCode::PrintScreen,
-            //0x6a => KeyCode::F16,
-            //0x6b => KeyCode::F14,
+            0x69 => Code::PrintScreen,
+            //0x6a => Code::F16,
+            //0x6b => Code::F14,
             //0x6c => unknown,
-            0x6d => KeyCode::F10,
+            0x6d => Code::F10,
             //0x6e => unknown,
-            0x6f => KeyCode::F12,
+            0x6f => Code::F12,
             //0x70 => unknown,
-            //0x71 => KeyCode::F15,
-            0x72 => KeyCode::Insert,
-            0x73 => KeyCode::Home,
-            0x74 => KeyCode::PageUp,
-            0x75 => KeyCode::Delete,
-            0x76 => KeyCode::F4,
-            0x77 => KeyCode::End,
-            0x78 => KeyCode::F2,
-            0x79 => KeyCode::PageDown,
-            0x7a => KeyCode::F1,
-            0x7b => KeyCode::ArrowLeft,
-            0x7c => KeyCode::ArrowRight,
-            0x7d => KeyCode::ArrowDown,
-            0x7e => KeyCode::ArrowUp,
+            //0x71 => Code::F15,
+            0x72 => Code::Insert,
+            0x73 => Code::Home,
+            0x74 => Code::PageUp,
+            0x75 => Code::Delete,
+            0x76 => Code::F4,
+            0x77 => Code::End,
+            0x78 => Code::F2,
+            0x79 => Code::PageDown,
+            0x7a => Code::F1,
+            0x7b => Code::ArrowLeft,
+            0x7c => Code::ArrowRight,
+            0x7d => Code::ArrowDown,
+            0x7e => Code::ArrowUp,
             //0x7f =>  unknown,
-            //0xa => KeyCode::Caret,
-            other => KeyCode::Unknown(other),
+            //0xa => Code::Caret,
+            other => Code::Unknown(other),
         }
     }
 }
@@ -162,8 +162,8 @@ mod tests {
 
     #[test]
     fn vk_mac() {
-        assert_eq!(KeyCode::from(0x30_u16), KeyCode::Tab);
+        assert_eq!(Code::from(0x30_u16), Code::Tab);
         //F17
-        assert_eq!(KeyCode::from(0x40_u16), KeyCode::Unknown(64));
+        assert_eq!(Code::from(0x40_u16), Code::Unknown(64));
     }
 }
@@ -22,7 +22,7 @@ use super::util::make_nsstring;
 use crate::common_util::strip_access_key;
 use crate::hotkey::{HotKey, KeyCompare};
 use crate::keyboard::KeyModifiers;
-use crate::keycodes::KeyCode;
+use crate::keycodes::Code;
 
 pub struct Menu {
     pub menu: id,
@@ -111,54 +111,21 @@ impl HotKey {
     ///
     /// Returns the empty string if no key equivalent is known.
     fn key_equivalent(&self) -> &str {
-        match self.key {
-            KeyCompare::Text(t) => t,
-
-            // from NSText.h
-            KeyCompare::Code(KeyCode::Return) => "\u{0003}",
-            KeyCompare::Code(KeyCode::Backspace) => "\u{0008}",
-            KeyCompare::Code(KeyCode::Delete) => "\u{007f}",
-            // from NSEvent.h
-            KeyCompare::Code(KeyCode::Insert) => "\u{F727}",
-            KeyCompare::Code(KeyCode::Home) => "\u{F729}",
-            KeyCompare::Code(KeyCode::End) => "\u{F72B}",
-            KeyCompare::Code(KeyCode::PageUp) => "\u{F72C}",
-            KeyCompare::Code(KeyCode::PageDown) => "\u{F72D}",
-            KeyCompare::Code(KeyCode::PrintScreen) => "\u{F72E}",
-            KeyCompare::Code(KeyCode::ScrollLock) => "\u{F72F}",
-            KeyCompare::Code(KeyCode::ArrowUp) => "\u{F700}",
-            KeyCompare::Code(KeyCode::ArrowDown) => "\u{F701}",
-            KeyCompare::Code(KeyCode::ArrowLeft) => "\u{F702}",
-            KeyCompare::Code(KeyCode::ArrowRight) => "\u{F703}",
-            KeyCompare::Code(KeyCode::F1) => "\u{F704}",
-            KeyCompare::Code(KeyCode::F2) => "\u{F705}",
-            KeyCompare::Code(KeyCode::F3) => "\u{F706}",
-            KeyCompare::Code(KeyCode::F4) => "\u{F707}",
-            KeyCompare::Code(KeyCode::F5) => "\u{F708}",
-            KeyCompare::Code(KeyCode::F6) => "\u{F709}",
-            KeyCompare::Code(KeyCode::F7) => "\u{F70A}",
-            KeyCompare::Code(KeyCode::F8) => "\u{F70B}",
-            KeyCompare::Code(KeyCode::F9) => "\u{F70C}",
-            KeyCompare::Code(KeyCode::F10) => "\u{F70D}",
-            //KeyCompare::Code(KeyCode::F11)            => "\u{F70E}",
-            //KeyCompare::Code(KeyCode::F12)            => "\u{F70F}",
-            //KeyCompare::Code(KeyCode::F13)            => "\u{F710}",
-            //KeyCompare::Code(KeyCode::F14)            => "\u{F711}",
-            //KeyCompare::Code(KeyCode::F15)            => "\u{F712}",
-            //KeyCompare::Code(KeyCode::F16)            => "\u{F713}",
-            //KeyCompare::Code(KeyCode::F17)            => "\u{F714}",
-            //KeyCompare::Code(KeyCode::F18)            => "\u{F715}",
-            //KeyCompare::Code(KeyCode::F19)            => "\u{F716}",
-            //KeyCompare::Code(KeyCode::F20)            => "\u{F717}",
-            KeyCompare::Code(other) => {
-                eprintln!("no key equivalent for {:?}", other);
-                ""
-            }
+        match &self.key {
+            KeyCompare::Text(t) => t.as_str(),
+            KeyCompare::Code(code) => code_equivalent(*code),
+            KeyCompare::Key(key) => match key.code() {
+                Some(code) => code_equivalent(code),
+                None => {
+                    log::warn!("no key equivalent for {:?}", key);
+                    ""
+                }
+            },
         }
     }
 
     fn key_modifier_mask(&self) -> NSEventModifierFlags {
-        let mods: KeyModifiers = self.mods.into();
+        let mods: KeyModifiers = self.raw_mods().into();
         let mut flags = NSEventModifierFlags::empty();
         if mods.shift {
             flags.insert(NSEventModifierFlags::NSShiftKeyMask);
@@ -187,3 +154,49 @@ mod tests {
         assert_eq!(strip_access_key("E&xit").as_str(), "Exit");
     }
 }
+
+/// The key equivalent for a physical key, or the empty string if none is known.
+fn code_equivalent(code: Code) -> &'static str {
+    match code {
+        // from NSText.h
+        Code::Return => "\u{0003}",
+        Code::Backspace => "\u{0008}",
+        Code::Delete => "\u{007f}",
+        // from NSEvent.h
+        Code::Insert => "\u{F727}",
+        Code::Home => "\u{F729}",
+        Code::End => "\u{F72B}",
+        Code::PageUp => "\u{F72C}",
+        Code::PageDown => "\u{F72D}",
+        Code::PrintScreen => "\u{F72E}",
+        Code::ScrollLock => "\u{F72F}",
+        Code::ArrowUp => "\u{F700}",
+        Code::ArrowDown => "\u{F701}",
+        Code::ArrowLeft => "\u{F702}",
+        Code::ArrowRight => "\u{F703}",
+        Code::F1 => "\u{F704}",
+        Code::F2 => "\u{F705}",
+        Code::F3 => "\u{F706}",
+        Code::F4 => "\u{F707}",
+        Code::F5 => "\u{F708}",
+        Code::F6 => "\u{F709}",
+        Code::F7 => "\u{F70A}",
+        Code::F8 => "\u{F70B}",
+        Code::F9 => "\u{F70C}",
+        Code::F10 => "\u{F70D}",
+        //Code::F11            => "\u{F70E}",
+        //Code::F12            => "\u{F70F}",
+        //Code::F13            => "\u{F710}",
+        //Code::F14            => "\u{F711}",
+        //Code::F15            => "\u{F712}",
+        //Code::F16            => "\u{F713}",
+        //Code::F17            => "\u{F714}",
+        //Code::F18            => "\u{F715}",
+        //Code::F19            => "\u{F716}",
+        //Code::F20            => "\u{F717}",
+        other => {
+            log::warn!("no key equivalent for {:?}", other);
+            ""
+        }
+    }
+}
@@ -22,5 +22,6 @@ pub mod dialog;
 pub mod error;
 pub mod keycodes;
 pub mod menu;
+pub mod screen;
 pub mod util;
 pub mod window;
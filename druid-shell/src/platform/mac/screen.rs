@@ -0,0 +1,58 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! macOS monitors.
+
+use cocoa::appkit::CGFloat;
+use cocoa::base::id;
+use cocoa::foundation::{NSRect, NSUInteger};
+
+use super::window::main_screen_height;
+use crate::kurbo::Rect;
+use crate::scale::Scale;
+use crate::screen::Monitor;
+
+/// Convert a rect in Cocoa's screen coordinates, with the origin at the
+/// bottom left, to one with the origin at the top left, like window positions.
+fn flipped(rect: NSRect, screen_height: f64) -> Rect {
+    let top = screen_height - (rect.origin.y + rect.size.height);
+    Rect::from_origin_size((rect.origin.x, top), (rect.size.width, rect.size.height))
+}
+
+/// The monitors, from `NSScreen`.
+///
+/// Their frames are already in points, which are the px units that windows
+/// are positioned in; the first screen is the one with the menu bar.
+pub(crate) fn get_monitors() -> Vec<Monitor> {
+    unsafe {
+        let screen_height = main_screen_height();
+        let screens: id = msg_send![class!(NSScreen), screens];
+        let count: NSUInteger = msg_send![screens, count];
+        (0..count)
+            .map(|idx| {
+                let screen: id = msg_send![screens, objectAtIndex: idx];
+                let frame: NSRect = msg_send![screen, frame];
+                let visible: NSRect = msg_send![screen, visibleFrame];
+                let factor: CGFloat = msg_send![screen, backingScaleFactor];
+                Monitor::from_pixels(
+                    idx == 0,
+                    flipped(frame, screen_height),
+                    flipped(visible, screen_height),
+                    Scale::new(factor, factor),
+                    96.0,
+                )
+            })
+            .collect()
+    }
+}
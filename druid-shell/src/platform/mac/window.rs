@@ -19,39 +19,53 @@
 use std::any::Any;
 use std::ffi::c_void;
 use std::mem;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex, Weak};
 use std::time::Instant;
 
 use cocoa::appkit::{
     CGFloat, NSApp, NSApplication, NSAutoresizingMaskOptions, NSBackingStoreBuffered, NSEvent,
-    NSEventModifierFlags, NSView, NSViewHeightSizable, NSViewWidthSizable, NSWindow,
-    NSWindowStyleMask,
+    NSEventModifierFlags, NSEventPhase, NSPasteboardTypeString, NSView, NSViewHeightSizable,
+    NSViewWidthSizable, NSWindow, NSWindowButton, NSWindowStyleMask, NSWindowTitleVisibility,
 };
 use cocoa::base::{id, nil, BOOL, NO, YES};
-use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize, NSString};
+use cocoa::foundation::{
+    NSArray, NSAutoreleasePool, NSInteger, NSPoint, NSRect, NSSize, NSString, NSUInteger,
+};
 use objc::declare::ClassDecl;
 use objc::rc::WeakPtr;
-use objc::runtime::{Class, Object, Sel};
+use objc::runtime::{Class, Object, Protocol, Sel};
+use objc::Encode;
 
 use cairo::{Context, QuartzSurface};
 use log::{error, info};
 
-use crate::kurbo::{Point, Size, Vec2};
-use crate::piet::{Piet, RenderContext};
+use crate::access::{AccessEvent, AccessNode};
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::piet::{Color, Piet, RenderContext};
 
 use super::dialog;
 use super::menu::Menu;
-use super::util::{assert_main_thread, make_nsstring};
-use crate::common_util::IdleCallback;
+use super::util::{assert_main_thread, from_nsstring, make_nsstring};
+use crate::common_util::{
+    color_components, point_to_pixels, point_to_px, render_device_image, size_to_pixels,
+    size_to_px, utf16_to_utf8_offset, IdleCallback,
+};
 use crate::dialog::{FileDialogOptions, FileDialogType, FileInfo};
-use crate::keyboard::{KeyEvent, KeyModifiers};
-use crate::keycodes::KeyCode;
-use crate::mouse::{Cursor, MouseButton, MouseEvent};
-use crate::window::{IdleToken, Text, TimerToken, WinHandler};
+use crate::drag::{DragEvent, DragPayload};
+use crate::keyboard::{KbKey, KeyEvent, KeyModifiers, KeyState};
+use crate::keycodes::Code;
+use crate::mouse::{Cursor, MouseButton, MouseButtons, MouseEvent, ScrollPhase};
+use crate::scale::Scale;
+use crate::timer;
+use crate::window::{self, IdleToken, Text, TimerToken, WinHandler};
 use crate::Error;
 
 #[allow(non_upper_case_globals)]
 const NSWindowDidBecomeKeyNotification: &str = "NSWindowDidBecomeKeyNotification";
+#[allow(non_upper_case_globals)]
+const NSWorkspaceAccessibilityDisplayOptionsDidChangeNotification: &str =
+    "NSWorkspaceAccessibilityDisplayOptionsDidChangeNotification";
 
 #[derive(Clone)]
 pub(crate) struct WindowHandle {
@@ -76,8 +90,14 @@ pub(crate) struct WindowBuilder {
     title: String,
     menu: Option<Menu>,
     size: Size,
+    min_size: Option<Size>,
+    position: Option<Point>,
+    state: Option<window::WindowState>,
     resizable: bool,
     show_titlebar: bool,
+    transparent: bool,
+    level: window::WindowLevel,
+    background: Option<Color>,
 }
 
 #[derive(Clone)]
@@ -90,6 +110,8 @@ pub(crate) struct IdleHandle {
 enum IdleKind {
     Callback(Box<dyn IdleCallback>),
     Token(IdleToken),
+    /// A timer that is due, with its deadline.
+    Timer(TimerToken, Instant),
 }
 
 /// This is the state associated with our custom NSView.
@@ -98,6 +120,41 @@ struct ViewState {
     handler: Box<dyn WinHandler>,
     idle_queue: Arc<Mutex<Vec<IdleKind>>>,
     last_mods: KeyModifiers,
+    /// Whether the handler has been told that a composition started.
+    composing: bool,
+    /// The text of the composition, which AppKit calls the marked text.
+    marked_text: String,
+    /// Whether a key press is being interpreted by the input method.
+    in_key_down: bool,
+    /// Where the input method puts its windows, in the view's coordinates.
+    ime_area: Rect,
+}
+
+/// An `NSRange` that can be passed to and from methods, as cocoa's doesn't
+/// implement `Encode`. The range is in UTF-16 code units.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct NSRange {
+    location: NSUInteger,
+    length: NSUInteger,
+}
+
+/// The location of a range that isn't there.
+const NS_NOT_FOUND: NSUInteger = NSInteger::max_value() as NSUInteger;
+
+impl NSRange {
+    const NONE: NSRange = NSRange {
+        location: NS_NOT_FOUND,
+        length: 0,
+    };
+}
+
+unsafe impl objc::Encode for NSRange {
+    fn encode() -> objc::Encoding {
+        let unit = NSUInteger::encode();
+        let encoding = format!("{{_NSRange={}{}}}", unit.as_str(), unit.as_str());
+        unsafe { objc::Encoding::from_str(&encoding) }
+    }
 }
 
 impl WindowBuilder {
@@ -107,8 +164,14 @@ impl WindowBuilder {
             title: String::new(),
             menu: None,
             size: Size::new(500.0, 400.0),
+            min_size: None,
+            position: None,
+            state: None,
             resizable: true,
             show_titlebar: true,
+            transparent: false,
+            level: window::WindowLevel::AppWindow,
+            background: None,
         }
     }
 
@@ -120,44 +183,114 @@ impl WindowBuilder {
         self.size = size;
     }
 
+    pub fn set_min_size(&mut self, size: Size) {
+        self.min_size = Some(size);
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.position = Some(position);
+    }
+
+    pub fn set_window_state(&mut self, state: window::WindowState) {
+        self.state = Some(state);
+    }
+
     pub fn resizable(&mut self, resizable: bool) {
-        // TODO: Use this in `self.build`
         self.resizable = resizable;
     }
 
     pub fn show_titlebar(&mut self, show_titlebar: bool) {
-        // TODO: Use this in `self.build`
         self.show_titlebar = show_titlebar;
     }
 
+    pub fn transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
+    pub fn set_level(&mut self, level: window::WindowLevel) {
+        self.level = level;
+    }
+
     pub fn set_title(&mut self, title: impl Into<String>) {
         self.title = title.into();
     }
 
+    pub fn set_background_color(&mut self, color: Color) {
+        self.background = Some(color);
+    }
+
     pub fn set_menu(&mut self, menu: Menu) {
         self.menu = Some(menu);
     }
 
+    pub fn set_render_backend(&mut self, _backend: window::RenderBackend) {
+        // drawing is always done in software
+    }
+
     pub fn build(self) -> Result<WindowHandle, Error> {
         assert_main_thread();
         unsafe {
-            let style_mask = NSWindowStyleMask::NSTitledWindowMask
-                | NSWindowStyleMask::NSClosableWindowMask
-                | NSWindowStyleMask::NSMiniaturizableWindowMask
-                | NSWindowStyleMask::NSResizableWindowMask;
+            let popup = self.level.is_popup();
             let rect = NSRect::new(
                 NSPoint::new(0., 0.),
                 NSSize::new(self.size.width, self.size.height),
             );
 
-            let window = NSWindow::alloc(nil).initWithContentRect_styleMask_backing_defer_(
-                rect,
-                style_mask,
-                NSBackingStoreBuffered,
-                NO,
-            );
+            // a popup is a panel, which can be shown without activating the app
+            let window = if popup {
+                let panel: id = msg_send![class!(NSPanel), alloc];
+                let panel = panel.initWithContentRect_styleMask_backing_defer_(
+                    rect,
+                    NSWindowStyleMask::NSBorderlessWindowMask
+                        | NSWindowStyleMask::NSNonactivatingPanelMask,
+                    NSBackingStoreBuffered,
+                    NO,
+                );
+                let () = msg_send![panel, setLevel: NS_POP_UP_MENU_WINDOW_LEVEL];
+                let () = msg_send![panel, setBecomesKeyOnlyIfNeeded: YES];
+                if self.level == window::WindowLevel::Tooltip {
+                    let () = msg_send![panel, setIgnoresMouseEvents: YES];
+                }
+                panel
+            } else {
+                let style_mask = style_mask(self.show_titlebar, self.resizable);
+                NSWindow::alloc(nil).initWithContentRect_styleMask_backing_defer_(
+                    rect,
+                    style_mask,
+                    NSBackingStoreBuffered,
+                    NO,
+                )
+            };
 
-            window.cascadeTopLeftFromPoint_(NSPoint::new(20.0, 20.0));
+            if let Some(min_size) = self.min_size {
+                // the content size is in points, like our px units
+                let min_size = NSSize::new(min_size.width, min_size.height);
+                let () = msg_send![window, setContentMinSize: min_size];
+            }
+
+            if !popup {
+                set_titlebar_shown(window, self.show_titlebar);
+            }
+
+            if self.transparent {
+                window.setOpaque_(NO);
+                let clear: id = msg_send![class!(NSColor), clearColor];
+                let () = msg_send![window, setBackgroundColor: clear];
+            } else if let Some(color) = &self.background {
+                set_background_color(window, color);
+            }
+
+            match self.position {
+                Some(position) => set_top_left(window, position),
+                None => {
+                    window.cascadeTopLeftFromPoint_(NSPoint::new(20.0, 20.0));
+                }
+            }
+            match self.state {
+                Some(window::WindowState::Maximized) => window.zoom_(nil),
+                Some(window::WindowState::Minimized) => window.miniaturize_(nil),
+                Some(window::WindowState::Restored) | None => (),
+            }
             window.setTitle_(make_nsstring(&self.title));
             // TODO: this should probably be a tracking area instead
             window.setAcceptsMouseMovedEvents_(YES);
@@ -226,6 +359,10 @@ lazy_static! {
             sel!(windowDidBecomeKey:),
             window_did_become_key as extern "C" fn(&mut Object, Sel, id),
         );
+        decl.add_method(
+            sel!(accessibilityDisplayOptionsDidChange:),
+            accessibility_display_options_did_change as extern "C" fn(&mut Object, Sel, id),
+        );
         decl.add_method(
             sel!(setFrameSize:),
             set_frame_size as extern "C" fn(&mut Object, Sel, NSSize),
@@ -267,6 +404,54 @@ lazy_static! {
             key_down as extern "C" fn(&mut Object, Sel, id),
         );
         decl.add_method(sel!(keyUp:), key_up as extern "C" fn(&mut Object, Sel, id));
+
+        // the input method talks to the view as an NSTextInputClient
+        if let Some(protocol) = Protocol::get("NSTextInputClient") {
+            decl.add_protocol(protocol);
+        }
+        decl.add_method(
+            sel!(hasMarkedText),
+            has_marked_text as extern "C" fn(&mut Object, Sel) -> BOOL,
+        );
+        decl.add_method(
+            sel!(markedRange),
+            marked_range as extern "C" fn(&mut Object, Sel) -> NSRange,
+        );
+        decl.add_method(
+            sel!(selectedRange),
+            selected_range as extern "C" fn(&mut Object, Sel) -> NSRange,
+        );
+        decl.add_method(
+            sel!(setMarkedText:selectedRange:replacementRange:),
+            set_marked_text as extern "C" fn(&mut Object, Sel, id, NSRange, NSRange),
+        );
+        decl.add_method(sel!(unmarkText), unmark_text as extern "C" fn(&mut Object, Sel));
+        decl.add_method(
+            sel!(validAttributesForMarkedText),
+            valid_attributes_for_marked_text as extern "C" fn(&mut Object, Sel) -> id,
+        );
+        decl.add_method(
+            sel!(attributedSubstringForProposedRange:actualRange:),
+            attributed_substring_for_proposed_range
+                as extern "C" fn(&mut Object, Sel, NSRange, *mut c_void) -> id,
+        );
+        decl.add_method(
+            sel!(insertText:replacementRange:),
+            insert_text as extern "C" fn(&mut Object, Sel, id, NSRange),
+        );
+        decl.add_method(
+            sel!(characterIndexForPoint:),
+            character_index_for_point as extern "C" fn(&mut Object, Sel, NSPoint) -> NSUInteger,
+        );
+        decl.add_method(
+            sel!(firstRectForCharacterRange:actualRange:),
+            first_rect_for_character_range
+                as extern "C" fn(&mut Object, Sel, NSRange, *mut c_void) -> NSRect,
+        );
+        decl.add_method(
+            sel!(doCommandBySelector:),
+            do_command_by_selector as extern "C" fn(&mut Object, Sel, Sel),
+        );
         decl.add_method(
             sel!(flagsChanged:),
             mods_changed as extern "C" fn(&mut Object, Sel, id),
@@ -277,10 +462,6 @@ lazy_static! {
         );
         decl.add_method(sel!(runIdle), run_idle as extern "C" fn(&mut Object, Sel));
         decl.add_method(sel!(redraw), redraw as extern "C" fn(&mut Object, Sel));
-        decl.add_method(
-            sel!(handleTimer:),
-            handle_timer as extern "C" fn(&mut Object, Sel, id),
-        );
         decl.add_method(
             sel!(handleMenuItem:),
             handle_menu_item as extern "C" fn(&mut Object, Sel, id),
@@ -289,14 +470,46 @@ lazy_static! {
             sel!(showContextMenu:),
             show_context_menu as extern "C" fn(&mut Object, Sel, id),
         );
+        decl.add_method(
+            sel!(viewDidChangeBackingProperties),
+            view_did_change_backing_properties as extern "C" fn(&mut Object, Sel),
+        );
         decl.add_method(
             sel!(windowWillClose:),
             window_will_close as extern "C" fn(&mut Object, Sel, id),
         );
+        decl.add_method(
+            sel!(draggingEntered:),
+            dragging_entered as extern "C" fn(&mut Object, Sel, id) -> NSUInteger,
+        );
+        decl.add_method(
+            sel!(draggingUpdated:),
+            dragging_updated as extern "C" fn(&mut Object, Sel, id) -> NSUInteger,
+        );
+        decl.add_method(
+            sel!(draggingExited:),
+            dragging_exited as extern "C" fn(&mut Object, Sel, id),
+        );
+        decl.add_method(
+            sel!(performDragOperation:),
+            perform_drag_operation as extern "C" fn(&mut Object, Sel, id) -> BOOL,
+        );
         ViewClass(decl.register())
     };
 }
 
+/// Paint an image in memory. macOS always draws in software, so this is the
+/// same for every backend.
+pub(crate) fn render_image(
+    _backend: window::RenderBackend,
+    width: usize,
+    height: usize,
+    scale: f64,
+    paint: impl FnOnce(&mut Piet),
+) -> Result<window::CapturedImage, Error> {
+    render_device_image(width, height, scale, paint)
+}
+
 fn make_view(handler: Box<dyn WinHandler>) -> (id, Weak<Mutex<Vec<IdleKind>>>) {
     let idle_queue = Arc::new(Mutex::new(Vec::new()));
     let queue_handle = Arc::downgrade(&idle_queue);
@@ -308,11 +521,19 @@ fn make_view(handler: Box<dyn WinHandler>) -> (id, Weak<Mutex<Vec<IdleKind>>>) {
             handler,
             idle_queue,
             last_mods: KeyModifiers::default(),
+            composing: false,
+            marked_text: String::new(),
+            in_key_down: false,
+            ime_area: Rect::ZERO,
         };
         let state_ptr = Box::into_raw(Box::new(state));
         (*view).set_ivar("viewState", state_ptr as *mut c_void);
         let options: NSAutoresizingMaskOptions = NSViewWidthSizable | NSViewHeightSizable;
         view.setAutoresizingMask_(options);
+        // the view takes files and text
+        let types = [make_nsstring(FILENAMES_PBOARD_TYPE), NSPasteboardTypeString];
+        let types = NSArray::arrayWithObjects(nil, &types);
+        let () = msg_send![view, registerForDraggedTypes: types];
         (view.autorelease(), queue_handle)
     }
 }
@@ -329,14 +550,115 @@ extern "C" fn set_frame_size(this: &mut Object, _: Sel, size: NSSize) {
     }
 }
 
+/// The pasteboard type of the paths of files that are dragged.
+const FILENAMES_PBOARD_TYPE: &str = "NSFilenamesPboardType";
+
+const NS_DRAG_OPERATION_NONE: NSUInteger = 0;
+const NS_DRAG_OPERATION_COPY: NSUInteger = 1;
+
+/// The event for something that is dragged over the view, from its
+/// `NSDraggingInfo`.
+fn drag_event(info: id, view: id) -> DragEvent {
+    unsafe {
+        let point: NSPoint = msg_send![info, draggingLocation];
+        let view_point = view.convertPoint_fromView_(point, nil);
+        let flags: NSEventModifierFlags = msg_send![class!(NSEvent), modifierFlags];
+
+        let pasteboard: id = msg_send![info, draggingPasteboard];
+        let mut payload = DragPayload::new();
+        let files: id =
+            msg_send![pasteboard, propertyListForType: make_nsstring(FILENAMES_PBOARD_TYPE)];
+        if files != nil {
+            let files = (0..files.count())
+                .map(|i| PathBuf::from(from_nsstring(files.objectAtIndex(i))))
+                .collect::<Vec<_>>();
+            payload = payload.with_files(files);
+        }
+        let text: id = msg_send![pasteboard, stringForType: NSPasteboardTypeString];
+        if text != nil {
+            payload = payload.with_text(from_nsstring(text));
+        }
+
+        DragEvent {
+            pos: Point::new(view_point.x as f64, view_point.y as f64),
+            mods: make_modifiers(flags),
+            payload,
+        }
+    }
+}
+
+fn drag_operation(accepted: bool) -> NSUInteger {
+    if accepted {
+        NS_DRAG_OPERATION_COPY
+    } else {
+        NS_DRAG_OPERATION_NONE
+    }
+}
+
+extern "C" fn dragging_entered(this: &mut Object, _: Sel, info: id) -> NSUInteger {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let event = drag_event(info, this as id);
+        drag_operation((*view_state).handler.drag_enter(&event))
+    }
+}
+
+extern "C" fn dragging_updated(this: &mut Object, _: Sel, info: id) -> NSUInteger {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let event = drag_event(info, this as id);
+        drag_operation((*view_state).handler.drag_over(&event))
+    }
+}
+
+extern "C" fn dragging_exited(this: &mut Object, _: Sel, _info: id) {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        (*view_state).handler.drag_leave();
+    }
+}
+
+extern "C" fn perform_drag_operation(this: &mut Object, _: Sel, info: id) -> BOOL {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let event = drag_event(info, this as id);
+        if (*view_state).handler.drag_drop(&event) {
+            YES
+        } else {
+            NO
+        }
+    }
+}
+
+extern "C" fn view_did_change_backing_properties(this: &mut Object, _: Sel) {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let window: id = msg_send![this as *const _, window];
+        let scale = backing_scale(window);
+        (*view_state).handler.scale(scale);
+    }
+}
+
+/// The scale of a window's backing store.
+unsafe fn backing_scale(window: id) -> Scale {
+    if window == nil {
+        return Scale::default();
+    }
+    let factor: CGFloat = msg_send![window, backingScaleFactor];
+    Scale::new(factor, factor)
+}
+
 // NOTE: If we know the button (because of the origin call) we pass it through,
 // otherwise we get it from the event itself.
 fn mouse_event(nsevent: id, view: id, button: Option<MouseButton>) -> MouseEvent {
     unsafe {
-        let button = button.unwrap_or_else(|| {
-            let button = NSEvent::pressedMouseButtons(nsevent);
-            get_mouse_button(button as usize)
-        });
+        let pressed = NSEvent::pressedMouseButtons(nsevent) as usize;
+        let button = button.unwrap_or_else(|| get_mouse_button(pressed));
         let point = nsevent.locationInWindow();
         let view_point = view.convertPoint_fromView_(point, nil);
         let pos = Point::new(view_point.x as f64, view_point.y as f64);
@@ -348,10 +670,32 @@ fn mouse_event(nsevent: id, view: id, button: Option<MouseButton>) -> MouseEvent
             mods: modifiers,
             count,
             button,
+            buttons: get_mouse_buttons(pressed),
+            focus: false,
         }
     }
 }
 
+/// The buttons in the mask from `pressedMouseButtons`.
+fn get_mouse_buttons(mask: usize) -> MouseButtons {
+    let mut buttons = MouseButtons::new();
+    for (i, &button) in [
+        MouseButton::Left,
+        MouseButton::Right,
+        MouseButton::Middle,
+        MouseButton::X1,
+        MouseButton::X2,
+    ]
+    .iter()
+    .enumerate()
+    {
+        if mask & 1 << i != 0 {
+            buttons.insert(button);
+        }
+    }
+    buttons
+}
+
 fn get_mouse_button(mask: usize) -> MouseButton {
     //TODO: this doesn't correctly handle multiple buttons being pressed.
     match mask {
@@ -428,7 +772,34 @@ extern "C" fn scroll_wheel(this: &mut Object, _: Sel, nsevent: id) {
         let mods = make_modifiers(mods);
 
         let delta = Vec2::new(dx, dy);
-        (*view_state).handler.wheel(delta, mods);
+        // an event is either part of the gesture, or of the momentum after
+        // it; a mouse wheel has neither
+        let (phase, momentum_phase) = match (
+            scroll_phase(nsevent.phase()),
+            scroll_phase(nsevent.momentumPhase()),
+        ) {
+            (_, Some(momentum)) => (ScrollPhase::Ended, Some(momentum)),
+            (Some(phase), None) => (phase, None),
+            (None, None) => (ScrollPhase::Changed, None),
+        };
+        (*view_state)
+            .handler
+            .wheel_with_phase(delta, mods, phase, momentum_phase);
+    }
+}
+
+/// The phase of a scroll wheel event, if it has one.
+fn scroll_phase(phase: NSEventPhase) -> Option<ScrollPhase> {
+    if phase.intersects(NSEventPhase::NSEventPhaseBegan | NSEventPhase::NSEventPhaseMayBegin) {
+        Some(ScrollPhase::Started)
+    } else if phase
+        .intersects(NSEventPhase::NSEventPhaseEnded | NSEventPhase::NSEventPhaseCancelled)
+    {
+        Some(ScrollPhase::Ended)
+    } else if phase.is_empty() {
+        None
+    } else {
+        Some(ScrollPhase::Changed)
     }
 }
 
@@ -443,24 +814,175 @@ extern "C" fn pinch_event(this: &mut Object, _: Sel, nsevent: id) {
 }
 
 extern "C" fn key_down(this: &mut Object, _: Sel, nsevent: id) {
-    let event = make_key_event(nsevent);
+    let mut event = make_key_event(nsevent, KeyState::Down);
 
-    let view_state = unsafe {
-        let view_state: *mut c_void = *this.get_ivar("viewState");
-        &mut *(view_state as *mut ViewState)
+    let was_composing = {
+        let view_state = unsafe { view_state(this) };
+        view_state.last_mods = event.mods;
+        view_state.in_key_down = true;
+        view_state.composing
     };
+    // the input method sees the key first, and calls back with its text
+    unsafe {
+        let events = NSArray::arrayWithObject(nil, nsevent);
+        let () = msg_send![this, interpretKeyEvents: events];
+    }
+    let view_state = unsafe { view_state(this) };
+    view_state.in_key_down = false;
+    event.is_composing = was_composing || view_state.composing;
     (*view_state).handler.key_down(event);
-    view_state.last_mods = event.mods;
+}
+
+/// The state of a view, from the view.
+unsafe fn view_state(this: &mut Object) -> &mut ViewState {
+    let view_state: *mut c_void = *this.get_ivar("viewState");
+    &mut *(view_state as *mut ViewState)
+}
+
+/// The text of an `NSString`, or of an `NSAttributedString`.
+unsafe fn text_of(string: id) -> String {
+    let is_attributed: BOOL = msg_send![string, isKindOfClass: class!(NSAttributedString)];
+    if is_attributed == YES {
+        from_nsstring(msg_send![string, string])
+    } else {
+        from_nsstring(string)
+    }
+}
+
+extern "C" fn has_marked_text(this: &mut Object, _: Sel) -> BOOL {
+    let view_state = unsafe { view_state(this) };
+    if view_state.marked_text.is_empty() {
+        NO
+    } else {
+        YES
+    }
+}
+
+extern "C" fn marked_range(this: &mut Object, _: Sel) -> NSRange {
+    let view_state = unsafe { view_state(this) };
+    if view_state.marked_text.is_empty() {
+        NSRange::NONE
+    } else {
+        let length = view_state.marked_text.encode_utf16().count();
+        NSRange {
+            location: 0,
+            length: length as NSUInteger,
+        }
+    }
+}
+
+extern "C" fn selected_range(_this: &mut Object, _: Sel) -> NSRange {
+    // the handler has the text, and the selection in it
+    NSRange {
+        location: 0,
+        length: 0,
+    }
+}
+
+extern "C" fn set_marked_text(
+    this: &mut Object,
+    _: Sel,
+    string: id,
+    selected: NSRange,
+    _replacement: NSRange,
+) {
+    let text = unsafe { text_of(string) };
+    let view_state = unsafe { view_state(this) };
+    if text.is_empty() {
+        // the composition was deleted
+        if view_state.composing {
+            view_state.composing = false;
+            view_state.marked_text.clear();
+            view_state.handler.composition_end("");
+        }
+        return;
+    }
+    if !view_state.composing {
+        view_state.composing = true;
+        view_state.handler.composition_start();
+    }
+    let start = selected.location as usize;
+    let end = start + selected.length as usize;
+    let cursor = utf16_to_utf8_offset(&text, start)..utf16_to_utf8_offset(&text, end);
+    view_state.handler.composition_update(&text, cursor);
+    view_state.marked_text = text;
+}
+
+extern "C" fn unmark_text(this: &mut Object, _: Sel) {
+    // the composition is typed as it is
+    let view_state = unsafe { view_state(this) };
+    if view_state.composing {
+        view_state.composing = false;
+        let text = mem::take(&mut view_state.marked_text);
+        view_state.handler.composition_end(&text);
+    }
+}
+
+extern "C" fn valid_attributes_for_marked_text(_this: &mut Object, _: Sel) -> id {
+    unsafe { msg_send![class!(NSArray), array] }
+}
+
+extern "C" fn attributed_substring_for_proposed_range(
+    _this: &mut Object,
+    _: Sel,
+    _range: NSRange,
+    _actual_range: *mut c_void,
+) -> id {
+    nil
+}
+
+extern "C" fn insert_text(this: &mut Object, _: Sel, string: id, _replacement: NSRange) {
+    let text = unsafe { text_of(string) };
+    let view_state = unsafe { view_state(this) };
+    if view_state.composing {
+        view_state.composing = false;
+        view_state.marked_text.clear();
+        view_state.handler.composition_end(&text);
+    } else if !view_state.in_key_down {
+        // such as from the character viewer
+        view_state.handler.composition_start();
+        view_state.handler.composition_end(&text);
+    }
+    // otherwise the text is typed by the key press
+}
+
+extern "C" fn character_index_for_point(_this: &mut Object, _: Sel, _point: NSPoint) -> NSUInteger {
+    NS_NOT_FOUND
+}
+
+extern "C" fn first_rect_for_character_range(
+    this: &mut Object,
+    _: Sel,
+    _range: NSRange,
+    _actual_range: *mut c_void,
+) -> NSRect {
+    let area = unsafe { view_state(this) }.ime_area;
+    let rect = NSRect::new(
+        NSPoint::new(area.x0, area.y0),
+        NSSize::new(area.width(), area.height()),
+    );
+    unsafe {
+        let window: id = msg_send![this, window];
+        if window == nil {
+            return rect;
+        }
+        let in_window: NSRect = msg_send![this, convertRect: rect toView: nil];
+        msg_send![window, convertRectToScreen: in_window]
+    }
+}
+
+extern "C" fn do_command_by_selector(_this: &mut Object, _: Sel, _command: Sel) {
+    // the key press is sent to the handler, which performs its command
 }
 
 extern "C" fn key_up(this: &mut Object, _: Sel, nsevent: id) {
-    let event = make_key_event(nsevent);
+    let event = make_key_event(nsevent, KeyState::Up);
     let view_state = unsafe {
         let view_state: *mut c_void = *this.get_ivar("viewState");
         &mut *(view_state as *mut ViewState)
     };
-    (*view_state).handler.key_up(event);
     view_state.last_mods = event.mods;
+    (*view_state).handler.key_up(event);
 }
 
 extern "C" fn mods_changed(this: &mut Object, _: Sel, nsevent: id) {
@@ -495,7 +1017,12 @@ extern "C" fn draw_rect(this: &mut Object, _: Sel, dirtyRect: NSRect) {
         let mut piet_ctx = Piet::new(&mut cairo_ctx);
         let view_state: *mut c_void = *this.get_ivar("viewState");
         let view_state = &mut *(view_state as *mut ViewState);
-        let anim = (*view_state).handler.paint(&mut piet_ctx);
+        // the view is flipped, so this is in our coordinates
+        let invalid_rect = Rect::from_origin_size(
+            (dirtyRect.origin.x, dirtyRect.origin.y),
+            (dirtyRect.size.width, dirtyRect.size.height),
+        );
+        let anim = (*view_state).handler.paint(&mut piet_ctx, invalid_rect);
         if let Err(e) = piet_ctx.finish() {
             error!("{}", e)
         }
@@ -526,6 +1053,9 @@ extern "C" fn run_idle(this: &mut Object, _: Sel) {
             IdleKind::Token(it) => {
                 view_state.handler.as_mut().idle(it);
             }
+            IdleKind::Timer(token, deadline) => {
+                timer::deliver(view_state.handler.as_mut(), token, deadline);
+            }
         }
     }
 }
@@ -536,19 +1066,6 @@ extern "C" fn redraw(this: &mut Object, _: Sel) {
     }
 }
 
-extern "C" fn handle_timer(this: &mut Object, _: Sel, timer: id) {
-    let view_state = unsafe {
-        let view_state: *mut c_void = *this.get_ivar("viewState");
-        &mut *(view_state as *mut ViewState)
-    };
-    let token = unsafe {
-        let user_info: id = msg_send![timer, userInfo];
-        msg_send![user_info, unsignedIntValue]
-    };
-
-    (*view_state).handler.timer(TimerToken::from_raw(token));
-}
-
 extern "C" fn handle_menu_item(this: &mut Object, _: Sel, item: id) {
     unsafe {
         let tag: isize = msg_send![item, tag];
@@ -576,6 +1093,18 @@ extern "C" fn window_did_become_key(this: &mut Object, _: Sel, _notification: id
     }
 }
 
+extern "C" fn accessibility_display_options_did_change(
+    this: &mut Object,
+    _: Sel,
+    _notification: id,
+) {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        (*view_state).handler.system_settings_changed();
+    }
+}
+
 extern "C" fn window_will_close(this: &mut Object, _: Sel, _window: id) {
     unsafe {
         let view_state: *mut c_void = *this.get_ivar("viewState");
@@ -595,7 +1124,21 @@ impl WindowHandle {
                 .autorelease();
             let notif_center: id = msg_send![notif_center_class, defaultCenter];
             let () = msg_send![notif_center, addObserver:*self.nsview.load() selector: sel!(windowDidBecomeKey:) name: notif_string object: window];
-            window.makeKeyAndOrderFront_(nil)
+            // and when the accessibility options (such as reduced motion) change;
+            // these notifications are posted to the workspace's notification center.
+            let notif_string = NSString::alloc(nil)
+                .init_str(NSWorkspaceAccessibilityDisplayOptionsDidChangeNotification)
+                .autorelease();
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let workspace_center: id = msg_send![workspace, notificationCenter];
+            let () = msg_send![workspace_center, addObserver:*self.nsview.load() selector: sel!(accessibilityDisplayOptionsDidChange:) name: notif_string object: nil];
+            // a popup leaves the focus where it is
+            let popup: BOOL = msg_send![window, isKindOfClass: class!(NSPanel)];
+            if popup == YES {
+                let () = msg_send![window, orderFront: nil];
+            } else {
+                window.makeKeyAndOrderFront_(nil)
+            }
         }
     }
 
@@ -615,6 +1158,79 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_size(&self, size: Size) {
+        let size = size_to_pixels(size, self.get_dpi() as f64);
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            window.setContentSize_(NSSize::new(size.width, size.height));
+        }
+    }
+
+    pub fn get_size(&self) -> Size {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let frame = NSView::frame(window.contentView());
+            let size = Size::new(frame.size.width, frame.size.height);
+            size_to_px(size, self.get_dpi() as f64)
+        }
+    }
+
+    pub fn set_position(&self, position: Point) {
+        let position = point_to_pixels(position, self.get_dpi() as f64);
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            set_top_left(window, position);
+        }
+    }
+
+    pub fn get_position(&self) -> Point {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let frame = NSWindow::frame(window);
+            // Cocoa's origin is the bottom left of the screen, and of the window
+            let top = main_screen_height() - (frame.origin.y + frame.size.height);
+            point_to_px(Point::new(frame.origin.x, top), self.get_dpi() as f64)
+        }
+    }
+
+    pub fn to_screen(&self, point: Point) -> Point {
+        unsafe {
+            let view = self.nsview.load();
+            let window: id = msg_send![*view, window];
+            if window == nil {
+                return point;
+            }
+            // the view is flipped, so its origin is at the top left
+            let in_view = NSPoint::new(point.x, point.y);
+            let in_window: NSPoint = msg_send![*view, convertPoint: in_view toView: nil];
+            let rect = NSRect::new(in_window, NSSize::new(0., 0.));
+            let on_screen: NSRect = msg_send![window, convertRectToScreen: rect];
+            let top = main_screen_height() - on_screen.origin.y;
+            point_to_px(Point::new(on_screen.origin.x, top), self.get_dpi() as f64)
+        }
+    }
+
+    pub fn set_window_state(&self, state: window::WindowState) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let is_zoomed: BOOL = msg_send![window, isZoomed];
+            let is_miniaturized: BOOL = msg_send![window, isMiniaturized];
+            match state {
+                window::WindowState::Maximized if is_zoomed == NO => window.zoom_(nil),
+                window::WindowState::Minimized => window.miniaturize_(nil),
+                window::WindowState::Restored => {
+                    if is_miniaturized == YES {
+                        window.deminiaturize_(nil);
+                    }
+                    if is_zoomed == YES {
+                        window.zoom_(nil);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
     // Request invalidation of the entire window contents.
     pub fn invalidate(&self) {
         unsafe {
@@ -623,6 +1239,17 @@ impl WindowHandle {
         }
     }
 
+    pub fn invalidate_rect(&self, rect: Rect) {
+        let rect = NSRect::new(
+            NSPoint::new(rect.x0, rect.y0),
+            NSSize::new(rect.width(), rect.height()),
+        );
+        unsafe {
+            // the view is flipped, so its origin is at the top left, like ours
+            let () = msg_send![*self.nsview.load(), setNeedsDisplayInRect: rect];
+        }
+    }
+
     pub fn set_cursor(&mut self, cursor: &Cursor) {
         unsafe {
             let nscursor = class!(NSCursor);
@@ -639,16 +1266,12 @@ impl WindowHandle {
         }
     }
 
-    pub fn request_timer(&self, deadline: std::time::Instant) -> TimerToken {
-        let ti = time_interval_from_deadline(deadline);
+    pub fn request_timer(&self, deadline: Instant) -> TimerToken {
         let token = TimerToken::next();
-        unsafe {
-            let nstimer = class!(NSTimer);
-            let nsnumber = class!(NSNumber);
-            let user_info: id = msg_send![nsnumber, numberWithUnsignedInteger: token.into_raw()];
-            let selector = sel!(handleTimer:);
-            let view = self.nsview.load();
-            let _: id = msg_send![nstimer, scheduledTimerWithTimeInterval: ti target: view selector: selector userInfo: user_info repeats: NO];
+        if let Some(idle) = self.get_idle_handle() {
+            timer::schedule(token, deadline, move |token, deadline| {
+                idle.add_timer(token, deadline)
+            });
         }
         token
     }
@@ -658,13 +1281,13 @@ impl WindowHandle {
     }
 
     pub fn open_file_sync(&mut self, options: FileDialogOptions) -> Option<FileInfo> {
-        dialog::get_file_dialog_path(FileDialogType::Open, options)
-            .map(|s| FileInfo { path: s.into() })
+        dialog::get_file_dialog_paths(FileDialogType::Open, options)
+            .and_then(|paths| FileInfo::from_paths(paths.into_iter().map(Into::into).collect()))
     }
 
     pub fn save_as_sync(&mut self, options: FileDialogOptions) -> Option<FileInfo> {
-        dialog::get_file_dialog_path(FileDialogType::Save, options)
-            .map(|s| FileInfo { path: s.into() })
+        dialog::get_file_dialog_paths(FileDialogType::Save, options)
+            .and_then(|paths| FileInfo::from_paths(paths.into_iter().map(Into::into).collect()))
     }
 
     /// Set the title for this menu.
@@ -676,11 +1299,43 @@ impl WindowHandle {
         }
     }
 
-    // TODO: Implement this
-    pub fn show_titlebar(&self, _show_titlebar: bool) {}
+    pub fn set_background_color(&self, color: Color) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            set_background_color(window, &color);
+        }
+    }
+
+    pub fn show_titlebar(&self, show_titlebar: bool) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let resizable = window
+                .styleMask()
+                .contains(NSWindowStyleMask::NSResizableWindowMask);
+            window.setStyleMask_(style_mask(show_titlebar, resizable));
+            set_titlebar_shown(window, show_titlebar);
+        }
+    }
+
+    pub fn resizable(&self, resizable: bool) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let mut mask = window.styleMask();
+            mask.set(NSWindowStyleMask::NSResizableWindowMask, resizable);
+            window.setStyleMask_(mask);
+        }
+    }
 
-    // TODO: Implement this
-    pub fn resizable(&self, _resizable: bool) {}
+    pub fn begin_move_drag(&self) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            // this is called while the press is being handled
+            let event: id = msg_send![NSApp(), currentEvent];
+            if event != nil {
+                let () = msg_send![window, performWindowDragWithEvent: event];
+            }
+        }
+    }
 
     pub fn set_menu(&self, menu: Menu) {
         unsafe {
@@ -709,6 +1364,10 @@ impl WindowHandle {
         }
     }
 
+    pub fn render_backend(&self) -> window::RenderBackend {
+        window::RenderBackend::Software
+    }
+
     /// Get the dpi of the window.
     ///
     /// TODO: we want to migrate this from dpi (with 96 as nominal) to a scale
@@ -717,6 +1376,205 @@ impl WindowHandle {
         // TODO: get actual dpi
         96.0
     }
+
+    pub fn get_scale(&self) -> Scale {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            backing_scale(window)
+        }
+    }
+
+    // TODO: this could be an NSView added as a subview of ours
+    pub fn create_child_surface(&self) -> Result<ChildSurface, Error> {
+        Err(Error::Other(
+            "child surfaces are not supported on macOS yet",
+        ))
+    }
+
+    /// The tree isn't exposed with NSAccessibility yet.
+    pub fn update_access_tree(&self, _tree: AccessNode, _events: &[AccessEvent]) {}
+
+    // the view already gets the events of a drag that starts in it, wherever
+    // the mouse goes
+    pub fn set_ime_cursor_area(&self, rect: Rect) {
+        unsafe {
+            let view = self.nsview.load();
+            if view.is_null() {
+                return;
+            }
+            // the input method asks for it with firstRectForCharacterRange
+            let view_state: *mut c_void = *(**view).get_ivar("viewState");
+            (*(view_state as *mut ViewState)).ime_area = rect;
+        }
+    }
+
+    pub fn set_mouse_capture(&self, _capture: bool) {}
+
+    pub fn capture_image(&self) -> Result<window::CapturedImage, Error> {
+        unsafe {
+            let view = self.nsview.load();
+            if view.is_null() {
+                return Err(Error::Other("the window has been closed"));
+            }
+            let window: id = msg_send![*view, window];
+            let number: NSInteger = msg_send![window, windowNumber];
+            let bounds: NSRect = msg_send![*view, bounds];
+            let scale = backing_scale(window);
+            let width = (bounds.size.width * scale.x()).round() as usize;
+            let height = (bounds.size.height * scale.y()).round() as usize;
+
+            let image = CGWindowListCreateImage(
+                CG_RECT_NULL,
+                K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW,
+                number as u32,
+                K_CG_WINDOW_IMAGE_BOUNDS_IGNORE_FRAMING | K_CG_WINDOW_IMAGE_BEST_RESOLUTION,
+            );
+            if image.is_null() {
+                return Err(Error::Other(
+                    "the window server could not capture the window",
+                ));
+            }
+            let image_size = NSSize::new(
+                CGImageGetWidth(image) as f64,
+                CGImageGetHeight(image) as f64,
+            );
+
+            let mut pixels = vec![0u8; width * height * 4];
+            let space = CGColorSpaceCreateDeviceRGB();
+            let context = CGBitmapContextCreate(
+                pixels.as_mut_ptr() as *mut c_void,
+                width,
+                height,
+                8,
+                width * 4,
+                space,
+                K_CG_IMAGE_ALPHA_PREMULTIPLIED_LAST | K_CG_BITMAP_BYTE_ORDER_32_BIG,
+            );
+            // the image is of the whole window; the content view is at its
+            // bottom, under the title bar, which is left out of the context
+            let rect = NSRect::new(NSPoint::new(0.0, 0.0), image_size);
+            CGContextDrawImage(context, rect, image);
+            CGContextRelease(context);
+            CGColorSpaceRelease(space);
+            CGImageRelease(image);
+
+            Ok(window::CapturedImage {
+                pixels,
+                width,
+                height,
+            })
+        }
+    }
+}
+
+// The parts of Core Graphics that are used to capture a window.
+const CG_RECT_NULL: NSRect = NSRect {
+    origin: NSPoint {
+        x: std::f64::INFINITY,
+        y: std::f64::INFINITY,
+    },
+    size: NSSize {
+        width: 0.0,
+        height: 0.0,
+    },
+};
+const K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW: u32 = 1 << 3;
+const K_CG_WINDOW_IMAGE_BOUNDS_IGNORE_FRAMING: u32 = 1 << 0;
+const K_CG_WINDOW_IMAGE_BEST_RESOLUTION: u32 = 1 << 3;
+const K_CG_IMAGE_ALPHA_PREMULTIPLIED_LAST: u32 = 1;
+const K_CG_BITMAP_BYTE_ORDER_32_BIG: u32 = 4 << 12;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGWindowListCreateImage(bounds: NSRect, options: u32, window: u32, image: u32) -> id;
+    fn CGImageGetWidth(image: id) -> usize;
+    fn CGImageGetHeight(image: id) -> usize;
+    fn CGImageRelease(image: id);
+    fn CGColorSpaceCreateDeviceRGB() -> id;
+    fn CGColorSpaceRelease(space: id);
+    fn CGBitmapContextCreate(
+        data: *mut c_void,
+        width: usize,
+        height: usize,
+        bits_per_component: usize,
+        bytes_per_row: usize,
+        space: id,
+        bitmap_info: u32,
+    ) -> id;
+    fn CGContextDrawImage(context: id, rect: NSRect, image: id);
+    fn CGContextRelease(context: id);
+}
+
+/// A child surface; these can't be created yet, so this is never constructed.
+pub struct ChildSurface(());
+
+impl ChildSurface {
+    pub fn set_frame(&self, _frame: Rect) {}
+
+    pub fn set_visible(&self, _visible: bool) {}
+
+    pub fn set_passthrough(&self, _passthrough: bool) {}
+}
+
+/// The height of the main screen, for flipping between top-left and
+/// Cocoa's bottom-left origin.
+pub(crate) unsafe fn main_screen_height() -> f64 {
+    let screen: id = msg_send![class!(NSScreen), mainScreen];
+    let frame: NSRect = msg_send![screen, frame];
+    frame.size.height
+}
+
+/// The level of a popup panel, which is above the other windows.
+const NS_POP_UP_MENU_WINDOW_LEVEL: NSInteger = 101;
+
+/// Move a window so that its top-left corner is at `position`, in screen
+/// coordinates with the origin at the top left.
+unsafe fn set_top_left(window: id, position: Point) {
+    let top_left = NSPoint::new(position.x, main_screen_height() - position.y);
+    window.setFrameTopLeftPoint_(top_left);
+}
+
+/// The style of a window, from whether it shows a titlebar and whether it can
+/// be resized.
+///
+/// A window without a titlebar keeps one, hidden behind its content, so that
+/// it keeps its rounded corners and can still become the key window.
+fn style_mask(show_titlebar: bool, resizable: bool) -> NSWindowStyleMask {
+    let mut mask = NSWindowStyleMask::NSTitledWindowMask
+        | NSWindowStyleMask::NSClosableWindowMask
+        | NSWindowStyleMask::NSMiniaturizableWindowMask;
+    mask.set(
+        NSWindowStyleMask::NSFullSizeContentViewWindowMask,
+        !show_titlebar,
+    );
+    mask.set(NSWindowStyleMask::NSResizableWindowMask, resizable);
+    mask
+}
+
+/// Show or hide the titlebar of a window, and the buttons on it.
+unsafe fn set_titlebar_shown(window: id, show_titlebar: bool) {
+    let hidden = if show_titlebar { NO } else { YES };
+    window.setTitlebarAppearsTransparent_(hidden);
+    window.setTitleVisibility_(if show_titlebar {
+        NSWindowTitleVisibility::NSWindowTitleVisible
+    } else {
+        NSWindowTitleVisibility::NSWindowTitleHidden
+    });
+    for &kind in &[
+        NSWindowButton::NSWindowCloseButton,
+        NSWindowButton::NSWindowMiniaturizeButton,
+        NSWindowButton::NSWindowZoomButton,
+    ] {
+        let button = window.standardWindowButton_(kind);
+        let () = msg_send![button, setHidden: hidden];
+    }
+}
+
+/// Set the color that the window shows behind its content.
+unsafe fn set_background_color(window: id, color: &Color) {
+    let (r, g, b, a) = color_components(color);
+    let color: id = msg_send![class!(NSColor), colorWithRed: r green: g blue: b alpha: a];
+    let () = msg_send![window, setBackgroundColor: color];
 }
 
 unsafe impl Send for IdleHandle {}
@@ -760,13 +1618,29 @@ impl IdleHandle {
             queue.push(IdleKind::Token(token));
         }
     }
+
+    /// Deliver a timer that is due, from the timer thread.
+    fn add_timer(&self, token: TimerToken, deadline: Instant) {
+        if let Some(queue) = self.idle_queue.upgrade() {
+            let mut queue = queue.lock().expect("queue lock");
+            if queue.is_empty() {
+                unsafe {
+                    let nsview = self.nsview.load();
+                    // Note: the nsview might be nil here if the window has been dropped, but that's ok.
+                    let () = msg_send!(*nsview, performSelectorOnMainThread: sel!(runIdle)
+                        withObject: nil waitUntilDone: NO);
+                }
+            }
+            queue.push(IdleKind::Timer(token, deadline));
+        }
+    }
 }
 
 /// Convert an `Instant` into an NSTimeInterval, i.e. a fractional number
 /// of seconds from now.
 ///
 /// This may lose some precision for multi-month durations.
-fn time_interval_from_deadline(deadline: std::time::Instant) -> f64 {
+pub(crate) fn time_interval_from_deadline(deadline: std::time::Instant) -> f64 {
     let now = Instant::now();
     if now >= deadline {
         0.0
@@ -778,7 +1652,7 @@ fn time_interval_from_deadline(deadline: std::time::Instant) -> f64 {
     }
 }
 
-fn make_key_event(event: id) -> KeyEvent {
+fn make_key_event(event: id, state: KeyState) -> KeyEvent {
     unsafe {
         let chars = event.characters();
         let slice = std::slice::from_raw_parts(chars.UTF8String() as *const _, chars.len());
@@ -791,29 +1665,36 @@ fn make_key_event(event: id) -> KeyEvent {
         );
         let unmodified_text = std::str::from_utf8_unchecked(slice);
 
-        let virtual_key = event.keyCode();
+        let code: Code = event.keyCode().into();
         let is_repeat: bool = msg_send!(event, isARepeat);
         let modifiers = event.modifierFlags();
         let modifiers = make_modifiers(modifiers);
-        KeyEvent::new(virtual_key, is_repeat, modifiers, text, unmodified_text)
+        let mut key_event = KeyEvent::new(state, code, is_repeat, modifiers, text, unmodified_text);
+        // AppKit composes dead keys itself, and puts the result in the
+        // characters of the next key; the dead key has no characters.
+        if text.is_empty() && code.is_printable() && !(modifiers.ctrl || modifiers.meta) {
+            key_event.key = KbKey::Dead;
+        }
+        key_event
     }
 }
 
 fn mods_changed_key_event(prev: KeyModifiers, event: id) -> (bool, KeyEvent) {
     unsafe {
-        let key_code: KeyCode = event.keyCode().into();
+        let key_code: Code = event.keyCode().into();
         let is_repeat = false;
         let modifiers = event.modifierFlags();
         let modifiers = make_modifiers(modifiers);
 
         let down = match key_code {
-            KeyCode::LeftShift | KeyCode::RightShift if prev.shift => false,
-            KeyCode::LeftAlt | KeyCode::RightAlt if prev.alt => false,
-            KeyCode::LeftControl | KeyCode::RightControl if prev.ctrl => false,
-            KeyCode::LeftMeta | KeyCode::RightMeta if prev.meta => false,
+            Code::LeftShift | Code::RightShift if prev.shift => false,
+            Code::LeftAlt | Code::RightAlt if prev.alt => false,
+            Code::LeftControl | Code::RightControl if prev.ctrl => false,
+            Code::LeftMeta | Code::RightMeta if prev.meta => false,
             _ => true,
         };
-        let event = KeyEvent::new(key_code, is_repeat, modifiers, "", "");
+        let state = if down { KeyState::Down } else { KeyState::Up };
+        let event = KeyEvent::new(state, key_code, is_repeat, modifiers, "", "");
         (down, event)
     }
 }
@@ -826,3 +1707,21 @@ fn make_modifiers(raw: NSEventModifierFlags) -> KeyModifiers {
         meta: raw.contains(NSEventModifierFlags::NSCommandKeyMask),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style_mask_follows_the_builder() {
+        let mask = style_mask(true, true);
+        assert!(mask.contains(NSWindowStyleMask::NSResizableWindowMask));
+        assert!(!mask.contains(NSWindowStyleMask::NSFullSizeContentViewWindowMask));
+
+        // the titlebar is only hidden, so the window can still be focused
+        let mask = style_mask(false, false);
+        assert!(mask.contains(NSWindowStyleMask::NSTitledWindowMask));
+        assert!(mask.contains(NSWindowStyleMask::NSFullSizeContentViewWindowMask));
+        assert!(!mask.contains(NSWindowStyleMask::NSResizableWindowMask));
+    }
+}
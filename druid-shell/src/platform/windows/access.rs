@@ -0,0 +1,867 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The accessibility tree, exposed with UI Automation.
+//!
+//! Each node of the tree is given to UI Automation as a provider, a COM
+//! object that answers questions about the node by looking it up, by id, in
+//! the latest version of the tree. Windows asks for the root provider with
+//! `WM_GETOBJECT`.
+
+#![allow(non_snake_case, non_upper_case_globals)]
+
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::ptr::null_mut;
+use std::rc::Rc;
+
+use log::warn;
+use winapi::ctypes::{c_int, c_void};
+use winapi::shared::guiddef::{IsEqualGUID, REFIID};
+use winapi::shared::minwindef::{BOOL, FALSE, LPARAM, LRESULT, TRUE, ULONG, WPARAM};
+use winapi::shared::windef::{HWND, POINT};
+use winapi::shared::winerror::{E_INVALIDARG, E_NOINTERFACE, E_POINTER, HRESULT, S_OK};
+use winapi::shared::wtypes::{BSTR, VARIANT_FALSE, VARIANT_TRUE, VARTYPE, VT_BOOL, VT_BSTR, VT_I4};
+use winapi::um::oaidl::{SAFEARRAY, VARIANT};
+use winapi::um::oleauto::{
+    SafeArrayAccessData, SafeArrayCreateVector, SafeArrayUnaccessData, SysAllocStringLen,
+};
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::um::winnt::{LONG, LPCWSTR};
+use winapi::um::winuser::ClientToScreen;
+use winapi::Interface;
+
+use crate::access::{AccessEvent, AccessNode, Role};
+use crate::common_util::point_to_pixels;
+use crate::kurbo::Point;
+
+type PropertyId = c_int;
+type PatternId = c_int;
+type EventId = c_int;
+type ControlTypeId = c_int;
+
+const UiaRootObjectId: LONG = -25;
+const UiaAppendRuntimeId: c_int = 3;
+
+const ProviderOptions_ServerSideProvider: c_int = 0x1;
+const ProviderOptions_UseComThreading: c_int = 0x20;
+
+const UIA_ValuePatternId: PatternId = 10002;
+const UIA_TogglePatternId: PatternId = 10015;
+
+const UIA_AutomationFocusChangedEventId: EventId = 20005;
+
+const UIA_ControlTypePropertyId: PropertyId = 30003;
+const UIA_NamePropertyId: PropertyId = 30005;
+const UIA_HasKeyboardFocusPropertyId: PropertyId = 30008;
+const UIA_IsKeyboardFocusablePropertyId: PropertyId = 30009;
+const UIA_AutomationIdPropertyId: PropertyId = 30011;
+const UIA_ValueValuePropertyId: PropertyId = 30045;
+const UIA_ToggleToggleStatePropertyId: PropertyId = 30086;
+
+const UIA_ButtonControlTypeId: ControlTypeId = 50000;
+const UIA_CheckBoxControlTypeId: ControlTypeId = 50002;
+const UIA_EditControlTypeId: ControlTypeId = 50004;
+const UIA_TextControlTypeId: ControlTypeId = 50020;
+const UIA_GroupControlTypeId: ControlTypeId = 50026;
+const UIA_WindowControlTypeId: ControlTypeId = 50032;
+
+const NavigateDirection_Parent: c_int = 0;
+const NavigateDirection_NextSibling: c_int = 1;
+const NavigateDirection_PreviousSibling: c_int = 2;
+const NavigateDirection_FirstChild: c_int = 3;
+const NavigateDirection_LastChild: c_int = 4;
+
+const ToggleState_Off: c_int = 0;
+const ToggleState_On: c_int = 1;
+
+const StructureChangeType_ChildrenInvalidated: c_int = 2;
+
+/// The node is no longer in the tree.
+const UIA_E_ELEMENTNOTAVAILABLE: HRESULT = 0x8004_0201_u32 as HRESULT;
+/// The node can't do what was asked; the tree is read-only.
+const UIA_E_NOTSUPPORTED: HRESULT = 0x8004_0204_u32 as HRESULT;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UiaRect {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+RIDL! {#[uuid(0xd6dd68d1, 0x86fd, 0x4332, 0x86, 0x66, 0x9a, 0xbe, 0xde, 0xa2, 0xd2, 0x4c)]
+interface IRawElementProviderSimple(IRawElementProviderSimpleVtbl): IUnknown(IUnknownVtbl) {
+    fn get_ProviderOptions(pRetVal: *mut c_int,) -> HRESULT,
+    fn GetPatternProvider(patternId: PatternId, pRetVal: *mut *mut IUnknown,) -> HRESULT,
+    fn GetPropertyValue(propertyId: PropertyId, pRetVal: *mut VARIANT,) -> HRESULT,
+    fn get_HostRawElementProvider(pRetVal: *mut *mut IRawElementProviderSimple,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0xf7063da8, 0x8359, 0x439c, 0x92, 0x97, 0xbb, 0xc5, 0x29, 0x9a, 0x7d, 0x87)]
+interface IRawElementProviderFragment(IRawElementProviderFragmentVtbl): IUnknown(IUnknownVtbl) {
+    fn Navigate(direction: c_int, pRetVal: *mut *mut IRawElementProviderFragment,) -> HRESULT,
+    fn GetRuntimeId(pRetVal: *mut *mut SAFEARRAY,) -> HRESULT,
+    fn get_BoundingRectangle(pRetVal: *mut UiaRect,) -> HRESULT,
+    fn GetEmbeddedFragmentRoots(pRetVal: *mut *mut SAFEARRAY,) -> HRESULT,
+    fn SetFocus() -> HRESULT,
+    fn get_FragmentRoot(pRetVal: *mut *mut IRawElementProviderFragmentRoot,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x620ce2a5, 0xab8f, 0x40a9, 0x86, 0xcb, 0xde, 0x3c, 0x75, 0x59, 0x9b, 0x58)]
+interface IRawElementProviderFragmentRoot(IRawElementProviderFragmentRootVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn ElementProviderFromPoint(
+        x: f64,
+        y: f64,
+        pRetVal: *mut *mut IRawElementProviderFragment,
+    ) -> HRESULT,
+    fn GetFocus(pRetVal: *mut *mut IRawElementProviderFragment,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x56d00bd0, 0xc4f4, 0x433c, 0xa8, 0x36, 0x1a, 0x52, 0xa5, 0x7e, 0x08, 0x92)]
+interface IToggleProvider(IToggleProviderVtbl): IUnknown(IUnknownVtbl) {
+    fn Toggle() -> HRESULT,
+    fn get_ToggleState(pRetVal: *mut c_int,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0xc7935180, 0x6fb3, 0x4201, 0xb1, 0x74, 0x7d, 0xf7, 0x3a, 0xdb, 0xf6, 0x4a)]
+interface IValueProvider(IValueProviderVtbl): IUnknown(IUnknownVtbl) {
+    fn SetValue(val: LPCWSTR,) -> HRESULT,
+    fn get_Value(pRetVal: *mut BSTR,) -> HRESULT,
+    fn get_IsReadOnly(pRetVal: *mut BOOL,) -> HRESULT,
+}}
+
+#[link(name = "uiautomationcore")]
+extern "system" {
+    fn UiaReturnRawElementProvider(
+        hwnd: HWND,
+        wParam: WPARAM,
+        lParam: LPARAM,
+        el: *mut IRawElementProviderSimple,
+    ) -> LRESULT;
+    fn UiaHostProviderFromHwnd(
+        hwnd: HWND,
+        ppProvider: *mut *mut IRawElementProviderSimple,
+    ) -> HRESULT;
+    fn UiaRaiseAutomationEvent(provider: *mut IRawElementProviderSimple, id: EventId) -> HRESULT;
+    fn UiaRaiseAutomationPropertyChangedEvent(
+        provider: *mut IRawElementProviderSimple,
+        id: PropertyId,
+        oldValue: VARIANT,
+        newValue: VARIANT,
+    ) -> HRESULT;
+    fn UiaRaiseStructureChangedEvent(
+        provider: *mut IRawElementProviderSimple,
+        structureChangeType: c_int,
+        pRuntimeId: *mut c_int,
+        cRuntimeIdLen: c_int,
+    ) -> HRESULT;
+    fn UiaClientsAreListening() -> BOOL;
+}
+
+/// The accessibility tree of a window, shared by the window and the
+/// providers of its nodes.
+pub(crate) struct AccessTree {
+    hwnd: Cell<HWND>,
+    dpi: Cell<f32>,
+    tree: RefCell<Option<AccessNode>>,
+    /// UI Automation has asked for the root provider.
+    active: Cell<bool>,
+}
+
+impl AccessTree {
+    pub(crate) fn new() -> Rc<AccessTree> {
+        Rc::new(AccessTree {
+            hwnd: Cell::new(null_mut()),
+            dpi: Cell::new(96.0),
+            tree: RefCell::new(None),
+            active: Cell::new(false),
+        })
+    }
+
+    /// Answer `WM_GETOBJECT`, returning whether UI Automation asked for the
+    /// tree for the first time, along with the result of the message.
+    pub(crate) fn get_object(
+        self: &Rc<Self>,
+        hwnd: HWND,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> Option<(bool, LRESULT)> {
+        if lparam as LONG != UiaRootObjectId {
+            return None;
+        }
+        self.hwnd.set(hwnd);
+        let activated = !self.active.replace(true);
+        unsafe {
+            let root = Provider::create(self.clone(), None);
+            let result = UiaReturnRawElementProvider(hwnd, wparam, lparam, root);
+            (*root).Release();
+            Some((activated, result))
+        }
+    }
+
+    /// Disconnect the providers, as the window is being destroyed.
+    pub(crate) fn disconnect(&self, hwnd: HWND) {
+        if self.active.get() {
+            unsafe {
+                UiaReturnRawElementProvider(hwnd, 0, 0, null_mut());
+            }
+        }
+        self.tree.replace(None);
+    }
+
+    /// Replace the tree, and raise UI Automation events for the changes.
+    pub(crate) fn update(self: &Rc<Self>, tree: AccessNode, events: &[AccessEvent], dpi: f32) {
+        self.dpi.set(dpi);
+        self.tree.replace(Some(tree));
+        if !self.active.get() || unsafe { UiaClientsAreListening() } == FALSE {
+            return;
+        }
+        for event in events {
+            let tree = self.tree.borrow();
+            let node = |id| tree.as_ref().and_then(|tree| tree.find(id));
+            let (id, property, value) = match *event {
+                AccessEvent::FocusChanged(id) => {
+                    if node(id).is_some() {
+                        drop(tree);
+                        self.raise(Some(id), |p| unsafe {
+                            UiaRaiseAutomationEvent(p, UIA_AutomationFocusChangedEventId)
+                        });
+                    }
+                    continue;
+                }
+                AccessEvent::StructureChanged => {
+                    drop(tree);
+                    self.raise(None, |p| unsafe {
+                        let kind = StructureChangeType_ChildrenInvalidated;
+                        UiaRaiseStructureChangedEvent(p, kind, null_mut(), 0)
+                    });
+                    continue;
+                }
+                AccessEvent::LabelChanged(id) => match node(id) {
+                    Some(node) => (id, UIA_NamePropertyId, bstr_variant(node.label.as_ref())),
+                    None => continue,
+                },
+                AccessEvent::ValueChanged(id) => match node(id) {
+                    Some(node) => (
+                        id,
+                        UIA_ValueValuePropertyId,
+                        bstr_variant(node.value.as_ref()),
+                    ),
+                    None => continue,
+                },
+                AccessEvent::ToggleChanged(id) => match node(id) {
+                    Some(node) => (id, UIA_ToggleToggleStatePropertyId, toggle_variant(node)),
+                    None => continue,
+                },
+            };
+            drop(tree);
+            self.raise(Some(id), |p| unsafe {
+                let old: VARIANT = mem::zeroed();
+                UiaRaiseAutomationPropertyChangedEvent(p, property, old, value)
+            });
+        }
+    }
+
+    fn raise(
+        self: &Rc<Self>,
+        id: Option<u64>,
+        f: impl FnOnce(*mut IRawElementProviderSimple) -> HRESULT,
+    ) {
+        unsafe {
+            let provider = Provider::create(self.clone(), id);
+            let hr = f(provider);
+            if hr != S_OK {
+                warn!("raising a UI Automation event failed: {:x}", hr);
+            }
+            (*provider).Release();
+        }
+    }
+
+    /// The node with this id, or the root for `None`.
+    fn with_node<R>(&self, id: Option<u64>, f: impl FnOnce(&AccessNode) -> R) -> Option<R> {
+        let tree = self.tree.borrow();
+        let tree = tree.as_ref()?;
+        match id {
+            Some(id) => tree.find(id).map(f),
+            None => Some(f(tree)),
+        }
+    }
+
+    /// The id of the node in `direction` from the node with this id, as the
+    /// id of a provider; `Some(None)` is the root.
+    fn navigate(&self, id: Option<u64>, direction: c_int) -> Option<Option<u64>> {
+        let tree = self.tree.borrow();
+        let tree = tree.as_ref()?;
+        let node = match id {
+            Some(id) => tree.find(id)?,
+            None => tree,
+        };
+        let siblings = |offset: isize| {
+            let parent = tree.parent_of(node.id)?;
+            let idx = parent.children.iter().position(|c| c.id == node.id)? as isize;
+            let sibling = parent.children.get((idx + offset) as usize)?;
+            Some(Some(sibling.id))
+        };
+        match direction {
+            NavigateDirection_Parent if id.is_some() => {
+                let parent = tree.parent_of(node.id)?;
+                Some(Some(parent.id).filter(|id| *id != tree.id))
+            }
+            NavigateDirection_NextSibling if id.is_some() => siblings(1),
+            NavigateDirection_PreviousSibling if id.is_some() => siblings(-1),
+            NavigateDirection_FirstChild => node.children.first().map(|c| Some(c.id)),
+            NavigateDirection_LastChild => node.children.last().map(|c| Some(c.id)),
+            _ => None,
+        }
+    }
+
+    /// `point`, in physical pixels on the screen, in px units relative to the
+    /// window's content area.
+    fn screen_to_px(&self, point: Point) -> Point {
+        let dpi = f64::from(self.dpi.get());
+        let origin = self.client_origin();
+        let scale = 96.0 / dpi;
+        Point::new((point.x - origin.x) * scale, (point.y - origin.y) * scale)
+    }
+
+    /// The top left of the content area, in physical pixels on the screen.
+    fn client_origin(&self) -> Point {
+        let mut origin = POINT { x: 0, y: 0 };
+        unsafe {
+            ClientToScreen(self.hwnd.get(), &mut origin);
+        }
+        Point::new(f64::from(origin.x), f64::from(origin.y))
+    }
+}
+
+fn control_type(role: Role) -> ControlTypeId {
+    match role {
+        Role::Window => UIA_WindowControlTypeId,
+        Role::Group => UIA_GroupControlTypeId,
+        Role::Label => UIA_TextControlTypeId,
+        Role::Button => UIA_ButtonControlTypeId,
+        Role::CheckBox => UIA_CheckBoxControlTypeId,
+        Role::TextBox => UIA_EditControlTypeId,
+    }
+}
+
+fn alloc_bstr(text: &str) -> BSTR {
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    unsafe { SysAllocStringLen(wide.as_ptr(), wide.len() as u32) }
+}
+
+fn set_variant(variant: &mut VARIANT, vt: VARTYPE, set: impl FnOnce(&mut VARIANT)) {
+    unsafe {
+        variant.n1.n2_mut().vt = vt;
+    }
+    set(variant);
+}
+
+fn bstr_variant(text: Option<&String>) -> VARIANT {
+    let mut variant: VARIANT = unsafe { mem::zeroed() };
+    let text = text.map(String::as_str).unwrap_or("");
+    set_variant(&mut variant, VT_BSTR as VARTYPE, |v| unsafe {
+        *v.n1.n2_mut().n3.bstrVal_mut() = alloc_bstr(text);
+    });
+    variant
+}
+
+fn i4_variant(value: c_int) -> VARIANT {
+    let mut variant: VARIANT = unsafe { mem::zeroed() };
+    set_variant(&mut variant, VT_I4 as VARTYPE, |v| unsafe {
+        *v.n1.n2_mut().n3.lVal_mut() = value;
+    });
+    variant
+}
+
+fn bool_variant(value: bool) -> VARIANT {
+    let mut variant: VARIANT = unsafe { mem::zeroed() };
+    set_variant(&mut variant, VT_BOOL as VARTYPE, |v| unsafe {
+        *v.n1.n2_mut().n3.boolVal_mut() = if value { VARIANT_TRUE } else { VARIANT_FALSE };
+    });
+    variant
+}
+
+fn toggle_variant(node: &AccessNode) -> VARIANT {
+    i4_variant(toggle_state(node))
+}
+
+fn toggle_state(node: &AccessNode) -> c_int {
+    if node.toggled == Some(true) {
+        ToggleState_On
+    } else {
+        ToggleState_Off
+    }
+}
+
+/// The provider of one node, or of the root, which is the window.
+///
+/// It implements each of its interfaces with a vtable pointer; an interface
+/// pointer points at its vtable pointer, and the methods find the provider
+/// from its offset.
+#[repr(C)]
+struct Provider {
+    simple: *const IRawElementProviderSimpleVtbl,
+    fragment: *const IRawElementProviderFragmentVtbl,
+    root: *const IRawElementProviderFragmentRootVtbl,
+    toggle: *const IToggleProviderVtbl,
+    value: *const IValueProviderVtbl,
+    refs: Cell<ULONG>,
+    tree: Rc<AccessTree>,
+    /// The node, or `None` for the root.
+    id: Option<u64>,
+}
+
+const SIMPLE: usize = 0;
+const FRAGMENT: usize = 1;
+const ROOT: usize = 2;
+const TOGGLE: usize = 3;
+const VALUE: usize = 4;
+
+impl Provider {
+    /// A new provider, as an `IRawElementProviderSimple` with one reference.
+    unsafe fn create(tree: Rc<AccessTree>, id: Option<u64>) -> *mut IRawElementProviderSimple {
+        let provider = Box::new(Provider {
+            simple: &SIMPLE_VTBL,
+            fragment: &FRAGMENT_VTBL,
+            root: &ROOT_VTBL,
+            toggle: &TOGGLE_VTBL,
+            value: &VALUE_VTBL,
+            refs: Cell::new(1),
+            tree,
+            id,
+        });
+        Box::into_raw(provider) as *mut IRawElementProviderSimple
+    }
+
+    /// The provider that `this`, a pointer to the interface at `offset`,
+    /// belongs to.
+    unsafe fn from_interface<'a, I>(this: *mut I, offset: usize) -> &'a Provider {
+        let base = (this as *mut *const c_void).sub(offset);
+        &*(base as *const Provider)
+    }
+
+    fn interface(&self, offset: usize) -> *mut c_void {
+        let base = self as *const Provider as *mut *const c_void;
+        unsafe { base.add(offset) as *mut c_void }
+    }
+
+    fn add_ref(&self) -> ULONG {
+        let refs = self.refs.get() + 1;
+        self.refs.set(refs);
+        refs
+    }
+
+    unsafe fn release(&self) -> ULONG {
+        let refs = self.refs.get() - 1;
+        self.refs.set(refs);
+        if refs == 0 {
+            drop(Box::from_raw(self as *const Provider as *mut Provider));
+        }
+        refs
+    }
+
+    unsafe fn query_interface(&self, riid: REFIID, out: *mut *mut c_void) -> HRESULT {
+        if out.is_null() {
+            return E_POINTER;
+        }
+        let riid = &*riid;
+        let is_root = self.id.is_none();
+        let toggles = self.tree.with_node(self.id, |node| node.toggled.is_some()) == Some(true);
+        let offset = if IsEqualGUID(riid, &IUnknown::uuidof())
+            || IsEqualGUID(riid, &IRawElementProviderSimple::uuidof())
+        {
+            Some(SIMPLE)
+        } else if IsEqualGUID(riid, &IRawElementProviderFragment::uuidof()) {
+            Some(FRAGMENT)
+        } else if is_root && IsEqualGUID(riid, &IRawElementProviderFragmentRoot::uuidof()) {
+            Some(ROOT)
+        } else if toggles && IsEqualGUID(riid, &IToggleProvider::uuidof()) {
+            Some(TOGGLE)
+        } else if IsEqualGUID(riid, &IValueProvider::uuidof()) && self.has_value() {
+            Some(VALUE)
+        } else {
+            None
+        };
+        match offset {
+            Some(offset) => {
+                self.add_ref();
+                *out = self.interface(offset);
+                S_OK
+            }
+            None => {
+                *out = null_mut();
+                E_NOINTERFACE
+            }
+        }
+    }
+
+    fn has_value(&self) -> bool {
+        self.tree.with_node(self.id, |node| node.value.is_some()) == Some(true)
+    }
+
+    /// A new reference to the provider of another node, as the interface at
+    /// `offset`.
+    fn other(&self, id: Option<u64>, offset: usize) -> *mut c_void {
+        unsafe {
+            let provider = Provider::create(self.tree.clone(), id) as *mut Provider;
+            (*provider).interface(offset)
+        }
+    }
+
+    unsafe fn property(&self, property: PropertyId, out: *mut VARIANT) -> HRESULT {
+        if out.is_null() {
+            return E_POINTER;
+        }
+        let value = self.tree.with_node(self.id, |node| match property {
+            UIA_ControlTypePropertyId => Some(i4_variant(control_type(node.role))),
+            UIA_NamePropertyId if node.label.is_some() => Some(bstr_variant(node.label.as_ref())),
+            UIA_HasKeyboardFocusPropertyId => Some(bool_variant(node.focused)),
+            UIA_IsKeyboardFocusablePropertyId => Some(bool_variant(node.focusable)),
+            UIA_AutomationIdPropertyId => Some(bstr_variant(Some(&node.id.to_string()))),
+            _ => None,
+        });
+        match value {
+            None => UIA_E_ELEMENTNOTAVAILABLE,
+            Some(value) => {
+                // properties that aren't set are left empty, and UI
+                // Automation uses its defaults
+                *out = value.unwrap_or_else(|| mem::zeroed());
+                S_OK
+            }
+        }
+    }
+}
+
+macro_rules! unknown_vtbl {
+    ($offset:expr) => {{
+        unsafe extern "system" fn query_interface(
+            this: *mut IUnknown,
+            riid: REFIID,
+            out: *mut *mut c_void,
+        ) -> HRESULT {
+            Provider::from_interface(this, $offset).query_interface(riid, out)
+        }
+        unsafe extern "system" fn add_ref(this: *mut IUnknown) -> ULONG {
+            Provider::from_interface(this, $offset).add_ref()
+        }
+        unsafe extern "system" fn release(this: *mut IUnknown) -> ULONG {
+            Provider::from_interface(this, $offset).release()
+        }
+        IUnknownVtbl {
+            QueryInterface: query_interface,
+            AddRef: add_ref,
+            Release: release,
+        }
+    }};
+}
+
+static SIMPLE_VTBL: IRawElementProviderSimpleVtbl = IRawElementProviderSimpleVtbl {
+    parent: unknown_vtbl!(SIMPLE),
+    get_ProviderOptions: simple_provider_options,
+    GetPatternProvider: simple_pattern_provider,
+    GetPropertyValue: simple_property_value,
+    get_HostRawElementProvider: simple_host_provider,
+};
+
+unsafe extern "system" fn simple_provider_options(
+    _this: *mut IRawElementProviderSimple,
+    out: *mut c_int,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    // with COM threading, the calls come on the window's thread
+    *out = ProviderOptions_ServerSideProvider | ProviderOptions_UseComThreading;
+    S_OK
+}
+
+unsafe extern "system" fn simple_pattern_provider(
+    this: *mut IRawElementProviderSimple,
+    pattern: PatternId,
+    out: *mut *mut IUnknown,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, SIMPLE);
+    let offset = match pattern {
+        UIA_TogglePatternId => Some(TOGGLE),
+        UIA_ValuePatternId => Some(VALUE),
+        _ => None,
+    };
+    *out = null_mut();
+    if let Some(offset) = offset {
+        let iid = match offset {
+            TOGGLE => IToggleProvider::uuidof(),
+            _ => IValueProvider::uuidof(),
+        };
+        let mut ptr = null_mut();
+        if provider.query_interface(&iid, &mut ptr) == S_OK {
+            *out = ptr as *mut IUnknown;
+        }
+    }
+    S_OK
+}
+
+unsafe extern "system" fn simple_property_value(
+    this: *mut IRawElementProviderSimple,
+    property: PropertyId,
+    out: *mut VARIANT,
+) -> HRESULT {
+    Provider::from_interface(this, SIMPLE).property(property, out)
+}
+
+unsafe extern "system" fn simple_host_provider(
+    this: *mut IRawElementProviderSimple,
+    out: *mut *mut IRawElementProviderSimple,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, SIMPLE);
+    *out = null_mut();
+    // the root is hosted by the window, which gives it the window's own
+    // properties, such as its title
+    match provider.id {
+        None => UiaHostProviderFromHwnd(provider.tree.hwnd.get(), out),
+        Some(_) => S_OK,
+    }
+}
+
+static FRAGMENT_VTBL: IRawElementProviderFragmentVtbl = IRawElementProviderFragmentVtbl {
+    parent: unknown_vtbl!(FRAGMENT),
+    Navigate: fragment_navigate,
+    GetRuntimeId: fragment_runtime_id,
+    get_BoundingRectangle: fragment_bounding_rectangle,
+    GetEmbeddedFragmentRoots: fragment_embedded_roots,
+    SetFocus: fragment_set_focus,
+    get_FragmentRoot: fragment_root,
+};
+
+unsafe extern "system" fn fragment_navigate(
+    this: *mut IRawElementProviderFragment,
+    direction: c_int,
+    out: *mut *mut IRawElementProviderFragment,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, FRAGMENT);
+    *out = match provider.tree.navigate(provider.id, direction) {
+        Some(id) => provider.other(id, FRAGMENT) as *mut IRawElementProviderFragment,
+        None => null_mut(),
+    };
+    S_OK
+}
+
+unsafe extern "system" fn fragment_runtime_id(
+    this: *mut IRawElementProviderFragment,
+    out: *mut *mut SAFEARRAY,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, FRAGMENT);
+    *out = null_mut();
+    // the root's id comes from the window that hosts it
+    let id = match provider.id {
+        Some(id) => id,
+        None => return S_OK,
+    };
+    let parts = [UiaAppendRuntimeId, id as c_int, (id >> 32) as c_int];
+    let array = SafeArrayCreateVector(VT_I4 as VARTYPE, 0, parts.len() as ULONG);
+    if array.is_null() {
+        return E_POINTER;
+    }
+    let mut elements = null_mut();
+    if SafeArrayAccessData(array, &mut elements) == S_OK {
+        let elements = elements as *mut c_int;
+        for (idx, part) in parts.iter().enumerate() {
+            *elements.add(idx) = *part;
+        }
+        SafeArrayUnaccessData(array);
+    }
+    *out = array;
+    S_OK
+}
+
+unsafe extern "system" fn fragment_bounding_rectangle(
+    this: *mut IRawElementProviderFragment,
+    out: *mut UiaRect,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, FRAGMENT);
+    let tree = &provider.tree;
+    match tree.with_node(provider.id, |node| node.rect) {
+        Some(rect) => {
+            let dpi = f64::from(tree.dpi.get());
+            let origin = tree.client_origin();
+            let top_left = point_to_pixels(rect.origin(), dpi);
+            let bottom_right = point_to_pixels(Point::new(rect.x1, rect.y1), dpi);
+            *out = UiaRect {
+                left: origin.x + top_left.x,
+                top: origin.y + top_left.y,
+                width: bottom_right.x - top_left.x,
+                height: bottom_right.y - top_left.y,
+            };
+            S_OK
+        }
+        None => UIA_E_ELEMENTNOTAVAILABLE,
+    }
+}
+
+unsafe extern "system" fn fragment_embedded_roots(
+    _this: *mut IRawElementProviderFragment,
+    out: *mut *mut SAFEARRAY,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    *out = null_mut();
+    S_OK
+}
+
+unsafe extern "system" fn fragment_set_focus(_this: *mut IRawElementProviderFragment) -> HRESULT {
+    UIA_E_NOTSUPPORTED
+}
+
+unsafe extern "system" fn fragment_root(
+    this: *mut IRawElementProviderFragment,
+    out: *mut *mut IRawElementProviderFragmentRoot,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, FRAGMENT);
+    *out = provider.other(None, ROOT) as *mut IRawElementProviderFragmentRoot;
+    S_OK
+}
+
+static ROOT_VTBL: IRawElementProviderFragmentRootVtbl = IRawElementProviderFragmentRootVtbl {
+    parent: unknown_vtbl!(ROOT),
+    ElementProviderFromPoint: root_provider_from_point,
+    GetFocus: root_focus,
+};
+
+unsafe extern "system" fn root_provider_from_point(
+    this: *mut IRawElementProviderFragmentRoot,
+    x: f64,
+    y: f64,
+    out: *mut *mut IRawElementProviderFragment,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, ROOT);
+    let tree = &provider.tree;
+    let point = tree.screen_to_px(Point::new(x, y));
+    let found = tree.with_node(None, |root| root.node_at(point).map(|node| node.id));
+    *out = match found {
+        Some(Some(id)) => {
+            let root = tree.with_node(None, |root| root.id);
+            let id = Some(id).filter(|id| Some(*id) != root);
+            provider.other(id, FRAGMENT) as *mut IRawElementProviderFragment
+        }
+        _ => null_mut(),
+    };
+    S_OK
+}
+
+unsafe extern "system" fn root_focus(
+    this: *mut IRawElementProviderFragmentRoot,
+    out: *mut *mut IRawElementProviderFragment,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, ROOT);
+    let focused = provider
+        .tree
+        .with_node(None, |root| root.focused().map(|node| node.id));
+    *out = match focused {
+        Some(Some(id)) => provider.other(Some(id), FRAGMENT) as *mut IRawElementProviderFragment,
+        _ => null_mut(),
+    };
+    S_OK
+}
+
+static TOGGLE_VTBL: IToggleProviderVtbl = IToggleProviderVtbl {
+    parent: unknown_vtbl!(TOGGLE),
+    Toggle: toggle_toggle,
+    get_ToggleState: toggle_state_of,
+};
+
+unsafe extern "system" fn toggle_toggle(_this: *mut IToggleProvider) -> HRESULT {
+    UIA_E_NOTSUPPORTED
+}
+
+unsafe extern "system" fn toggle_state_of(this: *mut IToggleProvider, out: *mut c_int) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, TOGGLE);
+    match provider.tree.with_node(provider.id, toggle_state) {
+        Some(state) => {
+            *out = state;
+            S_OK
+        }
+        None => UIA_E_ELEMENTNOTAVAILABLE,
+    }
+}
+
+static VALUE_VTBL: IValueProviderVtbl = IValueProviderVtbl {
+    parent: unknown_vtbl!(VALUE),
+    SetValue: value_set,
+    get_Value: value_get,
+    get_IsReadOnly: value_is_read_only,
+};
+
+unsafe extern "system" fn value_set(_this: *mut IValueProvider, _val: LPCWSTR) -> HRESULT {
+    UIA_E_NOTSUPPORTED
+}
+
+unsafe extern "system" fn value_get(this: *mut IValueProvider, out: *mut BSTR) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let provider = Provider::from_interface(this, VALUE);
+    let value = provider.tree.with_node(provider.id, |node| {
+        alloc_bstr(node.value.as_deref().unwrap_or(""))
+    });
+    match value {
+        Some(value) => {
+            *out = value;
+            S_OK
+        }
+        None => UIA_E_ELEMENTNOTAVAILABLE,
+    }
+}
+
+unsafe extern "system" fn value_is_read_only(
+    _this: *mut IValueProvider,
+    out: *mut BOOL,
+) -> HRESULT {
+    if out.is_null() {
+        return E_INVALIDARG;
+    }
+    // values are changed by the application, not by screen readers
+    *out = TRUE;
+    S_OK
+}
@@ -14,31 +14,69 @@
 
 //! Windows implementation of features at the application scope.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::mem;
 use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use winapi::shared::minwindef::HINSTANCE;
+use winapi::ctypes::c_void;
+use winapi::shared::basetsd::UINT_PTR;
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, HINSTANCE, TRUE, UINT};
 use winapi::shared::ntdef::LPCWSTR;
-use winapi::shared::windef::HCURSOR;
+use winapi::shared::windef::{HBRUSH, HCURSOR, HICON};
+use winapi::um::ole2::OleInitialize;
+use winapi::um::processthreadsapi::GetCurrentThreadId;
 use winapi::um::shellscalingapi::PROCESS_SYSTEM_DPI_AWARE;
 use winapi::um::wingdi::CreateSolidBrush;
 use winapi::um::winuser::{
-    DispatchMessageW, GetAncestor, GetMessageW, LoadIconW, PostQuitMessage, RegisterClassW,
-    TranslateAcceleratorW, TranslateMessage, GA_ROOT, IDI_APPLICATION, MSG, WNDCLASSW,
+    DispatchMessageW, GetAncestor, GetMessageW, KillTimer, LoadIconW, PeekMessageW,
+    PostQuitMessage, PostThreadMessageW, RegisterClassW, SetTimer, SystemParametersInfoW,
+    TranslateAcceleratorW, TranslateMessage, GA_ROOT, IDI_APPLICATION, MSG, PM_NOREMOVE,
+    SPI_GETCLIENTAREAANIMATION, WM_APP, WM_TIMER, WM_USER, WNDCLASSW,
 };
 
 use crate::application::AppHandler;
+use crate::window::{IdleToken, TimerToken};
 
 use super::accels;
 use super::clipboard::Clipboard;
-use super::util::{self, ToWide, CLASS_NAME, OPTIONAL_FUNCTIONS};
-use super::window::win_proc_dispatch;
+use super::util::{self, ToWide, CHILD_CLASS_NAME, CLASS_NAME, OPTIONAL_FUNCTIONS};
+use super::window::{child_win_proc, win_proc_dispatch};
+
+/// Posted to the main thread when tokens are added to an empty
+/// `AppIdleHandle` queue.
+///
+/// This is a thread message, so it never reaches a window procedure. Note that
+/// modal loops, such as while a window is being resized, drop thread messages.
+const DS_APP_IDLE: UINT = WM_APP + 1;
+
+thread_local! {
+    static APP_HANDLER: RefCell<Option<Box<dyn AppHandler>>> = RefCell::new(None);
+    static APP_IDLE_HANDLE: RefCell<Option<AppIdleHandle>> = RefCell::new(None);
+    /// The tokens of the running app-level timers, by timer id.
+    static APP_TIMERS: RefCell<HashMap<UINT_PTR, TimerToken>> = RefCell::new(HashMap::new());
+}
 
 pub struct Application;
 
+#[derive(Clone)]
+pub struct AppIdleHandle {
+    thread_id: DWORD,
+    queue: Arc<Mutex<Vec<IdleToken>>>,
+}
+
 impl Application {
-    pub fn new(_handler: Option<Box<dyn AppHandler>>) -> Application {
+    pub fn new(handler: Option<Box<dyn AppHandler>>) -> Application {
         Application::init();
+        let idle_handle = AppIdleHandle {
+            thread_id: unsafe { GetCurrentThreadId() },
+            queue: Arc::new(Mutex::new(Vec::new())),
+        };
+        APP_HANDLER.with(move |x| *x.borrow_mut() = handler);
+        APP_IDLE_HANDLE.with(move |x| *x.borrow_mut() = Some(idle_handle));
         Application
     }
 
@@ -52,6 +90,9 @@ impl Application {
                     return;
                 }
                 let mut msg: MSG = msg.assume_init();
+                if msg.hwnd.is_null() && handle_thread_message(&msg) {
+                    continue;
+                }
                 let accels = accels::find_accels(GetAncestor(msg.hwnd, GA_ROOT));
                 let translated = accels.map_or(false, |it| {
                     TranslateAcceleratorW(msg.hwnd, it.handle(), &mut msg) != 0
@@ -76,6 +117,20 @@ impl Application {
         }
 
         unsafe {
+            // drag and drop needs OLE, which initializes COM for the thread
+            OleInitialize(ptr::null_mut());
+
+            // make sure this thread has a message queue, so that messages
+            // posted before the run loop starts are not lost
+            let mut msg = mem::MaybeUninit::uninit();
+            PeekMessageW(
+                msg.as_mut_ptr(),
+                ptr::null_mut(),
+                WM_USER,
+                WM_USER,
+                PM_NOREMOVE,
+            );
+
             let class_name = CLASS_NAME.to_wide();
             let icon = LoadIconW(0 as HINSTANCE, IDI_APPLICATION);
             let brush = CreateSolidBrush(0xff_ff_ff);
@@ -95,6 +150,23 @@ impl Application {
             if class_atom == 0 {
                 panic!("Error registering class");
             }
+
+            let child_class_name = CHILD_CLASS_NAME.to_wide();
+            let child_wnd = WNDCLASSW {
+                style: 0,
+                lpfnWndProc: Some(child_win_proc),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hInstance: 0 as HINSTANCE,
+                hIcon: 0 as HICON,
+                hCursor: 0 as HCURSOR,
+                hbrBackground: 0 as HBRUSH,
+                lpszMenuName: 0 as LPCWSTR,
+                lpszClassName: child_class_name.as_ptr(),
+            };
+            if RegisterClassW(&child_wnd) == 0 {
+                panic!("Error registering child surface class");
+            }
         }
     }
 
@@ -104,6 +176,26 @@ impl Application {
         }
     }
 
+    pub fn get_idle_handle() -> Option<AppIdleHandle> {
+        APP_IDLE_HANDLE.with(|x| x.borrow().clone())
+    }
+
+    pub fn request_timer(deadline: Instant) -> TimerToken {
+        let elapse = deadline
+            .checked_duration_since(Instant::now())
+            .unwrap_or_default()
+            .as_millis();
+        let elapse = UINT::try_from(elapse).unwrap_or(UINT::max_value());
+        let id = unsafe { SetTimer(ptr::null_mut(), 0, elapse, None) };
+        if id == 0 {
+            log::warn!("failed to create an app timer");
+            return TimerToken::INVALID;
+        }
+        let token = TimerToken::next();
+        APP_TIMERS.with(|timers| timers.borrow_mut().insert(id, token));
+        token
+    }
+
     pub fn clipboard() -> Clipboard {
         Clipboard
     }
@@ -112,4 +204,76 @@ impl Application {
         //TODO ahem
         "en-US".into()
     }
+
+    pub fn prefers_reduced_motion() -> bool {
+        let mut animations: BOOL = TRUE;
+        let ok = unsafe {
+            SystemParametersInfoW(
+                SPI_GETCLIENTAREAANIMATION,
+                0,
+                &mut animations as *mut BOOL as *mut c_void,
+                0,
+            )
+        };
+        ok != FALSE && animations == FALSE
+    }
+}
+
+impl AppIdleHandle {
+    pub fn add_idle_token(&self, token: IdleToken) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.is_empty() {
+            unsafe {
+                PostThreadMessageW(self.thread_id, DS_APP_IDLE, 0, 0);
+            }
+        }
+        queue.push(token);
+    }
+}
+
+/// Handle a message that was posted to the thread rather than to a window.
+///
+/// Returns `true` if the message was for the app handler.
+fn handle_thread_message(msg: &MSG) -> bool {
+    match msg.message {
+        DS_APP_IDLE => {
+            let queue = APP_IDLE_HANDLE.with(|x| {
+                x.borrow()
+                    .as_ref()
+                    .map(|handle| mem::replace(&mut *handle.queue.lock().unwrap(), Vec::new()))
+                    .unwrap_or_default()
+            });
+            with_handler(|handler| {
+                for token in queue {
+                    handler.idle(token);
+                }
+            });
+            true
+        }
+        WM_TIMER => {
+            let token = APP_TIMERS.with(|timers| timers.borrow_mut().remove(&msg.wParam));
+            match token {
+                Some(token) => {
+                    unsafe {
+                        KillTimer(ptr::null_mut(), msg.wParam);
+                    }
+                    with_handler(|handler| handler.timer(token));
+                    true
+                }
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn with_handler(f: impl FnOnce(&mut dyn AppHandler)) {
+    APP_HANDLER.with(|handler| match handler.try_borrow_mut() {
+        Ok(mut handler) => {
+            if let Some(handler) = handler.as_mut() {
+                f(handler.as_mut());
+            }
+        }
+        Err(_) => log::warn!("app handler is already borrowed, dropping message"),
+    })
 }
@@ -22,8 +22,10 @@
 
 #![allow(non_upper_case_globals)]
 
+use winapi::ctypes::c_void;
+use winapi::shared::guiddef::REFIID;
 use winapi::shared::minwindef::*;
-use winapi::shared::ntdef::LPWSTR;
+use winapi::shared::ntdef::{HRESULT, LPWSTR, PCWSTR};
 use winapi::shared::windef::*;
 use winapi::shared::wtypesbase::*;
 use winapi::um::combaseapi::*;
@@ -33,6 +35,8 @@ use winapi::um::shtypes::COMDLG_FILTERSPEC;
 use winapi::Interface;
 use wio::com::ComPtr;
 
+use log::warn;
+
 use super::error::Error;
 use super::util::{as_result, FromWide, ToWide};
 use crate::dialog::{FileDialogOptions, FileDialogType, FileSpec};
@@ -47,6 +51,16 @@ DEFINE_GUID! {CLSID_FileOpenDialog,
 DEFINE_GUID! {CLSID_FileSaveDialog,
 0xC0B4_E2F3, 0xBA21, 0x4773, 0x8D, 0xBA, 0x33, 0x5E, 0xC9, 0x46, 0xEB, 0x8B}
 
+#[link(name = "shell32")]
+extern "system" {
+    fn SHCreateItemFromParsingName(
+        pszPath: PCWSTR,
+        pbc: *mut c_void,
+        riid: REFIID,
+        ppv: *mut *mut c_void,
+    ) -> HRESULT;
+}
+
 /// For each item in `spec`, returns a pair of utf16 strings representing the name
 /// and the filter spec.
 ///
@@ -71,13 +85,13 @@ fn normalize_extension(ext: &&str) -> String {
     format!("*.{}", ext.trim_start_matches('*').trim_start_matches('.'))
 }
 
-pub(crate) unsafe fn get_file_dialog_path(
+pub(crate) unsafe fn get_file_dialog_paths(
     hwnd_owner: HWND,
     ty: FileDialogType,
     options: FileDialogOptions,
-) -> Result<OsString, Error> {
+) -> Result<Vec<OsString>, Error> {
     let mut pfd: *mut IFileDialog = null_mut();
-    let (class, id) = match ty {
+    let (class, id) = match &ty {
         FileDialogType::Open => (&CLSID_FileOpenDialog, IFileOpenDialog::uuidof()),
         FileDialogType::Save => (&CLSID_FileSaveDialog, IFileSaveDialog::uuidof()),
     };
@@ -147,15 +161,60 @@ pub(crate) unsafe fn get_file_dialog_path(
 
     as_result(file_dialog.SetOptions(flags))?;
 
+    if let Some(title) = &options.title {
+        as_result(file_dialog.SetTitle(title.to_wide().as_ptr()))?;
+    }
+    if let Some(name) = &options.default_name {
+        as_result(file_dialog.SetFileName(name.to_wide().as_ptr()))?;
+    }
+    if let Some(directory) = &options.starting_directory {
+        let mut folder: *mut IShellItem = null_mut();
+        let result = as_result(SHCreateItemFromParsingName(
+            directory.as_os_str().to_wide().as_ptr(),
+            null_mut(),
+            &IShellItem::uuidof(),
+            &mut folder as *mut *mut IShellItem as *mut LPVOID,
+        ));
+        // a directory we can't open shouldn't keep the dialog from showing
+        match result {
+            Ok(()) => {
+                let folder = ComPtr::from_raw(folder);
+                as_result(file_dialog.SetFolder(folder.as_raw()))?;
+            }
+            Err(e) => warn!("can't open starting directory {:?}: {}", directory, e),
+        }
+    }
+
     // show the dialog
     as_result(file_dialog.Show(hwnd_owner))?;
+
+    // only an open dialog can have several results
+    if let (FileDialogType::Open, true) = (&ty, options.multi_selection) {
+        let open_dialog = file_dialog.cast::<IFileOpenDialog>().map_err(Error::Hr)?;
+        let mut results_ptr: *mut IShellItemArray = null_mut();
+        as_result(open_dialog.GetResults(&mut results_ptr))?;
+        let results = ComPtr::from_raw(results_ptr);
+        let mut count: DWORD = 0;
+        as_result(results.GetCount(&mut count))?;
+        let mut paths = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let mut item_ptr: *mut IShellItem = null_mut();
+            as_result(results.GetItemAt(i, &mut item_ptr))?;
+            paths.push(shell_item_path(ComPtr::from_raw(item_ptr))?);
+        }
+        return Ok(paths);
+    }
+
     let mut result_ptr: *mut IShellItem = null_mut();
     as_result(file_dialog.GetResult(&mut result_ptr))?;
-    let shell_item = ComPtr::from_raw(result_ptr);
+    Ok(vec![shell_item_path(ComPtr::from_raw(result_ptr))?])
+}
+
+/// The file system path of a shell item.
+unsafe fn shell_item_path(shell_item: ComPtr<IShellItem>) -> Result<OsString, Error> {
     let mut display_name: LPWSTR = null_mut();
     as_result(shell_item.GetDisplayName(SIGDN_FILESYSPATH, &mut display_name))?;
     let filename = display_name.to_os_string();
     CoTaskMemFree(display_name as LPVOID);
-
     Ok(filename)
 }
@@ -0,0 +1,353 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The window as an OLE drop target.
+//!
+//! OLE calls the drop target from its own loop while something is dragged
+//! over the window, which may be while the window procedure is running. The
+//! drop target reads what is dragged when it enters, and relays each call to
+//! the window procedure with `XI_DRAG`, so that the handler is only borrowed
+//! there.
+
+#![allow(non_snake_case)]
+
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::ptr::null_mut;
+
+use log::warn;
+use winapi::ctypes::c_void;
+use winapi::shared::guiddef::{IsEqualGUID, REFIID};
+use winapi::shared::minwindef::{DWORD, LPARAM, UINT, ULONG};
+use winapi::shared::windef::{HWND, POINT, POINTL};
+use winapi::shared::winerror::{E_NOINTERFACE, E_POINTER, HRESULT, S_OK};
+use winapi::shared::wtypes::DVASPECT_CONTENT;
+use winapi::um::objidl::{IDataObject, FORMATETC, TYMED_HGLOBAL};
+use winapi::um::ole2::{RegisterDragDrop, RevokeDragDrop};
+use winapi::um::oleidl::{DROPEFFECT_COPY, DROPEFFECT_MOVE, DROPEFFECT_NONE};
+use winapi::um::shellapi::{DragQueryFileW, HDROP};
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::um::winbase::{GlobalLock, GlobalUnlock};
+use winapi::um::winuser::{SendMessageW, CF_HDROP, CF_UNICODETEXT, MK_CONTROL, MK_SHIFT};
+use winapi::Interface;
+
+use super::util::FromWide;
+use crate::drag::DragPayload;
+use crate::keyboard::KeyModifiers;
+
+/// Message that relays a call to the drop target to the window procedure;
+/// `lparam` points at a `DragMessage`.
+pub(crate) const XI_DRAG: UINT = winapi::um::winuser::WM_USER + 3;
+
+/// Which call to the drop target a `DragMessage` relays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum DragKind {
+    Enter,
+    Over,
+    Leave,
+    Drop,
+}
+
+/// The alt key is down; winapi has this as 20, instead of 0x20.
+const MK_ALT: DWORD = 0x20;
+
+/// A call to the drop target, relayed with `XI_DRAG`.
+pub(crate) struct DragMessage {
+    pub(crate) kind: DragKind,
+    /// The position of the pointer, in screen coordinates.
+    pub(crate) point: POINT,
+    pub(crate) mods: KeyModifiers,
+    pub(crate) payload: DragPayload,
+    /// Set by the window procedure, if the handler accepts the drop.
+    pub(crate) accepted: bool,
+}
+
+// winapi declares the point of these methods as a pointer, but it is passed
+// by value.
+RIDL! {#[uuid(0x00000122, 0x0000, 0x0000, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46)]
+interface IDropTarget(IDropTargetVtbl): IUnknown(IUnknownVtbl) {
+    fn DragEnter(
+        pDataObj: *mut IDataObject,
+        grfKeyState: DWORD,
+        pt: POINTL,
+        pdwEffect: *mut DWORD,
+    ) -> HRESULT,
+    fn DragOver(grfKeyState: DWORD, pt: POINTL, pdwEffect: *mut DWORD,) -> HRESULT,
+    fn DragLeave() -> HRESULT,
+    fn Drop(
+        pDataObj: *mut IDataObject,
+        grfKeyState: DWORD,
+        pt: POINTL,
+        pdwEffect: *mut DWORD,
+    ) -> HRESULT,
+}}
+
+/// `STGMEDIUM`, which winapi declares with a pointer to its union instead
+/// of the union; each of its members is a pointer or a handle.
+#[repr(C)]
+struct StgMedium {
+    tymed: DWORD,
+    handle: *mut c_void,
+    pUnkForRelease: *mut IUnknown,
+}
+
+#[link(name = "ole32")]
+extern "system" {
+    fn ReleaseStgMedium(medium: *mut StgMedium);
+}
+
+/// Make the window a drop target.
+pub(crate) fn register(hwnd: HWND) {
+    unsafe {
+        let target = DropTarget::create(hwnd);
+        let hr = RegisterDragDrop(hwnd, target as *mut _);
+        if hr != S_OK {
+            warn!("RegisterDragDrop failed: 0x{:x}", hr);
+        }
+        // the window holds a reference of its own
+        (*target).Release();
+    }
+}
+
+/// Stop the window from being a drop target, as it is being destroyed.
+pub(crate) fn revoke(hwnd: HWND) {
+    unsafe {
+        RevokeDragDrop(hwnd);
+    }
+}
+
+#[repr(C)]
+struct DropTarget {
+    vtbl: *const IDropTargetVtbl,
+    refs: Cell<ULONG>,
+    hwnd: HWND,
+    /// What is being dragged, which is read when it enters the window.
+    payload: RefCell<DragPayload>,
+}
+
+impl DropTarget {
+    /// A new drop target, with one reference.
+    unsafe fn create(hwnd: HWND) -> *mut IDropTarget {
+        let target = Box::new(DropTarget {
+            vtbl: &DROP_TARGET_VTBL,
+            refs: Cell::new(1),
+            hwnd,
+            payload: Default::default(),
+        });
+        Box::into_raw(target) as *mut IDropTarget
+    }
+
+    unsafe fn from_interface<'a, I>(this: *mut I) -> &'a DropTarget {
+        &*(this as *const DropTarget)
+    }
+
+    /// Relay a call to the window procedure, returning whether the handler
+    /// accepts the drop.
+    fn relay(&self, kind: DragKind, keys: DWORD, pt: POINTL) -> bool {
+        let mut message = DragMessage {
+            kind,
+            point: POINT { x: pt.x, y: pt.y },
+            mods: KeyModifiers {
+                shift: keys & MK_SHIFT as DWORD != 0,
+                ctrl: keys & MK_CONTROL as DWORD != 0,
+                alt: keys & MK_ALT != 0,
+                ..Default::default()
+            },
+            payload: self.payload.borrow().clone(),
+            accepted: false,
+        };
+        unsafe {
+            SendMessageW(self.hwnd, XI_DRAG, 0, &mut message as *mut _ as LPARAM);
+        }
+        message.accepted
+    }
+}
+
+/// Set the drop effect, from the effects the source allows; a copy is
+/// preferred, as the handler doesn't say which it does.
+unsafe fn set_effect(effect: *mut DWORD, accepted: bool) {
+    if effect.is_null() {
+        return;
+    }
+    *effect = if !accepted {
+        DROPEFFECT_NONE
+    } else if *effect & DROPEFFECT_COPY != 0 {
+        DROPEFFECT_COPY
+    } else {
+        *effect & DROPEFFECT_MOVE
+    };
+}
+
+/// Read the files and the text in a data object.
+unsafe fn read_payload(data: *mut IDataObject) -> DragPayload {
+    let mut payload = DragPayload::new();
+    if data.is_null() {
+        return payload;
+    }
+    if let Some(handle) = get_hglobal(data, CF_HDROP) {
+        let drop = GlobalLock(handle.0) as HDROP;
+        if !drop.is_null() {
+            let count = DragQueryFileW(drop, 0xFFFF_FFFF, null_mut(), 0);
+            let files = (0..count).map(|i| {
+                let len = DragQueryFileW(drop, i, null_mut(), 0) as usize;
+                let mut buf = vec![0u16; len + 1];
+                DragQueryFileW(drop, i, buf.as_mut_ptr(), buf.len() as UINT);
+                PathBuf::from(buf[..len].to_os_string())
+            });
+            payload = payload.with_files(files.collect::<Vec<_>>());
+            GlobalUnlock(handle.0);
+        }
+    }
+    if let Some(handle) = get_hglobal(data, CF_UNICODETEXT) {
+        let text = GlobalLock(handle.0) as *mut u16;
+        if !text.is_null() {
+            if let Some(text) = text.from_wide() {
+                payload = payload.with_text(text);
+            }
+            GlobalUnlock(handle.0);
+        }
+    }
+    payload
+}
+
+/// A global memory handle to the data in `format`, which is released when
+/// it is dropped.
+struct HGlobal(*mut c_void, StgMedium);
+
+impl Drop for HGlobal {
+    fn drop(&mut self) {
+        unsafe { ReleaseStgMedium(&mut self.1) }
+    }
+}
+
+unsafe fn get_hglobal(data: *mut IDataObject, format: UINT) -> Option<HGlobal> {
+    let format = FORMATETC {
+        cfFormat: format as u16,
+        ptd: null_mut(),
+        dwAspect: DVASPECT_CONTENT,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL,
+    };
+    let mut medium = StgMedium {
+        tymed: 0,
+        handle: null_mut(),
+        pUnkForRelease: null_mut(),
+    };
+    let hr = (*data).GetData(&format, &mut medium as *mut StgMedium as *mut _);
+    if hr != S_OK {
+        return None;
+    }
+    if medium.tymed != TYMED_HGLOBAL || medium.handle.is_null() {
+        ReleaseStgMedium(&mut medium);
+        return None;
+    }
+    Some(HGlobal(medium.handle, medium))
+}
+
+static DROP_TARGET_VTBL: IDropTargetVtbl = IDropTargetVtbl {
+    parent: IUnknownVtbl {
+        QueryInterface: query_interface,
+        AddRef: add_ref,
+        Release: release,
+    },
+    DragEnter: drag_enter,
+    DragOver: drag_over,
+    DragLeave: drag_leave,
+    Drop: drag_drop,
+};
+
+unsafe extern "system" fn query_interface(
+    this: *mut IUnknown,
+    riid: REFIID,
+    out: *mut *mut c_void,
+) -> HRESULT {
+    if out.is_null() {
+        return E_POINTER;
+    }
+    let riid = &*riid;
+    if IsEqualGUID(riid, &IUnknown::uuidof()) || IsEqualGUID(riid, &IDropTarget::uuidof()) {
+        add_ref(this);
+        *out = this as *mut c_void;
+        S_OK
+    } else {
+        *out = null_mut();
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn add_ref(this: *mut IUnknown) -> ULONG {
+    let target = DropTarget::from_interface(this);
+    let refs = target.refs.get() + 1;
+    target.refs.set(refs);
+    refs
+}
+
+unsafe extern "system" fn release(this: *mut IUnknown) -> ULONG {
+    let target = DropTarget::from_interface(this);
+    let refs = target.refs.get() - 1;
+    target.refs.set(refs);
+    if refs == 0 {
+        drop(Box::from_raw(this as *mut DropTarget));
+    }
+    refs
+}
+
+unsafe extern "system" fn drag_enter(
+    this: *mut IDropTarget,
+    data: *mut IDataObject,
+    keys: DWORD,
+    pt: POINTL,
+    effect: *mut DWORD,
+) -> HRESULT {
+    let target = DropTarget::from_interface(this);
+    target.payload.replace(read_payload(data));
+    let accepted = target.relay(DragKind::Enter, keys, pt);
+    set_effect(effect, accepted);
+    S_OK
+}
+
+unsafe extern "system" fn drag_over(
+    this: *mut IDropTarget,
+    keys: DWORD,
+    pt: POINTL,
+    effect: *mut DWORD,
+) -> HRESULT {
+    let target = DropTarget::from_interface(this);
+    let accepted = target.relay(DragKind::Over, keys, pt);
+    set_effect(effect, accepted);
+    S_OK
+}
+
+unsafe extern "system" fn drag_leave(this: *mut IDropTarget) -> HRESULT {
+    let target = DropTarget::from_interface(this);
+    target.relay(DragKind::Leave, 0, POINTL { x: 0, y: 0 });
+    target.payload.replace(DragPayload::new());
+    S_OK
+}
+
+unsafe extern "system" fn drag_drop(
+    this: *mut IDropTarget,
+    data: *mut IDataObject,
+    keys: DWORD,
+    pt: POINTL,
+    effect: *mut DWORD,
+) -> HRESULT {
+    let target = DropTarget::from_interface(this);
+    // the data may have changed since it entered
+    target.payload.replace(read_payload(data));
+    let accepted = target.relay(DragKind::Drop, keys, pt);
+    target.payload.replace(DragPayload::new());
+    set_effect(effect, accepted);
+    S_OK
+}
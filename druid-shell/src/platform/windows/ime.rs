@@ -0,0 +1,141 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compositions with the Input Method Manager, which is how the text of an
+//! input method, such as for Japanese, reaches a window.
+
+use std::ops::Range;
+use std::ptr::null_mut;
+
+use winapi::ctypes::c_void;
+use winapi::shared::minwindef::{BOOL, DWORD};
+use winapi::shared::ntdef::LONG;
+use winapi::shared::windef::{HWND, POINT, RECT};
+use winapi::um::imm::{
+    ImmGetContext, ImmReleaseContext, ImmSetCompositionWindow, CFS_EXCLUDE, CFS_RECT,
+    COMPOSITIONFORM, HIMC,
+};
+
+use crate::common_util::utf16_to_utf8_offset;
+
+/// The composition string, in the `lparam` of `WM_IME_COMPOSITION`.
+pub(crate) const GCS_COMPSTR: DWORD = 0x0008;
+/// The position of the cursor in the composition string.
+pub(crate) const GCS_CURSORPOS: DWORD = 0x0080;
+/// The text that the composition typed.
+pub(crate) const GCS_RESULTSTR: DWORD = 0x0800;
+/// In the `lparam` of `WM_IME_SETCONTEXT`, that the input method should show
+/// the composition in a window of its own.
+pub(crate) const ISC_SHOWUICOMPOSITIONWINDOW: DWORD = 0x8000_0000;
+
+// winapi doesn't have these yet.
+#[repr(C)]
+#[allow(non_snake_case, clippy::upper_case_acronyms)]
+struct CANDIDATEFORM {
+    dwIndex: DWORD,
+    dwStyle: DWORD,
+    ptCurrentPos: POINT,
+    rcArea: RECT,
+}
+
+#[link(name = "imm32")]
+extern "system" {
+    fn ImmGetCompositionStringW(himc: HIMC, index: DWORD, buf: *mut c_void, len: DWORD) -> LONG;
+    fn ImmSetCandidateWindow(himc: HIMC, candidate: *mut CANDIDATEFORM) -> BOOL;
+}
+
+/// The input context of a window, which is released when this is dropped.
+pub(crate) struct ImeContext {
+    hwnd: HWND,
+    himc: HIMC,
+}
+
+impl ImeContext {
+    /// The input context of `hwnd`, if it has one.
+    pub(crate) fn get(hwnd: HWND) -> Option<ImeContext> {
+        let himc = unsafe { ImmGetContext(hwnd) };
+        if himc.is_null() {
+            None
+        } else {
+            Some(ImeContext { hwnd, himc })
+        }
+    }
+
+    /// One of the strings of the composition, such as `GCS_COMPSTR`.
+    pub(crate) fn string(&self, index: DWORD) -> Option<String> {
+        unsafe {
+            // the length is in bytes, and negative for an error
+            let len = ImmGetCompositionStringW(self.himc, index, null_mut(), 0);
+            if len < 0 {
+                return None;
+            }
+            let mut buf = vec![0u16; len as usize / 2];
+            let len = ImmGetCompositionStringW(
+                self.himc,
+                index,
+                buf.as_mut_ptr() as *mut c_void,
+                len as DWORD,
+            );
+            if len < 0 {
+                return None;
+            }
+            buf.truncate(len as usize / 2);
+            Some(String::from_utf16_lossy(&buf))
+        }
+    }
+
+    /// The composition string, and the range of its cursor in bytes.
+    pub(crate) fn composition(&self) -> Option<(String, Range<usize>)> {
+        let text = self.string(GCS_COMPSTR)?;
+        // the cursor is in UTF-16 code units
+        let units = unsafe { ImmGetCompositionStringW(self.himc, GCS_CURSORPOS, null_mut(), 0) };
+        let cursor = utf16_to_utf8_offset(&text, units.max(0) as usize);
+        Some((text, cursor..cursor))
+    }
+
+    /// Put the composition and the list of candidates by `area`, in pixels
+    /// in the client area; the candidates go below it, without covering it.
+    pub(crate) fn set_cursor_area(&self, area: RECT) {
+        let top_left = POINT {
+            x: area.left,
+            y: area.top,
+        };
+        let mut composition = COMPOSITIONFORM {
+            dwStyle: CFS_RECT,
+            ptCurrentPos: top_left,
+            rcArea: area,
+        };
+        let mut candidate = CANDIDATEFORM {
+            dwIndex: 0,
+            dwStyle: CFS_EXCLUDE,
+            ptCurrentPos: POINT {
+                x: area.left,
+                y: area.bottom,
+            },
+            rcArea: area,
+        };
+        unsafe {
+            ImmSetCompositionWindow(self.himc, &mut composition);
+            ImmSetCandidateWindow(self.himc, &mut candidate);
+        }
+    }
+}
+
+impl Drop for ImeContext {
+    fn drop(&mut self) {
+        unsafe {
+            ImmReleaseContext(self.hwnd, self.himc);
+        }
+    }
+}
@@ -17,7 +17,7 @@
 use win_vks::*;
 use winapi::um::winuser::*;
 
-use crate::keycodes::KeyCode;
+use crate::keycodes::Code;
 
 pub type RawKeyCode = i32;
 
@@ -67,7 +67,7 @@ mod win_vks {
 
 macro_rules! map_keys {
     ($( $id:ident => $code:path),*) => {
-        impl KeyCode {
+        impl Code {
             pub(crate) fn to_i32(&self) -> Option<i32> {
                 // Some keycode are same value as others
                 #[allow(unreachable_patterns)]
@@ -80,13 +80,13 @@ macro_rules! map_keys {
             }
         }
 
-        impl From<i32> for KeyCode {
-            fn from(src: i32) -> KeyCode {
+        impl From<i32> for Code {
+            fn from(src: i32) -> Code {
                 match src {
                     $(
                         $id => $code
                     ),*,
-                    other => KeyCode::Unknown(other),
+                    other => Code::Unknown(other),
                 }
             }
         }
@@ -94,121 +94,121 @@ macro_rules! map_keys {
 }
 
 map_keys! {
-    VK_LSHIFT => KeyCode::LeftShift,
-    VK_SHIFT => KeyCode::LeftShift,
-    VK_RSHIFT => KeyCode::RightShift,
-    VK_LCONTROL => KeyCode::LeftControl,
-    VK_CONTROL => KeyCode::LeftControl,
-    VK_RCONTROL => KeyCode::RightControl,
-    VK_LMENU => KeyCode::LeftAlt,
-    VK_MENU => KeyCode::LeftAlt,
-    VK_RMENU => KeyCode::RightAlt,
-    VK_OEM_PLUS => KeyCode::Equals,
-    VK_OEM_COMMA => KeyCode::Comma,
-    VK_OEM_MINUS => KeyCode::Minus,
-    VK_OEM_PERIOD => KeyCode::Period,
-    VK_OEM_1 => KeyCode::Semicolon,
-    VK_OEM_2 => KeyCode::Slash,
-    VK_OEM_3 => KeyCode::Backtick,
-    VK_OEM_4 => KeyCode::LeftBracket,
-    VK_OEM_5 => KeyCode::Backslash,
-    VK_OEM_6 => KeyCode::RightBracket,
-    VK_OEM_7 => KeyCode::Quote,
-    VK_BACK => KeyCode::Backspace,
-    VK_TAB => KeyCode::Tab,
-    VK_RETURN => KeyCode::Return,
-    VK_PAUSE => KeyCode::Pause,
-    VK_ESCAPE => KeyCode::Escape,
-    VK_SPACE => KeyCode::Space,
-    VK_PRIOR => KeyCode::PageUp,
-    VK_NEXT => KeyCode::PageDown,
-    VK_END => KeyCode::End,
-    VK_HOME => KeyCode::Home,
-    VK_LEFT => KeyCode::ArrowLeft,
-    VK_UP => KeyCode::ArrowUp,
-    VK_RIGHT => KeyCode::ArrowRight,
-    VK_DOWN => KeyCode::ArrowDown,
-    VK_SNAPSHOT => KeyCode::PrintScreen,
-    VK_INSERT => KeyCode::Insert,
-    VK_DELETE => KeyCode::Delete,
-    VK_CAPITAL => KeyCode::CapsLock,
-    VK_NUMLOCK => KeyCode::NumLock,
-    VK_SCROLL => KeyCode::ScrollLock,
-    VK_0 => KeyCode::Key0,
-    VK_1 => KeyCode::Key1,
-    VK_2 => KeyCode::Key2,
-    VK_3 => KeyCode::Key3,
-    VK_4 => KeyCode::Key4,
-    VK_5 => KeyCode::Key5,
-    VK_6 => KeyCode::Key6,
-    VK_7 => KeyCode::Key7,
-    VK_8 => KeyCode::Key8,
-    VK_9 => KeyCode::Key9,
-    VK_A => KeyCode::KeyA,
-    VK_B => KeyCode::KeyB,
-    VK_C => KeyCode::KeyC,
-    VK_D => KeyCode::KeyD,
-    VK_E => KeyCode::KeyE,
-    VK_F => KeyCode::KeyF,
-    VK_G => KeyCode::KeyG,
-    VK_H => KeyCode::KeyH,
-    VK_I => KeyCode::KeyI,
-    VK_J => KeyCode::KeyJ,
-    VK_K => KeyCode::KeyK,
-    VK_L => KeyCode::KeyL,
-    VK_M => KeyCode::KeyM,
-    VK_N => KeyCode::KeyN,
-    VK_O => KeyCode::KeyO,
-    VK_P => KeyCode::KeyP,
-    VK_Q => KeyCode::KeyQ,
-    VK_R => KeyCode::KeyR,
-    VK_S => KeyCode::KeyS,
-    VK_T => KeyCode::KeyT,
-    VK_U => KeyCode::KeyU,
-    VK_V => KeyCode::KeyV,
-    VK_W => KeyCode::KeyW,
-    VK_X => KeyCode::KeyX,
-    VK_Y => KeyCode::KeyY,
-    VK_Z => KeyCode::KeyZ,
-    VK_NUMPAD0 => KeyCode::Numpad0,
-    VK_NUMPAD1 => KeyCode::Numpad1,
-    VK_NUMPAD2 => KeyCode::Numpad2,
-    VK_NUMPAD3 => KeyCode::Numpad3,
-    VK_NUMPAD4 => KeyCode::Numpad4,
-    VK_NUMPAD5 => KeyCode::Numpad5,
-    VK_NUMPAD6 => KeyCode::Numpad6,
-    VK_NUMPAD7 => KeyCode::Numpad7,
-    VK_NUMPAD8 => KeyCode::Numpad8,
-    VK_NUMPAD9 => KeyCode::Numpad9,
-    VK_MULTIPLY => KeyCode::NumpadMultiply,
-    VK_ADD => KeyCode::NumpadAdd,
-    VK_SUBTRACT => KeyCode::NumpadSubtract,
-    VK_DECIMAL => KeyCode::NumpadDecimal,
-    VK_DIVIDE => KeyCode::NumpadDivide,
-    VK_F1 => KeyCode::F1,
-    VK_F2 => KeyCode::F2,
-    VK_F3 => KeyCode::F3,
-    VK_F4 => KeyCode::F4,
-    VK_F5 => KeyCode::F5,
-    VK_F6 => KeyCode::F6,
-    VK_F7 => KeyCode::F7,
-    VK_F8 => KeyCode::F8,
-    VK_F9 => KeyCode::F9,
-    VK_F10 => KeyCode::F10,
-    VK_F11 => KeyCode::F11,
-    VK_F12 => KeyCode::F12
-    //VK_F13 => KeyCode::F13,
-    //VK_F14 => KeyCode::F14,
-    //VK_F15 => KeyCode::F15,
-    //VK_F16 => KeyCode::F16,
-    //VK_F17 => KeyCode::F17,
-    //VK_F18 => KeyCode::F18,
-    //VK_F19 => KeyCode::F19,
-    //VK_F20 => KeyCode::F20,
-    //VK_F21 => KeyCode::F21,
-    //VK_F22 => KeyCode::F22,
-    //VK_F23 => KeyCode::F23,
-    //VK_F24 => KeyCode::F24,
+    VK_LSHIFT => Code::LeftShift,
+    VK_SHIFT => Code::LeftShift,
+    VK_RSHIFT => Code::RightShift,
+    VK_LCONTROL => Code::LeftControl,
+    VK_CONTROL => Code::LeftControl,
+    VK_RCONTROL => Code::RightControl,
+    VK_LMENU => Code::LeftAlt,
+    VK_MENU => Code::LeftAlt,
+    VK_RMENU => Code::RightAlt,
+    VK_OEM_PLUS => Code::Equals,
+    VK_OEM_COMMA => Code::Comma,
+    VK_OEM_MINUS => Code::Minus,
+    VK_OEM_PERIOD => Code::Period,
+    VK_OEM_1 => Code::Semicolon,
+    VK_OEM_2 => Code::Slash,
+    VK_OEM_3 => Code::Backtick,
+    VK_OEM_4 => Code::LeftBracket,
+    VK_OEM_5 => Code::Backslash,
+    VK_OEM_6 => Code::RightBracket,
+    VK_OEM_7 => Code::Quote,
+    VK_BACK => Code::Backspace,
+    VK_TAB => Code::Tab,
+    VK_RETURN => Code::Return,
+    VK_PAUSE => Code::Pause,
+    VK_ESCAPE => Code::Escape,
+    VK_SPACE => Code::Space,
+    VK_PRIOR => Code::PageUp,
+    VK_NEXT => Code::PageDown,
+    VK_END => Code::End,
+    VK_HOME => Code::Home,
+    VK_LEFT => Code::ArrowLeft,
+    VK_UP => Code::ArrowUp,
+    VK_RIGHT => Code::ArrowRight,
+    VK_DOWN => Code::ArrowDown,
+    VK_SNAPSHOT => Code::PrintScreen,
+    VK_INSERT => Code::Insert,
+    VK_DELETE => Code::Delete,
+    VK_CAPITAL => Code::CapsLock,
+    VK_NUMLOCK => Code::NumLock,
+    VK_SCROLL => Code::ScrollLock,
+    VK_0 => Code::Key0,
+    VK_1 => Code::Key1,
+    VK_2 => Code::Key2,
+    VK_3 => Code::Key3,
+    VK_4 => Code::Key4,
+    VK_5 => Code::Key5,
+    VK_6 => Code::Key6,
+    VK_7 => Code::Key7,
+    VK_8 => Code::Key8,
+    VK_9 => Code::Key9,
+    VK_A => Code::KeyA,
+    VK_B => Code::KeyB,
+    VK_C => Code::KeyC,
+    VK_D => Code::KeyD,
+    VK_E => Code::KeyE,
+    VK_F => Code::KeyF,
+    VK_G => Code::KeyG,
+    VK_H => Code::KeyH,
+    VK_I => Code::KeyI,
+    VK_J => Code::KeyJ,
+    VK_K => Code::KeyK,
+    VK_L => Code::KeyL,
+    VK_M => Code::KeyM,
+    VK_N => Code::KeyN,
+    VK_O => Code::KeyO,
+    VK_P => Code::KeyP,
+    VK_Q => Code::KeyQ,
+    VK_R => Code::KeyR,
+    VK_S => Code::KeyS,
+    VK_T => Code::KeyT,
+    VK_U => Code::KeyU,
+    VK_V => Code::KeyV,
+    VK_W => Code::KeyW,
+    VK_X => Code::KeyX,
+    VK_Y => Code::KeyY,
+    VK_Z => Code::KeyZ,
+    VK_NUMPAD0 => Code::Numpad0,
+    VK_NUMPAD1 => Code::Numpad1,
+    VK_NUMPAD2 => Code::Numpad2,
+    VK_NUMPAD3 => Code::Numpad3,
+    VK_NUMPAD4 => Code::Numpad4,
+    VK_NUMPAD5 => Code::Numpad5,
+    VK_NUMPAD6 => Code::Numpad6,
+    VK_NUMPAD7 => Code::Numpad7,
+    VK_NUMPAD8 => Code::Numpad8,
+    VK_NUMPAD9 => Code::Numpad9,
+    VK_MULTIPLY => Code::NumpadMultiply,
+    VK_ADD => Code::NumpadAdd,
+    VK_SUBTRACT => Code::NumpadSubtract,
+    VK_DECIMAL => Code::NumpadDecimal,
+    VK_DIVIDE => Code::NumpadDivide,
+    VK_F1 => Code::F1,
+    VK_F2 => Code::F2,
+    VK_F3 => Code::F3,
+    VK_F4 => Code::F4,
+    VK_F5 => Code::F5,
+    VK_F6 => Code::F6,
+    VK_F7 => Code::F7,
+    VK_F8 => Code::F8,
+    VK_F9 => Code::F9,
+    VK_F10 => Code::F10,
+    VK_F11 => Code::F11,
+    VK_F12 => Code::F12
+    //VK_F13 => Code::F13,
+    //VK_F14 => Code::F14,
+    //VK_F15 => Code::F15,
+    //VK_F16 => Code::F16,
+    //VK_F17 => Code::F17,
+    //VK_F18 => Code::F18,
+    //VK_F19 => Code::F19,
+    //VK_F20 => Code::F20,
+    //VK_F21 => Code::F21,
+    //VK_F22 => Code::F22,
+    //VK_F23 => Code::F23,
+    //VK_F24 => Code::F24,
 }
 
 #[cfg(test)]
@@ -217,8 +217,8 @@ mod tests {
 
     #[test]
     fn win_vk() {
-        assert_eq!(KeyCode::from(0x4F_i32), KeyCode::KeyO);
+        assert_eq!(Code::from(0x4F_i32), Code::KeyO);
         // VK_ZOOM
-        assert_eq!(KeyCode::from(0xFB_i32), KeyCode::Unknown(251));
+        assert_eq!(Code::from(0xFB_i32), Code::Unknown(251));
     }
 }
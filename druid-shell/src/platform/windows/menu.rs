@@ -137,7 +137,7 @@ impl Menu {
 
 fn convert_hotkey(id: u32, key: &HotKey) -> Option<ACCEL> {
     let mut virt_key = FVIRTKEY;
-    let key_mods: KeyModifiers = key.mods.into();
+    let key_mods: KeyModifiers = key.raw_mods().into();
     if key_mods.ctrl {
         virt_key |= FCONTROL;
     }
@@ -148,8 +148,9 @@ fn convert_hotkey(id: u32, key: &HotKey) -> Option<ACCEL> {
         virt_key |= FSHIFT;
     }
 
-    let raw_key = match key.key {
+    let raw_key = match &key.key {
         KeyCompare::Code(code) => code.to_i32()?,
+        KeyCompare::Key(named) => named.code()?.to_i32()?,
         KeyCompare::Text(text) => {
             // See https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-vkkeyscana
             let wchar = match text.encode_utf16().next() {
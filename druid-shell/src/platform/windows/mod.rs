@@ -15,15 +15,18 @@
 //! Windows implementation of druid-shell.
 
 mod accels;
+mod access;
 pub mod application;
 pub mod clipboard;
 pub mod dcomp;
 pub mod dialog;
+mod drop_target;
 pub mod error;
+mod ime;
 pub mod keycodes;
 pub mod menu;
 pub mod paint;
-mod timers;
+pub mod screen;
 pub mod util;
 pub mod window;
 
@@ -51,7 +54,7 @@ use winapi::shared::windef::HWND;
 use winapi::shared::winerror::{HRESULT, SUCCEEDED};
 use winapi::um::d2d1::{
     ID2D1HwndRenderTarget, ID2D1RenderTarget, D2D1_HWND_RENDER_TARGET_PROPERTIES,
-    D2D1_RENDER_TARGET_PROPERTIES, D2D1_SIZE_U,
+    D2D1_RENDER_TARGET_PROPERTIES, D2D1_RENDER_TARGET_TYPE_SOFTWARE, D2D1_SIZE_U,
 };
 use winapi::um::dcommon::D2D1_PIXEL_FORMAT;
 use wio::com::ComPtr;
@@ -67,11 +70,15 @@ impl HwndRenderTarget {
         hwnd: HWND,
         width: u32,
         height: u32,
+        software: bool,
     ) -> Result<Self, Error> {
         // hardcode
-        // - RenderTargetType::Default
+        // - RenderTargetType::Default, unless software rendering is requested
         // - AlphaMode::Unknown
-        let rt_props = DEFAULT_PROPS;
+        let mut rt_props = DEFAULT_PROPS;
+        if software {
+            rt_props._type = D2D1_RENDER_TARGET_TYPE_SOFTWARE;
+        }
         let mut hwnd_props = DEFAULT_HWND_PROPS;
 
         hwnd_props.hwnd = hwnd;
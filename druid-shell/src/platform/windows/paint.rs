@@ -20,7 +20,7 @@
 //! invalidation and low-latency frame timing.
 
 use std::mem;
-use std::ptr::null_mut;
+use std::ptr::{self, null_mut};
 
 use log::{error, warn};
 
@@ -30,14 +30,23 @@ use winapi::shared::dxgi1_2::*;
 use winapi::shared::dxgiformat::*;
 use winapi::shared::windef::*;
 use winapi::shared::winerror::*;
+use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx, CoUninitialize};
 use winapi::um::d2d1::*;
+use winapi::um::d2d1_1::ID2D1DeviceContext;
 use winapi::um::dcommon::*;
+use winapi::um::objbase::COINIT_APARTMENTTHREADED;
+use winapi::um::wincodec::*;
 use winapi::um::winuser::*;
 use winapi::Interface;
+use wio::com::ComPtr;
 
 use piet_common::d2d::D2DFactory;
+use piet_common::dwrite::DwriteFactory;
+use piet_common::{Piet, RenderContext};
 
 use crate::platform::windows::{DeviceContext, DxgiSurfaceRenderTarget, HwndRenderTarget};
+use crate::window::{CapturedImage, RenderBackend};
 
 use super::error::Error;
 use super::util::as_result;
@@ -45,6 +54,7 @@ use super::util::as_result;
 pub(crate) unsafe fn create_render_target(
     d2d_factory: &D2DFactory,
     hwnd: HWND,
+    backend: RenderBackend,
 ) -> Result<DeviceContext, Error> {
     let mut rect: RECT = mem::zeroed();
     if GetClientRect(hwnd, &mut rect) == 0 {
@@ -53,7 +63,8 @@ pub(crate) unsafe fn create_render_target(
     } else {
         let width = (rect.right - rect.left) as u32;
         let height = (rect.bottom - rect.top) as u32;
-        let res = HwndRenderTarget::create(d2d_factory, hwnd, width, height);
+        let software = backend == RenderBackend::Software;
+        let res = HwndRenderTarget::create(d2d_factory, hwnd, width, height, software);
 
         if let Err(ref e) = res {
             error!("Creating hwnd render target failed: {:?}", e);
@@ -65,11 +76,15 @@ pub(crate) unsafe fn create_render_target(
 
 /// Create a render target from a DXGI swapchain.
 ///
+/// If `transparent` is set, the alpha of what is drawn is kept, so that the
+/// window shows through where it isn't opaque.
+///
 /// TODO: probably want to create a DeviceContext, it's more flexible.
 pub(crate) unsafe fn create_render_target_dxgi(
     d2d_factory: &D2DFactory,
     swap_chain: *mut IDXGISwapChain1,
     dpi: f32,
+    transparent: bool,
 ) -> Result<DxgiSurfaceRenderTarget, Error> {
     let mut buffer: *mut IDXGISurface = null_mut();
     as_result((*swap_chain).GetBuffer(
@@ -81,7 +96,11 @@ pub(crate) unsafe fn create_render_target_dxgi(
         _type: D2D1_RENDER_TARGET_TYPE_DEFAULT,
         pixelFormat: D2D1_PIXEL_FORMAT {
             format: DXGI_FORMAT_B8G8R8A8_UNORM,
-            alphaMode: D2D1_ALPHA_MODE_IGNORE,
+            alphaMode: if transparent {
+                D2D1_ALPHA_MODE_PREMULTIPLIED
+            } else {
+                D2D1_ALPHA_MODE_IGNORE
+            },
         },
         dpiX: dpi,
         dpiY: dpi,
@@ -101,6 +120,104 @@ pub(crate) unsafe fn create_render_target_dxgi(
     }
 }
 
+/// Paint an image in memory with Direct2D's software rasterizer, into a
+/// WIC bitmap.
+pub(crate) unsafe fn render_software_image(
+    width: usize,
+    height: usize,
+    scale: f64,
+    paint: impl FnOnce(&mut Piet),
+) -> Result<CapturedImage, Error> {
+    // WIC is COM, which the application initializes, but there may be none
+    let initialized = SUCCEEDED(CoInitializeEx(null_mut(), COINIT_APARTMENTTHREADED));
+    let pixels = paint_wic_bitmap(width, height, scale, paint);
+    if initialized {
+        CoUninitialize();
+    }
+    Ok(CapturedImage {
+        pixels: pixels?,
+        width,
+        height,
+    })
+}
+
+unsafe fn paint_wic_bitmap(
+    width: usize,
+    height: usize,
+    scale: f64,
+    paint: impl FnOnce(&mut Piet),
+) -> Result<Vec<u8>, Error> {
+    let mut wic: *mut IWICImagingFactory = null_mut();
+    as_result(CoCreateInstance(
+        &CLSID_WICImagingFactory,
+        null_mut(),
+        CLSCTX_INPROC_SERVER,
+        &IWICImagingFactory::uuidof(),
+        &mut wic as *mut *mut IWICImagingFactory as *mut *mut c_void,
+    ))?;
+    let wic = ComPtr::from_raw(wic);
+    let mut bitmap: *mut IWICBitmap = null_mut();
+    as_result(wic.CreateBitmap(
+        width as u32,
+        height as u32,
+        &GUID_WICPixelFormat32bppPBGRA,
+        WICBitmapCacheOnDemand,
+        &mut bitmap,
+    ))?;
+    let bitmap = ComPtr::from_raw(bitmap);
+
+    let props = D2D1_RENDER_TARGET_PROPERTIES {
+        _type: D2D1_RENDER_TARGET_TYPE_SOFTWARE,
+        pixelFormat: D2D1_PIXEL_FORMAT {
+            format: DXGI_FORMAT_B8G8R8A8_UNORM,
+            alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+        },
+        dpiX: 0.0,
+        dpiY: 0.0,
+        usage: D2D1_RENDER_TARGET_USAGE_NONE,
+        minLevel: D2D1_FEATURE_LEVEL_DEFAULT,
+    };
+    let d2d_factory = D2DFactory::new().map_err(|_| Error::D2Error)?;
+    let dwrite_factory = DwriteFactory::new().map_err(|_| Error::D2Error)?;
+    let mut render_target: *mut ID2D1RenderTarget = null_mut();
+    as_result((*d2d_factory.get_raw()).CreateWicBitmapRenderTarget(
+        bitmap.as_raw(),
+        &props,
+        &mut render_target,
+    ))?;
+    let render_target = ComPtr::from_raw(render_target);
+    let mut context = DeviceContext::new(
+        render_target
+            .cast::<ID2D1DeviceContext>()
+            .map_err(|_| Error::OldWindows)?,
+    );
+    context.set_dpi_scale(scale as f32);
+    context.begin_draw();
+    let painted = {
+        let mut piet = Piet::new(&d2d_factory, &dwrite_factory, &mut context);
+        paint(&mut piet);
+        piet.finish()
+    };
+    let ended = context.end_draw();
+    if painted.is_err() || ended.is_err() {
+        return Err(Error::D2Error);
+    }
+
+    let stride = width * 4;
+    let mut pixels = vec![0u8; stride * height];
+    as_result(bitmap.CopyPixels(
+        ptr::null(),
+        stride as u32,
+        pixels.len() as u32,
+        pixels.as_mut_ptr(),
+    ))?;
+    // BGRA to RGBA
+    for pixel in pixels.chunks_mut(4) {
+        pixel.swap(0, 2);
+    }
+    Ok(pixels)
+}
+
 /// Casts hwnd variant to DeviceTarget
 unsafe fn cast_to_device_context(hrt: &HwndRenderTarget) -> Option<DeviceContext> {
     hrt.get_comptr()
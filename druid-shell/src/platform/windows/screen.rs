@@ -0,0 +1,102 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Windows monitors.
+
+use std::mem;
+use std::ptr::null_mut;
+
+use log::warn;
+use winapi::shared::minwindef::{BOOL, LPARAM, TRUE, UINT};
+use winapi::shared::windef::{HDC, HMONITOR, LPRECT, RECT};
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::shellscalingapi::MDT_EFFECTIVE_DPI;
+use winapi::um::winuser::{
+    EnumDisplayMonitors, GetMonitorInfoW, MONITORINFO, MONITORINFOF_PRIMARY,
+};
+
+use super::util::{system_dpi, OPTIONAL_FUNCTIONS};
+use crate::kurbo::Rect;
+use crate::scale::Scale;
+use crate::screen::Monitor;
+
+/// What `EnumDisplayMonitors` collects the monitors into.
+struct Monitors {
+    dpi: f64,
+    monitors: Vec<Monitor>,
+}
+
+fn rect(rect: &RECT) -> Rect {
+    Rect::new(
+        f64::from(rect.left),
+        f64::from(rect.top),
+        f64::from(rect.right),
+        f64::from(rect.bottom),
+    )
+}
+
+/// The scale of content on a monitor, from its own dpi if the system can
+/// tell it.
+unsafe fn monitor_scale(hmonitor: HMONITOR, system_dpi: f64) -> Scale {
+    if let Some(func) = OPTIONAL_FUNCTIONS.GetDpiForMonitor {
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        if SUCCEEDED(func(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y)) {
+            return Scale::new(f64::from(dpi_x) / 96.0, f64::from(dpi_y) / 96.0);
+        }
+    }
+    Scale::from_dpi(system_dpi)
+}
+
+unsafe extern "system" fn monitor_enum_proc(
+    hmonitor: HMONITOR,
+    _hdc: HDC,
+    _rect: LPRECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let found = &mut *(lparam as *mut Monitors);
+    let mut info: MONITORINFO = mem::zeroed();
+    info.cbSize = mem::size_of::<MONITORINFO>() as UINT;
+    if GetMonitorInfoW(hmonitor, &mut info) == 0 {
+        warn!("failed to get monitor info");
+        return TRUE;
+    }
+    let primary = info.dwFlags & MONITORINFOF_PRIMARY != 0;
+    let scale = monitor_scale(hmonitor, found.dpi);
+    found.monitors.push(Monitor::from_pixels(
+        primary,
+        rect(&info.rcMonitor),
+        rect(&info.rcWork),
+        scale,
+        found.dpi,
+    ));
+    TRUE
+}
+
+/// The monitors, in the coordinates that windows are positioned in.
+///
+/// The process is aware of the system dpi, so the virtual screen is in
+/// physical pixels scaled by it.
+pub(crate) fn get_monitors() -> Vec<Monitor> {
+    let mut found = Monitors {
+        dpi: f64::from(system_dpi()),
+        monitors: Vec::new(),
+    };
+    unsafe {
+        let lparam = &mut found as *mut Monitors as LPARAM;
+        if EnumDisplayMonitors(null_mut(), null_mut(), Some(monitor_enum_proc), lparam) == 0 {
+            warn!("failed to enumerate monitors");
+        }
+    }
+    found.monitors
+}
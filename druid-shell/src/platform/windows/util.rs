@@ -107,7 +107,8 @@ impl FromWide for [u16] {
 // Types for functions we want to load, which are only supported on newer windows versions
 // from shcore.dll
 type GetDpiForSystem = unsafe extern "system" fn() -> UINT;
-type GetDpiForMonitor = unsafe extern "system" fn(HMONITOR, MONITOR_DPI_TYPE, *mut UINT, *mut UINT);
+type GetDpiForMonitor =
+    unsafe extern "system" fn(HMONITOR, MONITOR_DPI_TYPE, *mut UINT, *mut UINT) -> HRESULT;
 // from user32.dll
 type SetProcessDpiAwareness = unsafe extern "system" fn(PROCESS_DPI_AWARENESS) -> HRESULT;
 type DCompositionCreateDevice2 = unsafe extern "system" fn(
@@ -214,6 +215,21 @@ lazy_static! {
 
 pub(crate) const CLASS_NAME: &str = "druid";
 
+/// The window class of child surfaces.
+pub(crate) const CHILD_CLASS_NAME: &str = "druid-child-surface";
+
+/// The dpi of the system, which windows are scaled by (96 is equivalent to 100%).
+pub(crate) fn system_dpi() -> f32 {
+    if let Some(func) = OPTIONAL_FUNCTIONS.GetDpiForSystem {
+        // Only supported on windows 10
+        unsafe { func() as f32 }
+    } else {
+        // TODO GetDpiForMonitor is supported on windows 8.1, try falling back to that here
+        // Probably GetDeviceCaps(..., LOGPIXELSX) is the best to do pre-10
+        96.0
+    }
+}
+
 /// Convenience macro for defining accelerator tables.
 #[macro_export]
 macro_rules! accel {
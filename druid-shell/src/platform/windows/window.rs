@@ -22,6 +22,7 @@ use std::mem;
 use std::ptr::{null, null_mut};
 use std::rc::{Rc, Weak};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use log::{debug, error, warn};
 use winapi::ctypes::{c_int, c_void};
@@ -33,7 +34,12 @@ use winapi::shared::minwindef::*;
 use winapi::shared::windef::*;
 use winapi::shared::winerror::*;
 use winapi::um::d2d1::*;
+use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::unknwnbase::*;
+use winapi::um::wingdi::{
+    CreateCompatibleBitmap, CreateCompatibleDC, CreateSolidBrush, DeleteDC, DeleteObject,
+    GetDIBits, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+};
 use winapi::um::winnt::*;
 use winapi::um::winuser::*;
 
@@ -42,29 +48,65 @@ use piet_common::dwrite::DwriteFactory;
 
 use crate::platform::windows::HwndRenderTarget;
 
-use crate::kurbo::{Point, Size, Vec2};
-use crate::piet::{Piet, RenderContext};
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::piet::{Color, Piet, RenderContext};
 
 use super::accels::register_accel;
+use super::access::AccessTree;
 use super::dcomp::{D3D11Device, DCompositionDevice, DCompositionTarget, DCompositionVisual};
-use super::dialog::get_file_dialog_path;
+use super::dialog::get_file_dialog_paths;
+use super::drop_target::{self, DragKind, DragMessage, XI_DRAG};
 use super::error::Error;
+use super::ime::{self, ImeContext};
 use super::menu::Menu;
 use super::paint;
-use super::timers::TimerSlots;
-use super::util::{as_result, FromWide, ToWide, OPTIONAL_FUNCTIONS};
+use super::util::{as_result, system_dpi, FromWide, ToWide, CHILD_CLASS_NAME, OPTIONAL_FUNCTIONS};
 
-use crate::common_util::IdleCallback;
+use crate::access::{AccessEvent, AccessNode};
+use crate::common_util::{
+    color_to_colorref, point_to_pixels, point_to_px, render_device_image, size_to_pixels,
+    size_to_px, DeferredShow, IdleCallback, FIRST_PAINT_TIMEOUT,
+};
 use crate::dialog::{FileDialogOptions, FileDialogType, FileInfo};
-use crate::keyboard::{KeyEvent, KeyModifiers};
-use crate::keycodes::KeyCode;
-use crate::mouse::{Cursor, MouseButton, MouseEvent};
-use crate::window::{IdleToken, Text, TimerToken, WinHandler};
+use crate::drag::DragEvent;
+use crate::keyboard::{KbKey, KeyEvent, KeyModifiers, KeyState};
+use crate::keycodes::Code;
+use crate::mouse::{Cursor, MouseButton, MouseButtons, MouseEvent};
+use crate::scale::Scale;
+use crate::timer;
+use crate::window::{self, IdleToken, Text, TimerToken, WinHandler};
 
 extern "system" {
     pub fn DwmFlush();
 }
 
+// The gesture API isn't in the winapi version we depend on.
+#[allow(clippy::upper_case_acronyms)]
+type HGESTUREINFO = *mut c_void;
+
+#[repr(C)]
+#[allow(clippy::upper_case_acronyms)]
+struct GESTUREINFO {
+    cbSize: UINT,
+    dwFlags: DWORD,
+    dwID: DWORD,
+    hwndTarget: HWND,
+    ptsLocation: POINTS,
+    dwInstanceID: DWORD,
+    dwSequenceID: DWORD,
+    ullArguments: ULONGLONG,
+    cbExtraArgs: UINT,
+}
+
+const GID_ZOOM: DWORD = 3;
+const GF_BEGIN: DWORD = 0x1;
+const GF_END: DWORD = 0x4;
+
+extern "system" {
+    fn GetGestureInfo(hGestureInfo: HGESTUREINFO, pGestureInfo: *mut GESTUREINFO) -> BOOL;
+    fn CloseGestureInfoHandle(hGestureInfo: HGESTUREINFO) -> BOOL;
+}
+
 /// Builder abstraction for creating new windows.
 pub struct WindowBuilder {
     handler: Option<Box<dyn WinHandler>>,
@@ -72,9 +114,16 @@ pub struct WindowBuilder {
     title: String,
     menu: Option<Menu>,
     present_strategy: PresentStrategy,
+    render_backend: window::RenderBackend,
     resizable: bool,
     show_titlebar: bool,
+    transparent: bool,
     size: Size,
+    min_size: Option<Size>,
+    position: Option<Point>,
+    state: Option<window::WindowState>,
+    level: window::WindowLevel,
+    background: Option<Color>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -110,6 +159,55 @@ pub struct WindowHandle {
     state: Weak<WindowState>,
 }
 
+/// A child window, drawn over its parent.
+pub struct ChildSurface {
+    hwnd: HWND,
+    /// The parent, for its dpi.
+    parent: WindowHandle,
+}
+
+impl ChildSurface {
+    pub fn set_frame(&self, frame: Rect) {
+        let (x0, y0) = self
+            .parent
+            .px_to_pixels_xy(frame.x0 as f32, frame.y0 as f32);
+        let (x1, y1) = self
+            .parent
+            .px_to_pixels_xy(frame.x1 as f32, frame.y1 as f32);
+        unsafe {
+            let flags = SWP_NOZORDER | SWP_NOACTIVATE;
+            if SetWindowPos(self.hwnd, HWND_TOP, x0, y0, x1 - x0, y1 - y0, flags) == 0 {
+                warn!("failed to move a child surface");
+            }
+        }
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        let cmd = if visible { SW_SHOWNOACTIVATE } else { SW_HIDE };
+        unsafe {
+            ShowWindow(self.hwnd, cmd);
+        }
+    }
+
+    pub fn set_passthrough(&self, passthrough: bool) {
+        unsafe {
+            SetWindowLongPtrW(self.hwnd, GWLP_USERDATA, passthrough as WindowLongPtr);
+        }
+    }
+
+    pub fn hwnd(&self) -> HWND {
+        self.hwnd
+    }
+}
+
+impl Drop for ChildSurface {
+    fn drop(&mut self) {
+        unsafe {
+            DestroyWindow(self.hwnd);
+        }
+    }
+}
+
 /// A handle that can get used to schedule an idle handler. Note that
 /// this handle is thread safe. If the handle is used after the hwnd
 /// has been destroyed, probably not much will go wrong (the XI_RUN_IDLE
@@ -124,6 +222,8 @@ pub struct IdleHandle {
 enum IdleKind {
     Callback(Box<dyn IdleCallback>),
     Token(IdleToken),
+    /// A timer that is due, with its deadline.
+    Timer(TimerToken, Instant),
 }
 
 /// This is the low level window state. All mutable contents are protected
@@ -131,9 +231,28 @@ enum IdleKind {
 struct WindowState {
     hwnd: Cell<HWND>,
     dpi: Cell<f32>,
+    /// The command passed to `ShowWindow` when the window is first shown.
+    show_cmd: c_int,
+    /// The window is shown after its first paint.
+    deferred_show: Cell<DeferredShow>,
     wndproc: Box<dyn WndProc>,
     idle_queue: Arc<Mutex<Vec<IdleKind>>>,
-    timers: Arc<Mutex<TimerSlots>>,
+    /// The backend in use; this is never `Auto`.
+    render_backend: window::RenderBackend,
+    /// The color the window is erased with, if it is set.
+    background: Cell<Option<COLORREF>>,
+    /// Whether the window has a titlebar and a frame. Without them, the
+    /// client area covers the whole window.
+    show_titlebar: Cell<bool>,
+    /// Whether the window can be resized by dragging its edges.
+    resizable: Cell<bool>,
+    /// Set by `begin_move_drag`, while the handler is handling a mouse press.
+    move_drag: Cell<bool>,
+    /// Set while the window holds the mouse capture that the handler asked
+    /// for with `set_mouse_capture`.
+    mouse_captured: Cell<bool>,
+    /// The accessibility tree, which is exposed with UI Automation.
+    access: Rc<AccessTree>,
 }
 
 /// Generic handler trait for the winapi window procedure entry point.
@@ -152,6 +271,8 @@ struct MyWndProc {
     dwrite_factory: DwriteFactory,
     state: RefCell<Option<WndState>>,
     present_strategy: PresentStrategy,
+    render_backend: window::RenderBackend,
+    transparent: bool,
 }
 
 /// The mutable state of the window.
@@ -160,13 +281,28 @@ struct WndState {
     render_target: Option<DeviceContext>,
     dcomp_state: Option<DCompState>,
     dpi: f32,
-    /// The `KeyCode` of the last `WM_KEYDOWN` event. We stash this so we can
+    /// The `Code` of the last `WM_KEYDOWN` event. We stash this so we can
     /// include it when handling `WM_CHAR` events.
-    stashed_key_code: KeyCode,
+    stashed_key_code: Code,
     /// The `char` of the last `WM_CHAR` event, if there has not already been
     /// a `WM_KEYUP` event.
     stashed_char: Option<char>,
+    /// Whether a dead key has started a composition, which the next
+    /// `WM_CHAR` ends with the composed text.
+    composing: bool,
+    /// Whether the input method is composing text, between
+    /// `WM_IME_STARTCOMPOSITION` and the text it types.
+    ime_composing: bool,
     //TODO: track surrogate orphan
+    /// The distance between the touch points at the last `GID_ZOOM` gesture
+    /// message, if a zoom gesture is in progress.
+    zoom_distance: Option<f64>,
+    /// The smallest size of the client area, in px units.
+    min_size: Option<Size>,
+    /// Whether the swapchain keeps the alpha of what is drawn.
+    transparent: bool,
+    /// Whether the next mouse press is the one that activated the window.
+    focus_click: bool,
 }
 
 /// State for DirectComposition. This is optional because it is only supported
@@ -195,6 +331,20 @@ const XI_RUN_IDLE: UINT = WM_USER;
 /// time it is handled, we can successfully borrow the handler.
 const XI_REQUEST_DESTROY: UINT = WM_USER + 1;
 
+/// Message requesting the first paint of a window that is waiting to be shown.
+///
+/// Hidden windows don't get `WM_PAINT`, so we paint them ourselves.
+const XI_FIRST_PAINT: UINT = WM_USER + 2;
+
+/// The id of the timer that shows a window if its first paint takes too long.
+///
+/// This is the only `SetTimer` timer; those requested by the handler are
+/// kept by the timer thread, and arrive as `XI_RUN_IDLE`.
+const FIRST_PAINT_TIMER_ID: usize = 1;
+
+/// The width of the edges that resize a window without a frame, in px units.
+const RESIZE_BORDER: f32 = 8.0;
+
 impl Default for PresentStrategy {
     fn default() -> PresentStrategy {
         // We probably want to change this, but we need GDI to work. Too bad about
@@ -203,6 +353,23 @@ impl Default for PresentStrategy {
     }
 }
 
+/// The mouse buttons that are held down, from the `wParam` of a mouse message.
+fn get_buttons(wparam: WPARAM) -> MouseButtons {
+    let mut buttons = MouseButtons::new();
+    for &(flag, button) in &[
+        (MK_LBUTTON, MouseButton::Left),
+        (MK_RBUTTON, MouseButton::Right),
+        (MK_MBUTTON, MouseButton::Middle),
+        (MK_XBUTTON1, MouseButton::X1),
+        (MK_XBUTTON2, MouseButton::X2),
+    ] {
+        if wparam & flag != 0 {
+            buttons.insert(button);
+        }
+    }
+    buttons
+}
+
 /// Must only be called while handling an input message.
 /// This queries the keyboard state at the time of message delivery.
 fn get_mod_state() -> KeyModifiers {
@@ -226,20 +393,28 @@ impl WndState {
     fn rebuild_render_target(&mut self, d2d: &D2DFactory) {
         unsafe {
             let swap_chain = self.dcomp_state.as_ref().unwrap().swap_chain;
-            let rt = paint::create_render_target_dxgi(d2d, swap_chain, self.dpi)
+            let rt = paint::create_render_target_dxgi(d2d, swap_chain, self.dpi, self.transparent)
                 .map(|rt| rt.as_device_context().expect("TODO remove this expect"));
             self.render_target = rt.ok();
         }
     }
 
     // Renders but does not present.
-    fn render(&mut self, d2d: &D2DFactory, dw: &DwriteFactory, handle: &RefCell<WindowHandle>) {
+    //
+    // `invalid` is the part of the window to repaint, in px units.
+    fn render(
+        &mut self,
+        d2d: &D2DFactory,
+        dw: &DwriteFactory,
+        handle: &RefCell<WindowHandle>,
+        invalid: Rect,
+    ) {
         let rt = self.render_target.as_mut().unwrap();
         rt.begin_draw();
         let anim;
         {
             let mut piet_ctx = Piet::new(d2d, dw, rt);
-            anim = self.handler.paint(&mut piet_ctx);
+            anim = self.handler.paint(&mut piet_ctx, invalid);
             if let Err(e) = piet_ctx.finish() {
                 error!("piet error on render: {:?}", e);
             }
@@ -259,6 +434,62 @@ impl WndState {
 }
 
 impl MyWndProc {
+    /// Render and present the window.
+    ///
+    /// Returns `false` if the state is borrowed, and the window was not painted.
+    fn paint(&self, hwnd: HWND) -> bool {
+        let mut s = match self.state.try_borrow_mut() {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        let s = s.as_mut().unwrap();
+        unsafe {
+            if s.render_target.is_none() {
+                let rt = paint::create_render_target(&self.d2d_factory, hwnd, self.render_backend);
+                s.render_target = rt.ok();
+            }
+            s.handler.rebuild_resources();
+            // the back buffers of a swap chain are not preserved between
+            // presents, so those windows are always repainted in full
+            let invalid = if s.dcomp_state.is_some() {
+                client_rect_px(hwnd, s.dpi)
+            } else {
+                update_rect_px(hwnd, s.dpi)
+            };
+            s.render(
+                &self.d2d_factory,
+                &self.dwrite_factory,
+                &self.handle,
+                invalid,
+            );
+            if let Some(ref mut ds) = s.dcomp_state {
+                if !ds.sizing {
+                    (*ds.swap_chain).Present(1, 0);
+                    let _ = ds.dcomp_device.commit();
+                }
+            }
+            ValidateRect(hwnd, null_mut());
+        }
+        true
+    }
+
+    /// Show the window, if it is waiting for its first paint.
+    fn paint_completed(&self) {
+        let state = self.handle.borrow().state.upgrade();
+        if let Some(state) = state {
+            let mut deferred = state.deferred_show.get();
+            let show = deferred.paint_completed();
+            // set before showing, which paints again
+            state.deferred_show.set(deferred);
+            if show {
+                unsafe {
+                    KillTimer(state.hwnd.get(), FIRST_PAINT_TIMER_ID);
+                }
+                state.show_now();
+            }
+        }
+    }
+
     /// Create debugging output for dropped messages due to wndproc reentrancy.
     ///
     /// In the future, we choose to do something else other than logging and dropping,
@@ -289,10 +520,11 @@ impl WndProc for MyWndProc {
         match msg {
             WM_CREATE => {
                 let dcomp_state = unsafe {
-                    create_dcomp_state(self.present_strategy, hwnd).unwrap_or_else(|e| {
-                        warn!("Creating swapchain failed, falling back to hwnd: {:?}", e);
-                        None
-                    })
+                    create_dcomp_state(self.present_strategy, self.transparent, hwnd)
+                        .unwrap_or_else(|e| {
+                            warn!("Creating swapchain failed, falling back to hwnd: {:?}", e);
+                            None
+                        })
                 };
 
                 self.state.borrow_mut().as_mut().unwrap().dcomp_state = dcomp_state;
@@ -303,10 +535,47 @@ impl WndProc for MyWndProc {
                 if let Some(state) = self.state.borrow_mut().as_mut() {
                     state.handler.connect(&handle.into());
                 }
+                drop_target::register(hwnd);
 
                 Some(0)
             }
-            WM_ERASEBKGND => Some(0),
+            WM_ERASEBKGND => {
+                let background = self
+                    .handle
+                    .borrow()
+                    .state
+                    .upgrade()
+                    .and_then(|state| state.background.get());
+                match background {
+                    Some(color) => unsafe {
+                        let hdc = wparam as HDC;
+                        let mut rect: RECT = mem::zeroed();
+                        GetClientRect(hwnd, &mut rect);
+                        let brush = CreateSolidBrush(color);
+                        FillRect(hdc, &rect, brush);
+                        DeleteObject(brush as HGDIOBJ);
+                        Some(1)
+                    },
+                    // the whole window is painted anyway
+                    None => Some(0),
+                }
+            }
+            WM_CAPTURECHANGED => {
+                // the `lparam` is the window that takes the capture; only a
+                // capture that the handler asked for is reported as lost
+                let state = self.handle.borrow().state.upgrade();
+                let lost = lparam as HWND != hwnd
+                    && state.map_or(false, |w| w.mouse_captured.replace(false));
+                if lost {
+                    if let Ok(mut s) = self.state.try_borrow_mut() {
+                        let s = s.as_mut().unwrap();
+                        s.handler.mouse_capture_lost();
+                    } else {
+                        self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                    }
+                }
+                Some(0)
+            }
             WM_SETFOCUS => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
@@ -316,36 +585,60 @@ impl WndProc for MyWndProc {
                 }
                 Some(0)
             }
-            WM_PAINT => unsafe {
+            WM_SETTINGCHANGE => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
-                    if s.render_target.is_none() {
-                        let rt = paint::create_render_target(&self.d2d_factory, hwnd);
-                        s.render_target = rt.ok();
-                    }
-                    s.handler.rebuild_resources();
-                    s.render(&self.d2d_factory, &self.dwrite_factory, &self.handle);
-                    if let Some(ref mut ds) = s.dcomp_state {
-                        if !ds.sizing {
-                            (*ds.swap_chain).Present(1, 0);
-                            let _ = ds.dcomp_device.commit();
-                        }
+                    s.handler.system_settings_changed();
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                Some(0)
+            }
+            WM_PAINT => {
+                if self.paint(hwnd) {
+                    self.paint_completed();
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                Some(0)
+            }
+            XI_FIRST_PAINT => {
+                // a hidden window may not have been told its size yet
+                let mut rect: RECT = unsafe { mem::zeroed() };
+                if unsafe { GetClientRect(hwnd, &mut rect) } != 0 {
+                    if let Ok(mut s) = self.state.try_borrow_mut() {
+                        let width = (rect.right - rect.left) as u32;
+                        let height = (rect.bottom - rect.top) as u32;
+                        s.as_mut().unwrap().handler.size(width, height);
                     }
-                    ValidateRect(hwnd, null_mut());
+                }
+                if self.paint(hwnd) {
+                    self.paint_completed();
                 } else {
+                    // the timeout will show the window
                     self.log_dropped_msg(hwnd, msg, wparam, lparam);
                 }
                 Some(0)
-            },
+            }
             WM_ENTERSIZEMOVE => unsafe {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
                     if s.dcomp_state.is_some() {
-                        let rt = paint::create_render_target(&self.d2d_factory, hwnd);
+                        let rt = paint::create_render_target(
+                            &self.d2d_factory,
+                            hwnd,
+                            self.render_backend,
+                        );
                         s.render_target = rt.ok();
                         {
                             s.handler.rebuild_resources();
-                            s.render(&self.d2d_factory, &self.dwrite_factory, &self.handle);
+                            let invalid = client_rect_px(hwnd, s.dpi);
+                            s.render(
+                                &self.d2d_factory,
+                                &self.dwrite_factory,
+                                &self.handle,
+                                invalid,
+                            );
                         }
 
                         if let Some(ref mut ds) = s.dcomp_state {
@@ -380,7 +673,13 @@ impl WndProc for MyWndProc {
                         if SUCCEEDED(res) {
                             s.handler.rebuild_resources();
                             s.rebuild_render_target(&self.d2d_factory);
-                            s.render(&self.d2d_factory, &self.dwrite_factory, &self.handle);
+                            let invalid = client_rect_px(hwnd, s.dpi);
+                            s.render(
+                                &self.d2d_factory,
+                                &self.dwrite_factory,
+                                &self.handle,
+                                invalid,
+                            );
                             (*s.dcomp_state.as_ref().unwrap().swap_chain).Present(0, 0);
                         } else {
                             error!("ResizeBuffers failed: 0x{:x}", res);
@@ -436,7 +735,13 @@ impl WndProc for MyWndProc {
                         }
                         if SUCCEEDED(res) {
                             s.rebuild_render_target(&self.d2d_factory);
-                            s.render(&self.d2d_factory, &self.dwrite_factory, &self.handle);
+                            let invalid = client_rect_px(hwnd, s.dpi);
+                            s.render(
+                                &self.d2d_factory,
+                                &self.dwrite_factory,
+                                &self.handle,
+                                invalid,
+                            );
                             if let Some(ref mut dcomp_state) = s.dcomp_state {
                                 (*dcomp_state.swap_chain).Present(0, 0);
                                 let _ = dcomp_state.dcomp_device.commit();
@@ -451,6 +756,38 @@ impl WndProc for MyWndProc {
                 }
                 Some(0)
             },
+            WM_DPICHANGED => unsafe {
+                let dpi = f32::from(HIWORD(wparam as u32));
+                if let Some(w) = self.handle.borrow().state.upgrade() {
+                    w.dpi.set(dpi);
+                }
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    s.dpi = dpi;
+                    // the render target is made again at the new dpi
+                    if s.dcomp_state.is_some() {
+                        s.rebuild_render_target(&self.d2d_factory);
+                    } else {
+                        s.render_target = None;
+                    }
+                    s.handler.scale(Scale::from_dpi(f64::from(dpi)));
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                // move to the size and position that the system suggests
+                let rect = &*(lparam as *const RECT);
+                let flags = SWP_NOZORDER | SWP_NOACTIVATE;
+                SetWindowPos(
+                    hwnd,
+                    null_mut(),
+                    rect.left,
+                    rect.top,
+                    rect.right - rect.left,
+                    rect.bottom - rect.top,
+                    flags,
+                );
+                Some(0)
+            },
             WM_COMMAND => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
@@ -477,9 +814,19 @@ impl WndProc for MyWndProc {
 
                     let modifiers = get_mod_state();
                     let is_repeat = (lparam & 0xFFFF) > 0;
-                    let event = KeyEvent::new(key_code, is_repeat, modifiers, text, text);
-
-                    if s.handler.key_down(event) {
+                    let mut event =
+                        KeyEvent::new(KeyState::Down, key_code, is_repeat, modifiers, text, text);
+                    // Windows has already composed the text with the dead key
+                    event.is_composing = s.composing;
+
+                    let handled = s.handler.key_down(event);
+                    if s.composing {
+                        s.composing = false;
+                        let text = text.to_string();
+                        s.handler.composition_update(&text, text.len()..text.len());
+                        s.handler.composition_end(&text);
+                    }
+                    if handled {
                         Some(0)
                     } else {
                         None
@@ -489,13 +836,114 @@ impl WndProc for MyWndProc {
                     None
                 }
             }
+            WM_DEADCHAR | WM_SYSDEADCHAR => {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let accent = match std::char::from_u32(wparam as u32) {
+                        Some(c) => c,
+                        None => {
+                            warn!("failed to convert WM_DEADCHAR to char: {:#X}", wparam);
+                            return None;
+                        }
+                    };
+                    let key_code = s.stashed_key_code;
+                    let modifiers = get_mod_state();
+                    let is_repeat = (lparam & 0xFFFF) > 0;
+                    let mut event =
+                        KeyEvent::new(KeyState::Down, key_code, is_repeat, modifiers, "", "");
+                    event.key = KbKey::Dead;
+                    event.is_composing = s.composing;
+
+                    s.handler.key_down(event);
+                    if !s.composing {
+                        s.composing = true;
+                        s.handler.composition_start();
+                    }
+                    let accent = accent.to_string();
+                    s.handler
+                        .composition_update(&accent, accent.len()..accent.len());
+                    Some(0)
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                    None
+                }
+            }
+            WM_IME_SETCONTEXT => {
+                // the composition is shown by the handler, where it is typed
+                let lparam = lparam & !(ime::ISC_SHOWUICOMPOSITIONWINDOW as LPARAM);
+                Some(unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) })
+            }
+            WM_IME_STARTCOMPOSITION => {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    if !s.ime_composing {
+                        s.ime_composing = true;
+                        s.handler.composition_start();
+                    }
+                    // the input method doesn't show its own composition window
+                    Some(0)
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                    None
+                }
+            }
+            WM_IME_COMPOSITION => {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let ime = match ImeContext::get(hwnd) {
+                        Some(ime) => ime,
+                        None => return None,
+                    };
+                    let flags = lparam as DWORD;
+                    // the text is typed, and the next composition may
+                    // already have started
+                    if flags & ime::GCS_RESULTSTR != 0 {
+                        let text = ime.string(ime::GCS_RESULTSTR).unwrap_or_default();
+                        if !s.ime_composing {
+                            s.handler.composition_start();
+                        }
+                        s.ime_composing = false;
+                        s.handler.composition_end(&text);
+                    }
+                    if flags & ime::GCS_COMPSTR != 0 {
+                        if let Some((text, cursor)) = ime.composition() {
+                            if !text.is_empty() || s.ime_composing {
+                                if !s.ime_composing {
+                                    s.ime_composing = true;
+                                    s.handler.composition_start();
+                                }
+                                s.handler.composition_update(&text, cursor);
+                            }
+                        }
+                    }
+                    // handled, so that the text doesn't come again as WM_IME_CHAR
+                    Some(0)
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                    None
+                }
+            }
+            WM_IME_ENDCOMPOSITION => {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    // ending without typing the text cancels it
+                    if s.ime_composing {
+                        s.ime_composing = false;
+                        s.handler.composition_end("");
+                    }
+                    Some(0)
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                    None
+                }
+            }
             WM_KEYDOWN | WM_SYSKEYDOWN => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
-                    let key_code: KeyCode = (wparam as i32).into();
+                    let key_code: Code = (wparam as i32).into();
                     s.stashed_key_code = key_code;
 
-                    if key_code.is_printable() || key_code == KeyCode::Backspace {
+                    if key_code.is_printable() || key_code == Code::Backspace {
                         //FIXME: this will fail to propogate key combinations such as alt+s
                         return None;
                     }
@@ -504,7 +952,10 @@ impl WndProc for MyWndProc {
                     // bits 0-15 of iparam are the repeat count:
                     // https://docs.microsoft.com/en-ca/windows/desktop/inputdev/wm-keydown
                     let is_repeat = (lparam & 0xFFFF) > 0;
-                    let event = KeyEvent::new(key_code, is_repeat, modifiers, "", "");
+                    let mut event =
+                        KeyEvent::new(KeyState::Down, key_code, is_repeat, modifiers, "", "");
+                    // the input method takes the key, for its composition
+                    event.is_composing = wparam == VK_PROCESSKEY as WPARAM;
 
                     if s.handler.key_down(event) {
                         Some(0)
@@ -519,11 +970,12 @@ impl WndProc for MyWndProc {
             WM_KEYUP => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
-                    let key_code: KeyCode = (wparam as i32).into();
+                    let key_code: Code = (wparam as i32).into();
                     let modifiers = get_mod_state();
                     let is_repeat = false;
                     let text = s.stashed_char.take();
-                    let event = KeyEvent::new(key_code, is_repeat, modifiers, text, text);
+                    let event =
+                        KeyEvent::new(KeyState::Up, key_code, is_repeat, modifiers, text, text);
                     s.handler.key_up(event);
                 } else {
                     self.log_dropped_msg(hwnd, msg, wparam, lparam);
@@ -557,6 +1009,40 @@ impl WndProc for MyWndProc {
                 }
                 Some(0)
             }
+            WM_GESTURE => unsafe {
+                let gesture = lparam as HGESTUREINFO;
+                let mut info: GESTUREINFO = mem::zeroed();
+                info.cbSize = mem::size_of::<GESTUREINFO>() as UINT;
+                if GetGestureInfo(gesture, &mut info) == FALSE || info.dwID != GID_ZOOM {
+                    // DefWindowProc closes the handle
+                    return None;
+                }
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    // for a zoom, the argument is the distance between the touch points
+                    let distance = info.ullArguments as f64;
+                    let last = s.zoom_distance.replace(distance);
+                    if info.dwFlags & GF_BEGIN == 0 {
+                        if let Some(last) = last.filter(|last| *last > 0.0) {
+                            s.handler.zoom(distance / last - 1.0);
+                        }
+                    }
+                    if info.dwFlags & GF_END != 0 {
+                        s.zoom_distance = None;
+                    }
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                CloseGestureInfoHandle(gesture);
+                Some(0)
+            },
+            WM_MOUSEACTIVATE => {
+                // the click that follows activates the window
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    s.as_mut().unwrap().focus_click = true;
+                }
+                None
+            }
             WM_MOUSEMOVE => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
@@ -565,21 +1051,17 @@ impl WndProc for MyWndProc {
                     let (px, py) = self.handle.borrow().pixels_to_px_xy(x, y);
                     let pos = Point::new(px as f64, py as f64);
                     let mods = get_mod_state();
-                    let button = match wparam {
-                        w if (w & 1) > 0 => MouseButton::Left,
-                        w if (w & 1 << 1) > 0 => MouseButton::Right,
-                        w if (w & 1 << 5) > 0 => MouseButton::Middle,
-                        w if (w & 1 << 6) > 0 => MouseButton::X1,
-                        w if (w & 1 << 7) > 0 => MouseButton::X2,
-                        //FIXME: I guess we probably do want `MouseButton::None`?
-                        //this feels bad, but also this gets discarded in druid anyway.
-                        _ => MouseButton::Left,
-                    };
+                    let buttons = get_buttons(wparam);
+                    //FIXME: I guess we probably do want `MouseButton::None`?
+                    //this feels bad, but also this gets discarded in druid anyway.
+                    let button = buttons.iter().next().unwrap_or(MouseButton::Left);
                     let event = MouseEvent {
                         pos,
                         mods,
                         button,
+                        buttons,
                         count: 0,
+                        focus: false,
                     };
                     s.handler.mouse_move(&event);
                 } else {
@@ -622,11 +1104,17 @@ impl WndProc for MyWndProc {
                     let (px, py) = self.handle.borrow().pixels_to_px_xy(x, y);
                     let pos = Point::new(px as f64, py as f64);
                     let mods = get_mod_state();
+                    // for the X buttons, the high word is the button
+                    let buttons = get_buttons(LOWORD(wparam as u32) as WPARAM);
+                    // the click that activated the window
+                    let focus = count > 0 && mem::replace(&mut s.focus_click, false);
                     let event = MouseEvent {
                         pos,
                         mods,
                         button,
+                        buttons,
                         count,
+                        focus,
                     };
                     if count > 0 {
                         s.handler.mouse_down(&event);
@@ -636,6 +1124,44 @@ impl WndProc for MyWndProc {
                 } else {
                     self.log_dropped_msg(hwnd, msg, wparam, lparam);
                 }
+                // this is done once the handler is no longer borrowed, since
+                // the system handles the messages until the drag is over
+                let move_drag = self.handle.borrow().state.upgrade();
+                if move_drag.map(|w| w.move_drag.replace(false)) == Some(true) {
+                    unsafe {
+                        let mut pos: POINT = mem::zeroed();
+                        GetCursorPos(&mut pos);
+                        let pos = (pos.y as u16 as u32) << 16 | pos.x as u16 as u32;
+                        ReleaseCapture();
+                        SendMessageW(hwnd, WM_NCLBUTTONDOWN, HTCAPTION as WPARAM, pos as LPARAM);
+                    }
+                }
+                Some(0)
+            }
+            XI_DRAG => {
+                let message = unsafe { &mut *(lparam as *mut DragMessage) };
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let mut point = message.point;
+                    unsafe { ScreenToClient(hwnd, &mut point) };
+                    let (px, py) = self.handle.borrow().pixels_to_px_xy(point.x, point.y);
+                    let event = DragEvent {
+                        pos: Point::new(px as f64, py as f64),
+                        mods: message.mods,
+                        payload: mem::take(&mut message.payload),
+                    };
+                    message.accepted = match message.kind {
+                        DragKind::Enter => s.handler.drag_enter(&event),
+                        DragKind::Over => s.handler.drag_over(&event),
+                        DragKind::Leave => {
+                            s.handler.drag_leave();
+                            false
+                        }
+                        DragKind::Drop => s.handler.drag_drop(&event),
+                    };
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
                 Some(0)
             }
             XI_REQUEST_DESTROY => {
@@ -644,7 +1170,90 @@ impl WndProc for MyWndProc {
                 }
                 Some(0)
             }
+            WM_NCCALCSIZE => {
+                let state = self.handle.borrow().state.upgrade()?;
+                if wparam == 0 || state.show_titlebar.get() {
+                    return None;
+                }
+                // without a frame, the client area covers the whole window,
+                // except that a maximized window extends past the edges of
+                // the screen by the width of its frame
+                unsafe {
+                    if IsZoomed(hwnd) != FALSE {
+                        let padding = GetSystemMetrics(SM_CXPADDEDBORDER);
+                        let frame_x = GetSystemMetrics(SM_CXFRAME) + padding;
+                        let frame_y = GetSystemMetrics(SM_CYFRAME) + padding;
+                        let params = lparam as *mut NCCALCSIZE_PARAMS;
+                        let rect = &mut (*params).rgrc[0];
+                        rect.left += frame_x;
+                        rect.top += frame_y;
+                        rect.right -= frame_x;
+                        rect.bottom -= frame_y;
+                    }
+                }
+                Some(0)
+            }
+            WM_NCHITTEST => {
+                let state = self.handle.borrow().state.upgrade()?;
+                if state.show_titlebar.get() || !state.resizable.get() {
+                    return None;
+                }
+                // without a frame, the edges of the window resize it
+                unsafe {
+                    let x = LOWORD(lparam as u32) as i16 as i32;
+                    let y = HIWORD(lparam as u32) as i16 as i32;
+                    let mut rect: RECT = mem::zeroed();
+                    GetWindowRect(hwnd, &mut rect);
+                    let border = (RESIZE_BORDER * state.dpi.get() / 96.0).round() as i32;
+                    Some(border_hit_test(&rect, x, y, border))
+                }
+            }
+            WM_GETMINMAXINFO => {
+                let min_size = match self.state.try_borrow() {
+                    Ok(s) => s
+                        .as_ref()
+                        .and_then(|s| s.min_size.map(|size| (size, s.dpi))),
+                    // sent while the window is being created
+                    Err(_) => None,
+                };
+                if let Some((min_size, dpi)) = min_size {
+                    let size = size_to_pixels(min_size, f64::from(dpi));
+                    unsafe {
+                        // the minimum is for the whole window, including the frame
+                        let mut rect = RECT {
+                            left: 0,
+                            top: 0,
+                            right: size.width.ceil() as i32,
+                            bottom: size.height.ceil() as i32,
+                        };
+                        let style = GetWindowLongW(hwnd, GWL_STYLE) as DWORD;
+                        let has_menu = !GetMenu(hwnd).is_null();
+                        AdjustWindowRect(&mut rect, style, has_menu as BOOL);
+                        let info = lparam as *mut MINMAXINFO;
+                        (*info).ptMinTrackSize.x = rect.right - rect.left;
+                        (*info).ptMinTrackSize.y = rect.bottom - rect.top;
+                    }
+                }
+                Some(0)
+            }
+            WM_GETOBJECT => {
+                let access = self.handle.borrow().state.upgrade()?.access.clone();
+                let (activated, result) = access.get_object(hwnd, wparam, lparam)?;
+                if activated {
+                    if let Ok(mut s) = self.state.try_borrow_mut() {
+                        let s = s.as_mut().unwrap();
+                        s.handler.accessibility_activated();
+                    } else {
+                        self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                    }
+                }
+                Some(result)
+            }
             WM_DESTROY => {
+                if let Some(state) = self.handle.borrow().state.upgrade() {
+                    state.access.disconnect(hwnd);
+                }
+                drop_target::revoke(hwnd);
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
                     s.handler.destroy();
@@ -658,13 +1267,19 @@ impl WndProc for MyWndProc {
                 unsafe {
                     KillTimer(hwnd, id);
                 }
-                let token = TimerToken::from_raw(id as u64);
-                self.handle.borrow().free_timer_slot(token);
-                if let Ok(mut s) = self.state.try_borrow_mut() {
-                    let s = s.as_mut().unwrap();
-                    s.handler.timer(token);
+                if id == FIRST_PAINT_TIMER_ID {
+                    let state = self.handle.borrow().state.upgrade();
+                    if let Some(state) = state {
+                        let mut deferred = state.deferred_show.get();
+                        let show = deferred.timed_out();
+                        state.deferred_show.set(deferred);
+                        if show {
+                            warn!("first paint timed out, showing the window anyway");
+                            state.show_now();
+                        }
+                    }
                 }
-                Some(1)
+                Some(0)
             }
             XI_RUN_IDLE => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
@@ -674,6 +1289,9 @@ impl WndProc for MyWndProc {
                         match callback {
                             IdleKind::Callback(it) => it.call(s.handler.as_any()),
                             IdleKind::Token(token) => s.handler.idle(token),
+                            IdleKind::Timer(token, deadline) => {
+                                timer::deliver(&mut *s.handler, token, deadline)
+                            }
                         }
                     }
                     Some(0)
@@ -704,8 +1322,15 @@ impl WindowBuilder {
             menu: None,
             resizable: true,
             show_titlebar: true,
+            transparent: false,
             present_strategy: Default::default(),
+            render_backend: Default::default(),
             size: Size::new(500.0, 400.0),
+            min_size: None,
+            position: None,
+            state: None,
+            level: window::WindowLevel::AppWindow,
+            background: None,
         }
     }
 
@@ -718,24 +1343,50 @@ impl WindowBuilder {
         self.size = size;
     }
 
+    pub fn set_min_size(&mut self, size: Size) {
+        self.min_size = Some(size);
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.position = Some(position);
+    }
+
+    pub fn set_window_state(&mut self, state: window::WindowState) {
+        self.state = Some(state);
+    }
+
     pub fn resizable(&mut self, resizable: bool) {
-        // TODO: Use this in `self.build`
         self.resizable = resizable;
     }
 
     pub fn show_titlebar(&mut self, show_titlebar: bool) {
-        // TODO: Use this in `self.build`
         self.show_titlebar = show_titlebar;
     }
 
+    pub fn transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
+    pub fn set_level(&mut self, level: window::WindowLevel) {
+        self.level = level;
+    }
+
     pub fn set_title<S: Into<String>>(&mut self, title: S) {
         self.title = title.into();
     }
 
+    pub fn set_background_color(&mut self, color: Color) {
+        self.background = Some(color);
+    }
+
     pub fn set_menu(&mut self, menu: Menu) {
         self.menu = Some(menu);
     }
 
+    pub fn set_render_backend(&mut self, backend: window::RenderBackend) {
+        self.render_backend = backend;
+    }
+
     pub fn build(self) -> Result<WindowHandle, Error> {
         unsafe {
             // Maybe separate registration in build api? Probably only need to
@@ -744,20 +1395,51 @@ impl WindowBuilder {
             let class_name = super::util::CLASS_NAME.to_wide();
             let dwrite_factory = DwriteFactory::new().unwrap();
             let dw_clone = clone_dwrite(&dwrite_factory);
+            // Direct2D over remote desktop is much faster in software
+            let remote_session = GetSystemMetrics(SM_REMOTESESSION) != 0;
+            let render_backend = self.render_backend.resolve(remote_session);
+            // the swapchain is always hardware
+            let present_strategy = match render_backend {
+                window::RenderBackend::Software => PresentStrategy::Hwnd,
+                // the redirection surface is opaque, so a transparent window
+                // is only drawn through DirectComposition
+                _ if self.transparent => PresentStrategy::Flip,
+                _ => self.present_strategy,
+            };
+            if self.transparent && present_strategy == PresentStrategy::Hwnd {
+                warn!("transparent windows need hardware rendering");
+            }
+            let popup = self.level.is_popup();
+            let resizable = self.resizable && !popup;
             let wndproc = MyWndProc {
                 handle: Default::default(),
                 d2d_factory: D2DFactory::new().unwrap(),
                 dwrite_factory: dw_clone,
                 state: RefCell::new(None),
-                present_strategy: self.present_strategy,
+                present_strategy,
+                render_backend,
+                transparent: self.transparent,
             };
 
             let window = WindowState {
                 hwnd: Cell::new(0 as HWND),
                 dpi: Cell::new(0.0),
+                show_cmd: match self.state {
+                    _ if popup => SW_SHOWNOACTIVATE,
+                    Some(window::WindowState::Maximized) => SW_SHOWMAXIMIZED,
+                    Some(window::WindowState::Minimized) => SW_SHOWMINIMIZED,
+                    Some(window::WindowState::Restored) | None => SW_SHOWNORMAL,
+                },
+                deferred_show: Default::default(),
                 wndproc: Box::new(wndproc),
                 idle_queue: Default::default(),
-                timers: Arc::new(Mutex::new(TimerSlots::new(1))),
+                render_backend,
+                background: Cell::new(self.background.as_ref().map(color_to_colorref)),
+                show_titlebar: Cell::new(self.show_titlebar),
+                resizable: Cell::new(resizable),
+                move_drag: Cell::new(false),
+                mouse_captured: Cell::new(false),
+                access: AccessTree::new(),
             };
             let win = Rc::new(window);
             let handle = WindowHandle {
@@ -765,15 +1447,8 @@ impl WindowBuilder {
                 state: Rc::downgrade(&win),
             };
 
-            // Simple scaling based on System Dpi (96 is equivalent to 100%)
-            let dpi = if let Some(func) = OPTIONAL_FUNCTIONS.GetDpiForSystem {
-                // Only supported on windows 10
-                func() as f32
-            } else {
-                // TODO GetDpiForMonitor is supported on windows 8.1, try falling back to that here
-                // Probably GetDeviceCaps(..., LOGPIXELSX) is the best to do pre-10
-                96.0
-            };
+            // Simple scaling based on System Dpi
+            let dpi = system_dpi();
             win.dpi.set(dpi);
 
             let state = WndState {
@@ -781,13 +1456,25 @@ impl WindowBuilder {
                 render_target: None,
                 dcomp_state: None,
                 dpi,
-                stashed_key_code: KeyCode::Unknown(0),
+                stashed_key_code: Code::Unknown(0),
                 stashed_char: None,
+                composing: false,
+                ime_composing: false,
+                zoom_distance: None,
+                min_size: self.min_size,
+                transparent: self.transparent,
+                focus_click: false,
             };
             win.wndproc.connect(&handle, state);
 
-            let width = (self.size.width * (f64::from(dpi) / 96.0)) as i32;
-            let height = (self.size.height * (f64::from(dpi) / 96.0)) as i32;
+            let size = size_to_pixels(self.size, f64::from(dpi));
+            let (x, y) = match self.position {
+                Some(position) => {
+                    let position = point_to_pixels(position, f64::from(dpi));
+                    (position.x as i32, position.y as i32)
+                }
+                None => (CW_USEDEFAULT, CW_USEDEFAULT),
+            };
 
             let (hmenu, accels) = match self.menu {
                 Some(menu) => {
@@ -797,18 +1484,25 @@ impl WindowBuilder {
                 None => (0 as HMENU, None),
             };
             let mut dwExStyle = 0;
-            if self.present_strategy == PresentStrategy::Flip {
+            if present_strategy == PresentStrategy::Flip {
                 dwExStyle |= WS_EX_NOREDIRECTIONBITMAP;
             }
+            // a popup has no frame, and is not activated when it is clicked
+            let dwStyle = if popup {
+                dwExStyle |= WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE | WS_EX_TOPMOST;
+                WS_POPUP
+            } else {
+                window_style(self.dwStyle, resizable)
+            };
             let hwnd = create_window(
                 dwExStyle,
                 class_name.as_ptr(),
                 self.title.to_wide().as_ptr(),
-                self.dwStyle,
-                CW_USEDEFAULT,
-                CW_USEDEFAULT,
-                width,
-                height,
+                dwStyle,
+                x,
+                y,
+                size.width as i32,
+                size.height as i32,
                 0 as HWND,
                 hmenu,
                 0 as HINSTANCE,
@@ -862,8 +1556,69 @@ unsafe fn choose_adapter(factory: *mut IDXGIFactory2) -> *mut IDXGIAdapter {
     best_adapter
 }
 
+/// The window style `style`, changed to allow resizing or not.
+/// Paint an image in memory, with Direct2D on the GPU or in software.
+pub(crate) fn render_image(
+    backend: window::RenderBackend,
+    width: usize,
+    height: usize,
+    scale: f64,
+    paint: impl FnOnce(&mut Piet),
+) -> Result<window::CapturedImage, crate::Error> {
+    match backend {
+        window::RenderBackend::Software => unsafe {
+            paint::render_software_image(width, height, scale, paint).map_err(Into::into)
+        },
+        _ => render_device_image(width, height, scale, paint),
+    }
+}
+
+fn window_style(style: DWORD, resizable: bool) -> DWORD {
+    let resize_styles = WS_THICKFRAME | WS_MAXIMIZEBOX;
+    if resizable {
+        style | resize_styles
+    } else {
+        style & !resize_styles
+    }
+}
+
+/// The part of a window without a frame at the screen position `x`, `y`,
+/// where the `border` pixels at its edges resize it.
+fn border_hit_test(rect: &RECT, x: i32, y: i32, border: i32) -> LRESULT {
+    let left = x < rect.left + border;
+    let right = x >= rect.right - border;
+    let top = y < rect.top + border;
+    let bottom = y >= rect.bottom - border;
+    match (left, right, top, bottom) {
+        (true, _, true, _) => HTTOPLEFT,
+        (_, true, true, _) => HTTOPRIGHT,
+        (true, _, _, true) => HTBOTTOMLEFT,
+        (_, true, _, true) => HTBOTTOMRIGHT,
+        (true, ..) => HTLEFT,
+        (_, true, ..) => HTRIGHT,
+        (_, _, true, _) => HTTOP,
+        (.., true) => HTBOTTOM,
+        _ => HTCLIENT,
+    }
+}
+
+/// Have the system recompute the frame of the window, after its style or
+/// whether it shows a titlebar has changed.
+unsafe fn frame_changed(hwnd: HWND) {
+    let flags = SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE;
+    if SetWindowPos(hwnd, null_mut(), 0, 0, 0, 0, flags) == FALSE {
+        warn!("failed to update the window frame");
+    }
+}
+
+/// The error for the last failed win32 call on this thread.
+unsafe fn last_error() -> Error {
+    Error::Hr(HRESULT_FROM_WIN32(GetLastError()))
+}
+
 unsafe fn create_dcomp_state(
     present_strategy: PresentStrategy,
+    transparent: bool,
     hwnd: HWND,
 ) -> Result<Option<DCompState>, Error> {
     if present_strategy == PresentStrategy::Hwnd {
@@ -905,7 +1660,11 @@ unsafe fn create_dcomp_state(
             BufferCount: bufs,
             Scaling: DXGI_SCALING_STRETCH,
             SwapEffect: swap_effect,
-            AlphaMode: DXGI_ALPHA_MODE_IGNORE,
+            AlphaMode: if transparent {
+                DXGI_ALPHA_MODE_PREMULTIPLIED
+            } else {
+                DXGI_ALPHA_MODE_IGNORE
+            },
             Flags: 0,
         };
         let mut swap_chain: *mut IDXGISwapChain1 = null_mut();
@@ -968,6 +1727,22 @@ pub(crate) unsafe extern "system" fn win_proc_dispatch(
     }
 }
 
+/// The window procedure of child surfaces.
+///
+/// A surface's `GWLP_USERDATA` is nonzero while it passes the mouse through;
+/// `HTTRANSPARENT` sends the mouse to the window below, which is the parent.
+pub(crate) unsafe extern "system" fn child_win_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if msg == WM_NCHITTEST && GetWindowLongPtrW(hwnd, GWLP_USERDATA) != 0 {
+        return HTTRANSPARENT as LRESULT;
+    }
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
+
 /// Create a window (same parameters as CreateWindowExW) with associated WndProc.
 #[allow(clippy::too_many_arguments)]
 unsafe fn create_window(
@@ -1025,14 +1800,33 @@ impl Clone for WindowHandle {
     }
 }
 
+impl WindowState {
+    fn show_now(&self) {
+        let hwnd = self.hwnd.get();
+        unsafe {
+            ShowWindow(hwnd, self.show_cmd);
+            UpdateWindow(hwnd);
+        }
+    }
+}
+
 impl WindowHandle {
+    /// Show the window, once it has been painted.
+    ///
+    /// The window shows when its first paint completes, or after
+    /// `FIRST_PAINT_TIMEOUT` if that takes too long.
     pub fn show(&self) {
         if let Some(w) = self.state.upgrade() {
-            let hwnd = w.hwnd.get();
-            unsafe {
-                ShowWindow(hwnd, SW_SHOWNORMAL);
-                UpdateWindow(hwnd);
+            let mut deferred = w.deferred_show.get();
+            if deferred.request_show() {
+                let hwnd = w.hwnd.get();
+                let timeout = FIRST_PAINT_TIMEOUT.as_millis() as UINT;
+                unsafe {
+                    SetTimer(hwnd, FIRST_PAINT_TIMER_ID, timeout, None);
+                    PostMessageW(hwnd, XI_FIRST_PAINT, 0, 0);
+                }
             }
+            w.deferred_show.set(deferred);
         }
     }
 
@@ -1051,6 +1845,103 @@ impl WindowHandle {
         log::warn!("bring_to_front_and_focus not yet implemented on windows");
     }
 
+    pub fn set_size(&self, size: Size) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            let size = size_to_pixels(size, f64::from(w.dpi.get()));
+            let flags = SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE;
+            unsafe {
+                let (width, height) = (size.width as i32, size.height as i32);
+                if SetWindowPos(hwnd, null_mut(), 0, 0, width, height, flags) == FALSE {
+                    warn!("failed to set window size");
+                }
+            }
+        }
+    }
+
+    pub fn get_size(&self) -> Size {
+        self.get_window_rect()
+            .map(|rect| {
+                let size = Size::new(
+                    f64::from(rect.right - rect.left),
+                    f64::from(rect.bottom - rect.top),
+                );
+                size_to_px(size, f64::from(self.get_dpi()))
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn set_position(&self, position: Point) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            let position = point_to_pixels(position, f64::from(w.dpi.get()));
+            let flags = SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE;
+            unsafe {
+                let (x, y) = (position.x as i32, position.y as i32);
+                if SetWindowPos(hwnd, null_mut(), x, y, 0, 0, flags) == FALSE {
+                    warn!("failed to set window position");
+                }
+            }
+        }
+    }
+
+    pub fn get_position(&self) -> Point {
+        self.get_window_rect()
+            .map(|rect| {
+                let position = Point::new(f64::from(rect.left), f64::from(rect.top));
+                point_to_px(position, f64::from(self.get_dpi()))
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn to_screen(&self, point: Point) -> Point {
+        let hwnd = match self.get_hwnd() {
+            Some(hwnd) if !hwnd.is_null() => hwnd,
+            _ => return point,
+        };
+        let dpi = f64::from(self.get_dpi());
+        let pixels = point_to_pixels(point, dpi);
+        let mut screen = POINT {
+            x: pixels.x.round() as i32,
+            y: pixels.y.round() as i32,
+        };
+        unsafe {
+            if ClientToScreen(hwnd, &mut screen) == FALSE {
+                warn!("failed to convert a point to screen coordinates");
+                return point;
+            }
+        }
+        point_to_px(Point::new(f64::from(screen.x), f64::from(screen.y)), dpi)
+    }
+
+    pub fn set_window_state(&self, state: window::WindowState) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            let cmd = match state {
+                window::WindowState::Maximized => SW_MAXIMIZE,
+                window::WindowState::Minimized => SW_MINIMIZE,
+                window::WindowState::Restored => SW_RESTORE,
+            };
+            unsafe {
+                ShowWindow(hwnd, cmd);
+            }
+        }
+    }
+
+    /// The bounds of the window, in physical pixels in screen coordinates.
+    fn get_window_rect(&self) -> Option<RECT> {
+        let w = self.state.upgrade()?;
+        let hwnd = w.hwnd.get();
+        unsafe {
+            let mut rect = mem::zeroed();
+            if GetWindowRect(hwnd, &mut rect) == FALSE {
+                warn!("failed to get window rect");
+                return None;
+            }
+            Some(rect)
+        }
+    }
+
     pub fn invalidate(&self) {
         if let Some(w) = self.state.upgrade() {
             let hwnd = w.hwnd.get();
@@ -1060,6 +1951,25 @@ impl WindowHandle {
         }
     }
 
+    pub fn invalidate_rect(&self, rect: Rect) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            let dpi = f64::from(w.dpi.get());
+            let origin = point_to_pixels(rect.origin(), dpi);
+            let size = size_to_pixels(rect.size(), dpi);
+            let rect = Rect::from_origin_size(origin, size).expand();
+            let rect = RECT {
+                left: rect.x0 as LONG,
+                top: rect.y0 as LONG,
+                right: rect.x1 as LONG,
+                bottom: rect.y1 as LONG,
+            };
+            unsafe {
+                InvalidateRect(hwnd, &rect, FALSE);
+            }
+        }
+    }
+
     /// Set the title for this menu.
     pub fn set_title(&self, title: &str) {
         if let Some(w) = self.state.upgrade() {
@@ -1072,11 +1982,43 @@ impl WindowHandle {
         }
     }
 
-    // TODO: Implement this
-    pub fn show_titlebar(&self, _show_titlebar: bool) {}
+    pub fn set_background_color(&self, color: Color) {
+        if let Some(w) = self.state.upgrade() {
+            w.background.set(Some(color_to_colorref(&color)));
+            let hwnd = w.hwnd.get();
+            unsafe {
+                // erase, so that the new color shows until the next paint
+                InvalidateRect(hwnd, null(), TRUE);
+            }
+        }
+    }
+
+    pub fn show_titlebar(&self, show_titlebar: bool) {
+        if let Some(w) = self.state.upgrade() {
+            w.show_titlebar.set(show_titlebar);
+            unsafe {
+                frame_changed(w.hwnd.get());
+            }
+        }
+    }
 
-    // TODO: Implement this
-    pub fn resizable(&self, _resizable: bool) {}
+    pub fn resizable(&self, resizable: bool) {
+        if let Some(w) = self.state.upgrade() {
+            w.resizable.set(resizable);
+            let hwnd = w.hwnd.get();
+            unsafe {
+                let style = GetWindowLongW(hwnd, GWL_STYLE) as DWORD;
+                SetWindowLongW(hwnd, GWL_STYLE, window_style(style, resizable) as LONG);
+                frame_changed(hwnd);
+            }
+        }
+    }
+
+    pub fn begin_move_drag(&self) {
+        if let Some(w) = self.state.upgrade() {
+            w.move_drag.set(true);
+        }
+    }
 
     pub fn set_menu(&self, menu: Menu) {
         let accels = menu.accels();
@@ -1120,14 +2062,14 @@ impl WindowHandle {
     /// Request a timer event.
     ///
     /// The return value is an identifier.
-    pub fn request_timer(&self, deadline: std::time::Instant) -> TimerToken {
-        let (id, elapse) = self.get_timer_slot(deadline);
-        let id = self
-            .get_hwnd()
-            // we reuse timer ids; if this is greater than u32::max we have a problem.
-            .map(|hwnd| unsafe { SetTimer(hwnd, id.into_raw() as usize, elapse, None) as u64 })
-            .unwrap_or(0);
-        TimerToken::from_raw(id)
+    pub fn request_timer(&self, deadline: Instant) -> TimerToken {
+        let token = TimerToken::next();
+        if let Some(idle) = self.get_idle_handle() {
+            timer::schedule(token, deadline, move |token, deadline| {
+                idle.add_timer(token, deadline)
+            });
+        }
+        token
     }
 
     /// Set the cursor icon.
@@ -1138,6 +2080,41 @@ impl WindowHandle {
         }
     }
 
+    pub fn set_mouse_capture(&self, capture: bool) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            // this is updated first, so that releasing the capture isn't
+            // reported back as losing it
+            w.mouse_captured.set(capture);
+            unsafe {
+                if capture {
+                    SetCapture(hwnd);
+                } else if GetCapture() == hwnd {
+                    ReleaseCapture();
+                }
+            }
+        }
+    }
+
+    pub fn set_ime_cursor_area(&self, rect: Rect) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            let dpi = f64::from(w.dpi.get());
+            let origin = point_to_pixels(rect.origin(), dpi);
+            let size = size_to_pixels(rect.size(), dpi);
+            let rect = Rect::from_origin_size(origin, size).expand();
+            let area = RECT {
+                left: rect.x0 as LONG,
+                top: rect.y0 as LONG,
+                right: rect.x1 as LONG,
+                bottom: rect.y1 as LONG,
+            };
+            if let Some(ime) = ImeContext::get(hwnd) {
+                ime.set_cursor_area(area);
+            }
+        }
+    }
+
     //FIXME: these two methods will be reworked to avoid reentrancy problems.
     // Currently, calling it may result in important messages being dropped.
     /// Prompt the user to chose a file to open.
@@ -1146,9 +2123,9 @@ impl WindowHandle {
     pub fn open_file_sync(&mut self, options: FileDialogOptions) -> Option<FileInfo> {
         let hwnd = self.get_hwnd()?;
         unsafe {
-            get_file_dialog_path(hwnd, FileDialogType::Open, options)
+            get_file_dialog_paths(hwnd, FileDialogType::Open, options)
                 .ok()
-                .map(|s| FileInfo { path: s.into() })
+                .and_then(|paths| FileInfo::from_paths(paths.into_iter().map(Into::into).collect()))
         }
     }
 
@@ -1158,11 +2135,9 @@ impl WindowHandle {
     pub fn save_as_sync(&mut self, options: FileDialogOptions) -> Option<FileInfo> {
         let hwnd = self.get_hwnd()?;
         unsafe {
-            get_file_dialog_path(hwnd, FileDialogType::Save, options)
+            get_file_dialog_paths(hwnd, FileDialogType::Save, options)
                 .ok()
-                .map(|os_str| FileInfo {
-                    path: os_str.into(),
-                })
+                .and_then(|paths| FileInfo::from_paths(paths.into_iter().map(Into::into).collect()))
         }
     }
 
@@ -1188,6 +2163,20 @@ impl WindowHandle {
         }
     }
 
+    pub fn render_backend(&self) -> window::RenderBackend {
+        self.state
+            .upgrade()
+            .map(|w| w.render_backend)
+            .unwrap_or(window::RenderBackend::Auto)
+    }
+
+    /// Replace the accessibility tree, telling UI Automation about `events`.
+    pub fn update_access_tree(&self, tree: AccessNode, events: &[AccessEvent]) {
+        if let Some(w) = self.state.upgrade() {
+            w.access.update(tree, events, w.dpi.get());
+        }
+    }
+
     /// Get the dpi of the window.
     pub fn get_dpi(&self) -> f32 {
         if let Some(w) = self.state.upgrade() {
@@ -1197,6 +2186,105 @@ impl WindowHandle {
         }
     }
 
+    pub fn get_scale(&self) -> Scale {
+        Scale::from_dpi(f64::from(self.get_dpi()))
+    }
+
+    pub fn create_child_surface(&self) -> Result<ChildSurface, Error> {
+        let parent = match self.get_hwnd() {
+            Some(hwnd) if !hwnd.is_null() => hwnd,
+            _ => return Err(Error::NullHwnd),
+        };
+        let class_name = CHILD_CLASS_NAME.to_wide();
+        let hwnd = unsafe {
+            CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                null(),
+                WS_CHILD | WS_CLIPSIBLINGS,
+                0,
+                0,
+                0,
+                0,
+                parent,
+                null_mut(),
+                null_mut(),
+                null_mut(),
+            )
+        };
+        if hwnd.is_null() {
+            return Err(Error::NullHwnd);
+        }
+        Ok(ChildSurface {
+            hwnd,
+            parent: self.clone(),
+        })
+    }
+
+    pub fn capture_image(&self) -> Result<window::CapturedImage, Error> {
+        let hwnd = match self.get_hwnd() {
+            Some(hwnd) if !hwnd.is_null() => hwnd,
+            _ => return Err(Error::NullHwnd),
+        };
+        unsafe {
+            let mut rect: RECT = mem::zeroed();
+            if GetClientRect(hwnd, &mut rect) == 0 {
+                return Err(last_error());
+            }
+            let width = rect.right - rect.left;
+            let height = rect.bottom - rect.top;
+
+            let hdc = GetDC(hwnd);
+            let mem_dc = CreateCompatibleDC(hdc);
+            let bitmap = CreateCompatibleBitmap(hdc, width, height);
+            let old = SelectObject(mem_dc, bitmap as HGDIOBJ);
+            // the full content flag is what includes DirectComposition content
+            const PW_RENDERFULLCONTENT: UINT = 0x2;
+            let printed = PrintWindow(hwnd, mem_dc, PW_CLIENTONLY | PW_RENDERFULLCONTENT);
+            SelectObject(mem_dc, old);
+
+            let mut info: BITMAPINFO = mem::zeroed();
+            info.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as DWORD;
+            info.bmiHeader.biWidth = width;
+            // a negative height is a bitmap with its rows from the top
+            info.bmiHeader.biHeight = -height;
+            info.bmiHeader.biPlanes = 1;
+            info.bmiHeader.biBitCount = 32;
+            info.bmiHeader.biCompression = BI_RGB;
+            let mut pixels = vec![0u8; width as usize * height as usize * 4];
+            let rows = if printed == 0 {
+                0
+            } else {
+                GetDIBits(
+                    mem_dc,
+                    bitmap,
+                    0,
+                    height as UINT,
+                    pixels.as_mut_ptr() as *mut c_void,
+                    &mut info,
+                    DIB_RGB_COLORS,
+                )
+            };
+            let result = if rows == 0 { Err(last_error()) } else { Ok(()) };
+
+            DeleteObject(bitmap as HGDIOBJ);
+            DeleteDC(mem_dc);
+            ReleaseDC(hwnd, hdc);
+            result?;
+
+            // the bitmap is BGRA, without alpha
+            for px in pixels.chunks_mut(4) {
+                px.swap(0, 2);
+                px[3] = 0xff;
+            }
+            Ok(window::CapturedImage {
+                pixels,
+                width: width as usize,
+                height: height as usize,
+            })
+        }
+    }
+
     /// Convert a dimension in px units to physical pixels (rounding).
     pub fn px_to_pixels(&self, x: f32) -> i32 {
         (x * self.get_dpi() * (1.0 / 96.0)).round() as i32
@@ -1218,26 +2306,6 @@ impl WindowHandle {
         let scale = 96.0 / self.get_dpi();
         ((x.into() as f32) * scale, (y.into() as f32) * scale)
     }
-
-    /// Allocate a timer slot.
-    ///
-    /// Returns an id and an elapsed time in ms
-    fn get_timer_slot(&self, deadline: std::time::Instant) -> (TimerToken, u32) {
-        if let Some(w) = self.state.upgrade() {
-            let mut timers = w.timers.lock().unwrap();
-            let id = timers.alloc();
-            let elapsed = timers.compute_elapsed(deadline);
-            (id, elapsed)
-        } else {
-            (TimerToken::INVALID, 0)
-        }
-    }
-
-    fn free_timer_slot(&self, token: TimerToken) {
-        if let Some(w) = self.state.upgrade() {
-            w.timers.lock().unwrap().free(token)
-        }
-    }
 }
 
 // There is a tiny risk of things going wrong when hwnd is sent across threads.
@@ -1270,6 +2338,17 @@ impl IdleHandle {
         queue.push(IdleKind::Token(token));
     }
 
+    /// Deliver a timer that is due, from the timer thread.
+    fn add_timer(&self, token: TimerToken, deadline: Instant) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.is_empty() {
+            unsafe {
+                PostMessageW(self.hwnd, XI_RUN_IDLE, 0, 0);
+            }
+        }
+        queue.push(IdleKind::Timer(token, deadline));
+    }
+
     fn invalidate(&self) {
         unsafe {
             InvalidateRect(self.hwnd, null(), FALSE);
@@ -1277,6 +2356,36 @@ impl IdleHandle {
     }
 }
 
+/// Convert a `RECT` in physical pixels to a `Rect` in px units.
+fn rect_to_px(rect: RECT, dpi: f32) -> Rect {
+    let dpi = f64::from(dpi);
+    let origin = point_to_px(Point::new(rect.left.into(), rect.top.into()), dpi);
+    let size = Size::new(
+        (rect.right - rect.left).into(),
+        (rect.bottom - rect.top).into(),
+    );
+    Rect::from_origin_size(origin, size_to_px(size, dpi))
+}
+
+/// The whole client area of the window, in px units.
+unsafe fn client_rect_px(hwnd: HWND, dpi: f32) -> Rect {
+    let mut rect = mem::zeroed();
+    if GetClientRect(hwnd, &mut rect) == 0 {
+        warn!("GetClientRect failed.");
+    }
+    rect_to_px(rect, dpi)
+}
+
+/// The part of the window that needs painting, in px units.
+unsafe fn update_rect_px(hwnd: HWND, dpi: f32) -> Rect {
+    let mut rect = mem::zeroed();
+    if GetUpdateRect(hwnd, &mut rect, FALSE) == 0 {
+        // nothing was invalidated, as when painting is forced
+        return client_rect_px(hwnd, dpi);
+    }
+    rect_to_px(rect, dpi)
+}
+
 /// Casts render target to hwnd variant.
 unsafe fn cast_to_hwnd(dc: &DeviceContext) -> Option<HwndRenderTarget> {
     dc.get_comptr()
@@ -1293,3 +2402,33 @@ impl Default for WindowHandle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_follow_the_builder() {
+        let style = window_style(WS_OVERLAPPEDWINDOW, false);
+        assert_eq!(style & (WS_THICKFRAME | WS_MAXIMIZEBOX), 0);
+        assert_eq!(style & WS_CAPTION, WS_CAPTION);
+        assert_eq!(window_style(style, true), WS_OVERLAPPEDWINDOW);
+    }
+
+    #[test]
+    fn edges_resize_a_window_without_a_frame() {
+        let rect = RECT {
+            left: 100,
+            top: 100,
+            right: 300,
+            bottom: 200,
+        };
+        assert_eq!(border_hit_test(&rect, 200, 150, 8), HTCLIENT);
+        assert_eq!(border_hit_test(&rect, 102, 150, 8), HTLEFT);
+        assert_eq!(border_hit_test(&rect, 299, 150, 8), HTRIGHT);
+        assert_eq!(border_hit_test(&rect, 200, 100, 8), HTTOP);
+        assert_eq!(border_hit_test(&rect, 200, 195, 8), HTBOTTOM);
+        assert_eq!(border_hit_test(&rect, 101, 101, 8), HTTOPLEFT);
+        assert_eq!(border_hit_test(&rect, 295, 199, 8), HTBOTTOMRIGHT);
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion between display points and physical pixels.
+
+use crate::kurbo::{Insets, Point, Rect, Size, Vec2};
+
+/// The number of physical pixels in a display point, on each axis.
+///
+/// Display points ("dp") are the units that windows are laid out and
+/// painted in; they are resolution independent, with 96 of them to the
+/// inch on a typical desktop display. Physical pixels ("px") are the
+/// pixels of the display.
+///
+/// A window's scale can change, for instance when it moves to a display
+/// with a different resolution; the [`WinHandler`] is told with
+/// [`WinHandler::scale`].
+///
+/// [`WinHandler`]: trait.WinHandler.html
+/// [`WinHandler::scale`]: trait.WinHandler.html#method.scale
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scale {
+    x: f64,
+    y: f64,
+}
+
+/// A geometric value that can be converted between display points and
+/// physical pixels.
+pub trait Scalable {
+    /// Convert from display points to physical pixels.
+    fn to_px(&self, scale: Scale) -> Self;
+    /// Convert from physical pixels to display points.
+    fn to_dp(&self, scale: Scale) -> Self;
+}
+
+impl Scale {
+    /// A scale of one pixel per display point.
+    pub const IDENTITY: Scale = Scale { x: 1.0, y: 1.0 };
+
+    /// Create a scale with `x` and `y` pixels per display point on each axis.
+    pub fn new(x: f64, y: f64) -> Scale {
+        Scale { x, y }
+    }
+
+    /// The scale for a display with `dpi` pixels per inch on both axes.
+    pub fn from_dpi(dpi: f64) -> Scale {
+        let scale = dpi / 96.0;
+        Scale::new(scale, scale)
+    }
+
+    /// The number of pixels per display point horizontally.
+    pub fn x(self) -> f64 {
+        self.x
+    }
+
+    /// The number of pixels per display point vertically.
+    pub fn y(self) -> f64 {
+        self.y
+    }
+
+    /// Convert `value` from display points to physical pixels.
+    pub fn to_px<T: Scalable>(self, value: &T) -> T {
+        value.to_px(self)
+    }
+
+    /// Convert `value` from physical pixels to display points.
+    pub fn to_dp<T: Scalable>(self, value: &T) -> T {
+        value.to_dp(self)
+    }
+
+    /// Convert a point to physical pixels, rounded to the nearest pixel.
+    pub fn to_px_point_rounded(self, point: Point) -> Point {
+        let point = point.to_px(self);
+        Point::new(point.x.round(), point.y.round())
+    }
+
+    /// Convert a rect to physical pixels, moving each edge to the nearest
+    /// pixel boundary.
+    ///
+    /// Since each edge is rounded on its own, rects that share an edge in
+    /// display points still share it in pixels, so they neither overlap
+    /// nor leave a gap.
+    pub fn to_px_rect_rounded(self, rect: Rect) -> Rect {
+        let rect = rect.to_px(self);
+        Rect::new(
+            rect.x0.round(),
+            rect.y0.round(),
+            rect.x1.round(),
+            rect.y1.round(),
+        )
+    }
+
+    /// Convert a rect to physical pixels, moving each edge outwards to a
+    /// pixel boundary, so that the result covers every pixel that the rect
+    /// touches.
+    pub fn to_px_rect_expanded(self, rect: Rect) -> Rect {
+        let rect = rect.to_px(self);
+        Rect::new(
+            rect.x0.floor(),
+            rect.y0.floor(),
+            rect.x1.ceil(),
+            rect.y1.ceil(),
+        )
+    }
+
+    /// Move the edges of `rect`, in display points, to the nearest physical
+    /// pixel boundaries.
+    ///
+    /// Filling the result covers whole pixels, without blurry edges.
+    pub fn snap_rect(self, rect: Rect) -> Rect {
+        self.to_px_rect_rounded(rect).to_dp(self)
+    }
+
+    /// Move the edges of `rect`, in display points, so that a line of
+    /// `width` display points stroked along them covers whole pixels.
+    ///
+    /// A line is drawn centered on its path, so a line that is an odd number
+    /// of pixels wide is only crisp if its path runs through the middle of
+    /// the pixels.
+    pub fn snap_stroke_rect(self, rect: Rect, width: f64) -> Rect {
+        let px = self.to_px_rect_rounded(rect);
+        let offset = |width_px: f64| {
+            if width_px.round().max(1.0) as i64 % 2 == 1 {
+                0.5
+            } else {
+                0.0
+            }
+        };
+        let dx = offset(width * self.x);
+        let dy = offset(width * self.y);
+        Rect::new(px.x0 + dx, px.y0 + dy, px.x1 - dx, px.y1 - dy).to_dp(self)
+    }
+}
+
+impl Default for Scale {
+    fn default() -> Scale {
+        Scale::IDENTITY
+    }
+}
+
+impl Scalable for Vec2 {
+    fn to_px(&self, scale: Scale) -> Vec2 {
+        Vec2::new(self.x * scale.x, self.y * scale.y)
+    }
+
+    fn to_dp(&self, scale: Scale) -> Vec2 {
+        Vec2::new(self.x / scale.x, self.y / scale.y)
+    }
+}
+
+impl Scalable for Point {
+    fn to_px(&self, scale: Scale) -> Point {
+        Point::new(self.x * scale.x, self.y * scale.y)
+    }
+
+    fn to_dp(&self, scale: Scale) -> Point {
+        Point::new(self.x / scale.x, self.y / scale.y)
+    }
+}
+
+impl Scalable for Size {
+    fn to_px(&self, scale: Scale) -> Size {
+        Size::new(self.width * scale.x, self.height * scale.y)
+    }
+
+    fn to_dp(&self, scale: Scale) -> Size {
+        Size::new(self.width / scale.x, self.height / scale.y)
+    }
+}
+
+impl Scalable for Rect {
+    fn to_px(&self, scale: Scale) -> Rect {
+        Rect::new(
+            self.x0 * scale.x,
+            self.y0 * scale.y,
+            self.x1 * scale.x,
+            self.y1 * scale.y,
+        )
+    }
+
+    fn to_dp(&self, scale: Scale) -> Rect {
+        Rect::new(
+            self.x0 / scale.x,
+            self.y0 / scale.y,
+            self.x1 / scale.x,
+            self.y1 / scale.y,
+        )
+    }
+}
+
+impl Scalable for Insets {
+    fn to_px(&self, scale: Scale) -> Insets {
+        Insets::new(
+            self.x0 * scale.x,
+            self.y0 * scale.y,
+            self.x1 * scale.x,
+            self.y1 * scale.y,
+        )
+    }
+
+    fn to_dp(&self, scale: Scale) -> Insets {
+        Insets::new(
+            self.x0 / scale.x,
+            self.y0 / scale.y,
+            self.x1 / scale.x,
+            self.y1 / scale.y,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // kurbo's Rect and Insets don't implement PartialEq
+    fn rect(rect: Rect) -> (f64, f64, f64, f64) {
+        (rect.x0, rect.y0, rect.x1, rect.y1)
+    }
+
+    fn insets(insets: Insets) -> (f64, f64, f64, f64) {
+        (insets.x0, insets.y0, insets.x1, insets.y1)
+    }
+
+    #[test]
+    fn conversions() {
+        let scale = Scale::new(2.0, 1.5);
+        assert_eq!(Scale::from_dpi(144.0), Scale::new(1.5, 1.5));
+        assert_eq!(scale.to_px(&Point::new(3.0, 4.0)), Point::new(6.0, 6.0));
+        assert_eq!(scale.to_dp(&Size::new(6.0, 6.0)), Size::new(3.0, 4.0));
+        let r = Rect::new(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(rect(scale.to_dp(&scale.to_px(&r))), rect(r));
+        let i = Insets::uniform(2.0);
+        assert_eq!(
+            insets(scale.to_px(&i)),
+            insets(Insets::uniform_xy(4.0, 3.0))
+        );
+        assert_eq!(
+            scale.to_px_point_rounded(Point::new(0.3, 0.3)),
+            Point::new(1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn adjacent_rects_do_not_overlap() {
+        let scale = Scale::from_dpi(144.0);
+        // at 1.5x, none of these edges fall on pixel boundaries
+        let width = 0.7;
+        let rects: Vec<Rect> = (0..6)
+            .map(|i| Rect::new(i as f64 * width, 0.0, (i + 1) as f64 * width, 1.0))
+            .map(|rect| scale.to_px_rect_rounded(rect))
+            .collect();
+        for pair in rects.windows(2) {
+            assert_eq!(pair[0].x1, pair[1].x0);
+        }
+        for rect in &rects {
+            assert_eq!(rect.x0, rect.x0.round());
+            assert_eq!(rect.x1, rect.x1.round());
+        }
+        assert_eq!(rects[0].x0, 0.0);
+        assert_eq!(rects[5].x1, 6.0);
+    }
+
+    #[test]
+    fn expanded_rects_cover_every_pixel() {
+        let scale = Scale::new(2.0, 2.0);
+        let r = Rect::new(0.2, 0.8, 1.1, 1.6);
+        assert_eq!(
+            rect(scale.to_px_rect_expanded(r)),
+            rect(Rect::new(0.0, 1.0, 3.0, 4.0))
+        );
+    }
+
+    #[test]
+    fn snapping() {
+        let scale = Scale::from_dpi(144.0);
+        let snapped = scale.snap_rect(Rect::new(0.1, 0.1, 10.1, 10.1));
+        let px = scale.to_px(&snapped);
+        assert_eq!(rect(px), rect(Rect::new(0.0, 0.0, 15.0, 15.0)));
+
+        // a one point line at 1.5x is two pixels wide, so it goes on the
+        // pixel boundaries; at 1x it is one pixel wide, and goes between them
+        let r = Rect::new(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(rect(scale.snap_stroke_rect(r, 1.0)), rect(r));
+        let r = Scale::IDENTITY.snap_stroke_rect(r, 1.0);
+        assert_eq!(rect(r), rect(Rect::new(0.5, 0.5, 9.5, 9.5)));
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The monitors of the system, and the parts of the screen they show.
+
+use std::cmp::Ordering;
+
+use crate::common_util::rect_to_px;
+use crate::kurbo::{Point, Rect};
+use crate::platform::screen as platform;
+use crate::scale::Scale;
+
+/// A monitor, and where it is in the virtual screen.
+///
+/// The virtual screen is the space that all of the monitors show part of;
+/// its coordinates are those of [`WindowHandle::set_position`], in px units,
+/// whatever the scale of each monitor.
+///
+/// [`WindowHandle::set_position`]: struct.WindowHandle.html#method.set_position
+#[derive(Clone, Debug)]
+pub struct Monitor {
+    primary: bool,
+    rect: Rect,
+    work_rect: Rect,
+    scale: Scale,
+}
+
+// kurbo's Rect doesn't implement PartialEq, so this compares coordinates
+impl PartialEq for Monitor {
+    fn eq(&self, other: &Monitor) -> bool {
+        self.primary == other.primary
+            && coords(self.rect) == coords(other.rect)
+            && coords(self.work_rect) == coords(other.work_rect)
+            && self.scale == other.scale
+    }
+}
+
+fn coords(rect: Rect) -> (f64, f64, f64, f64) {
+    (rect.x0, rect.y0, rect.x1, rect.y1)
+}
+
+/// The screen of the system, which is shown by one or more monitors.
+pub struct Screen;
+
+impl Monitor {
+    /// Describe a monitor whose rects are in physical pixels, at the `dpi`
+    /// that windows are positioned with.
+    pub(crate) fn from_pixels(
+        primary: bool,
+        rect: Rect,
+        work_rect: Rect,
+        scale: Scale,
+        dpi: f64,
+    ) -> Monitor {
+        Monitor {
+            primary,
+            rect: rect_to_px(rect, dpi),
+            work_rect: rect_to_px(work_rect, dpi),
+            scale,
+        }
+    }
+
+    /// Whether this is the primary monitor, which shows the taskbar or the
+    /// menu bar.
+    pub fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    /// The part of the virtual screen that this monitor shows.
+    pub fn virtual_rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// The part of the virtual screen that windows can use on this monitor,
+    /// which leaves out the taskbar, the dock and the menu bar.
+    pub fn virtual_work_rect(&self) -> Rect {
+        self.work_rect
+    }
+
+    /// The number of physical pixels in a display point on this monitor.
+    pub fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    /// Convert a position relative to the top-left corner of this monitor's
+    /// work area to a position in the virtual screen.
+    pub fn to_virtual(&self, position: Point) -> Point {
+        self.work_rect.origin() + position.to_vec2()
+    }
+
+    /// Whether `point`, in the virtual screen, is on this monitor.
+    pub fn contains(&self, point: Point) -> bool {
+        self.rect.contains(point)
+    }
+}
+
+impl Screen {
+    /// The monitors of the system, with the primary one first, and the others
+    /// from left to right and top to bottom.
+    ///
+    /// This can be called before the application is launched, without a
+    /// window. It returns an empty list if the monitors can't be found.
+    pub fn get_monitors() -> Vec<Monitor> {
+        let mut monitors = platform::get_monitors();
+        sort_monitors(&mut monitors);
+        monitors
+    }
+
+    /// The monitor that shows `point`, in the virtual screen, if there is one.
+    pub fn monitor_at(point: Point) -> Option<Monitor> {
+        Screen::get_monitors()
+            .into_iter()
+            .find(|m| m.contains(point))
+    }
+}
+
+/// Put the primary monitor first, and the others from left to right and top
+/// to bottom, so that the order doesn't depend on the platform.
+fn sort_monitors(monitors: &mut [Monitor]) {
+    monitors.sort_by(|a, b| {
+        b.primary.cmp(&a.primary).then_with(|| {
+            let (a, b) = (a.rect.origin(), b.rect.origin());
+            a.x.partial_cmp(&b.x)
+                .unwrap_or(Ordering::Equal)
+                .then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(primary: bool, rect: Rect) -> Monitor {
+        Monitor::from_pixels(primary, rect, rect, Scale::IDENTITY, 96.)
+    }
+
+    #[test]
+    fn pixels_are_converted_at_the_dpi() {
+        // 144 dpi is a scale factor of 1.5, and the work area leaves out a
+        // 60 pixel taskbar
+        let rect = Rect::new(-1920., 0., 0., 1080.);
+        let work_rect = Rect::new(-1920., 0., 0., 1020.);
+        let scaled = Monitor::from_pixels(false, rect, work_rect, Scale::new(1.5, 1.5), 144.);
+        assert_eq!(coords(scaled.virtual_rect()), (-1280., 0., 0., 720.));
+        assert_eq!(coords(scaled.virtual_work_rect()), (-1280., 0., 0., 680.));
+        assert_eq!(scaled.scale(), Scale::new(1.5, 1.5));
+
+        let nominal = Monitor::from_pixels(true, rect, work_rect, Scale::IDENTITY, 96.);
+        assert_eq!(coords(nominal.virtual_rect()), coords(rect));
+        assert_eq!(coords(nominal.virtual_work_rect()), coords(work_rect));
+    }
+
+    #[test]
+    fn positions_are_relative_to_the_work_area() {
+        let rect = Rect::new(1920., 0., 3840., 1080.);
+        let work_rect = Rect::new(1920., 40., 3840., 1080.);
+        let monitor = Monitor::from_pixels(false, rect, work_rect, Scale::IDENTITY, 96.);
+        assert_eq!(
+            monitor.to_virtual(Point::new(100., 50.)),
+            Point::new(2020., 90.)
+        );
+        assert!(monitor.contains(Point::new(2020., 90.)));
+        assert!(!monitor.contains(Point::new(100., 50.)));
+    }
+
+    #[test]
+    fn primary_monitor_comes_first() {
+        let left = monitor(false, Rect::new(-1280., 0., 0., 1024.));
+        let above = monitor(false, Rect::new(0., -1080., 1920., 0.));
+        let primary = monitor(true, Rect::new(0., 0., 1920., 1080.));
+        let below = monitor(false, Rect::new(0., 1080., 1920., 2160.));
+        let right = monitor(false, Rect::new(1920., 0., 3840., 1080.));
+
+        let mut monitors = vec![
+            right.clone(),
+            below.clone(),
+            primary.clone(),
+            left.clone(),
+            above.clone(),
+        ];
+        sort_monitors(&mut monitors);
+        assert_eq!(monitors, vec![primary, left, above, below, right]);
+    }
+}
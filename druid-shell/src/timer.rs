@@ -0,0 +1,352 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The timers of windows.
+//!
+//! Rather than a platform timer for each request, which is coarse on some
+//! platforms (`SetTimer` on Windows rounds up to about 16ms) and doesn't
+//! scale to many widgets blinking their cursors, the application has one
+//! thread that waits for the earliest of all of the deadlines. When a timer
+//! is due, that thread puts it on the idle queue of its window, and the
+//! window's thread delivers it, unless it was cancelled in the meantime.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use std::sync::{Condvar, Mutex, MutexGuard, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::window::{TimerToken, WinHandler};
+
+/// Timers that are due this soon after the first one fire along with it,
+/// so that they wake the window once.
+const COALESCE: Duration = Duration::from_millis(1);
+
+/// What fires a timer, by putting it on the idle queue of its window.
+type Wake = Box<dyn FnOnce(TimerToken, Instant) + Send>;
+
+struct Entry<W> {
+    deadline: Instant,
+    /// The order the timers were requested in, for those with the same
+    /// deadline.
+    seq: u64,
+    token: TimerToken,
+    wake: W,
+}
+
+/// The timers that are waiting for their deadlines, and those that are due
+/// and on their way to their windows.
+///
+/// This doesn't read the clock; the time is passed in.
+pub(crate) struct TimerQueue<W> {
+    heap: BinaryHeap<Reverse<Entry<W>>>,
+    next_seq: u64,
+    /// Timers in the heap that were cancelled; they are dropped when they
+    /// come to the top.
+    cancelled: HashSet<TimerToken>,
+    /// Timers that are due, but haven't been delivered.
+    in_flight: HashSet<TimerToken>,
+}
+
+impl<W> TimerQueue<W> {
+    pub(crate) fn new() -> TimerQueue<W> {
+        TimerQueue {
+            heap: BinaryHeap::new(),
+            next_seq: 0,
+            cancelled: HashSet::new(),
+            in_flight: HashSet::new(),
+        }
+    }
+
+    /// Add a timer, which is woken with `wake` once `deadline` has passed.
+    pub(crate) fn add(&mut self, token: TimerToken, deadline: Instant, wake: W) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(Reverse(Entry {
+            deadline,
+            seq,
+            token,
+            wake,
+        }));
+    }
+
+    /// When the next timers should be woken: the deadline of the first
+    /// one, or a little after, if others are due just after it.
+    pub(crate) fn wake_time(&mut self) -> Option<Instant> {
+        while let Some(Reverse(top)) = self.heap.peek() {
+            if !self.cancelled.remove(&top.token) {
+                break;
+            }
+            self.heap.pop();
+        }
+        let first = self.heap.peek()?.0.deadline;
+        let cancelled = &self.cancelled;
+        self.heap
+            .iter()
+            .map(|Reverse(entry)| entry)
+            .filter(|entry| entry.deadline <= first + COALESCE)
+            .filter(|entry| !cancelled.contains(&entry.token))
+            .map(|entry| entry.deadline)
+            .max()
+    }
+
+    /// Take the timers that are due at `now`, in the order of their
+    /// deadlines. They are in flight until they are [delivered].
+    ///
+    /// [delivered]: #method.deliver
+    pub(crate) fn pop_due(&mut self, now: Instant) -> Vec<(TimerToken, Instant, W)> {
+        let mut due = Vec::new();
+        while let Some(Reverse(top)) = self.heap.peek() {
+            if top.deadline > now {
+                break;
+            }
+            let entry = self.heap.pop().unwrap().0;
+            if !self.cancelled.remove(&entry.token) {
+                self.in_flight.insert(entry.token);
+                due.push((entry.token, entry.deadline, entry.wake));
+            }
+        }
+        due
+    }
+
+    /// Whether a timer that was taken by [`pop_due`] should be delivered:
+    /// it hasn't been cancelled, or delivered already.
+    ///
+    /// [`pop_due`]: #method.pop_due
+    pub(crate) fn deliver(&mut self, token: TimerToken) -> bool {
+        self.in_flight.remove(&token)
+    }
+
+    /// Cancel a timer, whether it is waiting or in flight, returning whether
+    /// it was either.
+    pub(crate) fn cancel(&mut self, token: TimerToken) -> bool {
+        if self.in_flight.remove(&token) {
+            return true;
+        }
+        let waiting = self.heap.iter().any(|Reverse(entry)| entry.token == token);
+        waiting && self.cancelled.insert(token)
+    }
+}
+
+impl<W> PartialEq for Entry<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<W> Eq for Entry<W> {}
+
+impl<W> PartialOrd for Entry<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W> Ord for Entry<W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.deadline, self.seq).cmp(&(other.deadline, other.seq))
+    }
+}
+
+/// The queue of the application, and its thread.
+struct Scheduler {
+    queue: Mutex<TimerQueue<Wake>>,
+    /// Notified when a timer is added, which may be due before the one
+    /// that the thread is waiting for.
+    added: Condvar,
+}
+
+lazy_static::lazy_static! {
+    static ref SCHEDULER: Scheduler = Scheduler {
+        queue: Mutex::new(TimerQueue::new()),
+        added: Condvar::new(),
+    };
+}
+
+impl Scheduler {
+    fn run(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            queue = match queue.wake_time() {
+                Some(wake_time) if wake_time <= now => {
+                    let due = queue.pop_due(now);
+                    // the windows' queues are locked when waking them
+                    drop(queue);
+                    for (token, deadline, wake) in due {
+                        wake(token, deadline);
+                    }
+                    self.queue.lock().unwrap()
+                }
+                Some(wake_time) => wait_precisely(&self.added, queue, wake_time - now),
+                None => self.added.wait(queue).unwrap(),
+            };
+        }
+    }
+}
+
+/// Wait on `condvar` until `timeout` has passed.
+///
+/// On Windows, the resolution of the system's timer is raised to a
+/// millisecond while waiting; otherwise, it is about 16ms.
+fn wait_precisely<'a, T>(
+    condvar: &Condvar,
+    guard: MutexGuard<'a, T>,
+    timeout: Duration,
+) -> MutexGuard<'a, T> {
+    #[cfg(target_os = "windows")]
+    unsafe {
+        winapi::um::timeapi::timeBeginPeriod(1);
+    }
+    let guard = condvar.wait_timeout(guard, timeout).unwrap().0;
+    #[cfg(target_os = "windows")]
+    unsafe {
+        winapi::um::timeapi::timeEndPeriod(1);
+    }
+    guard
+}
+
+/// Schedule a timer; once `deadline` has passed, `wake` is called from the
+/// timer thread, and should arrange for the timer to be [delivered] on the
+/// thread of its window.
+///
+/// [delivered]: fn.deliver.html
+pub(crate) fn schedule(
+    token: TimerToken,
+    deadline: Instant,
+    wake: impl FnOnce(TimerToken, Instant) + Send + 'static,
+) {
+    static START: Once = Once::new();
+    START.call_once(|| {
+        let spawned = thread::Builder::new()
+            .name("druid-shell timers".into())
+            .spawn(|| SCHEDULER.run());
+        if let Err(e) = spawned {
+            log::error!("failed to start the timer thread: {}", e);
+        }
+    });
+    SCHEDULER
+        .queue
+        .lock()
+        .unwrap()
+        .add(token, deadline, Box::new(wake));
+    SCHEDULER.added.notify_one();
+}
+
+/// Deliver a timer that was woken to its window's handler, unless it
+/// was cancelled, or has been delivered already.
+pub(crate) fn deliver(handler: &mut dyn WinHandler, token: TimerToken, deadline: Instant) {
+    if SCHEDULER.queue.lock().unwrap().deliver(token) {
+        let lateness = Instant::now().saturating_duration_since(deadline);
+        handler.timer_with_lateness(token, lateness);
+    }
+}
+
+/// Cancel a timer, returning whether it hadn't been delivered.
+pub(crate) fn cancel(token: TimerToken) -> bool {
+    SCHEDULER.queue.lock().unwrap().cancel(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn tokens(due: Vec<(TimerToken, Instant, ())>) -> Vec<u64> {
+        due.into_iter()
+            .map(|(token, _, ())| token.into_raw())
+            .collect()
+    }
+
+    fn add(queue: &mut TimerQueue<()>, token: u64, deadline: Instant) {
+        queue.add(TimerToken::from_raw(token), deadline, ());
+    }
+
+    #[test]
+    fn interleaved_deadlines_fire_in_order() {
+        let start = Instant::now();
+        let mut queue = TimerQueue::new();
+        add(&mut queue, 1, start + 30 * MS);
+        add(&mut queue, 2, start + 10 * MS);
+        add(&mut queue, 3, start + 20 * MS);
+        // the same deadline as 3, requested later
+        add(&mut queue, 4, start + 20 * MS);
+
+        assert_eq!(queue.wake_time(), Some(start + 10 * MS));
+        assert!(queue.pop_due(start + 9 * MS).is_empty());
+        assert_eq!(tokens(queue.pop_due(start + 10 * MS)), vec![2]);
+
+        // one added while others wait goes in its place
+        add(&mut queue, 5, start + 15 * MS);
+        assert_eq!(queue.wake_time(), Some(start + 15 * MS));
+        // woken late, everything due fires at once, still in order
+        assert_eq!(tokens(queue.pop_due(start + 40 * MS)), vec![5, 3, 4, 1]);
+        assert_eq!(queue.wake_time(), None);
+    }
+
+    #[test]
+    fn close_deadlines_are_coalesced() {
+        let start = Instant::now();
+        let mut queue = TimerQueue::new();
+        add(&mut queue, 1, start + 10 * MS);
+        add(&mut queue, 2, start + 10 * MS + MS / 2);
+        add(&mut queue, 3, start + 10 * MS + MS);
+        add(&mut queue, 4, start + 12 * MS);
+
+        // one wake for the first three, none of which fires early
+        let wake_time = queue.wake_time().unwrap();
+        assert_eq!(wake_time, start + 11 * MS);
+        assert_eq!(tokens(queue.pop_due(wake_time)), vec![1, 2, 3]);
+        assert_eq!(queue.wake_time(), Some(start + 12 * MS));
+    }
+
+    #[test]
+    fn a_timer_never_fires_twice() {
+        let start = Instant::now();
+        let mut queue = TimerQueue::new();
+        let token = TimerToken::from_raw(1);
+        add(&mut queue, 1, start + MS);
+
+        assert!(queue.pop_due(start).is_empty());
+        assert_eq!(tokens(queue.pop_due(start + MS)), vec![1]);
+        assert!(queue.pop_due(start + 2 * MS).is_empty());
+        assert!(queue.deliver(token));
+        assert!(!queue.deliver(token));
+        // and it can't be cancelled after it was delivered
+        assert!(!queue.cancel(token));
+    }
+
+    #[test]
+    fn cancelled_timers_are_not_delivered() {
+        let start = Instant::now();
+        let mut queue = TimerQueue::new();
+        add(&mut queue, 1, start + 10 * MS);
+        add(&mut queue, 2, start + 20 * MS);
+        add(&mut queue, 3, start + 30 * MS);
+
+        // cancelled while waiting, it is skipped
+        assert!(queue.cancel(TimerToken::from_raw(1)));
+        assert_eq!(queue.wake_time(), Some(start + 20 * MS));
+
+        // cancelled while in flight, it isn't delivered
+        assert_eq!(tokens(queue.pop_due(start + 20 * MS)), vec![2]);
+        assert!(queue.cancel(TimerToken::from_raw(2)));
+        assert!(!queue.deliver(TimerToken::from_raw(2)));
+
+        assert!(!queue.cancel(TimerToken::from_raw(4)));
+        assert_eq!(tokens(queue.pop_due(start + 30 * MS)), vec![3]);
+        assert!(queue.deliver(TimerToken::from_raw(3)));
+    }
+}
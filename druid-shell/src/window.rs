@@ -15,15 +15,20 @@
 //! Platform independent window types.
 
 use std::any::Any;
+use std::ops::Range;
 
+use crate::access::{AccessEvent, AccessNode};
 use crate::common_util::Counter;
 use crate::dialog::{FileDialogOptions, FileInfo};
+use crate::drag::DragEvent;
 use crate::error::Error;
 use crate::keyboard::{KeyEvent, KeyModifiers};
-use crate::kurbo::{Point, Size, Vec2};
+use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::menu::Menu;
-use crate::mouse::{Cursor, MouseEvent};
+use crate::mouse::{Cursor, MouseEvent, ScrollPhase};
+use crate::piet::Color;
 use crate::platform::window as platform;
+use crate::scale::Scale;
 
 // It's possible we'll want to make this type alias at a lower level,
 // see https://github.com/linebender/piet/pull/37 for more discussion.
@@ -92,6 +97,95 @@ impl IdleToken {
     }
 }
 
+/// The state of a window: whether it is maximized, minimized, or neither.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowState {
+    /// The window fills the screen, or the available space on it.
+    Maximized,
+    /// The window is hidden, and shown only in a dock or taskbar.
+    Minimized,
+    /// The window has its normal size and position.
+    Restored,
+}
+
+/// Where a window sits among the other windows, and how it behaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowLevel {
+    /// An ordinary window of the application.
+    AppWindow,
+    /// A small window that describes what is under the mouse. It has no
+    /// decorations, doesn't take focus, and stays above other windows.
+    Tooltip,
+    /// A window that pops up from a control, such as the list of a combo
+    /// box. It has no decorations, doesn't take focus, and stays above
+    /// other windows.
+    DropDown,
+}
+
+impl WindowLevel {
+    /// Whether this is one of the levels of a popup, which doesn't take
+    /// focus from the window that it was opened from.
+    pub fn is_popup(self) -> bool {
+        self != WindowLevel::AppWindow
+    }
+}
+
+impl Default for WindowLevel {
+    fn default() -> Self {
+        WindowLevel::AppWindow
+    }
+}
+
+/// How a window's contents are drawn.
+///
+/// On Windows, drawing is done with Direct2D, which can render either on the
+/// GPU or in software. Software rendering is usually much faster over remote
+/// desktop connections. On other platforms, drawing is always done in software.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderBackend {
+    /// Use software rendering in remote desktop sessions, and hardware
+    /// rendering otherwise.
+    Auto,
+    /// Render on the GPU, where possible.
+    Hardware,
+    /// Render on the CPU.
+    Software,
+}
+
+impl RenderBackend {
+    /// Paint an image of `width` by `height` pixels in memory with this
+    /// backend, without a window.
+    ///
+    /// `paint` draws in display points, which are `scale` pixels each.
+    /// `Auto` paints as a window outside of a remote desktop session would.
+    /// This is how the output of the backends can be compared headlessly.
+    pub fn render_image(
+        self,
+        width: usize,
+        height: usize,
+        scale: f64,
+        paint: impl FnOnce(&mut piet_common::Piet),
+    ) -> Result<CapturedImage, Error> {
+        platform::render_image(self.resolve(false), width, height, scale, paint)
+    }
+
+    /// The backend to use, given whether software rendering is preferred,
+    /// such as in a remote desktop session.
+    pub(crate) fn resolve(self, prefer_software: bool) -> RenderBackend {
+        match self {
+            RenderBackend::Auto if prefer_software => RenderBackend::Software,
+            RenderBackend::Auto => RenderBackend::Hardware,
+            other => other,
+        }
+    }
+}
+
+impl Default for RenderBackend {
+    fn default() -> Self {
+        RenderBackend::Auto
+    }
+}
+
 /// A handle to a platform window object.
 #[derive(Clone, Default)]
 pub struct WindowHandle(platform::WindowHandle);
@@ -101,6 +195,10 @@ impl WindowHandle {
     ///
     /// This is part of the initialization process; it should only be called
     /// once, when a window is first created.
+    ///
+    /// On Windows and GTK, the window becomes visible once its first paint
+    /// has completed, so that it doesn't appear without its content. If the
+    /// first paint takes too long, it becomes visible anyway.
     pub fn show(&self) {
         self.0.show()
     }
@@ -120,21 +218,91 @@ impl WindowHandle {
         self.0.show_titlebar(show_titlebar)
     }
 
+    /// Start moving the window with the mouse, as if it were being dragged
+    /// by its titlebar.
+    ///
+    /// This lets a window without a titlebar draw its own. It should be
+    /// called while handling the [`mouse_down`] of the left button; the
+    /// window then follows the mouse until the button is released, and
+    /// doesn't get a [`mouse_up`] for the press.
+    ///
+    /// [`mouse_down`]: trait.WinHandler.html#method.mouse_down
+    /// [`mouse_up`]: trait.WinHandler.html#method.mouse_up
+    pub fn begin_move_drag(&self) {
+        self.0.begin_move_drag()
+    }
+
     /// Bring this window to the front of the window stack and give it focus.
     pub fn bring_to_front_and_focus(&self) {
         self.0.bring_to_front_and_focus()
     }
 
+    /// Set the size of the window, in px units.
+    ///
+    /// The platform will resize the window, and the handler will get the
+    /// usual [`WinHandler::size()`] call once it has happened.
+    ///
+    /// [`WinHandler::size()`]: trait.WinHandler.html#method.size
+    pub fn set_size(&self, size: Size) {
+        self.0.set_size(size)
+    }
+
+    /// Get the size of the window, in px units.
+    pub fn get_size(&self) -> Size {
+        self.0.get_size()
+    }
+
+    /// Set the position of the window's top-left corner on the screen, in px units.
+    pub fn set_position(&self, position: Point) {
+        self.0.set_position(position)
+    }
+
+    /// Get the position of the window's top-left corner on the screen, in px units.
+    pub fn get_position(&self) -> Point {
+        self.0.get_position()
+    }
+
+    /// Convert a point in the window's content area to a point on the
+    /// screen, both in px units.
+    ///
+    /// This is where a popup should be positioned, to appear over the point.
+    pub fn to_screen(&self, point: Point) -> Point {
+        self.0.to_screen(point)
+    }
+
+    /// Maximize, minimize, or restore the window.
+    pub fn set_window_state(&self, state: WindowState) {
+        self.0.set_window_state(state)
+    }
+
     /// Request invalidation of the entire window contents.
     pub fn invalidate(&self) {
         self.0.invalidate()
     }
 
-    /// Set the title for this menu.
+    /// Request invalidation of a part of the window's contents, in display
+    /// points.
+    ///
+    /// The platform may combine the rects it is asked to invalidate, and may
+    /// ask for more than them to be painted.
+    pub fn invalidate_rect(&self, rect: Rect) {
+        self.0.invalidate_rect(rect)
+    }
+
+    /// Set the title of the window.
+    ///
+    /// In druid, a title set this way lasts until the window's own title
+    /// changes; the `SET_WINDOW_TITLE` command replaces the window's title.
     pub fn set_title(&self, title: &str) {
         self.0.set_title(title)
     }
 
+    /// Set the color the platform fills the window with before it is painted,
+    /// such as while it is being resized.
+    pub fn set_background_color(&self, color: Color) {
+        self.0.set_background_color(color)
+    }
+
     /// Set the top-level menu for this window.
     pub fn set_menu(&self, menu: Menu) {
         self.0.set_menu(menu.into_inner())
@@ -151,21 +319,61 @@ impl WindowHandle {
     /// return value is a token that can be used to associate the request
     /// with the handler call.
     ///
-    /// Note that this is not a precise timer. On Windows, the typical
-    /// resolution is around 10ms. Therefore, it's best used for things
-    /// like blinking a cursor or triggering tooltips, not for anything
-    /// requiring precision.
+    /// The timers of all of the windows are kept by one thread, which wakes
+    /// the window's thread at the deadline, within about a millisecond;
+    /// timers that are due at nearly the same time wake it once. The call
+    /// is never early, but it is late when the window's thread is busy; see
+    /// [`WinHandler::timer_with_lateness()`].
     ///
     /// [`WinHandler::timer()`]: trait.WinHandler.html#tymethod.timer
+    /// [`WinHandler::timer_with_lateness()`]: trait.WinHandler.html#method.timer_with_lateness
     pub fn request_timer(&self, deadline: std::time::Instant) -> TimerToken {
         self.0.request_timer(deadline)
     }
 
+    /// Cancel a timer that was requested with [`request_timer`].
+    ///
+    /// Once this returns, the timer won't be delivered, even if it is already
+    /// due. Returns `false` if it was delivered already, or was never
+    /// requested.
+    ///
+    /// [`request_timer`]: #method.request_timer
+    pub fn cancel_timer(&self, token: TimerToken) -> bool {
+        crate::timer::cancel(token)
+    }
+
     /// Set the cursor icon.
     pub fn set_cursor(&mut self, cursor: &Cursor) {
         self.0.set_cursor(cursor)
     }
 
+    /// Capture the mouse, or release it.
+    ///
+    /// While the mouse is captured, the window gets its events even when it
+    /// is outside of the window, with positions that are out of bounds, and
+    /// negative above and to the left. This is for a slider or a popup that
+    /// should see the button released wherever it is.
+    ///
+    /// This uses `SetCapture` on Windows and a seat grab on GTK. On macOS a
+    /// view already gets the events of a drag that starts in it, so this
+    /// does nothing there. If the capture is taken away, the handler is told
+    /// with [`WinHandler::mouse_capture_lost`].
+    ///
+    /// [`WinHandler::mouse_capture_lost`]: trait.WinHandler.html#method.mouse_capture_lost
+    pub fn set_mouse_capture(&self, capture: bool) {
+        self.0.set_mouse_capture(capture)
+    }
+
+    /// Set the area of the text cursor, in display points, so that the
+    /// input method can put its windows by it.
+    ///
+    /// This is where the candidates of a composition are listed; a text
+    /// field with focus should set it when a composition starts and as the
+    /// cursor moves during one.
+    pub fn set_ime_cursor_area(&self, rect: Rect) {
+        self.0.set_ime_cursor_area(rect)
+    }
+
     /// Prompt the user to chose a file to open.
     ///
     /// Blocks while the user picks the file.
@@ -192,6 +400,15 @@ impl WindowHandle {
         self.0.get_idle_handle().map(IdleHandle)
     }
 
+    /// The backend that is being used to draw the window's contents.
+    ///
+    /// This is never [`RenderBackend::Auto`] for a window that is open.
+    ///
+    /// [`RenderBackend::Auto`]: enum.RenderBackend.html#variant.Auto
+    pub fn render_backend(&self) -> RenderBackend {
+        self.0.render_backend()
+    }
+
     /// Get the dpi of the window.
     ///
     /// TODO: we want to migrate this from dpi (with 96 as nominal) to a scale
@@ -199,6 +416,114 @@ impl WindowHandle {
     pub fn get_dpi(&self) -> f32 {
         self.0.get_dpi()
     }
+
+    /// The number of physical pixels in a display point, in this window.
+    ///
+    /// This can change while the window is open; the [`WinHandler`] is told
+    /// with [`WinHandler::scale`].
+    ///
+    /// [`WinHandler`]: trait.WinHandler.html
+    /// [`WinHandler::scale`]: trait.WinHandler.html#method.scale
+    pub fn get_scale(&self) -> Scale {
+        self.0.get_scale()
+    }
+
+    /// Create a native surface inside this window, for content that is drawn
+    /// by something other than the window, such as an OpenGL renderer.
+    ///
+    /// The surface starts hidden, with an empty frame; it is destroyed when
+    /// the [`ChildSurface`] is dropped.
+    ///
+    /// On Windows this is a child window, and on GTK a `GLArea`. Child
+    /// surfaces are not yet supported on macOS, where this returns an error.
+    ///
+    /// [`ChildSurface`]: struct.ChildSurface.html
+    pub fn create_child_surface(&self) -> Result<ChildSurface, Error> {
+        self.0
+            .create_child_surface()
+            .map(ChildSurface)
+            .map_err(Into::into)
+    }
+
+    /// Read back what was last painted in the window's content area.
+    ///
+    /// The image is in physical pixels. It is read from the window's surface
+    /// on GTK, with `PrintWindow` on Windows, and from the window server on
+    /// macOS; child surfaces are included where the platform composites them.
+    pub fn capture_image(&self) -> Result<CapturedImage, Error> {
+        self.0.capture_image().map_err(Into::into)
+    }
+
+    /// Replace the window's accessibility tree, and tell screen readers
+    /// about the changes in `events`.
+    ///
+    /// The tree only needs to be given to the window once the
+    /// [`WinHandler`] has been told that it is used, with
+    /// [`WinHandler::accessibility_activated`]. It is exposed with UI
+    /// Automation on Windows; on the other platforms this does nothing yet.
+    ///
+    /// [`WinHandler`]: trait.WinHandler.html
+    /// [`WinHandler::accessibility_activated`]: trait.WinHandler.html#method.accessibility_activated
+    pub fn update_access_tree(&self, tree: AccessNode, events: &[AccessEvent]) {
+        self.0.update_access_tree(tree, events)
+    }
+}
+
+/// The contents of a window, as read back by [`WindowHandle::capture_image`].
+///
+/// [`WindowHandle::capture_image`]: struct.WindowHandle.html#method.capture_image
+#[derive(Debug, Clone)]
+pub struct CapturedImage {
+    /// The pixels, as premultiplied RGBA, one row after another from the top.
+    pub pixels: Vec<u8>,
+    /// The width, in pixels.
+    pub width: usize,
+    /// The height, in pixels.
+    pub height: usize,
+}
+
+/// A native surface embedded in a window.
+///
+/// It is drawn over the window's own contents, and is positioned by its
+/// [`set_frame`]. Created with [`WindowHandle::create_child_surface`].
+///
+/// [`set_frame`]: #method.set_frame
+/// [`WindowHandle::create_child_surface`]: struct.WindowHandle.html#method.create_child_surface
+pub struct ChildSurface(platform::ChildSurface);
+
+impl ChildSurface {
+    /// Move and resize the surface.
+    ///
+    /// The frame is in display points, relative to the top left of the
+    /// window's content area.
+    pub fn set_frame(&self, frame: Rect) {
+        self.0.set_frame(frame)
+    }
+
+    /// Show or hide the surface.
+    pub fn set_visible(&self, visible: bool) {
+        self.0.set_visible(visible)
+    }
+
+    /// Set whether mouse events over the surface go to the window, as if
+    /// the surface weren't there, instead of to the surface.
+    ///
+    /// This is `false` by default.
+    pub fn set_passthrough(&self, passthrough: bool) {
+        self.0.set_passthrough(passthrough)
+    }
+
+    /// The surface's window.
+    #[cfg(all(target_os = "windows", not(feature = "use_gtk")))]
+    pub fn hwnd(&self) -> winapi::shared::windef::HWND {
+        self.0.hwnd()
+    }
+
+    /// The surface's `GLArea`.
+    #[cfg(any(feature = "use_gtk", target_os = "linux"))]
+    pub fn gl_area(&self) -> &gtk::GLArea {
+        self.0.gl_area()
+    }
 }
 
 /// A builder type for creating new windows.
@@ -223,26 +548,93 @@ impl WindowBuilder {
         self.0.set_size(size)
     }
 
+    /// Set the smallest size that the user can resize the window to, in px units.
+    ///
+    /// This is the size of the content area, not including the titlebar or
+    /// the window frame.
+    pub fn set_min_size(&mut self, size: Size) {
+        self.0.set_min_size(size)
+    }
+
+    /// Set the window's initial position on the screen, in px units.
+    ///
+    /// If this is not set, the platform picks a position.
+    pub fn set_position(&mut self, position: Point) {
+        self.0.set_position(position)
+    }
+
+    /// Set the window's initial state.
+    pub fn set_window_state(&mut self, state: WindowState) {
+        self.0.set_window_state(state)
+    }
+
     /// Set whether the window should be resizable
     pub fn resizable(&mut self, resizable: bool) {
         self.0.resizable(resizable)
     }
 
     /// Set whether the window should have a titlebar and decorations
+    ///
+    /// A window without them can still be resized from its edges, if it is
+    /// [`resizable`], and it can be moved with
+    /// [`WindowHandle::begin_move_drag`].
+    ///
+    /// [`resizable`]: #method.resizable
+    /// [`WindowHandle::begin_move_drag`]: struct.WindowHandle.html#method.begin_move_drag
     pub fn show_titlebar(&mut self, show_titlebar: bool) {
         self.0.show_titlebar(show_titlebar)
     }
 
+    /// Set whether the parts of the window that aren't painted opaquely
+    /// show what is behind it.
+    ///
+    /// The window should be cleared with a transparent color. On Windows
+    /// this needs hardware rendering; the window is opaque with the
+    /// software [`RenderBackend`].
+    ///
+    /// [`RenderBackend`]: enum.RenderBackend.html
+    pub fn transparent(&mut self, transparent: bool) {
+        self.0.transparent(transparent)
+    }
+
+    /// Set the window's level. The default is [`WindowLevel::AppWindow`].
+    ///
+    /// A popup has no titlebar or decorations, whatever [`show_titlebar`]
+    /// says, and it is shown without taking focus.
+    ///
+    /// [`WindowLevel::AppWindow`]: enum.WindowLevel.html#variant.AppWindow
+    /// [`show_titlebar`]: #method.show_titlebar
+    pub fn set_level(&mut self, level: WindowLevel) {
+        self.0.set_level(level)
+    }
+
     /// Set the window's initial title.
     pub fn set_title(&mut self, title: impl Into<String>) {
         self.0.set_title(title)
     }
 
+    /// Set the window's initial background color, which the platform fills
+    /// the window with before it is painted.
+    pub fn set_background_color(&mut self, color: Color) {
+        self.0.set_background_color(color)
+    }
+
     /// Set the window's menu.
     pub fn set_menu(&mut self, menu: Menu) {
         self.0.set_menu(menu.into_inner())
     }
 
+    /// Set how the window's contents should be drawn.
+    ///
+    /// This only has an effect on platforms that support more than one
+    /// [`RenderBackend`]. The default is [`RenderBackend::Auto`].
+    ///
+    /// [`RenderBackend`]: enum.RenderBackend.html
+    /// [`RenderBackend::Auto`]: enum.RenderBackend.html#variant.Auto
+    pub fn set_render_backend(&mut self, backend: RenderBackend) {
+        self.0.set_render_backend(backend)
+    }
+
     /// Attempt to construct the platform window.
     ///
     /// If this fails, your application should exit.
@@ -270,10 +662,25 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn size(&mut self, width: u32, height: u32) {}
 
+    /// Called when the window's scale changes, for instance because it has
+    /// moved to a display with a different resolution.
+    ///
+    /// The platform usually follows this with a call to [`size`], since the
+    /// size of the window in physical pixels changes as well.
+    ///
+    /// [`size`]: #method.size
+    #[allow(unused_variables)]
+    fn scale(&mut self, scale: Scale) {}
+
     /// Request the handler to paint the window contents. Return value
     /// indicates whether window is animating, i.e. whether another paint
     /// should be scheduled for the next animation frame.
-    fn paint(&mut self, piet: &mut piet_common::Piet) -> bool;
+    ///
+    /// `invalid_rect` is the part of the window that needs painting, in
+    /// display points. The platform keeps what was painted before outside
+    /// of it, so the handler only needs to paint inside it, though it may
+    /// paint more.
+    fn paint(&mut self, piet: &mut piet_common::Piet, invalid_rect: Rect) -> bool;
 
     /// Called when the resources need to be rebuilt.
     ///
@@ -287,6 +694,12 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn command(&mut self, id: u32) {}
 
+    /// Called when a system setting that affects the user interface, such
+    /// as [`Application::prefers_reduced_motion`], may have changed.
+    ///
+    /// [`Application::prefers_reduced_motion`]: struct.Application.html#method.prefers_reduced_motion
+    fn system_settings_changed(&mut self) {}
+
     /// Called on a key down event.
     ///
     /// Return `true` if the event is handled.
@@ -300,6 +713,33 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn key_up(&mut self, event: KeyEvent) {}
 
+    /// Called when the user starts composing text from several key presses,
+    /// such as with a dead key for an accent, or with an input method.
+    ///
+    /// The key events of the composition are sent to [`key_down`] as well,
+    /// with [`is_composing`] set. The input method's windows, such as its
+    /// list of candidates, are put by the area set with
+    /// [`WindowHandle::set_ime_cursor_area`].
+    ///
+    /// This is backed by `WM_IME_*` messages on Windows, a `GtkIMContext`
+    /// on GTK, and `NSTextInputClient` on macOS.
+    ///
+    /// [`key_down`]: #method.key_down
+    /// [`is_composing`]: struct.KeyEvent.html#structfield.is_composing
+    /// [`WindowHandle::set_ime_cursor_area`]: struct.WindowHandle.html#method.set_ime_cursor_area
+    fn composition_start(&mut self) {}
+
+    /// Called when the text being composed changes; `text` is the whole of
+    /// it, and `cursor` is the range of the cursor in it, in bytes.
+    #[allow(unused_variables)]
+    fn composition_update(&mut self, text: &str, cursor: Range<usize>) {}
+
+    /// Called when a composition ends, with the text that it typed.
+    ///
+    /// The text is empty if the composition was cancelled.
+    #[allow(unused_variables)]
+    fn composition_end(&mut self, text: &str) {}
+
     /// Called on a mouse wheel event.
     ///
     /// The polarity is the amount to be added to the scroll position,
@@ -311,8 +751,32 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn wheel(&mut self, delta: Vec2, mods: KeyModifiers) {}
 
+    /// Called on a wheel event, with where it is in a scrolling gesture.
+    ///
+    /// `phase` is that of the user's gesture. Trackpads that scroll on
+    /// after the fingers are lifted send momentum events, whose phase is
+    /// `Ended` and whose `momentum_phase` is that of the momentum; for
+    /// other events it is `None`. Platforms and devices that don't report
+    /// phases, such as mouse wheels, give `Changed` and `None`. The default
+    /// implementation calls [`wheel`].
+    ///
+    /// [`wheel`]: #method.wheel
+    #[allow(unused_variables)]
+    fn wheel_with_phase(
+        &mut self,
+        delta: Vec2,
+        mods: KeyModifiers,
+        phase: ScrollPhase,
+        momentum_phase: Option<ScrollPhase>,
+    ) {
+        self.wheel(delta, mods)
+    }
+
     /// Called when a platform-defined zoom gesture occurs (such as pinching
     /// on the trackpad).
+    ///
+    /// The `delta` is an increment to a multiplicative scale factor: a zoom
+    /// from `scale` goes to `scale * (1.0 + delta)`.
     #[allow(unused_variables)]
     fn zoom(&mut self, delta: f64) {}
 
@@ -328,6 +792,46 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn mouse_up(&mut self, event: &MouseEvent) {}
 
+    /// Called when the window loses the mouse capture that it asked for with
+    /// [`WindowHandle::set_mouse_capture`], because another window or the
+    /// system took it, as when a system dialog opens during a drag.
+    ///
+    /// The window won't be told when the button is released, so whatever
+    /// the press started should be ended.
+    ///
+    /// [`WindowHandle::set_mouse_capture`]: struct.WindowHandle.html#method.set_mouse_capture
+    fn mouse_capture_lost(&mut self) {}
+
+    /// Called when something is dragged into the window.
+    ///
+    /// Return `true` if it could be dropped where it is, which the platform
+    /// shows with the drag cursor.
+    #[allow(unused_variables)]
+    fn drag_enter(&mut self, event: &DragEvent) -> bool {
+        false
+    }
+
+    /// Called when something that is dragged over the window moves.
+    ///
+    /// Return `true` if it could be dropped where it is now.
+    #[allow(unused_variables)]
+    fn drag_over(&mut self, event: &DragEvent) -> bool {
+        false
+    }
+
+    /// Called when something that was dragged over the window leaves it, or
+    /// the drag is cancelled.
+    fn drag_leave(&mut self) {}
+
+    /// Called when something that is dragged over the window is dropped.
+    ///
+    /// Return `true` if the drop was accepted, so that the application it
+    /// came from can tell whether it was.
+    #[allow(unused_variables)]
+    fn drag_drop(&mut self, event: &DragEvent) -> bool {
+        false
+    }
+
     /// Called on timer event.
     ///
     /// This is called at (approximately) the requested deadline by a
@@ -338,16 +842,44 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn timer(&mut self, token: TimerToken) {}
 
+    /// Called on timer event, with how long after its deadline it is.
+    ///
+    /// Each timer is delivered once. It is late when the window's thread was
+    /// busy at its deadline; something that repeats, like an animation, can
+    /// make up for it. The default implementation calls [`timer`].
+    ///
+    /// [`timer`]: #method.timer
+    #[allow(unused_variables)]
+    fn timer_with_lateness(&mut self, token: TimerToken, lateness: std::time::Duration) {
+        self.timer(token)
+    }
+
     /// Called when this window becomes the focused window.
     #[allow(unused_variables)]
     fn got_focus(&mut self) {}
 
-    /// Called when the window is being destroyed. Note that this happens
-    /// earlier in the sequence than drop (at WM_DESTROY, while the latter is
-    /// WM_NCDESTROY).
+    /// Called when the window is being destroyed.
+    ///
+    /// On all platforms, this is called once, after the last event for the
+    /// window and before the handler is dropped, while the window still
+    /// exists. Note that on Windows this happens earlier in the sequence than
+    /// drop (at WM_DESTROY, while the latter is WM_NCDESTROY).
+    ///
+    /// It is not called for windows that are still open when the application
+    /// quits; handlers that need to clean up should do it before quitting.
     #[allow(unused_variables)]
     fn destroy(&mut self) {}
 
+    /// Called when a screen reader, or another accessibility client, starts
+    /// to use the window.
+    ///
+    /// From then on, the handler should keep the window's accessibility tree
+    /// up to date, with [`WindowHandle::update_access_tree`].
+    ///
+    /// [`WindowHandle::update_access_tree`]: struct.WindowHandle.html#method.update_access_tree
+    #[allow(unused_variables)]
+    fn accessibility_activated(&mut self) {}
+
     /// Called when a idle token is requested by [`IdleHandle::schedule_idle()`] call.
     #[allow(unused_variables)]
     fn idle(&mut self, token: IdleToken) {}
@@ -361,3 +893,46 @@ impl From<platform::WindowHandle> for WindowHandle {
         WindowHandle(src)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_render_backend() {
+        assert_eq!(RenderBackend::Auto.resolve(true), RenderBackend::Software);
+        assert_eq!(RenderBackend::Auto.resolve(false), RenderBackend::Hardware);
+        assert_eq!(
+            RenderBackend::Hardware.resolve(true),
+            RenderBackend::Hardware
+        );
+        assert_eq!(
+            RenderBackend::Software.resolve(false),
+            RenderBackend::Software
+        );
+    }
+
+    #[test]
+    fn render_image_with_each_backend() {
+        use crate::piet::RenderContext;
+
+        for &backend in &[RenderBackend::Hardware, RenderBackend::Software] {
+            let paint = |piet: &mut piet_common::Piet| {
+                piet.clear(Color::WHITE);
+                // a point is two pixels
+                piet.fill(Rect::new(0., 0., 2., 4.), &Color::rgb8(0xff, 0, 0));
+            };
+            let image = match backend.render_image(8, 8, 2.0, paint) {
+                Ok(image) => image,
+                Err(e) => {
+                    eprintln!("skipping {:?}, it can't paint here: {}", backend, e);
+                    continue;
+                }
+            };
+            assert_eq!((image.width, image.height), (8, 8));
+            let pixel = |x: usize, y: usize| &image.pixels[(y * 8 + x) * 4..][..4];
+            assert_eq!(pixel(3, 7), [0xff, 0, 0, 0xff], "{:?}", backend);
+            assert_eq!(pixel(4, 0), [0xff; 4], "{:?}", backend);
+        }
+    }
+}
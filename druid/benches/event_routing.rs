@@ -0,0 +1,132 @@
+// Copyright 2019 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! How long it takes to send an event to one widget in a large tree.
+//!
+//! Targeted commands and timers only go down the subtrees that contain
+//! their widget. Sending the same command to every widget stands in for what
+//! routing cost before, when every container passed events on to all its
+//! children. It is only an approximation: the old routing also checked each
+//! widget's id against the target, and a broadcast is handled by every leaf
+//! rather than by one.
+//!
+//! As in an app, every event is followed by an update of the tree.
+//!
+//! Run with `cargo bench --features bench-internals`.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use druid::widget::{Flex, WidgetExt};
+use druid::{
+    BoxConstraints, Env, Event, EventCtx, HeadlessWindow, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Selector, Size, Target, TimerToken, UpdateCtx, Widget, WidgetId,
+};
+
+const PING: Selector = Selector::new("druid-bench.ping");
+const START_TIMER: Selector = Selector::new("druid-bench.start-timer");
+
+/// The tree is this many columns of this many widgets.
+const SIDE: usize = 100;
+
+/// Counts the pings that it gets, and starts a timer when asked.
+struct Leaf {
+    pings: Rc<Cell<usize>>,
+    timer: Rc<Cell<Option<TimerToken>>>,
+}
+
+impl Widget<()> for Leaf {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        match event {
+            Event::Command(cmd) if cmd.selector == PING => self.pings.set(self.pings.get() + 1),
+            Event::Command(cmd) if cmd.selector == START_TIMER => {
+                let deadline = Instant::now() + Duration::from_secs(60);
+                self.timer.set(Some(ctx.request_timer(deadline)));
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &(), _env: &Env) {}
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &(),
+        _env: &Env,
+    ) -> Size {
+        bc.constrain((10., 10.))
+    }
+
+    fn paint(&mut self, _ctx: &mut PaintCtx, _data: &(), _env: &Env) {}
+}
+
+fn targeted_events(c: &mut Criterion) {
+    let pings = Rc::new(Cell::new(0));
+    let timer = Rc::new(Cell::new(None));
+    let target = WidgetId::next();
+    let mut row = Flex::row();
+    for i in 0..SIDE {
+        let mut column = Flex::column();
+        for j in 0..SIDE {
+            let leaf = Leaf {
+                pings: pings.clone(),
+                timer: timer.clone(),
+            };
+            // one widget in the middle of the tree is the target
+            let id = if (i, j) == (SIDE / 2, SIDE / 2) {
+                target
+            } else {
+                WidgetId::next()
+            };
+            column.add_child(leaf.with_id(id).padding(1.), 0.);
+        }
+        row.add_child(column, 0.);
+    }
+    let size = Size::new(1200., 1200.);
+    let mut window = HeadlessWindow::new((), row, size);
+
+    // the targeted command only reaches its widget, and the other one all
+    window.submit_command(PING, target);
+    assert_eq!(pings.get(), 1);
+    window.submit_command(PING, Target::Auto);
+    assert_eq!(pings.get(), 1 + SIDE * SIDE);
+
+    let mut group = c.benchmark_group("event routing");
+    group.bench_function("command to one widget", |b| {
+        b.iter(|| window.submit_command(PING, target))
+    });
+    group.bench_function("command to every widget", |b| {
+        b.iter(|| window.submit_command(PING, Target::Auto))
+    });
+    group.bench_function(
+        "timer of one widget, with the command that starts it",
+        |b| {
+            b.iter(|| {
+                window.submit_command(START_TIMER, target);
+                window.event(Event::Timer(timer.take().unwrap()));
+            })
+        },
+    );
+    group.finish();
+}
+
+criterion_group!(benches, targeted_events);
+criterion_main!(benches);
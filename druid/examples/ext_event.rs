@@ -79,6 +79,8 @@ fn main() {
         LocalizedString::new("identity-demo-window-title").with_placeholder("External Event Demo"),
     );
 
+    // the sink has no window of its own, so the colors are sent to this one
+    let window_id = window.id;
     let launcher = AppLauncher::with_window(window);
 
     let event_sink = launcher.get_external_handle();
@@ -101,7 +103,7 @@ fn main() {
             let next_color = Color::rgb8(red as u8, green as u8, blue as u8);
 
             // if this fails we're shutting down
-            if let Err(_) = event_sink.submit_command(SET_COLOR, next_color, None) {
+            if let Err(_) = event_sink.submit_command(SET_COLOR, next_color, window_id) {
                 break;
             }
             thread::sleep(Duration::from_millis(150));
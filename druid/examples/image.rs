@@ -27,12 +27,12 @@ fn main() {
 #[cfg(feature = "image")]
 fn main() {
     use druid::{
-        widget::{FillStrat, Flex, Image, ImageData, WidgetExt},
+        widget::{FillStrat, Flex, Image, ImageBuf, WidgetExt},
         AppLauncher, Widget, WindowDesc,
     };
 
     fn ui_builder() -> impl Widget<u32> {
-        let png_data = ImageData::from_file("examples/PicWithAlpha.png").unwrap();
+        let png_data = ImageBuf::from_file("examples/PicWithAlpha.png").unwrap();
 
         let mut col = Flex::column();
 
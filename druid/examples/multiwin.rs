@@ -17,8 +17,8 @@
 use druid::widget::{Align, Button, Flex, Label, Padding};
 use druid::{
     commands as sys_cmds, AppDelegate, AppLauncher, Command, ContextMenu, Data, DelegateCtx, Env,
-    Event, EventCtx, LocalizedString, MenuDesc, MenuItem, Selector, Target, Widget, WindowDesc,
-    WindowId,
+    Event, EventCtx, Handled, LocalizedString, MenuDesc, MenuItem, Selector, Target, Widget,
+    WindowDesc, WindowId,
 };
 
 use log::info;
@@ -96,7 +96,7 @@ impl AppDelegate<State> for Delegate {
             Event::MouseDown(ref mouse) if mouse.button.is_right() => {
                 let menu = ContextMenu::new(make_context_menu::<State>(), mouse.pos);
                 let cmd = Command::new(druid::commands::SHOW_CONTEXT_MENU, menu);
-                ctx.submit_command(cmd, None);
+                ctx.submit_command(cmd, Target::Auto);
                 None
             }
             other => Some(other),
@@ -110,22 +110,21 @@ impl AppDelegate<State> for Delegate {
         cmd: &Command,
         data: &mut State,
         _env: &Env,
-    ) -> bool {
+    ) -> Handled {
         match (target, &cmd.selector) {
             (_, &sys_cmds::NEW_FILE) => {
                 let new_win = WindowDesc::new(ui_builder)
                     .menu(make_menu(data))
                     .window_size((data.selected as f64 * 100.0 + 300.0, 500.0));
-                let command = Command::one_shot(sys_cmds::NEW_WINDOW, new_win);
-                ctx.submit_command(command, Target::Global);
-                false
+                ctx.new_window(new_win);
+                Handled::Yes
             }
             (Target::Window(id), &MENU_COUNT_ACTION) => {
                 data.selected = *cmd.get_object().unwrap();
                 let menu = make_menu::<State>(data);
                 let cmd = Command::new(druid::commands::SET_MENU, menu);
                 ctx.submit_command(cmd, *id);
-                false
+                Handled::Yes
             }
             // wouldn't it be nice if a menu (like a button) could just mutate state
             // directly if desired?
@@ -134,16 +133,16 @@ impl AppDelegate<State> for Delegate {
                 let menu = make_menu::<State>(data);
                 let cmd = Command::new(druid::commands::SET_MENU, menu);
                 ctx.submit_command(cmd, *id);
-                false
+                Handled::Yes
             }
             (Target::Window(id), &MENU_DECREMENT_ACTION) => {
                 data.menu_count = data.menu_count.saturating_sub(1);
                 let menu = make_menu::<State>(data);
                 let cmd = Command::new(druid::commands::SET_MENU, menu);
                 ctx.submit_command(cmd, *id);
-                false
+                Handled::Yes
             }
-            _ => true,
+            _ => Handled::No,
         }
     }
 
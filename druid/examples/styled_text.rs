@@ -50,24 +50,23 @@ fn ui_builder() -> impl Widget<AppData> {
     let label =
         Label::new(|data: &String, _env: &_| format!("Default: {}", data)).lens(AppData::text);
 
-    // The text_color and text_size builder methods can override the defaults
-    // provided by the theme by passing in a Key or a concrete value.
+    // The with_text_color and with_text_size builder methods can override the
+    // defaults provided by the theme by passing in a Key or a concrete value.
     //
-    // In this example, text_color receives a Key from the theme, while
-    // text_size gets a custom key which we set with the env_scope wrapper.
+    // In this example, with_text_color receives a Key from the theme, while
+    // with_text_size gets a custom key which we set with the env_scope wrapper.
     let styled_label =
         Label::new(|data: &AppData, _env: &_| format!("Size {:.1}: {}", data.size, data.text))
-            .text_color(theme::PRIMARY_LIGHT)
-            .text_size(MY_CUSTOM_TEXT_SIZE)
+            .with_text_color(theme::PRIMARY_LIGHT)
+            .with_text_size(MY_CUSTOM_TEXT_SIZE)
             .env_scope(|env: &mut druid::Env, data: &AppData| {
                 env.set(MY_CUSTOM_TEXT_SIZE, data.size)
             });
 
     let stepper = Stepper::new()
-        .max(100.0)
-        .min(0.0)
-        .step(1.0)
-        .wrap(false)
+        .with_range(0.0, 100.0)
+        .with_step(1.0)
+        .with_wraparound(false)
         .lens(AppData::size);
 
     let stepper_textbox = LensWrap::new(
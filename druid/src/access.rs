@@ -0,0 +1,455 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The accessibility tree, which describes the widgets to screen readers.
+//!
+//! Widgets describe themselves with [`set_role`] and the other methods next
+//! to it on the contexts. Once a screen reader asks for the tree, the window
+//! builds it after each layout, from what the widgets set and where they
+//! are, and gives it to the platform along with what changed since the last
+//! one.
+//!
+//! [`set_role`]: struct.LifeCycleCtx.html#method.set_role
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::core::BaseState;
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::WidgetId;
+
+pub use crate::shell::{AccessEvent, AccessNode, Role};
+
+/// The id of the node for the window, which no widget has.
+const WINDOW_NODE_ID: u64 = 0;
+
+/// What a widget has told screen readers about itself.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AccessInfo {
+    pub(crate) role: Option<Role>,
+    pub(crate) label: Option<String>,
+    pub(crate) value: Option<String>,
+    pub(crate) toggled: Option<bool>,
+}
+
+/// The widgets of a window, collected to build its accessibility tree.
+///
+/// This is carried by `LifeCycle::RouteAccessTree`.
+#[derive(Clone, Default)]
+pub struct AccessTreeBuilder(Rc<RefCell<Vec<Entry>>>);
+
+/// A widget, as it was visited.
+struct Entry {
+    id: WidgetId,
+    /// `None` for the root widget.
+    parent: Option<WidgetId>,
+    layout_origin: Point,
+    size: Size,
+    viewport_offset: Vec2,
+    info: AccessInfo,
+    focusable: bool,
+    labeled_by: Option<WidgetId>,
+}
+
+impl PartialEq for AccessTreeBuilder {
+    fn eq(&self, other: &AccessTreeBuilder) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for AccessTreeBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AccessTreeBuilder({} widgets)", self.0.borrow().len())
+    }
+}
+
+impl AccessTreeBuilder {
+    pub(crate) fn new() -> AccessTreeBuilder {
+        Default::default()
+    }
+
+    /// Visit a widget, in tree order.
+    pub(crate) fn visit(&self, state: &BaseState, parent: Option<WidgetId>) {
+        self.0.borrow_mut().push(Entry {
+            id: state.id,
+            parent,
+            layout_origin: state.layout_rect.origin(),
+            size: state.layout_rect.size(),
+            viewport_offset: state.viewport_offset,
+            info: state.access.clone(),
+            focusable: state.focus_chain.contains(&state.id),
+            labeled_by: state.labeled_by,
+        });
+    }
+
+    /// Build the tree from the widgets that were visited.
+    ///
+    /// The root of the tree is the window, of `size` and with `title`. Rects
+    /// are multiplied by `scale`, to go from the content's coordinate space
+    /// to the window's.
+    pub(crate) fn build(
+        &self,
+        title: Option<String>,
+        size: Size,
+        scale: f64,
+        focus: Option<WidgetId>,
+    ) -> AccessNode {
+        let entries = self.0.borrow();
+        let index: HashMap<WidgetId, usize> =
+            entries.iter().enumerate().map(|(i, e)| (e.id, i)).collect();
+        let parent_of = |entry: &Entry| entry.parent.and_then(|id| index.get(&id).copied());
+
+        // parents are visited before their children
+        let mut origins: Vec<Point> = Vec::with_capacity(entries.len());
+        for entry in entries.iter() {
+            let origin = match parent_of(entry) {
+                Some(parent) => {
+                    let offset = origins[parent].to_vec2() - entries[parent].viewport_offset;
+                    entry.layout_origin + offset
+                }
+                None => entry.layout_origin,
+            };
+            origins.push(origin);
+        }
+
+        // the node of each widget is a child of the node of its closest
+        // ancestor that has a role, and widgets without one are left out
+        let mut node_parents: Vec<Option<usize>> = Vec::with_capacity(entries.len());
+        for entry in entries.iter() {
+            let node_parent = parent_of(entry).and_then(|parent| {
+                if entries[parent].info.role.is_some() {
+                    Some(parent)
+                } else {
+                    node_parents[parent]
+                }
+            });
+            node_parents.push(node_parent);
+        }
+
+        let mut nodes: Vec<Option<AccessNode>> = entries
+            .iter()
+            .zip(origins.iter())
+            .map(|(entry, origin)| {
+                let role = entry.info.role?;
+                let rect = Rect::from_origin_size(*origin, entry.size);
+                let rect = Rect::new(
+                    rect.x0 * scale,
+                    rect.y0 * scale,
+                    rect.x1 * scale,
+                    rect.y1 * scale,
+                );
+                let mut node = AccessNode::new(entry.id.to_raw(), role, rect);
+                node.label = entry.info.label.clone();
+                node.value = entry.info.value.clone();
+                node.toggled = entry.info.toggled;
+                node.focusable = entry.focusable;
+                node.focused = focus == Some(entry.id);
+                Some(node)
+            })
+            .collect();
+
+        // a control that is labeled elsewhere, as in a `FormRow`, is named
+        // after its label
+        for (idx, entry) in entries.iter().enumerate() {
+            let label = entry
+                .labeled_by
+                .and_then(|id| index.get(&id))
+                .and_then(|label| entries[*label].info.label.clone());
+            if let (Some(node), Some(label)) = (nodes[idx].as_mut(), label) {
+                if node.label.is_none() {
+                    node.label = Some(label);
+                }
+            }
+        }
+
+        let mut window = AccessNode::new(
+            WINDOW_NODE_ID,
+            Role::Window,
+            Rect::from_origin_size(Point::ORIGIN, size),
+        );
+        window.label = title;
+
+        // children are put in place before their parents, so each parent's
+        // children are complete when it is
+        for idx in (0..entries.len()).rev() {
+            let mut node = match nodes[idx].take() {
+                Some(node) => node,
+                None => continue,
+            };
+            fold_labels(&mut node);
+            let parent = match node_parents[idx] {
+                Some(parent) => nodes[parent].as_mut().unwrap(),
+                None => &mut window,
+            };
+            parent.children.insert(0, node);
+        }
+        window
+    }
+}
+
+/// A button or a checkbox without a label of its own is named after the
+/// labels inside it, which are not read on their own.
+fn fold_labels(node: &mut AccessNode) {
+    if node.label.is_some() || !(node.role == Role::Button || node.role == Role::CheckBox) {
+        return;
+    }
+    let mut labels = Vec::new();
+    node.children.retain(|child| {
+        if child.role == Role::Label && child.children.is_empty() {
+            labels.extend(child.label.clone());
+            false
+        } else {
+            true
+        }
+    });
+    if !labels.is_empty() {
+        node.label = Some(labels.join(" "));
+    }
+}
+
+/// The events that tell screen readers how `new` is different from `old`,
+/// or that the tree is new.
+pub(crate) fn diff(old: Option<&AccessNode>, new: &AccessNode) -> Vec<AccessEvent> {
+    let old = match old {
+        Some(old) => old,
+        None => {
+            let mut events = vec![AccessEvent::StructureChanged];
+            events.extend(new.focused().map(|node| AccessEvent::FocusChanged(node.id)));
+            return events;
+        }
+    };
+
+    let old_nodes: HashMap<u64, &AccessNode> = old
+        .nodes()
+        .into_iter()
+        .map(|node| (node.id, node))
+        .collect();
+    let new_nodes = new.nodes();
+    let child_ids = |node: &AccessNode| node.children.iter().map(|c| c.id).collect::<Vec<_>>();
+
+    let mut events = Vec::new();
+    let mut structure_changed = old_nodes.len() != new_nodes.len();
+    for node in new_nodes {
+        let old_node = match old_nodes.get(&node.id) {
+            Some(old_node) => old_node,
+            None => {
+                structure_changed = true;
+                continue;
+            }
+        };
+        if child_ids(old_node) != child_ids(node) || old_node.role != node.role {
+            structure_changed = true;
+        }
+        if old_node.label != node.label {
+            events.push(AccessEvent::LabelChanged(node.id));
+        }
+        if old_node.value != node.value {
+            events.push(AccessEvent::ValueChanged(node.id));
+        }
+        if old_node.toggled != node.toggled {
+            events.push(AccessEvent::ToggleChanged(node.id));
+        }
+    }
+    if structure_changed {
+        events.insert(0, AccessEvent::StructureChanged);
+    }
+
+    let old_focus = old.focused().map(|node| node.id);
+    let new_focus = new.focused().map(|node| node.id);
+    if let Some(focus) = new_focus.filter(|focus| Some(*focus) != old_focus) {
+        events.push(AccessEvent::FocusChanged(focus));
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Button, Checkbox, Flex, FormRow, Label, TextBox, WidgetExt};
+    use crate::{Code, Widget};
+
+    fn node(id: u64, role: Role) -> AccessNode {
+        AccessNode::new(id, role, Rect::ZERO)
+    }
+
+    #[test]
+    fn labels_are_folded_into_checkboxes() {
+        let mut label = node(2, Role::Label);
+        label.label = Some("Dark mode".into());
+        let mut checkbox = node(1, Role::CheckBox);
+        checkbox.children.push(label);
+        fold_labels(&mut checkbox);
+        assert_eq!(checkbox.label.as_deref(), Some("Dark mode"));
+        assert!(checkbox.children.is_empty());
+
+        // a label of its own is kept
+        let mut other = node(3, Role::Label);
+        other.label = Some("ignored".into());
+        checkbox.children.push(other);
+        fold_labels(&mut checkbox);
+        assert_eq!(checkbox.label.as_deref(), Some("Dark mode"));
+        assert_eq!(checkbox.children.len(), 1);
+    }
+
+    #[test]
+    fn diff_finds_changes() {
+        let mut old = node(0, Role::Window);
+        let mut checkbox = node(1, Role::CheckBox);
+        checkbox.toggled = Some(false);
+        old.children.push(checkbox);
+        old.children.push(node(2, Role::TextBox));
+        assert_eq!(diff(None, &old), vec![AccessEvent::StructureChanged]);
+
+        let mut new = old.clone();
+        new.children[0].toggled = Some(true);
+        new.children[1].value = Some("a".into());
+        new.children[1].focused = true;
+        assert_eq!(
+            diff(Some(&old), &new),
+            vec![
+                AccessEvent::ToggleChanged(1),
+                AccessEvent::ValueChanged(2),
+                AccessEvent::FocusChanged(2),
+            ]
+        );
+        assert!(diff(Some(&new), &new).is_empty());
+
+        let mut removed = new.clone();
+        removed.children.pop();
+        assert_eq!(
+            diff(Some(&new), &removed),
+            vec![AccessEvent::StructureChanged]
+        );
+    }
+
+    type Settings = (bool, String);
+
+    /// A checkbox, a button, a label and a text box, in a column.
+    fn settings(ids: [WidgetId; 4]) -> impl Widget<Settings> {
+        let dark_mode = Checkbox::new("Dark mode").lens(crate::lens!(Settings, 0));
+        let name = TextBox::new()
+            .with_placeholder("Your name")
+            .lens(crate::lens!(Settings, 1));
+        Flex::column()
+            .with_child(dark_mode.with_id(ids[0]), 0.0)
+            .with_child(Button::new("Save", |_, _, _| ()).with_id(ids[1]), 0.0)
+            .with_child(Label::new("Name").with_id(ids[2]), 0.0)
+            .with_child(name.with_id(ids[3]), 0.0)
+    }
+
+    fn find_node(tree: &AccessNode, id: WidgetId) -> &AccessNode {
+        match tree.find(id.to_raw()) {
+            Some(node) => node,
+            None => panic!("widget {:?} is not in the access tree", id),
+        }
+    }
+
+    #[test]
+    fn core_widgets_describe_themselves() {
+        let (checkbox, button, label, textbox) = widget_id4();
+        let ids = [checkbox, button, label, textbox];
+        Harness::create((false, String::new()), settings(ids), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let tree = harness.access_tree();
+
+            // the column has no role, so the widgets in it are the window's
+            assert_eq!(tree.role, Role::Window);
+            let children: Vec<_> = tree.children.iter().map(|node| node.id).collect();
+            let expected: Vec<_> = ids.iter().map(|id| id.to_raw()).collect();
+            assert_eq!(children, expected);
+
+            // the checkbox's label is read as its name
+            let node_checkbox = find_node(&tree, checkbox);
+            assert_eq!(node_checkbox.role, Role::CheckBox);
+            assert_eq!(node_checkbox.label.as_deref(), Some("Dark mode"));
+            assert_eq!(node_checkbox.toggled, Some(false));
+            assert!(node_checkbox.children.is_empty());
+
+            let node_button = find_node(&tree, button);
+            assert_eq!(node_button.role, Role::Button);
+            assert_eq!(node_button.label.as_deref(), Some("Save"));
+
+            let node_label = find_node(&tree, label);
+            assert_eq!(node_label.role, Role::Label);
+            assert_eq!(node_label.label.as_deref(), Some("Name"));
+
+            let node_textbox = find_node(&tree, textbox);
+            assert_eq!(node_textbox.role, Role::TextBox);
+            assert_eq!(node_textbox.label.as_deref(), Some("Your name"));
+            assert_eq!(node_textbox.value.as_deref(), Some(""));
+            assert!(node_textbox.focusable);
+            assert!(!node_button.focusable);
+
+            // the rects are in the window's coordinate space
+            let rect = harness.layout_rect_in_window(textbox);
+            assert_eq!(node_textbox.rect.origin(), rect.origin());
+            assert_eq!(node_textbox.rect.size(), rect.size());
+        });
+    }
+
+    #[test]
+    fn changes_raise_events() {
+        let (checkbox, button, label, textbox) = widget_id4();
+        let ids = [checkbox, button, label, textbox];
+        Harness::create((false, String::new()), settings(ids), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let before = harness.access_tree();
+
+            let checkbox_rect = harness.layout_rect_in_window(checkbox);
+            click(harness, checkbox_rect.origin() + Vec2::new(5., 5.));
+            let toggled = harness.access_tree();
+            assert_eq!(find_node(&toggled, checkbox).toggled, Some(true));
+            let events = diff(Some(&before), &toggled);
+            let checkbox_id = checkbox.to_raw();
+            assert_eq!(events, vec![AccessEvent::ToggleChanged(checkbox_id)]);
+
+            let textbox_rect = harness.layout_rect_in_window(textbox);
+            click(harness, textbox_rect.center());
+            harness.event(key_down("a", Code::KeyA));
+            let typed = harness.access_tree();
+            assert!(find_node(&typed, textbox).focused);
+            assert_eq!(find_node(&typed, textbox).value.as_deref(), Some("a"));
+            let events = diff(Some(&toggled), &typed);
+            let textbox_id = textbox.to_raw();
+            assert_eq!(
+                events,
+                vec![
+                    AccessEvent::ValueChanged(textbox_id),
+                    AccessEvent::FocusChanged(textbox_id)
+                ]
+            );
+
+            // nothing changed, so there is nothing to tell
+            assert!(diff(Some(&typed), &harness.access_tree()).is_empty());
+        });
+    }
+
+    #[test]
+    fn controls_are_named_after_their_labels() {
+        let control = WidgetId::next();
+        let row = FormRow::new("Name", TextBox::new().with_id(control));
+        Harness::create(String::new(), row, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let tree = harness.access_tree();
+            assert_eq!(find_node(&tree, control).label.as_deref(), Some("Name"));
+        });
+    }
+}
@@ -0,0 +1,557 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Animating values over time.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::piet::Color;
+
+/// A spring has settled when it is this close to its target, relative to
+/// the distance it started from.
+const SPRING_TOLERANCE: f64 = 1e-3;
+/// The longest step of the spring simulation, in seconds.
+const SPRING_STEP: f64 = 1e-3;
+/// The number of halvings in finding the point on a cubic Bézier curve for a
+/// time, which is more than enough for the precision of an `f64`.
+const BEZIER_STEPS: usize = 60;
+
+/// The shape of a transition, from its start value to its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    /// The value changes at a constant rate.
+    Linear,
+    /// The value starts slowly, and speeds up.
+    EaseIn,
+    /// The value starts quickly, and slows down.
+    EaseOut,
+    /// The value starts slowly, speeds up, and slows down again.
+    EaseInOut,
+    /// The value follows a cubic Bézier curve from `(0, 0)` to `(1, 1)`,
+    /// with the control points `(x1, y1)` and `(x2, y2)`, as in CSS.
+    ///
+    /// The x axis is time and the y axis is progress. The x coordinates are
+    /// clamped to `0.0..=1.0`, so that each time has one point on the curve;
+    /// the y coordinates can be outside of it, for a curve that overshoots.
+    CubicBezier { x1: f64, y1: f64, x2: f64, y2: f64 },
+    /// The value is pulled to the target by a damped spring.
+    ///
+    /// `damping` is the damping ratio: at `1.0` the spring is critically
+    /// damped, and doesn't overshoot; smaller values make it oscillate around
+    /// the target before settling. The stiffness is chosen so that a critically
+    /// damped spring settles in about the duration of the transition, but the
+    /// transition lasts until the spring has settled.
+    ///
+    /// When a spring transition is retargeted, it keeps its velocity.
+    Spring { damping: f64 },
+}
+
+/// A value that can be animated by an [`Animator`].
+///
+/// The value is made of components that are interpolated independently.
+///
+/// [`Animator`]: struct.Animator.html
+pub trait Animatable: Sized {
+    /// The components of this value.
+    fn to_components(&self) -> Vec<f64>;
+
+    /// Create a value from components returned by `to_components`,
+    /// or interpolated between them.
+    fn from_components(components: &[f64]) -> Self;
+}
+
+/// A set of named values that transition smoothly to their targets.
+///
+/// Values are started with [`set`], and animated with [`animate_to`]. The
+/// animator is driven by calling [`advance`] with the interval of each
+/// `LifeCycle::AnimFrame`, and the current values are read with [`get`],
+/// usually in `layout` or `paint`.
+///
+/// ```
+/// use std::time::Duration;
+/// use druid::{Animator, Curve};
+///
+/// let mut animator = Animator::new();
+/// animator.set("opacity", 0.0);
+/// animator.animate_to("opacity", 1.0, Duration::from_millis(200), Curve::EaseOut);
+///
+/// // as with the interval of an `AnimFrame`
+/// animator.advance(100_000_000);
+/// let opacity: f64 = animator.get("opacity").unwrap();
+/// assert!(opacity > 0.5 && opacity < 1.0);
+///
+/// animator.advance(100_000_000);
+/// assert_eq!(animator.get("opacity"), Some(1.0));
+/// assert!(!animator.is_animating());
+/// ```
+///
+/// [`set`]: #method.set
+/// [`animate_to`]: #method.animate_to
+/// [`advance`]: #method.advance
+/// [`get`]: #method.get
+#[derive(Debug, Clone, Default)]
+pub struct Animator {
+    transitions: HashMap<&'static str, Transition>,
+}
+
+#[derive(Debug, Clone)]
+struct Transition {
+    from: Vec<f64>,
+    to: Vec<f64>,
+    value: Vec<f64>,
+    velocity: Vec<f64>,
+    duration: Duration,
+    elapsed: Duration,
+    curve: Curve,
+}
+
+impl Curve {
+    /// The progress of the fixed-shape curves at time `t`, both in `0..=1`.
+    fn ease(self, t: f64) -> f64 {
+        match self {
+            Curve::Linear | Curve::Spring { .. } => t,
+            Curve::EaseIn => t * t * t,
+            Curve::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Curve::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Curve::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+            Curve::CubicBezier { x1, y1, x2, y2 } => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+/// The progress of a cubic Bézier curve at time `t`.
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, t: f64) -> f64 {
+    if t <= 0.0 {
+        return 0.0;
+    } else if t >= 1.0 {
+        return 1.0;
+    }
+    // a coordinate at parameter `s`, with the end points at 0 and 1
+    let coord = |c1: f64, c2: f64, s: f64| {
+        let r = 1.0 - s;
+        3.0 * c1 * s * r * r + 3.0 * c2 * s * s * r + s * s * s
+    };
+    let (x1, x2) = (x1.max(0.0).min(1.0), x2.max(0.0).min(1.0));
+    // with the control points in 0..=1, x only grows with s
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..BEZIER_STEPS {
+        let mid = (lo + hi) / 2.0;
+        if coord(x1, x2, mid) < t {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    coord(y1, y2, (lo + hi) / 2.0)
+}
+
+impl Animator {
+    /// Create an animator with no values.
+    pub fn new() -> Animator {
+        Animator::default()
+    }
+
+    /// Set a value immediately, stopping any transition it was in.
+    pub fn set(&mut self, name: &'static str, value: impl Animatable) {
+        self.transitions
+            .insert(name, Transition::settled(value.to_components()));
+    }
+
+    /// Start a transition of a value to `target`.
+    ///
+    /// The transition starts from the current value, including while it is
+    /// in another transition. If there is no value with this name yet, it is
+    /// set to `target` immediately.
+    pub fn animate_to(
+        &mut self,
+        name: &'static str,
+        target: impl Animatable,
+        duration: Duration,
+        curve: Curve,
+    ) {
+        let target = target.to_components();
+        match self.transitions.get_mut(name) {
+            Some(transition) if transition.value.len() == target.len() => {
+                transition.retarget(target, duration, curve)
+            }
+            _ => {
+                self.transitions.insert(name, Transition::settled(target));
+            }
+        }
+    }
+
+    /// Advance all transitions by `interval`, in nanoseconds, returning
+    /// whether any are still in progress.
+    pub fn advance(&mut self, interval: u64) -> bool {
+        let dt = Duration::from_nanos(interval);
+        for transition in self.transitions.values_mut() {
+            transition.advance(dt);
+        }
+        self.is_animating()
+    }
+
+    /// Whether any transition is in progress.
+    pub fn is_animating(&self) -> bool {
+        self.transitions.values().any(|t| !t.is_done())
+    }
+
+    /// The current value with this name, if there is one.
+    pub fn get<V: Animatable>(&self, name: &str) -> Option<V> {
+        self.transitions
+            .get(name)
+            .map(|t| V::from_components(&t.value))
+    }
+}
+
+impl Transition {
+    fn settled(value: Vec<f64>) -> Transition {
+        Transition {
+            from: value.clone(),
+            to: value.clone(),
+            velocity: vec![0.0; value.len()],
+            value,
+            duration: Duration::default(),
+            elapsed: Duration::default(),
+            curve: Curve::Linear,
+        }
+    }
+
+    fn retarget(&mut self, to: Vec<f64>, duration: Duration, curve: Curve) {
+        self.from = self.value.clone();
+        self.to = to;
+        self.duration = duration;
+        self.elapsed = Duration::default();
+        self.curve = curve;
+    }
+
+    fn is_done(&self) -> bool {
+        self.value == self.to && self.velocity.iter().all(|v| *v == 0.0)
+    }
+
+    fn finish(&mut self) {
+        self.value = self.to.clone();
+        self.velocity.iter_mut().for_each(|v| *v = 0.0);
+    }
+
+    fn advance(&mut self, dt: Duration) {
+        if self.is_done() {
+            return;
+        }
+        match self.curve {
+            Curve::Spring { damping } => self.advance_spring(dt, damping),
+            curve => self.advance_eased(dt, curve),
+        }
+    }
+
+    fn advance_eased(&mut self, dt: Duration, curve: Curve) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+        if self.elapsed >= self.duration {
+            self.finish();
+            return;
+        }
+        let progress = curve.ease(self.elapsed.as_nanos() as f64 / self.duration.as_nanos() as f64);
+        let dt = seconds(dt);
+        let components = self.from.iter().zip(&self.to);
+        for ((value, velocity), (from, to)) in self
+            .value
+            .iter_mut()
+            .zip(&mut self.velocity)
+            .zip(components)
+        {
+            let next = from + (to - from) * progress;
+            if dt > 0.0 {
+                *velocity = (next - *value) / dt;
+            }
+            *value = next;
+        }
+    }
+
+    fn advance_spring(&mut self, dt: Duration, damping: f64) {
+        // one undamped oscillation per duration
+        let omega = 2.0 * PI / seconds(self.duration).max(SPRING_STEP);
+        let mut remaining = seconds(dt);
+        while remaining > 0.0 {
+            let step = remaining.min(SPRING_STEP);
+            let components = self.value.iter_mut().zip(&mut self.velocity);
+            for ((value, velocity), to) in components.zip(&self.to) {
+                let accel = -omega * omega * (*value - to) - 2.0 * damping * omega * *velocity;
+                *velocity += accel * step;
+                *value += *velocity * step;
+            }
+            remaining -= step;
+        }
+        self.elapsed += dt;
+
+        let distance = self
+            .from
+            .iter()
+            .zip(&self.to)
+            .map(|(from, to)| (from - to).abs())
+            .fold(1.0, f64::max);
+        let tolerance = SPRING_TOLERANCE * distance;
+        let settled =
+            self.value
+                .iter()
+                .zip(&self.velocity)
+                .zip(&self.to)
+                .all(|((value, velocity), to)| {
+                    (value - to).abs() <= tolerance && velocity.abs() <= tolerance * omega
+                });
+        if settled {
+            self.finish();
+        }
+    }
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_nanos() as f64 * 1e-9
+}
+
+impl Animatable for f64 {
+    fn to_components(&self) -> Vec<f64> {
+        vec![*self]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        components[0]
+    }
+}
+
+impl Animatable for Point {
+    fn to_components(&self) -> Vec<f64> {
+        vec![self.x, self.y]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        Point::new(components[0], components[1])
+    }
+}
+
+impl Animatable for Vec2 {
+    fn to_components(&self) -> Vec<f64> {
+        vec![self.x, self.y]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        Vec2::new(components[0], components[1])
+    }
+}
+
+impl Animatable for Size {
+    fn to_components(&self) -> Vec<f64> {
+        vec![self.width, self.height]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        Size::new(components[0], components[1])
+    }
+}
+
+impl Animatable for Rect {
+    fn to_components(&self) -> Vec<f64> {
+        vec![self.x0, self.y0, self.x1, self.y1]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        Rect::new(components[0], components[1], components[2], components[3])
+    }
+}
+
+impl Animatable for Color {
+    fn to_components(&self) -> Vec<f64> {
+        let rgba = self.as_rgba_u32();
+        vec![
+            (rgba >> 24) as f64,
+            ((rgba >> 16) & 0xff) as f64,
+            ((rgba >> 8) & 0xff) as f64,
+            (rgba & 0xff) as f64,
+        ]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        // a spring can overshoot the valid range
+        let channel = |i: usize| components[i].round().max(0.0).min(255.0) as u8;
+        Color::rgba8(channel(0), channel(1), channel(2), channel(3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: u64 = 10_000_000;
+    const SECOND: Duration = Duration::from_secs(1);
+    const SECOND_NS: u64 = 1_000_000_000;
+
+    /// Animate from 0 to 100 over a second, returning the value at each frame.
+    fn run(curve: Curve) -> Vec<f64> {
+        let mut animator = Animator::new();
+        animator.set("x", 0.0);
+        animator.animate_to("x", 100.0, SECOND, curve);
+        let mut values = vec![animator.get("x").unwrap()];
+        while animator.advance(FRAME) {
+            values.push(animator.get("x").unwrap());
+            assert!(values.len() < 1000, "{:?} didn't finish", curve);
+        }
+        values.push(animator.get("x").unwrap());
+        values
+    }
+
+    #[test]
+    fn curve_endpoints() {
+        for curve in &[
+            Curve::Linear,
+            Curve::EaseIn,
+            Curve::EaseOut,
+            Curve::EaseInOut,
+        ] {
+            assert_eq!(curve.ease(0.0), 0.0);
+            assert_eq!(curve.ease(1.0), 1.0);
+        }
+        assert!(Curve::EaseIn.ease(0.5) < 0.5);
+        assert!(Curve::EaseOut.ease(0.5) > 0.5);
+        assert!((Curve::EaseInOut.ease(0.5) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cubic_bezier_curves() {
+        // with the control points on the diagonal, the curve is a line
+        let linear = Curve::CubicBezier {
+            x1: 0.25,
+            y1: 0.25,
+            x2: 0.75,
+            y2: 0.75,
+        };
+        for t in &[0.0, 0.1, 0.5, 0.9, 1.0] {
+            assert!((linear.ease(*t) - t).abs() < 1e-9);
+        }
+
+        // CSS's `ease-in-out` is symmetric around the middle
+        let ease_in_out = Curve::CubicBezier {
+            x1: 0.42,
+            y1: 0.0,
+            x2: 0.58,
+            y2: 1.0,
+        };
+        assert!((ease_in_out.ease(0.5) - 0.5).abs() < 1e-9);
+        assert!(ease_in_out.ease(0.25) < 0.25);
+        assert!((ease_in_out.ease(0.25) + ease_in_out.ease(0.75) - 1.0).abs() < 1e-9);
+
+        // a transition with it lands exactly on the end
+        let values = run(ease_in_out);
+        assert_eq!(*values.last().unwrap(), 100.0);
+        assert!(values.windows(2).all(|w| w[0] <= w[1]));
+
+        let overshoot = Curve::CubicBezier {
+            x1: 0.3,
+            y1: 1.5,
+            x2: 0.7,
+            y2: 1.5,
+        };
+        assert!(run(overshoot).iter().any(|x| *x > 100.0));
+    }
+
+    #[test]
+    fn eased_transitions_are_monotonic() {
+        for curve in &[
+            Curve::Linear,
+            Curve::EaseIn,
+            Curve::EaseOut,
+            Curve::EaseInOut,
+        ] {
+            let values = run(*curve);
+            assert_eq!(values[0], 0.0);
+            assert_eq!(*values.last().unwrap(), 100.0);
+            // the last frame lands exactly on the end
+            assert_eq!(values.len(), 101, "{:?}", curve);
+            assert!(values.windows(2).all(|w| w[0] <= w[1]), "{:?}", curve);
+        }
+    }
+
+    #[test]
+    fn springs() {
+        let critical = run(Curve::Spring { damping: 1.0 });
+        assert_eq!(*critical.last().unwrap(), 100.0);
+        assert!(critical.windows(2).all(|w| w[0] <= w[1]));
+        assert!(critical.iter().all(|x| *x <= 100.0));
+
+        // an underdamped spring overshoots, and still settles on the target
+        let bouncy = run(Curve::Spring { damping: 0.3 });
+        assert_eq!(*bouncy.last().unwrap(), 100.0);
+        assert!(bouncy.iter().any(|x| *x > 100.0));
+        assert!(bouncy.len() > critical.len());
+    }
+
+    #[test]
+    fn retarget_starts_from_current_value() {
+        let mut animator = Animator::new();
+        animator.set("x", 0.0);
+        animator.animate_to("x", 100.0, SECOND, Curve::EaseInOut);
+        animator.advance(SECOND_NS / 2);
+        let halfway: f64 = animator.get("x").unwrap();
+        assert!((halfway - 50.0).abs() < 1e-9);
+
+        animator.animate_to("x", 0.0, SECOND, Curve::EaseInOut);
+        assert_eq!(animator.get("x"), Some(halfway));
+        animator.advance(FRAME);
+        let next: f64 = animator.get("x").unwrap();
+        assert!(next < halfway && halfway - next < 1.0);
+
+        while animator.advance(FRAME) {}
+        assert_eq!(animator.get("x"), Some(0.0));
+    }
+
+    #[test]
+    fn retargeted_spring_keeps_velocity() {
+        let mut animator = Animator::new();
+        animator.set("x", 0.0);
+        animator.animate_to("x", 100.0, SECOND, Curve::Spring { damping: 1.0 });
+        for _ in 0..20 {
+            animator.advance(FRAME);
+        }
+        let before: f64 = animator.get("x").unwrap();
+
+        // moving back, the spring first carries on forwards, and slows down
+        animator.animate_to("x", 0.0, SECOND, Curve::Spring { damping: 1.0 });
+        animator.advance(FRAME);
+        let after: f64 = animator.get("x").unwrap();
+        assert!(after > before);
+
+        while animator.advance(FRAME) {}
+        assert_eq!(animator.get("x"), Some(0.0));
+    }
+
+    #[test]
+    fn values_of_other_types() {
+        let mut animator = Animator::new();
+        animator.set("point", Point::new(0.0, 10.0));
+        animator.set("color", Color::BLACK);
+        animator.animate_to("point", Point::new(10.0, 0.0), SECOND, Curve::Linear);
+        animator.animate_to("color", Color::WHITE, SECOND, Curve::Linear);
+        animator.advance(SECOND_NS / 2);
+
+        assert_eq!(animator.get("point"), Some(Point::new(5.0, 5.0)));
+        let color: Color = animator.get("color").unwrap();
+        assert_eq!(color.as_rgba_u32(), 0x8080_80ff);
+
+        // there is nothing to animate from
+        animator.animate_to("size", Size::new(1.0, 2.0), SECOND, Curve::Linear);
+        assert_eq!(animator.get("size"), Some(Size::new(1.0, 2.0)));
+        assert!(animator.advance(FRAME));
+        assert!(!animator.advance(SECOND_NS));
+    }
+}
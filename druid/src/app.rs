@@ -14,10 +14,16 @@
 
 //! Window building and app lifecycle.
 
+use std::time::Duration;
+
 use crate::ext_event::{ExtEventHost, ExtEventSink};
-use crate::kurbo::Size;
-use crate::shell::{Application, Error as PlatformError, WindowBuilder, WindowHandle};
-use crate::widget::WidgetExt;
+use crate::kurbo::{Point, Size};
+use crate::piet::Color;
+use crate::shell::{
+    Application, Error as PlatformError, Monitor, RenderBackend, WindowBuilder, WindowHandle,
+    WindowLevel, WindowState,
+};
+use crate::widget::{LabelText, WidgetExt};
 use crate::win_handler::{AppHandler, AppState};
 use crate::window::WindowId;
 use crate::{theme, AppDelegate, Data, DruidHandler, Env, LocalizedString, MenuDesc, Widget};
@@ -33,17 +39,41 @@ pub struct AppLauncher<T> {
     ext_event_host: ExtEventHost,
 }
 
+/// The properties of a platform window, such as its size and level.
+///
+/// A [`WindowDesc`] has one of these, which its methods such as
+/// [`window_size`] set; a sub-window, opened with
+/// [`EventCtx::new_sub_window`], is described by one alone.
+///
+/// [`WindowDesc`]: struct.WindowDesc.html
+/// [`window_size`]: struct.WindowDesc.html#method.window_size
+/// [`EventCtx::new_sub_window`]: struct.EventCtx.html#method.new_sub_window
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowConfig {
+    pub(crate) size: Option<Size>,
+    pub(crate) min_size: Option<Size>,
+    pub(crate) position: Option<Point>,
+    pub(crate) state: Option<WindowState>,
+    pub(crate) resizable: bool,
+    pub(crate) show_titlebar: bool,
+    pub(crate) transparent: bool,
+    pub(crate) render_backend: RenderBackend,
+    pub(crate) level: WindowLevel,
+}
+
 /// A description of a window to be instantiated.
 ///
 /// This includes a function that can build the root widget, as well as other
 /// window properties such as the title.
 pub struct WindowDesc<T> {
     pub(crate) root: Box<dyn Widget<T>>,
-    pub(crate) title: LocalizedString<T>,
-    pub(crate) size: Option<Size>,
+    pub(crate) title: LabelText<T>,
+    pub(crate) config: WindowConfig,
     pub(crate) menu: Option<MenuDesc<T>>,
-    pub(crate) resizable: bool,
-    pub(crate) show_titlebar: bool,
+    pub(crate) background: Option<Color>,
+    pub(crate) layout_budget: Option<Duration>,
+    /// The window that this was opened from as a sub-window, if it was.
+    pub(crate) sub_window_of: Option<WindowId>,
     /// The `WindowId` that will be assigned to this window.
     ///
     /// This can be used to track a window from when it is launched and when
@@ -62,10 +92,34 @@ impl<T: Data> AppLauncher<T> {
         }
     }
 
+    /// Create a new `AppLauncher` without any windows.
+    ///
+    /// The app keeps running until its first window is opened, usually by the
+    /// [`AppDelegate`] submitting [`NEW_WINDOW`] once it knows which window it
+    /// wants, for instance in response to a command from an
+    /// [`ExtEventSink`]. It can also quit before that with [`QUIT_APP`].
+    ///
+    /// [`AppDelegate`]: trait.AppDelegate.html
+    /// [`NEW_WINDOW`]: commands/constant.NEW_WINDOW.html
+    /// [`ExtEventSink`]: struct.ExtEventSink.html
+    /// [`QUIT_APP`]: commands/constant.QUIT_APP.html
+    pub fn without_windows() -> Self {
+        AppLauncher {
+            windows: Vec::new(),
+            env_setup: None,
+            delegate: None,
+            ext_event_host: ExtEventHost::new(),
+        }
+    }
+
     /// Provide an optional closure that will be given mutable access to
     /// the environment and immutable access to the app state before launch.
     ///
-    /// This can be used to set or override theme values.
+    /// This can be used to set or override theme values, including switching
+    /// to the light palette with [`theme::apply_light`]. It is called before
+    /// the first window is created.
+    ///
+    /// [`theme::apply_light`]: theme/fn.apply_light.html
     pub fn configure_env(mut self, f: impl Fn(&mut Env, &T) + 'static) -> Self {
         self.env_setup = Some(Box::new(f));
         self
@@ -109,6 +163,10 @@ impl<T: Data> AppLauncher<T> {
     /// a fatal error.
     pub fn launch(mut self, data: T) -> Result<(), PlatformError> {
         let mut env = theme::init();
+        env.set(
+            theme::ANIMATIONS_ENABLED,
+            !Application::prefers_reduced_motion(),
+        );
         if let Some(f) = self.env_setup.take() {
             f(&mut env, &data);
         }
@@ -117,6 +175,14 @@ impl<T: Data> AppLauncher<T> {
         let handler = AppHandler::new(state.clone());
 
         let mut app = Application::new(Some(Box::new(handler)));
+        // external events wake the application rather than a window, so they
+        // are delivered even before the first window is opened
+        if let Some(idle) = Application::get_idle_handle() {
+            state.set_ext_event_idle_handle(idle);
+        }
+        if self.windows.is_empty() {
+            state.set_startup_pending();
+        }
         for desc in self.windows {
             let window = desc.build_native(&mut state)?;
             window.show();
@@ -143,21 +209,45 @@ impl<T: Data> WindowDesc<T> {
         // this just makes our API slightly cleaner; callers don't need to explicitly box.
         WindowDesc {
             root: root().boxed(),
-            title: LocalizedString::new("app-name"),
-            size: None,
+            title: LocalizedString::new("app-name").into(),
+            config: WindowConfig::default(),
             menu: MenuDesc::platform_default(),
-            resizable: true,
-            show_titlebar: true,
+            background: None,
+            layout_budget: None,
+            sub_window_of: None,
             id: WindowId::next(),
         }
     }
 
-    /// Set the title for this window. This is a [`LocalizedString`] that will
-    /// be kept up to date as the application's state changes.
+    /// Set the title for this window.
     ///
+    /// Like the text of a [`Label`], this can be a string, a
+    /// [`LocalizedString`], or a closure that computes the title from the
+    /// data. It is kept up to date as the application's state changes; the
+    /// platform window is only told when the text changes.
+    ///
+    /// ```
+    /// # use druid::widget::Label;
+    /// # use druid::{Env, WindowDesc};
+    /// # #[derive(Clone, druid::Data)]
+    /// # struct Document { name: String, modified: bool }
+    /// let window = WindowDesc::new(|| Label::new("contents")).title(|doc: &Document, _: &Env| {
+    ///     if doc.modified {
+    ///         format!("{} \u{2014} modified", doc.name)
+    ///     } else {
+    ///         doc.name.clone()
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// To set the title of an open window, use the [`SET_WINDOW_TITLE`]
+    /// command.
+    ///
+    /// [`Label`]: widget/struct.Label.html
     /// [`LocalizedString`]: struct.LocalizedString.html
-    pub fn title(mut self, title: LocalizedString<T>) -> Self {
-        self.title = title;
+    /// [`SET_WINDOW_TITLE`]: commands/constant.SET_WINDOW_TITLE.html
+    pub fn title(mut self, title: impl Into<LabelText<T>>) -> Self {
+        self.title = title.into();
         self
     }
 
@@ -175,17 +265,139 @@ impl<T: Data> WindowDesc<T> {
     /// window.window_size((1000.0, 500.0));
     /// ```
     pub fn window_size(mut self, size: impl Into<Size>) -> Self {
-        self.size = Some(size.into());
+        self.config.size = Some(size.into());
+        self
+    }
+
+    /// Set the smallest size that the window can be resized to, in display
+    /// points.
+    ///
+    /// The platform keeps the user from making the window any smaller, and
+    /// the root widget is never laid out at a smaller size, even if the
+    /// platform reports one. The size can be passed in the same ways as to
+    /// [`window_size`].
+    ///
+    /// [`window_size`]: #method.window_size
+    pub fn with_min_size(mut self, size: impl Into<Size>) -> Self {
+        self.config.min_size = Some(size.into());
+        self
+    }
+
+    /// Set the initial position of the window's top-left corner on the screen.
+    ///
+    /// Together with [`window_size`], this can restore a window to where it
+    /// was in a previous run.
+    ///
+    /// [`window_size`]: #method.window_size
+    pub fn set_position(mut self, position: impl Into<Point>) -> Self {
+        self.config.position = Some(position.into());
         self
     }
 
+    /// Set the initial position of the window's top-left corner, relative
+    /// to the top-left corner of the work area of a [`Monitor`].
+    ///
+    /// The monitors can be found with [`Screen::get_monitors`], before the
+    /// application is launched.
+    ///
+    /// [`Monitor`]: struct.Monitor.html
+    /// [`Screen::get_monitors`]: struct.Screen.html#method.get_monitors
+    pub fn set_position_on(self, monitor: &Monitor, position: impl Into<Point>) -> Self {
+        self.set_position(monitor.to_virtual(position.into()))
+    }
+
+    /// Set whether the window should start out maximized or minimized.
+    pub fn set_window_state(mut self, state: WindowState) -> Self {
+        self.config.state = Some(state);
+        self
+    }
+
+    /// Set whether the user can resize the window. The default is `true`.
     pub fn resizable(mut self, resizable: bool) -> Self {
-        self.resizable = resizable;
+        self.config.resizable = resizable;
         self
     }
 
+    /// Set whether the window has a titlebar and the platform's decorations.
+    /// The default is `true`.
+    ///
+    /// Without them, a [`resizable`] window can still be resized from its
+    /// edges, and a widget can act as the titlebar by calling
+    /// [`WindowHandle::begin_move_drag`] on a `MouseDown`, through
+    /// [`EventCtx::window`].
+    ///
+    /// [`resizable`]: #method.resizable
+    /// [`WindowHandle::begin_move_drag`]: struct.WindowHandle.html#method.begin_move_drag
+    /// [`EventCtx::window`]: struct.EventCtx.html#method.window
     pub fn show_titlebar(mut self, show_titlebar: bool) -> Self {
-        self.show_titlebar = show_titlebar;
+        self.config.show_titlebar = show_titlebar;
+        self
+    }
+
+    /// Set whether what is behind the window shows through the parts of it
+    /// that aren't painted opaquely. The default is `false`.
+    ///
+    /// The window is still cleared with its background color before it is
+    /// painted, so this should be set to a transparent color with
+    /// [`with_background_color`].
+    ///
+    /// [`with_background_color`]: #method.with_background_color
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.config.transparent = transparent;
+        self
+    }
+
+    /// Set how the window's contents should be drawn.
+    ///
+    /// The default, [`RenderBackend::Auto`], uses software rendering where
+    /// it is likely to be faster, such as over remote desktop. The backend
+    /// in use can be found with [`WindowHandle::render_backend`].
+    ///
+    /// [`RenderBackend::Auto`]: enum.RenderBackend.html#variant.Auto
+    /// [`WindowHandle::render_backend`]: struct.WindowHandle.html#method.render_backend
+    pub fn render_backend(mut self, backend: RenderBackend) -> Self {
+        self.config.render_backend = backend;
+        self
+    }
+
+    /// Set the color that the window is cleared to before it is painted,
+    /// instead of [`theme::WINDOW_BACKGROUND_COLOR`].
+    ///
+    /// The color can be changed later with [`SET_BACKGROUND_COLOR`].
+    ///
+    /// [`theme::WINDOW_BACKGROUND_COLOR`]: theme/constant.WINDOW_BACKGROUND_COLOR.html
+    /// [`SET_BACKGROUND_COLOR`]: commands/constant.SET_BACKGROUND_COLOR.html
+    pub fn with_background_color(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// Lay out the window incrementally, spending at most about `budget` on
+    /// each frame.
+    ///
+    /// This is meant for very large widget trees, where laying out the
+    /// whole tree at once would block the UI. The visible widgets are always
+    /// laid out; once the budget is spent, the layout of the offscreen
+    /// widgets that still need it is deferred to the following frames, and
+    /// they paint a placeholder until then. Mouse and keyboard events are not
+    /// delivered to a widget whose layout is deferred.
+    ///
+    /// Widgets are only laid out again when they have requested it with
+    /// [`request_layout`], or when their constraints change, so in this mode
+    /// a widget must request layout whenever its size might change.
+    ///
+    /// [`request_layout`]: struct.EventCtx.html#method.request_layout
+    pub fn incremental_layout(mut self, budget: Duration) -> Self {
+        self.layout_budget = Some(budget);
+        self
+    }
+
+    /// Set the properties of the platform window all at once, replacing
+    /// those set by the other methods, such as [`window_size`].
+    ///
+    /// [`window_size`]: #method.window_size
+    pub fn with_config(mut self, config: WindowConfig) -> Self {
+        self.config = config;
         self
     }
 
@@ -204,15 +416,17 @@ impl<T: Data> WindowDesc<T> {
 
         let mut builder = WindowBuilder::new();
 
-        builder.resizable(self.resizable);
-        builder.show_titlebar(self.show_titlebar);
+        self.config.apply_to_builder(&mut builder);
+        // shown until the first paint
+        builder.set_background_color(
+            self.background
+                .clone()
+                .unwrap_or_else(|| env.get(theme::WINDOW_BACKGROUND_COLOR)),
+        );
 
         builder.set_handler(Box::new(handler));
-        if let Some(size) = self.size {
-            builder.set_size(size);
-        }
 
-        builder.set_title(self.title.localized_str());
+        builder.set_title(self.title.with_display_text(|s| s.to_string()));
         if let Some(menu) = platform_menu {
             builder.set_menu(menu);
         }
@@ -221,9 +435,150 @@ impl<T: Data> WindowDesc<T> {
         let mut window = WindowDesc::new(|| root);
         window.title = self.title;
         window.menu = self.menu;
+        window.config = self.config;
+        window.background = self.background;
+        window.layout_budget = self.layout_budget;
+        window.sub_window_of = self.sub_window_of;
 
         state.add_window(self.id, window);
 
         builder.build()
     }
 }
+
+impl WindowConfig {
+    /// Set the initial size of the window, in display points.
+    pub fn window_size(mut self, size: impl Into<Size>) -> Self {
+        self.size = Some(size.into());
+        self
+    }
+
+    /// Set the smallest size that the window can be resized to, in display
+    /// points.
+    pub fn with_min_size(mut self, size: impl Into<Size>) -> Self {
+        self.min_size = Some(size.into());
+        self
+    }
+
+    /// Set the initial position of the window's top-left corner on the
+    /// screen, in display points.
+    ///
+    /// For a popup, this is usually found with [`EventCtx::to_screen`].
+    ///
+    /// [`EventCtx::to_screen`]: struct.EventCtx.html#method.to_screen
+    pub fn set_position(mut self, position: impl Into<Point>) -> Self {
+        self.position = Some(position.into());
+        self
+    }
+
+    /// Set the initial position of the window's top-left corner, relative
+    /// to the top-left corner of the work area of a [`Monitor`].
+    ///
+    /// [`Monitor`]: struct.Monitor.html
+    pub fn set_position_on(self, monitor: &Monitor, position: impl Into<Point>) -> Self {
+        self.set_position(monitor.to_virtual(position.into()))
+    }
+
+    /// Set whether the window should start out maximized or minimized.
+    pub fn set_window_state(mut self, state: WindowState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Set whether the user can resize the window. The default is `true`.
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Set whether the window has a titlebar and the platform's decorations.
+    /// The default is `true`.
+    pub fn show_titlebar(mut self, show_titlebar: bool) -> Self {
+        self.show_titlebar = show_titlebar;
+        self
+    }
+
+    /// Set whether what is behind the window shows through the parts of it
+    /// that aren't painted opaquely. The default is `false`.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Set how the window's contents should be drawn.
+    pub fn render_backend(mut self, backend: RenderBackend) -> Self {
+        self.render_backend = backend;
+        self
+    }
+
+    /// Set the level of the window. The default is
+    /// [`WindowLevel::AppWindow`].
+    ///
+    /// A sub-window at one of the popup levels, such as
+    /// [`WindowLevel::DropDown`], has no decorations and doesn't take the
+    /// focus. It is closed when the window that it was opened from is
+    /// clicked, or gets Escape.
+    ///
+    /// [`WindowLevel::AppWindow`]: enum.WindowLevel.html#variant.AppWindow
+    /// [`WindowLevel::DropDown`]: enum.WindowLevel.html#variant.DropDown
+    pub fn set_level(mut self, level: WindowLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub(crate) fn apply_to_builder(&self, builder: &mut WindowBuilder) {
+        builder.resizable(self.resizable);
+        builder.show_titlebar(self.show_titlebar);
+        builder.transparent(self.transparent);
+        builder.set_render_backend(self.render_backend);
+        builder.set_level(self.level);
+        if let Some(size) = self.size {
+            builder.set_size(size);
+        }
+        if let Some(min_size) = self.min_size {
+            builder.set_min_size(min_size);
+        }
+        if let Some(position) = self.position {
+            builder.set_position(position);
+        }
+        if let Some(state) = self.state {
+            builder.set_window_state(state);
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            size: None,
+            min_size: None,
+            position: None,
+            state: None,
+            resizable: true,
+            show_titlebar: true,
+            transparent: false,
+            render_backend: RenderBackend::Auto,
+            level: WindowLevel::AppWindow,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widget::SizedBox;
+
+    #[test]
+    fn window_desc_options() {
+        let desc = WindowDesc::<u32>::new(SizedBox::empty);
+        let config = &desc.config;
+        assert!(config.resizable && config.show_titlebar && !config.transparent);
+
+        let desc = WindowDesc::<u32>::new(SizedBox::empty)
+            .resizable(false)
+            .show_titlebar(false)
+            .transparent(true);
+        let config = &desc.config;
+        assert!(!config.resizable && !config.show_titlebar && config.transparent);
+    }
+}
@@ -16,7 +16,9 @@
 
 use std::collections::VecDeque;
 
-use crate::{Command, Data, Env, Event, Target, WindowId};
+use std::any::Any;
+
+use crate::{commands, Command, Data, Env, Event, Target, WindowDesc, WindowId};
 
 /// A context passed in to [`AppDelegate`] functions.
 ///
@@ -32,17 +34,57 @@ impl<'a> DelegateCtx<'a> {
     /// submitted during the handling of an event are executed before
     /// the [`update()`] method is called.
     ///
+    /// The delegate has no window, so a command sent to [`Target::Auto`]
+    /// is `Global`.
+    ///
     /// [`Command`]: struct.Command.html
     /// [`update()`]: widget/trait.Widget.html#tymethod.update
-    pub fn submit_command(
-        &mut self,
-        command: impl Into<Command>,
-        target: impl Into<Option<Target>>,
-    ) {
+    /// [`Target::Auto`]: enum.Target.html#variant.Auto
+    pub fn submit_command(&mut self, command: impl Into<Command>, target: impl Into<Target>) {
         let command = command.into();
-        let target = target.into().unwrap_or(Target::Global);
+        let target = target.into().resolve(Target::Global);
         self.command_queue.push_back((target, command))
     }
+
+    /// Request a new window be created.
+    ///
+    /// The window is created once the delegate returns, along with any other
+    /// submitted commands. `T` must be the application's data type; a window
+    /// for any other data is logged as an error and is not created.
+    pub fn new_window<T: Any>(&mut self, desc: WindowDesc<T>) {
+        let command = Command::one_shot(commands::NEW_WINDOW, desc);
+        self.submit_command(command, Target::Global);
+    }
+}
+
+/// Whether a [`Command`] was handled by the [`AppDelegate`].
+///
+/// [`Command`]: struct.Command.html
+/// [`AppDelegate`]: trait.AppDelegate.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handled {
+    /// The command was handled, and should not be sent to any window.
+    Yes,
+    /// The command was not handled, and should be sent down the widget tree.
+    No,
+}
+
+impl Handled {
+    /// Returns `true` if this is `Handled::Yes`.
+    pub fn is_handled(self) -> bool {
+        self == Handled::Yes
+    }
+}
+
+impl From<bool> for Handled {
+    /// `true` becomes `Handled::Yes`, and `false` becomes `Handled::No`.
+    fn from(handled: bool) -> Handled {
+        if handled {
+            Handled::Yes
+        } else {
+            Handled::No
+        }
+    }
 }
 
 /// A type that provides hooks for handling and modifying top-level events.
@@ -79,15 +121,20 @@ pub trait AppDelegate<T: Data> {
     /// This function is called with each ([`Target`], [`Command`]) pair before
     /// they are sent down the tree.
     ///
-    /// If your implementation returns `true`, the command will be sent down
-    /// the widget tree. Otherwise it will not.
+    /// If your implementation returns [`Handled::No`], the command will be sent
+    /// down the widget tree. If it returns [`Handled::Yes`], the command is
+    /// swallowed, and no window will see it. A command sent to
+    /// [`Target::Global`] is only seen here.
     ///
     /// To do anything fancier than this, you can submit arbitary commands
     /// via [`DelegateCtx::submit_command`].
     ///
     /// [`Target`]: enum.Target.html
+    /// [`Target::Global`]: enum.Target.html#variant.Global
     /// [`Command`]: struct.Command.html
     /// [`DelegateCtx::submit_command`]: struct.DelegateCtx.html#method.submit_command
+    /// [`Handled::No`]: enum.Handled.html#variant.No
+    /// [`Handled::Yes`]: enum.Handled.html#variant.Yes
     fn command(
         &mut self,
         ctx: &mut DelegateCtx,
@@ -95,8 +142,8 @@ pub trait AppDelegate<T: Data> {
         cmd: &Command,
         data: &mut T,
         env: &Env,
-    ) -> bool {
-        true
+    ) -> Handled {
+        Handled::No
     }
 
     /// The handler for window creation events.
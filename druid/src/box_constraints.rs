@@ -31,7 +31,7 @@ use log;
 ///
 /// [`layout`]: widget/trait.Widget.html#tymethod.layout
 /// [Flutter BoxConstraints]: https://api.flutter.dev/flutter/rendering/BoxConstraints-class.html
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct BoxConstraints {
     min: Size,
     max: Size,
@@ -80,6 +80,28 @@ impl BoxConstraints {
         self.min
     }
 
+    /// Returns the max size of these constraints, with the width or the
+    /// height of `fallback` on an axis that is unbounded.
+    ///
+    /// This is for widgets that fill the space they are given, and need
+    /// some size when there is no end to it; the fallback is constrained,
+    /// so it is never smaller than the min size.
+    pub fn max_or(&self, fallback: impl Into<Size>) -> Size {
+        let fallback = self.constrain(fallback);
+        Size::new(
+            if self.is_width_bounded() {
+                self.max.width
+            } else {
+                fallback.width
+            },
+            if self.is_height_bounded() {
+                self.max.height
+            } else {
+                fallback.height
+            },
+        )
+    }
+
     /// Whether there is an upper bound on the width.
     pub fn is_width_bounded(&self) -> bool {
         self.max.width.is_finite()
@@ -92,19 +114,38 @@ impl BoxConstraints {
 
     /// Check to see if these constraints are legit.
     ///
-    /// Logs a warning if BoxConstraints are invalid.
+    /// Logs a warning, naming the widget, if any of the sizes is NaN, if the
+    /// min size is negative or infinite, or if it is larger than the max size.
     pub fn debug_check(&self, name: &str) {
-        if !(0.0 <= self.min.width
-            && self.min.width <= self.max.width
-            && 0.0 <= self.min.height
-            && self.min.height <= self.max.height)
-        {
-            log::warn!("Bad BoxConstraints passed to {}:", name);
+        if let Some(problem) = self.problem() {
+            log::warn!("Bad BoxConstraints passed to {}, {}:", name, problem);
             log::warn!("{:?}", self);
         }
     }
 
-    /// Shrink min and max constraints by size
+    /// What is wrong with these constraints, if anything.
+    fn problem(&self) -> Option<&'static str> {
+        let BoxConstraints { min, max } = self;
+        if [min.width, min.height, max.width, max.height]
+            .iter()
+            .any(|x| x.is_nan())
+        {
+            Some("with a NaN")
+        } else if min.width < 0.0 || min.height < 0.0 {
+            Some("with a negative min")
+        } else if min.width.is_infinite() || min.height.is_infinite() {
+            Some("with an infinite min")
+        } else if min.width > max.width || min.height > max.height {
+            Some("with a min larger than the max")
+        } else {
+            None
+        }
+    }
+
+    /// Shrink min and max constraints by size, clamping them at zero.
+    ///
+    /// This is the space left for a child in insets of `diff`; padding its
+    /// size by `diff` again gives a size that meets these constraints.
     pub fn shrink(&self, diff: impl Into<Size>) -> BoxConstraints {
         let diff = diff.into();
         let min = Size::new(
@@ -118,4 +159,139 @@ impl BoxConstraints {
 
         BoxConstraints::new(min, max)
     }
+
+    /// Constraints with the same height, and a width between `min` and `max`.
+    pub fn with_width_range(&self, min: f64, max: f64) -> BoxConstraints {
+        BoxConstraints::new(
+            Size::new(min, self.min.height),
+            Size::new(max, self.max.height),
+        )
+    }
+
+    /// Constraints with the same width, and a height between `min` and `max`.
+    pub fn with_height_range(&self, min: f64, max: f64) -> BoxConstraints {
+        BoxConstraints::new(
+            Size::new(self.min.width, min),
+            Size::new(self.max.width, max),
+        )
+    }
+
+    /// The size that meets these constraints with an aspect ratio, the
+    /// height divided by the width, as close to `aspect_ratio` as it can be.
+    ///
+    /// When there are several sizes with the aspect ratio, the one with the
+    /// width closest to `width` is chosen; a `width` of `0.0` gives the
+    /// smallest of them, and one of `f64::INFINITY` the largest, which is
+    /// infinite when the constraints are unbounded. When there are none,
+    /// the size is the corner of the constraints closest to the aspect
+    /// ratio: the min width with the max height if the ratio is taller than
+    /// the constraints allow, and the max width with the min height if it
+    /// is wider.
+    ///
+    /// An aspect ratio that isn't positive and finite is not one that can
+    /// be kept, and `width` is just constrained, with a height of
+    /// `aspect_ratio * width`.
+    pub fn constrain_aspect_ratio(&self, aspect_ratio: f64, width: f64) -> Size {
+        if !(aspect_ratio > 0.0 && aspect_ratio.is_finite()) {
+            return self.constrain((width, aspect_ratio * width));
+        }
+        let BoxConstraints { min, max } = self;
+        // the widths for which the height with the aspect ratio fits
+        let min_width = min.width.max(min.height / aspect_ratio);
+        let max_width = max.width.min(max.height / aspect_ratio);
+        if min_width <= max_width {
+            let width = width.max(min_width).min(max_width);
+            Size::new(width, width * aspect_ratio)
+        } else if min.width > max.height / aspect_ratio {
+            Size::new(min.width, max.height)
+        } else {
+            Size::new(max.width, min.height)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bc(min_width: f64, min_height: f64, max_width: f64, max_height: f64) -> BoxConstraints {
+        BoxConstraints::new(
+            Size::new(min_width, min_height),
+            Size::new(max_width, max_height),
+        )
+    }
+
+    #[test]
+    fn aspect_ratio_with_unbounded_width() {
+        let bc = bc(0., 0., f64::INFINITY, 100.);
+        assert_eq!(bc.constrain_aspect_ratio(0.5, 100.), Size::new(100., 50.));
+        // as wide as the height allows
+        assert_eq!(bc.constrain_aspect_ratio(0.5, 1000.), Size::new(200., 100.));
+        assert_eq!(
+            bc.constrain_aspect_ratio(0.5, f64::INFINITY),
+            Size::new(200., 100.)
+        );
+    }
+
+    #[test]
+    fn aspect_ratio_with_unbounded_height() {
+        let bc = bc(20., 0., 100., f64::INFINITY);
+        assert_eq!(bc.constrain_aspect_ratio(2., 50.), Size::new(50., 100.));
+        assert_eq!(bc.constrain_aspect_ratio(2., 0.), Size::new(20., 40.));
+        assert_eq!(
+            bc.constrain_aspect_ratio(2., f64::INFINITY),
+            Size::new(100., 200.)
+        );
+
+        let unbounded = bc.loosen().with_width_range(0., f64::INFINITY);
+        let size = unbounded.constrain_aspect_ratio(2., f64::INFINITY);
+        assert!(size.width.is_infinite() && size.height.is_infinite());
+    }
+
+    #[test]
+    fn aspect_ratio_that_does_not_fit() {
+        // too tall: the narrowest and tallest size
+        let wide = bc(80., 0., 100., 10.);
+        assert_eq!(wide.constrain_aspect_ratio(1., 90.), Size::new(80., 10.));
+        // too wide: the widest and shortest size
+        let tall = bc(0., 80., 10., 100.);
+        assert_eq!(tall.constrain_aspect_ratio(1., 90.), Size::new(10., 80.));
+        // it fits, but not with the width that was asked for
+        let square = bc(0., 0., 100., 100.);
+        assert_eq!(square.constrain_aspect_ratio(2., 90.), Size::new(50., 100.));
+    }
+
+    #[test]
+    fn aspect_ratio_of_zero_sizes() {
+        let zero = BoxConstraints::tight(Size::ZERO);
+        assert_eq!(zero.constrain_aspect_ratio(1., 100.), Size::ZERO);
+        let flat = bc(0., 0., 100., 0.);
+        assert_eq!(flat.constrain_aspect_ratio(1., 100.), Size::ZERO);
+        let loose = bc(0., 0., 100., 100.);
+        assert_eq!(loose.constrain_aspect_ratio(1., 0.), Size::ZERO);
+        // no ratio can be kept
+        assert_eq!(loose.constrain_aspect_ratio(0., 50.), Size::new(50., 0.));
+        let nan = loose.constrain_aspect_ratio(f64::NAN, 50.);
+        assert_eq!(nan.width, 50.);
+    }
+
+    #[test]
+    fn max_of_unbounded_constraints() {
+        let bc = bc(50., 0., f64::INFINITY, 100.);
+        assert_eq!(bc.max_or((10., 10.)), Size::new(50., 100.));
+        assert_eq!(bc.max_or((200., 200.)), Size::new(200., 100.));
+    }
+
+    #[test]
+    fn bad_constraints() {
+        assert_eq!(bc(0., 0., 100., 100.).problem(), None);
+        assert_eq!(bc(0., 0., f64::INFINITY, 100.).problem(), None);
+        assert!(bc(0., f64::NAN, 100., 100.).problem().is_some());
+        assert!(bc(0., 0., f64::NAN, 100.).problem().is_some());
+        assert!(bc(-1., 0., 100., 100.).problem().is_some());
+        assert!(bc(f64::INFINITY, 0., f64::INFINITY, 100.)
+            .problem()
+            .is_some());
+        assert!(bc(0., 200., 100., 100.).problem().is_some());
+    }
 }
@@ -0,0 +1,256 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing and mixing colors.
+
+use std::fmt;
+
+use crate::{Animatable, Color};
+
+/// Extra methods for [`Color`].
+///
+/// `Color` itself has [`with_alpha`] and [`as_rgba_u32`]; these are the
+/// methods that it lacks.
+///
+/// [`Color`]: struct.Color.html
+/// [`with_alpha`]: struct.Color.html#method.with_alpha
+/// [`as_rgba_u32`]: struct.Color.html#method.as_rgba_u32
+pub trait ColorExt: Sized {
+    /// Parse a color from a hex string.
+    ///
+    /// The string has 3, 4, 6 or 8 hex digits, with or without a leading
+    /// `#`: `rgb`, `rgba`, `rrggbb` or `rrggbbaa`. Without an alpha
+    /// component, the color is opaque.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use druid::{Color, ColorExt};
+    ///
+    /// let red = Color::from_hex_str("#ff0000").unwrap();
+    /// assert_eq!(red.as_rgba_u32(), 0xff_00_00_ff);
+    ///
+    /// let faded = Color::from_hex_str("F008").unwrap();
+    /// assert_eq!(faded.as_rgba_u32(), 0xff_00_00_88);
+    ///
+    /// assert!(Color::from_hex_str("#ff00").is_ok());
+    /// assert!(Color::from_hex_str("#ff000").is_err());
+    /// ```
+    fn from_hex_str(hex: &str) -> Result<Self, ColorParseError>;
+
+    /// The alpha component, in the range 0.0 to 1.0.
+    fn alpha(&self) -> f64;
+
+    /// Multiply the alpha component by `factor`, clamping it to the range
+    /// 0.0 to 1.0.
+    fn multiply_alpha(self, factor: f64) -> Self;
+
+    /// The color that is the fraction `t` of the way from this color to
+    /// `other`, such as for a transition on hover.
+    ///
+    /// `t` is clamped to the range 0.0 to 1.0; each component, including
+    /// alpha, is interpolated separately.
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+/// The error returned by [`ColorExt::from_hex_str`].
+///
+/// [`ColorExt::from_hex_str`]: trait.ColorExt.html#tymethod.from_hex_str
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorParseError {
+    /// The string doesn't have 3, 4, 6 or 8 digits; this is the number it has.
+    WrongLength(usize),
+    /// The string has a character that is not a hex digit.
+    NotHex(char),
+}
+
+impl ColorExt for Color {
+    fn from_hex_str(hex: &str) -> Result<Color, ColorParseError> {
+        let digits = if hex.starts_with('#') { &hex[1..] } else { hex };
+        let mut values = Vec::with_capacity(8);
+        for c in digits.chars() {
+            let value = c.to_digit(16).ok_or(ColorParseError::NotHex(c))?;
+            values.push(value as u8);
+        }
+        let channels: Vec<u8> = match values.len() {
+            // each digit stands for a channel, repeated: `f` is `ff`
+            3 | 4 => values.iter().map(|v| v << 4 | v).collect(),
+            6 | 8 => values.chunks(2).map(|v| v[0] << 4 | v[1]).collect(),
+            len => return Err(ColorParseError::WrongLength(len)),
+        };
+        let alpha = channels.get(3).copied().unwrap_or(0xff);
+        Ok(Color::rgba8(channels[0], channels[1], channels[2], alpha))
+    }
+
+    fn alpha(&self) -> f64 {
+        f64::from(self.as_rgba_u32() as u8) / 255.0
+    }
+
+    fn multiply_alpha(self, factor: f64) -> Color {
+        let alpha = self.alpha() * factor;
+        self.with_alpha(alpha)
+    }
+
+    fn interpolate(&self, other: &Color, t: f64) -> Color {
+        let t = t.max(0.0).min(1.0);
+        let components: Vec<f64> = self
+            .to_components()
+            .iter()
+            .zip(other.to_components())
+            .map(|(from, to)| from + (to - from) * t)
+            .collect();
+        Color::from_components(&components)
+    }
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorParseError::WrongLength(len) => {
+                write!(f, "a hex color has 3, 4, 6 or 8 digits, not {}", len)
+            }
+            ColorParseError::NotHex(c) => write!(f, "'{}' is not a hex digit", c),
+        }
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Flex, WidgetExt};
+    use crate::{theme, Event, KeyOrValue, Selector, Target};
+
+    fn parse(hex: &str) -> Result<u32, ColorParseError> {
+        Color::from_hex_str(hex).map(|c| c.as_rgba_u32())
+    }
+
+    #[test]
+    fn parse_forms() {
+        assert_eq!(parse("#1a2b3c"), Ok(0x1a2b_3cff));
+        assert_eq!(parse("1a2b3c"), Ok(0x1a2b_3cff));
+        assert_eq!(parse("#1A2B3C80"), Ok(0x1a2b_3c80));
+        assert_eq!(parse("1a2b3c80"), Ok(0x1a2b_3c80));
+        assert_eq!(parse("#abc"), Ok(0xaabb_ccff));
+        assert_eq!(parse("abc"), Ok(0xaabb_ccff));
+        assert_eq!(parse("#abcd"), Ok(0xaabb_ccdd));
+        assert_eq!(parse("abcd"), Ok(0xaabb_ccdd));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse(""), Err(ColorParseError::WrongLength(0)));
+        assert_eq!(parse("#"), Err(ColorParseError::WrongLength(0)));
+        assert_eq!(parse("#12345"), Err(ColorParseError::WrongLength(5)));
+        assert_eq!(parse("#123456789"), Err(ColorParseError::WrongLength(9)));
+        assert_eq!(parse("##123"), Err(ColorParseError::NotHex('#')));
+        assert_eq!(parse("#12g"), Err(ColorParseError::NotHex('g')));
+        assert_eq!(parse(" 123"), Err(ColorParseError::NotHex(' ')));
+        assert_eq!(parse("#ééé"), Err(ColorParseError::NotHex('é')));
+    }
+
+    #[test]
+    fn alpha_is_clamped() {
+        let color = Color::rgba8(10, 20, 30, 0x80);
+        assert_eq!(color.clone().multiply_alpha(0.0).as_rgba_u32(), 0x0a14_1e00);
+        assert_eq!(color.clone().multiply_alpha(4.0).as_rgba_u32(), 0x0a14_1eff);
+        assert_eq!(
+            color.clone().multiply_alpha(-1.0).as_rgba_u32(),
+            0x0a14_1e00
+        );
+        assert_eq!(color.clone().with_alpha(2.0).as_rgba_u32(), 0x0a14_1eff);
+        assert!((color.alpha() - 128.0 / 255.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn interpolate() {
+        let black = Color::rgba8(0, 0, 0, 0);
+        let white = Color::rgba8(200, 100, 50, 255);
+        assert_eq!(black.interpolate(&white, 0.0).as_rgba_u32(), 0);
+        assert_eq!(black.interpolate(&white, 1.0).as_rgba_u32(), 0xc864_32ff);
+        assert_eq!(black.interpolate(&white, 0.5).as_rgba_u32(), 0x6432_1980);
+        // out of range fractions are clamped
+        assert_eq!(black.interpolate(&white, 3.0).as_rgba_u32(), 0xc864_32ff);
+        assert_eq!(black.interpolate(&white, -3.0).as_rgba_u32(), 0);
+    }
+
+    #[test]
+    fn key_color_follows_env_scope() {
+        const TOGGLE: Selector = Selector::new("druid-tests.toggle");
+        let red = Color::rgb8(0xff, 0, 0);
+        let blue = Color::rgb8(0, 0, 0xff);
+
+        let painted = Rc::new(RefCell::new(Vec::new()));
+        let swatch = |name: &'static str, color: KeyOrValue<Color>| {
+            ModularWidget::new((color, painted.clone())).paint_fn(
+                move |(color, painted), _ctx, _data: &bool, env| {
+                    let rgba = color.resolve(env).as_rgba_u32();
+                    painted.borrow_mut().push((name, rgba));
+                },
+            )
+        };
+        let toggle = ModularWidget::new(()).event_fn(|_, _ctx, event, data: &mut bool, _env| {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == TOGGLE {
+                    *data = !*data;
+                }
+            }
+        });
+
+        let scoped =
+            swatch("scoped", theme::LABEL_COLOR.into()).env_scope(move |env, on: &bool| {
+                if *on {
+                    env.set(theme::LABEL_COLOR, red.clone());
+                }
+            });
+        let widget = Flex::row()
+            .with_child(scoped, 0.)
+            .with_child(swatch("plain", theme::LABEL_COLOR.into()), 0.)
+            .with_child(swatch("concrete", Color::WHITE.into()), 0.)
+            .with_child(toggle, 0.);
+
+        Harness::create(false, widget, |harness| {
+            harness.configure_env(|env, _| env.set(theme::LABEL_COLOR, blue.clone()));
+            harness.send_initial_events();
+            harness.just_layout();
+            harness.paint();
+            assert_eq!(
+                *painted.borrow(),
+                vec![
+                    ("scoped", 0x0000_ffff),
+                    ("plain", 0x0000_ffff),
+                    ("concrete", 0xffff_ffff),
+                ]
+            );
+
+            painted.borrow_mut().clear();
+            harness.submit_command(TOGGLE, Target::Auto);
+            harness.paint();
+            assert_eq!(
+                *painted.borrow(),
+                vec![
+                    ("scoped", 0xff00_00ff),
+                    ("plain", 0x0000_ffff),
+                    ("concrete", 0xffff_ffff),
+                ]
+            );
+        });
+    }
+}
@@ -57,10 +57,16 @@ pub struct Selector(&'static str);
 /// assert_eq!(command.get_object(), Ok(&vec![1, 3, 10, 12]));
 /// ```
 ///
+/// # Equality
+///
+/// Two commands are equal if they have the same selector and share the same
+/// argument: a command is equal to its clones, but not to a command with a
+/// separately created argument, even one that compares equal.
+///
 /// [`Command::new`]: #method.new
 /// [`Command::get_object`]: #method.get_object
 /// [`Selector`]: struct.Selector.html
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Command {
     /// The command's `Selector`.
     pub selector: Selector,
@@ -87,15 +93,68 @@ pub enum ArgumentError {
 }
 
 /// The target of a command.
+///
+/// Every command is first given to the [`AppDelegate`], whatever its target;
+/// if the delegate doesn't handle it, it goes on to the target.
+///
+/// [`AppDelegate`]: trait.AppDelegate.html
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Target {
-    /// The target is the top-level application.
+    /// The target is the top-level application: the command goes to the
+    /// [`AppDelegate`], and to no window.
+    ///
+    /// [`AppDelegate`]: trait.AppDelegate.html
     Global,
-    /// The target is a window; the event will be delivered to all
-    /// widgets in that window.
+    /// The target is a window; the event will be delivered to the root of
+    /// that window, and from there to all of its widgets.
     Window(WindowId),
-    /// The target is a specific widget.
+    /// The target is a specific widget, wherever it is; the event only goes
+    /// down the branches that contain it.
     Widget(WidgetId),
+    /// The target is the window that submitted the command.
+    ///
+    /// This is resolved when the command is submitted: a command submitted
+    /// from a widget's context goes to that widget's window, and one
+    /// submitted by the delegate or an [`ExtEventSink`], which have no
+    /// window, is `Global`.
+    ///
+    /// [`ExtEventSink`]: struct.ExtEventSink.html
+    Auto,
+}
+
+/// The argument to [`CAPTURE_WINDOW`], and to the selector of a
+/// [`Capture`] widget: where to send the captured image.
+///
+/// The image is sent as the argument of a command with the `reply` selector,
+/// to `target`; it is an [`ImageBuf`].
+///
+/// [`CAPTURE_WINDOW`]: commands/constant.CAPTURE_WINDOW.html
+/// [`Capture`]: widget/struct.Capture.html
+/// [`ImageBuf`]: widget/struct.ImageBuf.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaptureRequest {
+    /// The selector of the command that carries the image.
+    pub reply: Selector,
+    /// Where the image is sent.
+    pub target: Target,
+}
+
+/// A message from a widget to its ancestors.
+///
+/// Notifications are sent with [`EventCtx::submit_notification`], and are
+/// delivered as an [`Event::Notification`] to the parent of the widget that
+/// sent them, and then to each ancestor in turn, until one of them handles
+/// it. Like a [`Command`], a notification has a [`Selector`] and an optional
+/// argument.
+///
+/// [`EventCtx::submit_notification`]: struct.EventCtx.html#method.submit_notification
+/// [`Event::Notification`]: enum.Event.html#variant.Notification
+/// [`Command`]: struct.Command.html
+/// [`Selector`]: struct.Selector.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    command: Command,
+    source: WidgetId,
 }
 
 /// Commands with special meaning, defined by druid.
@@ -127,12 +186,95 @@ pub mod sys {
     /// The command's argument should be the id of the target window.
     pub const SHOW_WINDOW: Selector = Selector::new("druid-builtin.show-window");
 
+    /// Resize the window. The argument must be a [`Size`], in px units.
+    ///
+    /// Once the platform has resized the window, the window's widgets get
+    /// the usual [`Event::Size`].
+    ///
+    /// [`Size`]: ../kurbo/struct.Size.html
+    /// [`Event::Size`]: ../enum.Event.html#variant.Size
+    pub const SET_WINDOW_SIZE: Selector = Selector::new("druid-builtin.set-window-size");
+
+    /// Move the window. The argument must be a [`Point`], the new position of
+    /// the window's top-left corner on the screen, in px units.
+    ///
+    /// [`Point`]: ../kurbo/struct.Point.html
+    pub const SET_WINDOW_POSITION: Selector = Selector::new("druid-builtin.set-window-position");
+
+    /// Maximize, minimize, or restore the window. The argument must be a
+    /// [`WindowState`].
+    ///
+    /// [`WindowState`]: ../enum.WindowState.html
+    pub const SET_WINDOW_STATE: Selector = Selector::new("druid-builtin.set-window-state");
+
+    /// Set the window's content scale, on top of the platform's DPI scaling.
+    /// The argument must be an `f64`; `1.0` is the normal size.
+    ///
+    /// The content is laid out again at the new scale, so text stays crisp.
+    /// The scale is clamped to a reasonable range.
+    pub const SET_USER_SCALE: Selector = Selector::new("druid-builtin.set-user-scale");
+
+    /// Set the color that the window is cleared to before it is painted. The
+    /// argument must be an `Option<Color>`; `None` goes back to the color
+    /// given to [`WindowDesc::with_background_color`], or else to
+    /// [`theme::WINDOW_BACKGROUND_COLOR`].
+    ///
+    /// [`WindowDesc::with_background_color`]: ../struct.WindowDesc.html#method.with_background_color
+    /// [`theme::WINDOW_BACKGROUND_COLOR`]: ../theme/constant.WINDOW_BACKGROUND_COLOR.html
+    pub const SET_BACKGROUND_COLOR: Selector = Selector::new("druid-builtin.set-background-color");
+
+    /// Set the title of the window. The argument must be a `String`.
+    ///
+    /// This replaces the title given to [`WindowDesc::title`], so a title
+    /// that was computed from the data stops following it.
+    ///
+    /// [`WindowDesc::title`]: ../struct.WindowDesc.html#method.title
+    pub const SET_WINDOW_TITLE: Selector = Selector::new("druid-builtin.set-window-title");
+
+    /// Capture the contents of the window as an image. The argument must be
+    /// a [`CaptureRequest`], which says where the [`ImageBuf`] is sent.
+    ///
+    /// This reads back what the platform last displayed, in physical pixels;
+    /// if that fails, the error is logged and no image is sent. To capture a
+    /// single widget, see [`WidgetExt::capture_on`].
+    ///
+    /// [`CaptureRequest`]: ../struct.CaptureRequest.html
+    /// [`ImageBuf`]: ../widget/struct.ImageBuf.html
+    /// [`WidgetExt::capture_on`]: ../widget/trait.WidgetExt.html#method.capture_on
+    pub const CAPTURE_WINDOW: Selector = Selector::new("druid-builtin.capture-window");
+
+    /// Make the window's content larger. This is sent by Ctrl+Plus (or
+    /// Cmd+Plus on macOS), if no widget handles that key.
+    pub const ZOOM_IN: Selector = Selector::new("druid-builtin.zoom-in");
+
+    /// Make the window's content smaller. This is sent by Ctrl+Minus (or
+    /// Cmd+Minus on macOS), if no widget handles that key.
+    pub const ZOOM_OUT: Selector = Selector::new("druid-builtin.zoom-out");
+
+    /// Return the window's content to its normal size. This is sent by
+    /// Ctrl+0 (or Cmd+0 on macOS), if no widget handles that key.
+    pub const ZOOM_RESET: Selector = Selector::new("druid-builtin.zoom-reset");
+
+    /// Sent to all of the widgets in a window with a key press that no
+    /// widget handled, so that default and cancel buttons, and controls
+    /// with a mnemonic, can act on it. The argument is the `KeyEvent`.
+    ///
+    /// A widget that acts on the key should handle the command.
+    pub const UNHANDLED_KEY: Selector = Selector::new("druid-builtin.unhandled-key");
+
     /// Display a context (right-click) menu. The argument must be the [`ContextMenu`].
     /// object to be displayed.
     ///
     /// [`ContextMenu`]: ../struct.ContextMenu.html
     pub const SHOW_CONTEXT_MENU: Selector = Selector::new("druid-builtin.show-context-menu");
 
+    /// Sent to the widget that started a drag with [`EventCtx::start_drag`]
+    /// once it ends. The argument is a `bool`, whether a widget took the
+    /// drop; it is `false` if the drag was cancelled.
+    ///
+    /// [`EventCtx::start_drag`]: ../struct.EventCtx.html#method.start_drag
+    pub const DRAG_FINISHED: Selector = Selector::new("druid-builtin.drag-finished");
+
     /// The selector for a command to set the window's menu. The argument should
     /// be a [`MenuDesc`] object.
     ///
@@ -163,8 +305,12 @@ pub mod sys {
     /// Open a file.
     ///
     /// The argument must be a [`FileInfo`] object for the file to be opened.
+    /// If the open panel allowed [`multi_selection`], it can hold several
+    /// paths, which are available from [`FileInfo::paths`].
     ///
     /// [`FileInfo`]: ../struct.FileInfo.html
+    /// [`multi_selection`]: ../struct.FileDialogOptions.html#method.multi_selection
+    /// [`FileInfo::paths`]: ../struct.FileInfo.html#method.paths
     pub const OPEN_FILE: Selector = Selector::new("druid-builtin.open-file-path");
 
     /// Special command. When issued, the system will show the 'save as' panel,
@@ -179,7 +325,10 @@ pub mod sys {
 
     /// Save the current file.
     ///
-    /// The argument, if present, should be the path where the file should be saved.
+    /// The argument, if present, should be a [`FileInfo`] with the path where
+    /// the file should be saved.
+    ///
+    /// [`FileInfo`]: ../struct.FileInfo.html
     pub const SAVE_FILE: Selector = Selector::new("druid-builtin.menu-file-save");
 
     /// Show the print-setup window.
@@ -205,6 +354,17 @@ pub mod sys {
 
     /// Redo.
     pub const REDO: Selector = Selector::new("druid-builtin.menu-redo");
+
+    /// Set or clear the validation error of a [`TextBox`], such as one found
+    /// by validating its contents on a server. The argument must be an
+    /// `Option<ValidationError>`, and the command should be targeted at the
+    /// text box.
+    ///
+    /// An error that is set this way is cleared when the text is next edited,
+    /// if it is then valid.
+    ///
+    /// [`TextBox`]: ../widget/struct.TextBox.html
+    pub const SET_VALIDATION_ERROR: Selector = Selector::new("druid-builtin.set-validation-error");
 }
 
 impl Selector {
@@ -296,6 +456,59 @@ impl From<Selector> for Command {
     }
 }
 
+impl PartialEq for Arg {
+    fn eq(&self, other: &Arg) -> bool {
+        match (self, other) {
+            (Arg::Reusable(a), Arg::Reusable(b)) => Arc::ptr_eq(a, b),
+            (Arg::OneShot(a), Arg::OneShot(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Notification {
+    pub(crate) fn new(command: Command, source: WidgetId) -> Self {
+        Notification { command, source }
+    }
+
+    /// The notification's `Selector`.
+    pub fn selector(&self) -> &Selector {
+        &self.command.selector
+    }
+
+    /// The id of the widget that sent the notification.
+    pub fn source(&self) -> WidgetId {
+        self.source
+    }
+
+    /// Return a reference to the notification's argument, if it has one,
+    /// as with [`Command::get_object`].
+    ///
+    /// [`Command::get_object`]: struct.Command.html#method.get_object
+    pub fn get_object<T: Any>(&self) -> Result<&T, ArgumentError> {
+        self.command.get_object()
+    }
+
+    /// Take the argument of a one-shot notification, as with
+    /// [`Command::take_object`].
+    ///
+    /// [`Command::take_object`]: struct.Command.html#method.take_object
+    pub fn take_object<T: Any>(&self) -> Result<Box<T>, ArgumentError> {
+        self.command.take_object()
+    }
+}
+
+impl CaptureRequest {
+    /// Create a request for the image to be sent to `target`, with the
+    /// `reply` selector.
+    pub fn new(reply: Selector, target: impl Into<Target>) -> Self {
+        CaptureRequest {
+            reply,
+            target: target.into(),
+        }
+    }
+}
+
 impl std::fmt::Display for Selector {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "Selector('{}')", self.0)
@@ -331,15 +544,13 @@ impl From<WidgetId> for Target {
     }
 }
 
-impl Into<Option<Target>> for WindowId {
-    fn into(self) -> Option<Target> {
-        Some(Target::Window(self))
-    }
-}
-
-impl Into<Option<Target>> for WidgetId {
-    fn into(self) -> Option<Target> {
-        Some(Target::Widget(self))
+impl Target {
+    /// This target, or `auto`, if this is `Target::Auto`.
+    pub(crate) fn resolve(self, auto: impl Into<Target>) -> Target {
+        match self {
+            Target::Auto => auto.into(),
+            target => target,
+        }
     }
 }
 
@@ -353,4 +564,30 @@ mod tests {
         let command = Command::new(sel, objs);
         assert_eq!(command.get_object(), Ok(&vec![0, 1, 2]));
     }
+
+    #[test]
+    fn file_info_round_trip() {
+        use std::path::PathBuf;
+
+        let paths = vec![PathBuf::from("one.txt"), PathBuf::from("two.txt")];
+        let info = crate::FileInfo::from_paths(paths.clone()).unwrap();
+        let command = Command::new(sys::OPEN_FILE, info.clone());
+        let received = command.get_object::<crate::FileInfo>().unwrap();
+        assert_eq!(received, &info);
+        assert_eq!(received.paths(), paths.as_slice());
+        assert_eq!(received.path(), paths[0].as_path());
+    }
+
+    #[test]
+    fn equality() {
+        let sel = Selector::new("my-selector");
+        assert_eq!(Command::from(sel.clone()), Command::from(sel.clone()));
+        assert_ne!(Command::from(sel.clone()), Command::from(Selector::NOOP));
+
+        // arguments are compared by identity
+        let command = Command::new(sel.clone(), 5u32);
+        assert_eq!(command, command.clone());
+        assert_ne!(command, Command::new(sel.clone(), 5u32));
+        assert_ne!(command, Command::from(sel));
+    }
 }
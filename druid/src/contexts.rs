@@ -14,17 +14,25 @@
 
 //! The context types that are passed into various widget methods.
 
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::ops::{Deref, DerefMut};
+use std::rc::Weak;
 use std::time::Instant;
 
 use log;
 
-use crate::core::{BaseState, CommandQueue, FocusChange};
+use crate::core::{BaseState, CommandQueue, CursorChange, FocusChange};
 use crate::piet::Piet;
 use crate::piet::RenderContext;
+use crate::sub_window::{SubWindowDesc, NEW_SUB_WINDOW};
+use crate::widget::HostedView;
+use crate::window::{DragStart, START_DRAG};
 use crate::{
-    Affine, Command, Cursor, Insets, Rect, Size, Target, Text, TimerToken, WidgetId, WindowHandle,
-    WindowId,
+    commands, Affine, Clipboard, Command, ContextMenu, Cursor, Data, DragPayload, Env, IdleToken,
+    Insets, Key, Notification, Point, Rect, Role, Scale, Size, Target, Text, TimerToken, Vec2,
+    Widget, WidgetId, WidgetPod, WindowConfig, WindowHandle, WindowId,
 };
 
 /// A mutable context provided to event handling methods of widgets.
@@ -36,16 +44,23 @@ use crate::{
 pub struct EventCtx<'a> {
     // Note: there's a bunch of state that's just passed down, might
     // want to group that into a single struct.
-    pub(crate) cursor: &'a mut Option<Cursor>,
     /// Commands submitted to be run after this event.
     pub(crate) command_queue: &'a mut CommandQueue,
+    /// Notifications for the parent of the widget, from the widget and its
+    /// descendants.
+    pub(crate) notifications: &'a mut VecDeque<Notification>,
     pub(crate) window_id: WindowId,
     pub(crate) window: &'a WindowHandle,
+    pub(crate) clipboard: &'a Clipboard,
     pub(crate) base_state: &'a mut BaseState,
     pub(crate) focus_widget: Option<WidgetId>,
     pub(crate) had_active: bool,
     pub(crate) is_handled: bool,
     pub(crate) is_root: bool,
+    /// The origin of the widget's coordinate space, in the window's.
+    pub(crate) window_origin: Point,
+    /// The zoom factor of the window's contents.
+    pub(crate) user_scale: f64,
 }
 
 /// A mutable context provided to the [`lifecycle`] method on widgets.
@@ -70,6 +85,7 @@ pub struct LifeCycleCtx<'a> {
 ///
 /// [`request_paint`]: #method.request_paint
 pub struct UpdateCtx<'a> {
+    pub(crate) command_queue: &'a mut CommandQueue,
     pub(crate) window: &'a WindowHandle,
     // Discussion: we probably want to propagate more fine-grained
     // invalidations, which would mean a structure very much like
@@ -77,6 +93,12 @@ pub struct UpdateCtx<'a> {
     // now keep it super-simple.
     pub(crate) window_id: WindowId,
     pub(crate) base_state: &'a mut BaseState,
+    /// The environment that the widget was last given, if any.
+    pub(crate) prev_env: Option<&'a Env>,
+    pub(crate) env: &'a Env,
+    /// An ancestor requested the update with `request_update`, so the
+    /// widget is updated even if its data and environment are the same.
+    pub(crate) force_update: bool,
 }
 
 /// A context provided to layout handling methods of widgets.
@@ -85,9 +107,36 @@ pub struct UpdateCtx<'a> {
 /// creating text layout objects, which are likely to be useful
 /// during widget layout.
 pub struct LayoutCtx<'a, 'b: 'a> {
+    pub(crate) command_queue: &'a mut CommandQueue,
     pub(crate) text_factory: &'a mut Text<'b>,
     pub(crate) paint_insets: Insets,
+    pub(crate) baseline_offset: f64,
     pub(crate) window_id: WindowId,
+    pub(crate) widget_id: WidgetId,
+    /// When the layout pass should stop laying out offscreen widgets, in a
+    /// window with incremental layout.
+    pub(crate) deadline: Option<Instant>,
+    /// The visible part of the window, in the coordinates of the widget
+    /// being laid out.
+    pub(crate) visible: Rect,
+    /// Where a container will put the child it is about to lay out, if it
+    /// knows; used to tell whether a child that hasn't been laid out yet
+    /// is visible.
+    pub(crate) child_origin: Option<Point>,
+    /// The origin of the widget being laid out, in the coordinate space of
+    /// the window.
+    pub(crate) window_origin: Point,
+    /// Where the mouse is, in the coordinates of the widget being laid out,
+    /// if it is over the widget.
+    pub(crate) mouse_pos: Option<Point>,
+    /// The widget being laid out has an active descendant, which has
+    /// captured the mouse.
+    pub(crate) has_active: bool,
+    /// Whether the layout of any widget has been deferred to a later pass.
+    pub(crate) deferred: bool,
+    /// The widget being laid out requested an animation frame.
+    pub(crate) request_anim: bool,
+    pub(crate) scale: Scale,
 }
 
 /// Z-order paint operations with transformations.
@@ -114,28 +163,47 @@ pub struct PaintCtx<'a, 'b: 'a> {
     pub(crate) region: Region,
     pub(crate) base_state: &'a BaseState,
     pub(crate) focus_widget: Option<WidgetId>,
+    pub(crate) scale: Scale,
+    pub(crate) window: &'a WindowHandle,
+    /// The native views painted so far, which are shown in this frame.
+    pub(crate) native_views: Vec<Weak<RefCell<HostedView>>>,
 }
 
 /// A region of a widget, generally used to describe what needs to be drawn.
-#[derive(Debug, Clone)]
-pub struct Region(Rect);
+///
+/// A region is made of rectangles, which may overlap.
+#[derive(Debug, Clone, Default)]
+pub struct Region {
+    rects: Vec<Rect>,
+}
 
 impl<'a> EventCtx<'a> {
     #[deprecated(since = "0.5.0", note = "use request_paint instead")]
     pub fn invalidate(&mut self) {
-        // Note: for the current functionality, we could shortcut and just
-        // request an invalidate on the window. But when we do fine-grained
-        // invalidation, we'll want to compute the invalidation region, and
-        // that needs to be propagated (with, likely, special handling for
-        // scrolling).
-        self.base_state.needs_inval = true;
+        self.request_paint();
     }
 
     /// Request a [`paint`] pass.
     ///
+    /// This repaints the widget's whole paint rect; use
+    /// [`request_paint_rect`] if only part of it changed.
+    ///
     /// [`paint`]: widget/trait.Widget.html#tymethod.paint
+    /// [`request_paint_rect`]: #method.request_paint_rect
     pub fn request_paint(&mut self) {
-        self.base_state.needs_inval = true;
+        self.base_state.request_paint();
+    }
+
+    /// Request a [`paint`] pass, for part of the widget.
+    ///
+    /// The rect is in the widget's coordinate space. Only the parts of the
+    /// window that were requested are painted again, so a widget that
+    /// changed more than this rect should call [`request_paint`] instead.
+    ///
+    /// [`paint`]: widget/trait.Widget.html#tymethod.paint
+    /// [`request_paint`]: #method.request_paint
+    pub fn request_paint_rect(&mut self, rect: Rect) {
+        self.base_state.request_paint_rect(rect);
     }
 
     /// Request a layout pass.
@@ -150,7 +218,28 @@ impl<'a> EventCtx<'a> {
     /// [`layout`]: widget/trait.Widget.html#tymethod.layout
     pub fn request_layout(&mut self) {
         self.base_state.needs_layout = true;
-        self.base_state.needs_inval = true;
+        self.base_state.request_paint();
+    }
+
+    /// Request an [`update`] of this widget and its descendants, even though
+    /// the data may not have changed.
+    ///
+    /// This is for widgets whose appearance depends on something outside of
+    /// the data, such as the time of day, or a controller that changes the
+    /// environment of its child. The update is sent after the event, with
+    /// the same `old_data` as always: the data of the last update. If the
+    /// data hasn't changed, `old_data` and `data` are the same, and
+    /// [`Data::same`] is true of them.
+    ///
+    /// The ancestors of the widget are also updated, so that they can pass
+    /// the update down; those that compare their data, as most do, do
+    /// nothing with it.
+    ///
+    /// [`update`]: widget/trait.Widget.html#tymethod.update
+    /// [`Data::same`]: trait.Data.html#tymethod.same
+    pub fn request_update(&mut self) {
+        self.base_state.request_update = true;
+        self.base_state.update_subtree = true;
     }
 
     /// Indicate that your children have changed.
@@ -160,6 +249,19 @@ impl<'a> EventCtx<'a> {
         self.base_state.children_changed = true;
     }
 
+    /// Remove a child from the widget tree.
+    ///
+    /// The child and all of its descendants get [`WidgetRemoved`], and the
+    /// timers they requested are cancelled. Widgets must call this before they
+    /// drop or replace a child; it also calls [`children_changed`].
+    ///
+    /// [`WidgetRemoved`]: enum.LifeCycle.html#variant.WidgetRemoved
+    /// [`children_changed`]: #method.children_changed
+    pub fn remove_child<T: Data, W: Widget<T>>(&mut self, child: &mut WidgetPod<T, W>) {
+        child.remove(&mut self.make_lifecycle_ctx());
+        self.children_changed();
+    }
+
     /// Get an object which can create text layouts.
     pub fn text(&mut self) -> Text {
         self.window.text()
@@ -167,19 +269,40 @@ impl<'a> EventCtx<'a> {
 
     /// Set the cursor icon.
     ///
-    /// Call this when handling a mouse move event, to set the cursor for the
-    /// widget. A container widget can safely call this method, then recurse
-    /// to its children, as a sequence of calls within an event propagation
-    /// only has the effect of the last one (ie no need to worry about
-    /// flashing).
+    /// The cursor is shown while the widget is hot or active, unless a hot
+    /// or active descendant sets its own; it is kept until the widget is
+    /// neither, or until [`clear_cursor`] is called. While a widget is active,
+    /// its cursor wins over those of the widgets under the mouse, so that it
+    /// stays the same during a drag. When no widget sets a cursor, the window
+    /// shows the arrow.
     ///
-    /// This method is expected to be called mostly from the [`MouseMoved`]
-    /// event handler, but can also be called in response to other events,
-    /// for example pressing a key to change the behavior of a widget.
+    /// This is usually called when handling [`MouseMoved`], but can also be
+    /// called in response to other events, for example pressing a key to
+    /// change the behavior of a widget.
     ///
-    /// [`MouseMoved`]: enum.Event.html#variant.MouseDown
+    /// [`clear_cursor`]: #method.clear_cursor
+    /// [`MouseMoved`]: enum.Event.html#variant.MouseMoved
     pub fn set_cursor(&mut self, cursor: &Cursor) {
-        *self.cursor = Some(cursor.clone());
+        self.base_state.cursor_change = CursorChange::Set(cursor.clone());
+    }
+
+    /// Set the cursor icon, overriding the cursors of the widget's
+    /// descendants.
+    ///
+    /// Like [`set_cursor`], this lasts while the widget is hot or active.
+    ///
+    /// [`set_cursor`]: #method.set_cursor
+    pub fn override_cursor(&mut self, cursor: &Cursor) {
+        self.base_state.cursor_change = CursorChange::Override(cursor.clone());
+    }
+
+    /// Stop showing the cursor set with [`set_cursor`] or
+    /// [`override_cursor`].
+    ///
+    /// [`set_cursor`]: #method.set_cursor
+    /// [`override_cursor`]: #method.override_cursor
+    pub fn clear_cursor(&mut self) {
+        self.base_state.cursor_change = CursorChange::Default;
     }
 
     /// Set the "active" state of the widget.
@@ -198,12 +321,15 @@ impl<'a> EventCtx<'a> {
     ///
     /// The hot status is computed from the widget's layout rect. In a
     /// container hierarchy, all widgets with layout rects containing the
-    /// mouse position have hot status.
+    /// mouse position have hot status. It is also recomputed when layout
+    /// moves a widget under the mouse, or out from under it, as when its
+    /// content is scrolled.
     ///
-    /// Discussion: there is currently some confusion about whether a
-    /// widget can be considered hot when some other widget is active (for
-    /// example, when clicking to one widget and dragging to the next).
-    /// The documentation should clearly state the resolution.
+    /// While some widget is active, it captures the mouse: only the active
+    /// widget and its ancestors have their hot status updated, so dragging
+    /// from one widget to the next doesn't make the next one hot. When the
+    /// active widget is released, hot status is recomputed for the current
+    /// mouse position.
     pub fn is_hot(&self) -> bool {
         self.base_state.is_hot
     }
@@ -215,18 +341,163 @@ impl<'a> EventCtx<'a> {
     /// down and then up.
     ///
     /// When a widget is active, it gets mouse events even when the mouse
-    /// is dragged away.
+    /// is dragged away, and widgets other than it and its ancestors get no
+    /// mouse events.
     ///
     /// [`set_active`]: struct.EventCtx.html#method.set_active
     pub fn is_active(&self) -> bool {
         self.base_state.is_active
     }
 
+    /// Set whether this widget, and with it all of its descendants, is
+    /// disabled.
+    ///
+    /// Disabled widgets are left out of the focus chain, don't receive
+    /// mouse or keyboard events, and should paint themselves dimmed. Each
+    /// widget whose disabled state changes is sent a
+    /// [`LifeCycle::DisabledChanged`] after this event is handled.
+    ///
+    /// [`LifeCycle::DisabledChanged`]: enum.LifeCycle.html#variant.DisabledChanged
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.base_state.set_disabled(disabled);
+    }
+
+    /// Whether this widget is disabled, because it or one of its ancestors
+    /// called [`set_disabled`].
+    ///
+    /// [`set_disabled`]: struct.EventCtx.html#method.set_disabled
+    pub fn is_disabled(&self) -> bool {
+        self.base_state.is_disabled()
+    }
+
+    /// Set what this widget is, for screen readers.
+    ///
+    /// Widgets without a role are left out of the accessibility tree, and
+    /// the widgets inside them are read as if they were inside the widget's
+    /// closest ancestor that has one. This is usually set when the widget is
+    /// added; a widget that shares its context with a child, as [`Button`]
+    /// does with its label, should set its role after the child's.
+    ///
+    /// [`Button`]: widget/struct.Button.html
+    pub fn set_role(&mut self, role: Role) {
+        self.base_state.access.role = Some(role);
+    }
+
+    /// Set the name that screen readers read out for this widget.
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.base_state.access.label = Some(label.into());
+    }
+
+    /// Set the value of this widget, for screen readers, such as the text in
+    /// a text box.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.base_state.access.value = Some(value.into());
+    }
+
+    /// Set whether this widget is on, for screen readers, for widgets that
+    /// can be toggled, such as a checkbox.
+    pub fn set_toggled(&mut self, toggled: bool) {
+        self.base_state.access.toggled = Some(toggled);
+    }
+
+    /// Set this widget's z-index among its siblings.
+    ///
+    /// Containers that stack their children, such as [`Flex`] and
+    /// [`Split`], paint a child with a higher z-index above its siblings
+    /// and give it mouse events first. This is for a widget that draws past
+    /// its bounds, such as an inline dropdown, and takes precedence over the
+    /// z-index that the container set with [`WidgetPod::set_z_index`].
+    ///
+    /// [`Flex`]: widget/struct.Flex.html
+    /// [`Split`]: widget/struct.Split.html
+    /// [`WidgetPod::set_z_index`]: struct.WidgetPod.html#method.set_z_index
+    pub fn set_z_index(&mut self, z_index: i32) {
+        if self.base_state.z_index != Some(z_index) {
+            self.base_state.z_index = Some(z_index);
+            self.request_paint();
+        }
+    }
+
     /// Returns a reference to the current `WindowHandle`.
     pub fn window(&self) -> &WindowHandle {
         &self.window
     }
 
+    /// Convert a point in the widget's coordinate space to the window's.
+    ///
+    /// This takes into account the positions of the widget's ancestors and
+    /// any scrolling, in the same way as the `pos` of a [`MouseEvent`].
+    ///
+    /// [`MouseEvent`]: struct.MouseEvent.html
+    pub fn to_window(&self, point: Point) -> Point {
+        self.window_origin + point.to_vec2()
+    }
+
+    /// Tell the input method where the text cursor is, in the widget's
+    /// coordinate space, so that it lists the candidates of a composition
+    /// by it.
+    ///
+    /// A widget that takes text should call this when it gets focus, and
+    /// as its cursor moves during a [`Composition`].
+    ///
+    /// [`Composition`]: enum.Event.html#variant.Composition
+    pub fn set_ime_cursor_area(&mut self, rect: Rect) {
+        let origin = self.to_window(rect.origin()).to_vec2() * self.user_scale;
+        let size = rect.size() * self.user_scale;
+        let rect = Rect::from_origin_size(origin.to_point(), size);
+        self.window.set_ime_cursor_area(rect);
+    }
+
+    /// Convert a point in the widget's coordinate space to the screen's.
+    ///
+    /// This is where to put a sub-window so that it appears at `point`; see
+    /// [`new_sub_window`].
+    ///
+    /// [`new_sub_window`]: #method.new_sub_window
+    pub fn to_screen(&self, point: Point) -> Point {
+        let in_window = self.to_window(point).to_vec2() * self.user_scale;
+        self.window.to_screen(in_window.to_point())
+    }
+
+    /// Open a window at the level of this widget, such as a tooltip or a
+    /// dropdown, that shows `widget` with its own `data` and `env`.
+    ///
+    /// The window is described by `config`; its position is in screen
+    /// coordinates, such as from [`to_screen`]. If `U` is this widget's
+    /// data type, the two are kept in sync: a change that the window's
+    /// widgets make to their data is made to this widget's, and a change to
+    /// this widget's data in the application reaches the window. Otherwise
+    /// the window has a snapshot of `data`.
+    ///
+    /// A window whose level is a popup closes when the user clicks outside
+    /// of it in this widget's window, or presses Escape. Return the result
+    /// of a popup with a command to this widget, and close it by sending
+    /// [`CLOSE_WINDOW`] to the returned id.
+    ///
+    /// [`to_screen`]: #method.to_screen
+    /// [`CLOSE_WINDOW`]: commands/constant.CLOSE_WINDOW.html
+    pub fn new_sub_window<U: Data>(
+        &mut self,
+        config: WindowConfig,
+        widget: impl Widget<U> + 'static,
+        data: U,
+        env: Env,
+    ) -> WindowId {
+        let desc = SubWindowDesc::new(self.widget_id(), self.window_id, config, widget, data, env);
+        let window_id = desc.id;
+        self.base_state.sub_window_hosts.push(desc.host_id);
+        self.submit_command(Command::one_shot(NEW_SUB_WINDOW, desc), Target::Global);
+        window_id
+    }
+
+    /// Returns a handle to the clipboard.
+    ///
+    /// This is the system clipboard, except in tests, where it may be
+    /// an in-memory one.
+    pub fn clipboard(&self) -> Clipboard {
+        self.clipboard.clone()
+    }
+
     /// Set the event as "handled", which stops its propagation to other
     /// widgets.
     pub fn set_handled(&mut self) {
@@ -264,13 +535,46 @@ impl<'a> EventCtx<'a> {
 
     /// Request keyboard focus.
     ///
+    /// A widget that takes focus should also call [`register_for_focus`] in
+    /// response to `LifeCycle::WidgetAdded`, so that it is in the focus chain;
+    /// in debug builds, a warning is logged if it isn't.
+    ///
     /// See [`has_focus`] for more information.
     ///
     /// [`has_focus`]: struct.EventCtx.html#method.has_focus
+    /// [`register_for_focus`]: struct.LifeCycleCtx.html#method.register_for_focus
     pub fn request_focus(&mut self) {
         self.base_state.request_focus = Some(FocusChange::Focus(self.widget_id()));
     }
 
+    /// Transfer keyboard focus to the widget with the given `WidgetId`.
+    ///
+    /// The target should be a widget that registers for focus; this is
+    /// useful for a widget such as a label to focus the control that it
+    /// labels.
+    pub fn set_focus(&mut self, target: WidgetId) {
+        self.base_state.request_focus = Some(FocusChange::Focus(target));
+    }
+
+    /// Request keyboard focus for a focusable part of this widget.
+    ///
+    /// The part must have been registered with [`register_focusable_part`].
+    ///
+    /// [`register_focusable_part`]: struct.LifeCycleCtx.html#method.register_focusable_part
+    pub fn request_part_focus(&mut self, part_id: WidgetId) {
+        self.base_state.request_focus = Some(FocusChange::Focus(part_id));
+    }
+
+    /// Whether the given focusable part of this widget has keyboard focus.
+    ///
+    /// While a part has focus, [`has_focus`] is also `true` for the widget,
+    /// so that it receives keyboard events.
+    ///
+    /// [`has_focus`]: #method.has_focus
+    pub fn part_has_focus(&self, part_id: WidgetId) -> bool {
+        self.focus_widget == Some(part_id)
+    }
+
     /// Transfer focus to the next focusable widget.
     ///
     /// This should only be called by a widget that currently has focus.
@@ -307,16 +611,28 @@ impl<'a> EventCtx<'a> {
     /// Request an animation frame.
     pub fn request_anim_frame(&mut self) {
         self.base_state.request_anim = true;
-        self.base_state.needs_inval = true;
+        self.base_state.request_paint();
     }
 
     /// Request a timer event.
     ///
     /// The return value is a token, which can be used to associate the
-    /// request with the event.
+    /// request with the event. The event is only sent to this widget.
     pub fn request_timer(&mut self, deadline: Instant) -> TimerToken {
-        self.base_state.request_timer = true;
-        self.window.request_timer(deadline)
+        let token = self.window.request_timer(deadline);
+        self.base_state.timers.insert(token, self.widget_id());
+        token
+    }
+
+    /// Request an [`Idle`] event.
+    ///
+    /// The event is sent to this widget once the current event cycle is
+    /// finished and the platform is idle. Requests made before then are
+    /// coalesced: they return the same token, and a single event is sent.
+    ///
+    /// [`Idle`]: enum.Event.html#variant.Idle
+    pub fn request_idle(&mut self) -> IdleToken {
+        self.base_state.request_idle()
     }
 
     /// The layout size.
@@ -338,17 +654,82 @@ impl<'a> EventCtx<'a> {
     /// submitted during the handling of an event are executed before
     /// the [`update`] method is called.
     ///
+    /// A command sent to [`Target::Auto`] goes to this widget's window.
+    ///
     /// [`Command`]: struct.Command.html
     /// [`update`]: widget/trait.Widget.html#tymethod.update
-    pub fn submit_command(
-        &mut self,
-        command: impl Into<Command>,
-        target: impl Into<Option<Target>>,
-    ) {
-        let target = target.into().unwrap_or_else(|| self.window_id.into());
+    /// [`Target::Auto`]: enum.Target.html#variant.Auto
+    pub fn submit_command(&mut self, command: impl Into<Command>, target: impl Into<Target>) {
+        let target = target.into().resolve(self.window_id);
         self.command_queue.push_back((target, command.into()))
     }
 
+    /// Send a [`Notification`] to the ancestors of this widget.
+    ///
+    /// Once this widget has handled the current event, the notification is
+    /// delivered as an [`Event::Notification`] to its parent, then to each
+    /// ancestor in turn, until one of them calls [`set_handled`]. Unlike a
+    /// command, it is not seen by any other widget. As with
+    /// [`submit_command`], a `Selector` can be passed for a notification
+    /// without an argument.
+    ///
+    /// [`Notification`]: struct.Notification.html
+    /// [`Event::Notification`]: enum.Event.html#variant.Notification
+    /// [`set_handled`]: #method.set_handled
+    /// [`submit_command`]: #method.submit_command
+    pub fn submit_notification(&mut self, command: impl Into<Command>) {
+        let notification = Notification::new(command.into(), self.widget_id());
+        self.notifications.push_back(notification);
+    }
+
+    /// Show a context menu.
+    ///
+    /// The menu's location is in window coordinates, such as the
+    /// `window_pos` of a [`MouseEvent`]. The commands of its items are
+    /// sent to this widget when they are chosen.
+    ///
+    /// [`MouseEvent`]: struct.MouseEvent.html
+    pub fn show_context_menu<T: 'static>(&mut self, mut menu: ContextMenu<T>) {
+        menu.target = Some(self.widget_id());
+        let cmd = Command::new(commands::SHOW_CONTEXT_MENU, menu);
+        self.submit_command(cmd, self.window_id);
+    }
+
+    /// Start dragging `payload` to other widgets in the window.
+    ///
+    /// This is called by an active widget, usually as the mouse moves with
+    /// the button held. Until the button is released, the widgets under the
+    /// mouse get [`Event::DragEnter`], [`Event::DragOver`] and
+    /// [`Event::DragLeave`], and on release the one under it gets
+    /// [`Event::Drop`]. Once the drag ends, this widget is sent
+    /// [`DRAG_FINISHED`], saying whether a widget took the drop.
+    ///
+    /// [`Event::DragEnter`]: enum.Event.html#variant.DragEnter
+    /// [`Event::DragOver`]: enum.Event.html#variant.DragOver
+    /// [`Event::DragLeave`]: enum.Event.html#variant.DragLeave
+    /// [`Event::Drop`]: enum.Event.html#variant.Drop
+    /// [`DRAG_FINISHED`]: commands/constant.DRAG_FINISHED.html
+    pub fn start_drag(&mut self, payload: DragPayload) {
+        let start = DragStart {
+            payload,
+            source: self.widget_id(),
+        };
+        self.submit_command(Command::new(START_DRAG, start), self.window_id);
+    }
+
+    /// Say that this widget would take what is dragged where it is.
+    ///
+    /// This is called while handling [`Event::DragEnter`] or
+    /// [`Event::DragOver`], and when handling [`Event::Drop`] if the widget
+    /// takes the drop. It only holds for the current event.
+    ///
+    /// [`Event::DragEnter`]: enum.Event.html#variant.DragEnter
+    /// [`Event::DragOver`]: enum.Event.html#variant.DragOver
+    /// [`Event::Drop`]: enum.Event.html#variant.Drop
+    pub fn accept_drop(&mut self) {
+        self.base_state.accepts_drop = true;
+    }
+
     /// Get the window id.
     pub fn window_id(&self) -> WindowId {
         self.window_id
@@ -371,14 +752,24 @@ impl<'a> EventCtx<'a> {
 impl<'a> LifeCycleCtx<'a> {
     #[deprecated(since = "0.5.0", note = "use request_paint instead")]
     pub fn invalidate(&mut self) {
-        self.base_state.needs_inval = true;
+        self.request_paint();
     }
 
     /// Request a [`paint`] pass.
     ///
     /// [`paint`]: widget/trait.Widget.html#tymethod.paint
     pub fn request_paint(&mut self) {
-        self.base_state.needs_inval = true;
+        self.base_state.request_paint();
+    }
+
+    /// Request a [`paint`] pass, for part of the widget.
+    ///
+    /// See [`EventCtx::request_paint_rect`] for more information.
+    ///
+    /// [`paint`]: widget/trait.Widget.html#tymethod.paint
+    /// [`EventCtx::request_paint_rect`]: struct.EventCtx.html#method.request_paint_rect
+    pub fn request_paint_rect(&mut self, rect: Rect) {
+        self.base_state.request_paint_rect(rect);
     }
 
     /// Request layout.
@@ -388,7 +779,7 @@ impl<'a> LifeCycleCtx<'a> {
     /// [`EventCtx::request_layout`]: struct.EventCtx.html#method.request_layout
     pub fn request_layout(&mut self) {
         self.base_state.needs_layout = true;
-        self.base_state.needs_inval = true;
+        self.base_state.request_paint();
     }
 
     /// Returns the current widget's `WidgetId`.
@@ -396,6 +787,14 @@ impl<'a> LifeCycleCtx<'a> {
         self.base_state.id
     }
 
+    /// Returns the layout size of the current widget.
+    ///
+    /// See [`EventCtx::size`](struct.EventCtx.html#method.size) for
+    /// additional information.
+    pub fn size(&self) -> Size {
+        self.base_state.size()
+    }
+
     /// Registers a child widget.
     ///
     /// This should only be called in response to a `LifeCycle::WidgetAdded` event.
@@ -406,9 +805,94 @@ impl<'a> LifeCycleCtx<'a> {
         self.base_state.children.add(&child_id);
     }
 
+    /// Set whether this widget, and with it all of its descendants, is
+    /// disabled.
+    ///
+    /// See [`EventCtx::set_disabled`](struct.EventCtx.html#method.set_disabled)
+    /// for more information.
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.base_state.set_disabled(disabled);
+    }
+
+    /// Whether this widget is disabled.
+    ///
+    /// See [`EventCtx::is_disabled`](struct.EventCtx.html#method.is_disabled)
+    /// for more information.
+    pub fn is_disabled(&self) -> bool {
+        self.base_state.is_disabled()
+    }
+
+    /// Set what this widget is, for screen readers.
+    ///
+    /// See [`EventCtx::set_role`](struct.EventCtx.html#method.set_role)
+    /// for more information.
+    pub fn set_role(&mut self, role: Role) {
+        self.base_state.access.role = Some(role);
+    }
+
+    /// Set the name that screen readers read out for this widget.
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.base_state.access.label = Some(label.into());
+    }
+
+    /// Set the value of this widget, for screen readers.
+    ///
+    /// See [`EventCtx::set_value`](struct.EventCtx.html#method.set_value)
+    /// for more information.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.base_state.access.value = Some(value.into());
+    }
+
+    /// Set whether this widget is on, for screen readers.
+    ///
+    /// See [`EventCtx::set_toggled`](struct.EventCtx.html#method.set_toggled)
+    /// for more information.
+    pub fn set_toggled(&mut self, toggled: bool) {
+        self.base_state.access.toggled = Some(toggled);
+    }
+
+    /// Set this widget's z-index among its siblings.
+    ///
+    /// See [`EventCtx::set_z_index`](struct.EventCtx.html#method.set_z_index)
+    /// for more information.
+    pub fn set_z_index(&mut self, z_index: i32) {
+        if self.base_state.z_index != Some(z_index) {
+            self.base_state.z_index = Some(z_index);
+            self.request_paint();
+        }
+    }
+
     /// Register this widget to be eligile to accept focus automatically.
+    ///
+    /// This should only be called in response to a `LifeCycle::WidgetAdded`
+    /// event. Registered widgets are in the focus chain, so they can be
+    /// reached with tab.
+    ///
+    /// Registering more than once has no further effect.
     pub fn register_for_focus(&mut self) {
-        self.base_state.focus_chain.push(self.widget_id());
+        let id = self.widget_id();
+        if !self.base_state.focus_chain.contains(&id) {
+            self.base_state.focus_chain.push(id);
+        }
+    }
+
+    /// Register a part of this widget that can take focus on its own.
+    ///
+    /// Some widgets have an element that should be a stop in the focus chain,
+    /// but that is not a child widget; for instance the draggable bar of a
+    /// [`Split`]. The widget makes up a `WidgetId` for the part, and registers
+    /// it here in response to `LifeCycle::WidgetAdded`, at the point in the
+    /// focus chain where the part should go.
+    ///
+    /// While the part has focus, keyboard events are delivered to the widget,
+    /// which can check [`EventCtx::part_has_focus`]. The widget receives the
+    /// `LifeCycle::RouteFocusChanged` when the part gains or loses focus.
+    ///
+    /// [`Split`]: widget/struct.Split.html
+    /// [`EventCtx::part_has_focus`]: struct.EventCtx.html#method.part_has_focus
+    pub fn register_focusable_part(&mut self, part_id: WidgetId) {
+        self.base_state.children.add(&part_id);
+        self.base_state.focus_chain.push(part_id);
     }
 
     /// Indicate that your children have changed.
@@ -418,25 +902,57 @@ impl<'a> LifeCycleCtx<'a> {
         self.base_state.children_changed = true;
     }
 
+    /// Record that this widget is a label for the widget with the given id.
+    pub(crate) fn set_label_for(&mut self, target: WidgetId) {
+        self.base_state.label_for = Some(target);
+    }
+
+    /// Record that this widget is labeled by the widget with the given id.
+    pub(crate) fn set_labeled_by(&mut self, label: WidgetId) {
+        self.base_state.labeled_by = Some(label);
+    }
+
+    /// Run `f`, leaving out of the focus chain anything it registers.
+    ///
+    /// This is used by widgets that hide their children.
+    pub(crate) fn without_focus(&mut self, f: impl FnOnce(&mut LifeCycleCtx)) {
+        let len = self.base_state.focus_chain.len();
+        f(self);
+        self.base_state.focus_chain.truncate(len);
+    }
+
     /// Request an animation frame.
     pub fn request_anim_frame(&mut self) {
         self.base_state.request_anim = true;
     }
 
+    /// Request an [`update`] of this widget and its descendants, even though
+    /// the data may not have changed.
+    ///
+    /// The update is sent after the current event, or, for an animation
+    /// frame, once it has been painted. See [`EventCtx::request_update`]
+    /// for what the widgets are given.
+    ///
+    /// [`update`]: widget/trait.Widget.html#tymethod.update
+    /// [`EventCtx::request_update`]: struct.EventCtx.html#method.request_update
+    pub fn request_update(&mut self) {
+        self.base_state.request_update = true;
+        self.base_state.update_subtree = true;
+    }
+
     /// Submit a [`Command`] to be run after this event is handled.
     ///
     /// Commands are run in the order they are submitted; all commands
     /// submitted during the handling of an event are executed before
     /// the [`update`] method is called.
     ///
+    /// A command sent to [`Target::Auto`] goes to this widget's window.
+    ///
     /// [`Command`]: struct.Command.html
     /// [`update`]: widget/trait.Widget.html#tymethod.update
-    pub fn submit_command(
-        &mut self,
-        command: impl Into<Command>,
-        target: impl Into<Option<Target>>,
-    ) {
-        let target = target.into().unwrap_or_else(|| self.window_id.into());
+    /// [`Target::Auto`]: enum.Target.html#variant.Auto
+    pub fn submit_command(&mut self, command: impl Into<Command>, target: impl Into<Target>) {
+        let target = target.into().resolve(self.window_id);
         self.command_queue.push_back((target, command.into()))
     }
 }
@@ -444,14 +960,28 @@ impl<'a> LifeCycleCtx<'a> {
 impl<'a> UpdateCtx<'a> {
     #[deprecated(since = "0.5.0", note = "use request_paint instead")]
     pub fn invalidate(&mut self) {
-        self.base_state.needs_inval = true;
+        self.request_paint();
     }
 
     /// Request a [`paint`] pass.
     ///
+    /// This does not request layout; a widget whose size depends on the data
+    /// should call [`request_layout`] instead.
+    ///
     /// [`paint`]: trait.Widget.html#tymethod.paint
+    /// [`request_layout`]: #method.request_layout
     pub fn request_paint(&mut self) {
-        self.base_state.needs_inval = true;
+        self.base_state.request_paint();
+    }
+
+    /// Request a [`paint`] pass, for part of the widget.
+    ///
+    /// See [`EventCtx::request_paint_rect`] for more information.
+    ///
+    /// [`paint`]: trait.Widget.html#tymethod.paint
+    /// [`EventCtx::request_paint_rect`]: struct.EventCtx.html#method.request_paint_rect
+    pub fn request_paint_rect(&mut self, rect: Rect) {
+        self.base_state.request_paint_rect(rect);
     }
 
     /// Request layout.
@@ -461,7 +991,51 @@ impl<'a> UpdateCtx<'a> {
     /// [`EventCtx::request_layout`]: struct.EventCtx.html#method.request_layout
     pub fn request_layout(&mut self) {
         self.base_state.needs_layout = true;
-        self.base_state.needs_inval = true;
+        self.base_state.request_paint();
+    }
+
+    /// Request an animation frame.
+    pub fn request_anim_frame(&mut self) {
+        self.base_state.request_anim = true;
+        self.base_state.request_paint();
+    }
+
+    /// Whether the [`Env`] has changed since the last update.
+    ///
+    /// [`Env`]: struct.Env.html
+    pub fn env_changed(&self) -> bool {
+        self.prev_env
+            .map(|prev| !prev.same(self.env))
+            .unwrap_or(false)
+    }
+
+    /// Whether the value of `key` in the [`Env`] has changed since the last
+    /// update.
+    ///
+    /// Widgets whose size depends on a key should check it in [`update`],
+    /// and call [`request_layout`] if it changed, so that a theme that is
+    /// changed with [`EnvScope`] or [`AppLauncher::configure_env`] affects
+    /// their layout as well as what they paint.
+    ///
+    /// [`Env`]: struct.Env.html
+    /// [`update`]: widget/trait.Widget.html#tymethod.update
+    /// [`request_layout`]: #method.request_layout
+    /// [`EnvScope`]: widget/struct.EnvScope.html
+    /// [`AppLauncher::configure_env`]: struct.AppLauncher.html#method.configure_env
+    pub fn env_key_changed<V>(&self, key: impl Borrow<Key<V>>) -> bool {
+        self.prev_env
+            .map(|prev| !prev.same_value(self.env, key.borrow()))
+            .unwrap_or(false)
+    }
+
+    /// Request an [`Idle`] event.
+    ///
+    /// See [`EventCtx::request_idle`] for more information.
+    ///
+    /// [`Idle`]: enum.Event.html#variant.Idle
+    /// [`EventCtx::request_idle`]: struct.EventCtx.html#method.request_idle
+    pub fn request_idle(&mut self) -> IdleToken {
+        self.base_state.request_idle()
     }
 
     /// Indicate that your children have changed.
@@ -471,6 +1045,24 @@ impl<'a> UpdateCtx<'a> {
         self.base_state.children_changed = true;
     }
 
+    /// Remove a child from the widget tree.
+    ///
+    /// The child and all of its descendants get [`WidgetRemoved`], and the
+    /// timers they requested are cancelled. Widgets must call this before they
+    /// drop or replace a child; it also calls [`children_changed`].
+    ///
+    /// [`WidgetRemoved`]: enum.LifeCycle.html#variant.WidgetRemoved
+    /// [`children_changed`]: #method.children_changed
+    pub fn remove_child<T: Data, W: Widget<T>>(&mut self, child: &mut WidgetPod<T, W>) {
+        let mut ctx = LifeCycleCtx {
+            command_queue: self.command_queue,
+            base_state: self.base_state,
+            window_id: self.window_id,
+        };
+        child.remove(&mut ctx);
+        self.children_changed();
+    }
+
     /// Get an object which can create text layouts.
     pub fn text(&mut self) -> Text {
         self.window.text()
@@ -491,6 +1083,71 @@ impl<'a> UpdateCtx<'a> {
     pub fn widget_id(&self) -> WidgetId {
         self.base_state.id
     }
+
+    /// Returns the layout size of the current widget.
+    ///
+    /// See [`EventCtx::size`](struct.EventCtx.html#method.size) for
+    /// additional information.
+    pub fn size(&self) -> Size {
+        self.base_state.size()
+    }
+
+    /// Set whether this widget, and with it all of its descendants, is
+    /// disabled.
+    ///
+    /// See [`EventCtx::set_disabled`](struct.EventCtx.html#method.set_disabled)
+    /// for more information.
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.base_state.set_disabled(disabled);
+    }
+
+    /// Whether this widget is disabled.
+    ///
+    /// See [`EventCtx::is_disabled`](struct.EventCtx.html#method.is_disabled)
+    /// for more information.
+    pub fn is_disabled(&self) -> bool {
+        self.base_state.is_disabled()
+    }
+
+    /// Set what this widget is, for screen readers.
+    ///
+    /// See [`EventCtx::set_role`](struct.EventCtx.html#method.set_role)
+    /// for more information.
+    pub fn set_role(&mut self, role: Role) {
+        self.base_state.access.role = Some(role);
+    }
+
+    /// Set the name that screen readers read out for this widget.
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.base_state.access.label = Some(label.into());
+    }
+
+    /// Set the value of this widget, for screen readers.
+    ///
+    /// See [`EventCtx::set_value`](struct.EventCtx.html#method.set_value)
+    /// for more information.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.base_state.access.value = Some(value.into());
+    }
+
+    /// Set whether this widget is on, for screen readers.
+    ///
+    /// See [`EventCtx::set_toggled`](struct.EventCtx.html#method.set_toggled)
+    /// for more information.
+    pub fn set_toggled(&mut self, toggled: bool) {
+        self.base_state.access.toggled = Some(toggled);
+    }
+
+    /// Set this widget's z-index among its siblings.
+    ///
+    /// See [`EventCtx::set_z_index`](struct.EventCtx.html#method.set_z_index)
+    /// for more information.
+    pub fn set_z_index(&mut self, z_index: i32) {
+        if self.base_state.z_index != Some(z_index) {
+            self.base_state.z_index = Some(z_index);
+            self.request_paint();
+        }
+    }
 }
 
 impl<'a, 'b> LayoutCtx<'a, 'b> {
@@ -518,6 +1175,45 @@ impl<'a, 'b> LayoutCtx<'a, 'b> {
     pub fn set_paint_insets(&mut self, insets: impl Into<Insets>) {
         self.paint_insets = insets.into().nonnegative();
     }
+
+    /// Set the distance from the bottom of this widget to the baseline of
+    /// its text.
+    ///
+    /// This is used to line up widgets with [`CrossAxisAlignment::Baseline`];
+    /// widgets without text don't need to set it, and their baseline is
+    /// their bottom edge.
+    ///
+    /// For more information, see [`WidgetPod::baseline_offset`].
+    ///
+    /// [`CrossAxisAlignment::Baseline`]: widget/enum.CrossAxisAlignment.html#variant.Baseline
+    /// [`WidgetPod::baseline_offset`]: struct.WidgetPod.html#method.baseline_offset
+    pub fn set_baseline_offset(&mut self, offset: f64) {
+        self.baseline_offset = offset;
+    }
+
+    /// Request an animation frame.
+    ///
+    /// This is for an animation that starts in layout, such as a child's
+    /// move with [`WidgetPod::set_layout_rect_animated`]; the first frame
+    /// comes after the window is painted.
+    ///
+    /// [`WidgetPod::set_layout_rect_animated`]: struct.WidgetPod.html#method.set_layout_rect_animated
+    pub fn request_anim_frame(&mut self) {
+        self.request_anim = true;
+    }
+
+    /// get the `WidgetId` of the current widget.
+    pub fn widget_id(&self) -> WidgetId {
+        self.widget_id
+    }
+
+    /// The number of physical pixels in a display point, in this window.
+    ///
+    /// See [`PaintCtx::scale`](struct.PaintCtx.html#method.scale) for
+    /// additional information.
+    pub fn scale(&self) -> Scale {
+        self.scale
+    }
 }
 
 impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
@@ -542,6 +1238,14 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
         self.base_state.is_active
     }
 
+    /// Whether this widget is disabled, and should paint itself dimmed.
+    ///
+    /// See [`EventCtx::is_disabled`](struct.EventCtx.html#method.is_disabled)
+    /// for more information.
+    pub fn is_disabled(&self) -> bool {
+        self.base_state.is_disabled()
+    }
+
     /// Returns the layout size of the current widget.
     ///
     /// See [`EventCtx::size`](struct.EventCtx.html#method.size) for
@@ -559,6 +1263,29 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
             .unwrap_or(false)
     }
 
+    /// Whether the given focusable part of this widget has keyboard focus.
+    ///
+    /// See [`LifeCycleCtx::register_focusable_part`] for more information.
+    ///
+    /// [`LifeCycleCtx::register_focusable_part`]: struct.LifeCycleCtx.html#method.register_focusable_part
+    pub fn part_has_focus(&self, part_id: WidgetId) -> bool {
+        self.focus_widget == Some(part_id)
+    }
+
+    /// The number of physical pixels in a display point, in this window.
+    ///
+    /// This includes the window's content scale, so it can be used to line
+    /// up lines and fills with the pixels of the display, as with
+    /// [`Scale::snap_rect`]. It changes when the window moves to a display
+    /// with a different resolution; widgets are sent an
+    /// [`Event::WindowScale`] and laid out again when it does.
+    ///
+    /// [`Scale::snap_rect`]: struct.Scale.html#method.snap_rect
+    /// [`Event::WindowScale`]: enum.Event.html#variant.WindowScale
+    pub fn scale(&self) -> Scale {
+        self.scale
+    }
+
     /// Returns the currently visible [`Region`].
     ///
     /// [`Region`]: struct.Region.html
@@ -580,9 +1307,13 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
             window_id: self.window_id,
             focus_widget: self.focus_widget,
             region: region.into(),
+            scale: self.scale,
+            window: self.window,
+            native_views: Vec::new(),
         };
         f(&mut child_ctx);
         self.z_ops.append(&mut child_ctx.z_ops);
+        self.native_views.append(&mut child_ctx.native_views);
     }
 
     /// Allows to specify order for paint operations.
@@ -603,21 +1334,76 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
 }
 
 impl Region {
+    /// The empty region.
+    pub const EMPTY: Region = Region { rects: Vec::new() };
+
+    /// Returns the rectangles that make up the region.
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
     /// Returns the smallest `Rect` that encloses the entire region.
     pub fn to_rect(&self) -> Rect {
-        self.0
+        let mut rects = self.rects.iter();
+        let first = rects.next().copied().unwrap_or(Rect::ZERO);
+        rects.fold(first, |bounds, rect| bounds.union(*rect))
+    }
+
+    /// Returns `true` if the region is empty.
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// Adds a rectangle to the region.
+    ///
+    /// Empty rectangles are ignored, as are rectangles that are already
+    /// covered by one in the region.
+    pub fn add_rect(&mut self, rect: Rect) {
+        let rect = rect.abs();
+        if rect.area() == 0.0 || self.rects.iter().any(|r| same_rect(r.union(rect), *r)) {
+            return;
+        }
+        self.rects.retain(|r| !same_rect(rect.union(*r), rect));
+        self.rects.push(rect);
+    }
+
+    /// Removes everything from the region.
+    pub fn clear(&mut self) {
+        self.rects.clear();
+    }
+
+    /// Keeps only the parts of the region that are inside `rect`.
+    pub fn intersect_with(&mut self, rect: Rect) {
+        let rects = std::mem::take(&mut self.rects);
+        for r in rects {
+            self.add_rect(r.intersect(rect));
+        }
+    }
+
+    /// Returns the region moved by `offset`.
+    pub fn translated(&self, offset: Vec2) -> Region {
+        let rects = self.rects.iter().map(|r| *r + offset).collect();
+        Region { rects }
     }
 
     /// Returns `true` if `self` intersects with `other`.
     #[inline]
     pub fn intersects(&self, other: Rect) -> bool {
-        self.0.intersect(other).area() > 0.
+        self.rects.iter().any(|r| r.intersect(other).area() > 0.)
     }
 }
 
+/// Whether two rects have the same coordinates; kurbo's `Rect` doesn't
+/// implement `PartialEq`.
+fn same_rect(a: Rect, b: Rect) -> bool {
+    (a.x0, a.y0, a.x1, a.y1) == (b.x0, b.y0, b.x1, b.y1)
+}
+
 impl From<Rect> for Region {
     fn from(src: Rect) -> Region {
-        Region(src)
+        let mut region = Region::EMPTY;
+        region.add_rect(src);
+        region
     }
 }
 
@@ -14,18 +14,160 @@
 
 //! The fundamental druid types.
 
-use std::collections::VecDeque;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
 use log;
 
+use crate::access::AccessInfo;
 use crate::bloom::Bloom;
-use crate::kurbo::{Affine, Insets, Rect, Shape, Size};
-use crate::piet::RenderContext;
+use crate::kurbo::{Affine, Insets, Point, Rect, Shape, Size, Vec2};
+use crate::piet::{Color, FontBuilder, RenderContext, Text, TextLayout, TextLayoutBuilder};
+use crate::shell::{Counter, Cursor, IdleToken};
+use crate::sub_window::{
+    SUB_WINDOW_HOST_CLOSED, SUB_WINDOW_HOST_TO_PARENT, SUB_WINDOW_PARENT_TO_HOST,
+};
 use crate::{
-    BoxConstraints, Command, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
-    PaintCtx, Target, UpdateCtx, Widget, WidgetId,
+    theme, Animator, BoxConstraints, Command, Curve, Data, Env, Event, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Region, Target, TimerToken, UpdateCtx, Widget, WidgetId,
+    WindowId,
 };
 
+#[cfg(test)]
+thread_local! {
+    /// The widgets that `warn_unregistered_focus` warned about, for tests.
+    pub(crate) static UNREGISTERED_FOCUS_WARNINGS:
+        std::cell::RefCell<Vec<(&'static str, WidgetId)>> = Default::default();
+}
+
+/// Warn, in debug builds, that a widget took focus without being in the
+/// focus chain.
+///
+/// The widget still gets focus, but tab can't move focus back to it.
+fn warn_unregistered_focus(type_name: &'static str, id: WidgetId) {
+    if cfg!(debug_assertions) {
+        log::warn!(
+            "{} {:?} requested focus without registering for it; it should call \
+             LifeCycleCtx::register_for_focus in response to LifeCycle::WidgetAdded",
+            type_name,
+            id
+        );
+        #[cfg(test)]
+        UNREGISTERED_FOCUS_WARNINGS.with(|warnings| warnings.borrow_mut().push((type_name, id)));
+    }
+}
+
+thread_local! {
+    /// How many widgets with each id each window has, in debug builds.
+    static LIVE_WIDGET_IDS: RefCell<HashMap<(WindowId, WidgetId), usize>> = Default::default();
+}
+
+#[cfg(test)]
+thread_local! {
+    /// The widgets that `IdRegistration::register` found duplicates of, for tests.
+    pub(crate) static DUPLICATE_ID_ERRORS: RefCell<Vec<(&'static str, WidgetId)>> =
+        Default::default();
+}
+
+/// A widget's entry in `LIVE_WIDGET_IDS`, which is removed when the widget
+/// is dropped.
+///
+/// Two widgets in a window with the same id confuse focus and command
+/// routing, so in debug builds we count the widgets with each id, and
+/// complain when a widget is added with an id that is already in use.
+#[derive(Default)]
+struct IdRegistration(Option<(WindowId, WidgetId)>);
+
+impl IdRegistration {
+    fn register(window_id: WindowId, id: WidgetId, type_name: &'static str, env: &Env) -> Self {
+        if !cfg!(debug_assertions) {
+            return IdRegistration(None);
+        }
+        let key = (window_id, id);
+        let count = LIVE_WIDGET_IDS.with(|ids| {
+            let mut ids = ids.borrow_mut();
+            let count = ids.entry(key).or_insert(0);
+            *count += 1;
+            *count
+        });
+        if count > 1 {
+            log::error!(
+                "{} {:?} has the same id as another widget in its window; \
+                 each WidgetId must only be used for one widget at a time",
+                type_name,
+                id
+            );
+            #[cfg(test)]
+            DUPLICATE_ID_ERRORS.with(|errors| errors.borrow_mut().push((type_name, id)));
+            if env.try_get(Env::PANIC_ON_DUPLICATE_ID).unwrap_or(false) {
+                panic!("duplicate WidgetId {:?}", id);
+            }
+        }
+        IdRegistration(Some(key))
+    }
+}
+
+impl Drop for IdRegistration {
+    fn drop(&mut self) {
+        if let Some(key) = self.0.take() {
+            // the thread local may already be gone if we are dropped
+            // while the thread exits
+            let _ = LIVE_WIDGET_IDS.try_with(|ids| {
+                let mut ids = ids.borrow_mut();
+                if let Some(count) = ids.get_mut(&key) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ids.remove(&key);
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// Whether two rects overlap, counting rects that only touch and empty rects
+/// inside the other.
+fn overlaps(a: Rect, b: Rect) -> bool {
+    a.x0 <= b.x1 && b.x0 <= a.x1 && a.y0 <= b.y1 && b.y0 <= a.y1
+}
+
+/// The order in which to paint children with the given z-indices.
+///
+/// Children are sorted by z-index, keeping the order in which they were added
+/// for equal z-indices, so that later children are painted above earlier ones.
+/// Pointer events go to the children in the reverse of this order, so that
+/// the topmost child gets them first.
+pub(crate) fn paint_order(z_indices: impl Iterator<Item = i32>) -> Vec<usize> {
+    let mut order: Vec<(i32, usize)> = z_indices.enumerate().map(|(i, z)| (z, i)).collect();
+    // the sort is stable, so equal z-indices stay in order
+    order.sort_by_key(|(z, _)| *z);
+    order.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Handle a command that a sub-window's host sent to the widget that opened
+/// the window, returning `true` if it was one.
+///
+/// The host's data replaces the widget's if it is of the same type; otherwise
+/// the sub-window's data is only a snapshot, and the change is dropped.
+fn sync_from_sub_window<T: Data>(state: &mut BaseState, cmd: &Command, data: &mut T) -> bool {
+    match cmd.selector {
+        SUB_WINDOW_HOST_TO_PARENT => {
+            if let Ok(new_data) = cmd.get_object::<T>() {
+                *data = new_data.clone();
+            }
+            true
+        }
+        SUB_WINDOW_HOST_CLOSED => {
+            if let Ok(host) = cmd.get_object::<WidgetId>() {
+                state.sub_window_hosts.retain(|id| id != host);
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
 /// Convenience type for dynamic boxed widget.
 pub type BoxedWidget<T> = WidgetPod<T, Box<dyn Widget<T>>>;
 
@@ -49,8 +191,24 @@ pub struct WidgetPod<T, W> {
     old_data: Option<T>,
     env: Option<Env>,
     inner: W,
+    /// The position of the widget in its parent's paint order.
+    z_index: i32,
+    /// Whether the widget is updated even if its data and env are the same.
+    always_update: bool,
+    /// The move of the layout rect started by `set_layout_rect_animated`.
+    layout_transition: Option<Box<LayoutTransition>>,
+    id_registration: IdRegistration,
 }
 
+/// A widget's layout rect, moving to the one its container last gave it.
+struct LayoutTransition {
+    animator: Animator,
+    target: Rect,
+}
+
+/// The name of the layout rect in a `LayoutTransition`'s animator.
+const LAYOUT_RECT: &str = "layout_rect";
+
 /// Generic state for all widgets in the hierarchy.
 ///
 /// This struct contains the widget's layout rect, flags
@@ -70,42 +228,135 @@ pub struct WidgetPod<T, W> {
 pub(crate) struct BaseState {
     pub(crate) id: WidgetId,
     pub(crate) layout_rect: Rect,
+    /// The size in the last `LifeCycle::Size` sent to the widget.
+    pub(crate) notified_size: Size,
+    /// The origin of the widget in the coordinate space of the window, as
+    /// of the last layout.
+    pub(crate) window_origin: Point,
+    /// The origin in the last `LifeCycle::WindowOriginChanged` sent to the
+    /// widget.
+    pub(crate) notified_window_origin: Point,
+    /// Whether the widget was under the mouse when its parent last placed
+    /// it, until the `RouteWindowOrigin` after that layout; `None` if it was
+    /// placed while its hot state couldn't change.
+    pub(crate) placed_hot: Option<bool>,
+    /// The widget was placed with the deprecated `set_raw_layout_rect`, so
+    /// its `window_origin` is worked out from its parent's in the next
+    /// `RouteWindowOrigin`.
+    pub(crate) placed_without_ctx: bool,
     /// The insets applied to the layout rect to generate the paint rect.
     /// In general, these will be zero; the exception is for things like
     /// drop shadows or overflowing text.
     paint_insets: Insets,
+    /// The distance from the bottom of the layout rect to the baseline.
+    baseline_offset: f64,
 
     // TODO: consider using bitflags for the booleans.
+    /// The parts of the widget that need to be painted again, in its own
+    /// coordinate space, including those requested by its descendants.
+    pub(crate) invalid: Region,
 
-    // This should become an invalidation rect.
-    pub(crate) needs_inval: bool,
+    /// The offset of the widget's content from its layout origin, such as
+    /// the scroll offset of a scrolled child; the child's invalid rects are
+    /// moved back by it as they are merged into its parent's.
+    pub(crate) viewport_offset: Vec2,
 
     pub(crate) is_hot: bool,
 
     pub(crate) is_active: bool,
 
+    /// Something that is dragged is over the widget.
+    pub(crate) drag_over: bool,
+    /// The widget or a descendant would take what is dragged, where it is,
+    /// as set in the last event routed to it.
+    pub(crate) accepts_drop: bool,
+
     pub(crate) needs_layout: bool,
 
+    /// The constraints and the resulting size of the last layout.
+    pub(crate) last_layout: Option<(BoxConstraints, Size)>,
+
+    /// The widget's layout was deferred in incremental layout, so its layout
+    /// rect is only a placeholder.
+    pub(crate) layout_deferred: bool,
+
     /// Any descendant is active.
     has_active: bool,
 
     /// Any descendant has requested an animation frame.
     pub(crate) request_anim: bool,
 
-    /// Any descendant has requested a timer.
+    /// The timers requested by this widget and its descendants that have
+    /// not fired yet, with the widget that requested each.
     ///
-    /// Note: we don't have any way of clearing this request, as it's
-    /// likely not worth the complexity.
-    pub(crate) request_timer: bool,
+    /// A timer event is only routed to the children that have its token.
+    pub(crate) timers: HashMap<TimerToken, WidgetId>,
+
+    /// This widget or a descendant has requested an update with
+    /// `request_update`, which has not been sent yet.
+    pub(crate) request_update: bool,
+    /// This widget itself requested the update, so its descendants are
+    /// updated too.
+    pub(crate) update_subtree: bool,
+
+    /// This widget or a descendant has requested an idle event, which has
+    /// not been delivered yet.
+    pub(crate) request_idle: bool,
+    /// The token for this widget's own pending idle request.
+    pub(crate) idle_token: Option<IdleToken>,
 
     pub(crate) focus_chain: Vec<WidgetId>,
     pub(crate) request_focus: Option<FocusChange>,
     pub(crate) children: Bloom<WidgetId>,
     pub(crate) children_changed: bool,
+
+    /// The widget called `set_disabled(true)`.
+    is_explicitly_disabled: bool,
+    /// An ancestor of the widget is disabled.
+    ancestor_disabled: bool,
+    /// Whether the widget was disabled in the last `DisabledChanged` it was
+    /// sent, or when it was added.
+    was_disabled: bool,
+    /// This widget or a descendant changed its explicit disabled state, and
+    /// the change hasn't been routed yet.
+    pub(crate) disabled_changed: bool,
+
+    /// The widget that this widget is a label for, for accessibility.
+    pub(crate) label_for: Option<WidgetId>,
+    /// The widget that labels this widget, for accessibility.
+    pub(crate) labeled_by: Option<WidgetId>,
+    /// What the widget set to describe itself to screen readers.
+    pub(crate) access: AccessInfo,
+
+    /// The hosts of the open sub-windows that this widget opened, which are
+    /// sent its data when it changes.
+    pub(crate) sub_window_hosts: Vec<WidgetId>,
+
+    /// The cursor that the widget asked for, which is cleared once it is
+    /// neither hot nor active.
+    pub(crate) cursor_change: CursorChange,
+    /// The cursor for this widget and its descendants, as resolved in the
+    /// last event that was routed to it.
+    pub(crate) cursor: Option<Cursor>,
+
+    /// The z-index that the widget asked for, which takes precedence over
+    /// the one its container set.
+    pub(crate) z_index: Option<i32>,
+}
+
+/// A widget's request for a cursor.
+#[derive(Clone)]
+pub(crate) enum CursorChange {
+    /// The widget has no cursor of its own.
+    Default,
+    /// The widget's cursor, unless a hot or active descendant has one.
+    Set(Cursor),
+    /// The widget's cursor, whatever its descendants ask for.
+    Override(Cursor),
 }
 
 /// Methods by which a widget can attempt to change focus state.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) enum FocusChange {
     /// The focused widget is giving up focus.
     Resign,
@@ -132,6 +383,10 @@ impl<T, W: Widget<T>> WidgetPod<T, W> {
             old_data: None,
             env: None,
             inner,
+            z_index: 0,
+            always_update: false,
+            layout_transition: None,
+            id_registration: IdRegistration::default(),
         }
     }
 
@@ -177,9 +432,161 @@ impl<T, W: Widget<T>> WidgetPod<T, W> {
     /// Set layout rectangle.
     ///
     /// Intended to be called on child widget in container's `layout`
-    /// implementation.
-    pub fn set_layout_rect(&mut self, layout_rect: Rect) {
+    /// implementation, after the child's own layout. If the size changes,
+    /// the widget is sent [`LifeCycle::Size`] after the layout, and if its
+    /// origin in the window changes, [`LifeCycle::WindowOriginChanged`].
+    ///
+    /// If the widget moves under the mouse, or out from under it, it
+    /// becomes hot, or stops being hot, right away, and is sent
+    /// [`LifeCycle::HotChanged`] without waiting for the mouse to move.
+    ///
+    /// [`LifeCycle::Size`]: enum.LifeCycle.html#variant.Size
+    /// [`LifeCycle::WindowOriginChanged`]: enum.LifeCycle.html#variant.WindowOriginChanged
+    /// [`LifeCycle::HotChanged`]: enum.LifeCycle.html#variant.HotChanged
+    pub fn set_layout_rect(&mut self, ctx: &mut LayoutCtx, data: &T, env: &Env, layout_rect: Rect) {
+        self.set_rect(layout_rect);
+        self.placed(ctx, data, env);
+    }
+
+    /// Set layout rectangle, without the `LayoutCtx`.
+    ///
+    /// This is what [`set_layout_rect`] did before it took the context. The
+    /// widget's origin in the window is worked out from its parent's after
+    /// the layout, but it doesn't become hot, or stop being hot, until the
+    /// mouse moves.
+    ///
+    /// [`set_layout_rect`]: #method.set_layout_rect
+    #[deprecated(
+        since = "0.5.0",
+        note = "use set_layout_rect, with the LayoutCtx, the data and the env, instead"
+    )]
+    pub fn set_raw_layout_rect(&mut self, layout_rect: Rect) {
+        self.set_rect(layout_rect);
+        self.state.placed_hot = None;
+        self.state.placed_without_ctx = true;
+    }
+
+    fn set_rect(&mut self, layout_rect: Rect) {
         self.state.layout_rect = layout_rect;
+        self.layout_transition = None;
+    }
+
+    /// Record where the widget is in the window, now that its parent has
+    /// placed it, and update whether it is hot, in case it moved under the
+    /// mouse or out from under it.
+    ///
+    /// A widget under the mouse is only hot if its parent is, which is known
+    /// once the parent has been placed too, so it is made hot in the
+    /// `RouteWindowOrigin` after the layout.
+    fn placed(&mut self, ctx: &mut LayoutCtx, data: &T, env: &Env) {
+        self.state.placed_without_ctx = false;
+        let offset = self.state.layout_rect.origin().to_vec2() - self.state.viewport_offset;
+        self.state.window_origin = ctx.window_origin + offset;
+
+        // as in events, a widget that isn't on the path to the active one
+        // can't become hot or stop being hot
+        let captured = ctx.has_active && !self.state.has_active;
+        if self.old_data.is_none()
+            || captured
+            || self.state.layout_deferred
+            || self.state.is_disabled()
+        {
+            self.state.placed_hot = None;
+            return;
+        }
+        let rect = self.state.layout_rect - self.state.viewport_offset;
+        let under_mouse = ctx.mouse_pos.map_or(false, |pos| rect.winding(pos) != 0);
+        self.state.placed_hot = Some(under_mouse);
+        if !under_mouse && self.state.is_hot {
+            self.state.is_hot = false;
+            let mut child_ctx = LifeCycleCtx {
+                command_queue: ctx.command_queue,
+                base_state: &mut self.state,
+                window_id: ctx.window_id,
+            };
+            self.inner
+                .lifecycle(&mut child_ctx, &LifeCycle::HotChanged(false), data, env);
+        }
+    }
+
+    /// Set the layout rectangle, moving the widget there over `duration`.
+    ///
+    /// This is called in the container's `layout` instead of
+    /// [`set_layout_rect`], with the rect the widget should end up at, on
+    /// every layout. When the rect is different from the last one, the
+    /// widget moves from where it is to the new one, following `curve`, even
+    /// if it was already moving; otherwise it carries on. The widget is laid
+    /// out as usual, so only its position and the size that it is painted
+    /// at change while it moves.
+    ///
+    /// A widget that hasn't been given a layout rect yet starts from an
+    /// empty one at the origin; to slide it in from somewhere else, give it
+    /// that rect with `set_layout_rect` first.
+    ///
+    /// [`set_layout_rect`]: #method.set_layout_rect
+    pub fn set_layout_rect_animated(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        data: &T,
+        env: &Env,
+        layout_rect: Rect,
+        duration: Duration,
+        curve: Curve,
+    ) {
+        let current = self.state.layout_rect;
+        let transition = self.layout_transition.get_or_insert_with(|| {
+            let mut animator = Animator::new();
+            animator.set(LAYOUT_RECT, current);
+            Box::new(LayoutTransition {
+                animator,
+                target: current,
+            })
+        });
+        let target = transition.target;
+        if target.origin() != layout_rect.origin() || target.size() != layout_rect.size() {
+            transition
+                .animator
+                .animate_to(LAYOUT_RECT, layout_rect, duration, curve);
+            transition.target = layout_rect;
+        }
+        if transition.animator.is_animating() {
+            ctx.request_anim_frame();
+        }
+        self.state.layout_rect = transition.animator.get(LAYOUT_RECT).unwrap_or(layout_rect);
+        self.placed(ctx, data, env);
+    }
+
+    /// Move the widget on in its layout transition, if it is in one,
+    /// returning whether it is still moving.
+    fn advance_layout_transition(&mut self, interval: u64) -> bool {
+        let transition = match self.layout_transition.as_mut() {
+            Some(transition) if transition.animator.is_animating() => transition,
+            _ => return false,
+        };
+        let old = self.state.paint_rect();
+        let moving = transition.animator.advance(interval);
+        self.state.layout_rect = transition
+            .animator
+            .get(LAYOUT_RECT)
+            .unwrap_or(transition.target);
+        // where it was and where it is are painted again, in the coordinate
+        // space of where it is
+        let origin = self.state.layout_rect.origin().to_vec2();
+        self.state.invalid.add_rect(old - origin);
+        self.state.request_paint();
+        moving
+    }
+
+    /// Set the offset of the widget's content from its layout origin.
+    ///
+    /// A container that paints its child moved back from the origin of its
+    /// layout rect, as a [`Scroll`] does, sets this to how far the child is
+    /// scrolled, so that the rects the child asks to paint are found where
+    /// they are shown.
+    ///
+    /// [`Scroll`]: widget/struct.Scroll.html
+    pub fn set_viewport_offset(&mut self, offset: Vec2) {
+        self.state.viewport_offset = offset;
     }
 
     #[deprecated(since = "0.5.0", note = "use layout_rect() instead")]
@@ -188,6 +595,41 @@ impl<T, W: Widget<T>> WidgetPod<T, W> {
         self.state.layout_rect
     }
 
+    /// Set the z-index of the widget.
+    ///
+    /// Containers that support it use this to order overlapping children:
+    /// children with a higher z-index are painted above those with a lower
+    /// one, and get mouse events first. Children with the same z-index are
+    /// in the order they were added. The default is `0`.
+    ///
+    /// A widget can also ask to be raised with [`EventCtx::set_z_index`],
+    /// which takes precedence over this.
+    ///
+    /// [`EventCtx::set_z_index`]: struct.EventCtx.html#method.set_z_index
+    pub fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
+
+    /// The z-index of the widget, as set by `set_z_index` or by the widget
+    /// itself.
+    pub fn z_index(&self) -> i32 {
+        self.state.z_index.unwrap_or(self.z_index)
+    }
+
+    /// Set whether the widget's [`update`] is called even when neither its
+    /// data nor the environment has changed.
+    ///
+    /// By default, `update` skips a widget and its descendants if the data
+    /// is [`same`] as last time, as is the environment. A widget that also
+    /// depends on something else can opt out of this. The default is
+    /// `false`.
+    ///
+    /// [`update`]: #method.update
+    /// [`same`]: trait.Data.html#tymethod.same
+    pub fn set_always_update(&mut self, always_update: bool) {
+        self.always_update = always_update;
+    }
+
     /// The layout rectangle.
     ///
     /// This will be same value as set by `set_layout_rect`.
@@ -226,6 +668,18 @@ impl<T, W: Widget<T>> WidgetPod<T, W> {
         self.state.paint_insets
     }
 
+    /// The distance from the bottom of the widget's layout rect to the
+    /// baseline of its text.
+    ///
+    /// A widget sets this by calling [`set_baseline_offset`] during its
+    /// [`layout`] method; it is zero for widgets that don't.
+    ///
+    /// [`set_baseline_offset`]: struct.LayoutCtx.html#method.set_baseline_offset
+    /// [`layout`]: widget/trait.Widget.html#tymethod.layout
+    pub fn baseline_offset(&self) -> f64 {
+        self.state.baseline_offset
+    }
+
     /// Given a parents layout size, determine the appropriate paint `Insets`
     /// for the parent.
     ///
@@ -256,6 +710,13 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
     /// [`paint`]: widget/trait.Widget.html#tymethod.paint
     /// [`paint_with_offset`]: #method.paint_with_offset
     pub fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if self.state.layout_deferred {
+            let rect = self.state.size().to_rect();
+            paint_ctx.fill(rect, &env.get(theme::BACKGROUND_LIGHT));
+            self.state.invalid.clear();
+            return;
+        }
+
         let mut ctx = PaintCtx {
             render_ctx: paint_ctx.render_ctx,
             window_id: paint_ctx.window_id,
@@ -263,9 +724,13 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             region: paint_ctx.region.clone(),
             base_state: &self.state,
             focus_widget: paint_ctx.focus_widget,
+            scale: paint_ctx.scale,
+            window: paint_ctx.window,
+            native_views: Vec::new(),
         };
         self.inner.paint(&mut ctx, data, &env);
         paint_ctx.z_ops.append(&mut ctx.z_ops);
+        paint_ctx.native_views.append(&mut ctx.native_views);
 
         if env.get(Env::DEBUG_PAINT) {
             const BORDER_WIDTH: f64 = 1.0;
@@ -275,7 +740,41 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             ctx.stroke(rect, &color, BORDER_WIDTH);
         }
 
-        self.state.needs_inval = false;
+        if env.get(Env::DEBUG_WIDGET_ID) {
+            self.paint_debug_widget_id(&mut ctx, env);
+        }
+
+        self.state.invalid.clear();
+    }
+
+    /// Paint the id of this widget in its top right corner.
+    fn paint_debug_widget_id(&self, ctx: &mut PaintCtx, env: &Env) {
+        const ID_FONT_SIZE: f64 = 10.0;
+        const ID_PADDING: f64 = 2.0;
+
+        let id = self.id().to_raw();
+        let font_name = env.get(theme::FONT_NAME);
+        let font = ctx.text().new_font_by_name(font_name, ID_FONT_SIZE).build();
+        let layout =
+            font.and_then(|font| ctx.text().new_text_layout(&font, &id.to_string()).build());
+        let layout = match layout {
+            Ok(layout) => layout,
+            Err(e) => {
+                log::error!("laying out widget id failed: {:?}", e);
+                return;
+            }
+        };
+
+        let width = ctx.size().width;
+        let rect = Rect::new(
+            width - layout.width() - 2.0 * ID_PADDING,
+            0.0,
+            width,
+            ID_FONT_SIZE + 2.0 * ID_PADDING,
+        );
+        ctx.fill(rect, &env.get_debug_color(id));
+        let origin = Point::new(rect.x0 + ID_PADDING, ID_FONT_SIZE + ID_PADDING / 2.0);
+        ctx.draw_text(&layout, origin, &Color::BLACK);
     }
 
     /// Paint the widget, translating it by the origin of its layout rectangle.
@@ -313,7 +812,7 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         let layout_origin = self.state.layout_rect.origin().to_vec2();
         paint_ctx.transform(Affine::translate(layout_origin));
 
-        let visible = paint_ctx.region().to_rect() - layout_origin;
+        let visible = paint_ctx.region().translated(-layout_origin);
 
         paint_ctx.with_child_ctx(visible, |ctx| self.paint(ctx, data, &env));
 
@@ -335,13 +834,93 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         data: &T,
         env: &Env,
     ) -> Size {
+        let origin = match layout_ctx.child_origin.take() {
+            Some(origin) => Some(origin),
+            None if self.state.last_layout.is_some() => Some(self.state.layout_rect.origin()),
+            None => None,
+        };
+
+        if layout_ctx.deadline.is_some() {
+            if let Some(size) = self.incremental_layout_size(layout_ctx, bc, origin) {
+                return size;
+            }
+        }
+
         layout_ctx.paint_insets = Insets::ZERO;
+        let parent_baseline = std::mem::replace(&mut layout_ctx.baseline_offset, 0.0);
+        let parent_id = std::mem::replace(&mut layout_ctx.widget_id, self.state.id);
+        let parent_deferred = std::mem::replace(&mut layout_ctx.deferred, false);
+        let parent_anim = std::mem::replace(&mut layout_ctx.request_anim, false);
+        let offset = origin.unwrap_or(Point::ORIGIN).to_vec2();
+        layout_ctx.visible = layout_ctx.visible - offset;
+        // the widget's content is where it will be shown, as far as we know
+        let content_offset = offset - self.state.viewport_offset;
+        let parent_origin = layout_ctx.window_origin;
+        let parent_mouse = layout_ctx.mouse_pos;
+        let parent_active = std::mem::replace(&mut layout_ctx.has_active, self.state.has_active);
+        layout_ctx.window_origin = parent_origin + content_offset;
+        // whether the widget is hot is only known once it has been placed,
+        // so its children are told where the mouse is either way
+        layout_ctx.mouse_pos = parent_mouse.map(|pos| pos - content_offset);
+
         let size = self.inner.layout(layout_ctx, bc, data, &env);
+
+        layout_ctx.visible = layout_ctx.visible + offset;
+        layout_ctx.window_origin = parent_origin;
+        layout_ctx.mouse_pos = parent_mouse;
+        layout_ctx.has_active = parent_active;
+        layout_ctx.widget_id = parent_id;
         self.state.paint_insets = layout_ctx.paint_insets;
-        self.state.needs_layout = false;
+        self.state.baseline_offset =
+            std::mem::replace(&mut layout_ctx.baseline_offset, parent_baseline);
+        // if a descendant was deferred, we still need layout
+        self.state.needs_layout = layout_ctx.deferred;
+        self.state.layout_deferred = false;
+        self.state.last_layout = Some((*bc, size));
+        layout_ctx.deferred |= parent_deferred;
+        // the frame goes to the parent too, so that it can route it here
+        self.state.request_anim |= layout_ctx.request_anim;
+        layout_ctx.request_anim |= parent_anim;
         size
     }
 
+    /// In incremental layout, the size to use instead of laying the widget
+    /// out, if it can skip layout.
+    ///
+    /// A widget can skip layout if it doesn't need it and its constraints
+    /// haven't changed, or if it is offscreen and the layout pass has run
+    /// out of time; in the latter case it is laid out in a later pass.
+    fn incremental_layout_size(
+        &mut self,
+        layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        origin: Option<Point>,
+    ) -> Option<Size> {
+        let last_size = match self.state.last_layout {
+            Some((last_bc, size)) if !self.state.needs_layout && last_bc == *bc => {
+                return Some(size)
+            }
+            Some((_, size)) => size,
+            None => Size::ZERO,
+        };
+        // a widget is visible unless we know where it is, and that is offscreen
+        let visible = match origin {
+            Some(origin) => overlaps(
+                layout_ctx.visible,
+                Rect::from_origin_size(origin, last_size),
+            ),
+            None => true,
+        };
+        let out_of_time = layout_ctx.deadline.map_or(false, |d| Instant::now() >= d);
+        if visible || !out_of_time {
+            return None;
+        }
+        self.state.needs_layout = true;
+        self.state.layout_deferred = true;
+        layout_ctx.deferred = true;
+        Some(bc.constrain(last_size))
+    }
+
     /// Propagate an event.
     ///
     /// Generally the [`event`] method of a container widget will call this
@@ -366,33 +945,67 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             // from other points in the library.
             return;
         }
+        // whether it takes a drop is only known for the current event
+        self.state.accepts_drop = false;
+        // a widget whose layout was deferred doesn't know where it is yet,
+        // and a disabled one doesn't take input
+        if self.state.layout_deferred || self.state.is_disabled() {
+            match event {
+                Event::MouseDown(_)
+                | Event::MouseUp(_)
+                | Event::MouseMoved(_)
+                | Event::Wheel(_)
+                | Event::Zoom(_)
+                | Event::DragEnter(_)
+                | Event::DragOver(_)
+                | Event::Drop(_)
+                | Event::KeyDown(_)
+                | Event::KeyUp(_)
+                | Event::Composition(_)
+                | Event::Paste(_) => return,
+                _ => (),
+            }
+        }
         let had_active = self.state.has_active;
+        let window_origin = ctx.window_origin + self.state.layout_rect.origin().to_vec2();
+        let mut notifications = VecDeque::new();
         let mut child_ctx = EventCtx {
-            cursor: ctx.cursor,
             command_queue: ctx.command_queue,
+            notifications: &mut notifications,
             window: &ctx.window,
             window_id: ctx.window_id,
+            clipboard: ctx.clipboard,
             base_state: &mut self.state,
             had_active,
             is_handled: false,
             is_root: false,
             focus_widget: ctx.focus_widget,
+            window_origin,
+            user_scale: ctx.user_scale,
         };
         let rect = child_ctx.base_state.layout_rect;
+        // While a widget is active it captures the mouse: widgets that aren't
+        // on its path don't become hot, and don't get mouse events.
+        let captured = ctx.had_active && !had_active;
         // Note: could also represent this as `Option<Event>`.
         let mut recurse = true;
         let mut hot_changed = None;
         let child_event = match event {
             Event::WindowConnected => Event::WindowConnected,
+            Event::WindowDisconnected => Event::WindowDisconnected,
             Event::Size(size) => {
                 child_ctx.request_layout();
                 recurse = ctx.is_root;
                 Event::Size(*size)
             }
+            Event::WindowScale(scale) => {
+                child_ctx.request_layout();
+                Event::WindowScale(*scale)
+            }
             Event::MouseDown(mouse_event) => {
                 let had_hot = child_ctx.base_state.is_hot;
                 let now_hot = rect.winding(mouse_event.pos) != 0;
-                if (!had_hot) && now_hot {
+                if !had_hot && now_hot && !captured {
                     child_ctx.base_state.is_hot = true;
                     hot_changed = Some(true);
                 }
@@ -407,24 +1020,34 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 mouse_event.pos -= rect.origin().to_vec2();
                 Event::MouseUp(mouse_event)
             }
+            Event::MouseCaptureLost => {
+                recurse = had_active;
+                Event::MouseCaptureLost
+            }
             Event::MouseMoved(mouse_event) => {
                 let had_hot = child_ctx.base_state.is_hot;
-                child_ctx.base_state.is_hot = rect.winding(mouse_event.pos) != 0;
+                if !captured {
+                    child_ctx.base_state.is_hot = rect.winding(mouse_event.pos) != 0;
+                }
                 if had_hot != child_ctx.base_state.is_hot {
                     hot_changed = Some(child_ctx.base_state.is_hot);
                 }
-                recurse = had_active || had_hot || child_ctx.base_state.is_hot;
+                recurse = had_active || !captured && (had_hot || child_ctx.base_state.is_hot);
                 let mut mouse_event = mouse_event.clone();
                 mouse_event.pos -= rect.origin().to_vec2();
                 Event::MouseMoved(mouse_event)
             }
             Event::KeyDown(e) => {
                 recurse = child_ctx.has_focus();
-                Event::KeyDown(*e)
+                Event::KeyDown(e.clone())
             }
             Event::KeyUp(e) => {
                 recurse = child_ctx.has_focus();
-                Event::KeyUp(*e)
+                Event::KeyUp(e.clone())
+            }
+            Event::Composition(e) => {
+                recurse = child_ctx.has_focus();
+                Event::Composition(e.clone())
             }
             Event::Paste(e) => {
                 recurse = child_ctx.has_focus();
@@ -432,25 +1055,88 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             }
             Event::Wheel(wheel_event) => {
                 recurse = had_active || child_ctx.base_state.is_hot;
-                Event::Wheel(wheel_event.clone())
+                let mut wheel_event = wheel_event.clone();
+                wheel_event.local_anchor -= rect.origin().to_vec2();
+                Event::Wheel(wheel_event)
             }
-            Event::Zoom(zoom) => {
+            Event::Zoom(zoom_event) => {
                 recurse = had_active || child_ctx.base_state.is_hot;
-                Event::Zoom(*zoom)
+                let mut zoom_event = zoom_event.clone();
+                zoom_event.local_anchor -= rect.origin().to_vec2();
+                Event::Zoom(zoom_event)
             }
-            Event::Timer(id) => {
-                recurse = child_ctx.base_state.request_timer;
-                Event::Timer(*id)
+            Event::DragEnter(drag) | Event::DragOver(drag) => {
+                let was_over = child_ctx.base_state.drag_over;
+                let is_over = rect.winding(drag.pos) != 0;
+                child_ctx.base_state.drag_over = is_over;
+                recurse = was_over || is_over;
+                if is_over {
+                    let mut drag = drag.clone();
+                    drag.pos -= rect.origin().to_vec2();
+                    if was_over {
+                        Event::DragOver(drag)
+                    } else {
+                        Event::DragEnter(drag)
+                    }
+                } else {
+                    Event::DragLeave
+                }
+            }
+            Event::DragLeave => {
+                recurse = child_ctx.base_state.drag_over;
+                child_ctx.base_state.drag_over = false;
+                Event::DragLeave
+            }
+            Event::Drop(drag) => {
+                let was_over = child_ctx.base_state.drag_over;
+                let is_over = rect.winding(drag.pos) != 0;
+                child_ctx.base_state.drag_over = false;
+                recurse = was_over || is_over;
+                if is_over {
+                    let mut drag = drag.clone();
+                    drag.pos -= rect.origin().to_vec2();
+                    Event::Drop(drag)
+                } else {
+                    Event::DragLeave
+                }
+            }
+            Event::Timer(token) => {
+                // each widget on the way to the one that requested the timer
+                // forgets it, as it only fires once
+                recurse = child_ctx.base_state.timers.remove(token).is_some();
+                Event::Timer(*token)
+            }
+            Event::Idle(token) => {
+                recurse = child_ctx.base_state.request_idle;
+                // requests made while handling the event are for the next one
+                child_ctx.base_state.request_idle = false;
+                match child_ctx.base_state.idle_token.take() {
+                    Some(own) => Event::Idle(own),
+                    None => Event::Idle(*token),
+                }
             }
             Event::Command(cmd) => Event::Command(cmd.clone()),
+            // notifications only go up, to the ancestors of their source
+            Event::Notification(notification) => {
+                recurse = false;
+                Event::Notification(notification.clone())
+            }
             Event::TargetedCommand(target, cmd) => match target {
                 Target::Window(_) => Event::Command(cmd.clone()),
-                Target::Widget(id) if *id == child_ctx.widget_id() => Event::Command(cmd.clone()),
+                Target::Widget(id) if *id == child_ctx.widget_id() => {
+                    if sync_from_sub_window(child_ctx.base_state, cmd, data) {
+                        recurse = false;
+                        child_ctx.is_handled = true;
+                    }
+                    Event::Command(cmd.clone())
+                }
                 Target::Widget(id) => {
                     recurse = child_ctx.base_state.children.contains(id);
                     Event::TargetedCommand(*target, cmd.clone())
                 }
-                Target::Global => panic!("Target::Global should be converted before WidgetPod"),
+                Target::Global | Target::Auto => {
+                    panic!("{:?} should be converted before WidgetPod", target)
+                }
             },
         };
         if let Some(is_hot) = hot_changed {
@@ -460,20 +1146,73 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 .lifecycle(&mut lc_ctx, &hot_changed_event, data, &env);
         }
         if recurse {
+            let focus_request = child_ctx.base_state.request_focus;
             child_ctx.base_state.has_active = false;
+            child_ctx.base_state.cursor = None;
             self.inner.event(&mut child_ctx, &child_event, data, &env);
+            // the press that made the widget active is over; it is repainted
+            // in case it shows that it is pressed
+            if let Event::MouseCaptureLost = event {
+                if child_ctx.base_state.is_active {
+                    child_ctx.base_state.is_active = false;
+                    child_ctx.request_paint();
+                }
+            }
+
+            // The widget's own notifications go to its parent; those from its
+            // descendants are delivered to it, and go on up unless it handles
+            // them.
+            let is_handled = child_ctx.is_handled;
+            while let Some(notification) = child_ctx.notifications.pop_front() {
+                if notification.source() == child_ctx.widget_id() {
+                    ctx.notifications.push_back(notification);
+                    continue;
+                }
+                child_ctx.is_handled = false;
+                let event = Event::Notification(notification);
+                self.inner.event(&mut child_ctx, &event, data, &env);
+                if !child_ctx.is_handled {
+                    if let Event::Notification(notification) = event {
+                        ctx.notifications.push_back(notification);
+                    }
+                }
+            }
+            child_ctx.is_handled = is_handled;
+
             child_ctx.base_state.has_active |= child_ctx.base_state.is_active;
+
+            let state = &mut child_ctx.base_state;
+            if !state.is_hot && !state.has_active {
+                state.cursor_change = CursorChange::Default;
+            }
+            state.resolve_cursor();
+
+            let own_request = Some(FocusChange::Focus(child_ctx.widget_id()));
+            if child_ctx.base_state.request_focus == own_request
+                && focus_request != own_request
+                && !child_ctx
+                    .base_state
+                    .focus_chain
+                    .contains(&child_ctx.widget_id())
+            {
+                warn_unregistered_focus(self.inner.type_name(), child_ctx.widget_id());
+            }
         };
 
         ctx.base_state.merge_up(&child_ctx.base_state);
+        ctx.base_state.merge_cursor(&child_ctx.base_state);
         ctx.is_handled |= child_ctx.is_handled;
     }
 
     pub fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
         let recurse = match event {
-            LifeCycle::AnimFrame(_) => {
+            LifeCycle::AnimFrame(interval) => {
                 let r = self.state.request_anim;
                 self.state.request_anim = false;
+                // the parent routes the next frame here
+                if self.advance_layout_transition(*interval) {
+                    ctx.base_state.request_anim = true;
+                }
                 r
             }
             LifeCycle::WidgetAdded => {
@@ -481,6 +1220,9 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
 
                 self.old_data = Some(data.clone());
                 self.env = Some(env.clone());
+                self.state.ancestor_disabled = ctx.base_state.is_disabled();
+                self.id_registration =
+                    IdRegistration::register(ctx.window_id, self.id(), self.inner.type_name(), env);
 
                 true
             }
@@ -496,12 +1238,51 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                     if self.state.children_changed {
                         self.state.children.clear();
                         self.state.focus_chain.clear();
+                        // the timers of the descendants are merged up again,
+                        // leaving out those of the ones that were removed
+                        let id = self.state.id;
+                        self.state.timers.retain(|_, widget| *widget == id);
                     }
 
                     self.state.children_changed
                 }
             }
+            // a widget that was never added has nothing to clean up
+            LifeCycle::WidgetRemoved => self.old_data.is_some(),
+            // this is for the widget whose size changed, and containers that
+            // pass it on to their children; the children are sent their own
+            // in `RouteSize`.
+            LifeCycle::Size(_) => false,
+            // every widget needs to check, since a parent doesn't know
+            // whether its children changed size
+            LifeCycle::RouteSize => true,
+            // like `Size`, this is for the widget whose state changed; the
+            // children are sent their own in `RouteDisabledChanged`.
+            LifeCycle::DisabledChanged(_) => false,
+            LifeCycle::RouteDisabledChanged => {
+                self.state.ancestor_disabled = ctx.base_state.is_disabled();
+                let changed = self.state.is_disabled() != self.state.was_disabled;
+                if changed {
+                    // the parent's focus chain has to be built again
+                    ctx.base_state.children_changed = true;
+                }
+                // the descendants inherit the change, if there is one
+                let recurse = changed || self.state.disabled_changed;
+                self.state.disabled_changed = false;
+                recurse
+            }
             LifeCycle::HotChanged(_) => false,
+            // like `Size`, this is for the widget whose origin changed; the
+            // children are sent their own in `RouteWindowOrigin`.
+            LifeCycle::WindowOriginChanged(_) => false,
+            LifeCycle::RouteWindowOrigin => {
+                // the parent knows where it is now that it has been placed,
+                // so its children do too, even those that weren't placed
+                // again
+                let offset = self.state.layout_rect.origin().to_vec2() - self.state.viewport_offset;
+                self.state.window_origin = ctx.base_state.window_origin + offset;
+                true
+            }
             LifeCycle::RouteFocusChanged { old, new } => {
                 self.state.request_focus = None;
 
@@ -518,11 +1299,20 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                     self.inner.lifecycle(ctx, &event, data, env);
                     false
                 } else {
-                    old.map(|id| self.state.children.contains(&id))
-                        .or_else(|| new.map(|id| self.state.children.contains(&id)))
-                        .unwrap_or(false)
+                    let contains = |id: &Option<WidgetId>| match id {
+                        Some(id) => self.state.children.contains(id),
+                        None => false,
+                    };
+                    contains(old) || contains(new)
                 }
             }
+            LifeCycle::RouteFocusAnchor(search) => search.visit(&self.state),
+            LifeCycle::RouteAccessTree(builder) => {
+                // the root's context has a state with the root's own id
+                let parent = Some(ctx.base_state.id).filter(|id| *id != self.id());
+                builder.visit(&self.state, parent);
+                true
+            }
             LifeCycle::FocusChanged(_) => {
                 self.state.request_focus = None;
                 true
@@ -541,6 +1331,17 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 f.call(&self.state);
                 true
             }
+            #[cfg(test)]
+            LifeCycle::DebugRecordTree(tree) => {
+                // the root's context has a state with the root's own id
+                let parent = Some(ctx.base_state.id).filter(|id| *id != self.id());
+                tree.push(crate::TreeEntry {
+                    id: self.id(),
+                    parent,
+                    layout_rect: self.state.layout_rect,
+                });
+                true
+            }
         };
 
         let mut child_ctx = LifeCycleCtx {
@@ -549,6 +1350,74 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             window_id: ctx.window_id,
         };
 
+        if let LifeCycle::RouteSize = event {
+            let size = child_ctx.base_state.size();
+            if size != child_ctx.base_state.notified_size {
+                child_ctx.base_state.notified_size = size;
+                self.inner
+                    .lifecycle(&mut child_ctx, &LifeCycle::Size(size), data, env);
+            }
+        }
+
+        // a widget that hasn't been added yet is told after the next layout
+        let is_added = self.old_data.is_some();
+        if let (LifeCycle::RouteWindowOrigin, true) = (event, is_added) {
+            // the parent has been made hot or not already, so a widget that
+            // was placed under the mouse is only hot if its parent is, as in
+            // events; the root's context has a state with the root's own id
+            let is_root = ctx.base_state.id == child_ctx.base_state.id;
+            let parent_hot = is_root || ctx.base_state.is_hot;
+            let state = &mut child_ctx.base_state;
+            if state.placed_without_ctx && !is_root {
+                let offset = state.layout_rect.origin().to_vec2() - state.viewport_offset;
+                state.window_origin = ctx.base_state.window_origin + offset;
+            }
+            let under_mouse = state.placed_hot.take().unwrap_or(state.is_hot);
+            let is_hot = under_mouse && (parent_hot || state.has_active);
+            if is_hot != state.is_hot {
+                state.is_hot = is_hot;
+                self.inner
+                    .lifecycle(&mut child_ctx, &LifeCycle::HotChanged(is_hot), data, env);
+            }
+            let origin = child_ctx.base_state.window_origin;
+            if origin != child_ctx.base_state.notified_window_origin {
+                child_ctx.base_state.notified_window_origin = origin;
+                self.inner.lifecycle(
+                    &mut child_ctx,
+                    &LifeCycle::WindowOriginChanged(origin),
+                    data,
+                    env,
+                );
+            }
+        }
+
+        if let LifeCycle::RouteDisabledChanged = event {
+            let disabled = child_ctx.base_state.is_disabled();
+            if disabled != child_ctx.base_state.was_disabled {
+                child_ctx.base_state.was_disabled = disabled;
+                if disabled {
+                    child_ctx.base_state.is_active = false;
+                    child_ctx.base_state.has_active = false;
+                    if child_ctx.base_state.is_hot {
+                        child_ctx.base_state.is_hot = false;
+                        self.inner.lifecycle(
+                            &mut child_ctx,
+                            &LifeCycle::HotChanged(false),
+                            data,
+                            env,
+                        );
+                    }
+                }
+                self.inner.lifecycle(
+                    &mut child_ctx,
+                    &LifeCycle::DisabledChanged(disabled),
+                    data,
+                    env,
+                );
+                child_ctx.request_paint();
+            }
+        }
+
         if recurse {
             self.inner.lifecycle(&mut child_ctx, event, data, env);
         }
@@ -558,15 +1427,38 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         // we need to (re)register children in case of one of the following events
         match event {
             LifeCycle::WidgetAdded | LifeCycle::RouteWidgetAdded => {
+                if let LifeCycle::WidgetAdded = event {
+                    self.state.was_disabled = self.state.is_disabled();
+                }
                 self.state.children_changed = false;
                 ctx.base_state.children = ctx.base_state.children.union(self.state.children);
-                ctx.base_state.focus_chain.extend(&self.state.focus_chain);
+                // a disabled subtree can't take focus
+                if !self.state.is_disabled() {
+                    ctx.base_state.focus_chain.extend(&self.state.focus_chain);
+                }
                 ctx.register_child(self.id());
             }
+            LifeCycle::WidgetRemoved => {
+                // the pod can be added again, as if it were new
+                self.old_data = None;
+                self.env = None;
+                self.id_registration = IdRegistration::default();
+                self.state.timers.clear();
+            }
             _ => (),
         }
     }
 
+    /// Send [`WidgetRemoved`] to this widget and its descendants, with the
+    /// data they last saw.
+    ///
+    /// [`WidgetRemoved`]: enum.LifeCycle.html#variant.WidgetRemoved
+    pub(crate) fn remove(&mut self, ctx: &mut LifeCycleCtx) {
+        if let (Some(data), Some(env)) = (self.old_data.clone(), self.env.clone()) {
+            self.lifecycle(ctx, &LifeCycle::WidgetRemoved, &data, &env);
+        }
+    }
+
     /// Propagate a data update.
     ///
     /// Generally called by container widgets as part of their [`update`]
@@ -574,8 +1466,9 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
     ///
     /// [`update`]: widget/trait.Widget.html#tymethod.update
     pub fn update(&mut self, ctx: &mut UpdateCtx, data: &T, env: &Env) {
+        let may_skip = !self.always_update && !ctx.force_update && !self.state.request_update;
         match (self.old_data.as_ref(), self.env.as_ref()) {
-            (Some(d), Some(e)) if d.same(data) && e.same(env) => return,
+            (Some(d), Some(e)) if may_skip && d.same(data) && e.same(env) => return,
             (None, _) => {
                 log::warn!("old_data missing in {:?}, skipping update", self.id());
                 self.old_data = Some(data.clone());
@@ -585,14 +1478,31 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             _ => (),
         }
 
+        // a requested update is only sent once
+        let force_update = ctx.force_update || self.state.update_subtree;
+        self.state.request_update = false;
+        self.state.update_subtree = false;
+
         let mut child_ctx = UpdateCtx {
+            command_queue: ctx.command_queue,
             window: ctx.window,
             base_state: &mut self.state,
             window_id: ctx.window_id,
+            prev_env: self.env.as_ref(),
+            env,
+            force_update,
         };
 
         self.inner
             .update(&mut child_ctx, self.old_data.as_ref().unwrap(), data, env);
+
+        let data_changed = !self.old_data.as_ref().unwrap().same(data);
+        if data_changed {
+            for host in &self.state.sub_window_hosts {
+                let cmd = Command::new(SUB_WINDOW_PARENT_TO_HOST, data.clone());
+                ctx.command_queue.push_back(((*host).into(), cmd));
+            }
+        }
         self.old_data = Some(data.clone());
         self.env = Some(env.clone());
 
@@ -615,37 +1525,118 @@ impl BaseState {
         BaseState {
             id,
             layout_rect: Rect::ZERO,
+            notified_size: Size::ZERO,
+            window_origin: Point::ORIGIN,
+            notified_window_origin: Point::ORIGIN,
+            placed_hot: None,
+            placed_without_ctx: false,
             paint_insets: Insets::ZERO,
-            needs_inval: false,
+            baseline_offset: 0.0,
+            invalid: Region::EMPTY,
+            viewport_offset: Vec2::ZERO,
             is_hot: false,
             needs_layout: false,
+            last_layout: None,
+            layout_deferred: false,
             is_active: false,
+            drag_over: false,
+            accepts_drop: false,
             has_active: false,
             request_anim: false,
-            request_timer: false,
+            timers: HashMap::new(),
+            request_idle: false,
+            request_update: false,
+            update_subtree: false,
+            idle_token: None,
             request_focus: None,
             focus_chain: Vec::new(),
             children: Bloom::new(),
             children_changed: false,
+            is_explicitly_disabled: false,
+            ancestor_disabled: false,
+            was_disabled: false,
+            disabled_changed: false,
+            label_for: None,
+            labeled_by: None,
+            access: AccessInfo::default(),
+            sub_window_hosts: Vec::new(),
+            cursor_change: CursorChange::Default,
+            cursor: None,
+            z_index: None,
         }
     }
 
     /// Update to incorporate state changes from a child.
     fn merge_up(&mut self, child_state: &BaseState) {
-        self.needs_inval |= child_state.needs_inval;
+        let clip = self.paint_rect() - self.layout_rect.origin().to_vec2();
+        let offset = child_state.layout_rect.origin().to_vec2() - child_state.viewport_offset;
+        for &rect in child_state.invalid.rects() {
+            self.invalid.add_rect((rect + offset).intersect(clip));
+        }
         self.needs_layout |= child_state.needs_layout;
         self.request_anim |= child_state.request_anim;
-        self.request_timer |= child_state.request_timer;
+        if !child_state.timers.is_empty() {
+            self.timers.extend(child_state.timers.iter());
+        }
+        self.request_idle |= child_state.request_idle;
+        self.request_update |= child_state.request_update;
         self.has_active |= child_state.has_active;
+        self.accepts_drop |= child_state.accepts_drop;
         self.children_changed |= child_state.children_changed;
+        self.disabled_changed |= child_state.disabled_changed;
         self.request_focus = self.request_focus.or(child_state.request_focus);
     }
 
+    /// Take the cursor of a child that an event was routed through.
+    ///
+    /// Only a child that is hot or active has a say, and an active one wins.
+    fn merge_cursor(&mut self, child_state: &BaseState) {
+        if child_state.has_active || (child_state.is_hot && self.cursor.is_none()) {
+            self.cursor = child_state.cursor.clone();
+        }
+    }
+
+    /// Combine the cursor this widget asked for with the one resolved for
+    /// its children.
+    fn resolve_cursor(&mut self) {
+        self.cursor = match &self.cursor_change {
+            CursorChange::Default => self.cursor.take(),
+            CursorChange::Set(cursor) => self.cursor.take().or_else(|| Some(cursor.clone())),
+            CursorChange::Override(cursor) => Some(cursor.clone()),
+        };
+    }
+
+    /// Request an idle event, returning the token it will be sent with.
+    ///
+    /// Until the event is sent, further requests return the same token.
+    pub(crate) fn request_idle(&mut self) -> IdleToken {
+        // the app's own idle tokens are small numbers, so widget tokens
+        // start well above them
+        const FIRST_WIDGET_TOKEN: usize = 1 << 16;
+        static IDLE_COUNTER: Counter = Counter::new();
+        self.request_idle = true;
+        *self.idle_token.get_or_insert_with(|| {
+            IdleToken::new(FIRST_WIDGET_TOKEN + IDLE_COUNTER.next() as usize)
+        })
+    }
+
     #[inline]
     pub(crate) fn size(&self) -> Size {
         self.layout_rect.size()
     }
 
+    pub(crate) fn set_disabled(&mut self, disabled: bool) {
+        if self.is_explicitly_disabled != disabled {
+            self.is_explicitly_disabled = disabled;
+            self.disabled_changed = true;
+        }
+    }
+
+    /// The widget, or one of its ancestors, is disabled.
+    pub(crate) fn is_disabled(&self) -> bool {
+        self.is_explicitly_disabled || self.ancestor_disabled
+    }
+
     /// The paint region for this widget.
     ///
     /// For more information, see [`WidgetPod::paint_rect`].
@@ -654,6 +1645,18 @@ impl BaseState {
     pub(crate) fn paint_rect(&self) -> Rect {
         self.layout_rect + self.paint_insets
     }
+
+    /// Mark the whole paint rect of the widget as needing to be painted.
+    pub(crate) fn request_paint(&mut self) {
+        let rect = self.paint_rect() - self.layout_rect.origin().to_vec2();
+        self.request_paint_rect(rect);
+    }
+
+    /// Mark a rect, in the widget's coordinate space, as needing to be
+    /// painted.
+    pub(crate) fn request_paint_rect(&mut self, rect: Rect) {
+        self.invalid.add_rect(rect);
+    }
 }
 
 #[cfg(test)]
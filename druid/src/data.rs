@@ -14,6 +14,7 @@
 
 //! Traits for handling value types.
 
+use std::borrow::Cow;
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -46,6 +47,8 @@ pub use druid_derive::Data;
 /// ### Derive macro attributes
 ///
 /// There are a number of field attributes available for use with `derive(Data)`.
+/// Each can also be written in a `#[data(...)]` attribute, such as
+/// `#[data(ignore)]`.
 ///
 /// - **`#[druid(ignore)]`**
 ///
@@ -131,6 +134,10 @@ impl_data_simple!(usize);
 impl_data_simple!(char);
 impl_data_simple!(bool);
 impl_data_simple!(String);
+impl_data_simple!(&'static str);
+impl_data_simple!(Cow<'static, str>);
+#[cfg(feature = "chrono")]
+impl_data_simple!(chrono::NaiveDate);
 
 impl Data for f32 {
     fn same(&self, other: &Self) -> bool {
@@ -144,12 +151,18 @@ impl Data for f64 {
     }
 }
 
+/// An `Arc` is only the same as another that points to the same value; the
+/// values are never compared. `T` need not be `Data`, and an `Arc<Vec<T>>` is
+/// cheap to compare. To change the value, make a new `Arc`, or use
+/// `Arc::make_mut`.
 impl<T: ?Sized + 'static> Data for Arc<T> {
     fn same(&self, other: &Self) -> bool {
         Arc::ptr_eq(self, other)
     }
 }
 
+/// As with `Arc`, an `Rc` is only the same as another that points to the
+/// same value.
 impl<T: ?Sized + 'static> Data for Rc<T> {
     fn same(&self, other: &Self) -> bool {
         Rc::ptr_eq(self, other)
@@ -176,6 +189,12 @@ impl<T: Data, U: Data> Data for Result<T, U> {
     }
 }
 
+impl<T: Data, const N: usize> Data for [T; N] {
+    fn same(&self, other: &Self) -> bool {
+        self.iter().zip(other.iter()).all(|(a, b)| a.same(b))
+    }
+}
+
 impl Data for () {
     fn same(&self, _other: &Self) -> bool {
         true
@@ -366,3 +385,182 @@ impl Data for piet::Color {
         self.as_rgba_u32().same(&other.as_rgba_u32())
     }
 }
+
+/// The `im` collections are the same as others that share their contents,
+/// without comparing the items; otherwise the items are compared, and the
+/// first difference is enough.
+#[cfg(feature = "im")]
+impl<T: Data> Data for im::Vector<T> {
+    fn same(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+            || (self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a.same(b)))
+    }
+}
+
+#[cfg(feature = "im")]
+impl<K, V, S> Data for im::HashMap<K, V, S>
+where
+    K: std::hash::Hash + Eq + Clone + 'static,
+    V: Data,
+    S: std::hash::BuildHasher + 'static,
+{
+    fn same(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+            || (self.len() == other.len()
+                && self
+                    .iter()
+                    .all(|(key, value)| other.get(key).map_or(false, |v| value.same(v))))
+    }
+}
+
+#[cfg(feature = "im")]
+impl<K: Ord + Clone + 'static, V: Data> Data for im::OrdMap<K, V> {
+    fn same(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+            || (self.len() == other.len()
+                && self
+                    .iter()
+                    .zip(other.iter())
+                    .all(|((k1, v1), (k2, v2))| k1 == k2 && v1.same(v2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::{Event, Selector, Target};
+
+    /// Counts the times it is compared.
+    #[derive(Clone)]
+    struct Counted(Rc<Cell<usize>>);
+
+    impl PartialEq for Counted {
+        fn eq(&self, _other: &Counted) -> bool {
+            self.0.set(self.0.get() + 1);
+            true
+        }
+    }
+
+    impl Data for Counted {
+        fn same(&self, other: &Self) -> bool {
+            self == other
+        }
+    }
+
+    #[test]
+    fn shared_values_compare_pointers() {
+        let count = Rc::new(Cell::new(0));
+        let arc = Arc::new(Counted(count.clone()));
+        assert!(arc.same(&arc.clone()));
+        assert!(!arc.same(&Arc::new(Counted(count.clone()))));
+        let rc = Rc::new(Counted(count.clone()));
+        assert!(rc.same(&rc.clone()));
+        assert!(!rc.same(&Rc::new(Counted(count.clone()))));
+        // a Vec isn't Data, but an Arc of one is
+        let items = Arc::new(vec![Counted(count.clone())]);
+        assert!(items.same(&items.clone()));
+        assert!(!items.same(&Arc::new(vec![Counted(count.clone())])));
+        assert_eq!(count.get(), 0);
+    }
+
+    #[cfg(feature = "im")]
+    #[test]
+    fn im_collections_compare_pointers_before_their_items() {
+        let count = Rc::new(Cell::new(0));
+        let vector: im::Vector<_> = (0..100).map(|_| Counted(count.clone())).collect();
+        assert!(vector.same(&vector.clone()));
+        let map: im::OrdMap<_, _> = (0..100).map(|i| (i, Counted(count.clone()))).collect();
+        assert!(map.same(&map.clone()));
+        let hash_map: im::HashMap<_, _> = map.clone().into_iter().collect();
+        assert!(hash_map.same(&hash_map.clone()));
+        assert_eq!(count.get(), 0);
+
+        assert!(vector.same(&vector.iter().cloned().collect()));
+        let copy = |(key, value): (&i32, &Counted)| (*key, value.clone());
+        assert!(map.same(&map.iter().map(copy).collect()));
+        assert!(hash_map.same(&hash_map.iter().map(copy).collect()));
+        assert_eq!(count.get(), 300);
+        let mut changed = map.clone();
+        changed.remove(&0);
+        assert!(!map.same(&changed));
+        assert_eq!(count.get(), 300);
+    }
+
+    #[test]
+    fn containers_compare_their_items() {
+        let count = Rc::new(Cell::new(0));
+        let item = Counted(count.clone());
+        assert!(Some(item.clone()).same(&Some(item.clone())));
+        assert!(!Some(item.clone()).same(&None));
+        assert!([item.clone(), item.clone()].same(&[item.clone(), item.clone()]));
+        let tuple = (1, item.clone(), 'a', 2.0, (), item.clone());
+        assert!(tuple.same(&tuple.clone()));
+        assert_eq!(count.get(), 5);
+
+        // the first difference is enough
+        assert!(!(0, item.clone()).same(&(1, item.clone())));
+        let ok: Result<Counted, u32> = Ok(item.clone());
+        assert!(!ok.same(&Err(0)));
+        assert_eq!(count.get(), 5);
+    }
+
+    #[test]
+    fn floats_are_compared_by_bits() {
+        assert!(std::f64::NAN.same(&std::f64::NAN));
+        assert!(!0.0f64.same(&-0.0));
+        let rect = kurbo::Rect::new(0., 0., std::f64::NAN, 10.);
+        assert!(rect.same(&rect));
+        let insets = kurbo::Insets::uniform(2.);
+        assert!(!insets.same(&kurbo::Insets::uniform(-2.)));
+    }
+
+    #[test]
+    fn strings_are_compared_by_value() {
+        let borrowed: Cow<'static, str> = Cow::Borrowed("druid");
+        assert!(borrowed.same(&Cow::Owned("druid".to_string())));
+        assert!(!borrowed.same(&Cow::Borrowed("xi")));
+        assert!("druid".same(&"druid"));
+    }
+
+    #[test]
+    fn ignored_fields_dont_update() {
+        const TOUCH_CACHE: Selector = Selector::new("druid-test.touch-cache");
+        const INCREMENT: Selector = Selector::new("druid-test.increment");
+
+        #[derive(Clone, Data)]
+        struct Model {
+            value: u32,
+            #[data(ignore)]
+            cache: u32,
+        }
+
+        let updates = Rc::new(Cell::new(0));
+        let widget = ModularWidget::<_, Model>::new(updates.clone())
+            .event_fn(|_, _ctx, event, data, _env| {
+                if let Event::Command(cmd) = event {
+                    if cmd.selector == TOUCH_CACHE {
+                        data.cache += 1;
+                    } else if cmd.selector == INCREMENT {
+                        data.value += 1;
+                    }
+                }
+            })
+            .update_fn(|updates, _ctx, _old_data, _data, _env| updates.set(updates.get() + 1));
+
+        Harness::create(Model { value: 0, cache: 0 }, widget, |harness| {
+            harness.send_initial_events();
+            let initial = updates.get();
+
+            harness.submit_command(TOUCH_CACHE, Target::Auto);
+            assert_eq!(harness.data().cache, 1);
+            assert_eq!(updates.get(), initial);
+
+            harness.submit_command(INCREMENT, Target::Auto);
+            assert_eq!(updates.get(), initial + 1);
+        });
+    }
+}
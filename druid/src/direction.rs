@@ -0,0 +1,312 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The direction of layout, which is mirrored for right-to-left languages.
+
+use crate::kurbo::{Insets, Rect};
+use crate::{theme, Env};
+
+/// The direction that text runs in, which layout follows.
+///
+/// This is [`theme::LAYOUT_DIRECTION`]; it can be set for the whole app with
+/// [`AppLauncher::configure_env`], or for part of it with [`env_scope`].
+///
+/// When it is `Rtl`, the leading edge is the right one: the children of a
+/// [`Flex`] row run from right to left, the first child of a vertical
+/// [`Split`] is on the right, a [`Label`] starts its lines on the right,
+/// and a [`Scroll`] starts scrolled to the right. What is given in terms
+/// of leading and trailing, such as [`Align::leading`] or
+/// [`DirectionalInsets`], is mirrored; what is given in terms of left and
+/// right is not.
+///
+/// [`theme::LAYOUT_DIRECTION`]: theme/constant.LAYOUT_DIRECTION.html
+/// [`AppLauncher::configure_env`]: struct.AppLauncher.html#method.configure_env
+/// [`env_scope`]: widget/trait.WidgetExt.html#method.env_scope
+/// [`Flex`]: widget/struct.Flex.html
+/// [`Split`]: widget/struct.Split.html
+/// [`Label`]: widget/struct.Label.html
+/// [`Scroll`]: widget/struct.Scroll.html
+/// [`Align::leading`]: widget/struct.Align.html#method.leading
+/// [`DirectionalInsets`]: struct.DirectionalInsets.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum LayoutDirection {
+    /// Left to right, as in English.
+    Ltr,
+    /// Right to left, as in Arabic or Hebrew.
+    Rtl,
+}
+
+/// Insets whose horizontal sides are the leading and trailing edges,
+/// rather than the left and right ones.
+///
+/// [`Padding::directional`] takes these.
+///
+/// [`Padding::directional`]: widget/struct.Padding.html#method.directional
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DirectionalInsets {
+    /// The inset at the leading edge.
+    pub leading: f64,
+    /// The inset at the top.
+    pub top: f64,
+    /// The inset at the trailing edge.
+    pub trailing: f64,
+    /// The inset at the bottom.
+    pub bottom: f64,
+}
+
+impl LayoutDirection {
+    /// The direction in the environment.
+    pub fn from_env(env: &Env) -> LayoutDirection {
+        env.get(theme::LAYOUT_DIRECTION)
+    }
+
+    /// Whether this is right to left.
+    pub fn is_rtl(self) -> bool {
+        self == LayoutDirection::Rtl
+    }
+
+    /// Mirror `rect` horizontally within a container of `width`, if this is
+    /// right to left; a rect laid out from the left comes out laid out from
+    /// the right.
+    pub fn mirror(self, rect: Rect, width: f64) -> Rect {
+        match self {
+            LayoutDirection::Ltr => rect,
+            LayoutDirection::Rtl => Rect::new(width - rect.x1, rect.y0, width - rect.x0, rect.y1),
+        }
+    }
+}
+
+impl Default for LayoutDirection {
+    fn default() -> LayoutDirection {
+        LayoutDirection::Ltr
+    }
+}
+
+impl DirectionalInsets {
+    /// Zero insets.
+    pub const ZERO: DirectionalInsets = DirectionalInsets::uniform(0.);
+
+    /// Insets from the leading, top, trailing and bottom edges.
+    pub const fn new(leading: f64, top: f64, trailing: f64, bottom: f64) -> DirectionalInsets {
+        DirectionalInsets {
+            leading,
+            top,
+            trailing,
+            bottom,
+        }
+    }
+
+    /// The same insets on every side.
+    pub const fn uniform(d: f64) -> DirectionalInsets {
+        DirectionalInsets::new(d, d, d, d)
+    }
+
+    /// The insets in terms of left and right, in `direction`.
+    pub fn resolve(self, direction: LayoutDirection) -> Insets {
+        match direction {
+            LayoutDirection::Ltr => Insets::new(self.leading, self.top, self.trailing, self.bottom),
+            LayoutDirection::Rtl => Insets::new(self.trailing, self.top, self.leading, self.bottom),
+        }
+    }
+}
+
+impl From<f64> for DirectionalInsets {
+    fn from(d: f64) -> DirectionalInsets {
+        DirectionalInsets::uniform(d)
+    }
+}
+
+impl From<(f64, f64, f64, f64)> for DirectionalInsets {
+    fn from((leading, top, trailing, bottom): (f64, f64, f64, f64)) -> DirectionalInsets {
+        DirectionalInsets::new(leading, top, trailing, bottom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{
+        CrossAxisAlignment, Flex, MainAxisAlignment, Padding, Scroll, SizedBox, Split, WidgetExt,
+    };
+    use crate::LayoutDirection::{Ltr, Rtl};
+    use crate::{Vec2, Widget, WidgetId};
+
+    #[test]
+    fn mirroring() {
+        let rect = Rect::new(10., 5., 30., 15.);
+        let ltr = LayoutDirection::Ltr.mirror(rect, 100.);
+        assert_eq!((ltr.x0, ltr.x1), (10., 30.));
+        let rtl = LayoutDirection::Rtl.mirror(rect, 100.);
+        assert_eq!((rtl.x0, rtl.y0, rtl.x1, rtl.y1), (70., 5., 90., 15.));
+
+        let insets = DirectionalInsets::new(1., 2., 3., 4.);
+        let rtl = insets.resolve(LayoutDirection::Rtl);
+        assert_eq!((rtl.x0, rtl.y0, rtl.x1, rtl.y1), (3., 2., 1., 4.));
+    }
+
+    /// Lay out `widget` in the window, with `direction`.
+    fn lay_out_in<W: Widget<()> + 'static>(
+        direction: LayoutDirection,
+        widget: W,
+        f: impl FnOnce(&mut Harness<()>),
+    ) {
+        let mut f = Some(f);
+        Harness::create((), widget, |harness| {
+            harness.configure_env(|env, _| env.set(theme::LAYOUT_DIRECTION, direction));
+            harness.send_initial_events();
+            harness.just_layout();
+            if let Some(f) = f.take() {
+                f(harness);
+            }
+        });
+    }
+
+    fn x0(harness: &mut Harness<()>, id: WidgetId) -> f64 {
+        harness.get_state(id).layout_rect.x0
+    }
+
+    fn boxes(ids: (WidgetId, WidgetId)) -> Flex<()> {
+        Flex::row()
+            .with_child(SizedBox::empty().width(30.).height(10.).with_id(ids.0), 0.0)
+            .with_child(SizedBox::empty().width(50.).height(10.).with_id(ids.1), 0.0)
+            .must_fill_main_axis(true)
+    }
+
+    #[test]
+    fn flex_row_runs_from_the_leading_edge() {
+        let cases = [
+            (Ltr, (0., 30.), (320., 350.)),
+            (Rtl, (370., 320.), (50., 0.)),
+        ];
+        for &(direction, start, end) in &cases {
+            let ids = widget_id2();
+            lay_out_in(direction, boxes(ids), |harness| {
+                assert_eq!((x0(harness, ids.0), x0(harness, ids.1)), start);
+            });
+
+            let ids = widget_id2();
+            let row = boxes(ids).main_axis_alignment(MainAxisAlignment::End);
+            lay_out_in(direction, row, |harness| {
+                assert_eq!((x0(harness, ids.0), x0(harness, ids.1)), end);
+            });
+        }
+    }
+
+    #[test]
+    fn flex_column_aligns_to_the_leading_edge() {
+        for &(direction, leading) in &[(Ltr, 0.), (Rtl, 370.)] {
+            let (id_1, id_2) = widget_id2();
+            let column = Flex::column()
+                .with_child(SizedBox::empty().width(30.).height(10.).with_id(id_1), 0.0)
+                .with_child(SizedBox::empty().width(30.).height(10.).with_id(id_2), 0.0)
+                .cross_axis_alignment(CrossAxisAlignment::Start);
+            lay_out_in(direction, column, |harness| {
+                assert_eq!(x0(harness, id_1), leading);
+                assert_eq!(x0(harness, id_2), leading);
+                // it still runs from the top
+                assert_eq!(harness.get_state(id_2).layout_rect.y0, 10.);
+            });
+        }
+    }
+
+    #[test]
+    fn align_leading_and_trailing_follow_the_direction() {
+        for &(direction, leading, trailing) in &[(Ltr, 0., 370.), (Rtl, 370., 0.)] {
+            let (id_1, id_2, id_3) = widget_id3();
+            let square = |id| SizedBox::empty().width(30.).height(30.).with_id(id);
+            let column = Flex::column()
+                .with_child(square(id_1).align_leading(), 0.0)
+                .with_child(square(id_2).align_trailing(), 0.0)
+                .with_child(square(id_3).align_left(), 0.0);
+            lay_out_in(direction, column, |harness| {
+                assert_eq!(x0(harness, id_1), leading);
+                assert_eq!(x0(harness, id_2), trailing);
+                // left is left, whichever the direction
+                assert_eq!(x0(harness, id_3), 0.);
+            });
+        }
+    }
+
+    #[test]
+    fn directional_padding_swaps_its_sides() {
+        for &(direction, left) in &[(Ltr, 20.), (Rtl, 5.)] {
+            let (padding, child) = widget_id2();
+            let widget = Padding::directional(
+                (20., 0., 5., 0.),
+                SizedBox::empty().width(30.).height(30.).with_id(child),
+            )
+            .with_id(padding)
+            .align_left();
+            lay_out_in(direction, widget, |harness| {
+                assert_eq!(x0(harness, child), left);
+                assert_eq!(harness.get_state(padding).size().width, 55.);
+            });
+        }
+    }
+
+    #[test]
+    fn vertical_split_puts_its_first_child_at_the_leading_edge() {
+        // 390 pixels to split with the splitter, a quarter for the first child
+        for &(direction, first, second) in &[(Ltr, 0., 107.5), (Rtl, 302.5, 0.)] {
+            let (id_1, id_2) = widget_id2();
+            let split = Split::vertical(
+                SizedBox::empty().with_id(id_1),
+                SizedBox::empty().with_id(id_2),
+            )
+            .split_point(0.25);
+            lay_out_in(direction, split, |harness| {
+                assert_eq!(x0(harness, id_1), first);
+                assert_eq!(x0(harness, id_2), second);
+                assert_eq!(harness.get_state(id_1).size().width, 97.5);
+            });
+        }
+    }
+
+    #[test]
+    fn scroll_starts_at_the_leading_edge() {
+        for &(direction, offset) in &[(Ltr, 0.), (Rtl, 600.)] {
+            let content = WidgetId::next();
+            let scroll = Scroll::new(SizedBox::empty().width(1000.).height(10.).with_id(content))
+                .horizontal();
+            lay_out_in(direction, scroll, |harness| {
+                assert_eq!(
+                    harness.get_state(content).viewport_offset,
+                    Vec2::new(offset, 0.)
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn env_scope_sets_the_direction_of_a_subtree() {
+        let (id_1, id_2, id_3) = widget_id3();
+        let rtl_row = boxes((id_1, id_2)).env_scope(|env, _| env.set(theme::LAYOUT_DIRECTION, Rtl));
+        let widget = Flex::column().with_child(rtl_row, 0.0).with_child(
+            SizedBox::empty()
+                .width(30.)
+                .height(10.)
+                .with_id(id_3)
+                .align_leading(),
+            0.0,
+        );
+        lay_out_in(Ltr, widget, |harness| {
+            assert_eq!((x0(harness, id_1), x0(harness, id_2)), (370., 320.));
+            assert_eq!(x0(harness, id_3), 0.);
+        });
+    }
+}
@@ -21,9 +21,17 @@ use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::localization::L10nManager;
-use crate::{Color, Data, Point, Rect, Size};
+use crate::theme;
+use crate::{Color, Data, Insets, LayoutDirection, Point, Rect, Size};
+
+#[cfg(feature = "serde")]
+mod overrides;
+
+#[cfg(feature = "serde")]
+pub use overrides::{EnvOverrides, OverrideError};
 
 /// An environment passed down through all widget traversals.
 ///
@@ -67,11 +75,13 @@ pub enum Value {
     Point(Point),
     Size(Size),
     Rect(Rect),
+    Insets(Insets),
     Color(Color),
     Float(f64),
     Bool(bool),
     UnsignedInt(u64),
     String(String),
+    LayoutDirection(LayoutDirection),
 }
 
 /// Either a concrete `T` or a [`Key<T>`] that can be resolved in the [`Env`].
@@ -120,6 +130,13 @@ impl Env {
     /// [`WidgetExt`]: widget/trait.WidgetExt.html
     pub(crate) const DEBUG_PAINT: Key<bool> = Key::new("druid.built-in.debug-paint");
 
+    /// State for whether or not to paint the ids of widgets, for debugging.
+    ///
+    /// Set by the `debug_widget_id()` method on [`WidgetExt`]'.
+    ///
+    /// [`WidgetExt`]: widget/trait.WidgetExt.html
+    pub(crate) const DEBUG_WIDGET_ID: Key<bool> = Key::new("druid.built-in.debug-widget-id");
+
     /// A key used to tell widgets to print additional debug information.
     ///
     /// This does nothing by default; however you can check this key while
@@ -143,6 +160,16 @@ impl Env {
     /// [`WidgetExt::debug_widget`]: widget/trait.WidgetExt.html#method.debug_widget
     pub const DEBUG_WIDGET: Key<bool> = Key::new("druid.built-in.debug-widget");
 
+    /// Whether to panic when a widget is added to a window that already has
+    /// a widget with the same [`WidgetId`].
+    ///
+    /// Duplicate ids are only detected in debug builds. They are always
+    /// logged as errors; set this to `true` to find where they come from.
+    /// The default is `false`.
+    ///
+    /// [`WidgetId`]: struct.WidgetId.html
+    pub const PANIC_ON_DUPLICATE_ID: Key<bool> = Key::new("druid.built-in.panic-on-duplicate-id");
+
     /// Gets a value from the environment, expecting it to be present.
     ///
     /// Note that the return value is a reference for "expensive" types such
@@ -152,12 +179,22 @@ impl Env {
     /// # Panics
     ///
     /// Panics if the key is not found, or if it is present with the wrong type.
+    /// A key that is not found has usually not been added to the theme; all
+    /// of the keys in [`theme`] are set by [`theme::init`].
+    ///
+    /// [`theme`]: theme/index.html
+    /// [`theme::init`]: theme/fn.init.html
     pub fn get<'a, V: ValueType<'a>>(&'a self, key: impl Borrow<Key<V>>) -> V {
         let key = key.borrow();
         if let Some(value) = self.0.map.get(key.key) {
             value.to_inner_unchecked()
         } else {
-            panic!("key for {} not found", key.key)
+            panic!(
+                "Env has no value for the key '{}' (of type {}); \
+                 it should be set with Env::set or AppLauncher::configure_env",
+                key.key,
+                std::any::type_name::<V>()
+            )
         }
     }
 
@@ -173,6 +210,32 @@ impl Env {
             .map(|value| value.to_inner_unchecked())
     }
 
+    /// Whether `key` has the same value in both environments, or is missing
+    /// from both.
+    pub(crate) fn same_value<V>(&self, other: &Env, key: &Key<V>) -> bool {
+        match (self.0.map.get(key.key), other.0.map.get(key.key)) {
+            (Some(a), Some(b)) => a.same(b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Gets the duration of an animated transition, in milliseconds.
+    ///
+    /// This is the value for `key` if present, or `default` otherwise;
+    /// if [`theme::ANIMATIONS_ENABLED`] is `false` it is always zero, and
+    /// widgets should go straight to the end of the transition.
+    ///
+    /// [`theme::ANIMATIONS_ENABLED`]: theme/constant.ANIMATIONS_ENABLED.html
+    pub fn transition_duration(&self, key: impl Borrow<Key<u64>>, default: Duration) -> Duration {
+        if self.try_get(theme::ANIMATIONS_ENABLED) == Some(false) {
+            return Duration::from_millis(0);
+        }
+        self.try_get(key)
+            .map(Duration::from_millis)
+            .unwrap_or(default)
+    }
+
     /// Adds a key/value, acting like a builder.
     pub fn adding<'a, V: ValueType<'a>>(mut self, key: Key<V>, value: impl Into<V::Owned>) -> Env {
         let env = Arc::make_mut(&mut self.0);
@@ -187,9 +250,42 @@ impl Env {
     /// Panics if the environment already has a value for the key, but it is
     /// of a different type.
     pub fn set<'a, V: ValueType<'a>>(&'a mut self, key: Key<V>, value: impl Into<V::Owned>) {
+        self.insert(key.into(), value.into().into());
+    }
+
+    /// Adds a key with a value that is either concrete or the value of another
+    /// key, acting like a builder.
+    ///
+    /// A key is resolved when this is called, so `key` does not follow later
+    /// changes to the other key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is a key that has no value in this environment.
+    pub fn adding_from<V>(mut self, key: Key<V>, value: impl Into<KeyOrValue<V>>) -> Env {
+        let value = self.resolve_raw(&value.into());
+        let env = Arc::make_mut(&mut self.0);
+        env.map.insert(key.into(), value);
+        self
+    }
+
+    /// Sets a key to a value that is either concrete or the value of another
+    /// key, such as `env.set_from(theme::BORDER_DARK, theme::PRIMARY_DARK)`.
+    ///
+    /// A key is resolved when this is called, so `key` does not follow later
+    /// changes to the other key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is a key that has no value in this environment, or if
+    /// the environment already has a value for `key` of a different type.
+    pub fn set_from<V>(&mut self, key: Key<V>, value: impl Into<KeyOrValue<V>>) {
+        let value = self.resolve_raw(&value.into());
+        self.insert(key.into(), value);
+    }
+
+    fn insert(&mut self, key: String, value: Value) {
         let env = Arc::make_mut(&mut self.0);
-        let value = value.into().into();
-        let key = key.into();
         // TODO: use of Entry might be more efficient
         if let Some(existing) = env.map.get(&key) {
             if !existing.is_same_type(&value) {
@@ -202,6 +298,17 @@ impl Env {
         env.map.insert(key, value);
     }
 
+    /// The `Value` for a `KeyOrValue`, without converting it.
+    fn resolve_raw<V>(&self, value: &KeyOrValue<V>) -> Value {
+        match value {
+            KeyOrValue::Concrete(value) => value.clone(),
+            KeyOrValue::Key(key) => match self.0.map.get(key.key) {
+                Some(value) => value.clone(),
+                None => panic!("Env has no value for the key '{}'", key.key),
+            },
+        }
+    }
+
     /// Returns a reference to the [`L10nManager`], which handles localization
     /// resources.
     ///
@@ -210,6 +317,15 @@ impl Env {
         &self.0.l10n
     }
 
+    /// Replace the [`L10nManager`], so tests don't depend on the resources
+    /// on disk or the system locale.
+    ///
+    /// [`L10nManager`]: struct.L10nManager.html
+    #[cfg(test)]
+    pub(crate) fn set_localization_manager(&mut self, l10n: L10nManager) {
+        Arc::make_mut(&mut self.0).l10n = Arc::new(l10n);
+    }
+
     /// Given an id, returns one of 18 distinct colors
     #[doc(hidden)]
     pub fn get_debug_color(&self, id: u64) -> Color {
@@ -258,11 +374,13 @@ impl Value {
             (Point(_), Point(_)) => true,
             (Size(_), Size(_)) => true,
             (Rect(_), Rect(_)) => true,
+            (Insets(_), Insets(_)) => true,
             (Color(_), Color(_)) => true,
             (Float(_), Float(_)) => true,
             (Bool(_), Bool(_)) => true,
             (UnsignedInt(_), UnsignedInt(_)) => true,
             (String(_), String(_)) => true,
+            (LayoutDirection(_), LayoutDirection(_)) => true,
             _ => false,
         }
     }
@@ -274,11 +392,13 @@ impl Debug for Value {
             Value::Point(p) => write!(f, "Point {:?}", p),
             Value::Size(s) => write!(f, "Size {:?}", s),
             Value::Rect(r) => write!(f, "Rect {:?}", r),
+            Value::Insets(i) => write!(f, "Insets {:?}", i),
             Value::Color(c) => write!(f, "Color {:?}", c),
             Value::Float(x) => write!(f, "Float {}", x),
             Value::Bool(b) => write!(f, "Bool {}", b),
             Value::UnsignedInt(x) => write!(f, "UnsignedInt {}", x),
             Value::String(s) => write!(f, "String {:?}", s),
+            Value::LayoutDirection(d) => write!(f, "LayoutDirection {:?}", d),
         }
     }
 }
@@ -291,12 +411,16 @@ impl Data for Value {
             (Rect(r1), Rect(r2)) => {
                 r1.x0.same(&r2.x0) && r1.y0.same(&r2.y0) && r1.x1.same(&r2.x1) && r1.y1.same(&r2.y1)
             }
+            (Insets(i1), Insets(i2)) => {
+                i1.x0.same(&i2.x0) && i1.y0.same(&i2.y0) && i1.x1.same(&i2.x1) && i1.y1.same(&i2.y1)
+            }
             (Size(s1), Size(s2)) => s1.width.same(&s2.width) && s1.height.same(&s2.height),
             (Color(c1), Color(c2)) => c1.as_rgba_u32() == c2.as_rgba_u32(),
             (Float(f1), Float(f2)) => f1.same(&f2),
             (Bool(b1), Bool(b2)) => b1 == b2,
             (UnsignedInt(f1), UnsignedInt(f2)) => f1.same(&f2),
             (String(s1), String(s2)) => s1 == s2,
+            (LayoutDirection(d1), LayoutDirection(d2)) => d1 == d2,
             _ => false,
         }
     }
@@ -353,7 +477,9 @@ impl Default for Env {
 
         Env(Arc::new(inner))
             .adding(Env::DEBUG_PAINT, false)
+            .adding(Env::DEBUG_WIDGET_ID, false)
             .adding(Env::DEBUG_WIDGET, false)
+            .adding(Env::PANIC_ON_DUPLICATE_ID, false)
     }
 }
 
@@ -430,9 +556,18 @@ impl_value_type_owned!(Color, Color);
 impl_value_type_owned!(Rect, Rect);
 impl_value_type_owned!(Point, Point);
 impl_value_type_owned!(Size, Size);
+impl_value_type_owned!(Insets, Insets);
+impl_value_type_owned!(LayoutDirection, LayoutDirection);
 impl_value_type_borrowed!(str, String, String);
 
+impl Into<Value> for &str {
+    fn into(self) -> Value {
+        Value::String(self.to_owned())
+    }
+}
+
 impl<'a, T: ValueType<'a>> KeyOrValue<T> {
+    /// Resolve to a concrete value, looking it up in the `Env` if this is a key.
     pub fn resolve(&'a self, env: &'a Env) -> T {
         match self {
             KeyOrValue::Concrete(value) => value.to_inner_unchecked(),
@@ -441,6 +576,16 @@ impl<'a, T: ValueType<'a>> KeyOrValue<T> {
     }
 }
 
+impl KeyOrValue<&'static str> {
+    /// Resolve a string, which may borrow from the `Env`.
+    pub fn resolve_str<'a>(&'a self, env: &'a Env) -> &'a str {
+        match self {
+            KeyOrValue::Concrete(value) => value.to_inner_unchecked(),
+            KeyOrValue::Key(key) => env.get(key),
+        }
+    }
+}
+
 impl<T: Into<Value>> From<T> for KeyOrValue<T> {
     fn from(value: T) -> KeyOrValue<T> {
         KeyOrValue::Concrete(value.into())
@@ -452,3 +597,18 @@ impl<T: Into<Value>> From<Key<T>> for KeyOrValue<T> {
         KeyOrValue::Key(key)
     }
 }
+
+/// Use this macro for the forms that insets can be given in.
+macro_rules! impl_insets_from {
+    ($ty:ty) => {
+        impl From<$ty> for KeyOrValue<Insets> {
+            fn from(insets: $ty) -> KeyOrValue<Insets> {
+                KeyOrValue::Concrete(Insets::from(insets).into())
+            }
+        }
+    };
+}
+
+impl_insets_from!(f64);
+impl_insets_from!((f64, f64));
+impl_insets_from!((f64, f64, f64, f64));
@@ -0,0 +1,360 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Saving the values of an [`Env`] that differ from the default theme, and
+//! applying them again, with serde.
+//!
+//! [`Env`]: ../struct.Env.html
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Env, Value};
+use crate::{theme, Color, ColorExt, Data, Insets, LayoutDirection, Point, Rect, Size};
+
+/// The values of an [`Env`] that differ from the default theme, by the
+/// names of their keys.
+///
+/// These are taken from an environment with [`Env::overrides`], and put
+/// back with [`Env::apply_overrides`]. They serialize as a map from the
+/// names to the values, each of which is tagged with its type; colors are
+/// hex strings, like `{"label_color": {"color": "#f0f0eaff"}}`.
+///
+/// [`Env`]: struct.Env.html
+/// [`Env::overrides`]: struct.Env.html#method.overrides
+/// [`Env::apply_overrides`]: struct.Env.html#method.apply_overrides
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EnvOverrides(BTreeMap<String, Value>);
+
+/// Why an override couldn't be applied to an [`Env`].
+///
+/// [`Env`]: struct.Env.html
+#[derive(Debug, Clone, PartialEq)]
+pub enum OverrideError {
+    /// The environment has no value for the key, which may have been
+    /// removed since the overrides were saved.
+    UnknownKey(String),
+    /// The override has a different type from the value in the environment.
+    WrongType {
+        /// The name of the key.
+        key: String,
+        /// The type of the value in the environment.
+        expected: &'static str,
+        /// The type of the override.
+        found: &'static str,
+    },
+}
+
+/// How a `Value` is serialized; the variant is the tag.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Repr {
+    Point { x: f64, y: f64 },
+    Size { width: f64, height: f64 },
+    Rect { x0: f64, y0: f64, x1: f64, y1: f64 },
+    Insets { x0: f64, y0: f64, x1: f64, y1: f64 },
+    Color(String),
+    Float(f64),
+    Bool(bool),
+    UnsignedInt(u64),
+    String(String),
+    LayoutDirection(LayoutDirection),
+}
+
+impl Env {
+    /// The values that differ from those of [`theme::init`], including those
+    /// of keys that it doesn't have, such as an app's own.
+    ///
+    /// [`theme::init`]: theme/fn.init.html
+    pub fn overrides(&self) -> EnvOverrides {
+        let default = theme::init();
+        let overrides = self
+            .0
+            .map
+            .iter()
+            .filter(|(key, value)| !default.0.map.get(*key).map_or(false, |d| d.same(value)))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        EnvOverrides(overrides)
+    }
+
+    /// Serialize the values that differ from the default theme; see
+    /// [`overrides`].
+    ///
+    /// [`overrides`]: #method.overrides
+    pub fn try_serialize_overrides<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.overrides().serialize(serializer)
+    }
+
+    /// Set the values of `overrides`.
+    ///
+    /// Each key must already have a value of the same type, so this should
+    /// be called once an app has added its own keys, such as in
+    /// [`AppLauncher::configure_env`]. The overrides that can't be applied
+    /// are skipped, and returned as errors; the others are still applied.
+    ///
+    /// [`AppLauncher::configure_env`]: struct.AppLauncher.html#method.configure_env
+    pub fn apply_overrides(&mut self, overrides: &EnvOverrides) -> Result<(), Vec<OverrideError>> {
+        let mut errors = Vec::new();
+        for (key, value) in &overrides.0 {
+            match self.0.map.get(key) {
+                None => errors.push(OverrideError::UnknownKey(key.clone())),
+                Some(existing) if !existing.is_same_type(value) => {
+                    errors.push(OverrideError::WrongType {
+                        key: key.clone(),
+                        expected: existing.type_name(),
+                        found: value.type_name(),
+                    })
+                }
+                Some(_) => self.insert(key.clone(), value.clone()),
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl EnvOverrides {
+    /// No overrides.
+    pub fn new() -> EnvOverrides {
+        EnvOverrides::default()
+    }
+
+    /// The override for the key named `key`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    /// Add an override, replacing any for the same key.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.0.insert(key.into(), value.into());
+    }
+
+    /// Remove the override for `key`, returning it.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.0.remove(key)
+    }
+
+    /// The overrides, in the order of their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// The number of overrides.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no overrides.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Value {
+    /// The name of the type of the value, for errors.
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Point(_) => "Point",
+            Value::Size(_) => "Size",
+            Value::Rect(_) => "Rect",
+            Value::Insets(_) => "Insets",
+            Value::Color(_) => "Color",
+            Value::Float(_) => "Float",
+            Value::Bool(_) => "Bool",
+            Value::UnsignedInt(_) => "UnsignedInt",
+            Value::String(_) => "String",
+            Value::LayoutDirection(_) => "LayoutDirection",
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            Value::Point(p) => Repr::Point { x: p.x, y: p.y },
+            Value::Size(s) => Repr::Size {
+                width: s.width,
+                height: s.height,
+            },
+            Value::Rect(r) => Repr::Rect {
+                x0: r.x0,
+                y0: r.y0,
+                x1: r.x1,
+                y1: r.y1,
+            },
+            Value::Insets(i) => Repr::Insets {
+                x0: i.x0,
+                y0: i.y0,
+                x1: i.x1,
+                y1: i.y1,
+            },
+            Value::Color(c) => Repr::Color(format!("#{:08x}", c.as_rgba_u32())),
+            Value::Float(x) => Repr::Float(*x),
+            Value::Bool(b) => Repr::Bool(*b),
+            Value::UnsignedInt(x) => Repr::UnsignedInt(*x),
+            Value::String(s) => Repr::String(s.clone()),
+            Value::LayoutDirection(d) => Repr::LayoutDirection(*d),
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        let value = match Repr::deserialize(deserializer)? {
+            Repr::Point { x, y } => Value::Point(Point::new(x, y)),
+            Repr::Size { width, height } => Value::Size(Size::new(width, height)),
+            Repr::Rect { x0, y0, x1, y1 } => Value::Rect(Rect::new(x0, y0, x1, y1)),
+            Repr::Insets { x0, y0, x1, y1 } => Value::Insets(Insets::new(x0, y0, x1, y1)),
+            Repr::Color(hex) => Value::Color(Color::from_hex_str(&hex).map_err(de::Error::custom)?),
+            Repr::Float(x) => Value::Float(x),
+            Repr::Bool(b) => Value::Bool(b),
+            Repr::UnsignedInt(x) => Value::UnsignedInt(x),
+            Repr::String(s) => Value::String(s),
+            Repr::LayoutDirection(d) => Value::LayoutDirection(d),
+        };
+        Ok(value)
+    }
+}
+
+impl fmt::Display for OverrideError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverrideError::UnknownKey(key) => write!(f, "the environment has no key '{}'", key),
+            OverrideError::WrongType {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the value for the key '{}' is a {}, not a {}",
+                key, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OverrideError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Key;
+
+    const POINT: Key<Point> = Key::new("test.point");
+    const SIZE: Key<Size> = Key::new("test.size");
+    const RECT: Key<Rect> = Key::new("test.rect");
+    const INSETS: Key<Insets> = Key::new("test.insets");
+    const COLOR: Key<Color> = Key::new("test.color");
+    const FLOAT: Key<f64> = Key::new("test.float");
+    const BOOL: Key<bool> = Key::new("test.bool");
+    const UNSIGNED: Key<u64> = Key::new("test.unsigned");
+    const STRING: Key<&str> = Key::new("test.string");
+
+    /// The default theme, with a key of each type.
+    fn app_env() -> Env {
+        theme::init()
+            .adding(POINT, Point::ZERO)
+            .adding(SIZE, Size::ZERO)
+            .adding(RECT, Rect::ZERO)
+            .adding(INSETS, Insets::ZERO)
+            .adding(COLOR, Color::BLACK)
+            .adding(FLOAT, 0.0)
+            .adding(BOOL, false)
+            .adding(UNSIGNED, 0u64)
+            .adding(STRING, "")
+    }
+
+    #[test]
+    fn every_type_round_trips() {
+        let mut env = app_env();
+        env.set(POINT, Point::new(1., 2.));
+        env.set(SIZE, Size::new(3., 4.));
+        env.set(RECT, Rect::new(5., 6., 7., 8.));
+        env.set(INSETS, Insets::new(9., 10., 11., 12.));
+        env.set(COLOR, Color::rgba8(0x12, 0x34, 0x56, 0x78));
+        env.set(FLOAT, 0.25);
+        env.set(BOOL, true);
+        env.set(UNSIGNED, 42u64);
+        env.set(STRING, "forty-two");
+        env.set(theme::LAYOUT_DIRECTION, LayoutDirection::Rtl);
+        env.set(theme::TEXT_SIZE_NORMAL, 17.0);
+
+        let json = serde_json::to_string(&env.overrides()).unwrap();
+        assert!(json.contains(r##""test.color":{"color":"#12345678"}"##));
+        let overrides: EnvOverrides = serde_json::from_str(&json).unwrap();
+        // only what was changed from the theme, or isn't in it
+        assert_eq!(overrides.len(), 11);
+
+        let mut loaded = app_env();
+        assert!(!loaded.same(&env));
+        loaded.apply_overrides(&overrides).unwrap();
+        assert!(loaded.same(&env));
+    }
+
+    #[test]
+    fn nothing_changed_is_nothing_saved() {
+        assert!(theme::init().overrides().is_empty());
+        let mut json = Vec::new();
+        let mut serializer = serde_json::Serializer::new(&mut json);
+        theme::init()
+            .try_serialize_overrides(&mut serializer)
+            .unwrap();
+        assert_eq!(json, b"{}");
+    }
+
+    #[test]
+    fn bad_overrides_are_reported_and_skipped() {
+        let json = r##"{
+            "test.removed": {"float": 1.0},
+            "test.float": {"color": "#ffffff"},
+            "test.bool": {"bool": true}
+        }"##;
+        let overrides: EnvOverrides = serde_json::from_str(json).unwrap();
+        let mut env = app_env();
+        let errors = env.apply_overrides(&overrides).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                OverrideError::WrongType {
+                    key: "test.float".into(),
+                    expected: "Float",
+                    found: "Color",
+                },
+                OverrideError::UnknownKey("test.removed".into()),
+            ]
+        );
+        // the good one is still applied, and the others left alone
+        assert!(env.get(BOOL));
+        assert_eq!(env.get(FLOAT), 0.0);
+    }
+
+    #[test]
+    fn colors_are_hex_strings() {
+        let parse = |json| serde_json::from_str::<EnvOverrides>(json);
+        let short = parse(r##"{"c": {"color": "#f00"}}"##).unwrap();
+        assert!(short
+            .get("c")
+            .unwrap()
+            .same(&Color::rgb8(0xff, 0, 0).into()));
+        assert!(parse(r##"{"c": {"color": "red"}}"##).is_err());
+    }
+}
@@ -14,12 +14,21 @@
 
 //! Events.
 
-use crate::kurbo::{Rect, Shape, Size, Vec2};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
 
-use druid_shell::{Clipboard, KeyEvent, KeyModifiers, TimerToken};
+use crate::kurbo::{Point, Rect, Shape, Size, Vec2};
 
+use druid_shell::{
+    Clipboard, CompositionEvent, DragPayload, IdleToken, KbKey, KeyEvent, KeyModifiers, Scale,
+    ScrollPhase, TimerToken,
+};
+
+use crate::access::AccessTreeBuilder;
+use crate::core::BaseState;
 use crate::mouse::MouseEvent;
-use crate::{Command, Target, WidgetId};
+use crate::{Command, Notification, Target, WidgetId};
 
 /// An event, propagated downwards during event flow.
 ///
@@ -42,9 +51,14 @@ use crate::{Command, Target, WidgetId};
 /// This enum is expected to grow considerably, as there are many, many
 /// different kinds of events that are relevant in a GUI.
 ///
+/// Events compare equal if they are of the same kind and carry the same
+/// details; commands are compared as described on [`Command`], and
+/// `Paste` events are always equal, as there is only one clipboard.
+///
 /// [`event`]: widget/trait.Widget.html#tymethod.event
 /// [`WidgetPod`]: struct.WidgetPod.html
-#[derive(Debug, Clone)]
+/// [`Command`]: struct.Command.html
+#[derive(Clone)]
 pub enum Event {
     /// Sent to all widgets in a given window when that window is first instantiated.
     ///
@@ -56,6 +70,16 @@ pub enum Event {
     ///
     /// [`LifeCycle::WidgetAdded`]: enum.LifeCycle.html#variant.WidgetAdded
     WindowConnected,
+    /// Sent to all widgets when the window is closing, before its widgets
+    /// are dropped.
+    ///
+    /// This is the last `Event` a widget receives, and is the place for
+    /// teardown such as saving state. It is also sent when the application
+    /// quits with windows still open.
+    ///
+    /// Commands submitted while handling this event are still delivered,
+    /// unless the application is quitting, in which case they are dropped.
+    WindowDisconnected,
     /// Called on the root widget when the window size changes.
     ///
     /// Discussion: it's not obvious this should be propagated to user
@@ -67,10 +91,27 @@ pub enum Event {
     /// of complexity and state in EventCtx, so if it's not useful it
     /// should be removed.
     Size(Size),
+    /// Sent to all widgets in a window when the number of physical pixels
+    /// in a display point changes, such as when the window moves to a
+    /// display with a different resolution.
+    ///
+    /// The widgets are laid out again afterwards. The new scale is also
+    /// available from [`PaintCtx::scale`] and [`LayoutCtx::scale`].
+    ///
+    /// [`PaintCtx::scale`]: struct.PaintCtx.html#method.scale
+    /// [`LayoutCtx::scale`]: struct.LayoutCtx.html#method.scale
+    WindowScale(Scale),
     /// Called when a mouse button is pressed.
     MouseDown(MouseEvent),
     /// Called when a mouse button is released.
     MouseUp(MouseEvent),
+    /// Called when the window loses the mouse while a widget is active, as
+    /// when another window or the system takes it during a drag.
+    ///
+    /// This is sent to the active widget and its ancestors, which stop being
+    /// active afterwards. The button may never be released in the window, so
+    /// whatever the press started should be cancelled.
+    MouseCaptureLost,
     /// Called when the mouse is moved.
     ///
     /// The `MouseMoved` event is propagated to the active widget, if
@@ -80,7 +121,8 @@ pub enum Event {
     /// to set a cursor, for example to an I-bar inside a text widget. A
     /// simple tactic is for the widget to unconditionally call
     /// [`set_cursor`] in the MouseMoved handler, as `MouseMove` is only
-    /// propagated to active or hot widgets.
+    /// propagated to active or hot widgets; the cursor is reset once the
+    /// widget is neither.
     ///
     /// [`set_cursor`]: struct.EventCtx.html#method.set_cursor
     MouseMoved(MouseEvent),
@@ -91,31 +133,100 @@ pub enum Event {
     /// raw events provided by the platform. In particular, Windows sends
     /// one or both of WM_KEYDOWN (a raw key code) and WM_CHAR (the
     /// Unicode value), depending on the actual key.
+    ///
+    /// While a [`Composition`] is in progress, the keys that are part of it
+    /// have [`is_composing`] set; their text is typed by the composition,
+    /// rather than by the key.
+    ///
+    /// [`Composition`]: #variant.Composition
+    /// [`is_composing`]: struct.KeyEvent.html#structfield.is_composing
     KeyDown(KeyEvent),
     /// Called when a key is released.
     ///
     /// Because of repeat, there may be a number `KeyDown` events before
     /// a corresponding `KeyUp` is sent.
     KeyUp(KeyEvent),
+    /// Called when text is composed from several key presses, such as an
+    /// accented letter typed with a dead key, or text typed with an input
+    /// method, such as Japanese.
+    ///
+    /// The text of the composition is shown while it is in progress, and
+    /// is typed when it ends. Like key events, this goes to the widget
+    /// that has focus, which should tell the input method where its cursor
+    /// is with [`EventCtx::set_ime_cursor_area`].
+    ///
+    /// [`EventCtx::set_ime_cursor_area`]: struct.EventCtx.html#method.set_ime_cursor_area
+    Composition(CompositionEvent),
     /// Called when a paste command is received.
     Paste(Clipboard),
     /// Called when the mouse wheel or trackpad is scrolled.
     Wheel(WheelEvent),
-    /// Called when the trackpad is pinched.
+    /// Called when the trackpad is pinched, or on a zoom gesture on a
+    /// touch screen.
+    ///
+    /// Like [`Wheel`], this is delivered to the hot widgets.
+    ///
+    /// [`Wheel`]: #variant.Wheel
+    Zoom(ZoomEvent),
+    /// Called when something that is dragged comes over the widget.
     ///
-    /// The value is a delta.
-    Zoom(f64),
+    /// What is dragged is either from another application, such as files
+    /// from the file manager, or from a widget in this window that called
+    /// [`EventCtx::start_drag`]. Like mouse events, drag events go to the
+    /// widgets under the pointer; [`WidgetPod`] sends `DragEnter` to a child
+    /// the pointer comes over, `DragOver` while it moves over it, and
+    /// [`DragLeave`] once it leaves.
+    ///
+    /// A widget that would take the drop where the pointer is calls
+    /// [`EventCtx::accept_drop`] when it handles `DragEnter` and `DragOver`,
+    /// which the platform shows with the drag cursor.
+    ///
+    /// [`EventCtx::start_drag`]: struct.EventCtx.html#method.start_drag
+    /// [`EventCtx::accept_drop`]: struct.EventCtx.html#method.accept_drop
+    /// [`WidgetPod`]: struct.WidgetPod.html
+    /// [`DragLeave`]: #variant.DragLeave
+    DragEnter(DragEvent),
+    /// Called when something that is dragged moves over the widget.
+    ///
+    /// See [`DragEnter`](#variant.DragEnter).
+    DragOver(DragEvent),
+    /// Called when something that was dragged over the widget leaves it,
+    /// or the drag is cancelled.
+    DragLeave,
+    /// Called when something that is dragged over the widget is dropped.
+    ///
+    /// The widget calls [`EventCtx::accept_drop`] if it takes it, which
+    /// tells where it came from that it was taken. Widgets that the drag
+    /// was over, and that it isn't dropped on, get [`DragLeave`] instead.
+    ///
+    /// [`EventCtx::accept_drop`]: struct.EventCtx.html#method.accept_drop
+    /// [`DragLeave`]: #variant.DragLeave
+    Drop(DragEvent),
     /// Called on a timer event.
     ///
     /// Request a timer event through [`EventCtx::request_timer()`]. That will
-    /// cause a timer event later.
+    /// cause a timer event later, which is only routed to the widget that
+    /// requested it. Containers must pass it on to their children.
     ///
-    /// Note that timer events from other widgets may be delivered as well. Use
-    /// the token returned from the `request_timer()` call to filter events more
-    /// precisely.
+    /// A widget with more than one timer can use the token returned from the
+    /// `request_timer()` call to tell them apart.
     ///
     /// [`EventCtx::request_timer()`]: struct.EventCtx.html#method.request_timer
     Timer(TimerToken),
+    /// Called once the application is idle, after a widget has requested it.
+    ///
+    /// Request an idle event through [`EventCtx::request_idle()`]. The event
+    /// is sent after the current event cycle, once the platform's event loop
+    /// has nothing else to do. A widget that requests several idle events
+    /// before then gets the same token each time, and a single event.
+    ///
+    /// Containers must pass this event on to their children. Each requesting
+    /// widget receives its own token, but the widgets on its way may see a
+    /// different one, so a widget should check that the token is the one it
+    /// was given.
+    ///
+    /// [`EventCtx::request_idle()`]: struct.EventCtx.html#method.request_idle
+    Idle(IdleToken),
     /// Called with an arbitrary [`Command`], submitted from elsewhere in
     /// the application.
     ///
@@ -127,6 +238,19 @@ pub enum Event {
     /// [`Widget`]: widget/trait.Widget.html
     /// [`EventCtx::submit_command`]: struct.EventCtx.html#method.submit_command
     Command(Command),
+    /// Called with a [`Notification`] from a descendant of this widget.
+    ///
+    /// A notification is sent with [`EventCtx::submit_notification`], and
+    /// is delivered once the widget that sent it has handled the current
+    /// event: first to that widget's parent, then to each ancestor in turn,
+    /// until one of them calls [`set_handled`]. It is only delivered to the
+    /// ancestors; containers don't need to pass it on to their children, and
+    /// children that are passed it ignore it.
+    ///
+    /// [`Notification`]: struct.Notification.html
+    /// [`EventCtx::submit_notification`]: struct.EventCtx.html#method.submit_notification
+    /// [`set_handled`]: struct.EventCtx.html#method.set_handled
+    Notification(Notification),
     /// A command still in the process of being dispatched. This is an internal
     /// event and should generally not be handled directly by widgets, but is
     /// important for containers to dispatch to their children.
@@ -134,7 +258,7 @@ pub enum Event {
 }
 
 /// Application life cycle events.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LifeCycle {
     /// Sent to a `Widget` when it is added to the widget tree. This should be
     /// the first message that each widget receives.
@@ -162,12 +286,71 @@ pub enum LifeCycle {
     WidgetAdded,
     /// Used internally by the framework to route WidgetAdded to the required widgets.
     RouteWidgetAdded,
+    /// Sent to a `Widget` and all of its descendants when it is about to be
+    /// removed from the widget tree. This is the last message that each
+    /// widget receives.
+    ///
+    /// Widgets that hold on to things outside of the tree, such as
+    /// subscriptions or native handles, should let go of them here. Timers
+    /// that the widgets requested are cancelled by the framework.
+    ///
+    /// Container widgets send this with [`EventCtx::remove_child`] or
+    /// [`UpdateCtx::remove_child`] before they drop or replace a child, and
+    /// must forward it to their children like any other event.
+    ///
+    /// [`EventCtx::remove_child`]: struct.EventCtx.html#method.remove_child
+    /// [`UpdateCtx::remove_child`]: struct.UpdateCtx.html#method.remove_child
+    WidgetRemoved,
     /// Called at the beginning of a new animation frame.
     ///
     /// On the first frame when transitioning from idle to animating, `interval`
     /// will be 0. (This logic is presently per-window but might change to
     /// per-widget to make it more consistent). Otherwise it is in nanoseconds.
     AnimFrame(u64),
+    /// Called when the size of the widget changes, with its new size.
+    ///
+    /// This is sent after a layout in which the widget's parent gave it a
+    /// different size with [`WidgetPod::set_layout_rect`], and before the
+    /// widget is painted. The first one comes after the first layout that
+    /// gives the widget a size other than zero.
+    ///
+    /// The size can also be read at any time with the `size` method on
+    /// the contexts.
+    ///
+    /// [`WidgetPod::set_layout_rect`]: struct.WidgetPod.html#method.set_layout_rect
+    Size(Size),
+    /// Internal: used by the framework to send `Size` to the widgets whose
+    /// size changed in the last layout.
+    RouteSize,
+    /// Called when the origin of the widget in the window changes, with its
+    /// new origin.
+    ///
+    /// This is sent after a layout in which the widget, or one of its
+    /// ancestors, was moved; a widget in a [`Scroll`] is also moved when it
+    /// scrolls. The origin is in the coordinate space of the window, as with
+    /// [`EventCtx::to_window`]. The first one comes after the first layout
+    /// that puts the widget somewhere other than the window's origin.
+    ///
+    /// [`Scroll`]: widget/struct.Scroll.html
+    /// [`EventCtx::to_window`]: struct.EventCtx.html#method.to_window
+    WindowOriginChanged(Point),
+    /// Internal: used by the framework to bring the window origins up to
+    /// date after a layout, and to send `WindowOriginChanged` to the widgets
+    /// whose origin changed.
+    RouteWindowOrigin,
+    /// Called when the widget becomes disabled or enabled.
+    ///
+    /// A widget is disabled if it, or any of its ancestors, called
+    /// [`set_disabled(true)`]. Disabled widgets are left out of the focus
+    /// chain and don't receive mouse or keyboard events, and should paint
+    /// themselves dimmed; see [`is_disabled`].
+    ///
+    /// [`set_disabled(true)`]: struct.EventCtx.html#method.set_disabled
+    /// [`is_disabled`]: struct.EventCtx.html#method.is_disabled
+    DisabledChanged(bool),
+    /// Internal: used by the framework to send `DisabledChanged` to the
+    /// widgets whose disabled state changed.
+    RouteDisabledChanged,
     /// Called when the "hot" status changes.
     ///
     /// This will always be called _before_ the event that triggered it; that is,
@@ -184,6 +367,12 @@ pub enum LifeCycle {
         /// the widget that is gaining focus, if any
         new: Option<WidgetId>,
     },
+    /// Internal: used by the framework to find where a focused widget that is
+    /// not in the focus chain is, relative to the widgets in the chain.
+    RouteFocusAnchor(FocusAnchor),
+    /// Internal: used by the framework to collect the widgets for the
+    /// accessibility tree.
+    RouteAccessTree(AccessTreeBuilder),
     /// Called when the focus status changes.
     ///
     /// This will always be called immediately after an event where a widget
@@ -205,6 +394,10 @@ pub enum LifeCycle {
     },
     #[cfg(test)]
     DebugInspectState(StateCheckFn),
+    /// Testing only: record each widget in the tree, with its parent, in the
+    /// order that lifecycle events reach them.
+    #[cfg(test)]
+    DebugRecordTree(TreeCell),
 }
 
 /// A mouse wheel event.
@@ -218,7 +411,7 @@ pub enum LifeCycle {
 /// See the
 /// [wiki](https://linebender.gitbook.io/linebender-graphics-wiki/mouse-wheel)
 /// for more discussion, including testing on various platforms.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WheelEvent {
     /// The wheel movement.
     ///
@@ -231,9 +424,107 @@ pub struct WheelEvent {
     pub delta: Vec2,
     /// The keyboard modifiers at the time of the event.
     pub mods: KeyModifiers,
+    /// The position of the mouse in the coordinate space of the window.
+    ///
+    /// The platform does not report a position with wheel events, so this
+    /// is the last known position of the mouse.
+    pub window_anchor: Point,
+    /// The position of the mouse in the coordinate space of the receiver.
+    ///
+    /// This is translated along with the event by [`WidgetPod`] and by
+    /// scrolling containers, exactly as the `pos` of a [`MouseEvent`].
+    ///
+    /// [`WidgetPod`]: struct.WidgetPod.html
+    /// [`MouseEvent`]: struct.MouseEvent.html
+    pub local_anchor: Point,
+    /// Where the event is in the user's scrolling gesture; for a momentum
+    /// event, which comes after it, this is `Ended`.
+    ///
+    /// A mouse wheel, and platforms that don't report phases, give `Changed`.
+    pub phase: ScrollPhase,
+    /// Where the event is in the momentum that a trackpad scrolls with after
+    /// the fingers are lifted, if it is a momentum event.
+    ///
+    /// [`Scroll`] stops applying the momentum once the user clicks, or the
+    /// content reaches its edge.
+    ///
+    /// [`Scroll`]: widget/struct.Scroll.html
+    pub momentum_phase: Option<ScrollPhase>,
+}
+
+/// Something that is dragged over the window, and where the pointer is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DragEvent {
+    /// The position of the pointer in the coordinate space of the receiver.
+    pub pos: Point,
+    /// The position of the pointer in the coordinate space of the window.
+    pub window_pos: Point,
+    /// The keyboard modifiers at the time of the event.
+    pub mods: KeyModifiers,
+    /// What is dragged.
+    pub payload: DragPayload,
+}
+
+impl DragEvent {
+    /// The event, with `pos` moved by `offset`.
+    fn offset(&self, offset: Vec2) -> DragEvent {
+        let mut drag = self.clone();
+        drag.pos += offset;
+        drag
+    }
+}
+
+impl From<druid_shell::DragEvent> for DragEvent {
+    fn from(src: druid_shell::DragEvent) -> DragEvent {
+        let druid_shell::DragEvent { pos, mods, payload } = src;
+        DragEvent {
+            pos,
+            window_pos: pos,
+            mods,
+            payload,
+        }
+    }
+}
+
+/// A trackpad pinch, with the point it should be anchored on.
+///
+/// A widget that zooms its content should keep the content point under
+/// `local_anchor` in place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoomEvent {
+    /// The change in scale, as an increment to a multiplicative factor; a
+    /// zoom from `scale` goes to `scale * (1.0 + delta)`.
+    pub delta: f64,
+    /// The position of the mouse in the coordinate space of the window.
+    ///
+    /// As with [`WheelEvent`], this is the last known position of the mouse.
+    ///
+    /// [`WheelEvent`]: struct.WheelEvent.html
+    pub window_anchor: Point,
+    /// The position of the mouse in the coordinate space of the receiver.
+    pub local_anchor: Point,
 }
 
 impl Event {
+    /// Whether the event is dispatched by position, to the widgets under
+    /// the pointer.
+    ///
+    /// Containers send these events to overlapping children topmost first.
+    pub(crate) fn is_pointer_event(&self) -> bool {
+        matches!(
+            self,
+            Event::MouseDown(_)
+                | Event::MouseUp(_)
+                | Event::MouseMoved(_)
+                | Event::Wheel(_)
+                | Event::Zoom(_)
+                | Event::DragEnter(_)
+                | Event::DragOver(_)
+                | Event::DragLeave
+                | Event::Drop(_)
+        )
+    }
+
     /// Transform the event for the contents of a scrolling container.
     ///
     /// the `force` flag is used to ensure an event is delivered even
@@ -268,17 +559,232 @@ impl Event {
                     None
                 }
             }
+            Event::Wheel(wheel_event) => {
+                let mut wheel_event = wheel_event.clone();
+                wheel_event.local_anchor += offset;
+                Some(Event::Wheel(wheel_event))
+            }
+            Event::Zoom(zoom_event) => {
+                let mut zoom_event = zoom_event.clone();
+                zoom_event.local_anchor += offset;
+                Some(Event::Zoom(zoom_event))
+            }
+            // a drag outside of the viewport has left the contents
+            Event::DragEnter(drag) | Event::DragOver(drag) | Event::Drop(drag)
+                if viewport.winding(drag.pos) == 0 =>
+            {
+                Some(Event::DragLeave)
+            }
+            Event::DragEnter(drag) => Some(Event::DragEnter(drag.offset(offset))),
+            Event::DragOver(drag) => Some(Event::DragOver(drag.offset(offset))),
+            Event::Drop(drag) => Some(Event::Drop(drag.offset(offset))),
             _ => Some(self.clone()),
         }
     }
 }
 
+impl PartialEq for Event {
+    fn eq(&self, other: &Event) -> bool {
+        match (self, other) {
+            (Event::WindowConnected, Event::WindowConnected) => true,
+            (Event::WindowDisconnected, Event::WindowDisconnected) => true,
+            (Event::Size(a), Event::Size(b)) => a == b,
+            (Event::WindowScale(a), Event::WindowScale(b)) => a == b,
+            (Event::MouseDown(a), Event::MouseDown(b)) => a == b,
+            (Event::MouseUp(a), Event::MouseUp(b)) => a == b,
+            (Event::MouseCaptureLost, Event::MouseCaptureLost) => true,
+            (Event::MouseMoved(a), Event::MouseMoved(b)) => a == b,
+            (Event::KeyDown(a), Event::KeyDown(b)) => a == b,
+            (Event::KeyUp(a), Event::KeyUp(b)) => a == b,
+            (Event::Composition(a), Event::Composition(b)) => a == b,
+            (Event::Paste(_), Event::Paste(_)) => true,
+            (Event::Wheel(a), Event::Wheel(b)) => a == b,
+            (Event::Zoom(a), Event::Zoom(b)) => a == b,
+            (Event::DragEnter(a), Event::DragEnter(b)) => a == b,
+            (Event::DragOver(a), Event::DragOver(b)) => a == b,
+            (Event::DragLeave, Event::DragLeave) => true,
+            (Event::Drop(a), Event::Drop(b)) => a == b,
+            (Event::Timer(a), Event::Timer(b)) => a == b,
+            (Event::Idle(a), Event::Idle(b)) => a == b,
+            (Event::Command(a), Event::Command(b)) => a == b,
+            (Event::Notification(a), Event::Notification(b)) => a == b,
+            (Event::TargetedCommand(t1, a), Event::TargetedCommand(t2, b)) => t1 == t2 && a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Events are printed with the details that matter for debugging: positions
+/// and buttons for the mouse, key codes and text for the keyboard, and
+/// selectors for commands.
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::WindowConnected => write!(f, "WindowConnected"),
+            Event::WindowDisconnected => write!(f, "WindowDisconnected"),
+            Event::Size(size) => write!(f, "Size({:?})", size),
+            Event::WindowScale(scale) => write!(f, "WindowScale({}, {})", scale.x(), scale.y()),
+            Event::MouseDown(mouse) => fmt_mouse(f, "MouseDown", mouse),
+            Event::MouseUp(mouse) => fmt_mouse(f, "MouseUp", mouse),
+            Event::MouseCaptureLost => write!(f, "MouseCaptureLost"),
+            Event::MouseMoved(mouse) => fmt_mouse(f, "MouseMoved", mouse),
+            Event::KeyDown(key) => fmt_key(f, "KeyDown", key),
+            Event::KeyUp(key) => fmt_key(f, "KeyUp", key),
+            Event::Composition(composition) => write!(f, "Composition({:?})", composition),
+            Event::Paste(_) => write!(f, "Paste"),
+            Event::Wheel(wheel) => {
+                write!(f, "Wheel({:?} at {:?}", wheel.delta, wheel.local_anchor)?;
+                fmt_mods(f, wheel.mods)?;
+                write!(f, ")")
+            }
+            Event::Zoom(zoom) => write!(f, "Zoom({} at {:?})", zoom.delta, zoom.local_anchor),
+            Event::DragEnter(drag) => fmt_drag(f, "DragEnter", drag),
+            Event::DragOver(drag) => fmt_drag(f, "DragOver", drag),
+            Event::DragLeave => write!(f, "DragLeave"),
+            Event::Drop(drag) => fmt_drag(f, "Drop", drag),
+            Event::Timer(token) => write!(f, "Timer({:?})", token),
+            Event::Idle(token) => write!(f, "Idle({:?})", token),
+            Event::Command(cmd) => write!(f, "Command({})", cmd.selector),
+            Event::Notification(notification) => write!(
+                f,
+                "Notification({} from {:?})",
+                notification.selector(),
+                notification.source()
+            ),
+            Event::TargetedCommand(target, cmd) => {
+                write!(f, "TargetedCommand({:?}, {})", target, cmd.selector)
+            }
+        }
+    }
+}
+
+fn fmt_mouse(f: &mut fmt::Formatter, name: &str, mouse: &MouseEvent) -> fmt::Result {
+    write!(f, "{}({:?} at {:?}", name, mouse.button, mouse.pos)?;
+    if mouse.count > 0 {
+        write!(f, ", count {}", mouse.count)?;
+    }
+    fmt_mods(f, mouse.mods)?;
+    write!(f, ")")
+}
+
+fn fmt_drag(f: &mut fmt::Formatter, name: &str, drag: &DragEvent) -> fmt::Result {
+    write!(f, "{}(at {:?}", name, drag.pos)?;
+    let files = drag.payload.get_files().len();
+    if files > 0 {
+        write!(f, ", {} files", files)?;
+    }
+    let formats: Vec<_> = drag.payload.formats().collect();
+    if !formats.is_empty() {
+        write!(f, ", {:?}", formats)?;
+    }
+    fmt_mods(f, drag.mods)?;
+    write!(f, ")")
+}
+
+fn fmt_key(f: &mut fmt::Formatter, name: &str, key: &KeyEvent) -> fmt::Result {
+    write!(f, "{}({:?}", name, key.code)?;
+    match &key.key {
+        KbKey::Character(text) => write!(f, " {:?}", text)?,
+        KbKey::Unidentified => (),
+        other => write!(f, " {:?}", other)?,
+    }
+    if key.is_composing {
+        write!(f, ", composing")?;
+    }
+    if key.repeat {
+        write!(f, ", repeat")?;
+    }
+    fmt_mods(f, key.mods)?;
+    write!(f, ")")
+}
+
+/// Write the modifiers, if any are pressed.
+fn fmt_mods(f: &mut fmt::Formatter, mods: KeyModifiers) -> fmt::Result {
+    if mods == KeyModifiers::default() {
+        Ok(())
+    } else {
+        write!(f, ", {:?}", mods)
+    }
+}
+
+/// The search for the last widget in the focus chain that comes before a
+/// given widget in the widget tree.
+///
+/// This is carried by `LifeCycle::RouteFocusAnchor`.
+#[derive(Clone)]
+pub struct FocusAnchor(Rc<RefCell<AnchorSearch>>);
+
+struct AnchorSearch {
+    target: WidgetId,
+    anchor: Option<WidgetId>,
+    found: bool,
+}
+
+impl FocusAnchor {
+    pub(crate) fn new(target: WidgetId) -> FocusAnchor {
+        FocusAnchor(Rc::new(RefCell::new(AnchorSearch {
+            target,
+            anchor: None,
+            found: false,
+        })))
+    }
+
+    /// Visit a widget, in tree order, returning whether to visit its children.
+    pub(crate) fn visit(&self, state: &BaseState) -> bool {
+        let mut search = self.0.borrow_mut();
+        if search.found {
+            return false;
+        }
+        if state.id == search.target {
+            search.found = true;
+            return false;
+        }
+        if state.children.contains(&search.target) {
+            // a widget comes before its children, if it registered first
+            if state.focus_chain.first() == Some(&state.id) {
+                search.anchor = Some(state.id);
+            }
+            true
+        } else {
+            // this whole subtree comes before the target
+            if let Some(last) = state.focus_chain.last() {
+                search.anchor = Some(*last);
+            }
+            false
+        }
+    }
+
+    /// The result of the search: `None` if the target wasn't found, and
+    /// otherwise the widget in the focus chain that comes before it, if any.
+    pub(crate) fn result(&self) -> Option<Option<WidgetId>> {
+        let search = self.0.borrow();
+        if search.found {
+            Some(search.anchor)
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialEq for FocusAnchor {
+    fn eq(&self, other: &FocusAnchor) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for FocusAnchor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FocusAnchor({:?})", self.0.borrow().target)
+    }
+}
+
 #[cfg(test)]
-pub(crate) use state_cell::{StateCell, StateCheckFn};
+pub(crate) use state_cell::{StateCell, StateCheckFn, TreeCell, TreeEntry};
 
 #[cfg(test)]
 mod state_cell {
     use crate::core::BaseState;
+    use crate::kurbo::Rect;
     use crate::WidgetId;
     use std::{cell::RefCell, rc::Rc};
 
@@ -289,6 +795,21 @@ mod state_cell {
     #[derive(Clone)]
     pub struct StateCheckFn(Rc<dyn Fn(&BaseState)>);
 
+    /// An interior-mutable list of the widgets in the tree, in the order
+    /// that `LifeCycle::DebugRecordTree` visits them.
+    #[derive(Clone, Default)]
+    pub struct TreeCell(Rc<RefCell<Vec<TreeEntry>>>);
+
+    /// A widget, as recorded in a `TreeCell`.
+    #[derive(Clone, Debug)]
+    pub struct TreeEntry {
+        pub id: WidgetId,
+        /// `None` for the root widget.
+        pub parent: Option<WidgetId>,
+        /// The widget's layout rect, in its parent's coordinate space.
+        pub layout_rect: Rect,
+    }
+
     /// a hacky way of printing the widget id if we panic
     struct WidgetDrop(bool, WidgetId);
 
@@ -327,6 +848,40 @@ mod state_cell {
         }
     }
 
+    impl TreeCell {
+        pub(crate) fn push(&self, entry: TreeEntry) {
+            self.0.borrow_mut().push(entry);
+        }
+
+        pub(crate) fn take(&self) -> Vec<TreeEntry> {
+            self.0.replace(Vec::new())
+        }
+    }
+
+    impl PartialEq for TreeCell {
+        fn eq(&self, other: &TreeCell) -> bool {
+            Rc::ptr_eq(&self.0, &other.0)
+        }
+    }
+
+    impl std::fmt::Debug for TreeCell {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "TreeCell({} widgets)", self.0.borrow().len())
+        }
+    }
+
+    impl PartialEq for StateCell {
+        fn eq(&self, other: &StateCell) -> bool {
+            Rc::ptr_eq(&self.0, &other.0)
+        }
+    }
+
+    impl PartialEq for StateCheckFn {
+        fn eq(&self, other: &StateCheckFn) -> bool {
+            Rc::ptr_eq(&self.0, &other.0)
+        }
+    }
+
     impl std::fmt::Debug for StateCell {
         fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
             let inner = if self.0.borrow().is_some() {
@@ -344,3 +899,57 @@ mod state_cell {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::*;
+    use crate::{Code, MouseButton, MouseButtons};
+
+    #[test]
+    fn event_debug_shows_details() {
+        let mouse = MouseEvent {
+            pos: Point::new(10., 20.),
+            window_pos: Point::new(10., 20.),
+            mods: KeyModifiers::default(),
+            count: 2,
+            button: MouseButton::Right,
+            buttons: MouseButtons::new(),
+            focus: false,
+        };
+        let debug = format!("{:?}", Event::MouseDown(mouse.clone()));
+        assert!(debug.starts_with("MouseDown(Right at "));
+        assert!(debug.contains("10.0") && debug.contains("count 2"));
+
+        let mods = KeyModifiers {
+            shift: true,
+            ..Default::default()
+        };
+        let key = KeyEvent::for_test(mods, "A", Code::KeyA);
+        let debug = format!("{:?}", Event::KeyDown(key));
+        assert!(debug.contains("KeyA") && debug.contains("\"A\"") && debug.contains("shift"));
+
+        let cmd = Event::TargetedCommand(Target::Global, REPLACE_CHILD.into());
+        assert!(format!("{:?}", cmd).contains("druid-test.replace-child"));
+
+        // equality compares the details
+        assert_eq!(
+            Event::MouseDown(mouse.clone()),
+            Event::MouseDown(mouse.clone())
+        );
+        assert_ne!(
+            Event::MouseDown(mouse.clone()),
+            Event::MouseUp(mouse.clone())
+        );
+        let mut moved = mouse.clone();
+        moved.pos.x += 1.;
+        assert_ne!(Event::MouseDown(mouse), Event::MouseDown(moved));
+        assert_eq!(
+            cmd,
+            Event::TargetedCommand(Target::Global, REPLACE_CHILD.into())
+        );
+        assert_ne!(cmd, Event::Command(REPLACE_CHILD.into()));
+        assert_eq!(LifeCycle::HotChanged(true), LifeCycle::HotChanged(true));
+        assert_ne!(LifeCycle::HotChanged(true), LifeCycle::FocusChanged(true));
+    }
+}
@@ -16,13 +16,13 @@
 
 use std::any::Any;
 use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 
-use crate::shell::IdleHandle;
+use crate::shell::AppIdleHandle;
 use crate::win_handler::EXT_EVENT_IDLE_TOKEN;
-use crate::{Command, Selector, Target, WindowId};
+use crate::{Command, Selector, Target};
 
-pub(crate) type ExtCommand = (Selector, Option<Box<dyn Any + Send>>, Option<Target>);
+pub(crate) type ExtCommand = (Selector, Option<Box<dyn Any + Send>>, Target);
 
 /// A thing that can move into other threads and be used to submit commands back
 /// to the running application.
@@ -30,8 +30,9 @@ pub(crate) type ExtCommand = (Selector, Option<Box<dyn Any + Send>>, Option<Targ
 /// This API is preliminary, and may be changed or removed without warning.
 #[derive(Clone)]
 pub struct ExtEventSink {
-    queue: Arc<Mutex<VecDeque<ExtCommand>>>,
-    handle: Arc<Mutex<Option<IdleHandle>>>,
+    /// This is weak so that we can tell when the application has gone away.
+    queue: Weak<Mutex<VecDeque<ExtCommand>>>,
+    handle: Arc<Mutex<Option<AppIdleHandle>>>,
 }
 
 /// The stuff that we hold onto inside the app that is related to the
@@ -40,12 +41,9 @@ pub struct ExtEventSink {
 pub(crate) struct ExtEventHost {
     /// A shared queue of items that have been sent to us.
     queue: Arc<Mutex<VecDeque<ExtCommand>>>,
-    /// This doesn't exist when the app starts and it can go away if a window
-    /// closes, so we keep a reference here and can update it when needed.
-    handle: Arc<Mutex<Option<IdleHandle>>>,
-    /// The window that the handle belongs to, so we can keep track of when
-    /// we need to get a new handle.
-    pub(crate) handle_window_id: Option<WindowId>,
+    /// This doesn't exist until the application has been created, so we
+    /// keep a reference here and set it at launch.
+    handle: Arc<Mutex<Option<AppIdleHandle>>>,
 }
 
 /// An error that occurs if an external event cannot be submitted.
@@ -60,21 +58,28 @@ impl ExtEventHost {
 
     pub(crate) fn make_sink(&self) -> ExtEventSink {
         ExtEventSink {
-            queue: self.queue.clone(),
+            queue: Arc::downgrade(&self.queue),
             handle: self.handle.clone(),
         }
     }
 
-    pub(crate) fn set_idle(&mut self, handle: IdleHandle, window_id: WindowId) {
-        self.handle.lock().unwrap().replace(handle);
-        self.handle_window_id = Some(window_id);
+    /// Set the handle that wakes the application when events arrive.
+    ///
+    /// Events that were submitted before this are delivered once the run
+    /// loop starts.
+    pub(crate) fn set_idle(&mut self, mut handle: AppIdleHandle) {
+        let mut current = self.handle.lock().unwrap();
+        if self.has_pending_items() {
+            handle.schedule_idle(EXT_EVENT_IDLE_TOKEN);
+        }
+        current.replace(handle);
     }
 
     pub(crate) fn has_pending_items(&self) -> bool {
         !self.queue.lock().unwrap().is_empty()
     }
 
-    pub(crate) fn recv(&mut self) -> Option<(Option<Target>, Command)> {
+    pub(crate) fn recv(&mut self) -> Option<(Target, Command)> {
         self.queue
             .lock()
             .unwrap()
@@ -93,37 +98,89 @@ impl ExtEventSink {
     /// The `obj` argument can be any type which implements `Any + Send`, or `None`
     /// if this command has no argument.
     ///
-    /// If no explicit `Target` is submitted, the `Command` will be sent to
-    /// the application's first window; if that window is subsequently closed,
-    /// then the command will be sent to *an arbitrary other window*.
+    /// The sink has no window, so a command sent to `Target::Auto` is sent
+    /// with `Target::Global`: it only goes to the [`AppDelegate`]. To reach
+    /// the widgets of a window, send it to that window.
     ///
-    /// This limitation may be removed in the future.
+    /// Commands are delivered in the order that they are submitted, once the
+    /// run loop has started; this does not need any window to be open, so
+    /// the delegate can receive them during startup. If the application has
+    /// quit, this returns an error.
     ///
     /// [`Command`]: struct.Command.html
     /// [`Selector`]: struct.Selector.html
+    /// [`AppDelegate`]: trait.AppDelegate.html
     pub fn submit_command<T: Any + Send>(
         &self,
         sel: Selector,
         obj: impl Into<Option<T>>,
-        target: impl Into<Option<Target>>,
+        target: impl Into<Target>,
     ) -> Result<(), ExtEventError> {
-        let target = target.into();
+        let target = target.into().resolve(Target::Global);
         let obj = obj.into().map(|o| Box::new(o) as Box<dyn Any + Send>);
-        if let Some(handle) = self.handle.lock().unwrap().as_mut() {
-            handle.schedule_idle(EXT_EVENT_IDLE_TOKEN);
-        }
-        self.queue
+        let queue = self.queue.upgrade().ok_or(ExtEventError)?;
+        queue
             .lock()
             .map_err(|_| ExtEventError)?
             .push_back((sel, obj, target));
+        // wake the app only once the command is in the queue, so it can't be missed
+        if let Some(handle) = self.handle.lock().map_err(|_| ExtEventError)?.as_mut() {
+            handle.schedule_idle(EXT_EVENT_IDLE_TOKEN);
+        }
         Ok(())
     }
 }
 
 impl std::fmt::Display for ExtEventError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Window missing for external event")
+        write!(f, "Application missing for external event")
     }
 }
 
 impl std::error::Error for ExtEventError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const TEST_SEL: Selector = Selector::new("druid-test.ext-event");
+
+    #[test]
+    fn submissions_arrive_in_order() {
+        let mut host = ExtEventHost::new();
+        let threads: Vec<_> = (0..4u32)
+            .map(|thread_idx| {
+                let sink = host.make_sink();
+                thread::spawn(move || {
+                    for i in 0..100u32 {
+                        sink.submit_command(TEST_SEL, (thread_idx, i), Target::Auto)
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let mut next = [0u32; 4];
+        while let Some((target, cmd)) = host.recv() {
+            assert_eq!(target, Target::Global);
+            assert_eq!(cmd.selector, TEST_SEL);
+            let (thread_idx, i) = *cmd.get_object::<(u32, u32)>().unwrap();
+            assert_eq!(i, next[thread_idx as usize]);
+            next[thread_idx as usize] += 1;
+        }
+        assert_eq!(next, [100; 4]);
+    }
+
+    #[test]
+    fn submit_after_app_quit() {
+        let host = ExtEventHost::new();
+        let sink = host.make_sink();
+        assert!(sink.submit_command(TEST_SEL, (), Target::Auto).is_ok());
+        drop(host);
+        assert!(sink.submit_command(TEST_SEL, (), Target::Auto).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2019 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A window without a platform window, for the benchmarks.
+
+use crate::core::CommandQueue;
+use crate::shell::{Clipboard, WindowHandle};
+use crate::{theme, Command, Data, Env, Event, Size, Target, Widget, Window, WindowDesc, WindowId};
+
+/// A window whose widgets get events straight from the caller, with no
+/// platform window or event loop.
+///
+/// This is for the benchmarks in `benches/`, which can't reach the test
+/// harness; it is only built with the `bench-internals` feature, and isn't
+/// part of the public API.
+#[doc(hidden)]
+pub struct HeadlessWindow<T> {
+    data: T,
+    env: Env,
+    window: Window<T>,
+    cmds: CommandQueue,
+}
+
+impl<T: Data> HeadlessWindow<T> {
+    /// A window of `size` with `root` as its contents, which have been sent
+    /// the events of a window that has just opened. They aren't laid out.
+    pub fn new(data: T, root: impl Widget<T> + 'static, size: Size) -> HeadlessWindow<T> {
+        let desc = WindowDesc::new(|| root);
+        let mut window = Window::new(WindowId::next(), WindowHandle::default(), desc);
+        window.clipboard = Clipboard::in_memory();
+        let mut headless = HeadlessWindow {
+            data,
+            env: theme::init(),
+            window,
+            cmds: Default::default(),
+        };
+        headless.event(Event::WindowConnected);
+        headless.event(Event::Size(size));
+        headless
+    }
+
+    /// Send an event to the widgets, then the commands that they submit,
+    /// and update them.
+    pub fn event(&mut self, event: Event) {
+        self.window
+            .event(&mut self.cmds, event, &mut self.data, &self.env);
+        while let Some((target, cmd)) = self.cmds.pop_front() {
+            self.submit_command(cmd, target);
+        }
+        self.window.update(&mut self.cmds, &self.data, &self.env);
+        self.window
+            .invalidate_and_finalize(&mut self.cmds, &self.data, &self.env);
+    }
+
+    /// Send a command to `target`.
+    ///
+    /// `Target::Auto` is this window. There is no application, so a
+    /// `Target::Global` command goes nowhere.
+    pub fn submit_command(&mut self, cmd: impl Into<Command>, target: impl Into<Target>) {
+        match target.into().resolve(self.window.id) {
+            Target::Global | Target::Auto => (),
+            target => self.event(Event::TargetedCommand(target, cmd.into())),
+        }
+    }
+}
@@ -214,7 +214,8 @@ impl<A: ?Sized, B: ?Sized, T: Lens<A, B>> LensExt<A, B> for T {}
 /// of that data. The `LensWrap` widget is a way to "focus" the data
 /// reference down, for the subtree. One advantage is performance;
 /// data changes that don't intersect the scope of the lens aren't
-/// propagated.
+/// propagated: the child's `update` is only called when the part of the
+/// data that the lens exposes, or the env, has changed.
 ///
 /// Another advantage is generality and reuse. If a widget (or tree of
 /// widgets) is designed to work with some chunk of data, then with a
@@ -228,6 +229,8 @@ impl<A: ?Sized, B: ?Sized, T: Lens<A, B>> LensExt<A, B> for T {}
 pub struct LensWrap<U, L, W> {
     inner: W,
     lens: L,
+    /// The env of the last update, to tell whether it has changed.
+    env: Option<Env>,
     // The following is a workaround for otherwise getting E0207.
     phantom: PhantomData<U>,
 }
@@ -241,6 +244,7 @@ impl<U, L, W> LensWrap<U, L, W> {
         LensWrap {
             inner,
             lens,
+            env: None,
             phantom: Default::default(),
         }
     }
@@ -260,17 +264,27 @@ where
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.env = Some(env.clone());
+        }
         let inner = &mut self.inner;
         self.lens
             .with(data, |data| inner.lifecycle(ctx, event, data, env))
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        let env_changed = match &self.env {
+            Some(old_env) => !old_env.same(env),
+            None => true,
+        };
+        if env_changed {
+            self.env = Some(env.clone());
+        }
         let inner = &mut self.inner;
         let lens = &self.lens;
         lens.with(old_data, |old_data| {
             lens.with(data, |data| {
-                if !old_data.same(data) {
+                if env_changed || !old_data.same(data) {
                     inner.update(ctx, old_data, data, env);
                 }
             })
@@ -294,6 +308,76 @@ where
     }
 }
 
+/// A wrapper for its widget subtree to see a transformed [`Env`].
+///
+/// This is to the env what [`LensWrap`] is to the data: the subtree sees
+/// the part or the version of its parent's env that the lens exposes. As
+/// widgets can't change the env, only the lens's [`with`] is used, so a
+/// lens that exposes part of the env doesn't clone it.
+///
+/// ```
+/// # use druid::widget::{Label, WidgetExt};
+/// # use druid::{lens, theme, Color, Env};
+/// let white = Label::<()>::new("white text").env_lens(lens::Map::new(
+///     |env: &Env| env.clone().adding(theme::LABEL_COLOR, Color::WHITE),
+///     |_: &mut Env, _| (),
+/// ));
+/// ```
+///
+/// [`Env`]: ../struct.Env.html
+/// [`LensWrap`]: struct.LensWrap.html
+/// [`with`]: trait.Lens.html#tymethod.with
+pub struct EnvLens<L, W> {
+    inner: W,
+    lens: L,
+}
+
+impl<L, W> EnvLens<L, W> {
+    /// Wrap a widget with a lens on the env.
+    pub fn new(inner: W, lens: L) -> EnvLens<L, W> {
+        EnvLens { inner, lens }
+    }
+}
+
+impl<T, L, W> Widget<T> for EnvLens<L, W>
+where
+    T: Data,
+    L: Lens<Env, Env>,
+    W: Widget<T>,
+{
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let inner = &mut self.inner;
+        self.lens
+            .with(env, |env| inner.event(ctx, event, data, env))
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        let inner = &mut self.inner;
+        self.lens
+            .with(env, |env| inner.lifecycle(ctx, event, data, env))
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        let inner = &mut self.inner;
+        self.lens
+            .with(env, |env| inner.update(ctx, old_data, data, env))
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let inner = &mut self.inner;
+        self.lens.with(env, |env| inner.layout(ctx, bc, data, env))
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let inner = &mut self.inner;
+        self.lens.with(env, |env| inner.paint(paint_ctx, data, env));
+    }
+
+    fn id(&self) -> Option<WidgetId> {
+        self.inner.id()
+    }
+}
+
 /// Lens accessing a member of some type using accessor functions
 ///
 /// See also the `lens` macro.
@@ -545,3 +629,141 @@ where
         v
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Checkbox, Controller, Flex, WidgetExt};
+    use crate::{Key, Selector, Target};
+
+    #[test]
+    fn lensed_checkbox_toggles_one_field() {
+        type State = ((bool, bool), u32);
+
+        let (id_1, id_2) = widget_id2();
+        let first = crate::lens!(State, 0).then(crate::lens!((bool, bool), 0));
+        let second = crate::lens!(State, 0).then(crate::lens!((bool, bool), 1));
+
+        let widget = Flex::column()
+            .with_child(Checkbox::new("first").lens(first).with_id(id_1), 0.0)
+            .with_child(Checkbox::new("second").lens(second).with_id(id_2), 0.0);
+
+        Harness::create(((false, false), 7), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            let center = harness.get_state(id_2).layout_rect.center();
+            click(harness, center);
+            assert_eq!(*harness.data(), ((false, true), 7));
+
+            let center = harness.get_state(id_1).layout_rect.center();
+            click(harness, center);
+            assert_eq!(*harness.data(), ((true, true), 7));
+        })
+    }
+
+    #[test]
+    fn lens_wrap_updates_for_its_field_or_env() {
+        const BUMP: Selector = Selector::new("druid-tests.bump-counter");
+        const NOTHING: Selector = Selector::new("druid-tests.nothing");
+        const SCALE: Key<f64> = Key::new("druid-tests.scale");
+
+        /// Data that counts how many times it is cloned.
+        struct Counted(Rc<Cell<usize>>);
+
+        impl Clone for Counted {
+            fn clone(&self) -> Counted {
+                self.0.set(self.0.get() + 1);
+                Counted(self.0.clone())
+            }
+        }
+
+        impl Data for Counted {
+            fn same(&self, other: &Counted) -> bool {
+                Rc::ptr_eq(&self.0, &other.0)
+            }
+        }
+
+        /// Increments the first field when it gets `BUMP`.
+        struct Bump;
+
+        impl<W: Widget<(u32, Counted)>> Controller<(u32, Counted), W> for Bump {
+            fn event(
+                &mut self,
+                child: &mut W,
+                ctx: &mut EventCtx,
+                event: &Event,
+                data: &mut (u32, Counted),
+                env: &Env,
+            ) {
+                match event {
+                    Event::Command(cmd) if cmd.selector == BUMP => data.0 += 1,
+                    _ => child.event(ctx, event, data, env),
+                }
+            }
+        }
+
+        let clones = Rc::new(Cell::new(0));
+        let recording = Recording::default();
+        let widget = ModularWidget::new(())
+            .record(&recording)
+            .lens(crate::lens!((u32, Counted), 1))
+            .controller(Bump);
+
+        Harness::create((0, Counted(clones.clone())), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            recording.clear();
+            clones.set(0);
+
+            // events that change nothing clone nothing
+            for _ in 0..5 {
+                harness.submit_command(NOTHING, Target::Auto);
+            }
+            assert_eq!(clones.get(), 0);
+
+            // the other field changes; only the root keeps a copy of the data
+            harness.submit_command(BUMP, Target::Auto);
+            assert_eq!(harness.data().0, 1);
+            assert_eq!(clones.get(), 1);
+            assert_eq!(
+                count_records(&recording, |r| matches!(r, Record::Update(_))),
+                0
+            );
+
+            // a change to the env is passed on, though the field is the same
+            harness.configure_env(|env, _| env.set(SCALE, 2.0));
+            harness.submit_command(NOTHING, Target::Auto);
+            assert_eq!(
+                count_records(&recording, |r| matches!(r, Record::Update(_))),
+                1
+            );
+        });
+    }
+
+    #[test]
+    fn env_lens_shows_the_transformed_env() {
+        const SCALE: Key<f64> = Key::new("druid-tests.scale");
+
+        let seen = Rc::new(Cell::new(0.0));
+        let seen_in_paint = seen.clone();
+        let widget = ModularWidget::new(())
+            .paint_fn(move |_, _, _: &u32, env| seen_in_paint.set(env.get(SCALE)))
+            .env_lens(crate::lens::Map::new(
+                |env: &Env| env.clone().adding(SCALE, 2.0),
+                |_: &mut Env, _| (),
+            ));
+
+        Harness::create(0, widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            harness.paint();
+            assert_eq!(seen.get(), 2.0);
+        });
+    }
+}
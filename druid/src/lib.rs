@@ -104,24 +104,34 @@
 #![allow(clippy::new_ret_no_self, clippy::needless_doctest_main)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+// the derive macros refer to `druid`, which lets them be used in this crate too
+extern crate self as druid;
+
 use druid_shell as shell;
 pub use druid_shell::{kurbo, piet};
 
+mod access;
+mod animation;
 mod app;
 mod app_delegate;
 mod bloom;
 mod box_constraints;
+mod color;
 mod command;
 mod contexts;
 mod core;
 mod data;
+mod direction;
 mod env;
 mod event;
 mod ext_event;
+#[cfg(feature = "bench-internals")]
+mod headless;
 pub mod lens;
 mod localization;
 mod menu;
 mod mouse;
+mod sub_window;
 #[cfg(test)]
 mod tests;
 mod text;
@@ -135,28 +145,42 @@ pub use kurbo::{Affine, Insets, Point, Rect, Size, Vec2};
 pub use piet::{Color, LinearGradient, RadialGradient, RenderContext, UnitPoint};
 // these are the types from shell that we expose; others we only use internally.
 pub use shell::{
-    Application, Clipboard, ClipboardFormat, Cursor, Error as PlatformError, FileDialogOptions,
-    FileInfo, FileSpec, FormatId, HotKey, KeyCode, KeyEvent, KeyModifiers, MouseButton, RawMods,
-    SysMods, Text, TimerToken, WindowHandle,
+    Application, ChildSurface, Clipboard, ClipboardFormat, Code, CompositionEvent, Cursor,
+    DragPayload, Error as PlatformError, FileDialogOptions, FileInfo, FileSpec, FormatId, HotKey,
+    IdleToken, KbKey, KeyEvent, KeyModifiers, KeyState, Monitor, MouseButton, MouseButtons,
+    RawMods, RenderBackend, Scalable, Scale, Screen, ScrollPhase, SysMods, Text, TimerToken,
+    WindowHandle, WindowLevel, WindowState,
 };
 
 pub use crate::core::{BoxedWidget, WidgetPod};
-pub use app::{AppLauncher, WindowDesc};
-pub use app_delegate::{AppDelegate, DelegateCtx};
+pub use access::{AccessEvent, AccessNode, Role};
+pub use animation::{Animatable, Animator, Curve};
+pub use app::{AppLauncher, WindowConfig, WindowDesc};
+pub use app_delegate::{AppDelegate, DelegateCtx, Handled};
 pub use box_constraints::BoxConstraints;
-pub use command::{sys as commands, Command, Selector, Target};
-pub use contexts::{EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, UpdateCtx};
+pub use color::{ColorExt, ColorParseError};
+pub use command::{sys as commands, CaptureRequest, Command, Notification, Selector, Target};
+pub use contexts::{EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, Region, UpdateCtx};
 pub use data::Data;
+pub use direction::{DirectionalInsets, LayoutDirection};
 pub use env::{Env, Key, KeyOrValue, Value, ValueType};
-pub use event::{Event, LifeCycle, WheelEvent};
+#[cfg(feature = "serde")]
+pub use env::{EnvOverrides, OverrideError};
+pub use event::{DragEvent, Event, LifeCycle, WheelEvent, ZoomEvent};
 pub use ext_event::{ExtEventError, ExtEventSink};
-pub use lens::{Lens, LensExt, LensWrap};
+#[cfg(feature = "bench-internals")]
+#[doc(hidden)]
+pub use headless::HeadlessWindow;
+pub use lens::{EnvLens, Lens, LensExt, LensWrap};
 pub use localization::LocalizedString;
 pub use menu::{sys as platform_menus, ContextMenu, MenuDesc, MenuItem};
 pub use mouse::MouseEvent;
+pub use text::{
+    CustomAction, EditAction, EditBuffer, Formatter, KeyBindings, ParseFormatter, Validation,
+};
 pub use widget::{Widget, WidgetId};
 pub use win_handler::DruidHandler;
 pub use window::{Window, WindowId};
 
 #[cfg(test)]
-pub(crate) use event::{StateCell, StateCheckFn};
+pub(crate) use event::{StateCell, StateCheckFn, TreeCell, TreeEntry};
@@ -387,6 +387,80 @@ impl<'a, T: std::fmt::Display> std::fmt::Display for PrintLocales<'a, T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    static TEST_EN_US: &str = "\
+hello-counter = The count is { $count }
+english-only = Only in English
+";
+    static TEST_FR_FR: &str = "\
+hello-counter = Le compte est { $count }
+";
+
+    /// A manager for `locale` that uses the embedded test bundles, instead
+    /// of the resources on disk.
+    fn test_manager(locale: &str) -> L10nManager {
+        let en_us: LanguageIdentifier = "en-US".parse().unwrap();
+        let fr_fr: LanguageIdentifier = "fr-FR".parse().unwrap();
+        let mut resources = HashMap::new();
+        for (locale, source) in &[("en-US", TEST_EN_US), ("fr-FR", TEST_FR_FR)] {
+            let res = FluentResource::try_new(source.to_string()).expect("invalid test bundle");
+            resources.insert(format!("{}/test.ftl", locale), Arc::new(res));
+        }
+        let mut res_mgr = ResourceManager {
+            resources,
+            locales: vec![en_us.clone(), fr_fr],
+            default_locale: en_us,
+            path_scheme: "{locale}/{res_id}".into(),
+        };
+        let resources = vec!["test.ftl".to_string()];
+        let current_locale: LanguageIdentifier = locale.parse().unwrap();
+        let current_bundle = res_mgr.get_bundle(&current_locale, &resources);
+        L10nManager {
+            res_mgr,
+            resources,
+            current_bundle,
+            current_locale,
+        }
+    }
+
+    fn test_env(locale: &str) -> Env {
+        let mut env = Env::default();
+        env.set_localization_manager(test_manager(locale));
+        env
+    }
+
+    #[test]
+    fn interpolation() {
+        let env = test_env("en-US");
+        let mut string =
+            LocalizedString::new("hello-counter").with_arg("count", |d: &u32, _| (*d).into());
+
+        assert!(string.resolve(&5, &env));
+        assert_eq!(string.localized_str(), "The count is 5");
+        // resolving again with the same data doesn't change the string
+        assert!(!string.resolve(&5, &env));
+        assert!(string.resolve(&42, &env));
+        assert_eq!(string.localized_str(), "The count is 42");
+    }
+
+    #[test]
+    fn locale_fallback() {
+        // fr-CA isn't available, so we get fr-FR, and then en-US for what
+        // fr-FR doesn't have.
+        let env = test_env("fr-CA");
+        let mut counter =
+            LocalizedString::new("hello-counter").with_arg("count", |d: &u32, _| (*d).into());
+        let mut english = LocalizedString::new("english-only");
+        let mut missing = LocalizedString::new("missing-key").with_placeholder("Missing");
+
+        counter.resolve(&3, &env);
+        english.resolve(&3, &env);
+        missing.resolve(&3, &env);
+        assert_eq!(counter.localized_str(), "Le compte est 3");
+        assert_eq!(english.localized_str(), "Only in English");
+        assert_eq!(missing.localized_str(), "Missing");
+    }
+
     #[test]
     fn resolve() {
         let en_us: LanguageIdentifier = "en-US".parse().unwrap();
@@ -106,10 +106,13 @@
 //! [`SET_MENU`]: ../struct.Selector.html#associatedconstant.SET_MENU
 
 use std::num::NonZeroU32;
+use std::sync::Arc;
 
 use crate::kurbo::Point;
 use crate::shell::{HotKey, KeyCompare, Menu as PlatformMenu, RawMods, SysMods};
-use crate::{commands, Command, Data, Env, KeyCode, LocalizedString, Selector};
+use crate::{commands, Code, Command, Data, Env, LocalizedString, Selector, WidgetId};
+
+type PredicateFn<T> = dyn Fn(&T, &Env) -> bool;
 
 /// A platform-agnostic description of an application, window, or context
 /// menu.
@@ -134,11 +137,17 @@ pub enum MenuEntry<T> {
 /// A `MenuItem` always has a title (a [`LocalizedString`]) as well a [`Command`],
 /// that is sent to the application when the item is selected.
 ///
+/// The command is sent to the window that the menu belongs to, or to the
+/// widget that showed it, for a [`ContextMenu`]; the items of an application
+/// menu with no window, as on macOS, send theirs to [`Target::Global`].
+///
 /// In addition, other properties can be set during construction, such as whether
 /// the item is selected (checked), or enabled, or if it has a hotkey.
 ///
 /// [`LocalizedString`]: struct.LocalizedString.html
 /// [`Command`]: struct.Command.html
+/// [`ContextMenu`]: struct.ContextMenu.html
+/// [`Target::Global`]: enum.Target.html#variant.Global
 #[derive(Debug, Clone)]
 pub struct MenuItem<T> {
     title: LocalizedString<T>,
@@ -148,17 +157,32 @@ pub struct MenuItem<T> {
     //highlighted: bool,
     selected: bool,
     enabled: bool, // (or state is stored elsewhere)
+    disabled_when: Option<StateFn<T>>,
+    selected_when: Option<StateFn<T>>,
     /// Identifies the platform object corresponding to this item.
     platform_id: MenuItemId,
 }
 
 /// A menu displayed as a pop-over.
+///
+/// A context menu can be shown by a widget with [`EventCtx::show_context_menu`],
+/// or by submitting a [`SHOW_CONTEXT_MENU`] command to a window.
+///
+/// [`EventCtx::show_context_menu`]: struct.EventCtx.html#method.show_context_menu
+/// [`SHOW_CONTEXT_MENU`]: commands/constant.SHOW_CONTEXT_MENU.html
 #[derive(Debug, Clone)]
 pub struct ContextMenu<T> {
     pub(crate) menu: MenuDesc<T>,
     pub(crate) location: Point,
+    /// The widget that the commands of the menu's items are sent to; if this
+    /// is `None`, they are sent to the window.
+    pub(crate) target: Option<WidgetId>,
 }
 
+/// A predicate on the data, used to resolve the state of a menu item
+/// when the menu is built.
+struct StateFn<T>(Arc<PredicateFn<T>>);
+
 /// Uniquely identifies a menu item.
 ///
 /// On the druid-shell side, the id is represented as a u32.
@@ -178,6 +202,8 @@ impl<T> MenuItem<T> {
             tool_tip: None,
             selected: false,
             enabled: true,
+            disabled_when: None,
+            selected_when: None,
             platform_id: MenuItemId::PLACEHOLDER,
         }
     }
@@ -214,6 +240,25 @@ impl<T> MenuItem<T> {
         self
     }
 
+    /// Disable this menu item while the provided predicate on the data is true.
+    ///
+    /// The predicate is checked each time the menu is built, and when the
+    /// data changes; the window's menu is rebuilt if the result changes.
+    pub fn disabled_when(mut self, p: impl Fn(&T, &Env) -> bool + 'static) -> Self {
+        self.disabled_when = Some(StateFn(Arc::new(p)));
+        self
+    }
+
+    /// Enable this menu item only while the provided predicate on the data
+    /// is true.
+    ///
+    /// This is the opposite of [`disabled_when`], and replaces it.
+    ///
+    /// [`disabled_when`]: #method.disabled_when
+    pub fn enabled_when(self, p: impl Fn(&T, &Env) -> bool + 'static) -> Self {
+        self.disabled_when(move |data, env| !p(data, env))
+    }
+
     /// Mark this menu item as selected. This will usually be indicated by
     /// a checkmark.
     pub fn selected(mut self) -> Self {
@@ -228,6 +273,31 @@ impl<T> MenuItem<T> {
         }
         self
     }
+
+    /// Mark this item as selected while the provided predicate on the data
+    /// is true.
+    ///
+    /// As with [`disabled_when`], the predicate is checked each time the
+    /// menu is built, and when the data changes.
+    ///
+    /// [`disabled_when`]: #method.disabled_when
+    pub fn selected_when(mut self, p: impl Fn(&T, &Env) -> bool + 'static) -> Self {
+        self.selected_when = Some(StateFn(Arc::new(p)));
+        self
+    }
+
+    /// Update the enabled and selected states from the data, returning
+    /// whether either changed.
+    fn resolve_state(&mut self, data: &T, env: &Env) -> bool {
+        let old = (self.enabled, self.selected);
+        if let Some(p) = &self.disabled_when {
+            self.enabled = !(p.0)(data, env);
+        }
+        if let Some(p) = &self.selected_when {
+            self.selected = (p.0)(data, env);
+        }
+        old != (self.enabled, self.selected)
+    }
 }
 
 impl<T: Data> MenuDesc<T> {
@@ -343,7 +413,12 @@ impl<T: Data> MenuDesc<T> {
             match item {
                 MenuEntry::Item(ref mut item) => {
                     item.title.resolve(data, env);
-                    item.platform_id = MenuItemId::next();
+                    item.resolve_state(data, env);
+                    // an item keeps its id when the menu is rebuilt, so that
+                    // choosing it from the old menu still works
+                    if item.platform_id == MenuItemId::PLACEHOLDER {
+                        item.platform_id = MenuItemId::next();
+                    }
                     menu.add_item(
                         item.platform_id.as_u32(),
                         item.title.localized_str(),
@@ -367,13 +442,35 @@ impl<T: Data> MenuDesc<T> {
         menu
     }
 
+    /// Resolve the titles and states of the items against the data,
+    /// returning whether any of them changed, so that the native menu needs
+    /// to be built again.
+    pub(crate) fn update_state(&mut self, data: &T, env: &Env) -> bool {
+        let mut changed = false;
+        for item in &mut self.items {
+            match item {
+                MenuEntry::Item(item) => {
+                    changed |= item.title.resolve(data, env);
+                    changed |= item.resolve_state(data, env);
+                }
+                MenuEntry::SubMenu(submenu) => {
+                    changed |= submenu.item.title.resolve(data, env);
+                    changed |= submenu.update_state(data, env);
+                }
+                MenuEntry::Separator => (),
+            }
+        }
+        changed
+    }
+
     /// Given a command identifier from druid-shell, returns the command
     /// corresponding to that id in this menu, if one exists.
     pub(crate) fn command_for_id(&self, id: u32) -> Option<Command> {
         for item in &self.items {
             match item {
+                // a disabled item can't be chosen, but we don't trust the platform
                 MenuEntry::Item(item) if item.platform_id.as_u32() == id => {
-                    return Some(item.command.clone())
+                    return Some(item.command.clone()).filter(|_| item.enabled)
                 }
                 MenuEntry::SubMenu(menu) => {
                     if let Some(cmd) = menu.command_for_id(id) {
@@ -385,11 +482,46 @@ impl<T: Data> MenuDesc<T> {
         }
         None
     }
+
+    /// The platform id of the item at `idx`, if the menu has been built.
+    #[cfg(test)]
+    pub(crate) fn item_platform_id(&self, idx: usize) -> Option<u32> {
+        match self.items.get(idx) {
+            Some(MenuEntry::Item(item)) => item.platform_id.0.map(NonZeroU32::get),
+            _ => None,
+        }
+    }
+
+    /// Whether the item at `idx` is enabled and selected, as last resolved.
+    #[cfg(test)]
+    pub(crate) fn item_state(&self, idx: usize) -> Option<(bool, bool)> {
+        match self.items.get(idx) {
+            Some(MenuEntry::Item(item)) => Some((item.enabled, item.selected)),
+            _ => None,
+        }
+    }
 }
 
 impl<T> ContextMenu<T> {
+    /// Create a context menu, to be shown at `location` in window coordinates.
     pub fn new(menu: MenuDesc<T>, location: Point) -> Self {
-        ContextMenu { menu, location }
+        ContextMenu {
+            menu,
+            location,
+            target: None,
+        }
+    }
+}
+
+impl<T> Clone for StateFn<T> {
+    fn clone(&self) -> Self {
+        StateFn(self.0.clone())
+    }
+}
+
+impl<T> std::fmt::Debug for StateFn<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "StateFn")
     }
 }
 
@@ -612,7 +744,7 @@ pub mod sys {
                     LocalizedString::new("win-menu-file-exit"),
                     commands::QUIT_APP,
                 )
-                .hotkey(RawMods::Alt, KeyCode::F4)
+                .hotkey(RawMods::Alt, Code::F4)
             }
         }
     }
@@ -805,3 +937,56 @@ pub mod sys {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme;
+
+    #[test]
+    fn menu_state_follows_the_data() {
+        const CHOSEN: Selector = Selector::new("druid-tests.menu-state-chosen");
+
+        let env = theme::init();
+        let mut menu = MenuDesc::<(bool, bool)>::empty()
+            .append(
+                MenuItem::new(LocalizedString::new("Save"), CHOSEN)
+                    .enabled_when(|data: &(bool, bool), _| data.0),
+            )
+            .append(
+                MenuItem::new(LocalizedString::new("Toolbar"), CHOSEN)
+                    .selected_when(|data: &(bool, bool), _| data.1),
+            )
+            .append(
+                MenuItem::new(LocalizedString::new("Animate"), CHOSEN)
+                    .enabled_when(|_, env| env.get(theme::ANIMATIONS_ENABLED)),
+            );
+
+        // the first update resolves the titles, and disables "Save"
+        assert!(menu.update_state(&(false, false), &env));
+        assert_eq!(menu.item_state(0), Some((false, false)));
+        assert_eq!(menu.item_state(1), Some((true, false)));
+        assert_eq!(menu.item_state(2), Some((true, false)));
+
+        // nothing resolves differently, so the menu needn't be rebuilt
+        assert!(!menu.update_state(&(false, false), &env));
+
+        assert!(menu.update_state(&(true, false), &env));
+        assert_eq!(menu.item_state(0), Some((true, false)));
+        assert!(menu.update_state(&(true, true), &env));
+        assert_eq!(menu.item_state(1), Some((true, true)));
+        assert!(!menu.update_state(&(true, true), &env));
+
+        let env = env.adding(theme::ANIMATIONS_ENABLED, false);
+        assert!(menu.update_state(&(true, true), &env));
+        assert_eq!(menu.item_state(2), Some((false, false)));
+
+        // items keep their ids when the menu is built again
+        let _ = menu.build_window_menu(&(true, true), &env);
+        let id = menu.item_platform_id(0);
+        assert!(id.is_some());
+        let _ = menu.build_window_menu(&(false, true), &env);
+        assert_eq!(menu.item_platform_id(0), id);
+        assert!(menu.command_for_id(id.unwrap()).is_none());
+    }
+}
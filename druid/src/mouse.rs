@@ -15,13 +15,20 @@
 //! The mousey bits
 
 use crate::kurbo::Point;
-use crate::{KeyModifiers, MouseButton};
+use crate::{KeyModifiers, MouseButton, MouseButtons};
 
 /// The state of the mouse for a click, mouse-up, or move event.
 ///
 /// In `druid`, unlike in `druid_shell`, we treat the widget's coordinate
-/// space and the window's coordinate space separately.
-#[derive(Debug, Clone)]
+/// space and the window's coordinate space separately. A [`WidgetPod`]
+/// moves `pos` into the coordinate space of its child, as does a [`Scroll`]
+/// by its scroll offset, while `window_pos` stays the same;
+/// [`EventCtx::to_window`] goes the other way, for any point.
+///
+/// [`WidgetPod`]: struct.WidgetPod.html
+/// [`Scroll`]: widget/struct.Scroll.html
+/// [`EventCtx::to_window`]: struct.EventCtx.html#method.to_window
+#[derive(Debug, Clone, PartialEq)]
 pub struct MouseEvent {
     /// The position of the mouse in the coordinate space of the receiver.
     pub pos: Point,
@@ -35,6 +42,16 @@ pub struct MouseEvent {
     /// The currently pressed button in the case of a move or click event,
     /// or the released button in the case of a mouse-up event.
     pub button: MouseButton,
+    /// The buttons that are held down once this event has happened.
+    ///
+    /// During a drag, this is how a `MouseMoved` tells which buttons are
+    /// held. For a `MouseDown` it includes `button`, and for a `MouseUp` it
+    /// doesn't.
+    pub buttons: MouseButtons,
+    /// Whether this click also brought the window into focus.
+    ///
+    /// This is only reported on Windows; elsewhere it is always `false`.
+    pub focus: bool,
 }
 
 impl From<druid_shell::MouseEvent> for MouseEvent {
@@ -44,6 +61,8 @@ impl From<druid_shell::MouseEvent> for MouseEvent {
             mods,
             count,
             button,
+            buttons,
+            focus,
         } = src;
         MouseEvent {
             pos,
@@ -51,6 +70,8 @@ impl From<druid_shell::MouseEvent> for MouseEvent {
             mods,
             count,
             button,
+            buttons,
+            focus,
         }
     }
 }
@@ -0,0 +1,372 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Windows opened by a widget, such as tooltips and dropdowns.
+//!
+//! A sub-window has its own data, which is not the application's: its root is
+//! a `SubWindowHost` that owns it. When that data is of the same type as the
+//! data of the widget that opened the window, the two are kept in sync with
+//! commands: the host sends its data to the widget when a widget in the
+//! sub-window changes it, and the widget's pod sends its data to the host when
+//! it changes in the application.
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::{
+    BoxConstraints, Command, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Selector, UpdateCtx, Widget, WidgetId, WidgetPod, WindowConfig, WindowDesc, WindowId,
+};
+
+/// Open a sub-window. The argument is a `SubWindowDesc`, as a one-shot.
+pub(crate) const NEW_SUB_WINDOW: Selector = Selector::new("druid-builtin.new-sub-window");
+
+/// Sent by the widget that opened a sub-window to its host, with the widget's
+/// data, when that data changes.
+pub(crate) const SUB_WINDOW_PARENT_TO_HOST: Selector =
+    Selector::new("druid-builtin.sub-window-parent-to-host");
+
+/// Sent by a host to the widget that opened its window, with the host's data,
+/// when a widget in the sub-window changes it.
+pub(crate) const SUB_WINDOW_HOST_TO_PARENT: Selector =
+    Selector::new("druid-builtin.sub-window-host-to-parent");
+
+/// Sent by a host to the widget that opened its window, with the host's
+/// `WidgetId`, when the sub-window closes.
+pub(crate) const SUB_WINDOW_HOST_CLOSED: Selector =
+    Selector::new("druid-builtin.sub-window-host-closed");
+
+/// Everything needed to open a sub-window, whatever the application's data.
+pub(crate) struct SubWindowDesc {
+    /// The id the window will have.
+    pub(crate) id: WindowId,
+    /// The id of the host at the root of the window.
+    pub(crate) host_id: WidgetId,
+    /// The window of the widget that opened this one.
+    pub(crate) parent_window: WindowId,
+    pub(crate) config: WindowConfig,
+    host: Box<dyn Widget<()>>,
+}
+
+/// The root of a sub-window, which owns its data.
+struct SubWindowHost<U> {
+    id: WidgetId,
+    parent: WidgetId,
+    data: U,
+    env: Env,
+    child: WidgetPod<U, Box<dyn Widget<U>>>,
+}
+
+/// The root widget of a sub-window's `WindowDesc`, which ignores the
+/// application's data.
+struct AppDataIgnored {
+    host: WidgetPod<(), Box<dyn Widget<()>>>,
+}
+
+impl SubWindowDesc {
+    /// Describe a sub-window opened by the widget `parent`, in `parent_window`,
+    /// that shows `widget` with its own `data`.
+    pub(crate) fn new<U: Data>(
+        parent: WidgetId,
+        parent_window: WindowId,
+        config: WindowConfig,
+        widget: impl Widget<U> + 'static,
+        data: U,
+        env: Env,
+    ) -> SubWindowDesc {
+        let host_id = WidgetId::next();
+        let host = SubWindowHost {
+            id: host_id,
+            parent,
+            data,
+            env,
+            child: WidgetPod::new(widget).boxed(),
+        };
+        SubWindowDesc {
+            id: WindowId::next(),
+            host_id,
+            parent_window,
+            config,
+            host: Box::new(host),
+        }
+    }
+
+    /// The description of the platform window, for an application whose data
+    /// is `T`.
+    pub(crate) fn make_window_desc<T: Data>(self) -> WindowDesc<T> {
+        let mut host = WidgetPod::new(self.host);
+        // the host's data is not the application's, so it is never "changed"
+        host.set_always_update(true);
+        let root = AppDataIgnored { host };
+        let mut desc = WindowDesc::new(move || root).with_config(self.config);
+        desc.id = self.id;
+        desc.menu = None;
+        desc.sub_window_of = Some(self.parent_window);
+        desc
+    }
+}
+
+impl<U: Data> Widget<()> for SubWindowHost<U> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        if let Event::Command(cmd) = event {
+            if cmd.selector == SUB_WINDOW_PARENT_TO_HOST {
+                // if the parent's data is of another type, ours is a snapshot
+                if let Ok(data) = cmd.get_object::<U>() {
+                    self.data = data.clone();
+                }
+                ctx.set_handled();
+                return;
+            }
+        }
+        let old_data = self.data.clone();
+        self.child.event(ctx, event, &mut self.data, &self.env);
+        if !old_data.same(&self.data) {
+            let cmd = Command::new(SUB_WINDOW_HOST_TO_PARENT, self.data.clone());
+            ctx.submit_command(cmd, self.parent);
+        }
+        if let Event::WindowDisconnected = event {
+            ctx.submit_command(Command::new(SUB_WINDOW_HOST_CLOSED, self.id), self.parent);
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &(), _env: &Env) {
+        self.child.lifecycle(ctx, event, &self.data, &self.env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {
+        self.child.update(ctx, &self.data, &self.env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), _env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, &self.data, &self.env);
+        self.child.set_layout_rect(
+            ctx,
+            &self.data,
+            &self.env,
+            Rect::from_origin_size(Point::ORIGIN, size),
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &(), _env: &Env) {
+        self.child.paint_with_offset(ctx, &self.data, &self.env);
+    }
+
+    fn id(&self) -> Option<WidgetId> {
+        Some(self.id)
+    }
+}
+
+impl<T: Data> Widget<T> for AppDataIgnored {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut T, env: &Env) {
+        self.host.event(ctx, event, &mut (), env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, env: &Env) {
+        self.host.lifecycle(ctx, event, &(), env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, _data: &T, env: &Env) {
+        self.host.update(ctx, &(), env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, env: &Env) -> Size {
+        let size = self.host.layout(ctx, bc, &(), env);
+        self.host
+            .set_layout_rect(ctx, &(), env, Rect::from_origin_size(Point::ORIGIN, size));
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, env: &Env) {
+        self.host.paint_with_offset(ctx, &(), env);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Flex, WidgetExt};
+    use crate::WindowLevel;
+
+    /// A widget with a slot that a test can fill from outside, such as with the
+    /// root of a sub-window, which the harness doesn't open.
+    fn slot(pending: Rc<RefCell<Option<Box<dyn Widget<u32>>>>>) -> impl Widget<u32> {
+        ModularWidget::new(None::<WidgetPod<u32, Box<dyn Widget<u32>>>>)
+            .event_fn(move |slot, ctx, event, data, env| {
+                if let Some(widget) = pending.borrow_mut().take() {
+                    let mut pod = WidgetPod::new(widget);
+                    // like the root of a window, whose contents have their own data
+                    pod.set_always_update(true);
+                    *slot = Some(pod);
+                    ctx.children_changed();
+                } else if let Some(pod) = slot {
+                    pod.event(ctx, event, data, env);
+                }
+            })
+            .lifecycle_fn(|slot, ctx, event, data, env| {
+                if let Some(pod) = slot {
+                    pod.lifecycle(ctx, event, data, env);
+                }
+            })
+            .update_fn(|slot, ctx, _old_data, data, env| {
+                if let Some(pod) = slot {
+                    pod.update(ctx, data, env);
+                }
+            })
+            .layout_fn(|slot, ctx, bc, data, env| match slot {
+                Some(pod) => {
+                    let size = pod.layout(ctx, bc, data, env);
+                    pod.set_layout_rect(
+                        ctx,
+                        data,
+                        env,
+                        Rect::from_origin_size(Point::ORIGIN, size),
+                    );
+                    size
+                }
+                None => bc.min(),
+            })
+    }
+
+    /// A widget that opens a sub-window with its data when it gets `OPEN`, and
+    /// adds one to its data when it gets `BUMP`.
+    fn sub_window_opener<U: Data>(
+        id: WidgetId,
+        contents: Rc<RefCell<Option<Box<dyn Widget<U>>>>>,
+        data_for_window: impl Fn(u32) -> U + 'static,
+    ) -> impl Widget<u32> {
+        const OPEN: Selector = Selector::new("druid-tests.open");
+        const BUMP: Selector = Selector::new("druid-tests.bump");
+        ModularWidget::new(())
+            .event_fn(move |_, ctx, event, data: &mut u32, env| {
+                if let Event::Command(cmd) = event {
+                    if cmd.selector == OPEN {
+                        let widget = contents.borrow_mut().take().unwrap();
+                        let config = WindowConfig::default().set_level(WindowLevel::DropDown);
+                        ctx.new_sub_window(config, widget, data_for_window(*data), env.clone());
+                    } else if cmd.selector == BUMP {
+                        *data += 1;
+                    }
+                }
+            })
+            .with_id(id)
+    }
+
+    /// A widget in a sub-window that records the data it sees, and adds ten to
+    /// it when it gets `BUMP_CHILD`.
+    fn sub_window_contents(id: WidgetId, seen: Rc<Cell<u32>>) -> impl Widget<u32> {
+        const BUMP_CHILD: Selector = Selector::new("druid-tests.bump-child");
+        let seen_in_update = seen.clone();
+        ModularWidget::new(())
+            .event_fn(|_, _ctx, event, data: &mut u32, _env| {
+                if let Event::Command(cmd) = event {
+                    if cmd.selector == BUMP_CHILD {
+                        *data += 10;
+                    }
+                }
+            })
+            .lifecycle_fn(move |_, _ctx, event, data, _env| {
+                if let LifeCycle::WidgetAdded = event {
+                    seen.set(*data);
+                }
+            })
+            .update_fn(move |_, _ctx, _old_data, data, _env| seen_in_update.set(*data))
+            .with_id(id)
+    }
+
+    #[test]
+    fn sub_window_data_is_kept_in_sync() {
+        const OPEN: Selector = Selector::new("druid-tests.open");
+        const BUMP: Selector = Selector::new("druid-tests.bump");
+        const BUMP_CHILD: Selector = Selector::new("druid-tests.bump-child");
+
+        let (opener_id, child_id) = widget_id2();
+        let seen = Rc::new(Cell::new(0));
+        let contents: Box<dyn Widget<u32>> = Box::new(sub_window_contents(child_id, seen.clone()));
+        let contents = Rc::new(RefCell::new(Some(contents)));
+        let pending = Rc::new(RefCell::new(None));
+        let widget = Flex::column()
+            .with_child(sub_window_opener(opener_id, contents, |n| n), 0.0)
+            .with_child(slot(pending.clone()), 0.0);
+
+        Harness::create(5u32, widget, |harness| {
+            harness.send_initial_events();
+            harness.submit_command(OPEN, opener_id);
+            let mut sub_windows = harness.take_sub_windows();
+            assert_eq!(sub_windows.len(), 1);
+            let desc = sub_windows.pop().unwrap();
+            assert_eq!(desc.config.level, WindowLevel::DropDown);
+            *pending.borrow_mut() = Some(desc.make_window_desc::<u32>().root);
+            // any event installs the contents in the slot
+            harness.event(Event::WindowConnected);
+            assert_eq!(seen.get(), 5);
+
+            // a change in the application reaches the sub-window
+            harness.submit_command(BUMP, opener_id);
+            assert_eq!(*harness.data(), 6);
+            assert_eq!(seen.get(), 6);
+
+            // and a change in the sub-window reaches the application
+            harness.submit_command(BUMP_CHILD, child_id);
+            assert_eq!(seen.get(), 16);
+            assert_eq!(*harness.data(), 16);
+        });
+    }
+
+    #[test]
+    fn sub_window_with_other_data_is_a_snapshot() {
+        const OPEN: Selector = Selector::new("druid-tests.open");
+        const BUMP: Selector = Selector::new("druid-tests.bump");
+
+        let opener_id = WidgetId::next();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_in_lifecycle = seen.clone();
+        let seen_in_update = seen.clone();
+        let label = ModularWidget::new(())
+            .lifecycle_fn(move |_, _ctx, event, data: &String, _env| {
+                if let LifeCycle::WidgetAdded = event {
+                    seen_in_lifecycle.borrow_mut().push(data.clone());
+                }
+            })
+            .update_fn(move |_, _ctx, _old_data, data, _env| {
+                seen_in_update.borrow_mut().push(data.clone())
+            });
+        let contents: Box<dyn Widget<String>> = Box::new(label);
+        let contents = Rc::new(RefCell::new(Some(contents)));
+        let pending = Rc::new(RefCell::new(None));
+        let widget = Flex::column()
+            .with_child(
+                sub_window_opener(opener_id, contents, |n| n.to_string()),
+                0.0,
+            )
+            .with_child(slot(pending.clone()), 0.0);
+
+        Harness::create(5u32, widget, |harness| {
+            harness.send_initial_events();
+            harness.submit_command(OPEN, opener_id);
+            let desc = harness.take_sub_windows().pop().unwrap();
+            *pending.borrow_mut() = Some(desc.make_window_desc::<u32>().root);
+            harness.event(Event::WindowConnected);
+
+            // the sub-window's data is a `String`, so it keeps the value it was
+            // opened with
+            harness.submit_command(BUMP, opener_id);
+            assert_eq!(*harness.data(), 6);
+            assert_eq!(*seen.borrow(), vec!["5".to_string()]);
+        });
+    }
+}
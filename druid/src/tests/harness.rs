@@ -13,12 +13,25 @@
 // limitations under the License.
 
 //! Tools and infrastructure for testing widgets.
+use std::env;
+use std::path::Path;
+
 use crate::core::{BaseState, CommandQueue};
-use crate::piet::{BitmapTarget, Device, Piet};
+use crate::piet::{BitmapTarget, Device, ImageFormat, Piet, RenderContext};
+use crate::sub_window::{SubWindowDesc, NEW_SUB_WINDOW};
 use crate::*;
 
 pub(crate) const DEFAULT_SIZE: Size = Size::new(400., 400.);
 
+/// The directory that the images for [`Harness::assert_snapshot`] are kept in.
+///
+/// [`Harness::assert_snapshot`]: struct.Harness.html#method.assert_snapshot
+const SNAPSHOT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/snapshots");
+
+/// If this environment variable is set, snapshots are saved instead of
+/// being compared.
+const UPDATE_SNAPSHOTS: &str = "DRUID_UPDATE_SNAPSHOTS";
+
 /// A type that tries very hard to provide a comforting and safe environment
 /// for widgets who are trying to find their way.
 ///
@@ -30,15 +43,24 @@ pub(crate) const DEFAULT_SIZE: Size = Size::new(400., 400.);
 /// it will call `update` automatically after an event.
 ///
 /// That said, it _is_ missing a bunch of logic that would normally be handled
-/// in `AppState`: for instance it does not clear the `needs_inval` and
-/// `children_changed` flags on the window after an update.
+/// in `AppState`: for instance it does not clear the `invalid` region of the
+/// window after an update.
 ///
 /// In addition, layout and paint **are not called automatically**. This is
 /// because paint is triggered by druid-shell, and there is no druid-shell here;
 ///
 /// if you want those functions run you will need to call them yourself.
 ///
+/// What is painted can be checked against a stored image with
+/// [`assert_snapshot`].
+///
+/// Widgets get an in-memory clipboard, instead of the system one; it is
+/// available with [`clipboard`].
+///
 /// Also, timers don't work.  ¯\_(ツ)_/¯
+///
+/// [`clipboard`]: #method.clipboard
+/// [`assert_snapshot`]: #method.assert_snapshot
 pub struct Harness<'a, T> {
     piet: Piet<'a>,
     inner: Inner<T>,
@@ -52,6 +74,9 @@ struct Inner<T> {
     env: Env,
     window: Window<T>,
     cmds: CommandQueue,
+    /// The sub-windows that widgets asked to open, which the harness doesn't
+    /// show.
+    sub_windows: Vec<SubWindowDesc>,
 }
 
 /// A way to clean up resources when our target goes out of scope.
@@ -65,20 +90,44 @@ impl<T: Data> Harness<'_, T> {
     ///
     /// For lifetime reasons™, we cannot just make a harness. It's complicated.
     /// I tried my best.
-    pub fn create(data: T, root: impl Widget<T> + 'static, mut f: impl FnMut(&mut Harness<T>)) {
-        let mut device = Device::new().expect("harness failed to get device");
-        let target = device.bitmap_target(400, 400, 2.).expect("bitmap_target");
+    ///
+    /// This panics if there is no backend to render with; see [`try_create`].
+    ///
+    /// [`try_create`]: #method.try_create
+    pub fn create(data: T, root: impl Widget<T> + 'static, f: impl FnMut(&mut Harness<T>)) {
+        if let Err(e) = Harness::try_create(data, root, f) {
+            panic!("harness failed to get a render target: {}", e);
+        }
+    }
+
+    /// Like [`create`], but returns the error instead of panicking if
+    /// there is no backend to render with, such as on a machine without
+    /// the native graphics libraries.
+    ///
+    /// Text is laid out by the same backend, so there is no way to provide
+    /// another one for it.
+    ///
+    /// [`create`]: #method.create
+    pub fn try_create(
+        data: T,
+        root: impl Widget<T> + 'static,
+        mut f: impl FnMut(&mut Harness<T>),
+    ) -> Result<(), piet::Error> {
+        let mut device = Device::new()?;
+        let target = device.bitmap_target(400, 400, 2.)?;
         let mut target = TargetGuard(Some(target));
         let piet = target.0.as_mut().unwrap().render_context();
 
         let desc = WindowDesc::new(|| root);
-        let window = Window::new(WindowId::next(), Default::default(), desc);
+        let mut window = Window::new(WindowId::next(), Default::default(), desc);
+        window.clipboard = Clipboard::in_memory();
 
         let inner = Inner {
             data,
             env: theme::init(),
             window,
             cmds: Default::default(),
+            sub_windows: Vec::new(),
         };
 
         let mut harness = Harness {
@@ -87,6 +136,7 @@ impl<T: Data> Harness<'_, T> {
             window_size: DEFAULT_SIZE,
         };
         f(&mut harness);
+        Ok(())
     }
 
     /// Set the size without sending a resize event; intended to be used
@@ -95,6 +145,14 @@ impl<T: Data> Harness<'_, T> {
         self.window_size = size;
     }
 
+    /// Modify the environment, as with [`AppLauncher::configure_env`];
+    /// intended to be used before calling `send_initial_events`.
+    ///
+    /// [`AppLauncher::configure_env`]: ../../struct.AppLauncher.html#method.configure_env
+    pub fn configure_env(&mut self, f: impl FnOnce(&mut Env, &T)) {
+        f(&mut self.inner.env, &self.inner.data);
+    }
+
     pub fn window(&self) -> &Window<T> {
         &self.inner.window
     }
@@ -109,6 +167,11 @@ impl<T: Data> Harness<'_, T> {
         &self.inner.data
     }
 
+    /// The in-memory clipboard that the widgets use.
+    pub fn clipboard(&self) -> Clipboard {
+        self.inner.window.clipboard.clone()
+    }
+
     /// Retrieve a copy of this widget's `BaseState`, or die trying.
     pub(crate) fn get_state(&mut self, widget: WidgetId) -> BaseState {
         match self.try_get_state(widget) {
@@ -133,11 +196,96 @@ impl<T: Data> Harness<'_, T> {
         self.lifecycle(LifeCycle::DebugInspectState(checkfn))
     }
 
+    /// The widgets in the tree, each with its parent.
+    ///
+    /// They are in the order that lifecycle events reach them: each widget
+    /// comes before its descendants, and its children come in the order that
+    /// their parent passes events to them, which for most containers is the
+    /// order that they are painted in.
+    pub(crate) fn tree(&mut self) -> Vec<TreeEntry> {
+        let tree = TreeCell::default();
+        self.lifecycle(LifeCycle::DebugRecordTree(tree.clone()));
+        tree.take()
+    }
+
+    /// The id of this widget's parent, or `None` for the root widget.
+    ///
+    /// Panics if the widget is not in the tree.
+    pub(crate) fn get_parent(&mut self, widget: WidgetId) -> Option<WidgetId> {
+        self.tree_entry(widget).parent
+    }
+
+    /// The ids of all of this widget's descendants, in the order of [`tree`].
+    ///
+    /// Panics if the widget is not in the tree.
+    ///
+    /// [`tree`]: #method.tree
+    pub(crate) fn descendants(&mut self, widget: WidgetId) -> Vec<WidgetId> {
+        let tree = self.tree();
+        let start = match tree.iter().position(|entry| entry.id == widget) {
+            Some(idx) => idx,
+            None => panic!("descendants failed for widget {:?}", widget),
+        };
+        // the descendants come right after the widget, up to the first widget
+        // whose parent is neither the widget nor one of them
+        let mut subtree = vec![widget];
+        for entry in &tree[start + 1..] {
+            match entry.parent {
+                Some(parent) if subtree.contains(&parent) => subtree.push(entry.id),
+                _ => break,
+            }
+        }
+        subtree.remove(0);
+        subtree
+    }
+
+    /// This widget's layout rect, in the window's coordinate space.
+    ///
+    /// This adds up the origins of the layout rects of the widget and its
+    /// ancestors, as the `window_pos` of mouse events does.
+    ///
+    /// Panics if the widget is not in the tree.
+    pub(crate) fn layout_rect_in_window(&mut self, widget: WidgetId) -> Rect {
+        let tree = self.tree();
+        let entry = |id: WidgetId| match tree.iter().find(|entry| entry.id == id) {
+            Some(entry) => entry,
+            None => panic!("layout_rect_in_window failed for widget {:?}", id),
+        };
+        let mut rect = entry(widget).layout_rect;
+        let mut parent = entry(widget).parent;
+        while let Some(id) = parent {
+            let ancestor = entry(id);
+            rect = rect + ancestor.layout_rect.origin().to_vec2();
+            parent = ancestor.parent;
+        }
+        rect
+    }
+
+    /// The accessibility tree of the window, as a screen reader would be
+    /// given it after the last layout.
+    pub(crate) fn access_tree(&mut self) -> AccessNode {
+        let inner = &mut self.inner;
+        inner
+            .window
+            .build_access_tree(&mut inner.cmds, &inner.data, &inner.env)
+    }
+
+    fn tree_entry(&mut self, widget: WidgetId) -> TreeEntry {
+        match self.tree().into_iter().find(|entry| entry.id == widget) {
+            Some(entry) => entry,
+            None => panic!("widget {:?} is not in the tree", widget),
+        }
+    }
+
     /// Send a command to a target.
-    pub fn submit_command(&mut self, cmd: impl Into<Command>, target: impl Into<Option<Target>>) {
-        let target = target.into().unwrap_or_else(|| self.inner.window.id.into());
-        let event = Event::TargetedCommand(target, cmd.into());
-        self.event(event);
+    ///
+    /// `Target::Auto` is the harness's window. There is no delegate, so a
+    /// `Target::Global` command goes nowhere.
+    pub fn submit_command(&mut self, cmd: impl Into<Command>, target: impl Into<Target>) {
+        match target.into().resolve(self.inner.window.id) {
+            Target::Global | Target::Auto => (),
+            target => self.event(Event::TargetedCommand(target, cmd.into())),
+        }
     }
 
     /// Send the events that would normally be sent when the app starts.
@@ -150,32 +298,113 @@ impl<T: Data> Harness<'_, T> {
     /// Send an event to the widget.
     ///
     /// If this event triggers lifecycle events, they will also be dispatched,
-    /// as will any resulting commands. This will also trigger `update`.
-    ///
-    /// Commands dispatched during `update` will not be sent?
+    /// as will any resulting commands. This will also trigger `update`;
+    /// commands submitted during `update` are then sent as well.
     pub fn event(&mut self, event: Event) {
         self.inner.event(event);
         self.process_commands();
         self.update();
     }
 
+    /// Send a drag event from the platform, returning whether a widget
+    /// would take the drop, as the window tells the platform.
+    ///
+    /// As with [`event`], any resulting commands are dispatched, and this
+    /// will also trigger `update`.
+    ///
+    /// [`event`]: #method.event
+    pub(crate) fn drag(&mut self, event: Event) -> bool {
+        let accepted = self.inner.event(event);
+        self.process_commands();
+        self.update();
+        accepted
+    }
+
+    /// Send the idle events that widgets have requested, as the platform
+    /// would once its event loop is idle.
+    ///
+    /// As with [`event`], any resulting commands are dispatched, and this
+    /// will also trigger `update`.
+    ///
+    /// [`event`]: #method.event
+    pub fn process_idle(&mut self) {
+        self.inner.idle();
+        self.process_commands();
+        self.update();
+    }
+
+    /// Send `WindowDisconnected`, as when the window is closed.
+    ///
+    /// As when a real window closes, any resulting commands are dispatched,
+    /// and this will also trigger `update`. The widgets are dropped along
+    /// with the harness.
+    pub fn disconnect(&mut self) {
+        self.inner.disconnect();
+        self.process_commands();
+        self.update();
+    }
+
     fn process_commands(&mut self) {
         loop {
             let cmd = self.inner.cmds.pop_front();
             match cmd {
-                Some((target, cmd)) => self.event(Event::TargetedCommand(target, cmd)),
+                // handled by the app, not the window
+                Some((Target::Window(_), ref cmd))
+                    if cmd.selector == commands::SHOW_CONTEXT_MENU =>
+                {
+                    self.inner.show_context_menu(cmd)
+                }
+                Some((_, ref cmd)) if cmd.selector == NEW_SUB_WINDOW => {
+                    let desc = cmd.take_object().expect("new-sub-window object error");
+                    self.inner.sub_windows.push(*desc);
+                }
+                Some((target, cmd)) => self.submit_command(cmd, target),
                 None => break,
             }
         }
     }
 
+    /// Take the descriptions of the sub-windows that widgets have asked to
+    /// open since the last call.
+    pub(crate) fn take_sub_windows(&mut self) -> Vec<SubWindowDesc> {
+        std::mem::take(&mut self.inner.sub_windows)
+    }
+
+    /// Choose the item at `idx` in the context menu that is being shown,
+    /// as if by the user, and send its command.
+    pub fn choose_context_menu_item(&mut self, idx: usize) {
+        let id = self
+            .inner
+            .window
+            .context_menu
+            .as_ref()
+            .and_then(|menu| menu.menu.item_platform_id(idx))
+            .expect("no context menu item at this index");
+        if let Some((target, cmd)) = self.inner.window.get_menu_cmd(id) {
+            self.event(Event::TargetedCommand(target, cmd));
+        }
+    }
+
     fn lifecycle(&mut self, event: LifeCycle) {
         self.inner.lifecycle(event)
     }
 
     //TODO: should we expose this? I don't think so?
     fn update(&mut self) {
-        self.inner.update()
+        self.inner.update();
+        if !self.inner.cmds.is_empty() {
+            self.process_commands();
+        }
+    }
+
+    /// Send an `AnimFrame` with the given interval, in nanoseconds, since
+    /// the previous frame.
+    ///
+    /// Unlike `paint`, this does not measure the interval, or do layout.
+    pub fn anim_frame(&mut self, interval: u64) {
+        self.inner.anim_frame(interval);
+        self.process_commands();
+        self.update();
     }
 
     /// Only do a layout pass, without painting
@@ -183,16 +412,157 @@ impl<T: Data> Harness<'_, T> {
         self.inner.layout(&mut self.piet)
     }
 
+    /// Paint the whole window.
     #[allow(dead_code)]
     pub fn paint(&mut self) {
-        self.inner.paint(&mut self.piet)
+        let rect = self.window_size.to_rect();
+        self.inner.paint(&mut self.piet, rect)
+    }
+
+    /// Paint only the widgets that intersect `invalid_rect`, as when the
+    /// platform asks for part of the window.
+    #[allow(dead_code)]
+    pub fn paint_rect(&mut self, invalid_rect: Rect) {
+        self.inner.paint(&mut self.piet, invalid_rect)
+    }
+
+    /// Paint the window into a new bitmap the size of the window, and return
+    /// its pixels, as premultiplied RGBA, one row after another.
+    ///
+    /// Like [`paint`], this also does a layout.
+    ///
+    /// [`paint`]: #method.paint
+    #[allow(dead_code)]
+    pub fn render_to_image(&mut self) -> Vec<u8> {
+        let mut device = Device::new().expect("failed to create a device");
+        self.render_with(&mut device)
+    }
+
+    /// Paint the window like [`render_to_image`], but with `backend`
+    /// instead of the harness's own device.
+    ///
+    /// This fails where the backend can't paint, such as without a GPU.
+    ///
+    /// [`render_to_image`]: #method.render_to_image
+    pub fn render_with_backend(
+        &mut self,
+        backend: RenderBackend,
+    ) -> Result<Vec<u8>, PlatformError> {
+        let (width, height) = self.image_size();
+        let window_rect = self.window_size.to_rect();
+        let inner = &mut self.inner;
+        let paint = |piet: &mut Piet| inner.paint(piet, window_rect);
+        let image = backend.render_image(width as usize, height as usize, 1.0, paint)?;
+        Ok(image.pixels)
+    }
+
+    fn render_with(&mut self, device: &mut Device) -> Vec<u8> {
+        let (width, height) = self.image_size();
+        let mut target = device
+            .bitmap_target(width as usize, height as usize, 1.0)
+            .expect("failed to create a bitmap target");
+        {
+            let mut piet = target.render_context();
+            self.inner.paint(&mut piet, self.window_size.to_rect());
+            piet.finish().expect("failed to paint");
+        }
+        target
+            .into_raw_pixels(ImageFormat::RgbaPremul)
+            .expect("failed to read the painted pixels")
     }
+
+    fn image_size(&self) -> (u32, u32) {
+        let size = self.window_size;
+        (size.width.ceil() as u32, size.height.ceil() as u32)
+    }
+
+    /// Paint the window, and compare it with the snapshot `name`, which is
+    /// a PNG in `src/tests/snapshots`.
+    ///
+    /// Pixels match if none of their channels differ by more than `tolerance`;
+    /// if any don't, this panics, and saves what was painted next to the
+    /// snapshot, as `name.actual.png`.
+    ///
+    /// If the `DRUID_UPDATE_SNAPSHOTS` environment variable is set, the
+    /// snapshot is saved instead. A missing snapshot is a failure otherwise.
+    /// If there is no backend to paint with, the comparison is skipped; a
+    /// failure to paint once there is one is a failure.
+    pub fn assert_snapshot(&mut self, name: &str, tolerance: u8) {
+        let mut device = match Device::new() {
+            Ok(device) => device,
+            Err(e) => {
+                eprintln!("skipping snapshot '{}', there is no device: {}", name, e);
+                return;
+            }
+        };
+        let pixels = self.render_with(&mut device);
+        let (width, height) = self.image_size();
+        let dir = Path::new(SNAPSHOT_DIR);
+        let path = dir.join(format!("{}.png", name));
+        if env::var_os(UPDATE_SNAPSHOTS).is_some() {
+            save_png(&path, &pixels, width, height);
+            return;
+        }
+        if !path.exists() {
+            let actual = dir.join(format!("{}.actual.png", name));
+            save_png(&actual, &pixels, width, height);
+            panic!(
+                "snapshot '{}' is missing; the painted image is at {:?}, \
+                 and setting {} saves it as the snapshot",
+                name, actual, UPDATE_SNAPSHOTS
+            );
+        }
+
+        let expected = image::open(&path)
+            .unwrap_or_else(|e| panic!("failed to read snapshot {:?}: {}", path, e))
+            .to_rgba();
+        assert_eq!(
+            expected.dimensions(),
+            (width, height),
+            "snapshot '{}' is a different size",
+            name
+        );
+        let different = pixels
+            .chunks(4)
+            .zip(expected.into_raw().chunks(4))
+            .filter(|(actual, expected)| {
+                let mut channels = actual.iter().zip(expected.iter());
+                channels.any(|(a, e)| (*a as i16 - *e as i16).abs() > tolerance as i16)
+            })
+            .count();
+        if different > 0 {
+            let actual = dir.join(format!("{}.actual.png", name));
+            save_png(&actual, &pixels, width, height);
+            panic!(
+                "{} pixels differ from snapshot '{}'; the painted image is at {:?}, \
+                 and setting {} saves it as the snapshot",
+                different, name, actual, UPDATE_SNAPSHOTS
+            );
+        }
+    }
+}
+
+fn save_png(path: &Path, pixels: &[u8], width: u32, height: u32) {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).expect("failed to create the snapshot directory");
+    }
+    image::save_buffer(path, pixels, width, height, image::ColorType::RGBA(8))
+        .unwrap_or_else(|e| panic!("failed to save {:?}: {}", path, e));
 }
 
 impl<T: Data> Inner<T> {
-    fn event(&mut self, event: Event) {
+    fn event(&mut self, event: Event) -> bool {
         self.window
-            .event(&mut self.cmds, event, &mut self.data, &self.env);
+            .event(&mut self.cmds, event, &mut self.data, &self.env)
+    }
+
+    fn idle(&mut self) {
+        self.window.idle(&mut self.cmds, &mut self.data, &self.env);
+    }
+
+    fn disconnect(&mut self) {
+        self.window
+            .disconnect(&mut self.cmds, &mut self.data, &self.env);
     }
 
     fn lifecycle(&mut self, event: LifeCycle) {
@@ -201,17 +571,33 @@ impl<T: Data> Inner<T> {
     }
 
     fn update(&mut self) {
-        self.window.update(&self.data, &self.env);
+        self.window.update(&mut self.cmds, &self.data, &self.env);
+        // children added during update need their `WidgetAdded`
+        self.window
+            .invalidate_and_finalize(&mut self.cmds, &self.data, &self.env);
+    }
+
+    fn show_context_menu(&mut self, cmd: &Command) {
+        let menu = cmd
+            .get_object::<ContextMenu<T>>()
+            .expect("show-context-menu object error");
+        self.window
+            .show_context_menu(menu.to_owned(), &self.data, &self.env);
+    }
+
+    fn anim_frame(&mut self, interval: u64) {
+        self.window
+            .just_anim_frame(&mut self.cmds, interval, &self.data, &self.env);
     }
 
     fn layout(&mut self, piet: &mut Piet) {
-        self.window.just_layout(piet, &self.data, &self.env);
+        self.window
+            .just_layout(piet, &mut self.cmds, &self.data, &self.env);
     }
 
-    #[allow(dead_code)]
-    fn paint(&mut self, piet: &mut Piet) {
+    fn paint(&mut self, piet: &mut Piet, invalid_rect: Rect) {
         self.window
-            .do_paint(piet, &mut self.cmds, &self.data, &self.env);
+            .do_paint(piet, invalid_rect, &mut self.cmds, &self.data, &self.env);
     }
 }
 
@@ -221,6 +607,6 @@ impl Drop for TargetGuard<'_> {
         let _ = self
             .0
             .take()
-            .map(|t| t.into_raw_pixels(piet::ImageFormat::RgbaPremul));
+            .map(|t| t.into_raw_pixels(ImageFormat::RgbaPremul));
     }
 }
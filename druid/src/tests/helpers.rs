@@ -16,10 +16,11 @@
 //!
 //! This includes tools for making throwaway widgets more easily.
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::VecDeque;
 use std::rc::Rc;
 
+use super::harness::Harness;
 use crate::widget::WidgetExt;
 use crate::*;
 
@@ -34,7 +35,7 @@ macro_rules! assert_matches {
      }
  }
 
-pub type EventFn<S, T> = dyn FnMut(&mut S, &mut EventCtx, &Event, &T, &Env);
+pub type EventFn<S, T> = dyn FnMut(&mut S, &mut EventCtx, &Event, &mut T, &Env);
 pub type LifeCycleFn<S, T> = dyn FnMut(&mut S, &mut LifeCycleCtx, &LifeCycle, &T, &Env);
 pub type UpdateFn<S, T> = dyn FnMut(&mut S, &mut UpdateCtx, &T, &T, &Env);
 pub type LayoutFn<S, T> = dyn FnMut(&mut S, &mut LayoutCtx, &BoxConstraints, &T, &Env) -> Size;
@@ -42,6 +43,22 @@ pub type PaintFn<S, T> = dyn FnMut(&mut S, &mut PaintCtx, &T, &Env);
 
 pub const REPLACE_CHILD: Selector = Selector::new("druid-test.replace-child");
 
+/// Raises a widget made with [`make_square`] above its siblings.
+///
+/// [`make_square`]: fn.make_square.html
+pub const RAISE: Selector = Selector::new("druid-test.raise");
+
+/// The paints and clicks of the widgets made with [`make_square`], with
+/// their tags.
+///
+/// [`make_square`]: fn.make_square.html
+/// Makes a widget made with [`make_focus_requester`] request focus.
+///
+/// [`make_focus_requester`]: fn.make_focus_requester.html
+pub const REQUEST_FOCUS: Selector = Selector::new("druid-tests.request-focus");
+
+pub type SquareLog = Rc<RefCell<Vec<(&'static str, u32)>>>;
+
 /// A widget that can be constructed from individual functions, builder-style.
 ///
 /// This widget is generic over its state, which is passed in at construction time.
@@ -85,7 +102,7 @@ pub struct Recording(Rc<RefCell<VecDeque<Record>>>);
 /// A recording of a method call on a widget.
 ///
 /// Each member of the enum coorresponds to one of the methods on `Widget`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Record {
     /// An `Event`.
     E(Event),
@@ -100,6 +117,14 @@ pub enum Record {
     None,
 }
 
+/// An entry in the sequence given to [`Recording::assert_sequence`].
+pub enum ExpectedRecord {
+    /// A record equal to this one.
+    Is(Record),
+    /// Any record that matches the predicate; the name is shown on failure.
+    Like(&'static str, fn(&Record) -> bool),
+}
+
 /// like WidgetExt but just for this one thing
 pub trait TestWidgetExt<T: Data>: Widget<T> + Sized + 'static {
     fn record(self, recording: &Recording) -> Recorder<Self> {
@@ -127,7 +152,7 @@ impl<S, T> ModularWidget<S, T> {
 
     pub fn event_fn(
         mut self,
-        f: impl FnMut(&mut S, &mut EventCtx, &Event, &T, &Env) + 'static,
+        f: impl FnMut(&mut S, &mut EventCtx, &Event, &mut T, &Env) + 'static,
     ) -> Self {
         self.event = Some(Box::new(f));
         self
@@ -216,8 +241,8 @@ impl<T: Data> Widget<T> for ReplaceChild<T> {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         if let Event::Command(cmd) = event {
             if cmd.selector == REPLACE_CHILD {
+                ctx.remove_child(&mut self.inner);
                 self.inner = WidgetPod::new((self.replacer)());
-                ctx.children_changed();
                 return;
             }
         }
@@ -263,11 +288,78 @@ impl Recording {
         self.0.borrow_mut().pop_front().unwrap_or(Record::None)
     }
 
+    /// Consume the whole recording, asserting that it matches `expected`.
+    ///
+    /// On failure, this shows where the recording first diverged from what
+    /// was expected, along with the full recording.
+    pub fn assert_sequence(&self, expected: &[ExpectedRecord]) {
+        let actual: Vec<_> = self.0.borrow_mut().drain(..).collect();
+        let divergence = (0..expected.len().max(actual.len())).find(|&i| {
+            match (expected.get(i), actual.get(i)) {
+                (Some(expected), Some(actual)) => !expected.matches(actual),
+                _ => true,
+            }
+        });
+        if let Some(i) = divergence {
+            let expected = expected
+                .get(i)
+                .map(|record| format!("{:?}", record))
+                .unwrap_or_else(|| "end of recording".into());
+            let found = actual
+                .get(i)
+                .map(|record| format!("{:?}", record))
+                .unwrap_or_else(|| "end of recording".into());
+            let all: Vec<_> = actual
+                .iter()
+                .enumerate()
+                .map(|(i, record)| format!("  {}: {:?}", i, record))
+                .collect();
+            panic!(
+                "recording diverged at {}:\n  expected: {}\n     found: {}\nrecording:\n{}",
+                i,
+                expected,
+                found,
+                all.join("\n")
+            );
+        }
+    }
+
     fn push(&self, event: Record) {
         self.0.borrow_mut().push_back(event)
     }
 }
 
+impl ExpectedRecord {
+    /// Any `MouseMoved` event, wherever it is.
+    pub fn mouse_moved() -> ExpectedRecord {
+        ExpectedRecord::Like("E(MouseMoved(_))", |record| {
+            matches!(record, Record::E(Event::MouseMoved(_)))
+        })
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        match self {
+            ExpectedRecord::Is(expected) => expected == record,
+            ExpectedRecord::Like(_, predicate) => predicate(record),
+        }
+    }
+}
+
+impl From<Record> for ExpectedRecord {
+    fn from(record: Record) -> ExpectedRecord {
+        ExpectedRecord::Is(record)
+    }
+}
+
+impl std::fmt::Debug for ExpectedRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExpectedRecord::Is(record) => write!(f, "{:?}", record),
+            ExpectedRecord::Like(name, _) => write!(f, "{}", name),
+        }
+    }
+}
+
 impl<T: Data, W: Widget<T>> Widget<T> for Recorder<W> {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         self.recording.push(Record::E(event.clone()));
@@ -290,7 +382,7 @@ impl<T: Data, W: Widget<T>> Widget<T> for Recorder<W> {
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
         self.inner.update(ctx, old_data, data, env);
-        let inval = ctx.base_state.needs_inval;
+        let inval = !ctx.base_state.invalid.is_empty();
         self.recording.push(Record::Update(inval));
     }
 
@@ -345,3 +437,116 @@ pub fn widget_id6() -> (WidgetId, WidgetId, WidgetId, WidgetId, WidgetId, Widget
         WidgetId::next(),
     )
 }
+
+/// A single left click's `MouseEvent` at `pos`, with no buttons held.
+pub fn make_mouse(pos: Point) -> MouseEvent {
+    MouseEvent {
+        pos,
+        window_pos: pos,
+        mods: KeyModifiers::default(),
+        count: 1,
+        button: MouseButton::Left,
+        buttons: MouseButtons::new(),
+        focus: false,
+    }
+}
+
+/// Move the mouse to `pos`, and press and release the left button there.
+pub fn click<T: Data>(harness: &mut Harness<T>, pos: Point) {
+    let mouse = make_mouse(pos);
+    harness.event(Event::MouseMoved(mouse.clone()));
+    harness.event(Event::MouseDown(mouse.clone()));
+    harness.event(Event::MouseUp(mouse));
+}
+
+/// A `KeyDown` event, with no modifiers.
+pub fn key_down(text: &'static str, code: Code) -> Event {
+    Event::KeyDown(KeyEvent::for_test(KeyModifiers::default(), text, code))
+}
+
+/// A widget that registers for focus, and keeps whether it has focus in
+/// `focused`, once it has been told.
+pub fn make_focusable<T: Data>(
+    focused: Rc<Cell<Option<bool>>>,
+) -> ModularWidget<Rc<Cell<Option<bool>>>, T> {
+    ModularWidget::new(focused).lifecycle_fn(|focused, ctx, event, _data, _env| match event {
+        LifeCycle::WidgetAdded => ctx.register_for_focus(),
+        LifeCycle::FocusChanged(focus) => focused.set(Some(*focus)),
+        _ => (),
+    })
+}
+
+/// A 50 by 50 square that logs its paints and the clicks it handles with
+/// `tag`, and that raises itself above its siblings on [`RAISE`].
+///
+/// [`RAISE`]: constant.RAISE.html
+pub fn make_square<T: Data>(tag: u32, log: SquareLog) -> impl Widget<T> {
+    ModularWidget::new(log)
+        .event_fn(move |log, ctx, event, _data, _env| match event {
+            Event::MouseDown(_) => {
+                log.borrow_mut().push(("click", tag));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.selector == RAISE => ctx.set_z_index(1),
+            _ => (),
+        })
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain(Size::new(50.0, 50.0)))
+        .paint_fn(move |log, _ctx, _data, _env| log.borrow_mut().push(("paint", tag)))
+}
+
+/// Consume a recording, counting the records that match `f`.
+pub fn count_records(recording: &Recording, f: fn(&Record) -> bool) -> usize {
+    std::iter::from_fn(|| match recording.next() {
+        Record::None => None,
+        record => Some(record),
+    })
+    .filter(f)
+    .count()
+}
+
+/// A widget that wants to be 100x50, with the given id.
+pub fn fixed_box(id: WidgetId) -> impl Widget<()> {
+    ModularWidget::new(())
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain((100., 50.)))
+        .with_id(id)
+}
+
+/// A widget that takes focus when sent `REQUEST_FOCUS`, and registers for focus
+/// if `register` is set.
+pub fn make_focus_requester(register: bool) -> ModularWidget<(), bool> {
+    ModularWidget::new(())
+        .event_fn(|_, ctx, event, _data, _env| {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == REQUEST_FOCUS {
+                    ctx.request_focus();
+                }
+            }
+        })
+        .lifecycle_fn(move |_, ctx, event, _data, _env| {
+            if let LifeCycle::WidgetAdded = event {
+                if register {
+                    ctx.register_for_focus();
+                }
+            }
+        })
+}
+
+/// A delegate that records the commands it receives, and their targets.
+pub struct CommandRecorder(pub Rc<RefCell<Vec<(Target, u32)>>>);
+
+impl AppDelegate<bool> for CommandRecorder {
+    fn command(
+        &mut self,
+        _ctx: &mut DelegateCtx,
+        target: &Target,
+        cmd: &Command,
+        data: &mut bool,
+        _env: &Env,
+    ) -> Handled {
+        if let Ok(value) = cmd.get_object::<u32>() {
+            self.0.borrow_mut().push((*target, *value));
+            *data = true;
+        }
+        Handled::No
+    }
+}
@@ -180,3 +180,138 @@ fn flex_paint_rect_overflow() {
         assert_eq!(state.paint_rect().size(), expected_paint_rect.size());
     })
 }
+
+#[test]
+fn checkbox_size_follows_the_env() {
+    let id = WidgetId::next();
+    // the box shrinks once the checkbox is checked
+    let checkbox = Checkbox::new("Compact")
+        .env_scope(|env, checked: &bool| {
+            if *checked {
+                env.set(theme::CHECKBOX_SIZE, 10.);
+                env.set(theme::WIDGET_CONTROL_COMPONENT_PADDING, 4.);
+            }
+        })
+        .with_id(id)
+        .center();
+    Harness::create(false, checkbox, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let before = harness.get_state(id).layout_rect.size();
+
+        let center = harness.layout_rect_in_window(id).center();
+        click(harness, center);
+        assert!(*harness.data());
+        harness.just_layout();
+        let after = harness.get_state(id).layout_rect.size();
+        assert_eq!(after.width, before.width - 8. - 4.);
+    });
+}
+
+#[test]
+fn button_padding_follows_the_env() {
+    let (wide, compact) = widget_id2();
+    let widget = Flex::column()
+        .with_child(Button::new("OK", Button::noop).with_id(wide), 0.0)
+        .with_child(
+            Button::new("OK", Button::noop)
+                .with_id(compact)
+                .env_scope(|env, _| env.set(theme::BUTTON_PADDING, Insets::uniform_xy(2., 2.))),
+            0.0,
+        );
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let wide = harness.get_state(wide).layout_rect.size();
+        let compact = harness.get_state(compact).layout_rect.size();
+        // 8px on either side by default
+        assert_eq!(compact.width, wide.width - 12.);
+    });
+}
+
+#[test]
+fn window_min_size_clamps_layout() {
+    let sizes: Rc<RefCell<Vec<Size>>> = Default::default();
+    let constraints: Rc<RefCell<Vec<BoxConstraints>>> = Default::default();
+    let (sizes_2, constraints_2) = (sizes.clone(), constraints.clone());
+    let widget = ModularWidget::new(())
+        .event_fn(move |_, _ctx, event, _data, _env| {
+            if let Event::Size(size) = event {
+                sizes_2.borrow_mut().push(*size);
+            }
+        })
+        .layout_fn(move |_, _ctx, bc, _data, _env| {
+            constraints_2.borrow_mut().push(*bc);
+            bc.max()
+        });
+
+    Harness::create((), widget, |harness| {
+        harness.window_mut().min_size = Some(Size::new(200., 150.));
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // a misbehaving platform makes the window too small on one axis
+        harness.event(Event::Size(Size::new(100., 300.)));
+        harness.just_layout();
+        assert_eq!(sizes.borrow().last(), Some(&Size::new(200., 300.)));
+        let bc = *constraints.borrow().last().unwrap();
+        assert_eq!(bc.min(), Size::new(200., 300.));
+        assert_eq!(bc.max(), Size::new(200., 300.));
+
+        // and then on both
+        harness.event(Event::Size(Size::new(50., 50.)));
+        harness.just_layout();
+        assert_eq!(sizes.borrow().last(), Some(&Size::new(200., 150.)));
+        assert_eq!(
+            constraints.borrow().last().unwrap().min(),
+            Size::new(200., 150.)
+        );
+
+        // larger sizes are unchanged
+        harness.event(Event::Size(Size::new(640., 480.)));
+        assert_eq!(sizes.borrow().last(), Some(&Size::new(640., 480.)));
+    });
+}
+
+#[test]
+fn incremental_layout_defers_offscreen_widgets() {
+    const CHILDREN: usize = 12;
+    let layouts = Rc::new(RefCell::new(vec![0; CHILDREN]));
+
+    let mut column = Flex::column();
+    for i in 0..CHILDREN {
+        let layouts = layouts.clone();
+        let slow = ModularWidget::new(()).layout_fn(move |_, _, _, _, _| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            layouts.borrow_mut()[i] += 1;
+            Size::new(100., 100.)
+        });
+        column.add_child(slow, 0.0);
+    }
+    let widget = Scroll::new(column).vertical();
+
+    Harness::create((), widget, |harness| {
+        let budget = std::time::Duration::from_millis(15);
+        harness.window_mut().layout_budget = Some(budget);
+        harness.send_initial_events();
+
+        // the five children that touch the window are laid out first, even
+        // though they take longer than the budget
+        harness.just_layout();
+        assert_eq!(&layouts.borrow()[..5], &[1; 5]);
+        assert_eq!(&layouts.borrow()[5..], &[0; CHILDREN - 5]);
+        assert!(harness.window().root.state().needs_layout);
+
+        // the rest follow, a few at a time, each being laid out once
+        let mut frames = 1;
+        while harness.window().root.state().needs_layout {
+            let before = layouts.borrow().iter().sum::<usize>();
+            harness.just_layout();
+            assert!(layouts.borrow().iter().sum::<usize>() > before);
+            frames += 1;
+            assert!(frames <= CHILDREN);
+        }
+        assert!(frames > 2);
+        assert_eq!(&layouts.borrow()[..], &[1; CHILDREN]);
+    });
+}
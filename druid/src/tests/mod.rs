@@ -14,17 +14,19 @@
 
 //! Additional unit tests that cross file or module boundaries.
 
-mod harness;
-mod helpers;
+pub mod harness;
+pub mod helpers;
 mod layout_tests;
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use crate::widget::*;
 use crate::*;
 use harness::*;
 use helpers::*;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// test that the first widget to request focus during an event gets it.
 #[test]
@@ -34,9 +36,10 @@ fn propogate_hot() {
     let root_rec = Recording::default();
     let padding_rec = Recording::default();
     let button_rec = Recording::default();
+    let empty_rec = Recording::default();
 
     let widget = Split::vertical(
-        SizedBox::empty().with_id(empty),
+        SizedBox::empty().record(&empty_rec).with_id(empty),
         Button::new("hot", |_, _, _| {})
             .record(&button_rec)
             .with_id(button)
@@ -47,16 +50,11 @@ fn propogate_hot() {
     .record(&root_rec)
     .with_id(root);
 
-    fn make_mouse(x: f64, y: f64) -> MouseEvent {
-        let pos = Point::new(x, y);
-        MouseEvent {
-            pos,
-            window_pos: pos,
-            mods: KeyModifiers::default(),
-            count: 0,
-            button: MouseButton::Left,
-        }
+    fn hot(is_hot: bool) -> ExpectedRecord {
+        Record::L(LifeCycle::HotChanged(is_hot)).into()
     }
+    let moved = ExpectedRecord::mouse_moved;
+
     Harness::create((), widget, |harness| {
         harness.send_initial_events();
         harness.just_layout();
@@ -72,53 +70,70 @@ fn propogate_hot() {
         // and verifying both the widget's `is_hot` status and also that
         // each widget received the expected HotChanged messages.
 
-        harness.event(Event::MouseMoved(make_mouse(10., 10.)));
+        harness.event(Event::MouseMoved(make_mouse(Point::new(10., 10.))));
         assert!(harness.get_state(root).is_hot);
         assert!(harness.get_state(empty).is_hot);
         assert!(!harness.get_state(pad).is_hot);
 
-        assert_matches!(root_rec.next(), Record::L(LifeCycle::HotChanged(true)));
-        assert_matches!(root_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert!(root_rec.is_empty() && padding_rec.is_empty() && button_rec.is_empty());
+        root_rec.assert_sequence(&[hot(true), moved()]);
+        padding_rec.assert_sequence(&[]);
+        button_rec.assert_sequence(&[]);
 
-        harness.event(Event::MouseMoved(make_mouse(210., 10.)));
+        harness.event(Event::MouseMoved(make_mouse(Point::new(210., 10.))));
 
         assert!(harness.get_state(root).is_hot);
         assert!(!harness.get_state(empty).is_hot);
         assert!(!harness.get_state(button).is_hot);
         assert!(harness.get_state(pad).is_hot);
 
-        assert_matches!(root_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert_matches!(padding_rec.next(), Record::L(LifeCycle::HotChanged(true)));
-        assert_matches!(padding_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert!(root_rec.is_empty() && padding_rec.is_empty() && button_rec.is_empty());
+        root_rec.assert_sequence(&[moved()]);
+        padding_rec.assert_sequence(&[hot(true), moved()]);
+        button_rec.assert_sequence(&[]);
 
-        harness.event(Event::MouseMoved(make_mouse(260., 60.)));
+        harness.event(Event::MouseMoved(make_mouse(Point::new(260., 60.))));
         assert!(harness.get_state(root).is_hot);
         assert!(!harness.get_state(empty).is_hot);
         assert!(harness.get_state(button).is_hot);
         assert!(harness.get_state(pad).is_hot);
 
-        assert_matches!(root_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert_matches!(padding_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert_matches!(button_rec.next(), Record::L(LifeCycle::HotChanged(true)));
-        assert_matches!(button_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert!(root_rec.is_empty() && padding_rec.is_empty() && button_rec.is_empty());
+        root_rec.assert_sequence(&[moved()]);
+        padding_rec.assert_sequence(&[moved()]);
+        button_rec.assert_sequence(&[hot(true), moved()]);
 
-        harness.event(Event::MouseMoved(make_mouse(10., 10.)));
+        harness.event(Event::MouseMoved(make_mouse(Point::new(10., 10.))));
         assert!(harness.get_state(root).is_hot);
         assert!(harness.get_state(empty).is_hot);
         assert!(!harness.get_state(button).is_hot);
         assert!(!harness.get_state(pad).is_hot);
 
-        assert_matches!(root_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert_matches!(padding_rec.next(), Record::L(LifeCycle::HotChanged(false)));
-        assert_matches!(padding_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert_matches!(button_rec.next(), Record::L(LifeCycle::HotChanged(false)));
-        assert_matches!(button_rec.next(), Record::E(Event::MouseMoved(_)));
-        assert!(root_rec.is_empty() && padding_rec.is_empty() && button_rec.is_empty());
+        root_rec.assert_sequence(&[moved()]);
+        padding_rec.assert_sequence(&[hot(false), moved()]);
+        button_rec.assert_sequence(&[hot(false), moved()]);
+
+        // While the button is active, it captures the mouse: dragging over
+        // its sibling doesn't make the sibling hot.
+        harness.event(Event::MouseMoved(make_mouse(Point::new(260., 60.))));
+        harness.event(Event::MouseDown(make_mouse(Point::new(260., 60.))));
+        root_rec.clear();
+        padding_rec.clear();
+        button_rec.clear();
+        empty_rec.clear();
+
+        harness.event(Event::MouseMoved(make_mouse(Point::new(10., 10.))));
+        assert!(harness.get_state(button).is_active);
+        assert!(!harness.get_state(button).is_hot);
+        assert!(!harness.get_state(empty).is_hot);
+        button_rec.assert_sequence(&[hot(false), moved()]);
+        empty_rec.assert_sequence(&[]);
+
+        // Releasing it makes the widget under the mouse hot.
+        harness.event(Event::MouseUp(make_mouse(Point::new(10., 10.))));
+        assert!(!harness.get_state(button).is_active);
+        assert!(harness.get_state(empty).is_hot);
+        empty_rec.assert_sequence(&[hot(true), moved()]);
     });
 }
+
 #[test]
 fn take_focus() {
     const TAKE_FOCUS: Selector = Selector::new("druid-tests.take-focus");
@@ -160,7 +175,7 @@ fn take_focus() {
         assert!(right_focus.get().is_none());
 
         // this is sent to all widgets; the first widget to request focus should get it
-        harness.submit_command(TAKE_FOCUS, None);
+        harness.submit_command(TAKE_FOCUS, Target::Auto);
         assert_eq!(harness.window().focus, Some(id_1));
         assert_eq!(left_focus.get(), Some(true));
         assert_eq!(right_focus.get(), None);
@@ -179,10 +194,11 @@ fn simple_lifecyle() {
     let widget = SizedBox::empty().record(&record);
     Harness::create(true, widget, |harness| {
         harness.send_initial_events();
-        assert_matches!(record.next(), Record::L(LifeCycle::WidgetAdded));
-        assert_matches!(record.next(), Record::E(Event::WindowConnected));
-        assert_matches!(record.next(), Record::E(Event::Size(_)));
-        assert!(record.is_empty());
+        record.assert_sequence(&[
+            Record::L(LifeCycle::WidgetAdded).into(),
+            Record::E(Event::WindowConnected).into(),
+            Record::E(Event::Size(Size::new(400., 400.))).into(),
+        ]);
     })
 }
 
@@ -203,18 +219,16 @@ fn adding_child_lifecycle() {
     Harness::create(String::new(), widget, |harness| {
         harness.send_initial_events();
 
-        assert_matches!(record.next(), Record::L(LifeCycle::WidgetAdded));
-        assert_matches!(record.next(), Record::E(Event::WindowConnected));
-        assert!(record.is_empty());
-
-        assert!(record_new_child.is_empty());
+        record.assert_sequence(&[
+            Record::L(LifeCycle::WidgetAdded).into(),
+            Record::E(Event::WindowConnected).into(),
+        ]);
+        record_new_child.assert_sequence(&[]);
 
-        harness.submit_command(REPLACE_CHILD, None);
-
-        assert_matches!(record.next(), Record::E(Event::Command(_)));
+        harness.submit_command(REPLACE_CHILD, Target::Auto);
 
-        assert_matches!(record_new_child.next(), Record::L(LifeCycle::WidgetAdded));
-        assert!(record_new_child.is_empty());
+        record.assert_sequence(&[Record::E(Event::Command(REPLACE_CHILD.into())).into()]);
+        record_new_child.assert_sequence(&[Record::L(LifeCycle::WidgetAdded).into()]);
     })
 }
 
@@ -246,7 +260,7 @@ fn participate_in_autofocus() {
         assert_eq!(harness.window().focus_chain(), &[id_1, id_2, id_3, id_4]);
 
         // tell the replacer widget to swap its children
-        harness.submit_command(REPLACE_CHILD, None);
+        harness.submit_command(REPLACE_CHILD, Target::Auto);
 
         // verify that the two new children are registered for focus.
         assert_eq!(
@@ -289,10 +303,15 @@ fn child_tracking() {
 #[test]
 /// Test that all children are registered correctly after a child is replaced.
 fn register_after_adding_child() {
-    let (id_1, id_2, id_3, id_4, id_5, id_6) = widget_id6();
-    let id_7 = WidgetId::next();
+    // the ids are fixed, so that whether the children set gives a false
+    // positive for a removed id doesn't change from run to run
+    let id = WidgetId::reserved;
+    let (id_1, id_2, id_3, id_4, id_5, id_6, id_7) =
+        (id(1), id(2), id(3), id(4), id(5), id(6), id(7));
+    let removed = Recording::default();
 
-    let replacer = ReplaceChild::new(TextBox::new().with_id(id_1), move || {
+    let replaced = TextBox::new().record(&removed).with_id(id_1);
+    let replacer = ReplaceChild::new(replaced, move || {
         Split::vertical(TextBox::new().with_id(id_2), TextBox::new().with_id(id_3)).with_id(id_7)
     })
     .with_id(id_6);
@@ -307,7 +326,7 @@ fn register_after_adding_child() {
         assert!(harness.get_state(id_5).children.contains(&id_4));
         assert_eq!(harness.get_state(id_5).children.entry_count(), 3);
 
-        harness.submit_command(REPLACE_CHILD, None);
+        harness.submit_command(REPLACE_CHILD, Target::Auto);
 
         assert!(harness.get_state(id_5).children.contains(&id_6));
         assert!(harness.get_state(id_5).children.contains(&id_4));
@@ -315,5 +334,2682 @@ fn register_after_adding_child() {
         assert!(harness.get_state(id_5).children.contains(&id_2));
         assert!(harness.get_state(id_5).children.contains(&id_3));
         assert_eq!(harness.get_state(id_5).children.entry_count(), 5);
+        assert!(!harness.get_state(id_5).children.contains(&id_1));
+        assert!(!harness.get_state(id_6).children.contains(&id_1));
+
+        let mut last = Record::None;
+        while !removed.is_empty() {
+            last = removed.next();
+        }
+        assert_eq!(last, Record::L(LifeCycle::WidgetRemoved));
+    })
+}
+
+#[test]
+fn try_create_runs_the_test() {
+    let id = WidgetId::next();
+    let mut size = None;
+    let result = Harness::try_create(
+        (),
+        SizedBox::empty()
+            .width(20.)
+            .height(10.)
+            .with_id(id)
+            .center(),
+        |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            size = Some(harness.get_state(id).layout_rect.size());
+        },
+    );
+    assert!(result.is_ok());
+    assert_eq!(size, Some(Size::new(20., 10.)));
+}
+
+#[test]
+fn tab_focus_traversal() {
+    fn make_tab(shift: bool) -> Event {
+        let mods = KeyModifiers {
+            shift,
+            ..Default::default()
+        };
+        Event::KeyDown(KeyEvent::for_test(mods, "\t", Code::Tab))
+    }
+
+    let (id_1, id_2, id_3) = widget_id3();
+    let focus_1: Rc<Cell<Option<bool>>> = Default::default();
+    let focus_2: Rc<Cell<Option<bool>>> = Default::default();
+    let focus_3: Rc<Cell<Option<bool>>> = Default::default();
+
+    let widget = Flex::row()
+        .with_child(make_focusable(focus_1.clone()).with_id(id_1), 1.0)
+        .with_child(make_focusable(focus_2.clone()).with_id(id_2), 1.0)
+        .with_child(make_focusable(focus_3.clone()).with_id(id_3), 1.0);
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        assert_eq!(harness.window().focus_chain(), &[id_1, id_2, id_3]);
+        assert_eq!(harness.window().focus, None);
+
+        // with nothing focused, tab focuses the first widget in the chain
+        harness.event(make_tab(false));
+        assert_eq!(harness.window().focus, Some(id_1));
+        assert_eq!(focus_1.get(), Some(true));
+
+        harness.event(make_tab(false));
+        assert_eq!(harness.window().focus, Some(id_2));
+        assert_eq!(focus_1.get(), Some(false));
+        assert_eq!(focus_2.get(), Some(true));
+
+        harness.event(make_tab(false));
+        assert_eq!(harness.window().focus, Some(id_3));
+
+        // wraps around at the end
+        harness.event(make_tab(false));
+        assert_eq!(harness.window().focus, Some(id_1));
+        assert_eq!(focus_3.get(), Some(false));
+        assert_eq!(focus_1.get(), Some(true));
+
+        // shift-tab goes backwards, also wrapping
+        harness.event(make_tab(true));
+        assert_eq!(harness.window().focus, Some(id_3));
+        harness.event(make_tab(true));
+        assert_eq!(harness.window().focus, Some(id_2));
+    })
+}
+
+#[test]
+fn tab_handled_by_widget() {
+    let id_1 = WidgetId::next();
+    let id_2 = WidgetId::next();
+
+    // this widget wants to keep tabs for itself
+    let greedy: ModularWidget<(), bool> = ModularWidget::new(())
+        .event_fn(|_, ctx, event, _data, _env| {
+            if let Event::KeyDown(_) = event {
+                ctx.set_handled();
+            }
+        })
+        .lifecycle_fn(|_, ctx, event, _data, _env| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.register_for_focus();
+            }
+        });
+    let other: ModularWidget<(), bool> =
+        ModularWidget::new(()).lifecycle_fn(|_, ctx, event, _data, _env| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.register_for_focus();
+            }
+        });
+
+    let widget = Split::vertical(greedy.with_id(id_1), other.with_id(id_2));
+    let tab = Event::KeyDown(KeyEvent::for_test(KeyModifiers::default(), "\t", Code::Tab));
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        harness.event(tab.clone());
+        assert_eq!(harness.window().focus, Some(id_1));
+
+        // the focused widget handles the tab, so focus stays put
+        harness.event(tab.clone());
+        assert_eq!(harness.window().focus, Some(id_1));
+    })
+}
+
+/// A zoomable canvas, nested in padding and a scroll, keeps the content
+/// point under the mouse in place when it zooms about `local_anchor`.
+#[test]
+fn zoom_anchor_is_local() {
+    fn zoom(delta: f64) -> Event {
+        Event::Zoom(ZoomEvent {
+            delta,
+            window_anchor: Point::ZERO,
+            local_anchor: Point::ZERO,
+        })
+    }
+
+    /// The anchor, and the content point under it before and after a zoom.
+    type Record = (Point, Point, Point);
+
+    struct Canvas {
+        scale: f64,
+        pan: Vec2,
+        zooms: Rc<RefCell<Vec<Record>>>,
+        wheels: Rc<RefCell<Vec<Point>>>,
+    }
+
+    impl Canvas {
+        fn content_point(&self, local: Point) -> Point {
+            ((local - self.pan).to_vec2() / self.scale).to_point()
+        }
+    }
+
+    let zooms = Rc::new(RefCell::new(Vec::new()));
+    let wheels = Rc::new(RefCell::new(Vec::new()));
+    let canvas = Canvas {
+        scale: 1.0,
+        pan: Vec2::new(5., 5.),
+        zooms: zooms.clone(),
+        wheels: wheels.clone(),
+    };
+
+    let canvas = ModularWidget::new(canvas)
+        .event_fn(|canvas, _ctx, event, _data: &mut (), _env| match event {
+            Event::Zoom(zoom) => {
+                let anchor = zoom.local_anchor;
+                let before = canvas.content_point(anchor);
+                canvas.scale *= 1.0 + zoom.delta;
+                canvas.pan = anchor.to_vec2() - before.to_vec2() * canvas.scale;
+                let after = canvas.content_point(anchor);
+                canvas.zooms.borrow_mut().push((anchor, before, after));
+            }
+            Event::Wheel(wheel) => canvas.wheels.borrow_mut().push(wheel.local_anchor),
+            _ => (),
+        })
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain(Size::new(1000., 1000.)));
+
+    let widget = Scroll::new(canvas.padding(20.)).padding(10.);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        let window_pos = Point::new(110., 110.);
+        harness.event(Event::MouseMoved(make_mouse(window_pos)));
+        harness.event(zoom(0.5));
+
+        // the canvas ignores the wheel, so the scroll scrolls
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., 50.),
+            mods: KeyModifiers::default(),
+            window_anchor: Point::ZERO,
+            local_anchor: Point::ZERO,
+            phase: ScrollPhase::Changed,
+            momentum_phase: None,
+        }));
+        harness.event(zoom(1.0));
+
+        assert_eq!(*wheels.borrow(), vec![Point::new(80., 80.)]);
+
+        let zooms = zooms.borrow();
+        assert_eq!(zooms.len(), 2);
+        assert_eq!(zooms[0].0, Point::new(80., 80.));
+        assert_eq!(zooms[1].0, Point::new(80., 130.));
+        for (_, before, after) in zooms.iter() {
+            assert!((*after - *before).hypot() < 1e-9);
+        }
+    })
+}
+
+/// A `Zoom` follows the same hot-widget routing as a `Wheel`.
+#[test]
+fn zoom_goes_to_hot_widgets() {
+    let (button, pad, root, empty) = widget_id4();
+
+    let root_rec = Recording::default();
+    let padding_rec = Recording::default();
+    let button_rec = Recording::default();
+    let empty_rec = Recording::default();
+
+    let widget = Split::vertical(
+        SizedBox::empty().record(&empty_rec).with_id(empty),
+        Button::new("hot", |_, _, _| {})
+            .record(&button_rec)
+            .with_id(button)
+            .padding(50.)
+            .record(&padding_rec)
+            .with_id(pad),
+    )
+    .record(&root_rec)
+    .with_id(root);
+
+    fn zoom() -> Event {
+        Event::Zoom(ZoomEvent {
+            delta: 0.1,
+            window_anchor: Point::ZERO,
+            local_anchor: Point::ZERO,
+        })
+    }
+
+    fn clear(recordings: &[&Recording]) {
+        recordings.iter().for_each(|rec| rec.clear());
+    }
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let all = [&root_rec, &padding_rec, &button_rec, &empty_rec];
+
+        // over the padding, but not the button
+        harness.event(Event::MouseMoved(make_mouse(Point::new(210., 10.))));
+        clear(&all);
+        harness.event(zoom());
+        assert_matches!(root_rec.next(), Record::E(Event::Zoom(_)));
+        assert_matches!(padding_rec.next(), Record::E(Event::Zoom(_)));
+        assert!(button_rec.is_empty() && empty_rec.is_empty());
+
+        // over the button
+        harness.event(Event::MouseMoved(make_mouse(Point::new(260., 60.))));
+        clear(&all);
+        harness.event(zoom());
+        assert_matches!(root_rec.next(), Record::E(Event::Zoom(_)));
+        assert_matches!(padding_rec.next(), Record::E(Event::Zoom(_)));
+        assert_matches!(button_rec.next(), Record::E(Event::Zoom(_)));
+        assert!(empty_rec.is_empty());
+
+        // over the empty side
+        harness.event(Event::MouseMoved(make_mouse(Point::new(10., 10.))));
+        clear(&all);
+        harness.event(zoom());
+        assert_matches!(root_rec.next(), Record::E(Event::Zoom(_)));
+        assert_matches!(empty_rec.next(), Record::E(Event::Zoom(_)));
+        assert!(padding_rec.is_empty() && button_rec.is_empty());
+    });
+}
+
+#[test]
+fn context_menu_commands_go_to_opener() {
+    const CHOSEN: Selector = Selector::new("druid-tests.context-menu-chosen");
+
+    fn make_menu() -> MenuDesc<bool> {
+        MenuDesc::empty()
+            .append(MenuItem::new(LocalizedString::new("Always"), CHOSEN))
+            .append(
+                MenuItem::new(LocalizedString::new("Sometimes"), CHOSEN)
+                    .disabled_when(|enabled: &bool, _| !*enabled),
+            )
+    }
+
+    /// Opens the menu on a right click, and counts the commands it gets.
+    fn make_opener(chosen: Rc<Cell<usize>>) -> impl Widget<bool> {
+        ModularWidget::new(chosen).event_fn(
+            |chosen, ctx, event, _data: &mut bool, _env| match event {
+                Event::MouseDown(mouse) if mouse.button.is_right() => {
+                    ctx.show_context_menu(ContextMenu::new(make_menu(), mouse.window_pos));
+                }
+                Event::Command(cmd) if cmd.selector == CHOSEN => chosen.set(chosen.get() + 1),
+                _ => (),
+            },
+        )
+    }
+
+    fn right_click(harness: &mut Harness<bool>, pos: Point) {
+        let mouse = MouseEvent {
+            button: MouseButton::Right,
+            ..make_mouse(pos)
+        };
+        harness.event(Event::MouseMoved(mouse.clone()));
+        harness.event(Event::MouseDown(mouse));
+    }
+
+    let left_chosen = Rc::new(Cell::new(0));
+    let right_chosen = Rc::new(Cell::new(0));
+    let widget = Flex::row()
+        .with_child(make_opener(left_chosen.clone()), 0.0)
+        .with_child(make_opener(right_chosen.clone()), 0.0);
+
+    Harness::create(false, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        right_click(harness, Point::new(150., 50.));
+
+        harness.choose_context_menu_item(0);
+        assert_eq!(left_chosen.get(), 0);
+        assert_eq!(right_chosen.get(), 1);
+
+        // the disabled item does nothing
+        harness.choose_context_menu_item(1);
+        assert_eq!(right_chosen.get(), 1);
+    });
+
+    Harness::create(true, make_opener(left_chosen.clone()), |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        right_click(harness, Point::new(50., 50.));
+
+        // enabled by the data when the menu was shown
+        harness.choose_context_menu_item(1);
+        assert_eq!(left_chosen.get(), 1);
+    });
+}
+
+/// How far apart a channel of the two backends can be before the pixel
+/// counts as different; antialiasing differs between rasterizers.
+const CHANNEL_TOLERANCE: i16 = 48;
+
+#[test]
+fn backends_paint_a_widget_tree_alike() {
+    let widget = Flex::column()
+        .with_child(Label::new("Reference"), 0.0)
+        .with_child(Button::new("Press me", |_, _, _| {}), 0.0)
+        .with_child(Slider::new(), 0.0)
+        .with_child(ProgressBar::new(), 0.0)
+        .padding(10.0);
+
+    Harness::create(0.5, widget, |harness| {
+        harness.set_initial_size(Size::new(200., 150.));
+        harness.send_initial_events();
+        let hardware = harness.render_with_backend(RenderBackend::Hardware);
+        let software = harness.render_with_backend(RenderBackend::Software);
+        let (hardware, software) = match (hardware, software) {
+            (Ok(hardware), Ok(software)) => (hardware, software),
+            (hardware, software) => {
+                eprintln!(
+                    "skipping the comparison, a backend can't paint here: {:?}, {:?}",
+                    hardware.err(),
+                    software.err()
+                );
+                return;
+            }
+        };
+        assert_eq!(hardware.len(), 200 * 150 * 4);
+        assert_eq!(hardware.len(), software.len());
+
+        let pixels = hardware.chunks(4).zip(software.chunks(4));
+        let different = pixels
+            .filter(|(hardware, software)| {
+                let mut channels = hardware.iter().zip(software.iter());
+                channels.any(|(h, s)| (*h as i16 - *s as i16).abs() > CHANNEL_TOLERANCE)
+            })
+            .count();
+        // the edges of text and shapes may differ, but not the widgets
+        assert!(
+            different * 100 < hardware.len() / 4,
+            "{} of the pixels differ",
+            different
+        );
+        // and something was painted over the background
+        let background = &hardware[..4];
+        assert!(hardware.chunks(4).any(|pixel| pixel != background));
+    });
+}
+
+#[test]
+fn user_scale_zooms_content() {
+    let (root, boxed) = widget_id2();
+    let widget = Align::new(
+        UnitPoint::TOP_LEFT,
+        SizedBox::empty().width(100.).height(50.).with_id(boxed),
+    )
+    .with_id(root);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let window = Target::Window(harness.window().id);
+        let original = harness.get_state(boxed).layout_rect;
+        assert_eq!(
+            harness.get_state(root).layout_rect.size(),
+            Size::new(400., 400.)
+        );
+
+        // in window points, the box at (0, 0, 100, 50) doubles
+        harness.submit_command(Command::new(commands::SET_USER_SCALE, 2.0), window);
+        harness.just_layout();
+        let scale = harness.window().user_scale();
+        assert_eq!(scale, 2.0);
+        assert_eq!(
+            harness.get_state(root).layout_rect.size(),
+            Size::new(200., 200.)
+        );
+        let rect = harness.get_state(boxed).layout_rect;
+        assert_eq!(
+            (rect.origin(), rect.size()),
+            (original.origin(), original.size())
+        );
+        assert_eq!(original.size() * scale, Size::new(200., 100.));
+
+        // hit-testing follows the scale
+        harness.event(Event::MouseMoved(make_mouse(Point::new(150., 80.))));
+        assert!(harness.get_state(boxed).is_hot);
+        harness.event(Event::MouseMoved(make_mouse(Point::new(210., 80.))));
+        assert!(!harness.get_state(boxed).is_hot);
+
+        // the scale is clamped
+        harness.submit_command(Command::new(commands::SET_USER_SCALE, 100.0), window);
+        assert!(harness.window().user_scale() < 100.);
+
+        // the shortcuts zoom, unless a widget handles them
+        let mods = KeyModifiers {
+            ctrl: !cfg!(target_os = "macos"),
+            meta: cfg!(target_os = "macos"),
+            ..Default::default()
+        };
+        harness.event(Event::KeyDown(KeyEvent::for_test(mods, "0", Code::Key0)));
+        assert_eq!(harness.window().user_scale(), 1.0);
+        harness.event(Event::KeyDown(KeyEvent::for_test(mods, "=", Code::Equals)));
+        assert!(harness.window().user_scale() > 1.0);
+        harness.submit_command(commands::ZOOM_OUT, window);
+        harness.submit_command(commands::ZOOM_OUT, window);
+        assert!(harness.window().user_scale() < 1.0);
+
+        // resetting restores the original metrics exactly
+        harness.submit_command(commands::ZOOM_RESET, window);
+        harness.just_layout();
+        assert_eq!(harness.window().user_scale(), 1.0);
+        assert_eq!(
+            harness.get_state(root).layout_rect.size(),
+            Size::new(400., 400.)
+        );
+        let rect = harness.get_state(boxed).layout_rect;
+        assert_eq!(
+            (rect.origin(), rect.size()),
+            (original.origin(), original.size())
+        );
+        harness.event(Event::MouseMoved(make_mouse(Point::new(150., 80.))));
+        assert!(!harness.get_state(boxed).is_hot);
+    })
+}
+
+#[test]
+fn ext_commands_reach_delegate_before_any_window() {
+    use crate::ext_event::ExtEventHost;
+    use crate::win_handler::{AppHandler, AppState, EXT_EVENT_IDLE_TOKEN};
+
+    const LOAD_CONFIG: Selector = Selector::new("druid-tests.load-config");
+
+    // submitted during startup, before the app has a way to be woken
+    let host = ExtEventHost::new();
+    let sink = host.make_sink();
+    sink.submit_command(LOAD_CONFIG, 1u32, Target::Auto)
+        .unwrap();
+    sink.submit_command(LOAD_CONFIG, 2u32, Target::Global)
+        .unwrap();
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let delegate = CommandRecorder(received.clone());
+    let state = AppState::new(false, theme::init(), Some(Box::new(delegate)), host);
+    state.set_startup_pending();
+
+    // the app handler delivers them, with no window open
+    let mut handler = AppHandler::new(state.clone());
+    crate::shell::AppHandler::idle(&mut handler, EXT_EVENT_IDLE_TOKEN);
+    assert_eq!(
+        *received.borrow(),
+        vec![(Target::Global, 1), (Target::Global, 2)]
+    );
+    assert!(state.data());
+
+    // later submissions are delivered in order too
+    sink.submit_command(LOAD_CONFIG, 3u32, Target::Auto)
+        .unwrap();
+    crate::shell::AppHandler::idle(&mut handler, EXT_EVENT_IDLE_TOKEN);
+    assert_eq!(received.borrow().last(), Some(&(Target::Global, 3)));
+}
+
+// on macOS the app always keeps running without windows
+#[cfg(not(all(target_os = "macos", not(feature = "use_gtk"))))]
+#[test]
+fn startup_keeps_app_alive() {
+    use crate::ext_event::ExtEventHost;
+    use crate::shell::WinHandler;
+    use crate::win_handler::AppState;
+
+    let state = AppState::new(0u32, theme::init(), None, ExtEventHost::new());
+    assert!(!state.keep_alive());
+
+    // launched without windows, the app waits for its first one
+    state.set_startup_pending();
+    assert!(state.keep_alive());
+
+    let desc = WindowDesc::new(SizedBox::empty);
+    let id = desc.id;
+    state.add_window(id, desc);
+    assert!(state.keep_alive());
+
+    // once it connects, closing the last window quits
+    let mut handler = DruidHandler::new_shared(state.clone(), id);
+    handler.connect(&WindowHandle::default());
+    assert!(!state.keep_alive());
+}
+
+#[test]
+fn debug_paint_flags_reach_descendants() {
+    type Log = Rc<RefCell<Vec<(WidgetId, bool, bool)>>>;
+
+    /// Logs its id, and the debug flags it is painted with.
+    fn leaf(id: WidgetId, painted: Log, laid_out: Rc<RefCell<Vec<WidgetId>>>) -> impl Widget<()> {
+        ModularWidget::new(())
+            .layout_fn(move |_, ctx, bc, _, _| {
+                laid_out.borrow_mut().push(ctx.widget_id());
+                bc.constrain(Size::new(10., 10.))
+            })
+            .paint_fn(move |_, ctx, _, env| {
+                let flags = (env.get(Env::DEBUG_PAINT), env.get(Env::DEBUG_WIDGET_ID));
+                painted
+                    .borrow_mut()
+                    .push((ctx.widget_id(), flags.0, flags.1));
+            })
+            .with_id(id)
+    }
+
+    let (id_1, id_2, id_3, id_4) = widget_id4();
+    let painted = Log::default();
+    let laid_out = Rc::new(RefCell::new(Vec::new()));
+    let widget = Flex::row()
+        .with_child(
+            Flex::column()
+                .with_child(leaf(id_1, painted.clone(), laid_out.clone()), 0.0)
+                .with_child(leaf(id_2, painted.clone(), laid_out.clone()), 0.0)
+                .debug_paint_layout(),
+            0.0,
+        )
+        .with_child(
+            leaf(id_3, painted.clone(), laid_out.clone()).debug_widget_id(),
+            0.0,
+        )
+        .with_child(leaf(id_4, painted.clone(), laid_out.clone()), 0.0);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        assert_eq!(&*laid_out.borrow(), &[id_1, id_2, id_3, id_4]);
+        harness.paint();
+    });
+
+    assert_eq!(
+        &*painted.borrow(),
+        &[
+            (id_1, true, false),
+            (id_2, true, false),
+            (id_3, false, true),
+            (id_4, false, false),
+        ]
+    );
+    assert!(id_1.to_raw() < id_2.to_raw());
+}
+
+#[test]
+fn unregistered_focus_warns() {
+    let (id_1, id_2) = widget_id2();
+    let widget = Split::vertical(
+        make_focus_requester(true).with_id(id_1),
+        make_focus_requester(false).with_id(id_2),
+    );
+    crate::core::UNREGISTERED_FOCUS_WARNINGS.with(|warnings| warnings.borrow_mut().clear());
+    let warnings =
+        || crate::core::UNREGISTERED_FOCUS_WARNINGS.with(|warnings| warnings.borrow().clone());
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(REQUEST_FOCUS, id_1);
+        assert_eq!(harness.window().focus, Some(id_1));
+        assert!(warnings().is_empty());
+
+        // the widget still gets focus, but with a warning
+        harness.submit_command(REQUEST_FOCUS, id_2);
+        assert_eq!(harness.window().focus, Some(id_2));
+        let warnings = warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].0.contains("ModularWidget"));
+        assert_eq!(warnings[0].1, id_2);
+    })
+}
+
+#[test]
+fn tab_from_unregistered_focus() {
+    let (id_1, id_2, id_3, id_4) = widget_id4();
+    let tab = |shift| {
+        let mods = KeyModifiers {
+            shift,
+            ..Default::default()
+        };
+        Event::KeyDown(KeyEvent::for_test(mods, "\t", Code::Tab))
+    };
+
+    let widget = Flex::row()
+        .with_child(make_focus_requester(false).with_id(id_4), 1.0)
+        .with_child(make_focus_requester(true).with_id(id_1), 1.0)
+        .with_child(make_focus_requester(false).with_id(id_2), 1.0)
+        .with_child(make_focus_requester(true).with_id(id_3), 1.0);
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        assert_eq!(harness.window().focus_chain(), &[id_1, id_3]);
+
+        // tab moves on from the unregistered widget's place in the tree
+        harness.submit_command(REQUEST_FOCUS, id_2);
+        harness.event(tab(false));
+        assert_eq!(harness.window().focus, Some(id_3));
+
+        harness.submit_command(REQUEST_FOCUS, id_2);
+        harness.event(tab(true));
+        assert_eq!(harness.window().focus, Some(id_1));
+
+        // before the whole chain, tab goes to the start and shift-tab to the end
+        harness.submit_command(REQUEST_FOCUS, id_4);
+        harness.event(tab(false));
+        assert_eq!(harness.window().focus, Some(id_1));
+
+        harness.submit_command(REQUEST_FOCUS, id_4);
+        harness.event(tab(true));
+        assert_eq!(harness.window().focus, Some(id_3));
     })
 }
+
+#[test]
+fn overlapping_children_stacking_order() {
+    // the squares overlap between 30 and 50
+    let overlap = Point::new(40.0, 25.0);
+    let log: SquareLog = Default::default();
+    let declared = Flex::row()
+        .with_child(make_square(1, log.clone()), 0.0)
+        .with_spacer(-20.0)
+        .with_child(make_square(2, log.clone()), 0.0);
+    let layered = Flex::row()
+        .with_layered_child(make_square(1, log.clone()), 0.0, 1)
+        .with_spacer(-20.0)
+        .with_child(make_square(2, log.clone()), 0.0);
+
+    // later children are on top
+    Harness::create(true, declared, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.paint();
+        assert_eq!(log.replace(Vec::new()), vec![("paint", 1), ("paint", 2)]);
+        click(harness, overlap);
+        assert_eq!(log.replace(Vec::new()), vec![("click", 2)]);
+    });
+
+    // the z-index flips both the paint order and the click routing
+    Harness::create(true, layered, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.paint();
+        assert_eq!(log.replace(Vec::new()), vec![("paint", 2), ("paint", 1)]);
+        click(harness, overlap);
+        assert_eq!(log.replace(Vec::new()), vec![("click", 1)]);
+    });
+}
+
+#[test]
+fn size_changes_are_notified() {
+    /// The sizes in the `LifeCycle::Size` events, and what `ctx.size()`
+    /// returned when each was handled.
+    type Sizes = Rc<RefCell<Vec<(Size, Size)>>>;
+
+    fn size_recorder(sizes: &Sizes) -> impl Widget<()> {
+        let sizes = sizes.clone();
+        ModularWidget::new(())
+            .lifecycle_fn(move |_, ctx, event, _, _| {
+                if let LifeCycle::Size(size) = event {
+                    sizes.borrow_mut().push((*size, ctx.size()));
+                }
+            })
+            .layout_fn(|_, _, bc, _, _| bc.max())
+    }
+
+    fn take(sizes: &Sizes) -> Vec<Size> {
+        sizes
+            .borrow_mut()
+            .drain(..)
+            .map(|(event_size, ctx_size)| {
+                assert_eq!(event_size, ctx_size);
+                event_size
+            })
+            .collect()
+    }
+
+    let flexible: Sizes = Default::default();
+    let fixed: Sizes = Default::default();
+    let widget = Flex::row()
+        .with_child(size_recorder(&flexible), 1.0)
+        .with_child(size_recorder(&fixed).fix_width(50.).fix_height(50.), 0.0);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        // the first layout gives both of them a size
+        assert_eq!(take(&flexible), vec![Size::new(350., 400.)]);
+        assert_eq!(take(&fixed), vec![Size::new(50., 50.)]);
+
+        // laying out again at the same size sends nothing
+        harness.just_layout();
+        assert!(take(&flexible).is_empty());
+
+        // after a resize, only the widget whose size changed is told
+        harness.event(Event::Size(Size::new(300., 400.)));
+        harness.just_layout();
+        assert_eq!(take(&flexible), vec![Size::new(250., 400.)]);
+        assert!(take(&fixed).is_empty());
+    });
+}
+
+#[test]
+fn background_color_resolution() {
+    fn platform_rgba<T: Data>(harness: &Harness<T>) -> Option<u32> {
+        let window = harness.window();
+        window.platform_background.as_ref().map(Color::as_rgba_u32)
+    }
+
+    let red = Color::rgb8(0xff, 0, 0);
+    let green = Color::rgb8(0, 0xff, 0);
+    let blue = Color::rgb8(0, 0, 0xff);
+
+    Harness::create((), SizedBox::empty(), |harness| {
+        harness.configure_env(|env, _| env.set(theme::WINDOW_BACKGROUND_COLOR, red.clone()));
+        harness.send_initial_events();
+        let window = Target::Window(harness.window().id);
+
+        // without an override, the color comes from the env
+        assert_eq!(platform_rgba(harness), Some(red.as_rgba_u32()));
+
+        // the desc takes precedence over the env
+        harness.window_mut().desc_background = Some(green.clone());
+        harness.event(Event::WindowConnected);
+        assert_eq!(platform_rgba(harness), Some(green.as_rgba_u32()));
+
+        // and the command over the desc
+        let cmd = Command::new(commands::SET_BACKGROUND_COLOR, Some(blue.clone()));
+        harness.submit_command(cmd, window);
+        assert_eq!(platform_rgba(harness), Some(blue.as_rgba_u32()));
+
+        // changing the env doesn't undo the command
+        harness.configure_env(|env, _| env.set(theme::WINDOW_BACKGROUND_COLOR, Color::BLACK));
+        harness.event(Event::WindowConnected);
+        assert_eq!(platform_rgba(harness), Some(blue.as_rgba_u32()));
+
+        // resetting goes back to the desc
+        let cmd = Command::new(commands::SET_BACKGROUND_COLOR, None::<Color>);
+        harness.submit_command(cmd, window);
+        assert_eq!(platform_rgba(harness), Some(green.as_rgba_u32()));
+    });
+}
+
+#[test]
+fn scale_changes_reach_widgets() {
+    let scales = Rc::new(RefCell::new(Vec::new()));
+    let layout_scales = scales.clone();
+    let events = Rc::new(Cell::new(0));
+    let seen = events.clone();
+    let widget = ModularWidget::new(())
+        .event_fn(move |_, _, event, _, _| {
+            if let Event::WindowScale(_) = event {
+                seen.set(seen.get() + 1);
+            }
+        })
+        .layout_fn(move |_, ctx, bc, _, _| {
+            layout_scales.borrow_mut().push(ctx.scale());
+            bc.max()
+        });
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        assert_eq!(scales.borrow().last(), Some(&Scale::IDENTITY));
+
+        harness.event(Event::WindowScale(Scale::new(2.0, 2.0)));
+        assert_eq!(events.get(), 1);
+        harness.just_layout();
+        assert_eq!(scales.borrow().last(), Some(&Scale::new(2.0, 2.0)));
+
+        // the content scale is included
+        let window = Target::Window(harness.window().id);
+        harness.submit_command(Command::new(commands::SET_USER_SCALE, 1.5), window);
+        harness.just_layout();
+        assert_eq!(scales.borrow().last(), Some(&Scale::new(3.0, 3.0)));
+    });
+}
+
+#[test]
+fn disabled_widgets_skip_focus_and_input() {
+    const ENABLE: Selector = Selector::new("druid-tests.enable");
+
+    /// Changes the data so that nothing is disabled when sent `ENABLE`.
+    struct Enable;
+
+    impl<W: Widget<String>> Controller<String, W> for Enable {
+        fn event(
+            &mut self,
+            child: &mut W,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut String,
+            env: &Env,
+        ) {
+            match event {
+                Event::Command(cmd) if cmd.selector == ENABLE => *data = "enabled".into(),
+                _ => child.event(ctx, event, data, env),
+            }
+        }
+    }
+
+    let (id_1, id_2, id_3, id_4, id_5, id_6) = widget_id6();
+    let record = Recording::default();
+
+    let disabled = Split::vertical(TextBox::new().with_id(id_4), TextBox::new().with_id(id_5))
+        .record(&record)
+        .disabled_if(|data: &String, _| data == "disabled")
+        .with_id(id_6);
+
+    let widget = Split::vertical(
+        Flex::row()
+            .with_child(TextBox::new().with_id(id_1), 1.0)
+            .with_child(TextBox::new().with_id(id_2), 1.0)
+            .with_child(TextBox::new().with_id(id_3), 1.0),
+        disabled,
+    )
+    .controller(Enable);
+
+    let has_mouse_events = |record: &Recording| {
+        let mut found = false;
+        while !record.is_empty() {
+            if let Record::E(Event::MouseDown(_)) | Record::E(Event::MouseUp(_)) = record.next() {
+                found = true;
+            }
+        }
+        found
+    };
+
+    Harness::create("disabled".to_string(), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // the disabled branch, and everything in it, is out of the focus chain
+        assert_eq!(harness.window().focus_chain(), &[id_1, id_2, id_3]);
+        assert!(harness.get_state(id_6).is_disabled());
+        assert!(harness.get_state(id_4).is_disabled());
+        assert!(!harness.get_state(id_1).is_disabled());
+
+        record.clear();
+        click(harness, Point::new(300., 10.));
+        assert!(!has_mouse_events(&record));
+
+        harness.submit_command(ENABLE, Target::Auto);
+        assert_eq!(
+            harness.window().focus_chain(),
+            &[id_1, id_2, id_3, id_4, id_5]
+        );
+        assert!(!harness.get_state(id_4).is_disabled());
+
+        click(harness, Point::new(300., 10.));
+        assert!(has_mouse_events(&record));
+    })
+}
+
+/// Paint a checkbox without a label in both states, and compare it with the
+/// snapshots; run with `DRUID_UPDATE_SNAPSHOTS=1` after changing how it looks.
+#[test]
+fn checkbox_snapshots() {
+    for &(checked, name) in &[(false, "checkbox_unchecked"), (true, "checkbox_checked")] {
+        Harness::create(checked, Checkbox::new(""), |harness| {
+            harness.set_initial_size(Size::new(24., 24.));
+            harness.send_initial_events();
+            harness.assert_snapshot(name, 2);
+        });
+    }
+}
+
+#[test]
+fn update_relayouts_only_what_changed() {
+    const BUMP: Selector = Selector::new("druid-tests.bump-first");
+
+    /// Increments the first field when it gets `BUMP`.
+    struct Bump;
+
+    impl<W: Widget<(u32, u32)>> Controller<(u32, u32), W> for Bump {
+        fn event(
+            &mut self,
+            child: &mut W,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut (u32, u32),
+            env: &Env,
+        ) {
+            match event {
+                Event::Command(cmd) if cmd.selector == BUMP => data.0 += 1,
+                _ => child.event(ctx, event, data, env),
+            }
+        }
+    }
+
+    let label_rec = Recording::default();
+    let other_rec = Recording::default();
+    let root = WidgetId::next();
+    let widget = Flex::column()
+        .with_child(
+            Label::new(|n: &u32, _env: &Env| format!("count: {}", n))
+                .record(&label_rec)
+                .lens(crate::lens!((u32, u32), 0)),
+            0.0,
+        )
+        .with_child(
+            Flex::row()
+                .with_child(ModularWidget::new(()).record(&other_rec), 0.0)
+                .lens(crate::lens!((u32, u32), 1)),
+            0.0,
+        )
+        .controller(Bump)
+        .with_id(root);
+
+    Harness::create((1, 1), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        label_rec.clear();
+        other_rec.clear();
+
+        harness.submit_command(BUMP, root);
+        assert_eq!(harness.data(), &(2, 1));
+        harness.paint();
+        harness.paint();
+        assert_eq!(
+            count_records(&label_rec, |r| matches!(r, Record::Layout(_))),
+            1
+        );
+        assert_eq!(
+            count_records(&other_rec, |r| matches!(r, Record::Update(_))),
+            0
+        );
+    });
+}
+
+#[test]
+fn checkbox_label_follows_the_data() {
+    let checkbox = Checkbox::new(|checked: &bool, _env: &Env| {
+        if *checked {
+            "on".to_string()
+        } else {
+            "off".to_string()
+        }
+    });
+
+    let checkbox_rec = Recording::default();
+    Harness::create(false, checkbox.record(&checkbox_rec), |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        checkbox_rec.clear();
+
+        click(harness, Point::new(10.0, 10.0));
+        assert!(*harness.data());
+        // the label's text changed, so the checkbox is laid out again
+        harness.paint();
+        assert_eq!(
+            count_records(&checkbox_rec, |r| matches!(r, Record::Layout(_))),
+            1
+        );
+    });
+}
+
+#[test]
+fn drag_reports_held_buttons() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let seen_inner = seen.clone();
+    let inner = ModularWidget::new(()).event_fn(move |_, ctx, event, _data: &mut (), _env| {
+        match event {
+            Event::MouseDown(_) => ctx.set_active(true),
+            Event::MouseUp(_) => ctx.set_active(false),
+            _ => (),
+        }
+        if let Event::MouseMoved(mouse) | Event::MouseDown(mouse) | Event::MouseUp(mouse) = event {
+            let in_window = ctx.to_window(mouse.pos);
+            assert_eq!(in_window, mouse.window_pos);
+            seen_inner.borrow_mut().push((mouse.buttons, in_window));
+        }
+    });
+    let widget = Padding::new(10., SizedBox::new(inner).width(50.).height(50.));
+
+    let shell_mouse = |x, y, buttons| druid_shell::MouseEvent {
+        pos: Point::new(x, y),
+        mods: KeyModifiers::default(),
+        count: 0,
+        button: MouseButton::Left,
+        buttons,
+        focus: false,
+    };
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        let left = MouseButtons::from(MouseButton::Left);
+        harness.event(Event::MouseMoved(
+            shell_mouse(20., 20., MouseButtons::new()).into(),
+        ));
+        harness.event(Event::MouseDown(shell_mouse(20., 20., left).into()));
+        harness.event(Event::MouseMoved(shell_mouse(30., 25., left).into()));
+        harness.event(Event::MouseUp(
+            shell_mouse(30., 25., MouseButtons::new()).into(),
+        ));
+    });
+
+    let seen = seen.borrow();
+    assert_eq!(seen.len(), 4);
+    assert!(seen[0].0.is_empty());
+    assert!(seen[1].0.has_left());
+    assert!(seen[2].0.has_left() && !seen[2].0.has_right());
+    assert_eq!(seen[2].1, Point::new(30., 25.));
+    assert!(seen[3].0.is_empty());
+}
+
+#[test]
+fn duplicate_ids_are_caught() {
+    let id = WidgetId::next();
+    let other = WidgetId::next();
+    let widget = Flex::column()
+        .with_child(make_focus_requester(true).with_id(id), 0.0)
+        .with_child(make_focus_requester(true).with_id(other), 0.0)
+        .with_child(make_focus_requester(true).with_id(id), 0.0);
+    crate::core::DUPLICATE_ID_ERRORS.with(|errors| errors.borrow_mut().clear());
+    let errors = || crate::core::DUPLICATE_ID_ERRORS.with(|errors| errors.borrow().clone());
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        let errors = errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].0.contains("IdentityWrapper"));
+        assert_eq!(errors[0].1, id);
+    });
+
+    // once the window is gone, so are its ids
+    Harness::create(true, make_focus_requester(true).with_id(id), |harness| {
+        harness.send_initial_events();
+    });
+    assert_eq!(errors().len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "duplicate WidgetId")]
+fn duplicate_ids_can_panic() {
+    let id = WidgetId::next();
+    let widget = Flex::column()
+        .with_child(make_focus_requester(true).with_id(id), 0.0)
+        .with_child(make_focus_requester(true).with_id(id), 0.0)
+        .env_scope(|env, _| env.set(Env::PANIC_ON_DUPLICATE_ID, true));
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+    });
+}
+
+#[test]
+fn reserved_id_survives_rebuild() {
+    const RESERVED: WidgetId = WidgetId::reserved(7);
+    const NEXT: Selector = Selector::new("druid-tests.next-child");
+
+    /// Moves on to the next child when it gets `NEXT`.
+    struct Next;
+
+    impl<W: Widget<u32>> Controller<u32, W> for Next {
+        fn event(
+            &mut self,
+            child: &mut W,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut u32,
+            env: &Env,
+        ) {
+            match event {
+                Event::Command(cmd) if cmd.selector == NEXT => *data += 1,
+                _ => child.event(ctx, event, data, env),
+            }
+        }
+    }
+
+    let received = Rc::new(Cell::new(0));
+    let received_inner = received.clone();
+    let widget = ViewSwitcher::new(
+        |data: &u32, _env| *data,
+        move |key, _data, _env| {
+            let key = *key;
+            let received = received_inner.clone();
+            ModularWidget::new(())
+                .event_fn(move |_, _ctx, event, _data, _env| {
+                    if let Event::Command(cmd) = event {
+                        if cmd.selector == REQUEST_FOCUS {
+                            received.set(key);
+                        }
+                    }
+                })
+                .lifecycle_fn(|_, ctx, event, _data, _env| {
+                    if let LifeCycle::WidgetAdded = event {
+                        ctx.register_for_focus();
+                        // a second registration is ignored
+                        ctx.register_for_focus();
+                    }
+                })
+                .with_id(RESERVED)
+                .boxed()
+        },
+    )
+    .controller(Next);
+    crate::core::DUPLICATE_ID_ERRORS.with(|errors| errors.borrow_mut().clear());
+
+    Harness::create(1u32, widget, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(REQUEST_FOCUS, RESERVED);
+        assert_eq!(received.get(), 1);
+
+        // the old child is dropped before the new one is added
+        harness.submit_command(NEXT, Target::Auto);
+        harness.submit_command(REQUEST_FOCUS, RESERVED);
+        assert_eq!(received.get(), 2);
+        assert_eq!(harness.window().focus_chain(), &[RESERVED]);
+    });
+
+    // same again, replacing the child with ReplaceChild
+    let replacer = ReplaceChild::new(make_focus_requester(true).with_id(RESERVED), || {
+        make_focus_requester(true).with_id(RESERVED)
+    });
+    Harness::create(true, replacer, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(REPLACE_CHILD, Target::Auto);
+        harness.submit_command(REQUEST_FOCUS, RESERVED);
+        assert_eq!(harness.window().focus, Some(RESERVED));
+        assert_eq!(harness.window().focus_chain(), &[RESERVED]);
+    });
+    assert!(crate::core::DUPLICATE_ID_ERRORS.with(|errors| errors.borrow().is_empty()));
+}
+
+/// A delegate that logs what it sees, and swallows commands with odd arguments.
+struct SwallowingRecorder(Rc<RefCell<Vec<String>>>);
+
+impl AppDelegate<u32> for SwallowingRecorder {
+    fn command(
+        &mut self,
+        _ctx: &mut DelegateCtx,
+        _target: &Target,
+        cmd: &Command,
+        _data: &mut u32,
+        _env: &Env,
+    ) -> Handled {
+        match cmd.get_object::<u32>() {
+            Ok(value) => {
+                self.0.borrow_mut().push(format!("delegate {}", value));
+                (value % 2 == 1).into()
+            }
+            Err(_) => Handled::No,
+        }
+    }
+
+    fn window_added(&mut self, _id: WindowId, _data: &mut u32, _env: &Env, _: &mut DelegateCtx) {
+        self.0.borrow_mut().push("added".to_string());
+    }
+
+    fn window_removed(&mut self, _id: WindowId, _data: &mut u32, _env: &Env, _: &mut DelegateCtx) {
+        self.0.borrow_mut().push("removed".to_string());
+    }
+}
+
+#[test]
+fn delegate_intercepts_commands() {
+    use crate::ext_event::ExtEventHost;
+    use crate::shell::WinHandler;
+    use crate::win_handler::{AppHandler, AppState, EXT_EVENT_IDLE_TOKEN};
+
+    const PING: Selector = Selector::new("druid-tests.ping");
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let host = ExtEventHost::new();
+    let sink = host.make_sink();
+    let delegate = SwallowingRecorder(log.clone());
+    let state = AppState::new(0u32, theme::init(), Some(Box::new(delegate)), host);
+
+    let widget_log = log.clone();
+    let desc = WindowDesc::new(move || {
+        let widget_log = widget_log.clone();
+        ModularWidget::new(()).event_fn(move |_, _, event, data: &mut u32, _| {
+            if let Event::Command(cmd) = event {
+                if let Ok(value) = cmd.get_object::<u32>() {
+                    widget_log.borrow_mut().push(format!("widget {}", value));
+                    *data = *value;
+                }
+            }
+        })
+    });
+    let id = desc.id;
+    state.add_window(id, desc);
+    let mut handler = DruidHandler::new_shared(state.clone(), id);
+    handler.connect(&WindowHandle::default());
+    assert_eq!(*log.borrow(), vec!["added"]);
+
+    // the delegate sees every command first, and the swallowed ones
+    // never reach the window
+    for value in 1u32..=4 {
+        sink.submit_command(PING, value, id).unwrap();
+    }
+    let mut app_handler = AppHandler::new(state.clone());
+    crate::shell::AppHandler::idle(&mut app_handler, EXT_EVENT_IDLE_TOKEN);
+    assert_eq!(
+        log.borrow()[1..],
+        [
+            "delegate 1",
+            "delegate 2",
+            "widget 2",
+            "delegate 3",
+            "delegate 4",
+            "widget 4",
+        ]
+    );
+    assert_eq!(state.data(), 4);
+
+    // a pending window keeps the app from quitting when this one closes
+    let pending = WindowDesc::new(SizedBox::empty);
+    state.add_window(pending.id, pending);
+    handler.destroy();
+    assert_eq!(log.borrow().last().map(String::as_str), Some("removed"));
+}
+
+#[test]
+fn idle_requests_are_coalesced() {
+    const REQUEST_IDLE: Selector = Selector::new("druid-tests.request-idle");
+
+    #[derive(Default)]
+    struct IdleLog {
+        requested: Vec<IdleToken>,
+        received: Vec<IdleToken>,
+    }
+
+    let log = Rc::new(RefCell::new(IdleLog::default()));
+    let other_log = Rc::new(RefCell::new(IdleLog::default()));
+
+    let requester: ModularWidget<_, ()> =
+        ModularWidget::new(log.clone()).event_fn(|log, ctx, event, _data, _env| match event {
+            Event::Command(cmd) if cmd.selector == REQUEST_IDLE => {
+                for _ in 0..3 {
+                    let token = ctx.request_idle();
+                    log.borrow_mut().requested.push(token);
+                }
+            }
+            Event::Idle(token) => log.borrow_mut().received.push(*token),
+            _ => (),
+        });
+    let other: ModularWidget<_, ()> =
+        ModularWidget::new(other_log.clone()).event_fn(|log, _ctx, event, _data, _env| {
+            if let Event::Idle(token) = event {
+                log.borrow_mut().received.push(*token);
+            }
+        });
+    let widget = Split::vertical(requester, other);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(REQUEST_IDLE, Target::Auto);
+
+        let token = log.borrow().requested[0];
+        assert_eq!(log.borrow().requested, vec![token; 3]);
+        assert!(log.borrow().received.is_empty());
+
+        harness.process_idle();
+        assert_eq!(log.borrow().received, vec![token]);
+        assert!(other_log.borrow().received.is_empty());
+
+        // the request was used up
+        harness.process_idle();
+        assert_eq!(log.borrow().received, vec![token]);
+
+        // a later request gets a new token
+        harness.submit_command(REQUEST_IDLE, Target::Auto);
+        let next = log.borrow().requested[3];
+        assert_ne!(next, token);
+        harness.process_idle();
+        assert_eq!(log.borrow().received, vec![token, next]);
+    });
+}
+
+#[test]
+fn window_disconnected_is_last() {
+    /// Logs when it is dropped.
+    struct DropLog(Rc<RefCell<Vec<&'static str>>>);
+
+    impl Drop for DropLog {
+        fn drop(&mut self) {
+            self.0.borrow_mut().push("dropped");
+        }
+    }
+
+    const SAVE: Selector = Selector::new("druid-tests.save");
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let record = Recording::default();
+    let widget = ModularWidget::new(DropLog(log.clone()))
+        .event_fn(|state, ctx, event, _data, _env| match event {
+            Event::WindowDisconnected => {
+                state.0.borrow_mut().push("disconnected");
+                ctx.submit_command(SAVE, Target::Auto);
+            }
+            Event::Command(cmd) if cmd.selector == SAVE => state.0.borrow_mut().push("saved"),
+            _ => (),
+        })
+        .record(&record);
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        harness.event(Event::MouseMoved(make_mouse(Point::new(10., 10.))));
+        record.clear();
+
+        harness.disconnect();
+        assert_eq!(record.next(), Record::E(Event::WindowDisconnected));
+        // the command from the teardown is still delivered
+        assert_matches!(record.next(), Record::E(Event::Command(_)));
+        assert_eq!(*log.borrow(), vec!["disconnected", "saved"]);
+    });
+    assert_eq!(*log.borrow(), vec!["disconnected", "saved", "dropped"]);
+}
+
+#[test]
+fn targeted_events_skip_other_subtrees() {
+    const PING: Selector = Selector::new("druid-tests.ping");
+    const START_TIMERS: Selector = Selector::new("druid-tests.start-timers");
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let tokens = Rc::new(RefCell::new(Vec::new()));
+    let ids: Vec<WidgetId> = (0..20).map(|_| WidgetId::next()).collect();
+
+    let mut row = Flex::row();
+    for (i, id) in ids.iter().enumerate() {
+        let counter: ModularWidget<_, ()> = ModularWidget::new((i, log.clone(), tokens.clone()))
+            .event_fn(|(i, log, tokens), ctx, event, _data, _env| match event {
+                Event::Command(cmd) if cmd.selector == PING => {
+                    log.borrow_mut().push(format!("ping {}", i))
+                }
+                Event::Command(cmd) if cmd.selector == START_TIMERS => {
+                    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+                    tokens.borrow_mut().push(ctx.request_timer(deadline));
+                }
+                Event::Timer(_) => log.borrow_mut().push(format!("timer {}", i)),
+                _ => (),
+            });
+        // nest each one, so there are subtrees to skip
+        row.add_child(counter.with_id(*id).padding(1.).padding(1.), 0.);
+    }
+
+    Harness::create((), row, |harness| {
+        harness.send_initial_events();
+
+        harness.submit_command(PING, ids[7]);
+        assert_eq!(*log.borrow(), vec!["ping 7"]);
+
+        log.borrow_mut().clear();
+        harness.submit_command(START_TIMERS, Target::Auto);
+        assert_eq!(tokens.borrow().len(), 20);
+
+        let token = tokens.borrow()[12];
+        harness.event(Event::Timer(token));
+        assert_eq!(*log.borrow(), vec!["timer 12"]);
+
+        // a timer only fires once
+        harness.event(Event::Timer(token));
+        assert_eq!(*log.borrow(), vec!["timer 12"]);
+    });
+}
+
+#[test]
+fn cursor_follows_hot_and_active_widgets() {
+    let plain: ModularWidget<(), String> = ModularWidget::new(());
+    let dragger: ModularWidget<(), String> =
+        ModularWidget::new(()).event_fn(|_, ctx, event, _data, _env| match event {
+            Event::MouseDown(_) => {
+                ctx.set_active(true);
+                ctx.set_cursor(&Cursor::Crosshair);
+            }
+            Event::MouseUp(_) => ctx.set_active(false),
+            _ => (),
+        });
+    let widget = Flex::row()
+        .with_child(TextBox::new().fix_size(100., 100.), 0.)
+        .with_child(plain, 0.)
+        .with_child(dragger, 0.);
+
+    Harness::create(String::new(), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let move_to = |harness: &mut Harness<String>, x| {
+            harness.event(Event::MouseMoved(make_mouse(Point::new(x, 50.))));
+            harness.window().cursor().cloned()
+        };
+
+        assert_eq!(move_to(harness, 50.), Some(Cursor::IBeam));
+        assert_eq!(move_to(harness, 150.), Some(Cursor::Arrow));
+        assert_eq!(move_to(harness, 50.), Some(Cursor::IBeam));
+        assert_eq!(move_to(harness, 250.), Some(Cursor::Arrow));
+
+        // during a drag, the active widget's cursor wins
+        harness.event(Event::MouseDown(make_mouse(Point::new(250., 50.))));
+        assert_eq!(harness.window().cursor(), Some(&Cursor::Crosshair));
+        assert_eq!(move_to(harness, 50.), Some(Cursor::Crosshair));
+        assert_eq!(move_to(harness, 150.), Some(Cursor::Crosshair));
+
+        // once it is released, the widget under the mouse has its say
+        harness.event(Event::MouseUp(make_mouse(Point::new(50., 50.))));
+        assert_eq!(harness.window().cursor(), Some(&Cursor::IBeam));
+        assert_eq!(move_to(harness, 250.), Some(Cursor::Arrow));
+    });
+}
+
+#[test]
+fn window_title_follows_data() {
+    const BUMP: Selector = Selector::new("druid-tests.bump");
+    let bumper: ModularWidget<(), u32> =
+        ModularWidget::new(()).event_fn(|_, _ctx, event, data, _env| {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == BUMP {
+                    *data += 1;
+                }
+            }
+        });
+
+    Harness::create(0, bumper, |harness| {
+        // the title only changes on every other bump
+        let title: LabelText<u32> = (|n: &u32, _: &Env| format!("doc {}", n / 2)).into();
+        harness.window_mut().title = title;
+        harness.send_initial_events();
+        let window = Target::Window(harness.window().id);
+
+        let mut titles = vec![harness.window().platform_title.clone()];
+        for _ in 0..4 {
+            harness.submit_command(BUMP, Target::Auto);
+            titles.push(harness.window().platform_title.clone());
+        }
+        let doc = |n: &str| Some(format!("doc {}", n));
+        assert_eq!(
+            titles,
+            vec![doc("0"), doc("0"), doc("1"), doc("1"), doc("2")]
+        );
+
+        // the command replaces the title, which then no longer follows the data
+        let cmd = Command::new(commands::SET_WINDOW_TITLE, "fixed".to_string());
+        harness.submit_command(cmd, window);
+        assert_eq!(harness.window().platform_title.as_deref(), Some("fixed"));
+        harness.submit_command(BUMP, Target::Auto);
+        assert_eq!(harness.window().platform_title.as_deref(), Some("fixed"));
+        assert_eq!(*harness.data(), 5);
+    });
+}
+
+#[test]
+fn capture_paints_the_widget_offscreen() {
+    const CAPTURE: Selector = Selector::new("druid-tests.capture");
+    const CAPTURED: Selector = Selector::new("druid-tests.captured");
+
+    let images = Rc::new(RefCell::new(Vec::new()));
+    let images_in_event = images.clone();
+    let receiver_id = WidgetId::next();
+    let receiver = ModularWidget::new(())
+        .event_fn(move |_, _ctx, event, _data, _env| {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == CAPTURED {
+                    let image = cmd.get_object::<ImageBuf>().unwrap().clone();
+                    images_in_event.borrow_mut().push(image);
+                }
+            }
+        })
+        .with_id(receiver_id);
+    // red on the left and blue on the right, with nothing under the window
+    // background in the way
+    let painted = ModularWidget::new(())
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain((40., 20.)))
+        .paint_fn(|_, ctx, _data, _env| {
+            ctx.fill(Rect::new(0., 0., 20., 20.), &Color::rgb8(0xff, 0, 0));
+            ctx.fill(Rect::new(20., 0., 40., 20.), &Color::rgb8(0, 0, 0xff));
+        })
+        .capture_on(CAPTURE);
+    let widget = Flex::column()
+        .with_child(receiver, 0.0)
+        .with_child(painted, 0.0);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        let request = CaptureRequest::new(CAPTURED, receiver_id);
+        harness.submit_command(Command::new(CAPTURE, request), Target::Auto);
+        let images = images.borrow();
+        assert_eq!(images.len(), 1);
+        let image = &images[0];
+        assert_eq!((image.width(), image.height()), (40, 20));
+        let pixel = |x: usize, y: usize| {
+            let i = (y * 40 + x) * 4;
+            &image.raw_pixels()[i..i + 4]
+        };
+        assert_eq!(pixel(5, 10), &[0xff, 0, 0, 0xff]);
+        assert_eq!(pixel(35, 10), &[0, 0, 0xff, 0xff]);
+    });
+}
+
+#[test]
+fn capture_window_without_a_platform_window_sends_nothing() {
+    const CAPTURED: Selector = Selector::new("druid-tests.captured");
+
+    let replies = Rc::new(Cell::new(0));
+    let replies_in_event = replies.clone();
+    let receiver_id = WidgetId::next();
+    let receiver = ModularWidget::new(())
+        .event_fn(move |_, _ctx, event, _data: &mut (), _env| {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == CAPTURED {
+                    replies_in_event.set(replies_in_event.get() + 1);
+                }
+            }
+        })
+        .with_id(receiver_id);
+
+    Harness::create((), receiver, |harness| {
+        harness.send_initial_events();
+        // the harness has no platform window to read back from, so this
+        // fails, and the error is logged
+        let request = CaptureRequest::new(CAPTURED, receiver_id);
+        harness.submit_command(
+            Command::new(commands::CAPTURE_WINDOW, request),
+            Target::Auto,
+        );
+        assert_eq!(replies.get(), 0);
+    });
+}
+
+/// A widget that requests a paint when it gets `REQUEST_PAINT`, or a paint
+/// of its top left corner when it gets `REQUEST_PAINT_CORNER`, and records
+/// when it is painted.
+fn paint_requester(id: WidgetId, painted: Rc<RefCell<Vec<WidgetId>>>) -> impl Widget<()> {
+    ModularWidget::new(())
+        .event_fn(|_, ctx, event, _, _| {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == REQUEST_PAINT {
+                    ctx.request_paint();
+                } else if cmd.selector == REQUEST_PAINT_CORNER {
+                    ctx.request_paint_rect(Rect::new(0., 0., 10., 10.));
+                }
+            }
+        })
+        .layout_fn(|_, _, bc, _, _| bc.constrain(Size::new(100., 50.)))
+        .paint_fn(move |_, _, _, _| painted.borrow_mut().push(id))
+        .with_id(id)
+}
+
+/// The coordinates of the window's invalid rects, as kurbo's `Rect` doesn't
+/// implement `PartialEq`.
+fn invalid_rects<T: Data>(harness: &Harness<T>) -> Vec<(f64, f64, f64, f64)> {
+    let invalid = &harness.window().root.state().invalid;
+    invalid
+        .rects()
+        .iter()
+        .map(|r| (r.x0, r.y0, r.x1, r.y1))
+        .collect()
+}
+
+fn coords(r: Rect) -> (f64, f64, f64, f64) {
+    (r.x0, r.y0, r.x1, r.y1)
+}
+
+const REQUEST_PAINT: Selector = Selector::new("druid-tests.request-paint");
+
+const REQUEST_PAINT_CORNER: Selector = Selector::new("druid-tests.request-paint-corner");
+
+#[test]
+fn request_paint_invalidates_only_the_widget() {
+    let (id_1, id_2) = widget_id2();
+    let painted = Rc::new(RefCell::new(Vec::new()));
+    let widget = Flex::column()
+        .with_child(paint_requester(id_1, painted.clone()), 0.0)
+        .with_child(paint_requester(id_2, painted.clone()), 0.0);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.paint();
+        assert!(harness.window().root.state().invalid.is_empty());
+        painted.borrow_mut().clear();
+
+        harness.submit_command(REQUEST_PAINT, id_2);
+        let rect_2 = harness.get_state(id_2).layout_rect;
+        assert_eq!(invalid_rects(harness), vec![coords(rect_2)]);
+
+        // only the widget that asked is painted again
+        harness.paint_rect(rect_2);
+        assert_eq!(*painted.borrow(), vec![id_2]);
+        assert!(harness.window().root.state().invalid.is_empty());
+
+        // a widget can ask for part of itself
+        harness.submit_command(REQUEST_PAINT_CORNER, id_1);
+        let origin = harness.get_state(id_1).layout_rect.origin();
+        let corner = Rect::from_origin_size(origin, Size::new(10., 10.));
+        assert_eq!(invalid_rects(harness), vec![coords(corner)]);
+    });
+}
+
+#[test]
+fn request_paint_in_a_scroll_is_offset_by_the_scroll() {
+    let id = WidgetId::next();
+    let painted = Rc::new(RefCell::new(Vec::new()));
+    let content = Flex::column()
+        .with_child(SizedBox::empty().height(500.), 0.0)
+        .with_child(paint_requester(id, painted), 0.0);
+
+    Harness::create((), Scroll::new(content).vertical(), |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let pos = Point::new(10., 10.);
+        harness.event(Event::MouseMoved(make_mouse(pos)));
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., 200.),
+            mods: KeyModifiers::default(),
+            window_anchor: pos,
+            local_anchor: pos,
+            phase: ScrollPhase::Changed,
+            momentum_phase: None,
+        }));
+        harness.paint();
+
+        harness.submit_command(REQUEST_PAINT, id);
+        let rect = harness.get_state(id).layout_rect - Vec2::new(0., 200.);
+        assert_eq!(rect.y0, 300.);
+        assert_eq!(invalid_rects(harness), vec![coords(rect)]);
+    });
+}
+
+#[test]
+fn removing_a_widget_cancels_its_timers() {
+    const START_TIMER: Selector = Selector::new("druid-tests.start-timer");
+
+    /// Requests a timer on `START_TIMER`, and logs its name when one fires.
+    fn timer_widget(
+        name: &'static str,
+        tokens: &Rc<RefCell<Vec<TimerToken>>>,
+        log: &Rc<RefCell<Vec<&'static str>>>,
+    ) -> impl Widget<()> {
+        ModularWidget::new((name, tokens.clone(), log.clone())).event_fn(
+            |(name, tokens, log), ctx, event, _data, _env| match event {
+                Event::Command(cmd) if cmd.selector == START_TIMER => {
+                    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+                    tokens.borrow_mut().push(ctx.request_timer(deadline));
+                }
+                Event::Timer(_) => log.borrow_mut().push(*name),
+                _ => (),
+            },
+        )
+    }
+
+    let tokens = Rc::new(RefCell::new(Vec::new()));
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let replacer = ReplaceChild::new(timer_widget("removed", &tokens, &log), || {
+        SizedBox::<()>::empty()
+    });
+    let widget = Split::vertical(timer_widget("kept", &tokens, &log), replacer);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(START_TIMER, Target::Auto);
+        let (kept, removed) = (tokens.borrow()[0], tokens.borrow()[1]);
+
+        harness.submit_command(REPLACE_CHILD, Target::Auto);
+        let timers = &harness.window().root.state().timers;
+        assert!(timers.contains_key(&kept));
+        assert!(!timers.contains_key(&removed));
+
+        harness.event(Event::Timer(removed));
+        harness.event(Event::Timer(kept));
+        assert_eq!(*log.borrow(), vec!["kept"]);
+    });
+}
+
+const SEND_NOTIFICATIONS: Selector = Selector::new("druid-tests.send-notifications");
+
+const PING: Selector = Selector::new("druid-tests.ping");
+
+const PONG: Selector = Selector::new("druid-tests.pong");
+
+type NotificationLog = Rc<RefCell<Vec<(&'static str, Selector, WidgetId, Option<u32>)>>>;
+
+/// Logs the notifications that its widget is sent, and handles them if
+/// `handle` is set.
+struct LogNotifications {
+    name: &'static str,
+    log: NotificationLog,
+    handle: bool,
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for LogNotifications {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Notification(notification) = event {
+            let arg = notification.get_object::<u32>().ok().copied();
+            let entry = (
+                self.name,
+                notification.selector().clone(),
+                notification.source(),
+                arg,
+            );
+            self.log.borrow_mut().push(entry);
+            if self.handle {
+                ctx.set_handled();
+                return;
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
+
+/// `inner` and `outer` around a widget that sends `PING`, with 7, and then
+/// `PONG`, with a sibling of `outer` next to them.
+fn notification_tree(
+    sender: WidgetId,
+    log: &NotificationLog,
+    handle_inner: bool,
+) -> impl Widget<()> {
+    let logger = |name, handle| LogNotifications {
+        name,
+        log: log.clone(),
+        handle,
+    };
+    let sending = ModularWidget::new(())
+        .event_fn(|_, ctx, event, _, _| match event {
+            Event::Command(cmd) if cmd.selector == SEND_NOTIFICATIONS => {
+                ctx.submit_notification(Command::new(PING, 7u32));
+                ctx.submit_notification(PONG);
+            }
+            _ => (),
+        })
+        .controller(logger("sender", false))
+        .with_id(sender);
+    let inner = Padding::new(5.0, sending).controller(logger("inner", handle_inner));
+    let outer = SizedBox::new(inner).controller(logger("outer", false));
+    let sibling = SizedBox::empty().controller(logger("sibling", false));
+    Flex::column()
+        .with_child(outer, 0.0)
+        .with_child(sibling, 0.0)
+        .controller(logger("root", false))
+}
+
+#[test]
+fn notifications_go_up_to_the_ancestors() {
+    let sender = WidgetId::next();
+    let log = NotificationLog::default();
+    Harness::create((), notification_tree(sender, &log, false), |harness| {
+        harness.send_initial_events();
+        harness.submit_command(SEND_NOTIFICATIONS, sender);
+
+        // each notification reaches the closest ancestor first; the sender
+        // and its sibling never see them
+        let expected = vec![
+            ("inner", PING, sender, Some(7)),
+            ("inner", PONG, sender, None),
+            ("outer", PING, sender, Some(7)),
+            ("outer", PONG, sender, None),
+            ("root", PING, sender, Some(7)),
+            ("root", PONG, sender, None),
+        ];
+        assert_eq!(*log.borrow(), expected);
+    });
+}
+
+#[test]
+fn handled_notifications_go_no_further() {
+    let sender = WidgetId::next();
+    let log = NotificationLog::default();
+    Harness::create((), notification_tree(sender, &log, true), |harness| {
+        harness.send_initial_events();
+        harness.submit_command(SEND_NOTIFICATIONS, sender);
+        let expected = vec![
+            ("inner", PING, sender, Some(7)),
+            ("inner", PONG, sender, None),
+        ];
+        assert_eq!(*log.borrow(), expected);
+    });
+}
+
+#[test]
+fn tree_queries() {
+    let (id_1, id_2, id_3, id_4) = widget_id4();
+
+    let widget = Split::vertical(
+        SizedBox::empty().with_id(id_1),
+        SizedBox::empty().with_id(id_2),
+    )
+    .with_id(id_3)
+    .padding(5.0)
+    .with_id(id_4);
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        assert_eq!(harness.get_parent(id_4), None);
+        assert_eq!(harness.get_parent(id_3), Some(id_4));
+        assert_eq!(harness.get_parent(id_1), Some(id_3));
+        assert_eq!(harness.get_parent(id_2), Some(id_3));
+
+        assert_eq!(harness.descendants(id_4), vec![id_3, id_1, id_2]);
+        assert_eq!(harness.descendants(id_3), vec![id_1, id_2]);
+        assert!(harness.descendants(id_2).is_empty());
+
+        // the split's children are laid out relative to the split, which is
+        // inset by the padding
+        let local = harness.get_state(id_2).layout_rect;
+        assert!(local.x0 > 0.);
+        let in_window = harness.layout_rect_in_window(id_2);
+        assert_eq!(in_window.origin(), local.origin() + Vec2::new(5., 5.));
+        assert_eq!(in_window.size(), local.size());
+        let first = harness.layout_rect_in_window(id_1);
+        assert_eq!(first.origin(), Point::new(5., 5.));
+        assert_eq!(harness.layout_rect_in_window(id_4).origin(), Point::ORIGIN);
+    });
+}
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+/// A widget that logs the drag events it gets under `name`, and that takes
+/// drops if `accepts` is set.
+fn target(log: &Log, name: &'static str, accepts: bool) -> impl Widget<String> {
+    let log = log.clone();
+    ModularWidget::new(())
+        .event_fn(move |_, ctx, event, _data: &mut String, _env| {
+            let kind = match event {
+                Event::DragEnter(_) => "enter".to_string(),
+                Event::DragOver(_) => "over".to_string(),
+                Event::DragLeave => "leave".to_string(),
+                Event::Drop(drag) => format!("drop at {:?}", drag.pos),
+                _ => return,
+            };
+            log.borrow_mut().push(format!("{}: {}", name, kind));
+            if accepts && !matches!(event, Event::DragLeave) {
+                ctx.accept_drop();
+            }
+        })
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain(Size::new(100., 100.)))
+}
+
+fn drag_at(pos: Point) -> DragEvent {
+    DragEvent {
+        pos,
+        window_pos: pos,
+        mods: KeyModifiers::default(),
+        payload: DragPayload::files(vec![PathBuf::from("notes.txt")]),
+    }
+}
+
+/// A left button mouse event at `pos`, with the left button held.
+fn mouse_at(pos: Point) -> MouseEvent {
+    MouseEvent {
+        buttons: MouseButtons::new().with(MouseButton::Left),
+        ..make_mouse(pos)
+    }
+}
+
+fn take(log: &Log) -> Vec<String> {
+    log.borrow_mut().drain(..).collect()
+}
+
+#[test]
+fn drags_enter_and_leave_widgets() {
+    let log = Log::default();
+    let (left, right) = widget_id2();
+    let widget = Flex::row()
+        .with_child(target(&log, "left", true).with_id(left), 0.0)
+        .with_child(target(&log, "right", false).with_id(right), 0.0);
+    Harness::create(String::new(), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let left_center = harness.layout_rect_in_window(left).center();
+        let right_center = harness.layout_rect_in_window(right).center();
+
+        assert!(harness.drag(Event::DragEnter(drag_at(left_center))));
+        assert_eq!(take(&log), vec!["left: enter"]);
+        assert!(harness.drag(Event::DragOver(drag_at(left_center))));
+        assert_eq!(take(&log), vec!["left: over"]);
+
+        // the right widget doesn't take drops
+        assert!(!harness.drag(Event::DragOver(drag_at(right_center))));
+        assert_eq!(take(&log), vec!["left: leave", "right: enter"]);
+        assert!(!harness.drag(Event::Drop(drag_at(right_center))));
+        assert_eq!(take(&log), vec!["right: drop at (50.0, 50.0)"]);
+
+        // once it is dropped, a new drag enters again
+        assert!(harness.drag(Event::DragEnter(drag_at(left_center))));
+        harness.drag(Event::DragLeave);
+        assert_eq!(take(&log), vec!["left: enter", "left: leave"]);
+    });
+}
+
+#[test]
+fn files_are_dropped_on_drop_targets() {
+    let (target_id, other) = widget_id2();
+    let widget = Flex::row()
+        .with_child(
+            SizedBox::empty()
+                .width(100.)
+                .height(100.)
+                .on_drop(
+                    |payload, _, _| !payload.get_files().is_empty(),
+                    |_, payload, data: &mut String, _| {
+                        *data = payload.get_files()[0].display().to_string();
+                    },
+                )
+                .with_id(target_id),
+            0.0,
+        )
+        .with_child(
+            SizedBox::empty().width(100.).height(100.).with_id(other),
+            0.0,
+        );
+    Harness::create(String::new(), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let target_center = harness.layout_rect_in_window(target_id).center();
+        let other_center = harness.layout_rect_in_window(other).center();
+
+        // text isn't taken
+        let mut text = drag_at(target_center);
+        text.payload = DragPayload::text("hello");
+        assert!(!harness.drag(Event::DragEnter(text)));
+        assert!(!harness.drag(Event::DragOver(drag_at(other_center))));
+        assert!(!harness.drag(Event::Drop(drag_at(other_center))));
+        assert_eq!(harness.data(), "");
+
+        assert!(harness.drag(Event::DragEnter(drag_at(target_center))));
+        assert!(harness.drag(Event::Drop(drag_at(target_center))));
+        assert_eq!(harness.data(), "notes.txt");
+    });
+}
+
+#[test]
+fn widgets_drag_to_other_widgets() {
+    let log = Log::default();
+    let finished = Rc::new(Cell::new(None));
+    let finished_inner = finished.clone();
+    let (source, right) = widget_id2();
+    let source_widget = ModularWidget::new(false)
+        .event_fn(
+            move |started, ctx, event, _data: &mut String, _env| match event {
+                Event::MouseDown(_) => ctx.set_active(true),
+                Event::MouseMoved(_) if ctx.is_active() && !*started => {
+                    *started = true;
+                    ctx.start_drag(DragPayload::text("hello"));
+                }
+                Event::MouseUp(_) => ctx.set_active(false),
+                Event::Command(cmd) if cmd.selector == commands::DRAG_FINISHED => {
+                    finished_inner.set(cmd.get_object::<bool>().ok().copied());
+                }
+                _ => (),
+            },
+        )
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain(Size::new(100., 100.)));
+    let widget = Flex::row()
+        .with_child(source_widget.with_id(source), 0.0)
+        .with_child(target(&log, "right", true).with_id(right), 0.0);
+    Harness::create(String::new(), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let source_center = harness.layout_rect_in_window(source).center();
+        let right_center = harness.layout_rect_in_window(right).center();
+
+        harness.event(Event::MouseDown(mouse_at(source_center)));
+        harness.event(Event::MouseMoved(mouse_at(
+            source_center + Vec2::new(5., 0.),
+        )));
+        assert!(log.borrow().is_empty());
+
+        harness.event(Event::MouseMoved(mouse_at(right_center)));
+        assert_eq!(take(&log), vec!["right: enter"]);
+        harness.event(Event::MouseUp(mouse_at(right_center)));
+        assert_eq!(take(&log), vec!["right: drop at (50.0, 50.0)"]);
+        assert_eq!(finished.get(), Some(true));
+    });
+}
+
+#[test]
+fn release_outside_the_window_ends_the_press() {
+    let checkbox = WidgetId::next();
+    let widget = Checkbox::new("check").with_id(checkbox);
+    Harness::create(false, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let inside = harness.layout_rect_in_window(checkbox).origin() + Vec2::new(5., 5.);
+        harness.event(Event::MouseMoved(make_mouse(inside)));
+        harness.event(Event::MouseDown(make_mouse(inside)));
+        assert!(harness.get_state(checkbox).is_active);
+        assert!(harness.window().mouse_captured);
+
+        // above and to the left of the window, while it has the mouse
+        let outside = Point::new(-20., -20.);
+        harness.event(Event::MouseMoved(make_mouse(outside)));
+        assert!(harness.get_state(checkbox).is_active);
+        assert!(!harness.get_state(checkbox).is_hot);
+        harness.event(Event::MouseUp(make_mouse(outside)));
+        assert!(!harness.get_state(checkbox).is_active);
+        assert!(!harness.window().mouse_captured);
+        assert!(!*harness.data());
+    });
+}
+
+#[test]
+fn losing_the_capture_ends_the_press() {
+    let checkbox = WidgetId::next();
+    let widget = Checkbox::new("check").with_id(checkbox);
+    Harness::create(false, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let inside = harness.layout_rect_in_window(checkbox).origin() + Vec2::new(5., 5.);
+        harness.event(Event::MouseMoved(make_mouse(inside)));
+        harness.event(Event::MouseDown(make_mouse(inside)));
+        assert!(harness.window().mouse_captured);
+
+        // another window takes the mouse, and the press is cancelled
+        harness.event(Event::MouseCaptureLost);
+        assert!(!harness.get_state(checkbox).is_active);
+        assert!(!harness.window().mouse_captured);
+        harness.event(Event::MouseUp(make_mouse(inside)));
+        assert!(!*harness.data());
+    });
+}
+
+#[test]
+fn widget_raises_itself_above_its_siblings() {
+    let first = WidgetId::next();
+    let overlap = Point::new(40.0, 25.0);
+    let log: SquareLog = Default::default();
+    let widget = Flex::row()
+        .with_child(make_square(1, log.clone()).with_id(first), 0.0)
+        .with_spacer(-20.0)
+        .with_child(make_square(2, log.clone()), 0.0);
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.submit_command(RAISE, Target::Widget(first));
+        harness.paint();
+        assert_eq!(log.replace(Vec::new()), vec![("paint", 2), ("paint", 1)]);
+        click(harness, overlap);
+        assert_eq!(log.replace(Vec::new()), vec![("click", 1)]);
+        assert!(harness.get_state(first).is_hot);
+    });
+}
+
+const SECOND: u64 = 1_000_000_000;
+
+/// Moves the child of a `mover` to the x in the argument.
+const MOVE_TO: Selector = Selector::new("druid-tests.move-to");
+
+/// A container that moves its child to `(data, 0)` over a second, once it has
+/// put it there the first time.
+fn mover(child: WidgetId) -> impl Widget<f64> {
+    let pod = WidgetPod::new(SizedBox::empty().width(20.).height(20.).with_id(child));
+    ModularWidget::new((pod, false))
+        .event_fn(|(pod, _), ctx, event, data, env| {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == MOVE_TO {
+                    *data = *cmd.get_object::<f64>().unwrap();
+                    ctx.request_layout();
+                }
+            }
+            pod.event(ctx, event, data, env);
+        })
+        .lifecycle_fn(|(pod, _), ctx, event, data, env| pod.lifecycle(ctx, event, data, env))
+        .update_fn(|(pod, _), ctx, _old_data, data, env| pod.update(ctx, data, env))
+        .layout_fn(|(pod, placed), ctx, bc, data, env| {
+            let size = pod.layout(ctx, &bc.loosen(), data, env);
+            let rect = Rect::from_origin_size(Point::new(*data, 0.), size);
+            if *placed {
+                let duration = Duration::from_secs(1);
+                pod.set_layout_rect_animated(ctx, data, env, rect, duration, Curve::Linear);
+            } else {
+                pod.set_layout_rect(ctx, data, env, rect);
+                *placed = true;
+            }
+            bc.max()
+        })
+        .paint_fn(|(pod, _), ctx, data, env| pod.paint_with_offset(ctx, data, env))
+}
+
+fn child_x(harness: &mut Harness<f64>, child: WidgetId) -> f64 {
+    harness.get_state(child).layout_rect.x0
+}
+
+#[test]
+fn layout_rect_moves_to_its_target() {
+    let (root, child) = widget_id2();
+    Harness::create(0.0, mover(child).with_id(root), |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        assert_eq!(child_x(harness, child), 0.0);
+        assert!(!harness.get_state(root).request_anim);
+
+        // it starts from where it was
+        harness.submit_command(Command::new(MOVE_TO, 100.0), Target::Auto);
+        harness.just_layout();
+        assert_eq!(child_x(harness, child), 0.0);
+        assert!(harness.get_state(root).request_anim);
+
+        harness.anim_frame(SECOND / 4);
+        assert_eq!(child_x(harness, child), 25.0);
+        // laying it out again with the same target doesn't start over
+        harness.just_layout();
+        assert_eq!(child_x(harness, child), 25.0);
+        harness.anim_frame(SECOND / 2);
+        assert_eq!(child_x(harness, child), 75.0);
+
+        // a new target interrupts it where it is
+        harness.submit_command(Command::new(MOVE_TO, 0.0), Target::Auto);
+        harness.just_layout();
+        assert_eq!(child_x(harness, child), 75.0);
+        harness.anim_frame(SECOND / 2);
+        assert_eq!(child_x(harness, child), 37.5);
+
+        // it settles exactly on the target, and stops asking for frames
+        harness.anim_frame(SECOND);
+        assert_eq!(child_x(harness, child), 0.0);
+        let rect = harness.get_state(child).layout_rect;
+        assert_eq!(rect.size(), Size::new(20., 20.));
+        assert!(!harness.get_state(root).request_anim);
+    });
+}
+
+#[test]
+fn opacity_fades_in_and_follows_the_data() {
+    const HIDE: Selector = Selector::new("druid-tests.hide");
+    // long enough that the time it takes to paint doesn't show
+    const DURATION: Duration = Duration::from_secs(100);
+    const QUARTER: u64 = 25 * SECOND;
+
+    /// The red channel of the window, which is white faded over black.
+    fn brightness(harness: &mut Harness<bool>) -> u8 {
+        harness.render_to_image()[0]
+    }
+
+    fn assert_close(actual: u8, expected: u8) {
+        let diff = (i16::from(actual) - i16::from(expected)).abs();
+        assert!(diff <= 2, "{} is not close to {}", actual, expected);
+    }
+
+    let square = ModularWidget::new(())
+        .event_fn(|_, _ctx, event, data: &mut bool, _env| {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == HIDE {
+                    *data = false;
+                }
+            }
+        })
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.max())
+        .paint_fn(|_, ctx, _data, _env| {
+            let rect = ctx.size().to_rect();
+            ctx.fill(rect, &Color::WHITE);
+        });
+    let opacity = |visible: &bool, _: &Env| if *visible { 1.0 } else { 0.2 };
+    let id = WidgetId::next();
+    let widget = square
+        .with_opacity_animation(opacity, DURATION, Curve::Linear)
+        .with_id(id);
+
+    Harness::create(true, widget, |harness| {
+        harness.set_initial_size(Size::new(20., 20.));
+        harness.configure_env(|env, _| env.set(theme::WINDOW_BACKGROUND_COLOR, Color::BLACK));
+        harness.send_initial_events();
+        harness.just_layout();
+        assert_eq!(brightness(harness), 0);
+        assert!(harness.get_state(id).request_anim);
+
+        harness.anim_frame(QUARTER);
+        assert_close(brightness(harness), 64);
+        harness.anim_frame(QUARTER);
+        assert_close(brightness(harness), 128);
+
+        // hidden halfway through fading in, it fades out from there
+        harness.submit_command(HIDE, Target::Auto);
+        harness.anim_frame(2 * QUARTER);
+        assert_close(brightness(harness), 89);
+
+        harness.anim_frame(4 * QUARTER);
+        assert_close(brightness(harness), 51);
+        assert!(!harness.get_state(id).request_anim);
+    });
+}
+
+/// A widget that takes the focus when it is clicked, and handles no keys.
+fn focus_taker<T: Data>() -> impl Widget<T> {
+    ModularWidget::new(())
+        .event_fn(|_, ctx, event, _data, _env| {
+            if let Event::MouseDown(_) = event {
+                ctx.request_focus();
+            }
+        })
+        .lifecycle_fn(|_, ctx, event, _data, _env| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.register_for_focus();
+            }
+        })
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain((100., 20.)))
+}
+
+fn alt_key(text: &'static str, code: Code) -> Event {
+    let mods = KeyModifiers {
+        alt: true,
+        ..Default::default()
+    };
+    Event::KeyDown(KeyEvent::for_test(mods, text, code))
+}
+
+fn click_on<T: Data>(harness: &mut Harness<T>, id: WidgetId) {
+    let center = harness.layout_rect_in_window(id).center();
+    click(harness, center);
+}
+
+#[test]
+fn enter_and_escape_press_default_and_cancel_buttons() {
+    let focus = WidgetId::next();
+    let widget = Flex::column()
+        .with_child(focus_taker().with_id(focus), 0.0)
+        .with_child(
+            Button::new("OK", |_, data: &mut u32, _| *data += 1).default(true),
+            0.0,
+        )
+        .with_child(
+            Button::new("Cancel", |_, data: &mut u32, _| *data += 10).cancel(true),
+            0.0,
+        )
+        .with_child(
+            Button::new("Other", |_, data: &mut u32, _| *data += 100),
+            0.0,
+        );
+    Harness::create(0u32, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        click_on(harness, focus);
+        assert_eq!(harness.window().focus, Some(focus));
+
+        harness.event(key_down("", Code::Return));
+        assert_eq!(*harness.data(), 1);
+        harness.event(key_down("", Code::Escape));
+        assert_eq!(*harness.data(), 11);
+
+        // other keys, and Enter with a modifier, press nothing
+        harness.event(key_down("x", Code::KeyX));
+        let mods = KeyModifiers {
+            shift: true,
+            ..Default::default()
+        };
+        harness.event(Event::KeyDown(KeyEvent::for_test(mods, "", Code::Return)));
+        assert_eq!(*harness.data(), 11);
+    });
+}
+
+#[test]
+fn focused_widget_handles_enter_first() {
+    let (single, multi) = widget_id2();
+    let widget = Flex::column()
+        .with_child(
+            TextBox::new()
+                .with_id(single)
+                .lens(crate::lens!((u32, String, String), 1)),
+            0.0,
+        )
+        .with_child(
+            TextBox::multiline()
+                .fix_height(60.)
+                .with_id(multi)
+                .lens(crate::lens!((u32, String, String), 2)),
+            0.0,
+        )
+        .with_child(
+            Button::new("OK", |_, data: &mut (u32, String, String), _| data.0 += 1).default(true),
+            0.0,
+        );
+    let data = (0u32, String::new(), String::new());
+    Harness::create(data, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // a multiline box takes Enter for a new line
+        click_on(harness, multi);
+        harness.event(key_down("", Code::Return));
+        assert_eq!(harness.data().0, 0);
+        assert_eq!(harness.data().2, "\n");
+
+        // a single line box commits its text, and leaves Enter unhandled
+        click_on(harness, single);
+        harness.event(key_down("a", Code::KeyA));
+        harness.event(key_down("", Code::Return));
+        assert_eq!(harness.data().0, 1);
+        assert_eq!(harness.data().1, "a");
+    });
+}
+
+#[test]
+fn alt_and_mnemonic_press_a_control() {
+    let save = Button::new("&Save", |_, data: &mut (u32, bool), _| data.0 += 1);
+    let save_as = Button::new("Save &As", |_, data: &mut (u32, bool), _| data.0 += 10);
+    let widget = Flex::column()
+        .with_child(save, 0.0)
+        .with_child(save_as, 0.0)
+        .with_child(
+            Button::new("Q&&A", |_, data: &mut (u32, bool), _| data.0 += 100),
+            0.0,
+        )
+        .with_child(
+            Checkbox::new("&Remember me").lens(crate::lens!((u32, bool), 1)),
+            0.0,
+        );
+    Harness::create((0u32, false), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        harness.event(alt_key("a", Code::KeyA));
+        assert_eq!(harness.data().0, 10);
+        harness.event(alt_key("S", Code::KeyS));
+        assert_eq!(harness.data().0, 11);
+
+        // without Alt, and for a literal `&`, nothing is pressed
+        harness.event(key_down("s", Code::KeyS));
+        harness.event(alt_key("q", Code::KeyQ));
+        assert_eq!(harness.data().0, 11);
+
+        harness.event(alt_key("r", Code::KeyR));
+        assert!(harness.data().1);
+        harness.event(alt_key("r", Code::KeyR));
+        assert!(!harness.data().1);
+    });
+}
+
+#[test]
+fn disabled_button_ignores_its_keys() {
+    let widget = Button::new("&OK", |_, data: &mut u32, _| *data += 1)
+        .default(true)
+        .disabled_if(|_, _| true);
+    Harness::create(0u32, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.event(key_down("", Code::Return));
+        harness.event(alt_key("o", Code::KeyO));
+        assert_eq!(*harness.data(), 0);
+    });
+}
+
+#[test]
+fn request_update_updates_though_the_data_is_the_same() {
+    const REFRESH: Selector = Selector::new("druid-tests.refresh");
+    const NOTHING: Selector = Selector::new("druid-tests.nothing");
+
+    /// Requests an update when it gets `REFRESH`.
+    struct Refresh;
+
+    impl<T, W: Widget<T>> Controller<T, W> for Refresh {
+        fn event(
+            &mut self,
+            child: &mut W,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut T,
+            env: &Env,
+        ) {
+            match event {
+                Event::Command(cmd) if cmd.selector == REFRESH => ctx.request_update(),
+                _ => child.event(ctx, event, data, env),
+            }
+        }
+    }
+
+    let requester_rec = Recording::default();
+    let child_rec = Recording::default();
+    let sibling_rec = Recording::default();
+    let (requester, host) = widget_id2();
+    let widget = Flex::column()
+        .with_child(
+            ModularWidget::<_, u32>::new(())
+                .event_fn(|_, ctx, event, _, _| match event {
+                    Event::Command(cmd) if cmd.selector == REFRESH => ctx.request_update(),
+                    _ => (),
+                })
+                .update_fn(|_, _, old_data, data, _| assert!(old_data.same(data)))
+                .record(&requester_rec)
+                .with_id(requester),
+            0.0,
+        )
+        .with_child(
+            Padding::new(0., ModularWidget::<_, u32>::new(()).record(&child_rec))
+                .controller(Refresh)
+                .with_id(host),
+            0.0,
+        )
+        .with_child(ModularWidget::<_, u32>::new(()).record(&sibling_rec), 0.0);
+    let updates =
+        |recording: &Recording| count_records(recording, |r| matches!(r, Record::Update(_)));
+
+    Harness::create(1u32, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        for recording in &[&requester_rec, &child_rec, &sibling_rec] {
+            recording.clear();
+        }
+
+        harness.submit_command(REFRESH, requester);
+        assert_eq!(updates(&requester_rec), 1);
+        assert_eq!(updates(&sibling_rec), 0);
+
+        // the descendants of the widget that asked are updated too
+        harness.submit_command(REFRESH, host);
+        assert_eq!(updates(&child_rec), 1);
+        assert_eq!(updates(&requester_rec), 0);
+        assert_eq!(updates(&sibling_rec), 0);
+
+        // and only once
+        harness.submit_command(NOTHING, Target::Auto);
+        assert_eq!(updates(&requester_rec), 0);
+        assert_eq!(updates(&child_rec), 0);
+    });
+}
+
+#[test]
+fn commands_are_routed_by_their_targets() {
+    use crate::ext_event::ExtEventHost;
+    use crate::shell::WinHandler;
+    use crate::win_handler::{AppHandler, AppState, EXT_EVENT_IDLE_TOKEN};
+
+    const PING: Selector = Selector::new("druid-tests.ping");
+    const ECHO: Selector = Selector::new("druid-tests.echo");
+
+    // each widget logs the commands it sees, and answers an echo by sending
+    // a ping, with one more, to its own window
+    fn recorder(
+        name: &'static str,
+        id: WidgetId,
+        log: Rc<RefCell<Vec<String>>>,
+    ) -> impl Widget<bool> {
+        ModularWidget::new(())
+            .event_fn(move |_, ctx, event, _: &mut bool, _| {
+                if let Event::Command(cmd) = event {
+                    if let Ok(value) = cmd.get_object::<u32>() {
+                        log.borrow_mut().push(format!("{} {}", name, value));
+                        if cmd.selector == ECHO {
+                            ctx.submit_command(Command::new(PING, value + 1), Target::Auto);
+                        }
+                    }
+                }
+            })
+            .with_id(id)
+    }
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let host = ExtEventHost::new();
+    let sink = host.make_sink();
+    let delegate = CommandRecorder(received.clone());
+    let state = AppState::new(false, theme::init(), Some(Box::new(delegate)), host);
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let window = |widgets: [(&'static str, WidgetId); 2]| {
+        let log = log.clone();
+        WindowDesc::new(move || {
+            widgets.iter().fold(Flex::row(), |row, &(name, id)| {
+                row.with_child(recorder(name, id, log.clone()), 0.0)
+            })
+        })
+    };
+    let (id_1a, id_1b, id_2a) = widget_id3();
+    let id_2b = WidgetId::next();
+    let window_1 = window([("1a", id_1a), ("1b", id_1b)]);
+    let window_2 = window([("2a", id_2a), ("2b", id_2b)]);
+    let (win_1, win_2) = (window_1.id, window_2.id);
+    state.add_window(win_1, window_1);
+    state.add_window(win_2, window_2);
+    let mut handler_1 = DruidHandler::new_shared(state.clone(), win_1);
+    let mut handler_2 = DruidHandler::new_shared(state.clone(), win_2);
+    handler_1.connect(&WindowHandle::default());
+    handler_2.connect(&WindowHandle::default());
+    handler_1.got_focus();
+
+    let mut app_handler = AppHandler::new(state.clone());
+    let mut deliver = |target: Target, sel: Selector, value: u32| {
+        log.borrow_mut().clear();
+        received.borrow_mut().clear();
+        sink.submit_command(sel, value, target).unwrap();
+        crate::shell::AppHandler::idle(&mut app_handler, EXT_EVENT_IDLE_TOKEN);
+        let seen = log.borrow().clone();
+        (received.borrow().clone(), seen)
+    };
+
+    // a global command is only for the delegate
+    let (delegate, widgets) = deliver(Target::Global, PING, 1);
+    assert_eq!(delegate, vec![(Target::Global, 1)]);
+    assert!(widgets.is_empty());
+
+    // a window's command goes to every widget in it, and no others
+    let (delegate, widgets) = deliver(win_2.into(), PING, 2);
+    assert_eq!(delegate, vec![(Target::Window(win_2), 2)]);
+    assert_eq!(widgets, vec!["2a 2", "2b 2"]);
+
+    // a widget's command reaches it in a window that doesn't have focus
+    let (delegate, widgets) = deliver(id_2b.into(), PING, 3);
+    assert_eq!(delegate, vec![(Target::Widget(id_2b), 3)]);
+    assert_eq!(widgets, vec!["2b 3"]);
+
+    // the sink has no window, so its automatic target is global
+    let (delegate, widgets) = deliver(Target::Auto, PING, 4);
+    assert_eq!(delegate, vec![(Target::Global, 4)]);
+    assert!(widgets.is_empty());
+
+    // a widget's automatic target is its own window
+    let (delegate, widgets) = deliver(id_1b.into(), ECHO, 5);
+    assert_eq!(
+        delegate,
+        vec![(Target::Widget(id_1b), 5), (Target::Window(win_1), 6)]
+    );
+    assert_eq!(widgets, vec!["1b 5", "1a 6", "1b 6"]);
+}
+
+#[test]
+fn content_scrolled_under_the_mouse_becomes_hot() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut rows = Flex::column();
+    for i in 0..40 {
+        let row: ModularWidget<_, ()> = ModularWidget::new(log.clone())
+            .lifecycle_fn(move |log, _ctx, event, _data, _env| match event {
+                LifeCycle::HotChanged(hot) => log.borrow_mut().push(format!("{} hot {}", i, hot)),
+                LifeCycle::WindowOriginChanged(origin) if i == 0 => {
+                    log.borrow_mut().push(format!("0 at {}", origin.y))
+                }
+                _ => (),
+            })
+            .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain(Size::new(100., 20.)));
+        rows.add_child(row, 0.0);
+    }
+
+    Harness::create((), Scroll::new(rows).vertical(), |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // the mouse is over the third row
+        let pos = Point::new(50., 50.);
+        harness.event(Event::MouseMoved(make_mouse(pos)));
+        assert_eq!(*log.borrow(), vec!["2 hot true"]);
+        log.borrow_mut().clear();
+
+        // two rows scroll by under it, without it moving
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., 40.),
+            mods: KeyModifiers::default(),
+            window_anchor: pos,
+            local_anchor: pos,
+            phase: ScrollPhase::Changed,
+            momentum_phase: None,
+        }));
+        harness.just_layout();
+        assert_eq!(*log.borrow(), vec!["2 hot false", "0 at -40", "4 hot true"]);
+    });
+}
+
+/// A widget that is scrolled under the mouse becomes hot even if its parent
+/// only becomes hot in the same layout, after the widget was placed.
+#[test]
+fn child_scrolled_under_the_mouse_in_a_new_hot_parent_becomes_hot() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut rows = Flex::column().with_spacer(40.);
+    for i in 0..40 {
+        let row: ModularWidget<_, ()> = ModularWidget::new(log.clone())
+            .lifecycle_fn(move |log, _ctx, event, _data, _env| {
+                if let LifeCycle::HotChanged(hot) = event {
+                    log.borrow_mut().push(format!("{} hot {}", i, hot));
+                }
+            })
+            .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain(Size::new(100., 20.)));
+        rows.add_child(Padding::new(0., row), 0.0);
+    }
+
+    Harness::create((), Scroll::new(rows).vertical(), |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // the mouse is over the spacer, so no row's padding is hot
+        let pos = Point::new(50., 10.);
+        harness.event(Event::MouseMoved(make_mouse(pos)));
+        assert!(log.borrow().is_empty());
+
+        // the first row and its padding scroll under it together
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., 40.),
+            mods: KeyModifiers::default(),
+            window_anchor: pos,
+            local_anchor: pos,
+            phase: ScrollPhase::Changed,
+            momentum_phase: None,
+        }));
+        harness.just_layout();
+        assert_eq!(*log.borrow(), vec!["0 hot true"]);
+    });
+}
+
+#[test]
+#[allow(deprecated)]
+fn child_placed_without_the_context_is_told_its_window_origin() {
+    /// A container that places its child at (30, 20) without the `LayoutCtx`.
+    struct RawPlacer(WidgetPod<(), Box<dyn Widget<()>>>);
+
+    impl Widget<()> for RawPlacer {
+        fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut (), env: &Env) {
+            self.0.event(ctx, event, data, env);
+        }
+
+        fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &(), env: &Env) {
+            self.0.lifecycle(ctx, event, data, env);
+        }
+
+        fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &(), data: &(), env: &Env) {
+            self.0.update(ctx, data, env);
+        }
+
+        fn layout(
+            &mut self,
+            ctx: &mut LayoutCtx,
+            bc: &BoxConstraints,
+            data: &(),
+            env: &Env,
+        ) -> Size {
+            let size = self.0.layout(ctx, &bc.loosen(), data, env);
+            self.0
+                .set_raw_layout_rect(Rect::from_origin_size((30., 20.), size));
+            bc.max()
+        }
+
+        fn paint(&mut self, ctx: &mut PaintCtx, data: &(), env: &Env) {
+            self.0.paint(ctx, data, env);
+        }
+    }
+
+    let origins = Rc::new(RefCell::new(Vec::new()));
+    let child = ModularWidget::new(origins.clone())
+        .lifecycle_fn(|origins, _ctx, event, _data, _env| {
+            if let LifeCycle::WindowOriginChanged(origin) = event {
+                origins.borrow_mut().push(*origin);
+            }
+        })
+        .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain(Size::new(50., 50.)));
+    let widget = RawPlacer(WidgetPod::new(child).boxed()).padding(10.);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        assert_eq!(*origins.borrow(), vec![Point::new(40., 30.)]);
+    });
+}
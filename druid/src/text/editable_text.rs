@@ -52,6 +52,14 @@ pub trait EditableText: Sized {
     /// Get the previous codepoint offset from the given offset, if it exists.
     fn next_codepoint_offset(&self, offset: usize) -> Option<usize>;
 
+    /// Get the offset of the start of the word before the given offset, if
+    /// it isn't at the start of the text.
+    fn prev_word_offset(&self, offset: usize) -> Option<usize>;
+
+    /// Get the offset of the end of the word after the given offset, if it
+    /// isn't at the end of the text.
+    fn next_word_offset(&self, offset: usize) -> Option<usize>;
+
     fn is_empty(&self) -> bool;
 
     fn from_str(s: &str) -> Self;
@@ -111,6 +119,33 @@ impl EditableText for String {
         }
     }
 
+    fn prev_word_offset(&self, from: usize) -> Option<usize> {
+        if from == 0 {
+            return None;
+        }
+        // skip the whitespace before the word, then the word itself
+        let before = self[..from].trim_end();
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(idx, c)| idx + c.len_utf8())
+            .unwrap_or(0);
+        Some(start)
+    }
+
+    fn next_word_offset(&self, from: usize) -> Option<usize> {
+        if from == self.len() {
+            return None;
+        }
+        let after = &self[from..];
+        let word_start = after.len() - after.trim_start().len();
+        let word_len = after[word_start..]
+            .find(char::is_whitespace)
+            .unwrap_or_else(|| after.len() - word_start);
+        Some(from + word_start + word_len)
+    }
+
     fn is_empty(&self) -> bool {
         self.is_empty()
     }
@@ -344,4 +379,17 @@ mod tests {
         assert_eq!(Some(17), a.next_grapheme_offset(9));
         assert_eq!(None, a.next_grapheme_offset(17));
     }
+
+    #[test]
+    fn word_offsets() {
+        let a = String::from("one  två three");
+        assert_eq!(Some(5), a.prev_word_offset(9));
+        assert_eq!(Some(5), a.prev_word_offset(7));
+        assert_eq!(Some(0), a.prev_word_offset(5));
+        assert_eq!(None, a.prev_word_offset(0));
+        assert_eq!(Some(3), a.next_word_offset(0));
+        assert_eq!(Some(9), a.next_word_offset(3));
+        assert_eq!(Some(15), a.next_word_offset(9));
+        assert_eq!(None, a.next_word_offset(15));
+    }
 }
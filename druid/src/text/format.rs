@@ -0,0 +1,119 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Converting values to and from the text that edits them.
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::widget::ValidationError;
+
+/// Converts a value to text for a [`ValueTextBox`], and text back to a value.
+///
+/// [`ValueTextBox`]: widget/struct.ValueTextBox.html
+pub trait Formatter<T> {
+    /// The text that shows `value` when it is not being edited.
+    fn format(&self, value: &T) -> String;
+
+    /// The text that `value` is edited as, when the box gets focus.
+    ///
+    /// By default this is the same as [`format`]; it can differ when the text
+    /// that is shown has decorations that aren't typed, such as a currency
+    /// symbol or separators between thousands.
+    ///
+    /// [`format`]: #tymethod.format
+    fn format_for_editing(&self, value: &T) -> String {
+        self.format(value)
+    }
+
+    /// Check text that is being typed, after each edit.
+    fn validate_partial_input(&self, input: &str) -> Validation;
+
+    /// The value of complete text, or why it isn't one.
+    fn value(&self, input: &str) -> Result<T, ValidationError>;
+}
+
+/// Whether text that is being typed is on the way to being a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Validation {
+    /// The text is a complete value.
+    Complete,
+    /// The text isn't a value yet, but more typing could make it one, as
+    /// with `-` for a number.
+    Partial,
+    /// No more typing can make the text a value.
+    Invalid(ValidationError),
+}
+
+/// A [`Formatter`] for any type that can be parsed and displayed.
+///
+/// Text is a value when it parses; any other text is taken as partial, since
+/// parsing can't tell whether more typing would help.
+///
+/// [`Formatter`]: trait.Formatter.html
+pub struct ParseFormatter<T> {
+    value: PhantomData<fn(&str) -> T>,
+}
+
+impl<T> ParseFormatter<T> {
+    /// Create a formatter that uses `T`'s `Display` and `FromStr`.
+    pub fn new() -> Self {
+        ParseFormatter { value: PhantomData }
+    }
+}
+
+impl<T> Default for ParseFormatter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Formatter<T> for ParseFormatter<T>
+where
+    T: FromStr + Display,
+    T::Err: Display,
+{
+    fn format(&self, value: &T) -> String {
+        value.to_string()
+    }
+
+    fn validate_partial_input(&self, input: &str) -> Validation {
+        match input.parse::<T>() {
+            Ok(_) => Validation::Complete,
+            Err(_) => Validation::Partial,
+        }
+    }
+
+    fn value(&self, input: &str) -> Result<T, ValidationError> {
+        input
+            .parse()
+            .map_err(|e: T::Err| ValidationError::new(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_formatter_takes_unparsed_text_as_partial() {
+        let formatter = ParseFormatter::<f64>::new();
+        assert_eq!(formatter.validate_partial_input("1."), Validation::Complete);
+        assert_eq!(formatter.value("1."), Ok(1.0));
+        assert_eq!(formatter.validate_partial_input("-"), Validation::Partial);
+        assert!(formatter.value("-").is_err());
+        assert_eq!(formatter.format(&-2.5), "-2.5");
+    }
+}
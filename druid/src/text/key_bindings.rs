@@ -0,0 +1,231 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tables that map keys to editing actions.
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::text::{EditableText, Selection};
+use crate::{HotKey, KbKey, KeyEvent, RawMods, SysMods};
+
+/// Something a text editing widget can do in response to a key.
+///
+/// The movements that work on lines move within the visual line in a widget
+/// that wraps its text, and within the whole text otherwise.
+#[derive(Debug, Clone)]
+pub enum EditAction {
+    /// Move the cursor left by one grapheme cluster.
+    MoveLeft,
+    /// Move the cursor right by one grapheme cluster.
+    MoveRight,
+    /// Move the cursor to the start of the word to its left.
+    MoveWordLeft,
+    /// Move the cursor to the end of the word to its right.
+    MoveWordRight,
+    /// Move the cursor to the start of the line.
+    MoveToLineStart,
+    /// Move the cursor to the end of the line.
+    MoveToLineEnd,
+    /// Move the cursor to the line above, if there is more than one line.
+    MoveUp,
+    /// Move the cursor to the line below, if there is more than one line.
+    MoveDown,
+    /// Extend the selection left by one grapheme cluster.
+    SelectLeft,
+    /// Extend the selection right by one grapheme cluster.
+    SelectRight,
+    /// Extend the selection to the start of the word to its left.
+    SelectWordLeft,
+    /// Extend the selection to the end of the word to its right.
+    SelectWordRight,
+    /// Extend the selection to the line above.
+    SelectUp,
+    /// Extend the selection to the line below.
+    SelectDown,
+    /// Select all of the text.
+    SelectAll,
+    /// Delete the selection, or the grapheme cluster before the cursor.
+    DeleteBackward,
+    /// Delete the selection, or the grapheme cluster after the cursor.
+    DeleteForward,
+    /// Copy the selection to the clipboard.
+    Copy,
+    /// Copy the selection to the clipboard, and delete it.
+    Cut,
+    /// Replace the selection with the text on the clipboard.
+    Paste,
+    /// Start a new line. In a widget that holds a single line, this commits
+    /// the text instead.
+    InsertNewline,
+    /// Run a closure on the text and its selection.
+    Custom(CustomAction),
+}
+
+/// A closure run by [`EditAction::Custom`].
+///
+/// [`EditAction::Custom`]: enum.EditAction.html#variant.Custom
+#[derive(Clone)]
+pub struct CustomAction(Arc<dyn Fn(&mut EditBuffer)>);
+
+/// The text being edited and its selection, as given to a [`CustomAction`].
+///
+/// [`CustomAction`]: struct.CustomAction.html
+pub struct EditBuffer<'a> {
+    text: &'a mut String,
+    selection: &'a mut Selection,
+}
+
+/// A table of the [`EditAction`]s that keys perform in a text editing widget.
+///
+/// The [`Default`] table has the usual bindings for the platform. A key that
+/// has no binding is typed into the text, if it is printable.
+///
+/// [`EditAction`]: enum.EditAction.html
+/// [`Default`]: #impl-Default
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: Vec<(HotKey, EditAction)>,
+}
+
+impl EditAction {
+    /// An action that runs `f` on the text and its selection.
+    pub fn custom(f: impl Fn(&mut EditBuffer) + 'static) -> EditAction {
+        EditAction::Custom(CustomAction(Arc::new(f)))
+    }
+}
+
+impl CustomAction {
+    /// Run this action on `buffer`.
+    pub fn run(&self, buffer: &mut EditBuffer) {
+        (self.0)(buffer)
+    }
+}
+
+impl fmt::Debug for CustomAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CustomAction")
+    }
+}
+
+impl<'a> EditBuffer<'a> {
+    pub(crate) fn new(text: &'a mut String, selection: &'a mut Selection) -> Self {
+        EditBuffer { text, selection }
+    }
+
+    /// The text.
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// The selected range of the text, in bytes; it is empty if there is
+    /// only a cursor.
+    pub fn selection(&self) -> Range<usize> {
+        self.selection.range()
+    }
+
+    /// The position of the cursor, which is the active end of the selection.
+    pub fn cursor(&self) -> usize {
+        self.selection.end
+    }
+
+    /// Select from `anchor` to `cursor`, which are offsets in bytes.
+    ///
+    /// Offsets past the end of the text are moved to the end. If either
+    /// offset is not at a codepoint boundary, the selection doesn't change.
+    pub fn set_selection(&mut self, anchor: usize, cursor: usize) {
+        let selection = Selection::new(anchor, cursor).constrain_to(&*self.text);
+        if self.text.cursor(selection.start).is_none() || self.text.cursor(selection.end).is_none()
+        {
+            log::error!("You can't move the cursor there.");
+            return;
+        }
+        *self.selection = selection;
+    }
+
+    /// Replace the selection with `text`, leaving the cursor after it.
+    pub fn replace_selection(&mut self, text: &str) {
+        let selection = self.selection.constrain_to(&*self.text);
+        self.text.edit(selection.range(), text);
+        *self.selection = Selection::caret(selection.min() + text.len());
+    }
+}
+
+impl KeyBindings {
+    /// A table without any bindings, so that every printable key is typed.
+    pub fn empty() -> KeyBindings {
+        KeyBindings {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Builder-style variant of `bind`.
+    pub fn with_binding(mut self, key: HotKey, action: EditAction) -> Self {
+        self.bind(key, action);
+        self
+    }
+
+    /// Bind `key` to `action`, taking precedence over any earlier binding
+    /// of the same key.
+    pub fn bind(&mut self, key: HotKey, action: EditAction) {
+        self.bindings.push((key, action));
+    }
+
+    /// The action for a key event, if it has a binding.
+    pub fn action(&self, event: &KeyEvent) -> Option<&EditAction> {
+        // later bindings take precedence
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(key, _)| key.matches(event))
+            .map(|(_, action)| action)
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use EditAction::*;
+        KeyBindings::empty()
+            .with_binding(HotKey::new(SysMods::Cmd, "a"), SelectAll)
+            .with_binding(HotKey::new(SysMods::Cmd, "c"), Copy)
+            .with_binding(HotKey::new(SysMods::Cmd, "x"), Cut)
+            .with_binding(HotKey::new(SysMods::Cmd, "v"), Paste)
+            .with_binding(HotKey::new(SysMods::Cmd, KbKey::ArrowLeft), MoveToLineStart)
+            .with_binding(HotKey::new(SysMods::None, KbKey::Home), MoveToLineStart)
+            .with_binding(HotKey::new(SysMods::Cmd, KbKey::ArrowRight), MoveToLineEnd)
+            .with_binding(HotKey::new(SysMods::None, KbKey::End), MoveToLineEnd)
+            .with_binding(HotKey::new(RawMods::Alt, KbKey::ArrowLeft), MoveWordLeft)
+            .with_binding(HotKey::new(RawMods::Alt, KbKey::ArrowRight), MoveWordRight)
+            .with_binding(
+                HotKey::new(RawMods::AltShift, KbKey::ArrowLeft),
+                SelectWordLeft,
+            )
+            .with_binding(
+                HotKey::new(RawMods::AltShift, KbKey::ArrowRight),
+                SelectWordRight,
+            )
+            .with_binding(HotKey::new(SysMods::Shift, KbKey::ArrowLeft), SelectLeft)
+            .with_binding(HotKey::new(SysMods::Shift, KbKey::ArrowRight), SelectRight)
+            .with_binding(HotKey::new(SysMods::Shift, KbKey::ArrowUp), SelectUp)
+            .with_binding(HotKey::new(SysMods::Shift, KbKey::ArrowDown), SelectDown)
+            .with_binding(HotKey::new(SysMods::None, KbKey::ArrowLeft), MoveLeft)
+            .with_binding(HotKey::new(SysMods::None, KbKey::ArrowRight), MoveRight)
+            .with_binding(HotKey::new(SysMods::None, KbKey::ArrowUp), MoveUp)
+            .with_binding(HotKey::new(SysMods::None, KbKey::ArrowDown), MoveDown)
+            .with_binding(HotKey::new(SysMods::None, KbKey::Backspace), DeleteBackward)
+            .with_binding(HotKey::new(SysMods::None, KbKey::Delete), DeleteForward)
+            .with_binding(HotKey::new(SysMods::None, KbKey::Enter), InsertNewline)
+    }
+}
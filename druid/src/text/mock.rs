@@ -0,0 +1,145 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Text with fixed metrics, for testing code that measures text.
+
+use crate::kurbo::Point;
+use crate::piet::{
+    Error, Font, FontBuilder, HitTestMetrics, HitTestPoint, HitTestTextPosition, Text, TextLayout,
+    TextLayoutBuilder,
+};
+
+/// Text whose characters are all the same width, whatever the font.
+///
+/// This is also its own font, and font builder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MockText {
+    glyph_width: f64,
+}
+
+/// A single line of text laid out by [`MockText`].
+///
+/// [`MockText`]: struct.MockText.html
+#[derive(Debug, Clone)]
+pub(crate) struct MockTextLayout {
+    text: String,
+    glyph_width: f64,
+}
+
+impl MockText {
+    /// Mock text whose characters are each `glyph_width` wide.
+    pub fn new(glyph_width: f64) -> MockText {
+        MockText { glyph_width }
+    }
+}
+
+impl MockTextLayout {
+    /// The number of characters before `offset`, or before the character
+    /// that it is in the middle of.
+    fn chars_before(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        self.text[..offset].chars().count()
+    }
+}
+
+impl Text for MockText {
+    type FontBuilder = MockText;
+    type Font = MockText;
+    type TextLayoutBuilder = MockTextLayout;
+    type TextLayout = MockTextLayout;
+
+    fn new_font_by_name(&mut self, _name: &str, _size: f64) -> Self::FontBuilder {
+        *self
+    }
+
+    fn new_text_layout(&mut self, font: &Self::Font, text: &str) -> Self::TextLayoutBuilder {
+        MockTextLayout {
+            text: text.to_owned(),
+            glyph_width: font.glyph_width,
+        }
+    }
+}
+
+impl Font for MockText {}
+
+impl FontBuilder for MockText {
+    type Out = MockText;
+
+    fn build(self) -> Result<Self::Out, Error> {
+        Ok(self)
+    }
+}
+
+impl TextLayoutBuilder for MockTextLayout {
+    type Out = MockTextLayout;
+
+    fn build(self) -> Result<Self::Out, Error> {
+        Ok(self)
+    }
+}
+
+impl TextLayout for MockTextLayout {
+    fn width(&self) -> f64 {
+        self.text.chars().count() as f64 * self.glyph_width
+    }
+
+    fn hit_test_point(&self, point: Point) -> HitTestPoint {
+        let glyph = (point.x / self.glyph_width).round().max(0.0) as usize;
+        let text_position = self
+            .text
+            .char_indices()
+            .nth(glyph)
+            .map(|(offset, _)| offset)
+            .unwrap_or_else(|| self.text.len());
+        HitTestPoint {
+            metrics: HitTestMetrics { text_position },
+            is_inside: point.x >= 0.0 && point.x <= self.width(),
+        }
+    }
+
+    fn hit_test_text_position(&self, text_position: usize) -> Option<HitTestTextPosition> {
+        let x = self.chars_before(text_position) as f64 * self.glyph_width;
+        Some(HitTestTextPosition {
+            point: Point::new(x, 0.0),
+            metrics: HitTestMetrics {
+                text_position: text_position.min(self.text.len()),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_layout_metrics() {
+        let mut text = MockText::new(10.0);
+        let font = text.new_font_by_name("Any", 20.0).build().unwrap();
+        let layout = text.new_text_layout(&font, "añb").build().unwrap();
+        assert_eq!(layout.width(), 30.0);
+        // 'ñ' is two bytes
+        let end = layout.hit_test_text_position(4).unwrap();
+        assert_eq!((end.point.x, end.metrics.text_position), (30.0, 4));
+        let hit = layout.hit_test_point(Point::new(14.0, 5.0));
+        assert_eq!(hit.metrics.text_position, 1);
+        assert!(hit.is_inside);
+        let past = layout.hit_test_point(Point::new(100.0, 5.0));
+        assert_eq!(past.metrics.text_position, 4);
+        assert!(!past.is_inside);
+    }
+}
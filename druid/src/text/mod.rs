@@ -23,5 +23,16 @@ pub use self::selection::Selection;
 pub mod movement;
 pub use self::movement::{movement, Movement};
 
+mod key_bindings;
+pub use self::key_bindings::{CustomAction, EditAction, EditBuffer, KeyBindings};
+
+mod format;
+pub use self::format::{Formatter, ParseFormatter, Validation};
+
 pub mod backspace;
 pub use self::backspace::offset_for_delete_backwards;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+pub(crate) use self::mock::MockText;
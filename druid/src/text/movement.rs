@@ -23,6 +23,10 @@ pub enum Movement {
     Left,
     /// Move to the right by one grapheme cluster.
     Right,
+    /// Move to the start of the word to the left.
+    LeftWord,
+    /// Move to the end of the word to the right.
+    RightWord,
     /// Move to left end of visible line.
     LeftOfLine,
     /// Move to right end of visible line.
@@ -54,6 +58,20 @@ pub fn movement(m: Movement, s: Selection, text: &impl EditableText, modify: boo
                 s.max()
             }
         }
+        Movement::LeftWord => {
+            if s.is_caret() || modify {
+                text.prev_word_offset(s.end).unwrap_or(0)
+            } else {
+                s.min()
+            }
+        }
+        Movement::RightWord => {
+            if s.is_caret() || modify {
+                text.next_word_offset(s.end).unwrap_or(s.end)
+            } else {
+                s.max()
+            }
+        }
 
         Movement::LeftOfLine => 0,
         Movement::RightOfLine => text.len(),
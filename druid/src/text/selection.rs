@@ -73,10 +73,15 @@ impl Selection {
     }
 
     /// Constrain selection to be not greater than input string
-    pub fn constrain_to(mut self, s: &impl EditableText) -> Self {
-        let s_len = s.len();
-        self.start = min(self.start, s_len);
-        self.end = min(self.end, s_len);
+    pub fn constrain_to(self, s: &impl EditableText) -> Self {
+        self.constrain_to_len(s.len())
+    }
+
+    /// Constrain selection to be not greater than `len`, the length of the
+    /// text in bytes.
+    pub(crate) fn constrain_to_len(mut self, len: usize) -> Self {
+        self.start = min(self.start, len);
+        self.end = min(self.end, len);
         self
     }
 }
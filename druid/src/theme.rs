@@ -14,14 +14,17 @@
 
 //! Theme keys and initial values.
 
+use crate::kurbo::Insets;
 use crate::piet::Color;
 
-use crate::{Env, Key};
+use crate::{Env, Key, LayoutDirection};
 
 pub const WINDOW_BACKGROUND_COLOR: Key<Color> = Key::new("window_background_color");
 
 pub const LABEL_COLOR: Key<Color> = Key::new("label_color");
 pub const PLACEHOLDER_COLOR: Key<Color> = Key::new("placeholder_color");
+/// The color of text in disabled widgets.
+pub const DISABLED_TEXT_COLOR: Key<Color> = Key::new("disabled_text_color");
 
 pub const PRIMARY_LIGHT: Key<Color> = Key::new("primary_light");
 pub const PRIMARY_DARK: Key<Color> = Key::new("primary_dark");
@@ -32,12 +35,18 @@ pub const FOREGROUND_LIGHT: Key<Color> = Key::new("foreground_light");
 pub const FOREGROUND_DARK: Key<Color> = Key::new("foreground_dark");
 pub const BUTTON_DARK: Key<Color> = Key::new("button_dark");
 pub const BUTTON_LIGHT: Key<Color> = Key::new("button_light");
+pub const DISABLED_BUTTON_DARK: Key<Color> = Key::new("disabled_button_dark");
+pub const DISABLED_BUTTON_LIGHT: Key<Color> = Key::new("disabled_button_light");
 pub const BUTTON_BORDER_RADIUS: Key<f64> = Key::new("button_radius");
 pub const BUTTON_BORDER_WIDTH: Key<f64> = Key::new("button_border_width");
+/// The space between the edge of a `Button` and its child.
+pub const BUTTON_PADDING: Key<Insets> = Key::new("button_padding");
 pub const BORDER_DARK: Key<Color> = Key::new("border");
 pub const BORDER_LIGHT: Key<Color> = Key::new("border_light");
 pub const SELECTION_COLOR: Key<Color> = Key::new("selection_color");
 pub const CURSOR_COLOR: Key<Color> = Key::new("cursor_color");
+/// The color for showing that something is wrong, such as text that isn't valid.
+pub const ERROR_COLOR: Key<Color> = Key::new("error_color");
 
 pub const FONT_NAME: Key<&str> = Key::new("font_name");
 pub const TEXT_SIZE_NORMAL: Key<f64> = Key::new("text_size_normal");
@@ -48,6 +57,14 @@ pub const BASIC_WIDGET_HEIGHT: Key<f64> = Key::new("basic_widget_height");
 pub const WIDE_WIDGET_WIDTH: Key<f64> = Key::new("druid.widgets.long-widget-width");
 pub const BORDERED_WIDGET_HEIGHT: Key<f64> = Key::new("bordered_widget_height");
 
+/// The space between a control, such as the box of a `Checkbox` or the
+/// circle of a `Radio`, and its label.
+pub const WIDGET_CONTROL_COMPONENT_PADDING: Key<f64> = Key::new("widget_control_component_padding");
+/// The width and height of the box of a `Checkbox`; the checkmark is scaled
+/// to fit it.
+pub const CHECKBOX_SIZE: Key<f64> = Key::new("checkbox_size");
+pub const CHECKBOX_BORDER_RADIUS: Key<f64> = Key::new("checkbox_radius");
+
 pub const TEXTBOX_BORDER_RADIUS: Key<f64> = Key::new("textbox_radius");
 
 pub const SCROLL_BAR_COLOR: Key<Color> = Key::new("scroll_bar_color");
@@ -59,12 +76,42 @@ pub const SCROLL_BAR_PAD: Key<f64> = Key::new("scroll_bar_pad");
 pub const SCROLL_BAR_RADIUS: Key<f64> = Key::new("scroll_bar_radius");
 pub const SCROLL_BAR_EDGE_WIDTH: Key<f64> = Key::new("scroll_bar_edge_width");
 
-/// An initial theme.
+/// Whether a `Scroll` that can't scroll any further passes the wheel on to
+/// an enclosing `Scroll`.
+///
+/// When this is `false`, a `Scroll` keeps every wheel event over it. This can
+/// be overridden for a single `Scroll` with [`Scroll::scroll_chaining`].
+///
+/// [`Scroll::scroll_chaining`]: ../widget/struct.Scroll.html#method.scroll_chaining
+pub const SCROLL_CHAINING: Key<bool> = Key::new("scroll_chaining");
+
+/// Whether widgets should animate their transitions.
+///
+/// When this is `false`, animated widgets go to their final state at once.
+/// `AppLauncher` sets this from the system's reduced-motion setting, and
+/// updates it when that setting changes. See also [`Env::transition_duration`].
+///
+/// [`Env::transition_duration`]: ../struct.Env.html#method.transition_duration
+pub const ANIMATIONS_ENABLED: Key<bool> = Key::new("animations_enabled");
+
+/// The direction of layout, which is right to left for languages like
+/// Arabic. See [`LayoutDirection`].
+///
+/// [`LayoutDirection`]: ../enum.LayoutDirection.html
+pub const LAYOUT_DIRECTION: Key<LayoutDirection> = Key::new("layout_direction");
+
+/// An initial theme, with a dark palette.
+///
+/// This sets every key that the built-in widgets read. Any of them can be
+/// overridden with [`AppLauncher::configure_env`].
+///
+/// [`AppLauncher::configure_env`]: ../struct.AppLauncher.html#method.configure_env
 pub fn init() -> Env {
     let mut env = Env::default()
         .adding(WINDOW_BACKGROUND_COLOR, Color::rgb8(0x29, 0x29, 0x29))
         .adding(LABEL_COLOR, Color::rgb8(0xf0, 0xf0, 0xea))
         .adding(PLACEHOLDER_COLOR, Color::rgb8(0x80, 0x80, 0x80))
+        .adding(DISABLED_TEXT_COLOR, Color::rgb8(0x6e, 0x6e, 0x6e))
         .adding(PRIMARY_LIGHT, Color::rgb8(0x5c, 0xc4, 0xff))
         .adding(PRIMARY_DARK, Color::rgb8(0x00, 0x8d, 0xdd))
         .adding(PROGRESS_BAR_RADIUS, 4.)
@@ -74,17 +121,24 @@ pub fn init() -> Env {
         .adding(FOREGROUND_DARK, Color::rgb8(0xbf, 0xbf, 0xbf))
         .adding(BUTTON_DARK, Color::BLACK)
         .adding(BUTTON_LIGHT, Color::rgb8(0x21, 0x21, 0x21))
+        .adding(DISABLED_BUTTON_DARK, Color::rgb8(0x28, 0x28, 0x28))
+        .adding(DISABLED_BUTTON_LIGHT, Color::rgb8(0x31, 0x31, 0x31))
         .adding(BUTTON_BORDER_RADIUS, 4.)
         .adding(BUTTON_BORDER_WIDTH, 2.)
+        .adding(BUTTON_PADDING, Insets::uniform_xy(8., 2.))
         .adding(BORDER_DARK, Color::rgb8(0x3a, 0x3a, 0x3a))
         .adding(BORDER_LIGHT, Color::rgb8(0xa1, 0xa1, 0xa1))
         .adding(SELECTION_COLOR, Color::rgb8(0xf3, 0x00, 0x21))
         .adding(CURSOR_COLOR, Color::WHITE)
+        .adding(ERROR_COLOR, Color::rgb8(0xe0, 0x3c, 0x3c))
         .adding(TEXT_SIZE_NORMAL, 15.0)
         .adding(TEXT_SIZE_LARGE, 24.0)
         .adding(BASIC_WIDGET_HEIGHT, 18.0)
         .adding(WIDE_WIDGET_WIDTH, 100.)
         .adding(BORDERED_WIDGET_HEIGHT, 24.0)
+        .adding(WIDGET_CONTROL_COMPONENT_PADDING, 8.0)
+        .adding(CHECKBOX_SIZE, 18.0)
+        .adding(CHECKBOX_BORDER_RADIUS, 2.)
         .adding(TEXTBOX_BORDER_RADIUS, 2.)
         .adding(SCROLL_BAR_COLOR, Color::rgb8(0xff, 0xff, 0xff))
         .adding(SCROLL_BAR_BORDER_COLOR, Color::rgb8(0x77, 0x77, 0x77))
@@ -93,7 +147,10 @@ pub fn init() -> Env {
         .adding(SCROLL_BAR_WIDTH, 8.)
         .adding(SCROLL_BAR_PAD, 2.)
         .adding(SCROLL_BAR_RADIUS, 5.)
-        .adding(SCROLL_BAR_EDGE_WIDTH, 1.);
+        .adding(SCROLL_BAR_EDGE_WIDTH, 1.)
+        .adding(SCROLL_CHAINING, true)
+        .adding(ANIMATIONS_ENABLED, true)
+        .adding(LAYOUT_DIRECTION, LayoutDirection::Ltr);
 
     #[cfg(target_os = "windows")]
     {
@@ -111,3 +168,128 @@ pub fn init() -> Env {
     }
     env
 }
+
+/// The initial theme, with a light palette.
+///
+/// This is [`init`] with the colors replaced by [`apply_light`].
+///
+/// [`init`]: fn.init.html
+/// [`apply_light`]: fn.apply_light.html
+pub fn light() -> Env {
+    let mut env = init();
+    apply_light(&mut env);
+    env
+}
+
+/// Replace the colors in `env` with those of the light theme, leaving the
+/// other values alone.
+///
+/// This is meant for use in [`AppLauncher::configure_env`], where it keeps
+/// the values that the launcher sets from the system, such as
+/// [`ANIMATIONS_ENABLED`]:
+///
+/// ```no_run
+/// # use druid::{theme, AppLauncher, WindowDesc, widget::Label};
+/// # let window = WindowDesc::new(|| Label::new("hello"));
+/// AppLauncher::with_window(window)
+///     .configure_env(|env, _| theme::apply_light(env))
+///     .launch(())
+///     .expect("launch failed");
+/// ```
+///
+/// [`AppLauncher::configure_env`]: ../struct.AppLauncher.html#method.configure_env
+/// [`ANIMATIONS_ENABLED`]: constant.ANIMATIONS_ENABLED.html
+pub fn apply_light(env: &mut Env) {
+    env.set(WINDOW_BACKGROUND_COLOR, Color::rgb8(0xf2, 0xf2, 0xf2));
+    env.set(LABEL_COLOR, Color::rgb8(0x1e, 0x1e, 0x1e));
+    env.set(PLACEHOLDER_COLOR, Color::rgb8(0x8a, 0x8a, 0x8a));
+    env.set(DISABLED_TEXT_COLOR, Color::rgb8(0xa0, 0xa0, 0xa0));
+    env.set(PRIMARY_LIGHT, Color::rgb8(0x1f, 0x9c, 0xf0));
+    env.set(PRIMARY_DARK, Color::rgb8(0x00, 0x6f, 0xbe));
+    env.set(BACKGROUND_LIGHT, Color::rgb8(0xff, 0xff, 0xff));
+    env.set(BACKGROUND_DARK, Color::rgb8(0xe6, 0xe6, 0xe6));
+    env.set(FOREGROUND_LIGHT, Color::rgb8(0x5a, 0x5a, 0x5a));
+    env.set(FOREGROUND_DARK, Color::rgb8(0x30, 0x30, 0x30));
+    env.set(BUTTON_DARK, Color::rgb8(0xd4, 0xd4, 0xd4));
+    env.set(BUTTON_LIGHT, Color::rgb8(0xf7, 0xf7, 0xf7));
+    env.set(DISABLED_BUTTON_DARK, Color::rgb8(0xe0, 0xe0, 0xe0));
+    env.set(DISABLED_BUTTON_LIGHT, Color::rgb8(0xea, 0xea, 0xea));
+    env.set(BORDER_DARK, Color::rgb8(0xc8, 0xc8, 0xc8));
+    env.set(BORDER_LIGHT, Color::rgb8(0x7a, 0x7a, 0x7a));
+    env.set(SELECTION_COLOR, Color::rgb8(0xa6, 0xcc, 0xff));
+    env.set(CURSOR_COLOR, Color::BLACK);
+    env.set(ERROR_COLOR, Color::rgb8(0xc6, 0x28, 0x28));
+    env.set(SCROLL_BAR_COLOR, Color::rgb8(0x40, 0x40, 0x40));
+    env.set(SCROLL_BAR_BORDER_COLOR, Color::rgb8(0xcc, 0xcc, 0xcc));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::widget::{
+        Align, Button, Checkbox, Flex, Label, Scroll, SizedBox, Switch, TextBox, WidgetExt,
+    };
+    use crate::{theme, UnitPoint, WidgetId};
+
+    #[test]
+    fn env_overrides_widget_metrics() {
+        let id = WidgetId::next();
+        let widget = Align::new(UnitPoint::TOP_LEFT, Checkbox::new("hello").with_id(id));
+
+        Harness::create(false, widget, |harness| {
+            harness.configure_env(|env, _| {
+                env.set(theme::BASIC_WIDGET_HEIGHT, 40.);
+                // a key can take the value of another
+                env.set_from(theme::BORDER_DARK, theme::PRIMARY_DARK);
+            });
+            harness.send_initial_events();
+            harness.just_layout();
+            assert_eq!(harness.get_state(id).layout_rect.height(), 40.);
+            harness.paint();
+        });
+
+        let white = Color::WHITE.as_rgba_u32();
+        let env = theme::init().adding_from(theme::BORDER_LIGHT, Color::WHITE);
+        assert_eq!(env.get(theme::BORDER_LIGHT).as_rgba_u32(), white);
+        let env = env.adding_from(theme::SELECTION_COLOR, theme::BORDER_LIGHT);
+        assert_eq!(env.get(theme::SELECTION_COLOR).as_rgba_u32(), white);
+    }
+
+    #[test]
+    fn light_theme_covers_widgets() {
+        let dark = theme::init();
+        let light = theme::light();
+        assert_ne!(
+            light.get(theme::LABEL_COLOR).as_rgba_u32(),
+            dark.get(theme::LABEL_COLOR).as_rgba_u32()
+        );
+        assert_eq!(
+            light.get(theme::BASIC_WIDGET_HEIGHT),
+            dark.get(theme::BASIC_WIDGET_HEIGHT)
+        );
+
+        // painting reads every key that these widgets use
+        let widget = Flex::column()
+            .with_child(Label::new("label"), 0.0)
+            .with_child(Button::new("button", Button::noop), 0.0)
+            .with_child(Checkbox::new("checkbox"), 0.0)
+            .with_child(Switch::new(), 0.0)
+            .with_child(Scroll::new(SizedBox::empty().height(1000.)), 1.0);
+
+        Harness::create(true, widget, |harness| {
+            harness.configure_env(|env, _| theme::apply_light(env));
+            harness.send_initial_events();
+            harness.just_layout();
+            harness.paint();
+        });
+
+        let widget = TextBox::new().with_placeholder("placeholder");
+        Harness::create(String::new(), widget, |harness| {
+            harness.configure_env(|env, _| theme::apply_light(env));
+            harness.send_initial_events();
+            harness.just_layout();
+            harness.paint();
+        })
+    }
+}
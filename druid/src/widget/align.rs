@@ -16,15 +16,32 @@
 
 use crate::kurbo::{Rect, Size};
 use crate::{
-    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    UpdateCtx, Widget, WidgetPod,
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LayoutDirection, LifeCycle,
+    LifeCycleCtx, PaintCtx, UpdateCtx, Widget, WidgetPod,
 };
 
 use crate::piet::UnitPoint;
 
 /// A widget that aligns its child.
+///
+/// The child is positioned within the largest size the constraints allow.
+/// On an axis where the constraints are unbounded, such as the scrolling
+/// axis of a [`Scroll`], there is no extra space, and the `Align` takes
+/// the child's size on that axis.
+///
+/// An alignment is in terms of left and right, unless it is made with
+/// [`directional`], [`leading`] or [`trailing`], when it is mirrored if the
+/// [`LayoutDirection`] is right to left.
+///
+/// [`Scroll`]: struct.Scroll.html
+/// [`directional`]: #method.directional
+/// [`leading`]: #method.leading
+/// [`trailing`]: #method.trailing
+/// [`LayoutDirection`]: ../enum.LayoutDirection.html
 pub struct Align<T> {
     align: UnitPoint,
+    /// Whether the x of `align` is from the leading edge, not the left.
+    directional: bool,
     child: WidgetPod<T, Box<dyn Widget<T>>>,
     width_factor: Option<f64>,
     height_factor: Option<f64>,
@@ -34,17 +51,40 @@ impl<T> Align<T> {
     /// Create widget with alignment.
     ///
     /// Note that the `align` parameter is specified as a `UnitPoint` in
-    /// terms of left and right. This is inadequate for bidi-aware layout
-    /// and thus the API will change when druid gains bidi capability.
+    /// terms of left and right; see [`directional`] for one that follows
+    /// the layout direction.
+    ///
+    /// [`directional`]: #method.directional
     pub fn new(align: UnitPoint, child: impl Widget<T> + 'static) -> Align<T> {
         Align {
             align,
+            directional: false,
             child: WidgetPod::new(child).boxed(),
             width_factor: None,
             height_factor: None,
         }
     }
 
+    /// Create widget with an alignment whose x is from the leading edge, so
+    /// that `UnitPoint::LEFT` is the right when the layout is right to left.
+    pub fn directional(align: UnitPoint, child: impl Widget<T> + 'static) -> Align<T> {
+        Align {
+            directional: true,
+            ..Align::new(align, child)
+        }
+    }
+
+    /// Create widget aligned to the leading edge, which is the left, or the
+    /// right when the layout is right to left.
+    pub fn leading(child: impl Widget<T> + 'static) -> Align<T> {
+        Align::directional(UnitPoint::LEFT, child)
+    }
+
+    /// Create widget aligned to the trailing edge.
+    pub fn trailing(child: impl Widget<T> + 'static) -> Align<T> {
+        Align::directional(UnitPoint::RIGHT, child)
+    }
+
     /// Create centered widget.
     pub fn centered(child: impl Widget<T> + 'static) -> Align<T> {
         Align::new(UnitPoint::CENTER, child)
@@ -64,6 +104,7 @@ impl<T> Align<T> {
     pub fn horizontal(align: UnitPoint, child: impl Widget<T> + 'static) -> Align<T> {
         Align {
             align,
+            directional: false,
             child: WidgetPod::new(child).boxed(),
             width_factor: None,
             height_factor: Some(1.0),
@@ -74,6 +115,7 @@ impl<T> Align<T> {
     pub fn vertical(align: UnitPoint, child: impl Widget<T> + 'static) -> Align<T> {
         Align {
             align,
+            directional: false,
             child: WidgetPod::new(child).boxed(),
             width_factor: Some(1.0),
             height_factor: None,
@@ -122,14 +164,20 @@ impl<T: Data> Widget<T> for Align<T> {
         my_size = bc.constrain(my_size);
         let extra_width = (my_size.width - size.width).max(0.);
         let extra_height = (my_size.height - size.height).max(0.);
-        let origin = self
+        let mut origin = self
             .align
             .resolve(Rect::new(0., 0., extra_width, extra_height));
+        if self.directional && LayoutDirection::from_env(env).is_rtl() {
+            origin.x = extra_width - origin.x;
+        }
         self.child
-            .set_layout_rect(Rect::from_origin_size(origin, size));
+            .set_layout_rect(layout_ctx, data, env, Rect::from_origin_size(origin, size));
 
         let my_insets = self.child.compute_parent_paint_insets(my_size);
         layout_ctx.set_paint_insets(my_insets);
+        let child_bottom = origin.y + size.height;
+        layout_ctx
+            .set_baseline_offset(my_size.height - child_bottom + self.child.baseline_offset());
         my_size
     }
 
@@ -137,3 +185,106 @@ impl<T: Data> Widget<T> for Align<T> {
         self.child.paint_with_offset(ctx, data, env);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Scroll, WidgetExt};
+    use crate::{Point, WidgetId};
+
+    #[test]
+    fn align_positions_child() {
+        let id = WidgetId::next();
+        type MakeAlign = fn(WidgetId) -> Box<dyn Widget<()>>;
+        // origins are relative to the align, which is 400x400 where bounded
+        let cases: [(MakeAlign, Point); 8] = [
+            (
+                |id| Box::new(Align::centered(fixed_box(id))),
+                Point::new(150., 175.),
+            ),
+            (
+                |id| Box::new(Align::left(fixed_box(id))),
+                Point::new(0., 175.),
+            ),
+            (
+                |id| Box::new(Align::right(fixed_box(id))),
+                Point::new(300., 175.),
+            ),
+            (
+                |id| Box::new(Align::horizontal(UnitPoint::RIGHT, fixed_box(id)).align_left()),
+                Point::new(300., 0.),
+            ),
+            (
+                |id| Box::new(Align::vertical(UnitPoint::BOTTOM, fixed_box(id)).align_left()),
+                Point::new(0., 350.),
+            ),
+            // unbounded on the scrolling axis, so the align shrink-wraps that axis
+            (
+                |id| Box::new(Scroll::new(Align::centered(fixed_box(id))).vertical()),
+                Point::new(150., 0.),
+            ),
+            (
+                |id| Box::new(Scroll::new(Align::right(fixed_box(id))).horizontal()),
+                Point::new(0., 175.),
+            ),
+            (
+                |id| Box::new(Scroll::new(Align::centered(fixed_box(id)))),
+                Point::ORIGIN,
+            ),
+        ];
+
+        for (i, (make, origin)) in cases.iter().enumerate() {
+            Harness::create((), make(id), |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                let rect = harness.get_state(id).layout_rect;
+                assert_eq!(rect.origin(), *origin, "case {}", i);
+                assert_eq!(rect.size(), Size::new(100., 50.), "case {}", i);
+            });
+        }
+    }
+
+    #[test]
+    fn expand_fills_bounded_axes() {
+        let id = WidgetId::next();
+        type MakeExpand = fn(WidgetId) -> Box<dyn Widget<()>>;
+        let cases: [(MakeExpand, Size); 5] = [
+            (
+                |id| Box::new(fixed_box(id).expand().center()),
+                Size::new(400., 400.),
+            ),
+            (
+                |id| Box::new(fixed_box(id).expand_width().center()),
+                Size::new(400., 50.),
+            ),
+            (
+                |id| Box::new(fixed_box(id).expand_height().center()),
+                Size::new(100., 400.),
+            ),
+            // the scrolling axis has nothing to fill
+            (
+                |id| Box::new(Scroll::new(fixed_box(id).expand()).vertical()),
+                Size::new(400., 50.),
+            ),
+            (
+                |id| Box::new(Scroll::new(fixed_box(id).expand())),
+                Size::new(100., 50.),
+            ),
+        ];
+
+        for (i, (make, size)) in cases.iter().enumerate() {
+            Harness::create((), make(id), |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                assert_eq!(
+                    harness.get_state(id).layout_rect.size(),
+                    *size,
+                    "case {}",
+                    i
+                );
+            });
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that animates the changes in the size of its child.
+
+use std::time::Duration;
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::theme;
+use crate::{
+    Animator, BoxConstraints, Curve, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, UpdateCtx, Widget, WidgetPod,
+};
+
+/// The name of the animated size in the `Animator`.
+const SIZE: &str = "size";
+
+/// A widget that animates the changes in the size of its child.
+///
+/// When the child's size changes, this widget moves from the old size to the
+/// new one over the duration of the animation, laying the child out with
+/// tight constraints at each size in between.
+///
+/// The child's size is checked after it requests a layout. If
+/// [`theme::ANIMATIONS_ENABLED`] is `false`, the size changes immediately.
+///
+/// This is usually created with [`WidgetExt::animate_size`].
+///
+/// [`theme::ANIMATIONS_ENABLED`]: ../theme/constant.ANIMATIONS_ENABLED.html
+/// [`WidgetExt::animate_size`]: trait.WidgetExt.html#method.animate_size
+pub struct AnimatedSize<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    duration: Duration,
+    curve: Curve,
+    animator: Animator,
+    /// The size of the child in the last layout, without animation.
+    target: Option<Size>,
+    /// Whether the child may have a new size, which is found in the next layout.
+    measuring: bool,
+}
+
+impl<T> AnimatedSize<T> {
+    /// Create a widget that animates the size changes of `child` over `duration`.
+    pub fn new(child: impl Widget<T> + 'static, duration: Duration) -> AnimatedSize<T> {
+        AnimatedSize {
+            child: WidgetPod::new(child).boxed(),
+            duration,
+            curve: Curve::EaseInOut,
+            animator: Animator::new(),
+            target: None,
+            measuring: false,
+        }
+    }
+
+    /// Builder-style method for setting the curve of the animation.
+    ///
+    /// The default is [`Curve::EaseInOut`].
+    ///
+    /// [`Curve::EaseInOut`]: ../enum.Curve.html#variant.EaseInOut
+    pub fn with_curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Start measuring the child if it requested a layout.
+    fn check_child(&mut self) -> bool {
+        if self.child.state().needs_layout && !self.measuring {
+            self.measuring = true;
+            return true;
+        }
+        false
+    }
+}
+
+impl<T: Data> Widget<T> for AnimatedSize<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.child.event(ctx, event, data, env);
+        if self.check_child() {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::AnimFrame(interval) = event {
+            // the first frame of a transition comes before the layout that starts it
+            if self.animator.advance(*interval) || self.measuring {
+                ctx.request_anim_frame();
+            }
+            ctx.request_layout();
+        }
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+        if self.check_child() {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("AnimatedSize");
+
+        let natural = self.child.layout(ctx, bc, data, env);
+        match self.target {
+            Some(target) if target == natural => (),
+            Some(_) if env.get(theme::ANIMATIONS_ENABLED) => {
+                self.animator
+                    .animate_to(SIZE, natural, self.duration, self.curve)
+            }
+            _ => self.animator.set(SIZE, natural),
+        }
+        self.target = Some(natural);
+        self.measuring = false;
+
+        let size = bc.constrain(self.animator.get(SIZE).unwrap_or(natural));
+        let child_size = if size == natural {
+            natural
+        } else {
+            self.child
+                .layout(ctx, &BoxConstraints::tight(size), data, env)
+        };
+        self.child.set_layout_rect(
+            ctx,
+            data,
+            env,
+            Rect::from_origin_size(Point::ORIGIN, child_size),
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint_with_offset(ctx, data, env);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::WidgetExt;
+    use crate::{Selector, Target, WidgetId};
+
+    #[test]
+    fn animate_size_with_frames() {
+        const GROW: Selector = Selector::new("druid-test.grow");
+        const FRAME: u64 = 50_000_000;
+
+        fn width(harness: &mut Harness<bool>, id: WidgetId) -> f64 {
+            harness.just_layout();
+            harness.get_state(id).size().width
+        }
+
+        let child: ModularWidget<(), bool> = ModularWidget::new(())
+            .event_fn(|_, _ctx, event, data, _env| {
+                if let Event::Command(cmd) = event {
+                    if cmd.selector == GROW {
+                        *data = true;
+                    }
+                }
+            })
+            .update_fn(|_, ctx, old_data, data, _env| {
+                if old_data != data {
+                    ctx.request_layout();
+                }
+            })
+            .layout_fn(|_, _ctx, bc, data, _env| {
+                let width = if *data { 200.0 } else { 100.0 };
+                bc.constrain(Size::new(width, 50.0))
+            });
+        let id = WidgetId::next();
+        let widget = child
+            .animate_size(std::time::Duration::from_millis(100))
+            .with_id(id)
+            .align_left();
+
+        Harness::create(false, widget, |harness| {
+            harness.send_initial_events();
+            assert_eq!(width(harness, id), 100.0);
+
+            harness.submit_command(GROW, Target::Auto);
+            assert!(harness.get_state(id).request_anim);
+
+            // the first frame comes before the layout that finds the new size
+            harness.anim_frame(0);
+            assert_eq!(width(harness, id), 100.0);
+            assert!(harness.get_state(id).request_anim);
+
+            // halfway through an ease-in-out
+            harness.anim_frame(FRAME);
+            assert!((width(harness, id) - 150.0).abs() < 1e-9);
+            assert!(harness.get_state(id).request_anim);
+
+            harness.anim_frame(FRAME);
+            assert_eq!(width(harness, id), 200.0);
+            assert!(!harness.get_state(id).request_anim);
+        });
+    }
+}
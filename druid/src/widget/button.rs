@@ -15,39 +15,103 @@
 //! A button widget.
 
 use crate::theme;
-use crate::widget::{Label, LabelText};
+use crate::widget::{Click, Controller, Label, LabelText};
 use crate::{
-    Affine, BoxConstraints, Data, Env, Event, EventCtx, Insets, LayoutCtx, LifeCycle, LifeCycleCtx,
-    LinearGradient, PaintCtx, Point, Rect, RenderContext, Size, UnitPoint, UpdateCtx, Widget,
+    commands, Affine, BoxConstraints, Data, Env, Event, EventCtx, HotKey, KbKey, KeyEvent,
+    LayoutCtx, LifeCycle, LifeCycleCtx, LinearGradient, PaintCtx, Point, Rect, RenderContext, Role,
+    Size, SysMods, UnitPoint, UpdateCtx, Widget,
 };
 
-// the minimum padding added to a button.
-// NOTE: these values are chosen to match the existing look of TextBox; these
-// should be reevaluated at some point.
-const LABEL_INSETS: Insets = Insets::uniform_xy(8., 2.);
-
-/// A button with a text label.
+/// A button, which shows a child widget on a themed background.
+///
+/// The button looks pressed while the mouse is held down on it, whatever
+/// the child is, and its action is called when the mouse is released over
+/// it; the press handling is done by a [`Click`].
+///
+/// In a dialog, a button can be the [default], which is pressed by Enter,
+/// or the [cancel] button, which is pressed by Escape, when the focused
+/// widget doesn't handle those keys. A button with a text label can also be
+/// pressed with Alt and the [mnemonic] of the label, such as S for
+/// `"&Save"`.
+///
+/// [`Click`]: struct.Click.html
+/// [default]: #method.default
+/// [cancel]: #method.cancel
+/// [mnemonic]: struct.Label.html#method.with_mnemonic
 pub struct Button<T> {
-    label: Label<T>,
-    label_size: Size,
-    /// A closure that will be invoked when the button is clicked.
-    action: Box<dyn Fn(&mut EventCtx, &mut T, &Env)>,
+    child: Box<dyn Widget<T>>,
+    child_size: Size,
+    click: Click<T>,
+    is_default: bool,
+    is_cancel: bool,
 }
 
 impl<T: Data> Button<T> {
-    /// Create a new button. The closure provided will be called when the button
-    /// is clicked.
+    /// Create a new button with a text label. The closure provided will be
+    /// called when the button is clicked.
+    ///
+    /// A `&` in the text marks the [mnemonic] of the button.
+    ///
+    /// [mnemonic]: struct.Label.html#method.with_mnemonic
     pub fn new(
         text: impl Into<LabelText<T>>,
         action: impl Fn(&mut EventCtx, &mut T, &Env) + 'static,
+    ) -> Button<T> {
+        Button::from_label(Label::new(text).with_mnemonic(true), action)
+    }
+
+    /// Create a new button with the provided [`Label`], so that the label
+    /// can be customized first.
+    ///
+    /// The button has a mnemonic if the label [has one].
+    ///
+    /// [`Label`]: struct.Label.html
+    /// [has one]: struct.Label.html#method.with_mnemonic
+    pub fn from_label(
+        label: Label<T>,
+        action: impl Fn(&mut EventCtx, &mut T, &Env) + 'static,
+    ) -> Button<T> {
+        Button::new_with_child(label, action)
+    }
+
+    /// Create a new button that shows any widget, such as an [`Image`].
+    ///
+    /// The child is centered in the button, with a little padding around it.
+    ///
+    /// [`Image`]: struct.Image.html
+    pub fn new_with_child(
+        child: impl Widget<T> + 'static,
+        action: impl Fn(&mut EventCtx, &mut T, &Env) + 'static,
     ) -> Button<T> {
         Button {
-            label: Label::new(text),
-            label_size: Size::ZERO,
-            action: Box::new(action),
+            child: Box::new(child),
+            child_size: Size::ZERO,
+            click: Click::new(action),
+            is_default: false,
+            is_cancel: false,
         }
     }
 
+    /// Builder-style method for making this the default button, which is
+    /// pressed by Enter when no widget handles it; it has an accented border.
+    pub fn default(mut self, is_default: bool) -> Self {
+        self.is_default = is_default;
+        self
+    }
+
+    /// Builder-style method for making this the cancel button, which is
+    /// pressed by Escape when no widget handles it.
+    pub fn cancel(mut self, is_cancel: bool) -> Self {
+        self.is_cancel = is_cancel;
+        self
+    }
+
+    /// Whether `key` presses the button when no widget handled it.
+    fn is_pressed_by(&self, key: &KeyEvent) -> bool {
+        (self.is_default && HotKey::new(SysMods::None, KbKey::Enter).matches(key))
+            || (self.is_cancel && HotKey::new(SysMods::None, KbKey::Escape).matches(key))
+    }
+
     /// A function that can be passed to `Button::new`, for buttons with no action.
     ///
     /// # Examples
@@ -62,33 +126,41 @@ impl<T: Data> Button<T> {
 
 impl<T: Data> Widget<T> for Button<T> {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
-        match event {
-            Event::MouseDown(_) => {
-                ctx.set_active(true);
-                ctx.request_paint();
-            }
-            Event::MouseUp(_) => {
-                if ctx.is_active() {
-                    ctx.set_active(false);
-                    ctx.request_paint();
-                    if ctx.is_hot() {
-                        (self.action)(ctx, data, env);
-                    }
+        self.click.event(&mut self.child, ctx, event, data, env);
+        if let Event::Command(cmd) = event {
+            if cmd.selector == commands::UNHANDLED_KEY && !ctx.is_disabled() {
+                // the label handles the key if it is its mnemonic
+                let pressed = ctx.is_handled()
+                    || cmd
+                        .get_object::<KeyEvent>()
+                        .map_or(false, |key| self.is_pressed_by(key));
+                if pressed {
+                    ctx.set_handled();
+                    self.click.click(ctx, data, env);
                 }
             }
-            _ => (),
         }
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
-        if let LifeCycle::HotChanged(_) = event {
-            ctx.request_paint();
+        self.click.lifecycle(&mut self.child, ctx, event, data, env);
+        // the label shares the button's context, and sets its own role
+        if let LifeCycle::WidgetAdded = event {
+            ctx.set_role(Role::Button);
         }
-        self.label.lifecycle(ctx, event, data, env)
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
-        self.label.update(ctx, old_data, data, env)
+        self.child.update(ctx, old_data, data, env);
+        if ctx.env_key_changed(theme::BUTTON_PADDING)
+            || ctx.env_key_changed(theme::BORDERED_WIDGET_HEIGHT)
+        {
+            ctx.request_layout();
+        } else if ctx.env_key_changed(theme::BUTTON_BORDER_RADIUS)
+            || ctx.env_key_changed(theme::BUTTON_BORDER_WIDTH)
+        {
+            ctx.request_paint();
+        }
     }
 
     fn layout(
@@ -99,16 +171,17 @@ impl<T: Data> Widget<T> for Button<T> {
         env: &Env,
     ) -> Size {
         bc.debug_check("Button");
-        let padding = Size::new(LABEL_INSETS.x_value(), LABEL_INSETS.y_value());
-        let label_bc = bc.shrink(padding).loosen();
-        self.label_size = self.label.layout(layout_ctx, &label_bc, data, env);
+        let insets = env.get(theme::BUTTON_PADDING);
+        let padding = Size::new(insets.x_value(), insets.y_value());
+        let child_bc = bc.shrink(padding).loosen();
+        self.child_size = self.child.layout(layout_ctx, &child_bc, data, env);
         // HACK: to make sure we look okay at default sizes when beside a textbox,
         // we make sure we will have at least the same height as the default textbox.
         let min_height = env.get(theme::BORDERED_WIDGET_HEIGHT);
 
         bc.constrain(Size::new(
-            self.label_size.width + padding.width,
-            (self.label_size.height + padding.height).max(min_height),
+            self.child_size.width + padding.width,
+            (self.child_size.height + padding.height).max(min_height),
         ))
     }
 
@@ -120,7 +193,16 @@ impl<T: Data> Widget<T> for Button<T> {
         let rounded_rect = Rect::from_origin_size(Point::ORIGIN, size)
             .to_rounded_rect(env.get(theme::BUTTON_BORDER_RADIUS));
 
-        let bg_gradient = if is_active {
+        let bg_gradient = if ctx.is_disabled() {
+            LinearGradient::new(
+                UnitPoint::TOP,
+                UnitPoint::BOTTOM,
+                (
+                    env.get(theme::DISABLED_BUTTON_DARK),
+                    env.get(theme::DISABLED_BUTTON_LIGHT),
+                ),
+            )
+        } else if is_active {
             LinearGradient::new(
                 UnitPoint::TOP,
                 UnitPoint::BOTTOM,
@@ -134,7 +216,9 @@ impl<T: Data> Widget<T> for Button<T> {
             )
         };
 
-        let border_color = if is_hot {
+        let border_color = if self.is_default && !ctx.is_disabled() {
+            env.get(theme::PRIMARY_LIGHT)
+        } else if is_hot {
             env.get(theme::BORDER_LIGHT)
         } else {
             env.get(theme::BORDER_DARK)
@@ -148,18 +232,89 @@ impl<T: Data> Widget<T> for Button<T> {
 
         ctx.fill(rounded_rect, &bg_gradient);
 
-        let label_offset = (size.to_vec2() - self.label_size.to_vec2()) / 2.0;
+        let child_offset = (size.to_vec2() - self.child_size.to_vec2()) / 2.0;
 
         if let Err(e) = ctx.save() {
             log::error!("saving render context failed: {:?}", e);
             return;
         }
 
-        ctx.transform(Affine::translate(label_offset));
-        self.label.paint(ctx, data, env);
+        ctx.transform(Affine::translate(child_offset));
+        self.child.paint(ctx, data, env);
 
         if let Err(e) = ctx.restore() {
             log::error!("restoring render context failed: {:?}", e);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Flex, SizedBox, WidgetExt};
+
+    #[test]
+    fn button_with_child_clicks_on_release_inside() {
+        let (button, clickable) = widget_id2();
+        let clicks = Rc::new(Cell::new(0));
+        let on_click = clicks.clone();
+        let image = SizedBox::empty().width(40.).height(30.);
+        let widget = Flex::column()
+            .with_child(
+                Button::new_with_child(image, |_, data: &mut u32, _| *data += 1).with_id(button),
+                0.0,
+            )
+            .with_child(
+                SizedBox::empty()
+                    .width(40.)
+                    .height(30.)
+                    .on_click(move |_, _, _| on_click.set(on_click.get() + 1))
+                    .with_id(clickable),
+                0.0,
+            );
+
+        Harness::create(0u32, widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let rect = harness.get_state(button).layout_rect;
+            assert!(rect.width() >= 40. && rect.height() >= 30.);
+            let inside = rect.center();
+            let outside = Point::new(rect.center().x, 390.);
+
+            // a press and release inside clicks once
+            click(harness, inside);
+            assert_eq!(*harness.data(), 1);
+
+            // the button is pressed while the mouse is down, whatever its child
+            harness.event(Event::MouseMoved(make_mouse(inside)));
+            harness.event(Event::MouseDown(make_mouse(inside)));
+            assert!(harness.get_state(button).is_active);
+
+            // releasing outside doesn't click
+            harness.event(Event::MouseMoved(make_mouse(outside)));
+            harness.event(Event::MouseUp(make_mouse(outside)));
+            assert!(!harness.get_state(button).is_active);
+            assert_eq!(*harness.data(), 1);
+
+            // nor does a release without a press
+            harness.event(Event::MouseMoved(make_mouse(inside)));
+            harness.event(Event::MouseUp(make_mouse(inside)));
+            assert_eq!(*harness.data(), 1);
+
+            // on_click works the same way, on any widget
+            let inside = harness.get_state(clickable).layout_rect.center();
+            click(harness, inside);
+            assert_eq!(clicks.get(), 1);
+            harness.event(Event::MouseDown(make_mouse(inside)));
+            harness.event(Event::MouseMoved(make_mouse(outside)));
+            harness.event(Event::MouseUp(make_mouse(outside)));
+            assert_eq!(clicks.get(), 1);
+            assert_eq!(*harness.data(), 1);
+        });
+    }
+}
@@ -0,0 +1,558 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A calendar that picks a day.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::piet::{
+    Color, FontBuilder, PietFont, RenderContext, Text, TextLayout, TextLayoutBuilder,
+};
+use crate::{
+    theme, BoxConstraints, Env, Event, EventCtx, HotKey, KbKey, KeyEvent, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, SysMods, UpdateCtx, Widget,
+};
+
+/// The size of a day in the grid.
+const CELL_SIZE: Size = Size::new(32.0, 24.0);
+/// The height of the row with the month and the navigation buttons.
+const HEADER_HEIGHT: f64 = 28.0;
+/// The height of the row with the names of the days of the week.
+const WEEKDAY_HEIGHT: f64 = 20.0;
+
+/// The navigation buttons, as their column, how many months they move by,
+/// and their text.
+const NAV_BUTTONS: &[(usize, i32, &str)] =
+    &[(0, -12, "«"), (1, -1, "‹"), (5, 1, "›"), (6, 12, "»")];
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// A month grid that shows the days around a date, and picks one.
+///
+/// The grid has a row for each week that has days of the shown month in it,
+/// so a month takes four, five or six rows. The days of the neighbouring
+/// months that fill the first and last week are dimmed, and picking one of
+/// them moves to its month. The buttons on either side of the month's name
+/// move back and forward by a month, and the outer ones by a year, without
+/// changing the date.
+///
+/// When the calendar has focus, the arrow keys move the date by a day or a
+/// week, and Page Up and Page Down by a month.
+pub struct Calendar {
+    /// The first day of the month that is shown.
+    month: NaiveDate,
+    first_weekday: Weekday,
+    /// The part that the mouse was pressed on.
+    pressed: Option<Part>,
+    /// The part that the mouse is over.
+    hot: Option<Part>,
+}
+
+/// A part of the calendar that can be clicked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    /// A navigation button, which moves by this many months.
+    Nav(i32),
+    Day(NaiveDate),
+}
+
+impl Calendar {
+    /// Create a calendar, with weeks that start on Monday.
+    pub fn new() -> Calendar {
+        Calendar {
+            month: NaiveDate::from_ymd_opt(2000, 1, 1).expect("2000-01-01 is a date"),
+            first_weekday: Weekday::Mon,
+            pressed: None,
+            hot: None,
+        }
+    }
+
+    /// Builder-style method to set the day that weeks start on.
+    pub fn with_first_weekday(mut self, weekday: Weekday) -> Self {
+        self.first_weekday = weekday;
+        self
+    }
+
+    /// The size of a calendar that shows `rows` weeks.
+    pub(crate) fn size_for_rows(rows: usize) -> Size {
+        Size::new(
+            7.0 * CELL_SIZE.width,
+            HEADER_HEIGHT + WEEKDAY_HEIGHT + rows as f64 * CELL_SIZE.height,
+        )
+    }
+
+    /// The day in the first cell of the grid.
+    fn grid_start(&self) -> NaiveDate {
+        let lead = (7 + self.month.weekday().num_days_from_monday()
+            - self.first_weekday.num_days_from_monday())
+            % 7;
+        self.month - Duration::days(lead.into())
+    }
+
+    /// The number of weeks in the grid.
+    fn rows(&self) -> usize {
+        match add_months(self.month, 1) {
+            Some(next) => ((next - self.grid_start()).num_days() as usize).div_ceil(7),
+            // the last month that chrono has, with no next one to end it
+            None => 6,
+        }
+    }
+
+    /// The rect of a cell in the grid.
+    fn cell_rect(row: usize, column: usize) -> Rect {
+        let origin = Point::new(
+            column as f64 * CELL_SIZE.width,
+            HEADER_HEIGHT + WEEKDAY_HEIGHT + row as f64 * CELL_SIZE.height,
+        );
+        Rect::from_origin_size(origin, CELL_SIZE)
+    }
+
+    /// The part of the calendar at `pos`, if any.
+    fn part_at(&self, pos: Point) -> Option<Part> {
+        if pos.x < 0.0 || pos.y < 0.0 {
+            return None;
+        }
+        let column = (pos.x / CELL_SIZE.width) as usize;
+        if column >= 7 {
+            return None;
+        }
+        if pos.y < HEADER_HEIGHT {
+            return NAV_BUTTONS
+                .iter()
+                .find(|(c, _, _)| *c == column)
+                .map(|(_, months, _)| Part::Nav(*months));
+        }
+        let y = pos.y - HEADER_HEIGHT - WEEKDAY_HEIGHT;
+        if y < 0.0 {
+            return None;
+        }
+        let row = (y / CELL_SIZE.height) as usize;
+        if row >= self.rows() {
+            return None;
+        }
+        let offset = row * 7 + column;
+        Some(Part::Day(self.grid_start() + Duration::days(offset as i64)))
+    }
+
+    /// Show the month of `date`.
+    fn show_month(&mut self, ctx: &mut EventCtx, date: NaiveDate) {
+        let month = first_of_month(date);
+        if month != self.month {
+            self.month = month;
+            // the number of rows can change
+            ctx.request_layout();
+        }
+        ctx.request_paint();
+    }
+
+    fn activate(&mut self, ctx: &mut EventCtx, part: Part, data: &mut NaiveDate) {
+        match part {
+            Part::Nav(months) => {
+                if let Some(month) = add_months(self.month, months) {
+                    self.show_month(ctx, month);
+                }
+            }
+            Part::Day(date) => {
+                *data = date;
+                self.show_month(ctx, date);
+            }
+        }
+    }
+
+    /// The date that a key moves the selection to, if it is one of ours.
+    fn date_for_key(key: &KeyEvent, date: NaiveDate) -> Option<NaiveDate> {
+        let moves = [
+            (KbKey::ArrowLeft, Move::Days(-1)),
+            (KbKey::ArrowRight, Move::Days(1)),
+            (KbKey::ArrowUp, Move::Days(-7)),
+            (KbKey::ArrowDown, Move::Days(7)),
+            (KbKey::PageUp, Move::Months(-1)),
+            (KbKey::PageDown, Move::Months(1)),
+        ];
+        let (_, step) = moves
+            .iter()
+            .find(|(k, _)| HotKey::new(SysMods::None, k.clone()).matches(key))?;
+        match *step {
+            Move::Days(days) => date.checked_add_signed(Duration::days(days)),
+            Move::Months(months) => add_months_clamped(date, months),
+        }
+    }
+}
+
+/// How far a key moves the date.
+#[derive(Clone, Copy)]
+enum Move {
+    Days(i64),
+    Months(i32),
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The first day of the month of `date`.
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// The first day of the month that is `months` after that of `first`, if
+/// chrono can represent it.
+fn add_months(first: NaiveDate, months: i32) -> Option<NaiveDate> {
+    let index = first.year() * 12 + first.month0() as i32 + months;
+    NaiveDate::from_ymd_opt(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, 1)
+}
+
+/// The same day `months` later, or the last day of that month if it is
+/// shorter.
+fn add_months_clamped(date: NaiveDate, months: i32) -> Option<NaiveDate> {
+    let month = add_months(first_of_month(date), months)?;
+    let last = add_months(month, 1)?.pred_opt()?;
+    Some(month.with_day(date.day().min(last.day())).unwrap_or(last))
+}
+
+impl Widget<NaiveDate> for Calendar {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut NaiveDate, _env: &Env) {
+        match event {
+            Event::MouseDown(mouse) => {
+                ctx.request_focus();
+                self.pressed = self.part_at(mouse.pos);
+                if self.pressed.is_some() {
+                    ctx.set_active(true);
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseUp(mouse) => {
+                if ctx.is_active() {
+                    ctx.set_active(false);
+                    let pressed = self.pressed;
+                    if let Some(part) = self.part_at(mouse.pos).filter(|p| Some(*p) == pressed) {
+                        self.activate(ctx, part, data);
+                    }
+                    ctx.request_paint();
+                }
+                self.pressed = None;
+            }
+            Event::MouseMoved(mouse) => {
+                let hot = if ctx.is_hot() {
+                    self.part_at(mouse.pos)
+                } else {
+                    None
+                };
+                if hot != self.hot {
+                    self.hot = hot;
+                    ctx.request_paint();
+                }
+            }
+            Event::KeyDown(key) if ctx.has_focus() => {
+                if let Some(date) = Self::date_for_key(key, *data) {
+                    *data = date;
+                    self.show_month(ctx, date);
+                    ctx.set_handled();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &NaiveDate,
+        _env: &Env,
+    ) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                self.month = first_of_month(*data);
+                ctx.register_for_focus();
+            }
+            LifeCycle::HotChanged(false) => {
+                self.hot = None;
+                ctx.request_paint();
+            }
+            LifeCycle::FocusChanged(_) => ctx.request_paint(),
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &NaiveDate, data: &NaiveDate, _env: &Env) {
+        if old_data != data {
+            let month = first_of_month(*data);
+            if month != self.month {
+                self.month = month;
+                ctx.request_layout();
+            }
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &NaiveDate,
+        _env: &Env,
+    ) -> Size {
+        bc.debug_check("Calendar");
+        bc.constrain(Self::size_for_rows(self.rows()))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &NaiveDate, env: &Env) {
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let font = match ctx
+            .text()
+            .new_font_by_name(env.get(theme::FONT_NAME), font_size)
+            .build()
+        {
+            Ok(font) => font,
+            Err(e) => {
+                log::error!("failed to build the calendar's font: {:?}", e);
+                return;
+            }
+        };
+        let label_color = env.get(theme::LABEL_COLOR);
+        let hot_color = env.get(theme::BUTTON_LIGHT);
+
+        // the header, with the month and the navigation buttons
+        let width = 7.0 * CELL_SIZE.width;
+        let title = format!(
+            "{} {}",
+            MONTH_NAMES[self.month.month0() as usize],
+            self.month.year()
+        );
+        let title_rect = Rect::new(0.0, 0.0, width, HEADER_HEIGHT);
+        draw_centered(ctx, &font, font_size, &title, title_rect, &label_color);
+        for &(column, months, text) in NAV_BUTTONS {
+            let rect = Rect::from_origin_size(
+                Point::new(column as f64 * CELL_SIZE.width, 0.0),
+                Size::new(CELL_SIZE.width, HEADER_HEIGHT),
+            );
+            if self.hot == Some(Part::Nav(months)) {
+                ctx.fill(rect, &hot_color);
+            }
+            draw_centered(ctx, &font, font_size, text, rect, &label_color);
+        }
+
+        // the names of the days of the week
+        let weekday_color = env.get(theme::PLACEHOLDER_COLOR);
+        let mut weekday = self.first_weekday;
+        for column in 0..7 {
+            let rect = Rect::from_origin_size(
+                Point::new(column as f64 * CELL_SIZE.width, HEADER_HEIGHT),
+                Size::new(CELL_SIZE.width, WEEKDAY_HEIGHT),
+            );
+            let name = &format!("{:?}", weekday)[..2];
+            draw_centered(ctx, &font, font_size, name, rect, &weekday_color);
+            weekday = weekday.succ();
+        }
+
+        // the days
+        let dimmed_color = env.get(theme::DISABLED_TEXT_COLOR);
+        let start = self.grid_start();
+        for row in 0..self.rows() {
+            for column in 0..7 {
+                let date = start + Duration::days((row * 7 + column) as i64);
+                let rect = Self::cell_rect(row, column);
+                if date == *data {
+                    ctx.fill(rect.inset(-1.0), &env.get(theme::PRIMARY_LIGHT));
+                    if ctx.has_focus() {
+                        ctx.stroke(rect.inset(-1.5), &env.get(theme::PRIMARY_DARK), 1.0);
+                    }
+                } else if self.hot == Some(Part::Day(date)) {
+                    ctx.fill(rect.inset(-1.0), &hot_color);
+                }
+                let color = if date.month() == self.month.month() {
+                    &label_color
+                } else {
+                    &dimmed_color
+                };
+                let text = date.day().to_string();
+                draw_centered(ctx, &font, font_size, &text, rect, color);
+            }
+        }
+    }
+}
+
+/// Draw a line of text in the middle of `rect`.
+fn draw_centered(
+    ctx: &mut PaintCtx,
+    font: &PietFont,
+    font_size: f64,
+    text: &str,
+    rect: Rect,
+    color: &Color,
+) {
+    let layout = match ctx.text().new_text_layout(font, text).build() {
+        Ok(layout) => layout,
+        Err(e) => {
+            log::error!("laying out calendar text failed: {:?}", e);
+            return;
+        }
+    };
+    // the baseline is about a third of the font size below the middle
+    let origin = Point::new(
+        rect.center().x - layout.width() / 2.0,
+        rect.center().y + font_size * 0.35,
+    );
+    ctx.draw_text(&layout, origin, color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Align, WidgetExt};
+    use crate::{Code, KeyModifiers, UnitPoint, WidgetId};
+    use chrono::NaiveDate;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// A calendar in the top left corner of the window, so that it gets its
+    /// own size.
+    fn calendar(id: WidgetId) -> impl Widget<NaiveDate> {
+        Align::new(UnitPoint::TOP_LEFT, Calendar::new().with_id(id))
+    }
+
+    /// The middle of the day in `row` and `column` of the grid; days are 32 by
+    /// 24, below a 28 high header and a 20 high row of weekday names.
+    fn cell(row: usize, column: usize) -> Point {
+        Point::new(column as f64 * 32. + 16., 48. + row as f64 * 24. + 12.)
+    }
+
+    #[test]
+    fn months_take_four_five_or_six_rows() {
+        // February 2021 starts on a Monday and has 28 days, June 2020 starts
+        // on a Monday, and August 2020 starts on a Saturday
+        for &(data, rows) in &[
+            (date(2021, 2, 10), 4),
+            (date(2020, 6, 10), 5),
+            (date(2020, 8, 10), 6),
+        ] {
+            let id = WidgetId::next();
+            Harness::create(data, calendar(id), |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                let height = 48. + 24. * rows as f64;
+                assert_eq!(harness.get_state(id).size(), Size::new(224., height));
+            });
+        }
+    }
+
+    #[test]
+    fn clicking_a_day_picks_it() {
+        let id = WidgetId::next();
+        Harness::create(date(2020, 6, 15), calendar(id), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            click(harness, cell(0, 0));
+            assert_eq!(*harness.data(), date(2020, 6, 1));
+            click(harness, cell(2, 3));
+            assert_eq!(*harness.data(), date(2020, 6, 18));
+
+            // the last week ends in July, and picking a day there shows July,
+            // whose first week starts in June
+            click(harness, cell(4, 6));
+            assert_eq!(*harness.data(), date(2020, 7, 5));
+            click(harness, cell(0, 0));
+            assert_eq!(*harness.data(), date(2020, 6, 29));
+        });
+    }
+
+    #[test]
+    fn leading_days_cross_month_and_year_boundaries() {
+        // March 2020 starts on a Sunday, so its first week has the leap day
+        let id = WidgetId::next();
+        Harness::create(date(2020, 3, 10), calendar(id), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            click(harness, cell(0, 5));
+            assert_eq!(*harness.data(), date(2020, 2, 29));
+        });
+
+        // January 2021 starts on a Friday
+        let id = WidgetId::next();
+        Harness::create(date(2021, 1, 15), calendar(id), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            click(harness, cell(0, 0));
+            assert_eq!(*harness.data(), date(2020, 12, 28));
+        });
+    }
+
+    #[test]
+    fn navigation_buttons_change_the_month_shown() {
+        let id = WidgetId::next();
+        Harness::create(date(2020, 1, 31), calendar(id), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            // the next month button doesn't change the date
+            click(harness, Point::new(5. * 32. + 16., 14.));
+            assert_eq!(*harness.data(), date(2020, 1, 31));
+            // February 2020 starts on a Saturday, and has 29 days
+            assert_eq!(harness.get_state(id).size().height, 48. + 5. * 24.);
+            click(harness, cell(4, 5));
+            assert_eq!(*harness.data(), date(2020, 2, 29));
+
+            // back a year, to February 2019, which starts on a Friday
+            click(harness, Point::new(16., 14.));
+            click(harness, cell(0, 4));
+            assert_eq!(*harness.data(), date(2019, 2, 1));
+        });
+    }
+
+    #[test]
+    fn keys_move_the_date_when_focused() {
+        let id = WidgetId::next();
+        Harness::create(date(2020, 1, 30), calendar(id), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            // focus the calendar by clicking its current date
+            click(harness, cell(4, 3));
+            assert_eq!(*harness.data(), date(2020, 1, 30));
+
+            let key = |code| Event::KeyDown(KeyEvent::for_test(KeyModifiers::default(), "", code));
+            harness.event(key(Code::ArrowRight));
+            assert_eq!(*harness.data(), date(2020, 1, 31));
+            // a month later is clamped to the end of February, in a leap year
+            harness.event(key(Code::PageDown));
+            assert_eq!(*harness.data(), date(2020, 2, 29));
+            harness.event(key(Code::ArrowDown));
+            assert_eq!(*harness.data(), date(2020, 3, 7));
+            harness.event(key(Code::ArrowUp));
+            harness.event(key(Code::ArrowLeft));
+            assert_eq!(*harness.data(), date(2020, 2, 28));
+        });
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that can paint its child into an image.
+
+use std::mem;
+
+use crate::core::BaseState;
+use crate::kurbo::{Point, Rect, Size};
+use crate::piet::{self, Device, ImageFormat, RenderContext};
+use crate::shell::Scale;
+use crate::widget::ImageBuf;
+use crate::{
+    BoxConstraints, CaptureRequest, Command, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Selector, UpdateCtx, Widget, WidgetPod,
+};
+
+/// A widget that paints its child into an image when it gets a command.
+///
+/// When the command with its selector arrives, whose argument must be a
+/// [`CaptureRequest`], the child is painted offscreen at its layout size,
+/// and the resulting [`ImageBuf`] is sent as the request says. The image is
+/// in display points, with the child's own background, if any: whatever is
+/// behind the child in the window is not in it, and neither are native
+/// views, such as those of a [`NativeViewHost`].
+///
+/// This is usually created with [`WidgetExt::capture_on`]. To capture the
+/// whole window as the platform displays it, see [`CAPTURE_WINDOW`].
+///
+/// [`CaptureRequest`]: ../struct.CaptureRequest.html
+/// [`ImageBuf`]: struct.ImageBuf.html
+/// [`NativeViewHost`]: struct.NativeViewHost.html
+/// [`WidgetExt::capture_on`]: trait.WidgetExt.html#method.capture_on
+/// [`CAPTURE_WINDOW`]: ../commands/constant.CAPTURE_WINDOW.html
+pub struct Capture<T> {
+    selector: Selector,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+}
+
+impl<T> Capture<T> {
+    /// Create a widget that captures `child` when it gets a command with
+    /// `selector`.
+    pub fn new(selector: Selector, child: impl Widget<T> + 'static) -> Capture<T> {
+        Capture {
+            selector,
+            child: WidgetPod::new(child).boxed(),
+        }
+    }
+}
+
+impl<T: Data> Capture<T> {
+    /// Paint the child into a new bitmap of its size.
+    fn paint_offscreen(
+        &mut self,
+        ctx: &EventCtx,
+        data: &T,
+        env: &Env,
+    ) -> Result<ImageBuf, piet::Error> {
+        let size = ctx.size();
+        let (width, height) = (size.width.ceil() as usize, size.height.ceil() as usize);
+        let mut device = Device::new()?;
+        let mut target = device.bitmap_target(width, height, 1.0)?;
+        {
+            let mut piet = target.render_context();
+            let base_state = BaseState::new(ctx.widget_id());
+            let visible = size.to_rect();
+            let mut paint_ctx = PaintCtx {
+                render_ctx: &mut piet,
+                base_state: &base_state,
+                window_id: ctx.window_id,
+                z_ops: Vec::new(),
+                focus_widget: ctx.focus_widget,
+                region: visible.into(),
+                scale: Scale::default(),
+                window: ctx.window,
+                native_views: Vec::new(),
+            };
+            let child = &mut self.child;
+            paint_ctx.with_child_ctx(visible, |ctx| child.paint(ctx, data, env));
+
+            let mut z_ops = mem::take(&mut paint_ctx.z_ops);
+            z_ops.sort_by_key(|k| k.z_index);
+            for z_op in z_ops.into_iter() {
+                paint_ctx.with_child_ctx(visible, |ctx| {
+                    if let Err(e) = ctx.render_ctx.save() {
+                        log::error!("saving render context failed: {:?}", e);
+                        return;
+                    }
+                    ctx.render_ctx.transform(z_op.transform);
+                    (z_op.paint_func)(ctx);
+                    if let Err(e) = ctx.render_ctx.restore() {
+                        log::error!("restoring render context failed: {:?}", e);
+                    }
+                });
+            }
+            piet.finish()?;
+        }
+        let pixels = target.into_raw_pixels(ImageFormat::RgbaPremul)?;
+        Ok(ImageBuf::from_raw(
+            pixels,
+            ImageFormat::RgbaPremul,
+            width,
+            height,
+        ))
+    }
+}
+
+impl<T: Data> Widget<T> for Capture<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if cmd.selector == self.selector {
+                ctx.set_handled();
+                match cmd.get_object::<CaptureRequest>() {
+                    Ok(request) => match self.paint_offscreen(ctx, data, env) {
+                        Ok(image) => {
+                            let reply = Command::new(request.reply.clone(), image);
+                            ctx.submit_command(reply, request.target);
+                        }
+                        Err(e) => log::error!("failed to capture a widget: {}", e),
+                    },
+                    Err(e) => log::error!("capture object error: '{}'", e),
+                }
+                return;
+            }
+        }
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("Capture");
+
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child
+            .set_layout_rect(ctx, data, env, Rect::from_origin_size(Point::ORIGIN, size));
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint_with_offset(ctx, data, env);
+    }
+}
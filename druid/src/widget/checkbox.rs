@@ -14,13 +14,13 @@
 
 //! A checkbox widget.
 
-use crate::kurbo::{BezPath, Point, Rect, RoundedRect, Size};
+use crate::kurbo::{BezPath, Point, Rect, Size};
 use crate::piet::{LineCap, LineJoin, LinearGradient, RenderContext, StrokeStyle, UnitPoint};
 use crate::theme;
 use crate::widget::{Label, LabelText, WidgetExt};
 use crate::{
-    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, UpdateCtx,
-    Widget, WidgetPod,
+    commands, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Role, UpdateCtx, Widget, WidgetPod,
 };
 
 /// A checkbox that toggles a `bool`.
@@ -32,13 +32,13 @@ impl Checkbox {
     /// Create a new `Checkbox` with a label.
     pub fn new(label: impl Into<LabelText<bool>>) -> Checkbox {
         Checkbox {
-            child_label: WidgetPod::new(Label::new(label).boxed()),
+            child_label: WidgetPod::new(Label::new(label).with_mnemonic(true).boxed()),
         }
     }
 }
 
 impl Widget<bool> for Checkbox {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut bool, _env: &Env) {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut bool, env: &Env) {
         match event {
             Event::MouseDown(_) => {
                 ctx.set_active(true);
@@ -57,18 +57,44 @@ impl Widget<bool> for Checkbox {
                     ctx.request_paint();
                 }
             }
+            Event::Command(cmd) if cmd.selector == commands::UNHANDLED_KEY => {
+                // the label handles the key if it is its mnemonic
+                self.child_label.event(ctx, event, data, env);
+                if ctx.is_handled() {
+                    *data = !*data;
+                    ctx.request_paint();
+                }
+            }
             _ => (),
         }
     }
 
-    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &bool, _env: &Env) {
-        if let LifeCycle::HotChanged(_) = event {
-            ctx.request_paint();
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &bool, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                ctx.set_role(Role::CheckBox);
+                ctx.set_toggled(*data);
+            }
+            LifeCycle::HotChanged(_) => ctx.request_paint(),
+            _ => (),
         }
+        self.child_label.lifecycle(ctx, event, data, env);
     }
 
-    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &bool, _data: &bool, _env: &Env) {
-        ctx.request_paint();
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &bool, data: &bool, env: &Env) {
+        // the label requests layout itself if its text changes
+        self.child_label.update(ctx, data, env);
+        if old_data != data {
+            ctx.set_toggled(*data);
+            ctx.request_paint();
+        }
+        if ctx.env_key_changed(theme::CHECKBOX_SIZE)
+            || ctx.env_key_changed(theme::WIDGET_CONTROL_COMPONENT_PADDING)
+        {
+            ctx.request_layout();
+        } else if ctx.env_key_changed(theme::CHECKBOX_BORDER_RADIUS) {
+            ctx.request_paint();
+        }
     }
 
     fn layout(
@@ -81,24 +107,34 @@ impl Widget<bool> for Checkbox {
         bc.debug_check("Checkbox");
 
         let label_size = self.child_label.layout(layout_ctx, &bc, data, env);
-        let padding = 8.0;
-        let label_x_offset = env.get(theme::BASIC_WIDGET_HEIGHT) + padding;
+        let check_size = env.get(theme::CHECKBOX_SIZE);
+        let padding = env.get(theme::WIDGET_CONTROL_COMPONENT_PADDING);
+        let label_x_offset = check_size + padding;
         let origin = Point::new(label_x_offset, 0.0);
 
-        self.child_label
-            .set_layout_rect(Rect::from_origin_size(origin, label_size));
+        self.child_label.set_layout_rect(
+            layout_ctx,
+            data,
+            env,
+            Rect::from_origin_size(origin, label_size),
+        );
 
         bc.constrain(Size::new(
             label_x_offset + label_size.width,
-            env.get(theme::BASIC_WIDGET_HEIGHT).max(label_size.height),
+            check_size.max(label_size.height),
         ))
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &bool, env: &Env) {
-        let size = env.get(theme::BASIC_WIDGET_HEIGHT);
+        let size = env.get(theme::CHECKBOX_SIZE);
 
-        let rect =
-            RoundedRect::from_origin_size(Point::ORIGIN, Size::new(size, size).to_vec2(), 2.);
+        // put the border on whole physical pixels, so that it is crisp
+        let border_width = 1.;
+        let rect = Rect::from_origin_size(Point::ORIGIN, Size::new(size, size));
+        let rect = ctx
+            .scale()
+            .snap_stroke_rect(rect, border_width)
+            .to_rounded_rect(env.get(theme::CHECKBOX_BORDER_RADIUS));
 
         //Paint the background
         let background_gradient = LinearGradient::new(
@@ -118,20 +154,26 @@ impl Widget<bool> for Checkbox {
             env.get(theme::BORDER_DARK)
         };
 
-        ctx.stroke(rect, &border_color, 1.);
+        ctx.stroke(rect, &border_color, border_width);
 
         if *data {
-            // Paint the checkmark
+            // Paint the checkmark, which is drawn for an 18px box
+            let scale = size / 18.0;
             let mut path = BezPath::new();
-            path.move_to((4.0, 9.0));
-            path.line_to((8.0, 13.0));
-            path.line_to((14.0, 5.0));
+            path.move_to((4.0 * scale, 9.0 * scale));
+            path.line_to((8.0 * scale, 13.0 * scale));
+            path.line_to((14.0 * scale, 5.0 * scale));
 
             let mut style = StrokeStyle::new();
             style.set_line_cap(LineCap::Round);
             style.set_line_join(LineJoin::Round);
 
-            ctx.stroke_styled(path, &env.get(theme::LABEL_COLOR), 2., &style);
+            let color = if ctx.is_disabled() {
+                env.get(theme::DISABLED_TEXT_COLOR)
+            } else {
+                env.get(theme::LABEL_COLOR)
+            };
+            ctx.stroke_styled(path, &color, 2., &style);
         }
 
         // Paint the text label
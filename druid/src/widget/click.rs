@@ -0,0 +1,89 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A controller that makes a widget clickable.
+
+use crate::widget::Controller;
+use crate::{Data, Env, Event, EventCtx, LifeCycle, LifeCycleCtx, Widget};
+
+type ActionFn<T> = dyn Fn(&mut EventCtx, &mut T, &Env);
+
+/// A [`Controller`] that calls a closure when its widget is clicked.
+///
+/// A click is a press of the mouse that is released while the mouse is
+/// still over the widget; the widget is [active] in between. Releasing the
+/// mouse somewhere else does nothing. The widget is repainted when it
+/// becomes active or hot, and when it stops being so, so that it can show
+/// that it is pressed.
+///
+/// This is usually used with [`WidgetExt::on_click`].
+///
+/// [`Controller`]: trait.Controller.html
+/// [active]: ../struct.EventCtx.html#method.is_active
+/// [`WidgetExt::on_click`]: trait.WidgetExt.html#method.on_click
+pub struct Click<T> {
+    /// A closure that will be invoked when the widget is clicked.
+    action: Box<ActionFn<T>>,
+}
+
+impl<T: Data> Click<T> {
+    /// Create a new `Click` that calls `action` when its widget is clicked.
+    pub fn new(action: impl Fn(&mut EventCtx, &mut T, &Env) + 'static) -> Self {
+        Click {
+            action: Box::new(action),
+        }
+    }
+
+    /// Call the action, as if the widget had been clicked; for acting on
+    /// the keyboard, say.
+    pub fn click(&self, ctx: &mut EventCtx, data: &mut T, env: &Env) {
+        (self.action)(ctx, data, env);
+    }
+}
+
+impl<T: Data, W: Widget<T>> Controller<T, W> for Click<T> {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(_) => {
+                ctx.set_active(true);
+                ctx.request_paint();
+            }
+            Event::MouseUp(_) => {
+                if ctx.is_active() {
+                    ctx.set_active(false);
+                    ctx.request_paint();
+                    if ctx.is_hot() {
+                        self.click(ctx, data, env);
+                    }
+                }
+            }
+            _ => (),
+        }
+        child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        if let LifeCycle::HotChanged(_) = event {
+            ctx.request_paint();
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+}
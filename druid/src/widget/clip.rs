@@ -0,0 +1,216 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that clips its child to a shape.
+
+use crate::kurbo::{BezPath, Point, Rect, RoundedRect, Shape, Size};
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, MouseEvent,
+    PaintCtx, RenderContext, UpdateCtx, Widget, WidgetPod,
+};
+
+/// A position that is outside of any widget.
+const OUTSIDE: Point = Point::new(std::f64::NEG_INFINITY, std::f64::NEG_INFINITY);
+
+/// The accuracy used to turn a shape into a path.
+const TOLERANCE: f64 = 0.1;
+
+/// A widget that clips its child to a shape.
+///
+/// The child is laid out as if it were not clipped, and only the part of it
+/// inside the shape is painted. The mouse is only over the child where it is
+/// inside the shape: outside of it, the child doesn't become hot and isn't
+/// clicked, unless it is active.
+///
+/// This is usually created with [`WidgetExt::clip`] or [`WidgetExt::rounded`].
+/// A background or border added around it is not clipped:
+///
+/// ```
+/// # use druid::widget::{Label, WidgetExt};
+/// # use druid::Color;
+/// let card = Label::<()>::new("card")
+///     .rounded(8.0)
+///     .border(Color::grey(0.5), 1.0);
+/// ```
+///
+/// [`WidgetExt::clip`]: trait.WidgetExt.html#method.clip
+/// [`WidgetExt::rounded`]: trait.WidgetExt.html#method.rounded
+pub struct Clip<T> {
+    shape: ClipShape,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+}
+
+enum ClipShape {
+    /// A fixed shape, in the widget's coordinates.
+    Path(BezPath),
+    /// A rounded rect that fills the layout rect, with this corner radius.
+    Rounded(f64),
+}
+
+impl<T> Clip<T> {
+    /// Create a widget that clips `child` to `shape`, which is in the
+    /// widget's coordinates.
+    pub fn new(shape: impl Shape, child: impl Widget<T> + 'static) -> Clip<T> {
+        Clip {
+            shape: ClipShape::Path(shape.into_bez_path(TOLERANCE)),
+            child: WidgetPod::new(child).boxed(),
+        }
+    }
+
+    /// Create a widget that clips `child` to its layout rect, with the
+    /// corners rounded off to `radius`.
+    pub fn rounded(radius: f64, child: impl Widget<T> + 'static) -> Clip<T> {
+        Clip {
+            shape: ClipShape::Rounded(radius),
+            child: WidgetPod::new(child).boxed(),
+        }
+    }
+
+    /// Whether `pos` is inside the shape, for a widget of the given size.
+    fn contains(&self, size: Size, pos: Point) -> bool {
+        match &self.shape {
+            ClipShape::Path(path) => path.winding(pos) != 0,
+            ClipShape::Rounded(radius) => {
+                RoundedRect::from_rect(size.to_rect(), *radius).winding(pos) != 0
+            }
+        }
+    }
+
+    fn path(&self, size: Size) -> BezPath {
+        match &self.shape {
+            ClipShape::Path(path) => path.clone(),
+            ClipShape::Rounded(radius) => {
+                RoundedRect::from_rect(size.to_rect(), *radius).into_bez_path(TOLERANCE)
+            }
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for Clip<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        // an active child keeps getting the mouse wherever it is
+        let clipped = |mouse: &MouseEvent| {
+            if self.child.has_active() || self.contains(ctx.size(), mouse.pos) {
+                None
+            } else {
+                Some(outside(mouse))
+            }
+        };
+        let clipped_event = match event {
+            Event::MouseDown(mouse) => clipped(mouse).map(Event::MouseDown),
+            Event::MouseUp(mouse) => clipped(mouse).map(Event::MouseUp),
+            Event::MouseMoved(mouse) => clipped(mouse).map(Event::MouseMoved),
+            // a drag outside of the shape has left the child
+            Event::DragEnter(drag) | Event::DragOver(drag) | Event::Drop(drag)
+                if !self.contains(ctx.size(), drag.pos) =>
+            {
+                Some(Event::DragLeave)
+            }
+            _ => None,
+        };
+        let event = clipped_event.as_ref().unwrap_or(event);
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("Clip");
+
+        let size = self.child.layout(ctx, bc, data, env);
+        // as in events, the child is only under the mouse inside the shape
+        let mouse_pos = ctx.mouse_pos;
+        if !self.child.has_active() && !mouse_pos.map_or(false, |pos| self.contains(size, pos)) {
+            ctx.mouse_pos = None;
+        }
+        self.child
+            .set_layout_rect(ctx, data, env, Rect::from_origin_size(Point::ORIGIN, size));
+        ctx.mouse_pos = mouse_pos;
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if let Err(e) = ctx.save() {
+            log::error!("{}", e);
+            return;
+        }
+        let size = ctx.size();
+        ctx.clip(self.path(size));
+        self.child.paint_with_offset(ctx, data, env);
+        if let Err(e) = ctx.restore() {
+            log::error!("{}", e);
+        }
+    }
+}
+
+/// The mouse event, moved outside of any widget.
+fn outside(mouse: &MouseEvent) -> MouseEvent {
+    let mut mouse = mouse.clone();
+    mouse.pos = OUTSIDE;
+    mouse
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::WidgetExt;
+    use crate::Color;
+
+    #[test]
+    fn rounded_clip_hit_testing() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let recorder: ModularWidget<_, ()> = ModularWidget::new(log.clone())
+            .event_fn(|log, _ctx, event, _data, _env| {
+                if let Event::MouseDown(mouse) = event {
+                    log.borrow_mut()
+                        .push(format!("down {} {}", mouse.pos.x, mouse.pos.y));
+                }
+            })
+            .lifecycle_fn(|log, _ctx, event, _data, _env| {
+                if let LifeCycle::HotChanged(hot) = event {
+                    log.borrow_mut().push(format!("hot {}", hot));
+                }
+            });
+        // the recorder is 100x100, so its corners are well outside of the radius
+        let widget = recorder.rounded(40.).border(Color::WHITE, 0.);
+
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            let corner = Point::new(5., 5.);
+            click(harness, corner);
+            assert!(log.borrow().is_empty());
+
+            let inside = Point::new(50., 50.);
+            click(harness, inside);
+            assert_eq!(*log.borrow(), vec!["hot true", "down 50 50"]);
+
+            harness.event(Event::MouseMoved(make_mouse(corner)));
+            assert_eq!(log.borrow().last().unwrap(), "hot false");
+        });
+    }
+}
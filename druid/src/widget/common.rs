@@ -15,7 +15,7 @@
 use crate::{Affine, Size};
 
 // These are based on https://api.flutter.dev/flutter/painting/BoxFit-class.html
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FillStrat {
     /// As large as posible without changing aspect ratio of image and all of image shown
     Contain,
@@ -73,3 +73,95 @@ impl FillStrat {
         Affine::new([scalex, 0., 0., scaley, origin_x, origin_y])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coeffs(strat: FillStrat, parent: (f64, f64), fit_box: (f64, f64)) -> [f64; 6] {
+        strat
+            .affine_to_fill(parent.into(), fit_box.into())
+            .as_coeffs()
+    }
+
+    #[test]
+    fn fill_wide_parent() {
+        // a square image in a parent twice as wide as it is tall
+        let parent = (200., 100.);
+        let image = (100., 100.);
+
+        assert_eq!(
+            coeffs(FillStrat::Contain, parent, image),
+            [1., 0., 0., 1., 50., 0.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::Cover, parent, image),
+            [2., 0., 0., 2., 0., -50.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::Fill, parent, image),
+            [2., 0., 0., 1., 0., 0.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::FitHeight, parent, image),
+            [1., 0., 0., 1., 50., 0.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::FitWidth, parent, image),
+            [2., 0., 0., 2., 0., -50.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::None, parent, image),
+            [1., 0., 0., 1., 50., 0.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::ScaleDown, parent, image),
+            [1., 0., 0., 1., 50., 0.]
+        );
+    }
+
+    #[test]
+    fn fill_tall_image() {
+        // an image four times taller than it is wide, larger than the parent
+        let parent = (100., 100.);
+        let image = (50., 200.);
+
+        assert_eq!(
+            coeffs(FillStrat::Contain, parent, image),
+            [0.5, 0., 0., 0.5, 37.5, 0.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::Cover, parent, image),
+            [2., 0., 0., 2., 0., -150.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::Fill, parent, image),
+            [2., 0., 0., 0.5, 0., 0.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::FitHeight, parent, image),
+            [0.5, 0., 0., 0.5, 37.5, 0.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::FitWidth, parent, image),
+            [2., 0., 0., 2., 0., -150.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::None, parent, image),
+            [1., 0., 0., 1., 25., -50.]
+        );
+        assert_eq!(
+            coeffs(FillStrat::ScaleDown, parent, image),
+            [0.5, 0., 0., 0.5, 37.5, 0.]
+        );
+    }
+
+    #[test]
+    fn scale_down_small_image() {
+        // ScaleDown never scales up
+        assert_eq!(
+            coeffs(FillStrat::ScaleDown, (200., 100.), (50., 50.)),
+            [1., 0., 0., 1., 75., 25.]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A text box for a date, with a calendar that drops down from it.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use chrono::NaiveDate;
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::widget::{Button, Calendar, Controller, TextBox, ValidationError, WidgetExt};
+use crate::{
+    commands, theme, BoxConstraints, Env, Event, EventCtx, HotKey, KbKey, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, SysMods, Target, UpdateCtx, Widget, WidgetPod, WindowConfig,
+    WindowLevel,
+};
+
+/// The format that dates are shown in by default, as in `2020-06-30`.
+const DEFAULT_FORMAT: &str = "%Y-%m-%d";
+
+/// A text box that shows a date, with a button that opens a [`Calendar`]
+/// below it.
+///
+/// The date can be typed in the format of the picker, which is that of
+/// `chrono`'s [`format`]; while the text is not a date, the box shows an
+/// error and the data keeps the last date. Picking a day in the calendar
+/// sets the date and closes it.
+///
+/// [`Calendar`]: struct.Calendar.html
+/// [`format`]: https://docs.rs/chrono/0.4/chrono/format/strftime/index.html
+pub struct DatePicker {
+    text: WidgetPod<NaiveDate, DateText>,
+    button: WidgetPod<NaiveDate, Button<NaiveDate>>,
+    /// Set by the button, so that the calendar is opened below the whole
+    /// picker rather than below the button.
+    open_requested: Rc<Cell<bool>>,
+}
+
+/// A `TextBox` that edits a date as text.
+struct DateText {
+    format: String,
+    text: String,
+    textbox: TextBox,
+}
+
+/// Closes the window of a calendar when a day is picked in it.
+struct ClosesOnPick;
+
+impl DatePicker {
+    /// Create a picker that shows dates as `2020-06-30`.
+    pub fn new() -> DatePicker {
+        let open_requested = Rc::new(Cell::new(false));
+        let request = open_requested.clone();
+        let button = Button::new("▾", move |_ctx, _data: &mut NaiveDate, _env| {
+            request.set(true)
+        });
+        DatePicker {
+            text: WidgetPod::new(DateText::new(DEFAULT_FORMAT)),
+            button: WidgetPod::new(button),
+            open_requested,
+        }
+    }
+
+    /// Builder-style method to set the format of the date, in the syntax of
+    /// `chrono`'s [`format`].
+    ///
+    /// [`format`]: https://docs.rs/chrono/0.4/chrono/format/strftime/index.html
+    pub fn with_format(mut self, format: impl Into<String>) -> Self {
+        self.text = WidgetPod::new(DateText::new(format));
+        self
+    }
+
+    /// Open the calendar below the picker.
+    fn open_calendar(&self, ctx: &mut EventCtx, data: &NaiveDate, env: &Env) {
+        let calendar = Calendar::new()
+            .controller(ClosesOnPick)
+            .background(theme::BACKGROUND_LIGHT)
+            .border(theme::BORDER_LIGHT, 1.0);
+        // the calendar is given room for its largest months
+        let size = Calendar::size_for_rows(6) + Size::new(2.0, 2.0);
+        let config = WindowConfig::default()
+            .set_level(WindowLevel::DropDown)
+            .show_titlebar(false)
+            .resizable(false)
+            .window_size(size)
+            .set_position(ctx.to_screen(Point::new(0.0, ctx.size().height)));
+        ctx.new_sub_window(config, calendar, *data, env.clone());
+    }
+}
+
+impl Default for DatePicker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DateText {
+    fn new(format: impl Into<String>) -> DateText {
+        let format = format.into();
+        let validator_format = format.clone();
+        let textbox = TextBox::new().with_validator(move |text| {
+            NaiveDate::parse_from_str(text, &validator_format)
+                .map(|_| ())
+                .map_err(|e| ValidationError::new(e.to_string()))
+        });
+        DateText {
+            format,
+            text: String::new(),
+            textbox,
+        }
+    }
+
+    /// The date in the text, if it is one.
+    fn parsed(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.text, &self.format).ok()
+    }
+}
+
+impl Widget<NaiveDate> for DateText {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut NaiveDate, env: &Env) {
+        self.textbox.event(ctx, event, &mut self.text, env);
+        if let Some(date) = self.parsed() {
+            *data = date;
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &NaiveDate,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.text = data.format(&self.format).to_string();
+        }
+        self.textbox.lifecycle(ctx, event, &self.text, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &NaiveDate, data: &NaiveDate, env: &Env) {
+        // text that is being typed is kept, as long as it is the same date
+        if self.parsed() != Some(*data) {
+            let old_text = std::mem::replace(&mut self.text, data.format(&self.format).to_string());
+            self.textbox.update(ctx, &old_text, &self.text, env);
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &NaiveDate,
+        env: &Env,
+    ) -> Size {
+        self.textbox.layout(ctx, bc, &self.text, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &NaiveDate, env: &Env) {
+        self.textbox.paint(ctx, &self.text, env)
+    }
+}
+
+impl Controller<NaiveDate, Calendar> for ClosesOnPick {
+    fn event(
+        &mut self,
+        child: &mut Calendar,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut NaiveDate,
+        env: &Env,
+    ) {
+        let old_data = *data;
+        child.event(ctx, event, data, env);
+        let picked = match event {
+            Event::MouseUp(_) => *data != old_data,
+            Event::KeyDown(key) => HotKey::new(SysMods::None, KbKey::Enter).matches(key),
+            _ => false,
+        };
+        if picked {
+            ctx.submit_command(commands::CLOSE_WINDOW, Target::Auto);
+        }
+    }
+}
+
+impl Widget<NaiveDate> for DatePicker {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut NaiveDate, env: &Env) {
+        self.text.event(ctx, event, data, env);
+        self.button.event(ctx, event, data, env);
+        if self.open_requested.replace(false) {
+            self.open_calendar(ctx, data, env);
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &NaiveDate,
+        env: &Env,
+    ) {
+        self.text.lifecycle(ctx, event, data, env);
+        self.button.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &NaiveDate, data: &NaiveDate, env: &Env) {
+        self.text.update(ctx, data, env);
+        self.button.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &NaiveDate,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("DatePicker");
+
+        let button_size = self.button.layout(ctx, &bc.loosen(), data, env);
+        let shrink = |size: Size| Size::new((size.width - button_size.width).max(0.0), size.height);
+        let text_bc = BoxConstraints::new(shrink(bc.min()), shrink(bc.max()));
+        let text_size = self.text.layout(ctx, &text_bc, data, env);
+
+        let height = text_size.height.max(button_size.height);
+        let text_origin = Point::new(0.0, (height - text_size.height) / 2.0);
+        self.text.set_layout_rect(
+            ctx,
+            data,
+            env,
+            Rect::from_origin_size(text_origin, text_size),
+        );
+        let button_origin = Point::new(text_size.width, (height - button_size.height) / 2.0);
+        self.button.set_layout_rect(
+            ctx,
+            data,
+            env,
+            Rect::from_origin_size(button_origin, button_size),
+        );
+        bc.constrain(Size::new(text_size.width + button_size.width, height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &NaiveDate, env: &Env) {
+        self.text.paint_with_offset(ctx, data, env);
+        self.button.paint_with_offset(ctx, data, env);
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A controller that disables a widget depending on the data.
+
+use crate::widget::Controller;
+use crate::{Data, Env, LifeCycle, LifeCycleCtx, UpdateCtx, Widget};
+
+type PredicateFn<T> = dyn Fn(&T, &Env) -> bool;
+
+/// A [`Controller`] that disables its widget when a closure of the data
+/// returns `true`.
+///
+/// The closure is called when the widget is added, and on each update.
+/// See [`EventCtx::set_disabled`] for what being disabled means.
+///
+/// This is usually used with [`WidgetExt::disabled_if`].
+///
+/// [`Controller`]: trait.Controller.html
+/// [`EventCtx::set_disabled`]: ../struct.EventCtx.html#method.set_disabled
+/// [`WidgetExt::disabled_if`]: trait.WidgetExt.html#method.disabled_if
+pub struct DisabledIf<T> {
+    disabled_if: Box<PredicateFn<T>>,
+}
+
+impl<T: Data> DisabledIf<T> {
+    /// Create a new `DisabledIf` that disables its widget while
+    /// `disabled_if` returns `true`.
+    pub fn new(disabled_if: impl Fn(&T, &Env) -> bool + 'static) -> Self {
+        DisabledIf {
+            disabled_if: Box::new(disabled_if),
+        }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Controller<T, W> for DisabledIf<T> {
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.set_disabled((self.disabled_if)(data, env));
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, child: &mut W, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        ctx.set_disabled((self.disabled_if)(data, env));
+        child.update(ctx, old_data, data, env);
+    }
+}
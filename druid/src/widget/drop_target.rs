@@ -0,0 +1,71 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A controller that lets a widget take what is dropped on it.
+
+use crate::widget::Controller;
+use crate::{Data, DragPayload, Env, Event, EventCtx, Widget};
+
+type AcceptsFn<T> = dyn Fn(&DragPayload, &T, &Env) -> bool;
+type DropFn<T> = dyn Fn(&mut EventCtx, &DragPayload, &mut T, &Env);
+
+/// A [`Controller`] that lets its widget take things that are dragged onto
+/// it, such as files from the file manager.
+///
+/// While something is dragged over the widget, `accepts` says whether it
+/// would be taken; if so, dropping it calls `on_drop`. This is usually used
+/// with [`WidgetExt::on_drop`].
+///
+/// [`Controller`]: trait.Controller.html
+/// [`WidgetExt::on_drop`]: trait.WidgetExt.html#method.on_drop
+pub struct DropTarget<T> {
+    accepts: Box<AcceptsFn<T>>,
+    on_drop: Box<DropFn<T>>,
+}
+
+impl<T: Data> DropTarget<T> {
+    /// Create a new `DropTarget` that takes what `accepts` returns `true`
+    /// for, and calls `on_drop` when it is dropped.
+    pub fn new(
+        accepts: impl Fn(&DragPayload, &T, &Env) -> bool + 'static,
+        on_drop: impl Fn(&mut EventCtx, &DragPayload, &mut T, &Env) + 'static,
+    ) -> Self {
+        DropTarget {
+            accepts: Box::new(accepts),
+            on_drop: Box::new(on_drop),
+        }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Controller<T, W> for DropTarget<T> {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        // a child that takes the drop itself comes first
+        child.event(ctx, event, data, env);
+        match event {
+            Event::DragEnter(drag) | Event::DragOver(drag) => {
+                if (self.accepts)(&drag.payload, data, env) {
+                    ctx.accept_drop();
+                }
+            }
+            Event::Drop(drag) if !ctx.is_handled() => {
+                if (self.accepts)(&drag.payload, data, env) {
+                    (self.on_drop)(ctx, &drag.payload, data, env);
+                    ctx.accept_drop();
+                    ctx.set_handled();
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A button that picks one of a list of options from a dropdown.
+
+use std::rc::Rc;
+
+use crate::widget::{Button, Flex, Label, Painter, WidgetExt};
+use crate::{
+    commands, theme, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, RenderContext, Size, Target, UpdateCtx, Widget, WindowConfig,
+    WindowLevel,
+};
+
+/// A button that shows the selected one of a list of options, and opens a
+/// dropdown window below itself to pick another.
+///
+/// The dropdown shares the widget's data, so picking an option sets it, and
+/// closes the dropdown. Like other popups, the dropdown also closes when the
+/// user clicks elsewhere in the window, or presses Escape.
+pub struct DropdownSelect<T> {
+    button: Button<T>,
+}
+
+impl<T: Data + PartialEq> DropdownSelect<T> {
+    /// Given `(label, value)` pairs, create a widget that picks one of the
+    /// values.
+    ///
+    /// The button shows the label of the current value, or nothing if it is
+    /// none of them.
+    pub fn new(options: impl IntoIterator<Item = (impl Into<String>, T)>) -> DropdownSelect<T> {
+        let options: Rc<Vec<(String, T)>> = Rc::new(
+            options
+                .into_iter()
+                .map(|(label, value)| (label.into(), value))
+                .collect(),
+        );
+        let shown = options.clone();
+        let text = move |data: &T, _: &Env| {
+            shown
+                .iter()
+                .find(|(_, value)| value == data)
+                .map(|(label, _)| label.clone())
+                .unwrap_or_default()
+        };
+        let button = Button::new(text, move |ctx, data: &mut T, env| {
+            open_dropdown(ctx, &options, data, env)
+        });
+        DropdownSelect { button }
+    }
+}
+
+/// Open the dropdown below the widget.
+fn open_dropdown<T: Data + PartialEq>(
+    ctx: &mut EventCtx,
+    options: &[(String, T)],
+    data: &T,
+    env: &Env,
+) {
+    let item_height = env.get(theme::BORDERED_WIDGET_HEIGHT);
+    let mut list = Flex::column();
+    for (label, value) in options {
+        let value = value.clone();
+        let item = Label::new(label.as_str())
+            .padding((8.0, 2.0))
+            .expand_width()
+            .fix_height(item_height)
+            .background(Painter::new(|ctx, _: &T, env| {
+                if ctx.is_hot() {
+                    let rect = ctx.size().to_rect();
+                    ctx.fill(rect, &env.get(theme::SELECTION_COLOR));
+                }
+            }))
+            .on_click(move |ctx, data: &mut T, _| {
+                *data = value.clone();
+                ctx.submit_command(commands::CLOSE_WINDOW, Target::Auto);
+            });
+        list.add_child(item, 0.0);
+    }
+    let list = list
+        .background(theme::BACKGROUND_LIGHT)
+        .border(theme::BORDER_LIGHT, 1.0);
+
+    let size = Size::new(ctx.size().width, item_height * options.len() as f64 + 2.0);
+    let config = WindowConfig::default()
+        .set_level(WindowLevel::DropDown)
+        .show_titlebar(false)
+        .resizable(false)
+        .window_size(size)
+        .set_position(ctx.to_screen(Point::new(0.0, ctx.size().height)));
+    ctx.new_sub_window(config, list, data.clone(), env.clone());
+}
+
+impl<T: Data + PartialEq> Widget<T> for DropdownSelect<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.button.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.button.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.button.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("DropdownSelect");
+        self.button.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.button.paint(ctx, data, env)
+    }
+}
@@ -86,14 +86,22 @@ impl<T: Data> Widget<T> for Either<T> {
     ) -> Size {
         if self.current {
             let size = self.true_branch.layout(layout_ctx, bc, data, env);
-            self.true_branch
-                .set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+            self.true_branch.set_layout_rect(
+                layout_ctx,
+                data,
+                env,
+                Rect::from_origin_size(Point::ORIGIN, size),
+            );
             layout_ctx.set_paint_insets(self.true_branch.paint_insets());
             size
         } else {
             let size = self.false_branch.layout(layout_ctx, bc, data, env);
-            self.false_branch
-                .set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+            self.false_branch.set_layout_rect(
+                layout_ctx,
+                data,
+                env,
+                Rect::from_origin_size(Point::ORIGIN, size),
+            );
             layout_ctx.set_paint_insets(self.true_branch.paint_insets());
             size
         }
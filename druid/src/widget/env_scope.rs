@@ -14,19 +14,27 @@
 
 //! A widget that accepts a closure to update the environment for its child.
 
-use crate::kurbo::Size;
+use crate::kurbo::{Point, Rect, Size};
 use crate::{
     BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    UpdateCtx, Widget, WidgetId,
+    UpdateCtx, Widget, WidgetPod,
 };
 
 /// A widget that accepts a closure to update the environment for its child.
+///
+/// The child is in a [`WidgetPod`] of its own, so that it can tell in
+/// [`update`] which keys the closure changed, with
+/// [`UpdateCtx::env_key_changed`].
+///
+/// [`WidgetPod`]: ../struct.WidgetPod.html
+/// [`update`]: trait.Widget.html#tymethod.update
+/// [`UpdateCtx::env_key_changed`]: ../struct.UpdateCtx.html#method.env_key_changed
 pub struct EnvScope<T, W> {
     pub(crate) f: Box<dyn Fn(&mut Env, &T)>,
-    pub(crate) child: W,
+    pub(crate) child: WidgetPod<T, W>,
 }
 
-impl<T, W> EnvScope<T, W> {
+impl<T: Data, W: Widget<T>> EnvScope<T, W> {
     /// Create a widget that updates the environment for its child.
     ///
     /// Accepts a closure that sets Env values.
@@ -51,7 +59,7 @@ impl<T, W> EnvScope<T, W> {
     pub fn new(f: impl Fn(&mut Env, &T) + 'static, child: W) -> EnvScope<T, W> {
         EnvScope {
             f: Box::new(f),
-            child,
+            child: WidgetPod::new(child),
         }
     }
 }
@@ -67,14 +75,14 @@ impl<T: Data, W: Widget<T>> Widget<T> for EnvScope<T, W> {
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
         let mut new_env = env.clone();
         (self.f)(&mut new_env, &data);
-        self.child.lifecycle(ctx, event, data, env)
+        self.child.lifecycle(ctx, event, data, &new_env)
     }
 
-    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
         let mut new_env = env.clone();
         (self.f)(&mut new_env, &data);
 
-        self.child.update(ctx, old_data, data, &new_env);
+        self.child.update(ctx, data, &new_env);
     }
 
     fn layout(
@@ -89,17 +97,22 @@ impl<T: Data, W: Widget<T>> Widget<T> for EnvScope<T, W> {
         let mut new_env = env.clone();
         (self.f)(&mut new_env, &data);
 
-        self.child.layout(layout_ctx, &bc, data, &new_env)
+        let size = self.child.layout(layout_ctx, &bc, data, &new_env);
+        self.child.set_layout_rect(
+            layout_ctx,
+            data,
+            &new_env,
+            Rect::from_origin_size(Point::ORIGIN, size),
+        );
+        layout_ctx.set_paint_insets(self.child.compute_parent_paint_insets(size));
+        layout_ctx.set_baseline_offset(self.child.baseline_offset());
+        size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
         let mut new_env = env.clone();
         (self.f)(&mut new_env, &data);
 
-        self.child.paint(ctx, data, &new_env);
-    }
-
-    fn id(&self) -> Option<WidgetId> {
-        self.child.id()
+        self.child.paint_with_offset(ctx, data, &new_env);
     }
 }
@@ -16,13 +16,27 @@
 
 use crate::kurbo::{Point, Rect, Size};
 
+use crate::core::paint_order;
 use crate::widget::SizedBox;
 use crate::{
-    BoxConstraints, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle, LifeCycleCtx,
-    PaintCtx, UpdateCtx, Widget, WidgetPod,
+    BoxConstraints, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LayoutDirection, LifeCycle,
+    LifeCycleCtx, PaintCtx, UpdateCtx, Widget, WidgetPod,
 };
 
 /// A container with either horizontal or vertical layout.
+///
+/// Children can overlap, for instance with a negative spacer or when they
+/// paint outside of their layout rect. Later children are painted above
+/// earlier ones, and get mouse events first; a child with a higher z-index,
+/// added with [`add_layered_child`], is above those with a lower one,
+/// whatever the order.
+///
+/// When the [`LayoutDirection`] is right to left, the layout is mirrored: a
+/// row's children run from the right, with `Start` on the right, and in a
+/// column the leading cross alignment is the right.
+///
+/// [`add_layered_child`]: #method.add_layered_child
+/// [`LayoutDirection`]: ../enum.LayoutDirection.html
 pub struct Flex<T> {
     direction: Axis,
     cross_alignment: CrossAxisAlignment,
@@ -66,6 +80,19 @@ pub enum CrossAxisAlignment {
     /// In a vertical container, widgets are bottom aligned. In a horiziontal
     /// container, their trailing edges are aligned.
     End,
+    /// Widgets are aligned on the baseline of their text.
+    ///
+    /// In a horizontal container, the baselines reported by the children
+    /// (with [`LayoutCtx::set_baseline_offset`]) are lined up. In a vertical
+    /// container, this is the same as `Start`.
+    ///
+    /// [`LayoutCtx::set_baseline_offset`]: ../struct.LayoutCtx.html#method.set_baseline_offset
+    Baseline,
+    /// Widgets fill the container.
+    ///
+    /// The container is as big as its biggest child on the minor axis, and
+    /// the other children are stretched to the same size.
+    Fill,
 }
 
 /// Arrangement of children on the main axis.
@@ -125,14 +152,8 @@ impl Axis {
     /// Generate constraints with new values on the major axis.
     fn constraints(self, bc: &BoxConstraints, min_major: f64, major: f64) -> BoxConstraints {
         match self {
-            Axis::Horizontal => BoxConstraints::new(
-                Size::new(min_major, bc.min().height),
-                Size::new(major, bc.max().height),
-            ),
-            Axis::Vertical => BoxConstraints::new(
-                Size::new(bc.min().width, min_major),
-                Size::new(bc.max().width, major),
-            ),
+            Axis::Horizontal => bc.with_width_range(min_major, major),
+            Axis::Vertical => bc.with_height_range(min_major, major),
         }
     }
 }
@@ -207,6 +228,17 @@ impl<T: Data> Flex<T> {
         self
     }
 
+    /// Builder-style variant of `add_layered_child`.
+    pub fn with_layered_child(
+        mut self,
+        child: impl Widget<T> + 'static,
+        flex: f64,
+        z_index: i32,
+    ) -> Self {
+        self.add_layered_child(child, flex, z_index);
+        self
+    }
+
     /// Builder-style method for adding a fixed-size spacer to the container.
     pub fn with_spacer(mut self, len: impl Into<KeyOrValue<f64>>) -> Self {
         self.add_spacer(len);
@@ -251,7 +283,8 @@ impl<T: Data> Flex<T> {
     ///
     /// If `flex` is non-zero, then all the space left over after layout of
     /// the non-flex children is divided up, in proportion to the `flex` value,
-    /// among the flex children.
+    /// among the flex children. The flex children get whole numbers of display
+    /// points, except for the last, which gets whatever is left.
     ///
     /// See also `with_child`.
     pub fn add_child(&mut self, child: impl Widget<T> + 'static, flex: f64) {
@@ -263,6 +296,23 @@ impl<T: Data> Flex<T> {
         self.children.push(child);
     }
 
+    /// Add a child widget with a z-index.
+    ///
+    /// Where children overlap, those with a higher z-index are painted above
+    /// the others and get mouse events first; children added with
+    /// `add_child` have a z-index of `0`. This only changes the stacking
+    /// order, not the layout order or the focus order.
+    ///
+    /// See [`add_child`] for an overview of `flex`.
+    ///
+    /// [`add_child`]: #method.add_child
+    pub fn add_layered_child(&mut self, child: impl Widget<T> + 'static, flex: f64, z_index: i32) {
+        self.add_child(child, flex);
+        if let Some(child) = self.children.last_mut() {
+            child.widget.set_z_index(z_index);
+        }
+    }
+
     /// Add an empty spacer widget with the given length.
     pub fn add_spacer(&mut self, len: impl Into<KeyOrValue<f64>>) {
         let spacer = Spacer {
@@ -280,12 +330,24 @@ impl<T: Data> Flex<T> {
     pub fn add_flex_spacer(&mut self, flex: f64) {
         self.add_child(SizedBox::empty(), flex);
     }
+
+    /// The indices of the children, bottom to top.
+    fn paint_order(&self) -> Vec<usize> {
+        paint_order(self.children.iter().map(|child| child.widget.z_index()))
+    }
 }
 
 impl<T: Data> Widget<T> for Flex<T> {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
-        for child in &mut self.children {
-            child.widget.event(ctx, event, data, env);
+        if event.is_pointer_event() {
+            // the topmost child gets the first chance to handle it
+            for idx in self.paint_order().into_iter().rev() {
+                self.children[idx].widget.event(ctx, event, data, env);
+            }
+        } else {
+            for child in &mut self.children {
+                child.widget.event(ctx, event, data, env);
+            }
         }
     }
 
@@ -312,41 +374,64 @@ impl<T: Data> Widget<T> for Flex<T> {
         // we loosen our constraints when passing to children.
         let loosened_bc = bc.loosen();
 
+        // the size of each child; they are only placed once they are all
+        // measured
+        let mut sizes = vec![Size::ZERO; self.children.len()];
+
         // Measure non-flex children.
         let mut total_non_flex = 0.0;
         let mut minor = self.direction.minor(bc.min());
-        for child in &mut self.children {
+        for (child, size) in self.children.iter_mut().zip(&mut sizes) {
             if child.params.flex == 0.0 {
                 let child_bc = self
                     .direction
                     .constraints(&loosened_bc, 0.0, std::f64::INFINITY);
+                // roughly where the child goes, before spacing
+                layout_ctx.child_origin = Some(self.direction.pack(total_non_flex, 0.0).into());
                 let child_size = child.widget.layout(layout_ctx, &child_bc, data, env);
                 minor = minor.max(self.direction.minor(child_size));
                 total_non_flex += self.direction.major(child_size);
-                // Stash size.
-                let rect = Rect::from_origin_size(Point::ORIGIN, child_size);
-                child.widget.set_layout_rect(rect);
+                *size = child_size;
             }
         }
 
         let total_major = self.direction.major(bc.max());
         let remaining = (total_major - total_non_flex).max(0.0);
         let flex_sum: f64 = self.children.iter().map(|child| child.params.flex).sum();
+        let flex_count = self
+            .children
+            .iter()
+            .filter(|child| child.params.flex != 0.0)
+            .count();
         let mut flex_used: f64 = 0.0;
+        let mut flex_seen = 0;
+        // the space given to the flex children so far, and what they wanted
+        let mut major_given = 0.0;
+        let mut major_wanted = 0.0;
 
         // Measure flex children.
-        for child in &mut self.children {
+        for (child, size) in self.children.iter_mut().zip(&mut sizes) {
             if child.params.flex != 0.0 {
-                let major = remaining * child.params.flex / flex_sum;
+                flex_seen += 1;
+                major_wanted += remaining * child.params.flex / flex_sum;
+                // we round where each child ends rather than each child's
+                // length, so rounding errors don't add up; the last child
+                // ends exactly at the end.
+                let major = if remaining.is_infinite() {
+                    remaining
+                } else if flex_seen == flex_count {
+                    (remaining - major_given).max(0.0)
+                } else {
+                    (major_wanted.round() - major_given).max(0.0)
+                };
+                major_given += major;
 
                 let min_major = if major.is_infinite() { 0.0 } else { major };
                 let child_bc = self.direction.constraints(&loosened_bc, min_major, major);
                 let child_size = child.widget.layout(layout_ctx, &child_bc, data, env);
                 flex_used += self.direction.major(child_size);
                 minor = minor.max(self.direction.minor(child_size));
-                // Stash size.
-                let rect = Rect::from_origin_size(Point::ORIGIN, child_size);
-                child.widget.set_layout_rect(rect);
+                *size = child_size;
             }
         }
 
@@ -359,19 +444,50 @@ impl<T: Data> Widget<T> for Flex<T> {
             (self.direction.major(bc.min()) - (total_non_flex + flex_used)).max(0.0)
         };
 
+        // with baseline alignment, the children's tallest part above the
+        // baseline and below it
+        let baseline_row = match (self.cross_alignment, self.direction) {
+            (CrossAxisAlignment::Baseline, Axis::Horizontal) => true,
+            _ => false,
+        };
+        let mut above_baseline = 0.0_f64;
+        if baseline_row {
+            let mut below_baseline = 0.0_f64;
+            for (child, size) in self.children.iter().zip(&sizes) {
+                let baseline = child.widget.baseline_offset();
+                let height = size.height;
+                above_baseline = above_baseline.max(height - baseline);
+                below_baseline = below_baseline.max(baseline);
+            }
+            minor = minor.max(above_baseline + below_baseline);
+        }
+
+        // stretch the children that are smaller than the biggest one
+        if self.cross_alignment == CrossAxisAlignment::Fill {
+            for (child, size) in self.children.iter_mut().zip(&mut sizes) {
+                if self.direction.minor(*size) < minor {
+                    let major = self.direction.major(*size);
+                    let child_bc = BoxConstraints::tight(self.direction.pack(major, minor).into());
+                    *size = child.widget.layout(layout_ctx, &child_bc, data, env);
+                }
+            }
+        }
+
         let spacing = self.main_alignment.spacing(extra, self.children.len());
         // Finalize layout, assigning positions to each child.
         let mut major = spacing.pre;
-        let mut child_paint_rect = Rect::ZERO;
-        for child in &mut self.children {
-            let rect = child.widget.layout_rect();
-            let extra_minor = minor - self.direction.minor(rect.size());
-            let align_minor = self.cross_alignment.align(extra_minor);
+        let mut rects = Vec::with_capacity(self.children.len());
+        for (child, size) in self.children.iter().zip(&sizes) {
+            let align_minor = if baseline_row {
+                above_baseline - (size.height - child.widget.baseline_offset())
+            } else {
+                let extra_minor = minor - self.direction.minor(*size);
+                self.cross_alignment.align(extra_minor)
+            };
             let pos: Point = self.direction.pack(major, align_minor).into();
 
-            child.widget.set_layout_rect(rect.with_origin(pos));
-            child_paint_rect = child_paint_rect.union(child.widget.paint_rect());
-            major += self.direction.major(rect.size());
+            rects.push(Rect::from_origin_size(pos, *size));
+            major += self.direction.major(*size);
             major += spacing.between;
         }
         major -= spacing.between;
@@ -397,15 +513,35 @@ impl<T: Data> Widget<T> for Flex<T> {
             bc.constrain(my_size)
         };
 
+        // right to left, everything is mirrored: a row runs from the right,
+        // and a column's leading cross alignment is on the right
+        let layout_direction = LayoutDirection::from_env(env);
+        let mut child_paint_rect = Rect::ZERO;
+        for (child, rect) in self.children.iter_mut().zip(rects) {
+            let rect = layout_direction.mirror(rect, my_size.width);
+            child.widget.set_layout_rect(layout_ctx, data, env, rect);
+            child_paint_rect = child_paint_rect.union(child.widget.paint_rect());
+        }
+
         let my_bounds = Rect::ZERO.with_size(my_size);
         let insets = child_paint_rect - my_bounds;
         layout_ctx.set_paint_insets(insets);
+
+        // our baseline is the lowest of our children's
+        if !self.children.is_empty() {
+            let baseline = self
+                .children
+                .iter()
+                .map(|child| child.widget.layout_rect().y1 - child.widget.baseline_offset())
+                .fold(std::f64::NEG_INFINITY, f64::max);
+            layout_ctx.set_baseline_offset(my_size.height - baseline);
+        }
         my_size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
-        for child in &mut self.children {
-            child.widget.paint_with_offset(ctx, data, env);
+        for idx in self.paint_order() {
+            self.children[idx].widget.paint_with_offset(ctx, data, env);
         }
     }
 }
@@ -416,7 +552,9 @@ impl CrossAxisAlignment {
     /// this alignment.
     fn align(self, val: f64) -> f64 {
         match self {
-            CrossAxisAlignment::Start => 0.0,
+            CrossAxisAlignment::Start | CrossAxisAlignment::Baseline | CrossAxisAlignment::Fill => {
+                0.0
+            }
             CrossAxisAlignment::Center => val / 2.0,
             CrossAxisAlignment::End => val,
         }
@@ -444,6 +582,7 @@ impl MainAxisAlignment {
                 let space = extra / n;
                 (space, space, space)
             }
+            MainAxisAlignment::SpaceAround if n_children == 0 => (extra * 0.5, 0., extra * 0.5),
             MainAxisAlignment::SpaceAround => {
                 let n = n_children as f64;
                 let space = extra / n;
@@ -492,3 +631,189 @@ impl<T: Data> Widget<T> for Spacer {
     }
     fn paint(&mut self, _: &mut PaintCtx, _: &T, _: &Env) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Label, WidgetExt};
+    use crate::WidgetId;
+
+    /// A widget that wants to be `size`, with its baseline `baseline` above its
+    /// bottom edge.
+    fn baseline_box(id: WidgetId, size: Size, baseline: f64) -> impl Widget<()> {
+        ModularWidget::new(())
+            .layout_fn(move |_, ctx, bc, _data, _env| {
+                ctx.set_baseline_offset(baseline);
+                bc.constrain(size)
+            })
+            .with_id(id)
+    }
+
+    /// A widget that takes all the space it is given, with the given id.
+    fn greedy_box(id: WidgetId) -> impl Widget<()> {
+        ModularWidget::new(())
+            .layout_fn(|_, _ctx, bc, _data, _env| bc.max())
+            .with_id(id)
+    }
+
+    #[test]
+    fn flex_main_axis_alignment() {
+        let (id_1, id_2) = widget_id2();
+        let cases = [
+            (MainAxisAlignment::Start, 0., 100.),
+            (MainAxisAlignment::Center, 100., 200.),
+            (MainAxisAlignment::End, 200., 300.),
+            (MainAxisAlignment::SpaceBetween, 0., 300.),
+            (MainAxisAlignment::SpaceAround, 50., 250.),
+            (MainAxisAlignment::SpaceEvenly, 200. / 3., 400. / 3. + 100.),
+        ];
+
+        for (alignment, x_1, x_2) in cases.iter() {
+            let widget = Flex::row()
+                .with_child(fixed_box(id_1), 0.0)
+                .with_child(fixed_box(id_2), 0.0)
+                .main_axis_alignment(*alignment)
+                .must_fill_main_axis(true);
+            Harness::create((), widget, |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                let x0 = |harness: &mut Harness<()>, id| harness.get_state(id).layout_rect.x0;
+                assert!((x0(harness, id_1) - x_1).abs() < 1e-9, "{:?}", alignment);
+                assert!((x0(harness, id_2) - x_2).abs() < 1e-9, "{:?}", alignment);
+            });
+        }
+    }
+
+    #[test]
+    fn flex_divides_remaining_space() {
+        let (fixed, id_1, id_2, id_3) = widget_id4();
+        // 299 points are left for the flex children, which can't split it evenly
+        let widget = Flex::row()
+            .with_child(fixed_box(fixed), 0.0)
+            .with_spacer(1.0)
+            .with_child(greedy_box(id_1), 1.0)
+            .with_child(greedy_box(id_2), 1.0)
+            .with_child(greedy_box(id_3), 1.0);
+
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let rects: Vec<Rect> = [id_1, id_2, id_3]
+                .iter()
+                .map(|id| harness.get_state(*id).layout_rect)
+                .collect();
+            // each starts where the last ended, on a whole point
+            assert_eq!(rects[0].x0, 101.);
+            assert_eq!(rects[1].x0, rects[0].x1);
+            assert_eq!(rects[2].x0, rects[1].x1);
+            assert_eq!(rects[1].x0, rects[1].x0.round());
+            assert_eq!(rects[2].x0, rects[2].x0.round());
+            assert_eq!(rects[2].x1, 400.);
+            for rect in &rects {
+                assert!((rect.width() - 299. / 3.).abs() < 1.);
+            }
+        });
+    }
+
+    #[test]
+    fn flex_cross_axis_alignment() {
+        let (id_1, id_2) = widget_id2();
+        let cases = [
+            (CrossAxisAlignment::Start, (0., 50.), (0., 20.)),
+            (CrossAxisAlignment::Center, (0., 50.), (15., 20.)),
+            (CrossAxisAlignment::End, (0., 50.), (30., 20.)),
+            (CrossAxisAlignment::Fill, (0., 50.), (0., 50.)),
+            // 40 above the first baseline, 15 above the second
+            (CrossAxisAlignment::Baseline, (0., 50.), (25., 20.)),
+        ];
+
+        for (alignment, (y_1, height_1), (y_2, height_2)) in cases.iter() {
+            let widget = Flex::row()
+                .with_child(baseline_box(id_1, Size::new(100., 50.), 10.), 0.0)
+                .with_child(baseline_box(id_2, Size::new(100., 20.), 5.), 0.0)
+                .cross_axis_alignment(*alignment)
+                .align_left();
+            Harness::create((), widget, |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                let rect_1 = harness.get_state(id_1).layout_rect;
+                let rect_2 = harness.get_state(id_2).layout_rect;
+                assert_eq!(
+                    (rect_1.y0, rect_1.height()),
+                    (*y_1, *height_1),
+                    "{:?}",
+                    alignment
+                );
+                assert_eq!(
+                    (rect_2.y0, rect_2.height()),
+                    (*y_2, *height_2),
+                    "{:?}",
+                    alignment
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn flex_baseline_aligns_labels() {
+        let (big, small) = widget_id2();
+        let widget = Flex::row()
+            .with_child(Label::new("big").with_text_size(30.).with_id(big), 0.0)
+            .with_child(Label::new("small").with_text_size(10.).with_id(small), 0.0)
+            .cross_axis_alignment(CrossAxisAlignment::Baseline)
+            .align_left();
+
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let big = harness.get_state(big).layout_rect;
+            let small = harness.get_state(small).layout_rect;
+            // the small label is pushed down to the big one's baseline, which is
+            // above the big one's bottom
+            assert_eq!(big.y0, 0.);
+            assert!(small.y0 > 0.);
+            assert!(small.y1 < big.y1);
+        });
+    }
+
+    #[test]
+    fn flex_without_space() {
+        let (empty, id_1, id_2) = widget_id3();
+        // no children, but space to distribute
+        let widget = Flex::<()>::row()
+            .main_axis_alignment(MainAxisAlignment::SpaceAround)
+            .must_fill_main_axis(true)
+            .with_id(empty);
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            assert_eq!(harness.get_state(empty).layout_rect.width(), 400.);
+        });
+
+        // children, but no space
+        for alignment in [
+            MainAxisAlignment::SpaceBetween,
+            MainAxisAlignment::SpaceEvenly,
+        ]
+        .iter()
+        {
+            let widget = Flex::row()
+                .with_child(greedy_box(id_1), 1.0)
+                .with_flex_spacer(2.0)
+                .with_child(greedy_box(id_2), 1.0)
+                .main_axis_alignment(*alignment)
+                .fix_width(0.)
+                .align_left();
+            Harness::create((), widget, |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                assert_eq!(harness.get_state(id_1).layout_rect.width(), 0.);
+                let rect = harness.get_state(id_2).layout_rect;
+                assert_eq!(rect.origin(), Point::ZERO);
+                assert_eq!(rect.size(), Size::new(0., 400.));
+            });
+        }
+    }
+}
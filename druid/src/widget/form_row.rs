@@ -0,0 +1,224 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Associating labels with the widgets they describe.
+
+use crate::kurbo::Size;
+use crate::widget::{Controller, CrossAxisAlignment, Flex, Label, LabelText, WidgetExt};
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget, WidgetId,
+};
+
+/// The space between the label and the control in a `FormRow`.
+const LABEL_SPACING: f64 = 8.0;
+
+/// A [`Controller`] that makes its widget a label for another widget.
+///
+/// The association is recorded for accessibility, and clicking the label
+/// focuses the target, like a `label` element with a `for` attribute in
+/// HTML. The target is referred to by its id, so this keeps working if the
+/// target is rebuilt with the same explicit id.
+///
+/// This is usually created with [`WidgetExt::labels`].
+///
+/// [`Controller`]: trait.Controller.html
+/// [`WidgetExt::labels`]: trait.WidgetExt.html#method.labels
+pub struct LabelFor {
+    target: WidgetId,
+}
+
+/// A [`Controller`] that records the widget that labels its widget, for
+/// accessibility.
+///
+/// This is usually created with [`WidgetExt::labeled_by`].
+///
+/// [`Controller`]: trait.Controller.html
+/// [`WidgetExt::labeled_by`]: trait.WidgetExt.html#method.labeled_by
+pub struct LabeledBy {
+    label: WidgetId,
+}
+
+/// A label and a control, laid out in a row.
+///
+/// The label is associated with the control, as with [`WidgetExt::labels`]
+/// and [`WidgetExt::labeled_by`]. If the control has an explicit id, that id
+/// is used for the association.
+///
+/// [`WidgetExt::labels`]: trait.WidgetExt.html#method.labels
+/// [`WidgetExt::labeled_by`]: trait.WidgetExt.html#method.labeled_by
+pub struct FormRow<T> {
+    inner: Flex<T>,
+}
+
+impl LabelFor {
+    /// Create a controller that makes its widget a label for `target`.
+    pub fn new(target: WidgetId) -> LabelFor {
+        LabelFor { target }
+    }
+}
+
+impl LabeledBy {
+    /// Create a controller that records that its widget is labeled by `label`.
+    pub fn new(label: WidgetId) -> LabeledBy {
+        LabeledBy { label }
+    }
+}
+
+impl<T: Data> FormRow<T> {
+    /// Create a row with a label and a control.
+    pub fn new(label: impl Into<LabelText<T>>, control: impl Widget<T> + 'static) -> FormRow<T> {
+        let label_id = WidgetId::next();
+        let control_id = control.id().unwrap_or_else(WidgetId::next);
+
+        let label = Label::new(label).labels(control_id).with_id(label_id);
+        let control = control.labeled_by(label_id).with_id(control_id);
+        let inner = Flex::row()
+            .cross_axis_alignment(CrossAxisAlignment::Center)
+            .with_child(label, 0.0)
+            .with_spacer(LABEL_SPACING)
+            .with_child(control, 1.0);
+        FormRow { inner }
+    }
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for LabelFor {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(_) => ctx.set_active(true),
+            Event::MouseUp(_) if ctx.is_active() => {
+                ctx.set_active(false);
+                if ctx.is_hot() {
+                    ctx.set_focus(self.target);
+                }
+            }
+            _ => (),
+        }
+        child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.set_label_for(self.target);
+        }
+        child.lifecycle(ctx, event, data, env)
+    }
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for LabeledBy {
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.set_labeled_by(self.label);
+        }
+        child.lifecycle(ctx, event, data, env)
+    }
+}
+
+impl<T: Data> Widget<T> for FormRow<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.inner.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.inner.paint(ctx, data, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::TextBox;
+    use crate::{Code, KeyEvent, KeyModifiers, Target};
+
+    #[test]
+    fn form_row_label_focuses_control() {
+        let control = WidgetId::next();
+        let widget = FormRow::new("Email", TextBox::new().with_id(control));
+
+        Harness::create(String::new(), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            let label = harness
+                .get_state(control)
+                .labeled_by
+                .expect("control is labeled");
+            assert_eq!(harness.get_state(label).label_for, Some(control));
+            assert_eq!(harness.window().focus, None);
+
+            let center = harness.get_state(label).layout_rect.center();
+            click(harness, center);
+            assert_eq!(harness.window().focus, Some(control));
+        })
+    }
+
+    /// The label refers to the control by id, so it still works after the
+    /// control is rebuilt with the same id.
+    #[test]
+    fn label_for_survives_rebuild() {
+        let (label, control) = widget_id2();
+        let replacer = ReplaceChild::new(TextBox::new().with_id(control), move || {
+            TextBox::new().with_id(control)
+        });
+        let widget = Flex::row()
+            .with_child(Label::new("Email").labels(control).with_id(label), 0.0)
+            .with_child(replacer, 1.0);
+
+        Harness::create(String::new(), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            harness.submit_command(REPLACE_CHILD, Target::Auto);
+            harness.just_layout();
+            assert_eq!(harness.get_state(label).label_for, Some(control));
+
+            let center = harness.get_state(label).layout_rect.center();
+            click(harness, center);
+            assert_eq!(harness.window().focus, Some(control));
+
+            // the new text box has focus, and takes the typing
+            let key = KeyEvent::for_test(KeyModifiers::default(), "a", Code::KeyA);
+            harness.event(Event::KeyDown(key));
+            assert_eq!(harness.data(), "a");
+        })
+    }
+}
@@ -15,38 +15,46 @@
 //! An Image widget.
 //! Please consider using SVG and the SVG wideget as it scales much better.
 
-use std::convert::AsRef;
-use std::error::Error;
 use std::marker::PhantomData;
-use std::path::Path;
+use std::sync::Arc;
 
-use image;
+#[cfg(feature = "image")]
+use std::{convert::AsRef, error::Error, path::Path};
 
 use crate::{
-    piet::{ImageFormat, InterpolationMode},
+    piet::{ImageFormat, InterpolationMode, Piet},
     widget::common::FillStrat,
-    Affine, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
-    PaintCtx, Rect, RenderContext, Size, UpdateCtx, Widget,
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    RenderContext, Size, UpdateCtx, Widget,
 };
 
+/// The platform image object, as created by the render context.
+type PietImage = <Piet<'static> as RenderContext>::Image;
+
 /// A widget that renders an Image
 pub struct Image<T> {
-    image_data: ImageData,
+    image_data: ImageBuf,
     phantom: PhantomData<T>,
     fill: FillStrat,
     interpolation: InterpolationMode,
+    /// The image as last uploaded to the render context; invalidated
+    /// whenever the `ImageBuf` changes.
+    paint_data: Option<PietImage>,
 }
 
 impl<T: Data> Image<T> {
-    /// Create an image drawing widget from `ImageData`.
+    /// Create an image drawing widget from an [`ImageBuf`].
     ///
     /// The Image will scale to fit its box constraints.
-    pub fn new(image_data: ImageData) -> Self {
+    ///
+    /// [`ImageBuf`]: struct.ImageBuf.html
+    pub fn new(image_data: ImageBuf) -> Self {
         Image {
             image_data,
             phantom: Default::default(),
             fill: FillStrat::default(),
             interpolation: InterpolationMode::Bilinear,
+            paint_data: None,
         }
     }
 
@@ -71,6 +79,17 @@ impl<T: Data> Image<T> {
     pub fn set_interpolation_mode(&mut self, interpolation: InterpolationMode) {
         self.interpolation = interpolation;
     }
+
+    /// Replace the image displayed by this widget.
+    ///
+    /// The cached render context image is only rebuilt if the new
+    /// buffer's contents differ from the current one.
+    pub fn set_image_data(&mut self, image_data: ImageBuf) {
+        if !self.image_data.same(&image_data) {
+            self.paint_data = None;
+        }
+        self.image_data = image_data;
+    }
 }
 
 impl<T: Data> Widget<T> for Image<T> {
@@ -92,48 +111,106 @@ impl<T: Data> Widget<T> for Image<T> {
         if bc.is_width_bounded() {
             bc.max()
         } else {
-            bc.constrain(self.image_data.get_size())
+            bc.constrain(self.image_data.size())
         }
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, _env: &Env) {
-        let offset_matrix = self
-            .fill
-            .affine_to_fill(ctx.size(), self.image_data.get_size());
-
-        // The ImageData's to_piet function does not clip to the image's size
-        // CairoRenderContext is very like druids but with some extra goodies like clip
-        if self.fill != FillStrat::Contain {
-            let clip_rect = Rect::ZERO.with_size(ctx.size());
-            ctx.clip(clip_rect);
+        if self.image_data.is_empty() {
+            return;
+        }
+
+        let offset_matrix = self.fill.affine_to_fill(ctx.size(), self.image_data.size());
+
+        if self.paint_data.is_none() {
+            self.paint_data = self.image_data.to_piet_image(ctx.render_ctx);
+        }
+        let image = match self.paint_data.as_ref() {
+            Some(image) => image,
+            None => return,
+        };
+
+        let interpolation = self.interpolation;
+        let image_rect = self.image_data.size().to_rect();
+        // The image is drawn at its transformed size, which may overflow
+        // the widget; clip to our bounds for the strategies that can.
+        let clip_rect = if self.fill != FillStrat::Contain {
+            Some(ctx.size().to_rect())
+        } else {
+            None
+        };
+        let result = ctx.with_save(|ctx| {
+            if let Some(clip_rect) = clip_rect {
+                ctx.clip(clip_rect);
+            }
+            ctx.transform(offset_matrix);
+            ctx.draw_image(image, image_rect, interpolation);
+            Ok(())
+        });
+        if let Err(e) = result {
+            log::error!("painting image failed: {}", e);
         }
-        self.image_data
-            .to_piet(offset_matrix, ctx, self.interpolation);
     }
 }
 
-/// Stored Image data.
+/// An owned image buffer.
+///
+/// This holds the raw pixel data, along with its dimensions and format.
+/// The pixels are reference counted, so cloning an `ImageBuf` is cheap.
 #[derive(Clone)]
-pub struct ImageData {
-    pixels: Vec<u8>,
-    x_pixels: u32,
-    y_pixels: u32,
+pub struct ImageBuf {
+    pixels: Arc<[u8]>,
+    width: usize,
+    height: usize,
     format: ImageFormat,
 }
 
-impl ImageData {
-    /// Create an empty Image
+impl ImageBuf {
+    /// Create an empty image.
     pub fn empty() -> Self {
-        ImageData {
-            pixels: [].to_vec(),
-            x_pixels: 0,
-            y_pixels: 0,
+        ImageBuf {
+            pixels: Arc::new([0u8; 0]),
+            width: 0,
+            height: 0,
             format: ImageFormat::RgbaSeparate,
         }
     }
 
+    /// Create an image from a raw buffer of pixels.
+    ///
+    /// The buffer must contain exactly `width * height` pixels, each laid out
+    /// as described by `format`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `pixels` does not match the dimensions.
+    pub fn from_raw(
+        pixels: impl Into<Arc<[u8]>>,
+        format: ImageFormat,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        let pixels = pixels.into();
+        let bytes_per_pixel = match format {
+            ImageFormat::Rgb => 3,
+            _ => 4,
+        };
+        assert_eq!(
+            pixels.len(),
+            width * height * bytes_per_pixel,
+            "pixel buffer does not match image dimensions"
+        );
+        ImageBuf {
+            pixels,
+            width,
+            height,
+            format,
+        }
+    }
+
     /// Load an image from a DynamicImage from the image crate
-    pub fn from_dynamic_image(image_data: image::DynamicImage) -> ImageData {
+    #[cfg(feature = "image")]
+    pub fn from_dynamic_image(image_data: image::DynamicImage) -> ImageBuf {
         match image_data.color() {
             image::ColorType::RGBA(_) | image::ColorType::BGRA(_) | image::ColorType::GrayA(_) => {
                 Self::from_dynamic_image_with_alpha(image_data)
@@ -146,73 +223,153 @@ impl ImageData {
     }
 
     /// Load an image from a DynamicImage with alpha
-    pub fn from_dynamic_image_with_alpha(image_data: image::DynamicImage) -> ImageData {
+    #[cfg(feature = "image")]
+    pub fn from_dynamic_image_with_alpha(image_data: image::DynamicImage) -> ImageBuf {
         let rgba_image = image_data.to_rgba();
-        let sizeofimage = rgba_image.dimensions();
-        ImageData {
-            pixels: rgba_image.to_vec(),
-            x_pixels: sizeofimage.0,
-            y_pixels: sizeofimage.1,
-            format: ImageFormat::RgbaSeparate,
-        }
+        let (width, height) = rgba_image.dimensions();
+        ImageBuf::from_raw(
+            rgba_image.into_raw(),
+            ImageFormat::RgbaSeparate,
+            width as usize,
+            height as usize,
+        )
     }
 
     /// Load an image from a DynamicImage without alpha
-    pub fn from_dynamic_image_without_alpha(image_data: image::DynamicImage) -> ImageData {
+    #[cfg(feature = "image")]
+    pub fn from_dynamic_image_without_alpha(image_data: image::DynamicImage) -> ImageBuf {
         let rgb_image = image_data.to_rgb();
-        let sizeofimage = rgb_image.dimensions();
-        ImageData {
-            pixels: rgb_image.to_vec(),
-            x_pixels: sizeofimage.0,
-            y_pixels: sizeofimage.1,
-            format: ImageFormat::Rgb,
-        }
+        let (width, height) = rgb_image.dimensions();
+        ImageBuf::from_raw(
+            rgb_image.into_raw(),
+            ImageFormat::Rgb,
+            width as usize,
+            height as usize,
+        )
     }
 
-    /// Attempt to load an image from raw bytes.
+    /// Attempt to load an image from raw bytes, such as the contents of
+    /// a PNG or JPEG file.
     ///
     /// If the image crate can't decode an image from the data an error will be returned.
+    #[cfg(feature = "image")]
     pub fn from_data(raw_image: &[u8]) -> Result<Self, Box<dyn Error>> {
-        let image_data = image::load_from_memory(raw_image).map_err(|e| e)?;
-        Ok(ImageData::from_dynamic_image(image_data))
+        let image_data = image::load_from_memory(raw_image)?;
+        Ok(ImageBuf::from_dynamic_image(image_data))
     }
 
     /// Attempt to load an image from the file at the provided path.
+    #[cfg(feature = "image")]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
-        let image_data = image::open(path).map_err(|e| e)?;
-        Ok(ImageData::from_dynamic_image(image_data))
+        let image_data = image::open(path)?;
+        Ok(ImageBuf::from_dynamic_image(image_data))
+    }
+
+    /// The raw pixel data.
+    pub fn raw_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// The format of the pixel data.
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// The width of the image, in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the image, in pixels.
+    pub fn height(&self) -> usize {
+        self.height
     }
 
     /// Get the size in pixels of the contained image.
-    fn get_size(&self) -> Size {
-        Size::new(self.x_pixels as f64, self.y_pixels as f64)
+    pub fn size(&self) -> Size {
+        Size::new(self.width as f64, self.height as f64)
     }
 
-    /// Convert ImageData into Piet draw instructions
-    fn to_piet(&self, offset_matrix: Affine, ctx: &mut PaintCtx, interpolation: InterpolationMode) {
-        ctx.with_save(|ctx| {
-            ctx.transform(offset_matrix);
+    /// Returns `true` if the image has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
 
-            let im = ctx
-                .make_image(
-                    self.x_pixels as usize,
-                    self.y_pixels as usize,
-                    &self.pixels,
-                    self.format,
-                )
-                .unwrap();
-            let rec =
-                Rect::from_origin_size((0.0, 0.0), (self.x_pixels as f64, self.y_pixels as f64));
-            ctx.draw_image(&im, rec, interpolation);
+    /// Upload the pixels to the render context.
+    fn to_piet_image(&self, ctx: &mut Piet) -> Option<PietImage> {
+        match ctx.make_image(self.width, self.height, &self.pixels, self.format) {
+            Ok(image) => Some(image),
+            Err(e) => {
+                log::error!("failed to create image: {}", e);
+                None
+            }
+        }
+    }
+}
 
-            Ok(())
-        })
-        .unwrap();
+impl Data for ImageBuf {
+    fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.pixels, &other.pixels)
+            && self.format == other.format
+            && self.width == other.width
+            && self.height == other.height
     }
 }
 
-impl Default for ImageData {
+impl Default for ImageBuf {
     fn default() -> Self {
-        ImageData::empty()
+        ImageBuf::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piet::Device;
+
+    #[test]
+    fn from_raw_dimensions() {
+        let buf = ImageBuf::from_raw(vec![0u8; 2 * 3 * 4], ImageFormat::RgbaSeparate, 2, 3);
+        assert_eq!(buf.size(), Size::new(2., 3.));
+        assert!(!buf.is_empty());
+        assert!(ImageBuf::empty().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_raw_wrong_len() {
+        ImageBuf::from_raw(vec![0u8; 10], ImageFormat::Rgb, 2, 3);
+    }
+
+    #[test]
+    fn same_compares_pixels_and_format() {
+        let buf = ImageBuf::from_raw(vec![0u8; 4], ImageFormat::RgbaSeparate, 1, 1);
+        assert!(buf.same(&buf.clone()));
+        let other = ImageBuf::from_raw(vec![0u8; 4], ImageFormat::RgbaSeparate, 1, 1);
+        assert!(!buf.same(&other));
+        let mut reinterpreted = buf.clone();
+        reinterpreted.format = ImageFormat::RgbaPremul;
+        assert!(!buf.same(&reinterpreted));
+    }
+
+    #[test]
+    fn set_image_data_invalidates_only_on_change() {
+        let buf = ImageBuf::from_raw(vec![0u8; 4], ImageFormat::RgbaSeparate, 1, 1);
+        let mut image: Image<()> = Image::new(buf.clone());
+        let mut device = Device::new().expect("failed to get a render device");
+        let mut target = device
+            .bitmap_target(1, 1, 1.0)
+            .expect("failed to get a bitmap target");
+        let mut piet = target.render_context();
+        image.paint_data = buf.to_piet_image(&mut piet);
+        assert!(image.paint_data.is_some());
+
+        image.set_image_data(buf.clone());
+        assert!(image.paint_data.is_some());
+
+        let other = ImageBuf::from_raw(vec![0u8; 4], ImageFormat::RgbaSeparate, 1, 1);
+        image.set_image_data(other);
+        assert!(image.paint_data.is_none());
+        piet.finish().unwrap();
     }
 }
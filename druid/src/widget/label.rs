@@ -15,12 +15,13 @@
 //! A label widget.
 
 use crate::piet::{
-    Color, FontBuilder, PietText, PietTextLayout, RenderContext, Text, TextLayout,
+    Color, FontBuilder, PietFont, PietText, PietTextLayout, RenderContext, Text, TextLayout,
     TextLayoutBuilder, UnitPoint,
 };
 use crate::{
-    theme, BoxConstraints, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
-    LifeCycleCtx, LocalizedString, PaintCtx, Point, Size, UpdateCtx, Widget,
+    commands, theme, BoxConstraints, Data, Env, Event, EventCtx, KbKey, KeyEvent, KeyOrValue,
+    LayoutCtx, LayoutDirection, LifeCycle, LifeCycleCtx, LocalizedString, PaintCtx, Point, Rect,
+    Role, Size, UpdateCtx, Widget,
 };
 
 // a fudgey way to get an approximate line height from a font size
@@ -30,6 +31,41 @@ const BASELINE_GUESS_FACTOR: f64 = 0.8;
 // added padding between the edges of the widget and the text.
 const LABEL_X_PADDING: f64 = 2.0;
 
+/// How a [`Label`] handles text that is wider than its width constraint.
+///
+/// [`Label`]: struct.Label.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineBreaking {
+    /// The text is broken into lines at word boundaries. A word that is
+    /// wider than the constraint has a line of its own, and is clipped.
+    WordWrap,
+    /// The text is a single line, clipped to the width of the label.
+    Clip,
+    /// The text is a single line, which is painted past the edge of the label.
+    Overflow,
+}
+
+/// The horizontal alignment of the lines of a [`Label`].
+///
+/// This only has an effect when the label is wider than a line of its
+/// text, such as when the text is wrapped. The leading edge is the right
+/// when the [`LayoutDirection`] is right to left.
+///
+/// [`Label`]: struct.Label.html
+/// [`LayoutDirection`]: ../enum.LayoutDirection.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAlignment {
+    /// Lines start at the leading edge.
+    Start,
+    /// Lines are centered.
+    Center,
+    /// Lines end at the trailing edge.
+    End,
+    /// The space between words is stretched so that lines fill the width;
+    /// the last line of each paragraph is aligned to the leading edge.
+    Justified,
+}
+
 /// The text for the label
 pub enum LabelText<T> {
     /// Localized string that will be resolved through `Env`.
@@ -53,6 +89,31 @@ pub struct Label<T> {
     text: LabelText<T>,
     color: KeyOrValue<Color>,
     size: KeyOrValue<f64>,
+    font: KeyOrValue<&'static str>,
+    line_break_mode: LineBreaking,
+    alignment: TextAlignment,
+    /// Whether a `&` in the text marks a mnemonic.
+    mnemonic: bool,
+    /// The laid out text, if it is up to date.
+    laid_out: Option<LaidOutText>,
+}
+
+/// The lines of a label's text, and what they were laid out for.
+struct LaidOutText {
+    font_name: String,
+    font_size: f64,
+    /// The width the text was broken into lines for, if it was broken.
+    wrap_width: Option<f64>,
+    lines: Vec<Line>,
+    /// Where the mnemonic is underlined, from left to right, in a label
+    /// that has one and isn't wrapped.
+    underline: Option<(f64, f64)>,
+}
+
+struct Line {
+    layout: PietTextLayout,
+    /// The laid out words, for a line that is spread to fill the width.
+    justified_words: Vec<PietTextLayout>,
 }
 
 impl<T: Data> Label<T> {
@@ -78,6 +139,11 @@ impl<T: Data> Label<T> {
             text,
             color: theme::LABEL_COLOR.into(),
             size: theme::TEXT_SIZE_NORMAL.into(),
+            font: theme::FONT_NAME.into(),
+            line_break_mode: LineBreaking::Overflow,
+            alignment: TextAlignment::Start,
+            mnemonic: false,
+            laid_out: None,
         }
     }
 
@@ -92,6 +158,7 @@ impl<T: Data> Label<T> {
     /// [`LabelText`]: enum.LabelText.html
     pub fn set_text(&mut self, text: impl Into<String>) {
         self.text = LabelText::Specific(text.into());
+        self.laid_out = None;
     }
 
     /// Set text alignment.
@@ -100,13 +167,25 @@ impl<T: Data> Label<T> {
         self
     }
 
+    /// Builder-style method for setting the text color.
+    #[deprecated(since = "0.5.0", note = "Use with_text_color instead")]
+    pub fn text_color(self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.with_text_color(color)
+    }
+
+    /// Builder-style method for setting the text size.
+    #[deprecated(since = "0.5.0", note = "Use with_text_size instead")]
+    pub fn text_size(self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.with_text_size(size)
+    }
+
     /// Builder-style method for setting the text color.
     ///
     /// The argument can be either a `Color` or a [`Key<Color>`].
     ///
-    /// [`Key<Color>`]: struct.Key.html
-    pub fn text_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
-        self.color = color.into();
+    /// [`Key<Color>`]: ../struct.Key.html
+    pub fn with_text_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.set_text_color(color);
         self
     }
 
@@ -114,9 +193,55 @@ impl<T: Data> Label<T> {
     ///
     /// The argument can be either an `f64` or a [`Key<f64>`].
     ///
-    /// [`Key<f64>`]: struct.Key.html
-    pub fn text_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
-        self.size = size.into();
+    /// [`Key<f64>`]: ../struct.Key.html
+    pub fn with_text_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.set_text_size(size);
+        self
+    }
+
+    /// Builder-style method for setting the font.
+    ///
+    /// The argument can be either a font name or a [`Key<&str>`], such as
+    /// [`theme::FONT_NAME`], which is the default.
+    ///
+    /// [`Key<&str>`]: ../struct.Key.html
+    /// [`theme::FONT_NAME`]: ../theme/constant.FONT_NAME.html
+    pub fn with_font(mut self, font: impl Into<KeyOrValue<&'static str>>) -> Self {
+        self.set_font(font);
+        self
+    }
+
+    /// Builder-style method for setting how text that doesn't fit is
+    /// handled. The default is [`LineBreaking::Overflow`].
+    ///
+    /// [`LineBreaking::Overflow`]: enum.LineBreaking.html#variant.Overflow
+    pub fn with_line_break_mode(mut self, mode: LineBreaking) -> Self {
+        self.set_line_break_mode(mode);
+        self
+    }
+
+    /// Builder-style method for setting the alignment of the lines of text.
+    /// The default is [`TextAlignment::Start`].
+    ///
+    /// [`TextAlignment::Start`]: enum.TextAlignment.html#variant.Start
+    pub fn with_text_alignment(mut self, alignment: TextAlignment) -> Self {
+        self.set_text_alignment(alignment);
+        self
+    }
+
+    /// Builder-style method for whether a `&` in the text marks the
+    /// character after it as the label's [mnemonic]; `&&` is a literal `&`.
+    ///
+    /// The mnemonic is underlined. When Alt and the mnemonic are pressed and
+    /// no widget handles them, the label handles the
+    /// [`commands::UNHANDLED_KEY`] they are sent with, which is how the
+    /// control that it labels, such as a [`Button`], knows to act.
+    ///
+    /// [mnemonic]: #method.mnemonic
+    /// [`commands::UNHANDLED_KEY`]: ../commands/constant.UNHANDLED_KEY.html
+    /// [`Button`]: struct.Button.html
+    pub fn with_mnemonic(mut self, mnemonic: bool) -> Self {
+        self.set_mnemonic(mnemonic);
         self
     }
 
@@ -124,7 +249,7 @@ impl<T: Data> Label<T> {
     ///
     /// The argument can be either a `Color` or a [`Key<Color>`].
     ///
-    /// [`Key<Color>`]: struct.Key.html
+    /// [`Key<Color>`]: ../struct.Key.html
     pub fn set_text_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
         self.color = color.into();
     }
@@ -133,19 +258,140 @@ impl<T: Data> Label<T> {
     ///
     /// The argument can be either an `f64` or a [`Key<f64>`].
     ///
-    /// [`Key<f64>`]: struct.Key.html
+    /// [`Key<f64>`]: ../struct.Key.html
     pub fn set_text_size(&mut self, size: impl Into<KeyOrValue<f64>>) {
         self.size = size.into();
+        self.laid_out = None;
     }
 
-    fn get_layout(&mut self, t: &mut PietText, env: &Env) -> PietTextLayout {
-        let font_name = env.get(theme::FONT_NAME);
-        let font_size = self.size.resolve(env);
+    /// Set the font.
+    ///
+    /// The argument can be either a font name or a [`Key<&str>`].
+    ///
+    /// [`Key<&str>`]: ../struct.Key.html
+    pub fn set_font(&mut self, font: impl Into<KeyOrValue<&'static str>>) {
+        self.font = font.into();
+        self.laid_out = None;
+    }
+
+    /// Set how text that doesn't fit is handled.
+    pub fn set_line_break_mode(&mut self, mode: LineBreaking) {
+        self.line_break_mode = mode;
+        self.laid_out = None;
+    }
+
+    /// Set the alignment of the lines of text.
+    pub fn set_text_alignment(&mut self, alignment: TextAlignment) {
+        self.alignment = alignment;
+        self.laid_out = None;
+    }
 
-        // TODO: caching of both the format and the layout
+    /// Set whether a `&` in the text marks a mnemonic.
+    pub fn set_mnemonic(&mut self, mnemonic: bool) {
+        self.mnemonic = mnemonic;
+        self.laid_out = None;
+    }
+
+    /// The character that is the mnemonic of the label, if it has mnemonics
+    /// and its text marks one.
+    pub fn mnemonic(&self) -> Option<char> {
+        if self.mnemonic {
+            self.text.mnemonic()
+        } else {
+            None
+        }
+    }
+
+    /// The text as it is shown, without the marks of the mnemonic, along
+    /// with the offset of the mnemonic in it.
+    fn shown_text(&self) -> (String, Option<usize>) {
+        self.text.with_display_text(|text| {
+            if self.mnemonic {
+                parse_mnemonic(text)
+            } else {
+                (text.to_owned(), None)
+            }
+        })
+    }
+
+    /// Whether the text isn't laid out, or was laid out with another font.
+    fn font_changed(&self, env: &Env) -> bool {
+        match &self.laid_out {
+            Some(laid_out) => {
+                laid_out.font_name != self.font.resolve_str(env)
+                    || !laid_out.font_size.same(&self.size.resolve(env))
+            }
+            None => true,
+        }
+    }
+
+    /// Lay out the text, if it isn't laid out for this width already.
+    ///
+    /// If `keep_width` is `true`, text that is laid out with the current font
+    /// is kept, whatever width it was laid out for.
+    fn lay_out(
+        &mut self,
+        t: &mut PietText,
+        max_width: f64,
+        keep_width: bool,
+        env: &Env,
+    ) -> &LaidOutText {
+        let wrap_width = match self.line_break_mode {
+            LineBreaking::WordWrap => Some(max_width - 2. * LABEL_X_PADDING),
+            LineBreaking::Clip | LineBreaking::Overflow => None,
+        };
+        let up_to_date = !self.font_changed(env)
+            && (keep_width || self.laid_out.as_ref().map(|l| l.wrap_width) == Some(wrap_width));
+        if !up_to_date {
+            self.laid_out = Some(self.make_laid_out(t, wrap_width, env));
+        }
+        self.laid_out.as_ref().unwrap()
+    }
+
+    fn make_laid_out(&self, t: &mut PietText, wrap_width: Option<f64>, env: &Env) -> LaidOutText {
+        let font_name = self.font.resolve_str(env);
+        let font_size = self.size.resolve(env);
         let font = t.new_font_by_name(font_name, font_size).build().unwrap();
-        self.text
-            .with_display_text(|text| t.new_text_layout(&font, &text).build().unwrap())
+        let justify = self.alignment == TextAlignment::Justified;
+
+        let (text, mnemonic) = self.shown_text();
+        let lines: Vec<_> = match wrap_width {
+            Some(width) => break_lines(t, &font, &text, width)
+                .into_iter()
+                .map(|(line, ends_paragraph)| {
+                    let justified_words = if justify && !ends_paragraph {
+                        line.split(' ')
+                            .map(|word| lay_out_text(t, &font, word))
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    Line {
+                        layout: lay_out_text(t, &font, &line),
+                        justified_words,
+                    }
+                })
+                .collect(),
+            None => vec![Line {
+                layout: lay_out_text(t, &font, &text),
+                justified_words: Vec::new(),
+            }],
+        };
+        let underline = mnemonic.filter(|_| wrap_width.is_none()).and_then(|idx| {
+            let end = idx + text[idx..].chars().next()?.len_utf8();
+            let layout = &lines[0].layout;
+            let x0 = layout.hit_test_text_position(idx)?.point.x;
+            let x1 = layout.hit_test_text_position(end)?.point.x;
+            Some((x0, x1))
+        });
+
+        LaidOutText {
+            font_name: font_name.to_owned(),
+            font_size,
+            wrap_width,
+            lines,
+            underline,
+        }
     }
 }
 
@@ -168,6 +414,15 @@ impl<T: Data> LabelText<T> {
         }
     }
 
+    /// The mnemonic that the text marks, if any: the character after the
+    /// first `&` that isn't part of a `&&`.
+    pub fn mnemonic(&self) -> Option<char> {
+        self.with_display_text(|text| {
+            let (shown, idx) = parse_mnemonic(text);
+            idx.and_then(|idx| shown[idx..].chars().next())
+        })
+    }
+
     /// Update the localization, if necessary.
     /// This ensures that localized strings are up to date.
     ///
@@ -182,16 +437,36 @@ impl<T: Data> LabelText<T> {
 }
 
 impl<T: Data> Widget<T> for Label<T> {
-    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut T, _env: &Env) {}
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut T, _env: &Env) {
+        if let Event::Command(cmd) = event {
+            if cmd.selector == commands::UNHANDLED_KEY {
+                let pressed = match (self.mnemonic(), cmd.get_object::<KeyEvent>()) {
+                    (Some(mnemonic), Ok(key)) => is_mnemonic_key(mnemonic, key),
+                    _ => false,
+                };
+                if pressed && !ctx.is_disabled() {
+                    ctx.set_handled();
+                }
+            }
+        }
+    }
 
-    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
         if let LifeCycle::WidgetAdded = event {
             self.text.resolve(data, env);
+            self.laid_out = None;
+            ctx.set_role(Role::Label);
+            ctx.set_label(self.shown_text().0);
         }
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
-        if !old_data.same(data) && self.text.resolve(data, env) {
+        let text_changed = !old_data.same(data) && self.text.resolve(data, env);
+        if text_changed {
+            ctx.set_label(self.shown_text().0);
+        }
+        if text_changed || self.font_changed(env) {
+            self.laid_out = None;
             ctx.request_layout();
         }
     }
@@ -206,26 +481,160 @@ impl<T: Data> Widget<T> for Label<T> {
         bc.debug_check("Label");
 
         let font_size = self.size.resolve(env);
-        let text_layout = self.get_layout(layout_ctx.text(), env);
-        bc.constrain(Size::new(
-            text_layout.width() + 2. * LABEL_X_PADDING,
-            font_size * LINE_HEIGHT_FACTOR,
-        ))
+        let laid_out = self.lay_out(layout_ctx.text(), bc.max().width, false, env);
+        let text_width = laid_out
+            .lines
+            .iter()
+            .map(|line| line.layout.width())
+            .fold(0.0, f64::max);
+        let line_count = laid_out.lines.len().max(1);
+        let line_height = font_size * LINE_HEIGHT_FACTOR;
+        let size = bc.constrain(Size::new(
+            text_width + 2. * LABEL_X_PADDING,
+            line_height * line_count as f64,
+        ));
+        // the baseline of the last line, as painted
+        let baseline = line_height * ((line_count - 1) as f64 + BASELINE_GUESS_FACTOR);
+        layout_ctx.set_baseline_offset(size.height - baseline);
+        size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, env: &Env) {
         let font_size = self.size.resolve(env);
-        let text_layout = self.get_layout(ctx.text(), env);
         let line_height = font_size * LINE_HEIGHT_FACTOR;
+        let size = ctx.size();
+        let available_width = size.width - 2. * LABEL_X_PADDING;
+        let color = if ctx.is_disabled() {
+            env.get(theme::DISABLED_TEXT_COLOR)
+        } else {
+            self.color.resolve(env)
+        };
+        let alignment = self.alignment;
+        // the leading edge is the right, right to left
+        let rtl = LayoutDirection::from_env(env).is_rtl();
+        let clip = self.line_break_mode != LineBreaking::Overflow;
+        // keep the lines from layout; our size may be narrower than the width
+        // they were broken for
+        let laid_out = self.lay_out(ctx.text(), size.width, true, env);
 
-        // Find the origin for the text
-        let origin = Point::new(LABEL_X_PADDING, line_height * BASELINE_GUESS_FACTOR);
-        let color = self.color.resolve(env);
+        let result = ctx.with_save(|ctx| {
+            if clip {
+                ctx.clip(size.to_rect());
+            }
+            for (i, line) in laid_out.lines.iter().enumerate() {
+                // Find the origin for the line
+                let baseline = line_height * (i as f64 + BASELINE_GUESS_FACTOR);
+                let extra = (available_width - line.layout.width()).max(0.0);
+                let x = match (alignment, rtl) {
+                    (TextAlignment::Start, false) | (TextAlignment::Justified, false) => 0.0,
+                    (TextAlignment::End, true) => 0.0,
+                    (TextAlignment::Center, _) => extra / 2.0,
+                    _ => extra,
+                };
+                let origin = Point::new(LABEL_X_PADDING + x, baseline);
 
-        ctx.draw_text(&text_layout, origin, &color);
+                if i == 0 {
+                    if let Some((x0, x1)) = laid_out.underline {
+                        let y = baseline + 1.;
+                        let underline = Rect::new(origin.x + x0, y, origin.x + x1, y + 1.);
+                        ctx.fill(underline, &color);
+                    }
+                }
+                if line.justified_words.len() < 2 {
+                    ctx.draw_text(&line.layout, origin, &color);
+                    continue;
+                }
+                let words_width: f64 = line.justified_words.iter().map(|w| w.width()).sum();
+                let gaps = (line.justified_words.len() - 1) as f64;
+                let gap = (available_width - words_width).max(0.0) / gaps;
+                let mut x = LABEL_X_PADDING;
+                for word in &line.justified_words {
+                    ctx.draw_text(word, Point::new(x, baseline), &color);
+                    x += word.width() + gap;
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            log::error!("painting label failed: {}", e);
+        }
     }
 }
 
+/// Remove the marks of a mnemonic from `text`, returning what is left and
+/// the offset in it of the mnemonic, which is the character after the first
+/// `&`; `&&` is a literal `&`.
+fn parse_mnemonic(text: &str) -> (String, Option<usize>) {
+    let mut shown = String::with_capacity(text.len());
+    let mut mnemonic = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '&' {
+            shown.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('&') => shown.push('&'),
+            Some(marked) => {
+                if mnemonic.is_none() {
+                    mnemonic = Some(shown.len());
+                }
+                shown.push(marked);
+            }
+            // a `&` at the end marks nothing
+            None => (),
+        }
+    }
+    (shown, mnemonic)
+}
+
+/// Whether `key` is Alt and `mnemonic`, whatever the case.
+pub(crate) fn is_mnemonic_key(mnemonic: char, key: &KeyEvent) -> bool {
+    let mods = key.mods;
+    match &key.key {
+        KbKey::Character(text) if mods.alt && !mods.ctrl && !mods.meta => {
+            text.to_lowercase() == mnemonic.to_lowercase().to_string()
+        }
+        _ => false,
+    }
+}
+
+/// Lay out a single line of text.
+fn lay_out_text(t: &mut PietText, font: &PietFont, text: &str) -> PietTextLayout {
+    t.new_text_layout(font, text).build().unwrap()
+}
+
+/// Break `text` into lines that fit in `max_width`, at spaces and newlines.
+///
+/// Each line is returned along with whether it is the last line of a
+/// paragraph.
+fn break_lines<T: Text>(
+    t: &mut T,
+    font: &T::Font,
+    text: &str,
+    max_width: f64,
+) -> Vec<(String, bool)> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if line.is_empty() {
+                line.push_str(word);
+                continue;
+            }
+            let candidate = format!("{} {}", line, word);
+            let layout = t.new_text_layout(font, &candidate).build().unwrap();
+            if layout.width() <= max_width {
+                line = candidate;
+            } else {
+                lines.push((std::mem::replace(&mut line, word.to_owned()), false));
+            }
+        }
+        lines.push((line, true));
+    }
+    lines
+}
+
 impl<T> From<String> for LabelText<T> {
     fn from(src: String) -> LabelText<T> {
         LabelText::Specific(src)
@@ -253,3 +662,99 @@ impl<T, F: Fn(&T, &Env) -> String + 'static> From<F> for LabelText<T> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::text::MockText;
+    use crate::widget::{Align, WidgetExt};
+    use crate::WidgetId;
+
+    #[test]
+    fn mnemonics() {
+        assert_eq!(parse_mnemonic("&Save"), ("Save".to_owned(), Some(0)));
+        assert_eq!(parse_mnemonic("Save &As"), ("Save As".to_owned(), Some(5)));
+        assert_eq!(parse_mnemonic("Q&&A"), ("Q&A".to_owned(), None));
+        assert_eq!(parse_mnemonic("&&&R&un&"), ("&Run".to_owned(), Some(1)));
+        assert_eq!(parse_mnemonic("Save"), ("Save".to_owned(), None));
+
+        let text: LabelText<()> = "Sa&ve".into();
+        assert_eq!(text.mnemonic(), Some('v'));
+        let text: LabelText<()> = "Tom && Jerry".into();
+        assert_eq!(text.mnemonic(), None);
+    }
+
+    #[test]
+    fn line_breaking() {
+        let mut t = MockText::new(10.);
+        let font = t.new_font_by_name("Any", 10.).build().unwrap();
+        let lines = |text, max_width| -> Vec<(String, bool)> {
+            break_lines(&mut t.clone(), &font, text, max_width)
+        };
+        let line = |text: &str, last| (text.to_owned(), last);
+
+        // "two words" is 90 wide
+        assert_eq!(lines("two words", 90.), vec![line("two words", true)]);
+        assert_eq!(
+            lines("two words", 89.),
+            vec![line("two", false), line("words", true)]
+        );
+        // a word that doesn't fit is kept whole, on a line of its own
+        assert_eq!(
+            lines("a wordy one", 30.),
+            vec![line("a", false), line("wordy", false), line("one", true)]
+        );
+        // newlines end paragraphs, and runs of spaces are collapsed
+        assert_eq!(
+            lines("one  two\nthree", 100.),
+            vec![line("one two", true), line("three", true)]
+        );
+        assert_eq!(lines("", 100.), vec![line("", true)]);
+    }
+
+    #[test]
+    fn label_line_breaking() {
+        const TEXT: &str =
+            "The quick brown fox jumps over the lazy dog, again and again and again.";
+
+        /// The size of a label with at most `max_width`.
+        fn label_size(mode: LineBreaking, max_width: f64) -> Size {
+            let id = WidgetId::next();
+            let label = Label::new(TEXT).with_line_break_mode(mode).with_id(id);
+            let widget = Align::left(Align::left(label).fix_width(max_width));
+            let mut size = Size::ZERO;
+            Harness::create((), widget, |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                size = harness.get_state(id).layout_rect.size();
+            });
+            size
+        }
+
+        let widths = [400., 200., 100., 50.];
+        let wrapped: Vec<Size> = widths
+            .iter()
+            .map(|width| label_size(LineBreaking::WordWrap, *width))
+            .collect();
+        for (size, width) in wrapped.iter().zip(&widths) {
+            assert!(size.width <= *width, "{:?} wider than {}", size, width);
+        }
+        for pair in wrapped.windows(2) {
+            assert!(pair[0].height <= pair[1].height, "{:?}", wrapped);
+        }
+        assert!(wrapped[0].height < wrapped[3].height, "{:?}", wrapped);
+
+        let line_height = label_size(LineBreaking::Clip, 400.).height;
+        for width in &widths {
+            let clipped = label_size(LineBreaking::Clip, *width);
+            assert!(
+                clipped.width <= *width,
+                "{:?} wider than {}",
+                clipped,
+                width
+            );
+            assert_eq!(clipped.height, line_height);
+        }
+    }
+}
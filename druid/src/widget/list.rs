@@ -15,6 +15,7 @@
 //! Simple list view widget.
 
 use std::cmp::Ordering;
+use std::ops::Range;
 use std::sync::Arc;
 
 use crate::kurbo::{Point, Rect, Size};
@@ -69,6 +70,39 @@ pub trait ListIter<T>: Data {
 
     /// Return data length.
     fn data_len(&self) -> usize;
+
+    /// Iterate over the data children whose indices are in `range`.
+    ///
+    /// The default goes through all of them with [`for_each`], and skips
+    /// those outside of the range; collections that can go straight to the
+    /// start of the range should do that instead.
+    ///
+    /// [`for_each`]: #tymethod.for_each
+    fn for_each_in(&self, range: Range<usize>, mut cb: impl FnMut(&T, usize)) {
+        self.for_each(|child, i| {
+            if range.contains(&i) {
+                cb(child, i);
+            }
+        });
+    }
+
+    /// Iterate over the data children whose indices are in `range`, as with
+    /// [`for_each_mut`].
+    ///
+    /// [`for_each_mut`]: #tymethod.for_each_mut
+    fn for_each_mut_in(&mut self, range: Range<usize>, mut cb: impl FnMut(&mut T, usize)) {
+        self.for_each_mut(|child, i| {
+            if range.contains(&i) {
+                cb(child, i);
+            }
+        });
+    }
+}
+
+/// The part of `range` that is in a collection of length `len`.
+fn clamp_range(range: Range<usize>, len: usize) -> Range<usize> {
+    let end = range.end.min(len);
+    range.start.min(end)..end
 }
 
 impl<T: Data> ListIter<T> for Arc<Vec<T>> {
@@ -100,6 +134,22 @@ impl<T: Data> ListIter<T> for Arc<Vec<T>> {
     fn data_len(&self) -> usize {
         self.len()
     }
+
+    fn for_each_in(&self, range: Range<usize>, mut cb: impl FnMut(&T, usize)) {
+        for i in clamp_range(range, self.len()) {
+            cb(&self[i], i);
+        }
+    }
+
+    fn for_each_mut_in(&mut self, range: Range<usize>, mut cb: impl FnMut(&mut T, usize)) {
+        for i in clamp_range(range, self.len()) {
+            let mut d = self[i].to_owned();
+            cb(&mut d, i);
+            if !self[i].same(&d) {
+                Arc::make_mut(self)[i] = d;
+            }
+        }
+    }
 }
 
 impl<T1: Data, T: Data> ListIter<(T1, T)> for (T1, Arc<Vec<T>>) {
@@ -140,6 +190,26 @@ impl<T1: Data, T: Data> ListIter<(T1, T)> for (T1, Arc<Vec<T>>) {
     fn data_len(&self) -> usize {
         self.1.len()
     }
+
+    fn for_each_in(&self, range: Range<usize>, mut cb: impl FnMut(&(T1, T), usize)) {
+        for i in clamp_range(range, self.1.len()) {
+            let d = (self.0.clone(), self.1[i].to_owned());
+            cb(&d, i);
+        }
+    }
+
+    fn for_each_mut_in(&mut self, range: Range<usize>, mut cb: impl FnMut(&mut (T1, T), usize)) {
+        for i in clamp_range(range, self.1.len()) {
+            let mut d = (self.0.clone(), self.1[i].to_owned());
+            cb(&mut d, i);
+            if !self.0.same(&d.0) {
+                self.0 = d.0;
+            }
+            if !self.1[i].same(&d.1) {
+                Arc::make_mut(&mut self.1)[i] = d.1;
+            }
+        }
+    }
 }
 
 impl<C: Data, T: ListIter<C>> Widget<T> for List<C> {
@@ -178,6 +248,9 @@ impl<C: Data, T: ListIter<C>> Widget<T> for List<C> {
             }
         });
 
+        for child in self.children.iter_mut().skip(data.data_len()) {
+            ctx.remove_child(child);
+        }
         if self.update_child_count(data, env) {
             ctx.children_changed();
         }
@@ -208,7 +281,7 @@ impl<C: Data, T: ListIter<C>> Widget<T> for List<C> {
             );
             let child_size = child.layout(layout_ctx, &child_bc, child_data, env);
             let rect = Rect::from_origin_size(Point::new(0.0, y), child_size);
-            child.set_layout_rect(rect);
+            child.set_layout_rect(layout_ctx, child_data, env, rect);
             paint_rect = paint_rect.union(child.paint_rect());
             width = width.max(child_size.width);
             y += child_size.height;
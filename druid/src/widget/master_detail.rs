@@ -0,0 +1,1023 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A searchable list of items, next to a detail view of the selected item.
+
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::lens::Field;
+use crate::widget::{Button, Controller, Flex, Label, Scroll, TextBox, ViewSwitcher, WidgetExt};
+use crate::{
+    theme, BoxConstraints, Data, Env, Event, EventCtx, HotKey, KbKey, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, RenderContext, Selector, SysMods, TimerToken, UpdateCtx, Widget,
+    WidgetId, WidgetPod,
+};
+
+/// Sent to the search box to give it focus.
+const FOCUS_SEARCH: Selector = Selector::new("druid-builtin.master-detail.focus-search");
+/// Sent to the list to give it focus.
+const FOCUS_LIST: Selector = Selector::new("druid-builtin.master-detail.focus-list");
+
+const DEFAULT_MASTER_WIDTH: f64 = 200.0;
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(250);
+
+type KeyFn<T, K> = dyn Fn(&T) -> K;
+type FilterFn<T> = dyn Fn(&T, &str) -> bool;
+type RowBuilder<T> = dyn Fn() -> Box<dyn Widget<T>>;
+type NewItemFn<T, K> = dyn Fn(&MasterDetailData<T, K>) -> T;
+type Pod<T, K> = WidgetPod<MasterDetailData<T, K>, Box<dyn Widget<MasterDetailData<T, K>>>>;
+type ListPod<T, K> =
+    WidgetPod<MasterDetailData<T, K>, Scroll<MasterDetailData<T, K>, FilteredList<T, K>>>;
+
+/// The data for a [`MasterDetail`] widget.
+///
+/// The selection is stored as a key, so it survives the items being
+/// reordered, edited, or hidden by the search query.
+///
+/// [`MasterDetail`]: struct.MasterDetail.html
+#[derive(Debug, Clone)]
+pub struct MasterDetailData<T, K> {
+    /// All of the items, including ones hidden by the current query.
+    pub items: Arc<Vec<T>>,
+    /// The contents of the search box.
+    pub query: String,
+    /// The key of the selected item, if any.
+    pub selected: Option<K>,
+}
+
+impl<T: Data, K: Data + PartialEq> MasterDetailData<T, K> {
+    /// Create new data with the given items, an empty query, and nothing selected.
+    pub fn new(items: impl Into<Arc<Vec<T>>>) -> Self {
+        MasterDetailData {
+            items: items.into(),
+            query: String::new(),
+            selected: None,
+        }
+    }
+
+    /// The index in `items` of the selected item.
+    fn selected_index(&self, key: &KeyFn<T, K>) -> Option<usize> {
+        let selected = self.selected.as_ref()?;
+        self.items.iter().position(|item| key(item) == *selected)
+    }
+}
+
+impl<T: Data, K: Data> Data for MasterDetailData<T, K> {
+    fn same(&self, other: &Self) -> bool {
+        self.items.same(&other.items)
+            && self.query.same(&other.query)
+            && self.selected.same(&other.selected)
+    }
+}
+
+/// A searchable list of items, beside a detail pane for the selected item.
+///
+/// The master side is a search box above a list of rows, one per item that
+/// matches the query. Clicking a row, or moving with the arrow keys while the
+/// list is focused, selects it. The arrow keys also move focus between the
+/// search box and the list. The query is applied after the user stops typing
+/// for a short while; see [`debounce`].
+///
+/// The detail side is rebuilt whenever the selection changes, and is given the
+/// selected item as its data; edits made there are written back to the list.
+///
+/// Optional add and delete buttons send the [`ADD_ITEM`] and [`DELETE_ITEM`]
+/// commands to this widget; those commands can also be sent by the application.
+///
+/// [`debounce`]: #method.debounce
+/// [`ADD_ITEM`]: #associatedconstant.ADD_ITEM
+/// [`DELETE_ITEM`]: #associatedconstant.DELETE_ITEM
+pub struct MasterDetail<T, K> {
+    key: Rc<KeyFn<T, K>>,
+    search: Pod<T, K>,
+    list: ListPod<T, K>,
+    detail: Pod<T, K>,
+    buttons: Option<Pod<T, K>>,
+    new_item: Option<Box<NewItemFn<T, K>>>,
+    show_delete: bool,
+    master_width: f64,
+    debounce: Duration,
+    debounce_timer: TimerToken,
+}
+
+/// The list of rows on the master side.
+struct FilteredList<T, K> {
+    key: Rc<KeyFn<T, K>>,
+    filter: Box<FilterFn<T>>,
+    row_builder: Box<RowBuilder<T>>,
+    /// One row per item, including the hidden ones.
+    rows: Vec<WidgetPod<T, Box<dyn Widget<T>>>>,
+    /// The indices of the items that match `query`, in order.
+    visible: Vec<usize>,
+    /// The query, as last applied.
+    query: String,
+    search_id: WidgetId,
+}
+
+/// Gives the detail widget the item with a particular key.
+struct SelectedItem<T, K, W> {
+    key: K,
+    key_fn: Rc<KeyFn<T, K>>,
+    inner: W,
+}
+
+/// Moves focus from the search box to the list.
+struct SearchController {
+    list_id: WidgetId,
+}
+
+impl<T, K> MasterDetail<T, K> {
+    /// Add a new item, and select it.
+    pub const ADD_ITEM: Selector = Selector::new("druid-builtin.master-detail.add-item");
+
+    /// Delete the selected item.
+    pub const DELETE_ITEM: Selector = Selector::new("druid-builtin.master-detail.delete-item");
+}
+
+impl<T: Data, K: Data + PartialEq> MasterDetail<T, K> {
+    /// Create a new `MasterDetail`.
+    ///
+    /// `key` identifies an item, and must be unique among the items. An item
+    /// is shown in the list if `filter`, called with the item and the current
+    /// query, returns `true`. `row` builds the widget for each row of the
+    /// list, and `detail` the widget for the selected item.
+    pub fn new<R, D>(
+        key: impl Fn(&T) -> K + 'static,
+        filter: impl Fn(&T, &str) -> bool + 'static,
+        row: impl Fn() -> R + 'static,
+        detail: impl Fn() -> D + 'static,
+    ) -> Self
+    where
+        R: Widget<T> + 'static,
+        D: Widget<T> + 'static,
+    {
+        let key: Rc<KeyFn<T, K>> = Rc::new(key);
+        let search_id = WidgetId::next();
+
+        let list = FilteredList {
+            key: key.clone(),
+            filter: Box::new(filter),
+            row_builder: Box::new(move || Box::new(row())),
+            rows: Vec::new(),
+            visible: Vec::new(),
+            query: String::new(),
+            search_id,
+        };
+        let list = WidgetPod::new(Scroll::new(list).vertical());
+
+        let search = TextBox::new()
+            .lens(Field::new::<MasterDetailData<T, K>, _>(
+                |data| &data.query,
+                |data| &mut data.query,
+            ))
+            .controller(SearchController { list_id: list.id() })
+            .with_id(search_id)
+            .boxed();
+
+        let picker_key = key.clone();
+        let detail_key = key.clone();
+        let detail = ViewSwitcher::new(
+            move |data: &MasterDetailData<T, K>, _env: &Env| {
+                // only pick items that actually exist
+                data.selected_index(&*picker_key).and(data.selected.clone())
+            },
+            move |selected: &Option<K>, _data, _env| {
+                let widget: Box<dyn Widget<MasterDetailData<T, K>>> = match selected {
+                    Some(key) => Box::new(SelectedItem {
+                        key: key.clone(),
+                        key_fn: detail_key.clone(),
+                        inner: detail(),
+                    }),
+                    None => Box::new(Label::new("No selection").center()),
+                };
+                widget
+            },
+        );
+
+        MasterDetail {
+            key,
+            search: WidgetPod::new(search),
+            list,
+            detail: WidgetPod::new(Box::new(detail)),
+            buttons: None,
+            new_item: None,
+            show_delete: false,
+            master_width: DEFAULT_MASTER_WIDTH,
+            debounce: DEFAULT_DEBOUNCE,
+            debounce_timer: TimerToken::INVALID,
+        }
+    }
+
+    /// Builder-style method to add an 'add' button.
+    ///
+    /// When it is clicked, `new_item` is called to create the item, which is
+    /// then appended to the items and selected.
+    pub fn with_add_button(
+        mut self,
+        new_item: impl Fn(&MasterDetailData<T, K>) -> T + 'static,
+    ) -> Self {
+        self.new_item = Some(Box::new(new_item));
+        self
+    }
+
+    /// Builder-style method to add a 'delete' button, which removes the
+    /// selected item.
+    pub fn with_delete_button(mut self) -> Self {
+        self.show_delete = true;
+        self
+    }
+
+    /// Builder-style method to set the width of the master side.
+    pub fn master_width(mut self, width: f64) -> Self {
+        self.master_width = width;
+        self
+    }
+
+    /// Builder-style method to set how long to wait after the query
+    /// changes before filtering the list.
+    ///
+    /// If this is zero, the list is filtered on every change.
+    pub fn debounce(mut self, delay: Duration) -> Self {
+        self.debounce = delay;
+        self
+    }
+
+    /// Build the add and delete buttons, which send their commands to `id`.
+    fn build_buttons(&self, id: WidgetId) -> Option<Pod<T, K>> {
+        if self.new_item.is_none() && !self.show_delete {
+            return None;
+        }
+
+        let mut row = Flex::row();
+        if self.new_item.is_some() {
+            let button = Button::new("Add", move |ctx, _data, _env| {
+                ctx.submit_command(Self::ADD_ITEM, id)
+            });
+            row.add_child(button.padding(4.0), 0.0);
+        }
+        if self.show_delete {
+            let button = Button::new("Delete", move |ctx, _data, _env| {
+                ctx.submit_command(Self::DELETE_ITEM, id)
+            });
+            row.add_child(button.padding(4.0), 0.0);
+        }
+        Some(WidgetPod::new(row.boxed()))
+    }
+
+    /// Filter the list with the current query.
+    ///
+    /// Returns `true` if the query had changed.
+    fn apply_query(&mut self, data: &MasterDetailData<T, K>) -> bool {
+        let list = self.list.widget_mut().child_mut();
+        if list.query == data.query {
+            return false;
+        }
+        list.query = data.query.clone();
+        list.update_visible(&data.items);
+        true
+    }
+}
+
+impl<T: Data, K: Data + PartialEq> Widget<MasterDetailData<T, K>> for MasterDetail<T, K> {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.selector == Self::ADD_ITEM => {
+                if let Some(new_item) = self.new_item.as_ref() {
+                    let item = new_item(data);
+                    data.selected = Some((self.key)(&item));
+                    Arc::make_mut(&mut data.items).push(item);
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.selector == Self::DELETE_ITEM => {
+                if let Some(idx) = data.selected_index(&*self.key) {
+                    Arc::make_mut(&mut data.items).remove(idx);
+                    data.selected = None;
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::Timer(token) if *token == self.debounce_timer => {
+                self.debounce_timer = TimerToken::INVALID;
+                if self.apply_query(data) {
+                    ctx.request_layout();
+                }
+                return;
+            }
+            _ => (),
+        }
+
+        let old_query = data.query.clone();
+
+        self.search.event(ctx, event, data, env);
+        self.list.event(ctx, event, data, env);
+        self.detail.event(ctx, event, data, env);
+        if let Some(buttons) = self.buttons.as_mut() {
+            buttons.event(ctx, event, data, env);
+        }
+
+        if data.query != old_query {
+            if self.debounce == Duration::default() {
+                if self.apply_query(data) {
+                    ctx.request_layout();
+                }
+            } else {
+                let deadline = Instant::now() + self.debounce;
+                self.debounce_timer = ctx.request_timer(deadline);
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            // use the id of our pod, which may have been set with `with_id`
+            self.buttons = self.build_buttons(ctx.widget_id());
+            self.apply_query(data);
+        }
+
+        self.search.lifecycle(ctx, event, data, env);
+        self.list.lifecycle(ctx, event, data, env);
+        self.detail.lifecycle(ctx, event, data, env);
+        if let Some(buttons) = self.buttons.as_mut() {
+            buttons.lifecycle(ctx, event, data, env);
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &MasterDetailData<T, K>,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        // A query set by the application is applied right away; changes made
+        // by typing wait for the debounce timer.
+        if !old_data.query.same(&data.query)
+            && self.debounce_timer == TimerToken::INVALID
+            && self.apply_query(data)
+        {
+            ctx.request_layout();
+        }
+
+        self.search.update(ctx, data, env);
+        self.list.update(ctx, data, env);
+        self.detail.update(ctx, data, env);
+        if let Some(buttons) = self.buttons.as_mut() {
+            buttons.update(ctx, data, env);
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("MasterDetail");
+
+        if !bc.is_width_bounded() || !bc.is_height_bounded() {
+            log::warn!("A MasterDetail widget was given unbounded constraints.");
+        }
+
+        let size = bc.max();
+        let master_width = self.master_width.min(size.width);
+        let column_bc = BoxConstraints::new(
+            Size::new(master_width, 0.0),
+            Size::new(master_width, size.height),
+        );
+
+        let search_size = self.search.layout(ctx, &column_bc, data, env);
+        let search_rect = Rect::from_origin_size(Point::ORIGIN, search_size);
+        self.search.set_layout_rect(ctx, data, env, search_rect);
+
+        let buttons_height = match self.buttons.as_mut() {
+            Some(buttons) => {
+                let buttons_size = buttons.layout(ctx, &column_bc, data, env);
+                let origin = Point::new(0.0, size.height - buttons_size.height);
+                buttons.set_layout_rect(
+                    ctx,
+                    data,
+                    env,
+                    Rect::from_origin_size(origin, buttons_size),
+                );
+                buttons_size.height
+            }
+            None => 0.0,
+        };
+
+        let list_height = (size.height - search_size.height - buttons_height).max(0.0);
+        let list_bc = BoxConstraints::tight(Size::new(master_width, list_height));
+        let list_size = self.list.layout(ctx, &list_bc, data, env);
+        let list_origin = Point::new(0.0, search_size.height);
+        self.list.set_layout_rect(
+            ctx,
+            data,
+            env,
+            Rect::from_origin_size(list_origin, list_size),
+        );
+
+        let detail_width = (size.width - master_width).max(0.0);
+        let detail_bc = BoxConstraints::tight(Size::new(detail_width, size.height));
+        let detail_size = self.detail.layout(ctx, &detail_bc, data, env);
+        let detail_origin = Point::new(master_width, 0.0);
+        self.detail.set_layout_rect(
+            ctx,
+            data,
+            env,
+            Rect::from_origin_size(detail_origin, detail_size),
+        );
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &MasterDetailData<T, K>, env: &Env) {
+        self.search.paint_with_offset(ctx, data, env);
+        self.list.paint_with_offset(ctx, data, env);
+        if let Some(buttons) = self.buttons.as_mut() {
+            buttons.paint_with_offset(ctx, data, env);
+        }
+        self.detail.paint_with_offset(ctx, data, env);
+    }
+}
+
+impl<T: Data, K: Data + PartialEq> FilteredList<T, K> {
+    fn update_visible(&mut self, items: &[T]) {
+        let filter = &self.filter;
+        let query = &self.query;
+        self.visible = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| filter(item, query))
+            .map(|(idx, _)| idx)
+            .collect();
+    }
+
+    /// Create or remove rows to match the items.
+    ///
+    /// Returns `true` if rows were added or removed.
+    fn update_row_count(&mut self, items: &[T]) -> bool {
+        let len = self.rows.len();
+        if len > items.len() {
+            self.rows.truncate(items.len());
+        }
+        while self.rows.len() < items.len() {
+            self.rows.push(WidgetPod::new((self.row_builder)()));
+        }
+        len != items.len()
+    }
+
+    fn is_visible(&self, idx: usize) -> bool {
+        self.visible.binary_search(&idx).is_ok()
+    }
+
+    /// The position in `visible` of the selected item.
+    fn selected_position(&self, data: &MasterDetailData<T, K>) -> Option<usize> {
+        let idx = data.selected_index(&*self.key)?;
+        self.visible.binary_search(&idx).ok()
+    }
+
+    fn select_visible(&self, position: usize, data: &mut MasterDetailData<T, K>) {
+        if let Some(idx) = self.visible.get(position) {
+            data.selected = Some((self.key)(&data.items[*idx]));
+        }
+    }
+}
+
+impl<T: Data, K: Data + PartialEq> Widget<MasterDetailData<T, K>> for FilteredList<T, K> {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        if let Event::Command(cmd) = event {
+            if cmd.selector == FOCUS_LIST {
+                ctx.request_focus();
+                if self.selected_position(data).is_none() {
+                    self.select_visible(0, data);
+                }
+                ctx.request_paint();
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        // hidden rows have stale layout rects, so they don't get mouse events.
+        let is_mouse = match event {
+            Event::MouseDown(_)
+            | Event::MouseUp(_)
+            | Event::MouseMoved(_)
+            | Event::Wheel(_)
+            | Event::DragEnter(_)
+            | Event::DragOver(_)
+            | Event::Drop(_) => true,
+            _ => false,
+        };
+
+        for idx in 0..self.rows.len().min(data.items.len()) {
+            if is_mouse && !self.is_visible(idx) {
+                continue;
+            }
+            let mut item = data.items[idx].clone();
+            self.rows[idx].event(ctx, event, &mut item, env);
+            if !item.same(&data.items[idx]) {
+                Arc::make_mut(&mut data.items)[idx] = item;
+            }
+        }
+
+        if ctx.is_handled() {
+            return;
+        }
+
+        match event {
+            Event::MouseDown(mouse) => {
+                let clicked = self.visible.iter().position(|idx| {
+                    self.rows
+                        .get(*idx)
+                        .map(|row| row.layout_rect().contains(mouse.pos))
+                        .unwrap_or(false)
+                });
+                if let Some(position) = clicked {
+                    self.select_visible(position, data);
+                    ctx.request_focus();
+                    ctx.request_paint();
+                    ctx.set_handled();
+                }
+            }
+            Event::KeyDown(key) if ctx.has_focus() => {
+                let position = self.selected_position(data);
+                if HotKey::new(SysMods::None, KbKey::ArrowDown).matches(key) {
+                    let next = position.map(|pos| pos + 1).unwrap_or(0);
+                    self.select_visible(next, data);
+                    ctx.request_paint();
+                    ctx.set_handled();
+                } else if HotKey::new(SysMods::None, KbKey::ArrowUp).matches(key) {
+                    match position {
+                        Some(pos) if pos > 0 => self.select_visible(pos - 1, data),
+                        // moving up past the first row goes back to the search box
+                        _ => ctx.submit_command(FOCUS_SEARCH, self.search_id),
+                    }
+                    ctx.request_paint();
+                    ctx.set_handled();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.update_row_count(&data.items) {
+                ctx.children_changed();
+            }
+            self.update_visible(&data.items);
+            ctx.register_for_focus();
+        }
+
+        for (row, item) in self.rows.iter_mut().zip(data.items.iter()) {
+            row.lifecycle(ctx, event, item, env);
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &MasterDetailData<T, K>,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        for (row, item) in self.rows.iter_mut().zip(data.items.iter()) {
+            row.update(ctx, item, env);
+        }
+
+        if !old_data.items.same(&data.items) {
+            for row in self.rows.iter_mut().skip(data.items.len()) {
+                ctx.remove_child(row);
+            }
+            if self.update_row_count(&data.items) {
+                ctx.children_changed();
+            }
+            self.update_visible(&data.items);
+            ctx.request_layout();
+        }
+
+        if !old_data.selected.same(&data.selected) {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) -> Size {
+        let mut width = bc.min().width;
+        let mut y = 0.0;
+        let mut paint_rect = Rect::ZERO;
+
+        for idx in &self.visible {
+            let (row, item) = match (self.rows.get_mut(*idx), data.items.get(*idx)) {
+                (Some(row), Some(item)) => (row, item),
+                _ => continue,
+            };
+            let row_bc = BoxConstraints::new(
+                Size::new(bc.min().width, 0.0),
+                Size::new(bc.max().width, std::f64::INFINITY),
+            );
+            let row_size = row.layout(ctx, &row_bc, item, env);
+            row.set_layout_rect(
+                ctx,
+                item,
+                env,
+                Rect::from_origin_size(Point::new(0.0, y), row_size),
+            );
+            paint_rect = paint_rect.union(row.paint_rect());
+            width = width.max(row_size.width);
+            y += row_size.height;
+        }
+
+        let my_size = bc.constrain(Size::new(width, y));
+        let insets = paint_rect - Rect::ZERO.with_size(my_size);
+        ctx.set_paint_insets(insets);
+        my_size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &MasterDetailData<T, K>, env: &Env) {
+        if let Some(idx) = data.selected_index(&*self.key) {
+            if self.is_visible(idx) {
+                // highlight the full width of the list, not just the row
+                let row_rect = self.rows[idx].layout_rect();
+                let rect = Rect::new(0.0, row_rect.y0, ctx.size().width, row_rect.y1);
+                let color = if ctx.has_focus() {
+                    env.get(theme::SELECTION_COLOR)
+                } else {
+                    env.get(theme::BACKGROUND_LIGHT)
+                };
+                ctx.fill(rect, &color);
+            }
+        }
+
+        for idx in &self.visible {
+            if let (Some(row), Some(item)) = (self.rows.get_mut(*idx), data.items.get(*idx)) {
+                row.paint_with_offset(ctx, item, env);
+            }
+        }
+    }
+}
+
+impl<T: Data, K: Data + PartialEq, W: Widget<T>> SelectedItem<T, K, W> {
+    fn index(&self, data: &MasterDetailData<T, K>) -> Option<usize> {
+        data.items
+            .iter()
+            .position(|item| (self.key_fn)(item) == self.key)
+    }
+}
+
+impl<T: Data, K: Data + PartialEq, W: Widget<T>> Widget<MasterDetailData<T, K>>
+    for SelectedItem<T, K, W>
+{
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        if let Some(idx) = self.index(data) {
+            let mut item = data.items[idx].clone();
+            self.inner.event(ctx, event, &mut item, env);
+            if !item.same(&data.items[idx]) {
+                Arc::make_mut(&mut data.items)[idx] = item;
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        if let Some(idx) = self.index(data) {
+            self.inner.lifecycle(ctx, event, &data.items[idx], env);
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &MasterDetailData<T, K>,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) {
+        if let (Some(old_idx), Some(idx)) = (self.index(old_data), self.index(data)) {
+            let old_item = &old_data.items[old_idx];
+            let item = &data.items[idx];
+            if !old_item.same(item) {
+                self.inner.update(ctx, old_item, item, env);
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &MasterDetailData<T, K>,
+        env: &Env,
+    ) -> Size {
+        match self.index(data) {
+            Some(idx) => self.inner.layout(ctx, bc, &data.items[idx], env),
+            None => bc.min(),
+        }
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &MasterDetailData<T, K>, env: &Env) {
+        if let Some(idx) = self.index(data) {
+            self.inner.paint(ctx, &data.items[idx], env);
+        }
+    }
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for SearchController {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::Command(cmd) if cmd.selector == FOCUS_SEARCH => {
+                ctx.request_focus();
+                ctx.set_handled();
+            }
+            Event::KeyDown(key)
+                if ctx.has_focus() && HotKey::new(SysMods::None, KbKey::ArrowDown).matches(key) =>
+            {
+                ctx.submit_command(FOCUS_LIST, self.list_id);
+                ctx.set_handled();
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::{Code, Command, Target};
+
+    /// An item is a key and a name.
+    type Item = (u32, String);
+    type State = MasterDetailData<Item, u32>;
+
+    /// Renames the item whose key is in the argument.
+    const RENAME: Selector = Selector::new("druid-tests.rename");
+
+    fn items() -> State {
+        MasterDetailData::new(vec![
+            (1, "apple".to_string()),
+            (2, "banana".to_string()),
+            (3, "cherry".to_string()),
+        ])
+    }
+
+    /// A row that records the keys of the rows that are laid out, and
+    /// that handles `RENAME` commands.
+    fn make_row(laid_out: Rc<RefCell<Vec<u32>>>) -> impl Widget<Item> {
+        ModularWidget::new(laid_out)
+            .event_fn(|_, _ctx, event, data: &mut Item, _env| {
+                if let Event::Command(cmd) = event {
+                    if cmd.selector == RENAME {
+                        let (key, name) = cmd.get_object::<Item>().unwrap();
+                        if *key == data.0 {
+                            data.1 = name.clone();
+                        }
+                    }
+                }
+            })
+            .layout_fn(|laid_out, _ctx, bc, data, _env| {
+                laid_out.borrow_mut().push(data.0);
+                bc.constrain(Size::new(100., 20.))
+            })
+    }
+
+    /// A detail pane that records the name of the item it was last given.
+    fn make_detail(seen: Rc<RefCell<Option<String>>>) -> impl Widget<Item> {
+        ModularWidget::new(seen)
+            .lifecycle_fn(|seen, _ctx, event, data: &Item, _env| {
+                if let LifeCycle::WidgetAdded = event {
+                    *seen.borrow_mut() = Some(data.1.clone());
+                }
+            })
+            .update_fn(|seen, _ctx, _old, data, _env| {
+                *seen.borrow_mut() = Some(data.1.clone());
+            })
+    }
+
+    fn make_master_detail(
+        laid_out: &Rc<RefCell<Vec<u32>>>,
+        seen: &Rc<RefCell<Option<String>>>,
+    ) -> MasterDetail<Item, u32> {
+        let laid_out = laid_out.clone();
+        let seen = seen.clone();
+        MasterDetail::new(
+            |item: &Item| item.0,
+            |item: &Item, query| item.1.contains(query),
+            move || make_row(laid_out.clone()),
+            move || make_detail(seen.clone()),
+        )
+        .debounce(Duration::default())
+    }
+
+    /// Lay out, and return the keys of the rows that are visible.
+    fn visible_rows(harness: &mut Harness<State>, laid_out: &Rc<RefCell<Vec<u32>>>) -> Vec<u32> {
+        laid_out.borrow_mut().clear();
+        harness.just_layout();
+        laid_out.borrow().clone()
+    }
+
+    #[test]
+    fn selection_survives_filtering() {
+        let laid_out = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::new(RefCell::new(None));
+        let widget = make_master_detail(&laid_out, &seen);
+
+        let mut data = items();
+        data.selected = Some(2);
+
+        Harness::create(data, widget, |harness| {
+            harness.send_initial_events();
+            assert_eq!(visible_rows(harness, &laid_out), vec![1, 2, 3]);
+            assert_eq!(seen.borrow().as_deref(), Some("banana"));
+
+            // focus the search box, and type a query that hides the selection
+            harness.event(key_down("\t", Code::Tab));
+            harness.event(key_down("c", Code::KeyC));
+            assert_eq!(harness.data().query, "c");
+            assert_eq!(visible_rows(harness, &laid_out), vec![3]);
+            assert_eq!(harness.data().selected, Some(2));
+            assert_eq!(seen.borrow().as_deref(), Some("banana"));
+
+            // clearing the query shows it again, still selected
+            harness.event(key_down("", Code::Backspace));
+            assert_eq!(harness.data().query, "");
+            assert_eq!(visible_rows(harness, &laid_out), vec![1, 2, 3]);
+            assert_eq!(harness.data().selected, Some(2));
+        })
+    }
+
+    #[test]
+    fn keyboard_focus_handoff() {
+        let laid_out = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::new(RefCell::new(None));
+        let widget = make_master_detail(&laid_out, &seen);
+
+        Harness::create(items(), widget, |harness| {
+            harness.send_initial_events();
+
+            harness.event(key_down("\t", Code::Tab));
+            harness.event(key_down("a", Code::KeyA));
+            assert_eq!(harness.data().query, "a");
+            // "apple" and "banana"
+            assert_eq!(visible_rows(harness, &laid_out), vec![1, 2]);
+
+            // down from the search box moves to the list, selecting the first row
+            harness.event(key_down("", Code::ArrowDown));
+            assert_eq!(harness.data().selected, Some(1));
+            assert_eq!(seen.borrow().as_deref(), Some("apple"));
+
+            harness.event(key_down("", Code::ArrowDown));
+            assert_eq!(harness.data().selected, Some(2));
+            assert_eq!(seen.borrow().as_deref(), Some("banana"));
+
+            // the hidden "cherry" is skipped; we stay on the last visible row
+            harness.event(key_down("", Code::ArrowDown));
+            assert_eq!(harness.data().selected, Some(2));
+
+            // typing goes to the list, not the search box
+            harness.event(key_down("x", Code::KeyX));
+            assert_eq!(harness.data().query, "a");
+
+            // up past the first row moves back to the search box
+            harness.event(key_down("", Code::ArrowUp));
+            assert_eq!(harness.data().selected, Some(1));
+            harness.event(key_down("", Code::ArrowUp));
+            assert_eq!(harness.data().selected, Some(1));
+            harness.event(key_down("p", Code::KeyP));
+            assert_eq!(harness.data().query, "ap");
+            assert_eq!(visible_rows(harness, &laid_out), vec![1]);
+        })
+    }
+
+    #[test]
+    fn detail_follows_edits() {
+        let laid_out = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::new(RefCell::new(None));
+        let widget = make_master_detail(&laid_out, &seen);
+
+        let mut data = items();
+        data.selected = Some(2);
+
+        Harness::create(data, widget, |harness| {
+            harness.send_initial_events();
+            assert_eq!(seen.borrow().as_deref(), Some("banana"));
+
+            // the row for the selected item edits it; the detail pane updates
+            let rename = Command::new(RENAME, (2u32, "blueberry".to_string()));
+            harness.submit_command(rename, Target::Auto);
+            assert_eq!(harness.data().items[1].1, "blueberry");
+            assert_eq!(seen.borrow().as_deref(), Some("blueberry"));
+
+            // editing another item leaves the detail pane alone
+            let rename = Command::new(RENAME, (3u32, "cranberry".to_string()));
+            harness.submit_command(rename, Target::Auto);
+            assert_eq!(harness.data().items[2].1, "cranberry");
+            assert_eq!(seen.borrow().as_deref(), Some("blueberry"));
+        })
+    }
+
+    #[test]
+    fn add_and_delete() {
+        let laid_out = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::new(RefCell::new(None));
+        let widget = make_master_detail(&laid_out, &seen)
+            .with_add_button(|data| (data.items.len() as u32 + 1, "durian".to_string()))
+            .with_delete_button();
+
+        Harness::create(items(), widget, |harness| {
+            harness.send_initial_events();
+
+            harness.submit_command(MasterDetail::<Item, u32>::ADD_ITEM, Target::Auto);
+            assert_eq!(harness.data().items.len(), 4);
+            assert_eq!(harness.data().selected, Some(4));
+            assert_eq!(seen.borrow().as_deref(), Some("durian"));
+            assert_eq!(visible_rows(harness, &laid_out), vec![1, 2, 3, 4]);
+
+            harness.submit_command(MasterDetail::<Item, u32>::DELETE_ITEM, Target::Auto);
+            assert_eq!(harness.data().items.len(), 3);
+            assert_eq!(harness.data().selected, None);
+            assert_eq!(visible_rows(harness, &laid_out), vec![1, 2, 3]);
+        })
+    }
+
+    #[test]
+    fn add_button_with_an_explicit_id() {
+        let laid_out = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::new(RefCell::new(None));
+        let id = WidgetId::next();
+        let widget = make_master_detail(&laid_out, &seen)
+            .with_add_button(|data| (data.items.len() as u32 + 1, "durian".to_string()))
+            .with_id(id);
+
+        Harness::create(items(), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            // the button row is at the bottom of the master side
+            click(harness, Point::new(10., DEFAULT_SIZE.height - 10.));
+            assert_eq!(harness.data().items.len(), 4);
+            assert_eq!(harness.data().selected, Some(4));
+
+            harness.submit_command(MasterDetail::<Item, u32>::ADD_ITEM, id);
+            assert_eq!(harness.data().items.len(), 5);
+        })
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget for optional data.
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::widget::SizedBox;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget, WidgetPod,
+};
+
+/// A widget for `Option<T>` data.
+///
+/// When the data is `Some`, this shows a widget for the `T` inside it; when
+/// it is `None`, it shows a placeholder widget, whose data is `()`.
+///
+/// The child is built when the widget is added, and rebuilt whenever the
+/// data goes from `Some` to `None` or back; the old child is dropped, and
+/// the new one is added in its place, so it is part of the focus chain.
+pub struct Maybe<T> {
+    some_maker: Box<dyn Fn() -> Box<dyn Widget<T>>>,
+    none_maker: Box<dyn Fn() -> Box<dyn Widget<()>>>,
+    widget: Option<MaybeWidget<T>>,
+}
+
+/// The child that is currently shown.
+enum MaybeWidget<T> {
+    Some(WidgetPod<T, Box<dyn Widget<T>>>),
+    None(WidgetPod<(), Box<dyn Widget<()>>>),
+}
+
+impl<T: Data> Maybe<T> {
+    /// Create a new `Maybe` widget.
+    ///
+    /// `some_maker` builds the widget for the data when it is `Some`, and
+    /// `none_maker` builds the placeholder for when it is `None`.
+    pub fn new<W1, W2>(
+        some_maker: impl Fn() -> W1 + 'static,
+        none_maker: impl Fn() -> W2 + 'static,
+    ) -> Maybe<T>
+    where
+        W1: Widget<T> + 'static,
+        W2: Widget<()> + 'static,
+    {
+        Maybe {
+            some_maker: Box::new(move || Box::new(some_maker())),
+            none_maker: Box::new(move || Box::new(none_maker())),
+            widget: None,
+        }
+    }
+
+    /// Create a new `Maybe` widget that shows nothing when the data is `None`.
+    pub fn or_empty<W: Widget<T> + 'static>(some_maker: impl Fn() -> W + 'static) -> Maybe<T> {
+        Maybe::new(some_maker, SizedBox::empty)
+    }
+
+    /// Build the child for `data`.
+    fn make_widget(&self, data: &Option<T>) -> MaybeWidget<T> {
+        match data {
+            Some(_) => MaybeWidget::Some(WidgetPod::new((self.some_maker)())),
+            None => MaybeWidget::None(WidgetPod::new((self.none_maker)())),
+        }
+    }
+}
+
+impl<T: Data> Widget<Option<T>> for Maybe<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Option<T>, env: &Env) {
+        match (self.widget.as_mut(), data.as_mut()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => child.event(ctx, event, data, env),
+            (Some(MaybeWidget::None(child)), None) => child.event(ctx, event, &mut (), env),
+            // the data changed during this event, and we will rebuild the
+            // child in update
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &Option<T>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.widget = Some(self.make_widget(data));
+        }
+        match (self.widget.as_mut(), data.as_ref()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => child.lifecycle(ctx, event, data, env),
+            (Some(MaybeWidget::None(child)), None) => child.lifecycle(ctx, event, &(), env),
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &Option<T>, data: &Option<T>, env: &Env) {
+        let matches = match (&self.widget, data) {
+            (Some(MaybeWidget::Some(_)), Some(_)) | (Some(MaybeWidget::None(_)), None) => true,
+            _ => false,
+        };
+        if !matches {
+            // the new child has not been added yet, so it doesn't get an update;
+            // it gets `WidgetAdded` once the children have been rebuilt.
+            match self.widget.as_mut() {
+                Some(MaybeWidget::Some(child)) => ctx.remove_child(child),
+                Some(MaybeWidget::None(child)) => ctx.remove_child(child),
+                None => (),
+            }
+            self.widget = Some(self.make_widget(data));
+            ctx.children_changed();
+            ctx.request_layout();
+            return;
+        }
+        match (self.widget.as_mut(), data.as_ref()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => child.update(ctx, data, env),
+            (Some(MaybeWidget::None(child)), None) => child.update(ctx, &(), env),
+            _ => (),
+        }
+    }
+
+    fn layout(
+        &mut self,
+        layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &Option<T>,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("Maybe");
+
+        let (size, insets, baseline) = match (self.widget.as_mut(), data.as_ref()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => {
+                let size = child.layout(layout_ctx, bc, data, env);
+                child.set_layout_rect(
+                    layout_ctx,
+                    data,
+                    env,
+                    Rect::from_origin_size(Point::ORIGIN, size),
+                );
+                (size, child.paint_insets(), child.baseline_offset())
+            }
+            (Some(MaybeWidget::None(child)), None) => {
+                let size = child.layout(layout_ctx, bc, &(), env);
+                child.set_layout_rect(
+                    layout_ctx,
+                    &(),
+                    env,
+                    Rect::from_origin_size(Point::ORIGIN, size),
+                );
+                (size, child.paint_insets(), child.baseline_offset())
+            }
+            _ => return bc.min(),
+        };
+        layout_ctx.set_paint_insets(insets);
+        layout_ctx.set_baseline_offset(baseline);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Option<T>, env: &Env) {
+        match (self.widget.as_mut(), data.as_ref()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => child.paint(ctx, data, env),
+            (Some(MaybeWidget::None(child)), None) => child.paint(ctx, &(), env),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Controller, Label, Split, WidgetExt};
+    use crate::{Selector, Target};
+
+    #[test]
+    fn maybe_follows_the_option() {
+        const FLIP: Selector = Selector::new("druid-tests.flip-option");
+        const BUMP: Selector = Selector::new("druid-tests.bump-inner");
+
+        /// Registers for focus, and increments the data when it gets `BUMP`.
+        fn bumped_focusable() -> impl Widget<u32> {
+            make_focusable(Default::default()).event_fn(|_, _ctx, event, data: &mut u32, _env| {
+                if let Event::Command(cmd) = event {
+                    if cmd.selector == BUMP {
+                        *data += 1;
+                    }
+                }
+            })
+        }
+
+        /// Switches the data between `Some` and `None` when it gets `FLIP`.
+        struct Flip;
+
+        impl<W: Widget<Option<u32>>> Controller<Option<u32>, W> for Flip {
+            fn event(
+                &mut self,
+                child: &mut W,
+                ctx: &mut EventCtx,
+                event: &Event,
+                data: &mut Option<u32>,
+                env: &Env,
+            ) {
+                match event {
+                    Event::Command(cmd) if cmd.selector == FLIP => {
+                        *data = match data {
+                            Some(_) => None,
+                            None => Some(0),
+                        };
+                    }
+                    _ => child.event(ctx, event, data, env),
+                }
+            }
+        }
+
+        let (id_1, id_2, maybe) = widget_id3();
+        let widget = Maybe::new(
+            move || {
+                Split::vertical(
+                    bumped_focusable().with_id(id_1),
+                    bumped_focusable().with_id(id_2),
+                )
+            },
+            || Label::new("nothing"),
+        )
+        .with_id(maybe)
+        .controller(Flip);
+
+        Harness::create(None, widget, |harness| {
+            harness.send_initial_events();
+            assert!(harness.window().focus_chain().is_empty());
+            // the placeholder
+            assert_eq!(harness.get_state(maybe).children.entry_count(), 1);
+
+            harness.submit_command(FLIP, Target::Auto);
+            assert_eq!(harness.data(), &Some(0));
+            assert_eq!(harness.window().focus_chain(), &[id_1, id_2]);
+            // the split, and its two children
+            assert_eq!(harness.get_state(maybe).children.entry_count(), 3);
+            assert!(harness.get_state(maybe).needs_layout);
+
+            // the inner widget changes the data inside the option
+            harness.submit_command(BUMP, id_2);
+            assert_eq!(harness.data(), &Some(1));
+            assert_eq!(harness.window().focus_chain(), &[id_1, id_2]);
+
+            harness.submit_command(FLIP, Target::Auto);
+            assert_eq!(harness.data(), &None);
+            assert!(harness.window().focus_chain().is_empty());
+            assert!(!harness.get_state(maybe).children.contains(&id_1));
+            assert_eq!(harness.get_state(maybe).children.entry_count(), 1);
+
+            harness.submit_command(FLIP, Target::Auto);
+            assert_eq!(harness.window().focus_chain(), &[id_1, id_2]);
+            assert_eq!(harness.get_state(maybe).children.entry_count(), 3);
+        })
+    }
+}
@@ -15,20 +15,36 @@
 //! Common widgets.
 
 mod align;
+mod animated_size;
 mod button;
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+mod calendar;
+mod capture;
 mod checkbox;
+mod click;
+mod clip;
 mod common;
 mod container;
 mod controller;
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+mod date_picker;
+mod disabled_if;
+mod drop_target;
+mod dropdown_select;
 mod either;
 mod env_scope;
 mod flex;
+mod form_row;
 mod identity_wrapper;
-#[cfg(feature = "image")]
-#[cfg_attr(docsrs, doc(cfg(feature = "image")))]
 mod image;
 mod label;
 mod list;
+mod master_detail;
+mod maybe;
+mod native_view_host;
+mod opacity;
 mod padding;
 mod painter;
 mod parse;
@@ -37,31 +53,56 @@ mod radio;
 mod scroll;
 mod sized_box;
 mod slider;
+mod spinner;
 mod split;
 mod stepper;
 #[cfg(feature = "svg")]
 #[cfg_attr(docsrs, doc(cfg(feature = "svg")))]
 mod svg;
 mod switch;
+mod table;
+mod tabs;
 mod textbox;
+mod toolbar;
+mod tooltip;
+mod value_textbox;
 mod view_switcher;
+mod virtual_list;
+mod visibility;
 mod widget_ext;
 
-#[cfg(feature = "image")]
-#[cfg_attr(docsrs, doc(cfg(feature = "image")))]
-pub use self::image::{Image, ImageData};
+pub use self::image::{Image, ImageBuf};
 pub use align::Align;
+pub use animated_size::AnimatedSize;
 pub use button::Button;
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+pub use calendar::Calendar;
+pub use capture::Capture;
 pub use checkbox::Checkbox;
+pub use click::Click;
+pub use clip::Clip;
 pub use common::FillStrat;
 pub use container::Container;
 pub use controller::{Controller, ControllerHost};
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+pub use date_picker::DatePicker;
+pub use disabled_if::DisabledIf;
+pub use drop_target::DropTarget;
+pub use dropdown_select::DropdownSelect;
 pub use either::Either;
 pub use env_scope::EnvScope;
 pub use flex::{CrossAxisAlignment, Flex, MainAxisAlignment};
+pub use form_row::{FormRow, LabelFor, LabeledBy};
 pub use identity_wrapper::IdentityWrapper;
-pub use label::{Label, LabelText};
+pub use label::{Label, LabelText, LineBreaking, TextAlignment};
 pub use list::{List, ListIter};
+pub use master_detail::{MasterDetail, MasterDetailData};
+pub use maybe::Maybe;
+pub(crate) use native_view_host::HostedView;
+pub use native_view_host::{NativeView, NativeViewHost};
+pub use opacity::Opacity;
 pub use padding::Padding;
 pub use painter::{BackgroundBrush, Painter};
 pub use parse::Parse;
@@ -70,14 +111,22 @@ pub use radio::{Radio, RadioGroup};
 pub use scroll::Scroll;
 pub use sized_box::SizedBox;
 pub use slider::Slider;
+pub use spinner::Spinner;
 pub use split::Split;
 pub use stepper::Stepper;
 #[cfg(feature = "svg")]
 #[cfg_attr(docsrs, doc(cfg(feature = "svg")))]
 pub use svg::{Svg, SvgData};
 pub use switch::Switch;
-pub use textbox::TextBox;
+pub use table::{Column, SortDirection, Table};
+pub use tabs::{StaticTabs, Tabs, TabsPolicy};
+pub use textbox::{TextBox, ValidationError, ValidationMessage};
+pub use toolbar::Toolbar;
+pub use tooltip::Tooltip;
+pub use value_textbox::ValueTextBox;
 pub use view_switcher::ViewSwitcher;
+pub use virtual_list::VirtualList;
+pub use visibility::Visibility;
 pub use widget_ext::WidgetExt;
 
 use std::num::NonZeroU64;
@@ -118,7 +167,7 @@ use crate::{
 /// [`WidgetExt::with_id`]: ../trait.WidgetExt.html#tymethod.with_id
 /// [`IdentityWrapper`]: struct.IdentityWrapper.html
 // this is NonZeroU64 because we regularly store Option<WidgetId>
-#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct WidgetId(NonZeroU64);
 
 /// The trait implemented by all widgets.
@@ -193,7 +242,8 @@ pub trait Widget<T> {
     ///
     /// This method is called whenever the data changes. When the appearance of
     /// the widget depends on data, call [`request_paint`] so that it's scheduled
-    /// for repaint.
+    /// for repaint. It is also called when the widget or an ancestor asks for
+    /// it with [`request_update`]; then `old_data` may be the same as `data`.
     ///
     /// The previous value of the data is provided in case the widget wants to
     /// compute a fine-grained delta. Before any paint operation, this method
@@ -201,6 +251,7 @@ pub trait Widget<T> {
     /// used to build resources that will be retained for painting.
     ///
     /// [`request_paint`]: ../struct.UpdateCtx.html#method.request_paint
+    /// [`request_update`]: ../struct.EventCtx.html#method.request_update
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env);
 
     /// Compute layout.
@@ -246,6 +297,12 @@ pub trait Widget<T> {
     fn id(&self) -> Option<WidgetId> {
         None
     }
+
+    #[doc(hidden)]
+    /// The name of the concrete type of the widget, for debugging.
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 impl WidgetId {
@@ -256,7 +313,11 @@ impl WidgetId {
     /// if you want two sibling widgets to know each others' ids.
     ///
     /// You must ensure that a given `WidgetId` is only ever used for one
-    /// widget at a time.
+    /// widget at a time. In debug builds, adding a widget to a window that
+    /// already has a widget with its id is logged as an error; see
+    /// [`Env::PANIC_ON_DUPLICATE_ID`].
+    ///
+    /// [`Env::PANIC_ON_DUPLICATE_ID`]: ../struct.Env.html#associatedconstant.PANIC_ON_DUPLICATE_ID
     pub fn next() -> WidgetId {
         use crate::shell::Counter;
         static WIDGET_ID_COUNTER: Counter = Counter::new();
@@ -268,6 +329,12 @@ impl WidgetId {
     /// The caller is responsible for ensuring that this ID is in fact assigned
     /// to a single widget at any time, or your code may become haunted.
     ///
+    /// Reserved ids never collide with the ids from [`next`], so they can
+    /// name well-known widgets, for instance to target commands at a
+    /// widget that is rebuilt with the same id.
+    ///
+    /// [`next`]: #method.next
+    ///
     /// The actual inner representation of the returned `WidgetId` will not
     /// be the same as the raw value that is passed in; it will be
     /// `u64::max_value() - raw`.
@@ -278,7 +345,8 @@ impl WidgetId {
         WidgetId(unsafe { std::num::NonZeroU64::new_unchecked(id) })
     }
 
-    pub(crate) fn to_raw(self) -> u64 {
+    /// Returns the integer value of this id, such as for logging.
+    pub fn to_raw(self) -> u64 {
         self.0.into()
     }
 }
@@ -307,4 +375,8 @@ impl<T> Widget<T> for Box<dyn Widget<T>> {
     fn id(&self) -> Option<WidgetId> {
         self.deref().id()
     }
+
+    fn type_name(&self) -> &'static str {
+        self.deref().type_name()
+    }
 }
@@ -0,0 +1,296 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that makes room for a native surface.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::kurbo::{Rect, Size};
+use crate::shell::ChildSurface;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    RenderContext, UpdateCtx, Widget, WindowHandle,
+};
+
+/// A native surface that a [`NativeViewHost`] keeps over its layout rect.
+///
+/// This is implemented for the shell's [`ChildSurface`]; custom surfaces
+/// can implement it too.
+///
+/// [`NativeViewHost`]: struct.NativeViewHost.html
+/// [`ChildSurface`]: ../struct.ChildSurface.html
+pub trait NativeView {
+    /// Move and resize the surface.
+    ///
+    /// The frame is in display points, relative to the window's content area.
+    fn set_frame(&mut self, frame: Rect);
+
+    /// Show or hide the surface.
+    fn set_visible(&mut self, visible: bool);
+
+    /// Set whether mouse events over the surface go to the window instead.
+    fn set_passthrough(&mut self, passthrough: bool);
+}
+
+impl NativeView for ChildSurface {
+    fn set_frame(&mut self, frame: Rect) {
+        ChildSurface::set_frame(self, frame)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        ChildSurface::set_visible(self, visible)
+    }
+
+    fn set_passthrough(&mut self, passthrough: bool) {
+        ChildSurface::set_passthrough(self, passthrough)
+    }
+}
+
+/// A hosted view, along with what it was last told, so that it is only
+/// told about changes.
+///
+/// The window keeps a weak reference to each view it painted, so that it
+/// can hide the ones that are not painted in the next frame.
+pub(crate) struct HostedView {
+    view: Box<dyn NativeView>,
+    frame: Option<Rect>,
+    visible: bool,
+}
+
+impl HostedView {
+    fn show(&mut self, frame: Rect) {
+        // kurbo's Rect doesn't implement PartialEq
+        let coords = |r: Rect| (r.x0, r.y0, r.x1, r.y1);
+        if self.frame.map(coords) != Some(coords(frame)) {
+            self.frame = Some(frame);
+            self.view.set_frame(frame);
+        }
+        if !self.visible {
+            self.visible = true;
+            self.view.set_visible(true);
+        }
+    }
+
+    /// The view's frame, in display points, if it is shown.
+    pub(crate) fn shown_frame(&self) -> Option<Rect> {
+        self.frame.filter(|_| self.visible)
+    }
+
+    pub(crate) fn hide(&mut self) {
+        if self.visible {
+            self.visible = false;
+            self.view.set_visible(false);
+        }
+    }
+}
+
+type ViewMaker = dyn FnMut(&WindowHandle) -> Option<Box<dyn NativeView>>;
+
+/// A widget that keeps a native surface over its layout rect.
+///
+/// This is for content that druid doesn't draw, such as a 3D viewport that
+/// is rendered with OpenGL. The surface is created the first time the host
+/// is painted, and destroyed when the host is dropped.
+///
+/// The surface is moved to the host's rect in the window in each paint, as
+/// that is when it is known; it is hidden whenever the host is not painted,
+/// such as in the hidden branch of an [`Either`]. It is drawn over the
+/// window, and is not clipped by the host's ancestors, such as a [`Scroll`].
+///
+/// By default, mouse events over the surface go to the surface; with
+/// [`passthrough`], they go to the host instead.
+///
+/// The host fills the space it is given, on axes where that is bounded.
+///
+/// [`Either`]: struct.Either.html
+/// [`Scroll`]: struct.Scroll.html
+/// [`passthrough`]: #method.passthrough
+pub struct NativeViewHost {
+    maker: Option<Box<ViewMaker>>,
+    view: Option<Rc<RefCell<HostedView>>>,
+    passthrough: bool,
+}
+
+impl NativeViewHost {
+    /// Create a host for a [`ChildSurface`] of the window.
+    ///
+    /// `on_created` is called with the surface once it is created, which is
+    /// where a renderer can be set up to draw to it. If the platform can't
+    /// create the surface, the error is logged, and the host stays empty.
+    ///
+    /// [`ChildSurface`]: ../struct.ChildSurface.html
+    pub fn new(mut on_created: impl FnMut(&ChildSurface) + 'static) -> Self {
+        NativeViewHost::from_maker(move |window| match window.create_child_surface() {
+            Ok(surface) => {
+                on_created(&surface);
+                Some(Box::new(surface))
+            }
+            Err(e) => {
+                log::error!("failed to create a child surface: {}", e);
+                None
+            }
+        })
+    }
+
+    /// Create a host for a custom [`NativeView`].
+    ///
+    /// `maker` is called once, the first time the host is painted, with the
+    /// host's window.
+    ///
+    /// [`NativeView`]: trait.NativeView.html
+    pub fn from_maker(
+        maker: impl FnMut(&WindowHandle) -> Option<Box<dyn NativeView>> + 'static,
+    ) -> Self {
+        NativeViewHost {
+            maker: Some(Box::new(maker)),
+            view: None,
+            passthrough: false,
+        }
+    }
+
+    /// Builder-style method to set whether mouse events over the surface are
+    /// passed through to the host, rather than going to the surface.
+    pub fn passthrough(mut self, passthrough: bool) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+}
+
+impl<T: Data> Widget<T> for NativeViewHost {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut T, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &T, _env: &Env) {}
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &T, _data: &T, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, _env: &Env) -> Size {
+        bc.debug_check("NativeViewHost");
+
+        bc.max_or(bc.min())
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, _env: &Env) {
+        if let Some(mut maker) = self.maker.take() {
+            let passthrough = self.passthrough;
+            self.view = maker(ctx.window).map(|mut view| {
+                view.set_passthrough(passthrough);
+                Rc::new(RefCell::new(HostedView {
+                    view,
+                    frame: None,
+                    visible: false,
+                }))
+            });
+        }
+
+        if let Some(view) = &self.view {
+            let rect = ctx.size().to_rect();
+            let frame = ctx.render_ctx.current_transform().transform_rect_bbox(rect);
+            view.borrow_mut().show(frame);
+            ctx.native_views.push(Rc::downgrade(view));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::widget::{Align, Controller, Either, SizedBox, WidgetExt};
+    use crate::{Selector, Target, UnitPoint};
+
+    /// A native view that logs what it is told.
+    struct MockView(Rc<RefCell<Vec<String>>>);
+
+    impl NativeView for MockView {
+        fn set_frame(&mut self, frame: Rect) {
+            self.0.borrow_mut().push(format!(
+                "frame {} {} {} {}",
+                frame.x0, frame.y0, frame.x1, frame.y1
+            ));
+        }
+
+        fn set_visible(&mut self, visible: bool) {
+            self.0.borrow_mut().push(format!("visible {}", visible));
+        }
+
+        fn set_passthrough(&mut self, passthrough: bool) {
+            self.0
+                .borrow_mut()
+                .push(format!("passthrough {}", passthrough));
+        }
+    }
+
+    #[test]
+    fn native_view_follows_its_host() {
+        const TOGGLE: Selector = Selector::new("druid-tests.toggle");
+
+        /// Flips the data when sent `TOGGLE`.
+        struct Toggle;
+
+        impl<W: Widget<bool>> Controller<bool, W> for Toggle {
+            fn event(
+                &mut self,
+                child: &mut W,
+                ctx: &mut EventCtx,
+                event: &Event,
+                data: &mut bool,
+                env: &Env,
+            ) {
+                match event {
+                    Event::Command(cmd) if cmd.selector == TOGGLE => *data = !*data,
+                    _ => child.event(ctx, event, data, env),
+                }
+            }
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let maker_log = log.clone();
+        let host = NativeViewHost::from_maker(move |_| Some(Box::new(MockView(maker_log.clone()))))
+            .passthrough(true)
+            .fix_size(100., 50.)
+            .padding(20.);
+        let widget = Either::new(
+            |shown: &bool, _| *shown,
+            Align::new(UnitPoint::TOP_LEFT, host),
+            SizedBox::empty(),
+        )
+        .controller(Toggle);
+
+        Harness::create(true, widget, |harness| {
+            harness.send_initial_events();
+            // the view is created in the first paint, and put over the host
+            harness.paint();
+            assert_eq!(
+                *log.borrow(),
+                vec!["passthrough true", "frame 20 20 120 70", "visible true"]
+            );
+
+            // it is only told about changes
+            harness.paint();
+            assert_eq!(log.borrow().len(), 3);
+
+            // it is hidden with its host, and shown again in the same place
+            harness.submit_command(TOGGLE, Target::Auto);
+            harness.paint();
+            assert_eq!(
+                log.borrow().last().map(String::as_str),
+                Some("visible false")
+            );
+            harness.submit_command(TOGGLE, Target::Auto);
+            harness.paint();
+            assert_eq!(log.borrow()[3..], ["visible false", "visible true"]);
+        });
+    }
+}
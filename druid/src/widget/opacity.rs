@@ -0,0 +1,230 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that paints its child partly transparent.
+
+use std::mem;
+use std::time::Duration;
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::piet::{self, Device, ImageFormat, InterpolationMode, RenderContext};
+use crate::theme;
+use crate::{
+    Animator, BoxConstraints, Curve, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, UpdateCtx, Widget, WidgetPod,
+};
+
+/// The name of the opacity in the `Animator`.
+const OPACITY: &str = "opacity";
+
+type OpacityFn<T> = dyn Fn(&T, &Env) -> f64;
+
+/// A widget that paints its child partly transparent.
+///
+/// The opacity is computed from the data and the environment, from `0.0`
+/// for invisible to `1.0` for opaque. With an animation, the widget fades
+/// in from invisible when it is added, and changes of the opacity are
+/// animated; if [`theme::ANIMATIONS_ENABLED`] is `false`, they happen
+/// immediately.
+///
+/// While it is partly transparent, the child is painted into a bitmap of
+/// its layout size, which is then painted faded; this costs more than
+/// painting it directly, and what the child paints outside of its layout
+/// rect is cut off. An invisible child isn't painted at all, but it still
+/// gets events.
+///
+/// This is usually created with [`WidgetExt::with_opacity_animation`].
+///
+/// [`theme::ANIMATIONS_ENABLED`]: ../theme/constant.ANIMATIONS_ENABLED.html
+/// [`WidgetExt::with_opacity_animation`]: trait.WidgetExt.html#method.with_opacity_animation
+pub struct Opacity<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    opacity: Box<OpacityFn<T>>,
+    animation: Option<(Duration, Curve)>,
+    animator: Animator,
+    /// The opacity last computed from the data.
+    target: Option<f64>,
+}
+
+impl<T> Opacity<T> {
+    /// Create a widget that paints `child` with the opacity that the
+    /// closure returns.
+    pub fn new(
+        child: impl Widget<T> + 'static,
+        opacity: impl Fn(&T, &Env) -> f64 + 'static,
+    ) -> Opacity<T> {
+        Opacity {
+            child: WidgetPod::new(child).boxed(),
+            opacity: Box::new(opacity),
+            animation: None,
+            animator: Animator::new(),
+            target: None,
+        }
+    }
+
+    /// Builder-style method for animating the changes of the opacity over
+    /// `duration`, following `curve`.
+    pub fn animated(mut self, duration: Duration, curve: Curve) -> Self {
+        self.animation = Some((duration, curve));
+        self
+    }
+
+    /// The opacity that the child is painted with now.
+    pub fn opacity(&self) -> f64 {
+        self.animator.get(OPACITY).unwrap_or(1.0)
+    }
+
+    /// Move to the opacity for `data`, returning whether it changed.
+    fn retarget(&mut self, data: &T, env: &Env) -> bool {
+        let target = (self.opacity)(data, env).max(0.0).min(1.0);
+        if self.target == Some(target) {
+            return false;
+        }
+        let animation = self
+            .animation
+            .filter(|_| env.get(theme::ANIMATIONS_ENABLED));
+        match animation {
+            Some((duration, curve)) => {
+                // a widget that was just added fades in
+                if self.target.is_none() {
+                    self.animator.set(OPACITY, 0.0);
+                }
+                self.animator.animate_to(OPACITY, target, duration, curve);
+            }
+            None => self.animator.set(OPACITY, target),
+        }
+        self.target = Some(target);
+        true
+    }
+}
+
+impl<T: Data> Opacity<T> {
+    /// Paint the child into a bitmap, and paint that with `opacity`.
+    fn paint_faded(
+        &mut self,
+        ctx: &mut PaintCtx,
+        opacity: f64,
+        data: &T,
+        env: &Env,
+    ) -> Result<(), piet::Error> {
+        let size = ctx.size();
+        let scale = ctx.scale.x();
+        let width = (size.width * scale).ceil() as usize;
+        let height = (size.height * scale).ceil() as usize;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        let mut device = Device::new()?;
+        let mut target = device.bitmap_target(width, height, scale)?;
+        {
+            let mut piet = target.render_context();
+            let visible = size.to_rect();
+            let mut paint_ctx = PaintCtx {
+                render_ctx: &mut piet,
+                base_state: ctx.base_state,
+                window_id: ctx.window_id,
+                z_ops: Vec::new(),
+                focus_widget: ctx.focus_widget,
+                region: visible.into(),
+                scale: ctx.scale,
+                window: ctx.window,
+                native_views: Vec::new(),
+            };
+            let child = &mut self.child;
+            paint_ctx.with_child_ctx(visible, |ctx| child.paint(ctx, data, env));
+
+            let mut z_ops = mem::take(&mut paint_ctx.z_ops);
+            z_ops.sort_by_key(|k| k.z_index);
+            for z_op in z_ops.into_iter() {
+                paint_ctx.with_child_ctx(visible, |ctx| {
+                    if let Err(e) = ctx.render_ctx.save() {
+                        log::error!("saving render context failed: {:?}", e);
+                        return;
+                    }
+                    ctx.render_ctx.transform(z_op.transform);
+                    (z_op.paint_func)(ctx);
+                    if let Err(e) = ctx.render_ctx.restore() {
+                        log::error!("restoring render context failed: {:?}", e);
+                    }
+                });
+            }
+            // native views aren't in the bitmap, and are shown as they are
+            ctx.native_views.append(&mut paint_ctx.native_views);
+            piet.finish()?;
+        }
+        let mut pixels = target.into_raw_pixels(ImageFormat::RgbaPremul)?;
+        // the alpha is premultiplied, so all of the channels fade together
+        for channel in pixels.iter_mut() {
+            *channel = (f64::from(*channel) * opacity).round() as u8;
+        }
+        let image = ctx.make_image(width, height, &pixels, ImageFormat::RgbaPremul)?;
+        ctx.draw_image(&image, size.to_rect(), InterpolationMode::Bilinear);
+        Ok(())
+    }
+}
+
+impl<T: Data> Widget<T> for Opacity<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                if self.retarget(data, env) && self.animator.is_animating() {
+                    ctx.request_anim_frame();
+                }
+            }
+            LifeCycle::AnimFrame(interval) if self.animator.is_animating() => {
+                if self.animator.advance(*interval) {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+            _ => (),
+        }
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        if self.retarget(data, env) {
+            ctx.request_paint();
+            if self.animator.is_animating() {
+                ctx.request_anim_frame();
+            }
+        }
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("Opacity");
+
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child
+            .set_layout_rect(ctx, data, env, Rect::from_origin_size(Point::ORIGIN, size));
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let opacity = self.opacity();
+        if opacity >= 1.0 {
+            self.child.paint_with_offset(ctx, data, env);
+        } else if opacity > 0.0 {
+            if let Err(e) = self.paint_faded(ctx, opacity, data, env) {
+                log::error!("failed to paint a faded widget: {}", e);
+            }
+        }
+    }
+}
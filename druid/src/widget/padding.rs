@@ -16,24 +16,40 @@
 
 use crate::kurbo::{Insets, Point, Rect, Size};
 use crate::{
-    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    UpdateCtx, Widget, WidgetPod,
+    BoxConstraints, Data, DirectionalInsets, Env, Event, EventCtx, KeyOrValue, LayoutCtx,
+    LayoutDirection, LifeCycle, LifeCycleCtx, PaintCtx, UpdateCtx, Widget, WidgetPod,
 };
 
 /// A widget that just adds padding around its child.
+///
+/// The padding can come from the [`Env`], in which case the child is laid
+/// out again whenever its value changes.
+///
+/// Insets can't be negative. A negative inset is a bug, and panics in debug
+/// builds; in release builds it is treated as zero.
+///
+/// Padding made with [`directional`] has leading and trailing insets, which
+/// are swapped when the [`LayoutDirection`] is right to left.
+///
+/// [`Env`]: ../struct.Env.html
+/// [`directional`]: #method.directional
+/// [`LayoutDirection`]: ../enum.LayoutDirection.html
 pub struct Padding<T> {
-    left: f64,
-    right: f64,
-    top: f64,
-    bottom: f64,
+    insets: KeyOrValue<Insets>,
+    /// Whether the left and right of `insets` are the leading and trailing
+    /// edges.
+    directional: bool,
+    /// The insets used in the last layout.
+    resolved: Insets,
 
     child: WidgetPod<T, Box<dyn Widget<T>>>,
 }
 
 impl<T> Padding<T> {
     /// Create a new widget with the specified padding. This can either be an instance
-    /// of [`kurbo::Insets`], a f64 for uniform padding, a 2-tuple for axis-uniform padding
-    /// or 4-tuple with (left, top, right, bottom) values.
+    /// of [`kurbo::Insets`], a f64 for uniform padding, a 2-tuple for axis-uniform padding,
+    /// a 4-tuple with (left, top, right, bottom) values, or a [`Key`] for insets in the
+    /// [`Env`].
     ///
     /// # Examples
     ///
@@ -59,17 +75,96 @@ impl<T> Padding<T> {
     /// let _: Padding<()> = Padding::new(Insets::uniform_xy(10.0, 20.0), Label::new("ditto :)"));
     /// ```
     ///
+    /// Insets from the [`Env`]:
+    ///
+    /// ```
+    /// use druid::widget::{Label, Padding};
+    /// use druid::{Insets, Key};
+    ///
+    /// const SPACING: Key<Insets> = Key::new("my-app.spacing");
+    /// let _: Padding<()> = Padding::new(SPACING, Label::new("themed"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if any of the insets is negative.
+    ///
     /// [`kurbo::Insets`]: https://docs.rs/kurbo/0.5.3/kurbo/struct.Insets.html
-    pub fn new(insets: impl Into<Insets>, child: impl Widget<T> + 'static) -> Padding<T> {
+    /// [`Key`]: ../struct.Key.html
+    /// [`Env`]: ../struct.Env.html
+    pub fn new(
+        insets: impl Into<KeyOrValue<Insets>>,
+        child: impl Widget<T> + 'static,
+    ) -> Padding<T> {
         let insets = insets.into();
+        let resolved = match &insets {
+            KeyOrValue::Concrete(value) => checked(value.to_inner_unchecked()),
+            KeyOrValue::Key(_) => Insets::ZERO,
+        };
         Padding {
-            left: insets.x0,
-            right: insets.x1,
-            top: insets.y0,
-            bottom: insets.y1,
+            insets,
+            directional: false,
+            resolved,
             child: WidgetPod::new(child).boxed(),
         }
     }
+
+    /// Create a new widget with padding at the leading and trailing edges,
+    /// rather than the left and right ones. This can be a
+    /// [`DirectionalInsets`], a f64 for uniform padding, or a 4-tuple with
+    /// (leading, top, trailing, bottom) values.
+    ///
+    /// ```
+    /// use druid::widget::{Label, Padding};
+    ///
+    /// // more space before the label than after it, whichever way it reads
+    /// let _: Padding<()> = Padding::directional((20.0, 0.0, 5.0, 0.0), Label::new("indented"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if any of the insets is negative.
+    ///
+    /// [`DirectionalInsets`]: ../struct.DirectionalInsets.html
+    pub fn directional(
+        insets: impl Into<DirectionalInsets>,
+        child: impl Widget<T> + 'static,
+    ) -> Padding<T> {
+        let insets = insets.into().resolve(LayoutDirection::Ltr);
+        Padding {
+            directional: true,
+            ..Padding::new(insets, child)
+        }
+    }
+
+    /// The insets in the environment, in terms of left and right.
+    fn resolve(&self, env: &Env) -> Insets {
+        let insets = checked(self.insets.resolve(env));
+        if self.directional && LayoutDirection::from_env(env).is_rtl() {
+            Insets::new(insets.x1, insets.y0, insets.x0, insets.y1)
+        } else {
+            insets
+        }
+    }
+}
+
+/// Checks that no inset is negative, and clamps them in release builds.
+fn checked(insets: Insets) -> Insets {
+    debug_assert!(
+        insets.x0 >= 0. && insets.y0 >= 0. && insets.x1 >= 0. && insets.y1 >= 0.,
+        "Padding insets must not be negative: {:?}",
+        insets
+    );
+    Insets::new(
+        insets.x0.max(0.),
+        insets.y0.max(0.),
+        insets.x1.max(0.),
+        insets.y1.max(0.),
+    )
+}
+
+fn same_insets(a: Insets, b: Insets) -> bool {
+    a.x0 == b.x0 && a.y0 == b.y0 && a.x1 == b.x1 && a.y1 == b.y1
 }
 
 impl<T: Data> Widget<T> for Padding<T> {
@@ -82,6 +177,9 @@ impl<T: Data> Widget<T> for Padding<T> {
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        if !same_insets(self.resolve(env), self.resolved) {
+            ctx.request_layout();
+        }
         self.child.update(ctx, data, env);
     }
 
@@ -94,18 +192,21 @@ impl<T: Data> Widget<T> for Padding<T> {
     ) -> Size {
         bc.debug_check("Padding");
 
-        let hpad = self.left + self.right;
-        let vpad = self.top + self.bottom;
+        let insets = self.resolve(env);
+        self.resolved = insets;
+        let hpad = insets.x0 + insets.x1;
+        let vpad = insets.y0 + insets.y1;
 
         let child_bc = bc.shrink((hpad, vpad));
         let size = self.child.layout(layout_ctx, &child_bc, data, env);
-        let origin = Point::new(self.left, self.top);
+        let origin = Point::new(insets.x0, insets.y0);
         self.child
-            .set_layout_rect(Rect::from_origin_size(origin, size));
+            .set_layout_rect(layout_ctx, data, env, Rect::from_origin_size(origin, size));
 
         let my_size = Size::new(size.width + hpad, size.height + vpad);
         let my_insets = self.child.compute_parent_paint_insets(my_size);
         layout_ctx.set_paint_insets(my_insets);
+        layout_ctx.set_baseline_offset(self.child.baseline_offset() + insets.y1);
         my_size
     }
 
@@ -113,3 +214,99 @@ impl<T: Data> Widget<T> for Padding<T> {
         self.child.paint_with_offset(ctx, data, env);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Align, Controller, SizedBox, WidgetExt};
+    use crate::{Command, Key, Selector, Target, UnitPoint, WidgetId};
+
+    #[test]
+    fn padding_insets() {
+        const SPACING: Key<Insets> = Key::new("druid-tests.spacing");
+        const SET_SPACING: Selector = Selector::new("druid-tests.set-spacing");
+
+        /// Sets the data to the argument of `SET_SPACING`.
+        struct SetSpacing;
+
+        impl<W: Widget<f64>> Controller<f64, W> for SetSpacing {
+            fn event(
+                &mut self,
+                child: &mut W,
+                ctx: &mut EventCtx,
+                event: &Event,
+                data: &mut f64,
+                env: &Env,
+            ) {
+                match event {
+                    Event::Command(cmd) if cmd.selector == SET_SPACING => {
+                        *data = *cmd.get_object().unwrap()
+                    }
+                    _ => child.event(ctx, event, data, env),
+                }
+            }
+        }
+
+        fn fixed<T: Data>(id: WidgetId) -> impl Widget<T> {
+            ModularWidget::new(())
+                .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain((100., 50.)))
+                .with_id(id)
+        }
+
+        // the insets can differ on each side
+        let (id, padding) = widget_id2();
+        let widget = Align::new(
+            UnitPoint::TOP_LEFT,
+            Padding::new((1., 2., 3., 4.), fixed(id)).with_id(padding),
+        );
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let rect = harness.get_state(id).layout_rect;
+            assert_eq!(rect.origin(), Point::new(1., 2.));
+            assert_eq!(rect.size(), Size::new(100., 50.));
+            assert_eq!(
+                harness.get_state(padding).layout_rect.size(),
+                Size::new(104., 56.)
+            );
+        });
+
+        // insets from the env follow the env
+        let (id, padding) = widget_id2();
+        let widget = Align::new(
+            UnitPoint::TOP_LEFT,
+            Padding::new(SPACING, fixed(id))
+                .with_id(padding)
+                .env_scope(|env, spacing: &f64| env.set(SPACING, Insets::uniform(*spacing))),
+        )
+        .controller(SetSpacing);
+        Harness::create(5., widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            assert_eq!(
+                harness.get_state(id).layout_rect.origin(),
+                Point::new(5., 5.)
+            );
+
+            harness.submit_command(Command::new(SET_SPACING, 20.), Target::Auto);
+            harness.just_layout();
+            assert_eq!(
+                harness.get_state(id).layout_rect.origin(),
+                Point::new(20., 20.)
+            );
+            assert_eq!(
+                harness.get_state(padding).layout_rect.size(),
+                Size::new(140., 90.)
+            );
+        });
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "must not be negative")]
+    fn negative_padding_panics() {
+        let _ = Padding::<()>::new((10., -1.), SizedBox::empty());
+    }
+}
@@ -14,8 +14,8 @@
 
 use crate::piet::{FixedGradient, LinearGradient, PaintBrush, RadialGradient};
 use crate::{
-    BoxConstraints, Color, Data, Env, Event, EventCtx, Key, LayoutCtx, LifeCycle, LifeCycleCtx,
-    PaintCtx, RenderContext, Size, UpdateCtx, Widget,
+    BoxConstraints, Color, Data, Env, Event, EventCtx, Key, KeyOrValue, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, RenderContext, Size, UpdateCtx, Widget,
 };
 
 /// A widget that only handles painting.
@@ -149,6 +149,15 @@ impl<T> From<Key<Color>> for BackgroundBrush<T> {
     }
 }
 
+impl<T> From<KeyOrValue<Color>> for BackgroundBrush<T> {
+    fn from(src: KeyOrValue<Color>) -> BackgroundBrush<T> {
+        match src {
+            KeyOrValue::Concrete(value) => BackgroundBrush::Color(value.to_inner_unchecked()),
+            KeyOrValue::Key(key) => BackgroundBrush::ColorKey(key),
+        }
+    }
+}
+
 impl<T> From<LinearGradient> for BackgroundBrush<T> {
     fn from(src: LinearGradient) -> BackgroundBrush<T> {
         BackgroundBrush::Linear(src)
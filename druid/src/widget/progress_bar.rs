@@ -40,6 +40,11 @@ impl Widget<f64> for ProgressBar {
     fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &f64, _env: &Env) {}
 
     fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &f64, _data: &f64, _env: &Env) {
+        if ctx.env_key_changed(theme::BASIC_WIDGET_HEIGHT)
+            || ctx.env_key_changed(theme::WIDE_WIDGET_WIDTH)
+        {
+            ctx.request_layout();
+        }
         ctx.request_paint();
     }
 
@@ -60,15 +65,7 @@ impl Widget<f64> for ProgressBar {
     fn paint(&mut self, ctx: &mut PaintCtx, data: &f64, env: &Env) {
         let clamped = data.max(0.0).min(1.0);
 
-        let rounded_rect = RoundedRect::from_origin_size(
-            Point::ORIGIN,
-            (Size {
-                width: ctx.size().width,
-                height: env.get(theme::BASIC_WIDGET_HEIGHT),
-            })
-            .to_vec2(),
-            4.,
-        );
+        let rounded_rect = RoundedRect::from_origin_size(Point::ORIGIN, ctx.size().to_vec2(), 4.);
 
         //Paint the border
         ctx.stroke(rounded_rect, &env.get(theme::BORDER_DARK), 2.0);
@@ -90,7 +87,7 @@ impl Widget<f64> for ProgressBar {
             Point::ORIGIN,
             (Size {
                 width: calculated_bar_width,
-                height: env.get(theme::BASIC_WIDGET_HEIGHT),
+                height: ctx.size().height,
             })
             .to_vec2(),
             env.get(theme::PROGRESS_BAR_RADIUS),
@@ -103,3 +100,34 @@ impl Widget<f64> for ProgressBar {
         ctx.fill(rounded_rect, &bar_gradient);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Flex, WidgetExt};
+
+    #[test]
+    fn progress_bar_layout_respects_constraints() {
+        let env = theme::init();
+        let height = env.get(theme::BASIC_WIDGET_HEIGHT);
+        let width = env.get(theme::WIDE_WIDGET_WIDTH);
+
+        let (id_1, id_2, id_3) = widget_id3();
+        let widget = Flex::column()
+            .with_child(ProgressBar::new().with_id(id_1), 1.0)
+            .with_child(ProgressBar::new().with_id(id_2).center(), 0.0)
+            .with_child(ProgressBar::new().fix_width(50.).with_id(id_3), 0.0);
+
+        Harness::create(0.5, widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            assert_eq!(harness.get_state(id_2).size(), Size::new(width, height));
+            assert_eq!(harness.get_state(id_3).size(), Size::new(50., height));
+            // the column gives its flex child the remaining height
+            assert_eq!(harness.get_state(id_1).size().width, width);
+            assert!(harness.get_state(id_1).size().height > height);
+        })
+    }
+}
@@ -18,8 +18,8 @@ use crate::kurbo::{Circle, Point, Rect, Size};
 use crate::theme;
 use crate::widget::{Flex, Label, LabelText, Padding, WidgetExt};
 use crate::{
-    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, LinearGradient,
-    PaintCtx, RenderContext, UnitPoint, UpdateCtx, Widget, WidgetPod,
+    commands, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    LinearGradient, PaintCtx, RenderContext, UnitPoint, UpdateCtx, Widget, WidgetPod,
 };
 
 /// A group of radio buttons
@@ -51,13 +51,13 @@ impl<T: Data> Radio<T> {
     pub fn new(label: impl Into<LabelText<T>>, variant: T) -> Radio<T> {
         Radio {
             variant,
-            child_label: WidgetPod::new(Label::new(label).boxed()),
+            child_label: WidgetPod::new(Label::new(label).with_mnemonic(true).boxed()),
         }
     }
 }
 
 impl<T: Data + PartialEq> Widget<T> for Radio<T> {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, _env: &Env) {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         match event {
             Event::MouseDown(_) => {
                 ctx.set_active(true);
@@ -72,6 +72,14 @@ impl<T: Data + PartialEq> Widget<T> for Radio<T> {
                     ctx.request_paint();
                 }
             }
+            Event::Command(cmd) if cmd.selector == commands::UNHANDLED_KEY => {
+                // the label handles the key if it is its mnemonic
+                self.child_label.event(ctx, event, data, env);
+                if ctx.is_handled() {
+                    *data = self.variant.clone();
+                    ctx.request_paint();
+                }
+            }
             _ => (),
         }
     }
@@ -83,7 +91,13 @@ impl<T: Data + PartialEq> Widget<T> for Radio<T> {
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, _data: &T, _env: &Env) {
-        ctx.request_paint();
+        if ctx.env_key_changed(theme::BASIC_WIDGET_HEIGHT)
+            || ctx.env_key_changed(theme::WIDGET_CONTROL_COMPONENT_PADDING)
+        {
+            ctx.request_layout();
+        } else {
+            ctx.request_paint();
+        }
     }
 
     fn layout(
@@ -96,12 +110,16 @@ impl<T: Data + PartialEq> Widget<T> for Radio<T> {
         bc.debug_check("Radio");
 
         let label_size = self.child_label.layout(layout_ctx, &bc, data, env);
-        let padding = 5.0;
+        let padding = env.get(theme::WIDGET_CONTROL_COMPONENT_PADDING);
         let label_x_offset = env.get(theme::BASIC_WIDGET_HEIGHT) + padding;
         let origin = Point::new(label_x_offset, 0.0);
 
-        self.child_label
-            .set_layout_rect(Rect::from_origin_size(origin, label_size));
+        self.child_label.set_layout_rect(
+            layout_ctx,
+            data,
+            env,
+            Rect::from_origin_size(origin, label_size),
+        );
 
         bc.constrain(Size::new(
             label_x_offset + label_size.width,
@@ -112,7 +130,8 @@ impl<T: Data + PartialEq> Widget<T> for Radio<T> {
     fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
         let size = env.get(theme::BASIC_WIDGET_HEIGHT);
 
-        let circle = Circle::new((size / 2., size / 2.), 7.);
+        // a 7px radius in an 18px box
+        let circle = Circle::new((size / 2., size / 2.), size * 7. / 18.);
 
         // Paint the background
         let background_gradient = LinearGradient::new(
@@ -136,7 +155,7 @@ impl<T: Data + PartialEq> Widget<T> for Radio<T> {
 
         // Check if data enum matches our variant
         if *data == self.variant {
-            let inner_circle = Circle::new((size / 2., size / 2.), 2.);
+            let inner_circle = Circle::new((size / 2., size / 2.), size / 9.);
 
             ctx.fill(inner_circle, &env.get(theme::LABEL_COLOR));
         }
@@ -21,8 +21,8 @@ use std::time::{Duration, Instant};
 use crate::kurbo::{Affine, Point, Rect, RoundedRect, Size, Vec2};
 use crate::theme;
 use crate::{
-    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    RenderContext, TimerToken, UpdateCtx, Widget, WidgetPod,
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LayoutDirection, LifeCycle,
+    LifeCycleCtx, PaintCtx, RenderContext, ScrollPhase, TimerToken, UpdateCtx, Widget, WidgetPod,
 };
 
 #[derive(Debug, Clone)]
@@ -64,10 +64,10 @@ impl BarHoveredState {
 enum BarHeldState {
     None,
     /// Vertical scrollbar is being dragged. Contains an `f64` with
-    /// the initial y-offset of the dragging input
+    /// the y-offset of the dragging input from the top of the thumb
     Vertical(f64),
     /// Horizontal scrollbar is being dragged. Contains an `f64` with
-    /// the initial x-offset of the dragging input
+    /// the x-offset of the dragging input from the left of the thumb
     Horizontal(f64),
 }
 
@@ -105,12 +105,37 @@ impl ScrollBarsState {
 /// when the child's bounds are larger than the viewport.
 ///
 /// The child is laid out with completely unconstrained layout bounds.
+///
+/// Scrollbars are shown while scrolling, and fade out after
+/// [`theme::SCROLL_BAR_FADE_DELAY`]; they stay visible while hovered, and
+/// their thumbs can be dragged. Their look is set by the other
+/// `theme::SCROLL_BAR_*` keys.
+///
+/// When scrolls are nested, the wheel scrolls the innermost one under the
+/// mouse. Once that one can't scroll any further, the wheel is passed on to
+/// the next one out; see [`scroll_chaining`](#method.scroll_chaining).
+///
+/// When the [`LayoutDirection`] is right to left, the child starts scrolled
+/// all the way to the right.
+///
+/// The momentum that a trackpad scrolls with after the fingers are lifted is
+/// stopped when the user clicks or presses a key, or when the content
+/// reaches its edge; the rest of its events are ignored.
+///
+/// [`theme::SCROLL_BAR_FADE_DELAY`]: ../theme/constant.SCROLL_BAR_FADE_DELAY.html
+/// [`LayoutDirection`]: ../enum.LayoutDirection.html
 pub struct Scroll<T, W> {
     child: WidgetPod<T, W>,
     child_size: Size,
     scroll_offset: Vec2,
     direction: ScrollDirection,
     scroll_bars: ScrollBarsState,
+    /// Overrides `theme::SCROLL_CHAINING`, if set.
+    chaining: Option<bool>,
+    /// Whether the child has been laid out, and scrolled to where it starts.
+    placed: bool,
+    /// The momentum of the trackpad was stopped since it started.
+    momentum_stopped: bool,
 }
 
 impl<T, W: Widget<T>> Scroll<T, W> {
@@ -126,6 +151,9 @@ impl<T, W: Widget<T>> Scroll<T, W> {
             scroll_offset: Vec2::new(0.0, 0.0),
             direction: ScrollDirection::All,
             scroll_bars: ScrollBarsState::default(),
+            chaining: None,
+            placed: false,
+            momentum_stopped: false,
         }
     }
 
@@ -143,6 +171,18 @@ impl<T, W: Widget<T>> Scroll<T, W> {
         self
     }
 
+    /// Set whether wheel events that this can't scroll any further are
+    /// passed on to an enclosing scroll.
+    ///
+    /// If this is `false`, the scroll keeps every wheel event over it. By
+    /// default, this follows [`theme::SCROLL_CHAINING`].
+    ///
+    /// [`theme::SCROLL_CHAINING`]: ../theme/constant.SCROLL_CHAINING.html
+    pub fn scroll_chaining(mut self, chaining: bool) -> Self {
+        self.chaining = Some(chaining);
+        self
+    }
+
     /// Returns a reference to the child widget.
     pub fn child(&self) -> &W {
         self.child.widget()
@@ -162,12 +202,24 @@ impl<T, W: Widget<T>> Scroll<T, W> {
         offset.y = offset.y.min(self.child_size.height - size.height).max(0.0);
         if (offset - self.scroll_offset).hypot2() > 1e-12 {
             self.scroll_offset = offset;
+            self.child.set_viewport_offset(offset);
             true
         } else {
             false
         }
     }
 
+    /// Whether the content is at its edge in the direction of `delta`, on an
+    /// axis that it scrolls along.
+    fn is_at_edge(&self, delta: Vec2, size: Size) -> bool {
+        let hits = |delta: f64, offset: f64, max: f64| {
+            max > 0.0 && ((delta > 0.0 && offset >= max) || (delta < 0.0 && offset <= 0.0))
+        };
+        let max = self.child_size - size;
+        hits(delta.x, self.scroll_offset.x, max.width)
+            || hits(delta.y, self.scroll_offset.y, max.height)
+    }
+
     /// Makes the scrollbars visible, and resets the fade timer.
     pub fn reset_scrollbar_fade(&mut self, ctx: &mut EventCtx, env: &Env) {
         // Display scroll bars and schedule their disappearance
@@ -182,40 +234,72 @@ impl<T, W: Widget<T>> Scroll<T, W> {
         self.scroll_offset
     }
 
-    fn calc_vertical_bar_bounds(&self, viewport: Rect, env: &Env) -> Rect {
-        let bar_width = env.get(theme::SCROLL_BAR_WIDTH);
-        let bar_pad = env.get(theme::SCROLL_BAR_PAD);
+    /// Whether the vertical scrollbar is shown for this viewport.
+    fn has_vertical_bar(&self, viewport: Rect) -> bool {
+        viewport.height() < self.child_size.height
+    }
 
-        let scale_y = viewport.height() / self.child_size.height;
+    /// Whether the horizontal scrollbar is shown for this viewport.
+    fn has_horizontal_bar(&self, viewport: Rect) -> bool {
+        viewport.width() < self.child_size.width
+    }
 
-        let top_y_offset = (scale_y * self.scroll_offset.y).ceil();
-        let bottom_y_offset = (scale_y * viewport.height()).ceil() + top_y_offset;
+    /// The start and length of the vertical bar's track, in viewport coordinates.
+    ///
+    /// When both bars are shown, the corner is left to the horizontal bar.
+    fn vertical_track(&self, viewport: Rect, env: &Env) -> (f64, f64) {
+        let bar_width = env.get(theme::SCROLL_BAR_WIDTH);
+        let bar_pad = env.get(theme::SCROLL_BAR_PAD);
+        let mut len = viewport.height() - bar_pad * 2.;
+        if self.has_horizontal_bar(viewport) {
+            len -= bar_width + bar_pad;
+        }
+        (bar_pad, len.max(0.))
+    }
 
-        let x0 = self.scroll_offset.x + viewport.width() - bar_width - bar_pad;
-        let y0 = self.scroll_offset.y + top_y_offset + bar_pad;
+    /// The start and length of the horizontal bar's track, in viewport coordinates.
+    fn horizontal_track(&self, viewport: Rect, env: &Env) -> (f64, f64) {
+        let bar_width = env.get(theme::SCROLL_BAR_WIDTH);
+        let bar_pad = env.get(theme::SCROLL_BAR_PAD);
+        let mut len = viewport.width() - bar_pad * 2.;
+        if self.has_vertical_bar(viewport) {
+            len -= bar_width + bar_pad;
+        }
+        (bar_pad, len.max(0.))
+    }
 
-        let x1 = self.scroll_offset.x + viewport.width() - bar_pad;
-        let y1 = self.scroll_offset.y + bottom_y_offset - (bar_pad * 2.) - bar_width;
+    /// The bounds of the vertical bar's thumb, in viewport coordinates.
+    fn calc_vertical_bar_bounds(&self, viewport: Rect, env: &Env) -> Rect {
+        let bar_width = env.get(theme::SCROLL_BAR_WIDTH);
+        let bar_pad = env.get(theme::SCROLL_BAR_PAD);
+        let (track_start, track_len) = self.vertical_track(viewport, env);
+        let (y0, y1) = thumb_range(
+            viewport.height(),
+            self.child_size.height,
+            self.scroll_offset.y,
+            track_len,
+            bar_width,
+        );
 
-        Rect::new(x0, y0, x1, y1)
+        let x1 = viewport.width() - bar_pad;
+        Rect::new(x1 - bar_width, track_start + y0, x1, track_start + y1)
     }
 
+    /// The bounds of the horizontal bar's thumb, in viewport coordinates.
     fn calc_horizontal_bar_bounds(&self, viewport: Rect, env: &Env) -> Rect {
         let bar_width = env.get(theme::SCROLL_BAR_WIDTH);
         let bar_pad = env.get(theme::SCROLL_BAR_PAD);
+        let (track_start, track_len) = self.horizontal_track(viewport, env);
+        let (x0, x1) = thumb_range(
+            viewport.width(),
+            self.child_size.width,
+            self.scroll_offset.x,
+            track_len,
+            bar_width,
+        );
 
-        let scale_x = viewport.width() / self.child_size.width;
-
-        let left_x_offset = (scale_x * self.scroll_offset.x).ceil();
-        let right_x_offset = (scale_x * viewport.width()).ceil() + left_x_offset;
-
-        let x0 = self.scroll_offset.x + left_x_offset + bar_pad;
-        let y0 = self.scroll_offset.y + viewport.height() - bar_width - bar_pad;
-
-        let x1 = self.scroll_offset.x + right_x_offset - (bar_pad * 2.) - bar_width;
-        let y1 = self.scroll_offset.y + viewport.height() - bar_pad;
-
-        Rect::new(x0, y0, x1, y1)
+        let y1 = viewport.height() - bar_pad;
+        Rect::new(track_start + x0, y1 - bar_width, track_start + x1, y1)
     }
 
     /// Draw scroll bars.
@@ -236,17 +320,20 @@ impl<T, W: Widget<T>> Scroll<T, W> {
         let radius = env.get(theme::SCROLL_BAR_RADIUS);
         let edge_width = env.get(theme::SCROLL_BAR_EDGE_WIDTH);
 
+        // the bars are drawn in the scrolled content's coordinate space
+        let offset = self.scroll_offset;
+
         // Vertical bar
-        if viewport.height() < self.child_size.height {
-            let bounds = self.calc_vertical_bar_bounds(viewport, &env);
+        if self.has_vertical_bar(viewport) {
+            let bounds = self.calc_vertical_bar_bounds(viewport, &env) + offset;
             let rect = RoundedRect::from_rect(bounds, radius);
             ctx.render_ctx.fill(rect, &brush);
             ctx.render_ctx.stroke(rect, &border_brush, edge_width);
         }
 
         // Horizontal bar
-        if viewport.width() < self.child_size.width {
-            let bounds = self.calc_horizontal_bar_bounds(viewport, &env);
+        if self.has_horizontal_bar(viewport) {
+            let bounds = self.calc_horizontal_bar_bounds(viewport, &env) + offset;
             let rect = RoundedRect::from_rect(bounds, radius);
             ctx.render_ctx.fill(rect, &brush);
             ctx.render_ctx.stroke(rect, &border_brush, edge_width);
@@ -254,10 +341,10 @@ impl<T, W: Widget<T>> Scroll<T, W> {
     }
 
     fn point_hits_vertical_bar(&self, viewport: Rect, pos: Point, env: &Env) -> bool {
-        if viewport.height() < self.child_size.height {
+        if self.has_vertical_bar(viewport) {
             // Stretch hitbox to edge of widget
             let mut bounds = self.calc_vertical_bar_bounds(viewport, &env);
-            bounds.x1 = self.scroll_offset.x + viewport.width();
+            bounds.x1 = viewport.width();
             bounds.contains(pos)
         } else {
             false
@@ -265,10 +352,10 @@ impl<T, W: Widget<T>> Scroll<T, W> {
     }
 
     fn point_hits_horizontal_bar(&self, viewport: Rect, pos: Point, env: &Env) -> bool {
-        if viewport.width() < self.child_size.width {
+        if self.has_horizontal_bar(viewport) {
             // Stretch hitbox to edge of widget
             let mut bounds = self.calc_horizontal_bar_bounds(viewport, &env);
-            bounds.y1 = self.scroll_offset.y + viewport.height();
+            bounds.y1 = viewport.height();
             bounds.contains(pos)
         } else {
             false
@@ -276,16 +363,56 @@ impl<T, W: Widget<T>> Scroll<T, W> {
     }
 }
 
+/// The position of a scrollbar's thumb along its track.
+///
+/// `viewport` and `content` are the visible and total lengths on this axis,
+/// and `offset` is the scroll offset. The thumb's length is the visible
+/// fraction of the track, but never less than `min_len`; its start moves
+/// from the start of the track to the end as `offset` goes from zero to its
+/// maximum. Returns the start and end of the thumb, relative to the track.
+fn thumb_range(viewport: f64, content: f64, offset: f64, track: f64, min_len: f64) -> (f64, f64) {
+    let len = (track * viewport / content).max(min_len).min(track);
+    let max_offset = content - viewport;
+    let start = if max_offset > 0.0 {
+        (track - len) * (offset / max_offset).max(0.0).min(1.0)
+    } else {
+        0.0
+    };
+    (start, start + len)
+}
+
+/// The scroll offset that puts the start of the thumb at `thumb_start`.
+///
+/// This is the inverse of `thumb_range`, clamped to the content's edges.
+fn offset_for_thumb(
+    viewport: f64,
+    content: f64,
+    thumb_start: f64,
+    track: f64,
+    min_len: f64,
+) -> f64 {
+    let len = (track * viewport / content).max(min_len).min(track);
+    let free = track - len;
+    if free <= 0.0 {
+        return 0.0;
+    }
+    (thumb_start / free).max(0.0).min(1.0) * (content - viewport).max(0.0)
+}
+
 impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         let size = ctx.size();
         let viewport = Rect::from_origin_size(Point::ORIGIN, size);
+        let old_offset = self.scroll_offset;
+
+        if let Event::MouseDown(_) | Event::KeyDown(_) = event {
+            self.momentum_stopped = true;
+        }
 
         let scroll_bar_is_hovered = match event {
             Event::MouseMoved(e) | Event::MouseUp(e) | Event::MouseDown(e) => {
-                let offset_pos = e.pos + self.scroll_offset;
-                self.point_hits_vertical_bar(viewport, offset_pos, &env)
-                    || self.point_hits_horizontal_bar(viewport, offset_pos, &env)
+                self.point_hits_vertical_bar(viewport, e.pos, &env)
+                    || self.point_hits_horizontal_bar(viewport, e.pos, &env)
             }
             _ => false,
         };
@@ -295,19 +422,27 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
             match event {
                 Event::MouseMoved(event) => {
                     match self.scroll_bars.held {
-                        BarHeldState::Vertical(offset) => {
-                            let scale_y = viewport.height() / self.child_size.height;
-                            let bounds = self.calc_vertical_bar_bounds(viewport, &env);
-                            let mouse_y = event.pos.y + self.scroll_offset.y;
-                            let delta = mouse_y - bounds.y0 - offset;
-                            self.scroll(Vec2::new(0f64, (delta / scale_y).ceil()), size);
+                        BarHeldState::Vertical(grab) => {
+                            let (track_start, track_len) = self.vertical_track(viewport, &env);
+                            let y = offset_for_thumb(
+                                viewport.height(),
+                                self.child_size.height,
+                                event.pos.y - track_start - grab,
+                                track_len,
+                                env.get(theme::SCROLL_BAR_WIDTH),
+                            );
+                            self.scroll(Vec2::new(0.0, y - self.scroll_offset.y), size);
                         }
-                        BarHeldState::Horizontal(offset) => {
-                            let scale_x = viewport.width() / self.child_size.width;
-                            let bounds = self.calc_horizontal_bar_bounds(viewport, &env);
-                            let mouse_x = event.pos.x + self.scroll_offset.x;
-                            let delta = mouse_x - bounds.x0 - offset;
-                            self.scroll(Vec2::new((delta / scale_x).ceil(), 0f64), size);
+                        BarHeldState::Horizontal(grab) => {
+                            let (track_start, track_len) = self.horizontal_track(viewport, &env);
+                            let x = offset_for_thumb(
+                                viewport.width(),
+                                self.child_size.width,
+                                event.pos.x - track_start - grab,
+                                track_len,
+                                env.get(theme::SCROLL_BAR_WIDTH),
+                            );
+                            self.scroll(Vec2::new(x - self.scroll_offset.x, 0.0), size);
                         }
                         _ => (),
                     }
@@ -328,8 +463,7 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
             // if we're over a scrollbar but not dragging
             match event {
                 Event::MouseMoved(event) => {
-                    let offset_pos = event.pos + self.scroll_offset;
-                    if self.point_hits_vertical_bar(viewport, offset_pos, &env) {
+                    if self.point_hits_vertical_bar(viewport, event.pos, &env) {
                         self.scroll_bars.hovered = BarHoveredState::Vertical;
                     } else {
                         self.scroll_bars.hovered = BarHoveredState::Horizontal;
@@ -340,7 +474,7 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
                     ctx.request_paint();
                 }
                 Event::MouseDown(event) => {
-                    let pos = event.pos + self.scroll_offset;
+                    let pos = event.pos;
 
                     if self.point_hits_vertical_bar(viewport, pos, &env) {
                         ctx.set_active(true);
@@ -362,7 +496,11 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
             let force_event = self.child.is_hot() || self.child.is_active();
             let child_event = event.transform_scroll(self.scroll_offset, viewport, force_event);
             if let Some(child_event) = child_event {
-                self.child.event(ctx, &child_event, data, env)
+                // the child is in the coordinate space of the scrolled content
+                let window_origin = ctx.window_origin;
+                ctx.window_origin -= self.scroll_offset;
+                self.child.event(ctx, &child_event, data, env);
+                ctx.window_origin = window_origin;
             };
 
             match event {
@@ -376,8 +514,13 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
                 // Show the scrollbars any time our size changes
                 Event::Size(_) => self.reset_scrollbar_fade(ctx, &env),
                 Event::Timer(id) if *id == self.scroll_bars.timer_id => {
-                    // Schedule scroll bars animation
-                    ctx.request_anim_frame();
+                    if env.get(theme::ANIMATIONS_ENABLED) {
+                        // Schedule scroll bars animation
+                        ctx.request_anim_frame();
+                    } else {
+                        self.scroll_bars.opacity = 0.0;
+                        ctx.request_paint();
+                    }
                     self.scroll_bars.timer_id = TimerToken::INVALID;
                 }
                 _ => (),
@@ -386,13 +529,39 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
 
         if !ctx.is_handled() {
             if let Event::Wheel(wheel) = event {
-                if self.scroll(wheel.delta, size) {
+                // the user's gesture, or a new momentum, starts afresh
+                if let None | Some(ScrollPhase::Started) = wheel.momentum_phase {
+                    self.momentum_stopped = false;
+                }
+                let is_momentum = wheel.momentum_phase.is_some();
+                if is_momentum && self.momentum_stopped {
+                    ctx.set_handled();
+                } else if self.scroll(wheel.delta, size) {
                     ctx.request_paint();
                     ctx.set_handled();
                     self.reset_scrollbar_fade(ctx, &env);
+                    if is_momentum && self.is_at_edge(wheel.delta, size) {
+                        self.momentum_stopped = true;
+                    }
+                } else if is_momentum {
+                    // the content is already at its edge
+                    self.momentum_stopped = true;
+                    ctx.set_handled();
+                } else if !self
+                    .chaining
+                    .unwrap_or_else(|| env.get(theme::SCROLL_CHAINING))
+                {
+                    // keep the wheel from an enclosing scroll
+                    ctx.set_handled();
                 }
             }
         }
+
+        // the content is placed again, so that what scrolled under the mouse
+        // becomes hot
+        if self.scroll_offset != old_offset {
+            ctx.request_layout();
+        }
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
@@ -419,11 +588,21 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
         bc.debug_check("Scroll");
 
         let child_bc = BoxConstraints::new(Size::ZERO, self.direction.max_size(bc));
+        // the child's visible region is our viewport
+        let visible = ctx.visible;
+        let viewport = Rect::from_origin_size(Point::ORIGIN, bc.max()).intersect(visible);
+        ctx.visible = viewport + self.scroll_offset;
         let size = self.child.layout(ctx, &child_bc, data, env);
+        ctx.visible = visible;
         self.child_size = size;
         self.child
-            .set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+            .set_layout_rect(ctx, data, env, Rect::from_origin_size(Point::ORIGIN, size));
         let self_size = bc.constrain(self.child_size);
+        // right to left, the child starts scrolled to its leading edge
+        if !self.placed && LayoutDirection::from_env(env).is_rtl() {
+            let _ = self.scroll(Vec2::new(INFINITY, 0.0), self_size);
+        }
+        self.placed = true;
         let _ = self.scroll(Vec2::new(0.0, 0.0), self_size);
         self_size
     }
@@ -437,7 +616,9 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
         ctx.clip(viewport);
         ctx.transform(Affine::translate(-self.scroll_offset));
 
-        let visible = viewport.with_origin(self.scroll_offset.to_point());
+        let mut visible = ctx.region().clone();
+        visible.intersect_with(viewport);
+        let visible = visible.translated(self.scroll_offset);
         ctx.with_child_ctx(visible, |ctx| self.child.paint(ctx, data, env));
 
         self.draw_bars(ctx, viewport, env);
@@ -447,3 +628,300 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Controller, Flex, SizedBox, WidgetExt};
+    use crate::{KeyModifiers, WheelEvent, WidgetId};
+
+    #[test]
+    fn thumb_is_proportional() {
+        // a quarter of the content is visible
+        assert_eq!(thumb_range(100., 400., 0., 96., 8.), (0., 24.));
+        assert_eq!(thumb_range(100., 400., 150., 96., 8.), (36., 60.));
+        assert_eq!(thumb_range(100., 400., 300., 96., 8.), (72., 96.));
+        // out of range offsets stay on the track
+        assert_eq!(thumb_range(100., 400., 500., 96., 8.), (72., 96.));
+        // the thumb never gets shorter than the minimum, or longer than the track
+        assert_eq!(thumb_range(100., 10_000., 0., 96., 8.), (0., 8.));
+        assert_eq!(thumb_range(100., 100., 0., 96., 8.), (0., 96.));
+    }
+
+    #[test]
+    fn thumb_offset_round_trips() {
+        for &offset in &[0., 10., 150., 299., 300.] {
+            let (start, _) = thumb_range(100., 400., offset, 96., 8.);
+            let back = offset_for_thumb(100., 400., start, 96., 8.);
+            assert!((back - offset).abs() < 1e-9, "{} != {}", back, offset);
+        }
+        // dragging past either end clamps to the content's edges
+        assert_eq!(offset_for_thumb(100., 400., -20., 96., 8.), 0.);
+        assert_eq!(offset_for_thumb(100., 400., 500., 96., 8.), 300.);
+        // with no room to move, the offset is always zero
+        assert_eq!(offset_for_thumb(100., 100., 50., 96., 8.), 0.);
+    }
+
+    /// Records a `Scroll`'s offset after each event.
+    struct ScrollOffset(Rc<Cell<f64>>);
+
+    impl<W: Widget<()>> Controller<(), Scroll<(), W>> for ScrollOffset {
+        fn event(
+            &mut self,
+            child: &mut Scroll<(), W>,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut (),
+            env: &Env,
+        ) {
+            child.event(ctx, event, data, env);
+            self.0.set(child.offset().y);
+        }
+    }
+
+    /// A page with a scroll inside of it, at y = 300..400 in the window.
+    fn nested_scrolls(
+        inner: Scroll<(), SizedBox<()>>,
+        inner_offset: &Rc<Cell<f64>>,
+        outer_offset: &Rc<Cell<f64>>,
+    ) -> impl Widget<()> {
+        let page = Flex::column()
+            .with_child(SizedBox::empty().height(300.), 0.0)
+            .with_child(
+                inner
+                    .controller(ScrollOffset(inner_offset.clone()))
+                    .fix_height(100.),
+                0.0,
+            )
+            .with_child(SizedBox::empty().height(600.), 0.0);
+        Scroll::new(page)
+            .vertical()
+            .controller(ScrollOffset(outer_offset.clone()))
+    }
+
+    fn wheel_over_inner(harness: &mut Harness<()>, delta: f64) {
+        let pos = Point::new(50., 350.);
+        harness.event(Event::MouseMoved(make_mouse(pos)));
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., delta),
+            mods: KeyModifiers::default(),
+            window_anchor: pos,
+            local_anchor: pos,
+            phase: ScrollPhase::Changed,
+            momentum_phase: None,
+        }));
+    }
+
+    #[test]
+    fn nested_scroll_chaining() {
+        // the inner content can scroll by 900
+        let inner = || Scroll::new(SizedBox::empty().width(100.).height(1000.)).vertical();
+        let inner_offset = Rc::new(Cell::new(0.));
+        let outer_offset = Rc::new(Cell::new(0.));
+
+        let widget = nested_scrolls(inner(), &inner_offset, &outer_offset);
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            wheel_over_inner(harness, 1000.);
+            assert_eq!(inner_offset.get(), 900.);
+            assert_eq!(outer_offset.get(), 0.);
+
+            // the inner scroll is at its end, so the outer one scrolls
+            wheel_over_inner(harness, 50.);
+            assert_eq!(inner_offset.get(), 900.);
+            assert_eq!(outer_offset.get(), 50.);
+        });
+
+        // the inner scroll traps the wheel
+        let widget = nested_scrolls(inner().scroll_chaining(false), &inner_offset, &outer_offset);
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            wheel_over_inner(harness, 1000.);
+            wheel_over_inner(harness, 50.);
+            assert_eq!(inner_offset.get(), 900.);
+            assert_eq!(outer_offset.get(), 0.);
+        });
+
+        // the env turns chaining off, and the inner scroll turns it back on
+        let trapping = nested_scrolls(inner(), &inner_offset, &outer_offset)
+            .env_scope(|env, _| env.set(theme::SCROLL_CHAINING, false));
+        let chaining = nested_scrolls(inner().scroll_chaining(true), &inner_offset, &outer_offset)
+            .env_scope(|env, _| env.set(theme::SCROLL_CHAINING, false));
+        for (widget, outer_end) in vec![(trapping.boxed(), 0.), (chaining.boxed(), 50.)] {
+            Harness::create((), widget, |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+
+                wheel_over_inner(harness, 1000.);
+                wheel_over_inner(harness, 50.);
+                assert_eq!(inner_offset.get(), 900.);
+                assert_eq!(outer_offset.get(), outer_end);
+            });
+        }
+    }
+
+    #[test]
+    fn scrollbar_drag() {
+        let clicks = Rc::new(RefCell::new(Vec::new()));
+        let painted = Rc::new(Cell::new(Rect::ZERO));
+
+        let (clicks_2, painted_2) = (clicks.clone(), painted.clone());
+        let content = ModularWidget::new(())
+            .event_fn(move |_, _, event, _, _| {
+                if let Event::MouseDown(mouse) = event {
+                    clicks_2.borrow_mut().push(mouse.pos);
+                }
+            })
+            .layout_fn(|_, _, _, _, _| Size::new(400., 1600.))
+            .paint_fn(move |_, ctx, _, _| painted_2.set(ctx.region().to_rect()));
+
+        Harness::create((), Scroll::new(content).vertical(), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            // a quarter of the content is visible, so the thumb is a quarter of
+            // the track, which is the height less the padding at each end:
+            // it runs from (390, 2) to (398, 101).
+            let grab = Point::new(394., 50.);
+            harness.event(Event::MouseMoved(make_mouse(grab)));
+            harness.event(Event::MouseDown(make_mouse(grab)));
+            assert!(clicks.borrow().is_empty());
+
+            // moving the thumb by a third of its free track scrolls by a third
+            harness.event(Event::MouseMoved(make_mouse(Point::new(394., 149.))));
+            harness.paint();
+            assert_eq!(painted.get().origin(), Point::new(0., 400.));
+
+            // dragging past the end stops at the end of the content
+            harness.event(Event::MouseMoved(make_mouse(Point::new(394., 1000.))));
+            harness.paint();
+            assert_eq!(painted.get().origin(), Point::new(0., 1200.));
+            harness.event(Event::MouseUp(make_mouse(Point::new(394., 1000.))));
+
+            // a click away from the bar goes to the child, in its own coordinates
+            click(harness, Point::new(100., 100.));
+            assert_eq!(*clicks.borrow(), vec![Point::new(100., 1300.)]);
+        });
+    }
+
+    /// A wheel event in a trackpad's gesture, or in the momentum after it.
+    type PhasedWheel = (f64, ScrollPhase, Option<ScrollPhase>);
+
+    /// Send `wheels` over the window, returning the vertical offset of
+    /// `content` after each.
+    fn scroll_trajectory(
+        harness: &mut Harness<()>,
+        content: WidgetId,
+        wheels: &[PhasedWheel],
+    ) -> Vec<f64> {
+        let pos = Point::new(50., 50.);
+        harness.event(Event::MouseMoved(make_mouse(pos)));
+        let mut offsets = Vec::new();
+        for &(dy, phase, momentum_phase) in wheels {
+            harness.event(Event::Wheel(WheelEvent {
+                delta: Vec2::new(0., dy),
+                mods: KeyModifiers::default(),
+                window_anchor: pos,
+                local_anchor: pos,
+                phase,
+                momentum_phase,
+            }));
+            offsets.push(harness.get_state(content).viewport_offset.y);
+        }
+        offsets
+    }
+
+    /// Scroll 1000 pixels of content in the 400 pixel window.
+    fn tall_scroll(f: impl FnOnce(&mut Harness<()>, WidgetId)) {
+        let content = WidgetId::next();
+        let scroll = Scroll::new(SizedBox::empty().height(1000.).with_id(content)).vertical();
+        let mut f = Some(f);
+        Harness::create((), scroll, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            if let Some(f) = f.take() {
+                f(harness, content);
+            }
+        });
+    }
+
+    #[test]
+    fn scroll_momentum_follows_the_gesture() {
+        use ScrollPhase::{Changed, Ended, Started};
+        tall_scroll(|harness, content| {
+            let wheels = [
+                (0., Started, None),
+                (100., Changed, None),
+                (100., Changed, None),
+                (0., Ended, None),
+                (50., Ended, Some(Started)),
+                (40., Ended, Some(Changed)),
+                (30., Ended, Some(Changed)),
+                (0., Ended, Some(Ended)),
+            ];
+            assert_eq!(
+                scroll_trajectory(harness, content, &wheels),
+                vec![0., 100., 200., 200., 250., 290., 320., 320.]
+            );
+        });
+    }
+
+    #[test]
+    fn scroll_momentum_stops_on_a_click() {
+        use ScrollPhase::{Changed, Ended, Started};
+        tall_scroll(|harness, content| {
+            let wheels = [
+                (100., Changed, None),
+                (0., Ended, None),
+                (50., Ended, Some(Started)),
+            ];
+            assert_eq!(
+                scroll_trajectory(harness, content, &wheels),
+                vec![100., 100., 150.]
+            );
+
+            click(harness, Point::new(50., 50.));
+            // the rest of the momentum is ignored; the next gesture scrolls, and
+            // so does its momentum
+            let wheels = [
+                (40., Ended, Some(Changed)),
+                (30., Ended, Some(Changed)),
+                (0., Started, None),
+                (20., Changed, None),
+                (10., Ended, Some(Started)),
+            ];
+            assert_eq!(
+                scroll_trajectory(harness, content, &wheels),
+                vec![150., 150., 150., 170., 180.]
+            );
+        });
+    }
+
+    #[test]
+    fn scroll_momentum_stops_at_the_edge() {
+        use ScrollPhase::{Changed, Ended, Started};
+        tall_scroll(|harness, content| {
+            let wheels = [
+                (550., Changed, None),
+                (30., Ended, Some(Started)),
+                // this one reaches the end, at 600
+                (30., Ended, Some(Changed)),
+                (-10., Ended, Some(Changed)),
+                (-10., Started, None),
+            ];
+            assert_eq!(
+                scroll_trajectory(harness, content, &wheels),
+                vec![550., 580., 600., 600., 590.]
+            );
+        });
+    }
+}
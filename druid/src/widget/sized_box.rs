@@ -70,30 +70,53 @@ impl<T> SizedBox<T> {
 
     /// Expand container to fit the parent.
     /// It is equivalent to setting width and height to Infinity.
+    ///
+    /// On an axis where the parent's constraints are unbounded, such as
+    /// the scrolling axis of a [`Scroll`], there is nothing to fill, and
+    /// the container sizes itself to its child instead.
+    ///
+    /// [`Scroll`]: struct.Scroll.html
     pub fn expand(mut self) -> Self {
         self.width = Some(INFINITY);
         self.height = Some(INFINITY);
         self
     }
 
+    /// Expand the container's width to fit the parent, leaving the height
+    /// to the child.
+    ///
+    /// See [`expand`] for how unbounded constraints are handled.
+    ///
+    /// [`expand`]: #method.expand
+    pub fn expand_width(mut self) -> Self {
+        self.width = Some(INFINITY);
+        self
+    }
+
+    /// Expand the container's height to fit the parent, leaving the width
+    /// to the child.
+    ///
+    /// See [`expand`] for how unbounded constraints are handled.
+    ///
+    /// [`expand`]: #method.expand
+    pub fn expand_height(mut self) -> Self {
+        self.height = Some(INFINITY);
+        self
+    }
+
     fn child_constraints(&self, bc: &BoxConstraints) -> BoxConstraints {
         // if we don't have a width/height, we don't change that axis.
-        // if we have a width/height, we clamp it on that axis.
-        let (min_width, max_width) = match self.width {
-            Some(width) => {
-                let w = width.max(bc.min().width).min(bc.max().width);
-                (w, w)
-            }
-            None => (bc.min().width, bc.max().width),
-        };
-
-        let (min_height, max_height) = match self.height {
-            Some(height) => {
-                let h = height.max(bc.min().height).min(bc.max().height);
-                (h, h)
+        // if we have a width/height, we clamp it on that axis; if that
+        // leaves it infinite, we don't change that axis either.
+        let axis = |value: Option<f64>, min: f64, max: f64| match value {
+            Some(value) if value.max(min).min(max).is_finite() => {
+                let value = value.max(min).min(max);
+                (value, value)
             }
-            None => (bc.min().height, bc.max().height),
+            _ => (min, max),
         };
+        let (min_width, max_width) = axis(self.width, bc.min().width, bc.max().width);
+        let (min_height, max_height) = axis(self.height, bc.min().height, bc.max().height);
 
         BoxConstraints::new(
             Size::new(min_width, min_height),
@@ -132,7 +155,10 @@ impl<T: Data> Widget<T> for SizedBox<T> {
         let child_bc = self.child_constraints(bc);
         match self.inner.as_mut() {
             Some(inner) => inner.layout(ctx, &child_bc, data, env),
-            None => bc.constrain((self.width.unwrap_or(0.0), self.height.unwrap_or(0.0))),
+            None => child_bc.constrain((
+                self.width.filter(|w| w.is_finite()).unwrap_or(0.0),
+                self.height.filter(|h| h.is_finite()).unwrap_or(0.0),
+            )),
         }
     }
 
@@ -168,4 +194,19 @@ mod tests {
         assert_eq!(child_bc.min(), Size::new(0., 200.,));
         assert_eq!(child_bc.max(), Size::new(400., 200.,));
     }
+
+    #[test]
+    fn expand_unbounded() {
+        let expand = SizedBox::<()>::new(Label::new("hello!")).expand();
+        let bc = BoxConstraints::new(Size::ZERO, Size::new(400., INFINITY));
+        let child_bc = expand.child_constraints(&bc);
+        assert_eq!(child_bc.min(), Size::new(400., 0.));
+        assert_eq!(child_bc.max(), Size::new(400., INFINITY));
+
+        let expand = SizedBox::<()>::new(Label::new("hello!")).expand_height();
+        let bc = BoxConstraints::tight(Size::new(400., 300.)).loosen();
+        let child_bc = expand.child_constraints(&bc);
+        assert_eq!(child_bc.min(), Size::new(0., 300.));
+        assert_eq!(child_bc.max(), Size::new(400., 300.));
+    }
 }
@@ -117,6 +117,11 @@ impl Widget<f64> for Slider {
     fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &f64, _env: &Env) {}
 
     fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &f64, _data: &f64, _env: &Env) {
+        if ctx.env_key_changed(theme::BASIC_WIDGET_HEIGHT)
+            || ctx.env_key_changed(theme::WIDE_WIDGET_WIDTH)
+        {
+            ctx.request_layout();
+        }
         ctx.request_paint();
     }
 
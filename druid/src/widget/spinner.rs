@@ -0,0 +1,235 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An animated spinner widget.
+
+use std::f64::consts::PI;
+
+use crate::kurbo::{Line, Point, Size, Vec2};
+use crate::piet::{Color, RenderContext};
+use crate::theme;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, UpdateCtx, Widget,
+};
+
+/// The number of spokes in the spinner.
+const SPOKE_COUNT: usize = 12;
+/// The number of turns per second.
+const TURNS_PER_SECOND: f64 = 1.0;
+
+/// An animated spinner widget, for showing an indeterminate level of progress.
+///
+/// The spinner only animates while it is being painted: if it goes a frame
+/// without being painted, such as when it is in the hidden branch of an
+/// [`Either`], it stops requesting animation frames. It starts again the
+/// next time it receives an `update`.
+///
+/// If [`theme::ANIMATIONS_ENABLED`] is `false`, the spinner is painted at rest.
+///
+/// [`Either`]: struct.Either.html
+/// [`theme::ANIMATIONS_ENABLED`]: ../theme/constant.ANIMATIONS_ENABLED.html
+pub struct Spinner {
+    /// The position in the current turn, in `0.0..1.0`.
+    t: f64,
+    color: KeyOrValue<Color>,
+    animating: bool,
+    painted: bool,
+}
+
+impl Spinner {
+    /// Create a spinner widget.
+    pub fn new() -> Spinner {
+        Spinner::default()
+    }
+
+    /// Builder-style method for setting the spinner's color.
+    ///
+    /// The argument can be either a `Color` or a [`Key<Color>`].
+    ///
+    /// [`Key<Color>`]: ../struct.Key.html
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Set the spinner's color.
+    ///
+    /// The argument can be either a `Color` or a [`Key<Color>`].
+    ///
+    /// [`Key<Color>`]: ../struct.Key.html
+    pub fn set_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
+        self.color = color.into();
+    }
+
+    /// The angle of the leading spoke, in radians.
+    #[cfg(test)]
+    pub(crate) fn angle(&self) -> f64 {
+        self.t * 2.0 * PI
+    }
+
+    fn start(&mut self, env: &Env) -> bool {
+        if self.animating || !env.get(theme::ANIMATIONS_ENABLED) {
+            return false;
+        }
+        self.animating = true;
+        // the first frame comes before the first paint
+        self.painted = true;
+        true
+    }
+}
+
+impl Default for Spinner {
+    fn default() -> Self {
+        Spinner {
+            t: 0.0,
+            color: theme::LABEL_COLOR.into(),
+            animating: false,
+            painted: false,
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for Spinner {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut T, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                if self.start(env) {
+                    ctx.request_anim_frame();
+                }
+            }
+            LifeCycle::AnimFrame(interval) if self.animating => {
+                if !self.painted || !env.get(theme::ANIMATIONS_ENABLED) {
+                    self.animating = false;
+                    return;
+                }
+                self.painted = false;
+                self.t += (*interval as f64) * 1e-9 * TURNS_PER_SECOND;
+                self.t = self.t.fract();
+                ctx.request_anim_frame();
+            }
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, _data: &T, env: &Env) {
+        if self.start(env) {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &T,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("Spinner");
+        let size = env.get(theme::BASIC_WIDGET_HEIGHT);
+        bc.constrain(Size::new(size, size))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, env: &Env) {
+        self.painted = true;
+
+        let size = ctx.size();
+        let center = Point::new(size.width / 2.0, size.height / 2.0);
+        let radius = size.width.min(size.height) / 2.0;
+        let color = self.color.resolve(env);
+        // the spoke at the current position is the brightest
+        let lead = (self.t * SPOKE_COUNT as f64) as usize;
+
+        for spoke in 0..SPOKE_COUNT {
+            let angle = (spoke as f64 / SPOKE_COUNT as f64) * 2.0 * PI;
+            let direction = Vec2::new(angle.cos(), angle.sin());
+            let age = (lead + SPOKE_COUNT - spoke) % SPOKE_COUNT;
+            let alpha = 1.0 - age as f64 / SPOKE_COUNT as f64;
+            let line = Line::new(
+                center + direction * (radius * 0.5),
+                center + direction * (radius * 0.9),
+            );
+            ctx.stroke(line, &color.clone().with_alpha(alpha), radius * 0.15);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::widget::{Controller, Padding, WidgetExt};
+    use crate::WidgetId;
+
+    #[test]
+    fn spinner_advances_while_painted() {
+        use std::f64::consts::PI;
+
+        /// Records the spinner's angle after each lifecycle event.
+        struct Angle(Rc<Cell<f64>>);
+
+        impl Controller<(), Spinner> for Angle {
+            fn lifecycle(
+                &mut self,
+                child: &mut Spinner,
+                ctx: &mut LifeCycleCtx,
+                event: &LifeCycle,
+                data: &(),
+                env: &Env,
+            ) {
+                child.lifecycle(ctx, event, data, env);
+                self.0.set(child.angle());
+            }
+        }
+
+        const QUARTER_SECOND: u64 = 250_000_000;
+
+        let angle = Rc::new(Cell::new(0.0));
+        let id = WidgetId::next();
+        let widget = Spinner::new().controller(Angle(angle.clone())).with_id(id);
+
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            assert!(harness.get_state(id).request_anim);
+
+            // one full frame: the first `AnimFrame` has no interval
+            harness.paint();
+            assert_eq!(angle.get(), 0.0);
+            assert!(harness.get_state(id).request_anim);
+
+            harness.anim_frame(QUARTER_SECOND);
+            assert!((angle.get() - PI / 2.0).abs() < 1e-9);
+            assert!(harness.get_state(id).request_anim);
+
+            // not painted since the last frame; the spinner is hidden, so it stops
+            harness.anim_frame(QUARTER_SECOND);
+            assert!((angle.get() - PI / 2.0).abs() < 1e-9);
+            assert!(!harness.get_state(id).request_anim);
+        });
+
+        // without animations, the spinner never asks for a frame
+        let widget = Spinner::new()
+            .env_scope(|env, _| env.set(theme::ANIMATIONS_ENABLED, false))
+            .with_id(id);
+        Harness::create((), Padding::new(0., widget), |harness| {
+            harness.send_initial_events();
+            assert!(!harness.get_state(id).request_anim);
+        });
+    }
+}
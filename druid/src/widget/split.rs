@@ -17,18 +17,38 @@
 use crate::kurbo::{Line, Point, Rect, Size};
 use crate::widget::flex::Axis;
 use crate::{
-    theme, BoxConstraints, Color, Cursor, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, RenderContext, UpdateCtx, Widget, WidgetPod,
+    theme, BoxConstraints, Code, Color, Cursor, Data, Env, Event, EventCtx, KeyEvent, LayoutCtx,
+    LayoutDirection, LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, UpdateCtx, Widget, WidgetId,
+    WidgetPod,
 };
 
+/// How far the arrow keys move the splitter, in pixels.
+const KEYBOARD_STEP: f64 = 8.0;
+/// How far the arrow keys move the splitter with shift held, in pixels.
+const KEYBOARD_LARGE_STEP: f64 = 64.0;
+
 ///A container containing two other widgets, splitting the area either horizontally or vertically.
+///
+/// If the children overlap, the second child is painted above the first and
+/// gets mouse events first.
+///
+/// When the [`LayoutDirection`] is right to left, the first child of a
+/// vertical split is on the right, and the split point is measured from
+/// the right.
+///
+/// [`LayoutDirection`]: ../enum.LayoutDirection.html
 pub struct Split<T> {
     split_direction: Axis,
+    /// Whether the split runs from the right, as a vertical split does
+    /// right to left; this is from the last layout.
+    mirrored: bool,
     solid: bool,
     draggable: bool,
     min_size: f64,
     split_point: f64,
     splitter_size: f64,
+    /// The id of the splitter bar, which is in the focus chain when draggable.
+    bar_id: WidgetId,
     child1: WidgetPod<T, Box<dyn Widget<T>>>,
     child2: WidgetPod<T, Box<dyn Widget<T>>>,
 }
@@ -42,11 +62,13 @@ impl<T> Split<T> {
     ) -> Self {
         Split {
             split_direction,
+            mirrored: false,
             min_size: 0.0,
             solid: false,
             split_point: 0.5,
             splitter_size: 10.0,
             draggable: false,
+            bar_id: WidgetId::next(),
             child1: WidgetPod::new(child1).boxed(),
             child2: WidgetPod::new(child2).boxed(),
         }
@@ -89,6 +111,9 @@ impl<T> Split<T> {
         self
     }
     /// Set whether the splitter's split point can be changed by dragging.
+    ///
+    /// A draggable splitter can also take focus, and then be moved with the
+    /// arrow keys, or to either limit with Home and End.
     pub fn draggable(mut self, draggable: bool) -> Self {
         self.draggable = draggable;
         self
@@ -100,11 +125,25 @@ impl<T> Split<T> {
         self.solid = solid;
         self
     }
+    /// Whether the first child is painted above the second, which is only
+    /// the case if it has a higher z-index.
+    fn first_child_on_top(&self) -> bool {
+        self.child1.z_index() > self.child2.z_index()
+    }
+    /// The distance of `x` from the edge that the split runs from.
+    fn from_leading(&self, size: Size, x: f64) -> f64 {
+        if self.mirrored {
+            size.width - x
+        } else {
+            x
+        }
+    }
     fn splitter_hit_test(&self, size: Size, mouse_pos: Point) -> bool {
         match self.split_direction {
             Axis::Vertical => {
                 let center = size.width * self.split_point;
-                (center - mouse_pos.x).abs() < self.splitter_size.min(5.0) / 2.0
+                (center - self.from_leading(size, mouse_pos.x)).abs()
+                    < self.splitter_size.min(5.0) / 2.0
             }
             Axis::Horizontal => {
                 let center = size.height * self.split_point;
@@ -130,17 +169,62 @@ impl<T> Split<T> {
     }
 
     fn update_splitter(&mut self, size: Size, mouse_pos: Point) {
-        self.split_point = match self.split_direction {
-            Axis::Vertical => {
-                let (min_limit, max_limit) = self.calculate_limits(size);
-                clamp(mouse_pos.x, min_limit, max_limit) / size.width
-            }
-            Axis::Horizontal => {
-                let (min_limit, max_limit) = self.calculate_limits(size);
-                clamp(mouse_pos.y, min_limit, max_limit) / size.height
-            }
+        let pos = match self.split_direction {
+            Axis::Vertical => self.from_leading(size, mouse_pos.x),
+            Axis::Horizontal => mouse_pos.y,
+        };
+        self.set_splitter_pos(size, pos);
+    }
+
+    /// Move the splitter to `pos`, in pixels along the split direction,
+    /// keeping it within the limits.
+    fn set_splitter_pos(&mut self, size: Size, pos: f64) {
+        let (min_limit, max_limit) = self.calculate_limits(size);
+        self.split_point = clamp(pos, min_limit, max_limit) / self.split_direction.minor(size);
+    }
+
+    /// Handle a key press while the splitter bar has focus.
+    ///
+    /// Returns `true` if the key moved the splitter.
+    fn handle_key(&mut self, size: Size, key: &KeyEvent) -> bool {
+        let (min_limit, max_limit) = self.calculate_limits(size);
+        let pos = self.split_point * self.split_direction.minor(size);
+        let step = if key.mods.shift {
+            KEYBOARD_LARGE_STEP
+        } else {
+            KEYBOARD_STEP
+        };
+        // the arrows move it the way they point, whichever way it runs
+        let code = match key.code {
+            Code::ArrowLeft if self.mirrored => Code::ArrowRight,
+            Code::ArrowRight if self.mirrored => Code::ArrowLeft,
+            code => code,
+        };
+        let new_pos = match (self.split_direction, code) {
+            (Axis::Vertical, Code::ArrowLeft) | (Axis::Horizontal, Code::ArrowUp) => pos - step,
+            (Axis::Vertical, Code::ArrowRight) | (Axis::Horizontal, Code::ArrowDown) => pos + step,
+            (_, Code::Home) => min_limit,
+            (_, Code::End) => max_limit,
+            _ => return false,
+        };
+        self.set_splitter_pos(size, new_pos);
+        true
+    }
+
+    /// The area covered by the splitter bar.
+    fn bar_rect(&mut self, ctx: &PaintCtx) -> Rect {
+        let size = ctx.size();
+        let (edge1, edge2) = self.get_edges(ctx);
+        match self.split_direction {
+            Axis::Vertical => Rect::new(edge1, 0.0, edge2, size.height),
+            Axis::Horizontal => Rect::new(0.0, edge1, size.width, edge2),
         }
     }
+
+    fn paint_focus(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let rect = self.bar_rect(ctx).inset(-1.0);
+        ctx.stroke(rect, &env.get(theme::PRIMARY_LIGHT), 1.0);
+    }
     fn get_edges(&mut self, ctx: &PaintCtx) -> (f64, f64) {
         let size = ctx.size();
         match self.split_direction {
@@ -148,7 +232,11 @@ impl<T> Split<T> {
                 let reduced_width = size.width - self.splitter_size;
                 let edge1 = reduced_width * self.split_point;
                 let edge2 = edge1 + self.splitter_size;
-                (edge1, edge2)
+                if self.mirrored {
+                    (size.width - edge2, size.width - edge1)
+                } else {
+                    (edge1, edge2)
+                }
             }
             Axis::Horizontal => {
                 let reduced_height = size.height - self.splitter_size;
@@ -236,7 +324,15 @@ impl<T: Data> Widget<T> for Split<T> {
                 Event::MouseDown(mouse) => {
                     if mouse.button.is_left() && self.splitter_hit_test(ctx.size(), mouse.pos) {
                         ctx.set_active(true);
+                        ctx.request_part_focus(self.bar_id);
+                        ctx.set_handled();
+                    }
+                }
+                Event::KeyDown(key) if ctx.part_has_focus(self.bar_id) => {
+                    if self.handle_key(ctx.size(), key) {
+                        ctx.request_layout();
                         ctx.set_handled();
+                        return;
                     }
                 }
                 Event::MouseUp(mouse) => {
@@ -259,21 +355,42 @@ impl<T: Data> Widget<T> for Split<T> {
                             Axis::Horizontal => ctx.set_cursor(&Cursor::ResizeUpDown),
                             Axis::Vertical => ctx.set_cursor(&Cursor::ResizeLeftRight),
                         };
+                    } else {
+                        ctx.clear_cursor();
                     }
                 }
                 _ => {}
             }
         }
-        if !self.child1.is_active() {
-            self.child1.event(ctx, event, data, env);
+        // mouse events go to the topmost child first
+        let (first, second) = if event.is_pointer_event() && !self.first_child_on_top() {
+            (&mut self.child2, &mut self.child1)
+        } else {
+            (&mut self.child1, &mut self.child2)
+        };
+        if !first.is_active() {
+            first.event(ctx, event, data, env);
         }
-        if !self.child2.is_active() {
-            self.child2.event(ctx, event, data, env);
+        if !second.is_active() {
+            second.event(ctx, event, data, env);
         }
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
         self.child1.lifecycle(ctx, event, data, env);
+        match event {
+            // registered between the children, so it is between them in the focus chain;
+            // the chain is built again with `RouteWidgetAdded` when the children change
+            LifeCycle::WidgetAdded | LifeCycle::RouteWidgetAdded if self.draggable => {
+                ctx.register_focusable_part(self.bar_id)
+            }
+            LifeCycle::RouteFocusChanged { old, new } => {
+                if *old == Some(self.bar_id) || *new == Some(self.bar_id) {
+                    ctx.request_paint();
+                }
+            }
+            _ => (),
+        }
         self.child2.lifecycle(ctx, event, data, env);
     }
 
@@ -285,7 +402,14 @@ impl<T: Data> Widget<T> for Split<T> {
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
         bc.debug_check("Split");
 
-        let mut my_size = bc.max();
+        let direction = LayoutDirection::from_env(env);
+        self.mirrored = match self.split_direction {
+            Axis::Vertical => direction.is_rtl(),
+            Axis::Horizontal => false,
+        };
+
+        // with no end to the space to split, it splits the least it can have
+        let mut my_size = bc.max_or(bc.min());
 
         let reduced_width = my_size.width - self.splitter_size;
         let reduced_height = my_size.height - self.splitter_size;
@@ -297,31 +421,19 @@ impl<T: Data> Widget<T> for Split<T> {
                 let child1_width = (reduced_width * self.split_point).max(0.0);
                 let child2_width = (reduced_width - child1_width).max(0.0);
                 (
-                    BoxConstraints::new(
-                        Size::new(child1_width, bc.min().height),
-                        Size::new(child1_width, bc.max().height),
-                    ),
-                    BoxConstraints::new(
-                        Size::new(child2_width, bc.min().height),
-                        Size::new(child2_width, bc.max().height),
-                    ),
+                    bc.with_width_range(child1_width, child1_width),
+                    bc.with_width_range(child2_width, child2_width),
                 )
             }
             Axis::Horizontal => {
-                if !bc.is_width_bounded() {
+                if !bc.is_height_bounded() {
                     log::warn!("A Split widget was given an unbounded height to split.")
                 }
                 let child1_height = (reduced_height * self.split_point).max(0.0);
                 let child2_height = (reduced_height - child1_height).max(0.0);
                 (
-                    BoxConstraints::new(
-                        Size::new(bc.min().width, child1_height),
-                        Size::new(bc.max().width, child1_height),
-                    ),
-                    BoxConstraints::new(
-                        Size::new(bc.min().width, child2_height),
-                        Size::new(bc.max().width, child2_height),
-                    ),
+                    bc.with_height_range(child1_height, child1_height),
+                    bc.with_height_range(child2_height, child2_height),
                 )
             }
         };
@@ -352,8 +464,16 @@ impl<T: Data> Widget<T> for Split<T> {
                 )
             }
         };
-        self.child1.set_layout_rect(child1_rect);
-        self.child2.set_layout_rect(child2_rect);
+        let (child1_rect, child2_rect) = if self.mirrored {
+            (
+                direction.mirror(child1_rect, my_size.width),
+                direction.mirror(child2_rect, my_size.width),
+            )
+        } else {
+            (child1_rect, child2_rect)
+        };
+        self.child1.set_layout_rect(ctx, data, env, child1_rect);
+        self.child2.set_layout_rect(ctx, data, env, child2_rect);
 
         let paint_rect = self.child1.paint_rect().union(self.child2.paint_rect());
         let insets = paint_rect - Rect::ZERO.with_size(my_size);
@@ -395,8 +515,16 @@ impl<T: Data> Widget<T> for Split<T> {
         } else {
             self.paint_stroked(ctx, env);
         }
-        self.child1.paint_with_offset(ctx, &data, env);
-        self.child2.paint_with_offset(ctx, &data, env);
+        if ctx.part_has_focus(self.bar_id) {
+            self.paint_focus(ctx, env);
+        }
+        let (bottom, top) = if self.first_child_on_top() {
+            (&mut self.child2, &mut self.child1)
+        } else {
+            (&mut self.child1, &mut self.child2)
+        };
+        bottom.paint_with_offset(ctx, &data, env);
+        top.paint_with_offset(ctx, &data, env);
     }
 }
 
@@ -411,3 +539,102 @@ fn clamp(mut x: f64, min: f64, max: f64) -> f64 {
     }
     x
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{SizedBox, WidgetExt};
+    use crate::KeyModifiers;
+
+    #[test]
+    fn split_bar_in_focus_chain_when_draggable() {
+        let (id_1, id_2) = widget_id2();
+        let widget = Split::vertical(
+            make_focusable(Default::default()).with_id(id_1),
+            make_focusable(Default::default()).with_id(id_2),
+        );
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            assert_eq!(harness.window().focus_chain(), &[id_1, id_2]);
+        });
+
+        let widget = Split::vertical(
+            make_focusable(Default::default()).with_id(id_1),
+            make_focusable(Default::default()).with_id(id_2),
+        )
+        .draggable(true);
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            let chain = harness.window().focus_chain().to_owned();
+            // the bar sits between the two children
+            assert_eq!(chain.len(), 3);
+            assert_eq!(chain[0], id_1);
+            assert_eq!(chain[2], id_2);
+            assert!(chain[1] != id_1 && chain[1] != id_2);
+        });
+    }
+
+    #[test]
+    fn split_keyboard_limits() {
+        fn key(shift: bool, code: Code) -> Event {
+            let mods = KeyModifiers {
+                shift,
+                ..Default::default()
+            };
+            Event::KeyDown(KeyEvent::for_test(mods, "", code))
+        }
+
+        /// The width of the left child, and so the position of the splitter.
+        fn left_width(harness: &mut Harness<()>, id: WidgetId) -> f64 {
+            harness.just_layout();
+            harness.get_state(id).layout_rect.width()
+        }
+
+        fn assert_near(left: f64, right: f64) {
+            assert!((left - right).abs() < 1e-9, "{} != {}", left, right);
+        }
+
+        let left = WidgetId::next();
+        let widget = Split::vertical(SizedBox::empty().with_id(left), SizedBox::empty())
+            .draggable(true)
+            .min_size(50.);
+
+        // the window is 400 wide and the bar is 10, so the children share 390
+        // pixels; the splitter can move between 50 and 350.
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            assert_near(left_width(harness, left), 195.);
+
+            // keys do nothing until the bar has focus
+            harness.event(key(false, Code::ArrowRight));
+            assert_near(left_width(harness, left), 195.);
+
+            harness.event(key(false, Code::Tab));
+            assert!(harness.window().focus.is_some());
+
+            // from 200 to 208
+            harness.event(key(false, Code::ArrowRight));
+            assert_near(left_width(harness, left), 390. * 208. / 400.);
+
+            // from 208 to 144
+            harness.event(key(true, Code::ArrowLeft));
+            assert_near(left_width(harness, left), 390. * 144. / 400.);
+
+            // the keys for the other direction do nothing
+            harness.event(key(false, Code::ArrowDown));
+            assert_near(left_width(harness, left), 390. * 144. / 400.);
+
+            harness.event(key(false, Code::End));
+            assert_near(left_width(harness, left), 390. * 350. / 400.);
+            harness.event(key(true, Code::ArrowRight));
+            assert_near(left_width(harness, left), 390. * 350. / 400.);
+
+            harness.event(key(false, Code::Home));
+            assert_near(left_width(harness, left), 390. * 50. / 400.);
+            harness.event(key(false, Code::ArrowLeft));
+            assert_near(left_width(harness, left), 390. * 50. / 400.);
+        });
+    }
+}
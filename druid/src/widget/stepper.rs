@@ -33,6 +33,10 @@ const STEPPER_REPEAT_DELAY: Duration = Duration::from_millis(500);
 const STEPPER_REPEAT: Duration = Duration::from_millis(200);
 
 /// A stepper widget for step-wise increasing and decreasing a value.
+///
+/// Pressing the upper half of the stepper increases the value, and pressing
+/// the lower half decreases it. While a button is held down the value keeps
+/// changing, until the mouse is released or leaves the stepper.
 pub struct Stepper {
     max: f64,
     min: f64,
@@ -45,6 +49,7 @@ pub struct Stepper {
 }
 
 impl Stepper {
+    /// Create a new stepper, with an unbounded range and a step of `1.0`.
     pub fn new() -> Self {
         Stepper {
             max: std::f64::MAX,
@@ -57,30 +62,74 @@ impl Stepper {
         }
     }
 
+    /// Builder-style method to set the stepper's minimum and maximum values.
+    pub fn with_range(mut self, min: f64, max: f64) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// Builder-style method to set the amount by which the value increases
+    /// or decreases.
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Builder-style method to set whether the value wraps around.
+    ///
+    /// If it does, increasing past the maximum value jumps to the minimum,
+    /// and decreasing past the minimum jumps to the maximum. Otherwise, the
+    /// value stops at the ends of the range.
+    pub fn with_wraparound(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
     /// Set the stepper's maximum value.
+    #[deprecated(since = "0.5.0", note = "use with_range instead")]
     pub fn max(mut self, max: f64) -> Self {
         self.max = max;
         self
     }
 
     /// Set the stepper's minimum value.
+    #[deprecated(since = "0.5.0", note = "use with_range instead")]
     pub fn min(mut self, min: f64) -> Self {
         self.min = min;
         self
     }
 
     /// Set the steppers amount by which the value increases or decreases.
+    #[deprecated(since = "0.5.0", note = "use with_step instead")]
     pub fn step(mut self, step: f64) -> Self {
         self.step = step;
         self
     }
 
     /// Set whether the stepper should wrap around the minimum/maximum values.
+    #[deprecated(since = "0.5.0", note = "use with_wraparound instead")]
     pub fn wrap(mut self, wrap: bool) -> Self {
         self.wrap = wrap;
         self
     }
 
+    /// The token of the timer for the next repeated change.
+    #[cfg(test)]
+    pub(crate) fn repeat_timer(&self) -> TimerToken {
+        self.timer_id
+    }
+
+    fn is_repeating(&self) -> bool {
+        self.increase_active || self.decrease_active
+    }
+
+    fn stop_repeating(&mut self) {
+        self.decrease_active = false;
+        self.increase_active = false;
+        self.timer_id = TimerToken::INVALID;
+    }
+
     fn change_value(&mut self, _ctx: &mut EventCtx, data: &mut f64, _env: &Env) {
         // increase/decrease value depending on which button is currently active
         let delta = if self.increase_active {
@@ -91,15 +140,14 @@ impl Stepper {
             0.0
         };
 
-        *data = (*data + delta).max(self.min).min(self.max);
-
-        if self.wrap {
-            if (*data - self.min).abs() < EPSILON {
-                *data = self.max
-            } else {
-                *data = self.min
-            }
-        }
+        let value = *data + delta;
+        *data = if self.wrap && value > self.max {
+            self.min
+        } else if self.wrap && value < self.min {
+            self.max
+        } else {
+            value.max(self.min).min(self.max)
+        };
     }
 }
 
@@ -182,7 +230,7 @@ impl Widget<f64> for Stepper {
     }
 
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut f64, env: &Env) {
-        let height = env.get(theme::BORDERED_WIDGET_HEIGHT);
+        let height = ctx.size().height;
 
         match event {
             Event::MouseDown(mouse) => {
@@ -203,14 +251,10 @@ impl Widget<f64> for Stepper {
             }
             Event::MouseUp(_) => {
                 ctx.set_active(false);
-
-                self.decrease_active = false;
-                self.increase_active = false;
-                self.timer_id = TimerToken::INVALID;
-
+                self.stop_repeating();
                 ctx.request_paint();
             }
-            Event::Timer(id) if *id == self.timer_id => {
+            Event::Timer(id) if *id == self.timer_id && self.is_repeating() => {
                 self.change_value(ctx, data, env);
                 let delay = Instant::now() + STEPPER_REPEAT;
                 self.timer_id = ctx.request_timer(delay);
@@ -219,11 +263,139 @@ impl Widget<f64> for Stepper {
         }
     }
 
-    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &f64, _env: &Env) {}
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &f64, _env: &Env) {
+        // leaving the stepper stops the repeat; it doesn't restart on return
+        if let LifeCycle::HotChanged(false) = event {
+            if self.is_repeating() {
+                self.stop_repeating();
+                ctx.request_paint();
+            }
+        }
+    }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &f64, data: &f64, _env: &Env) {
+        if ctx.env_key_changed(theme::BASIC_WIDGET_HEIGHT)
+            || ctx.env_key_changed(theme::BORDERED_WIDGET_HEIGHT)
+        {
+            ctx.request_layout();
+        }
         if (*data - old_data).abs() > EPSILON {
             ctx.request_paint();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Align, Controller, WidgetExt};
+    use crate::{Selector, Target};
+
+    /// Sends a `Stepper` its repeat timer when it gets `FIRE_TIMER`.
+    struct StepperTimer;
+
+    const FIRE_TIMER: Selector = Selector::new("druid-tests.fire-timer");
+
+    impl Controller<f64, Stepper> for StepperTimer {
+        fn event(
+            &mut self,
+            child: &mut Stepper,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut f64,
+            env: &Env,
+        ) {
+            match event {
+                Event::Command(cmd) if cmd.selector == FIRE_TIMER => {
+                    let timer = Event::Timer(child.repeat_timer());
+                    child.event(ctx, &timer, data, env)
+                }
+                _ => child.event(ctx, event, data, env),
+            }
+        }
+    }
+
+    /// The stepper is 18x24, so its buttons meet at y = 12.
+    const STEPPER_UP: Point = Point::new(9., 6.);
+
+    const STEPPER_DOWN: Point = Point::new(9., 18.);
+
+    fn stepper_press(harness: &mut Harness<f64>, pos: Point) {
+        harness.event(Event::MouseMoved(make_mouse(pos)));
+        harness.event(Event::MouseDown(make_mouse(pos)));
+    }
+
+    #[test]
+    fn stepper_repeats_while_held() {
+        let stepper = Stepper::new().with_range(0., 3.).controller(StepperTimer);
+        let widget = Align::new(UnitPoint::TOP_LEFT, stepper);
+
+        Harness::create(0., widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            stepper_press(harness, STEPPER_UP);
+            assert_eq!(*harness.data(), 1.);
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 2.);
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 3.);
+            // clamped at the maximum
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 3.);
+
+            // releasing stops the repeat
+            harness.event(Event::MouseUp(make_mouse(STEPPER_UP)));
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 3.);
+
+            stepper_press(harness, STEPPER_DOWN);
+            assert_eq!(*harness.data(), 2.);
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 1.);
+
+            // so does leaving, even without a release; coming back doesn't restart it
+            harness.event(Event::MouseMoved(make_mouse(Point::new(200., 200.))));
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 1.);
+            harness.event(Event::MouseMoved(make_mouse(STEPPER_DOWN)));
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 1.);
+            harness.event(Event::MouseUp(make_mouse(STEPPER_DOWN)));
+
+            // clamped at the minimum
+            stepper_press(harness, STEPPER_DOWN);
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 0.);
+        })
+    }
+
+    #[test]
+    fn stepper_wraparound() {
+        let stepper = Stepper::new()
+            .with_range(0., 2.)
+            .with_wraparound(true)
+            .controller(StepperTimer);
+        let widget = Align::new(UnitPoint::TOP_LEFT, stepper);
+
+        Harness::create(2., widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            stepper_press(harness, STEPPER_UP);
+            assert_eq!(*harness.data(), 0.);
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 1.);
+            harness.event(Event::MouseUp(make_mouse(STEPPER_UP)));
+
+            stepper_press(harness, STEPPER_DOWN);
+            assert_eq!(*harness.data(), 0.);
+            harness.submit_command(FIRE_TIMER, Target::Auto);
+            assert_eq!(*harness.data(), 2.);
+            harness.event(Event::MouseUp(make_mouse(STEPPER_DOWN)));
+        })
+    }
+}
@@ -131,8 +131,11 @@ impl Widget<bool> for Switch {
 
                 ctx.request_paint();
                 self.knob_dragged = false;
-                self.animation_in_progress = true;
-                ctx.request_anim_frame();
+                // without animations, paint puts the knob in its final position
+                if env.get(theme::ANIMATIONS_ENABLED) {
+                    self.animation_in_progress = true;
+                    ctx.request_anim_frame();
+                }
             }
             Event::MouseMoved(mouse) => {
                 if ctx.is_active() {
@@ -171,6 +174,9 @@ impl Widget<bool> for Switch {
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &bool, data: &bool, _env: &Env) {
+        if ctx.env_key_changed(theme::BORDERED_WIDGET_HEIGHT) {
+            ctx.request_layout();
+        }
         if old_data != data {
             ctx.request_paint();
         }
@@ -280,3 +286,32 @@ impl Widget<bool> for Switch {
         self.paint_labels(ctx, env, switch_width);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{EnvScope, Padding, WidgetExt};
+    use crate::WidgetId;
+
+    /// With `ANIMATIONS_ENABLED` false, a switch settles without animating.
+    #[test]
+    fn switch_respects_animations_enabled() {
+        for &animate in &[false, true] {
+            let id = WidgetId::next();
+            let widget = EnvScope::new(
+                move |env, _| env.set(theme::ANIMATIONS_ENABLED, animate),
+                Padding::new(0., Switch::new().with_id(id)),
+            );
+
+            Harness::create(false, widget, |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                click(harness, Point::new(10., 10.));
+                assert!(*harness.data());
+                assert_eq!(harness.get_state(id).request_anim, animate);
+            });
+        }
+    }
+}
@@ -0,0 +1,789 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A table of rows, under a header that sorts and resizes its columns.
+
+use std::cmp::Ordering;
+
+use crate::kurbo::{BezPath, Line, Point, Rect, Size, Vec2};
+use crate::widget::{Label, LabelText, ListIter, Scroll};
+use crate::{
+    theme, BoxConstraints, Cursor, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, RenderContext, UpdateCtx, Widget, WidgetPod,
+};
+
+const DEFAULT_COLUMN_WIDTH: f64 = 100.0;
+const DEFAULT_MIN_COLUMN_WIDTH: f64 = 20.0;
+/// The space around the text of a header.
+const HEADER_PADDING: f64 = 4.0;
+/// How far from the divider between two headers it can be dragged.
+const DIVIDER_SLOP: f64 = 3.0;
+/// The width of the arrow on the header of the sorted column.
+const SORT_ARROW_WIDTH: f64 = 8.0;
+
+type CellBuilder<R> = dyn Fn() -> Box<dyn Widget<R>>;
+type Comparator<R> = dyn Fn(&R, &R) -> Ordering;
+type Cell<R> = WidgetPod<R, Box<dyn Widget<R>>>;
+
+/// The direction that a [`Table`] is sorted in.
+///
+/// [`Table`]: struct.Table.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    /// The first row is the least.
+    Ascending,
+    /// The first row is the greatest.
+    Descending,
+}
+
+/// A column of a [`Table`].
+///
+/// [`Table`]: struct.Table.html
+pub struct Column<R> {
+    header: LabelText<()>,
+    cell: Box<CellBuilder<R>>,
+    width: f64,
+    min_width: f64,
+    compare: Option<Box<Comparator<R>>>,
+}
+
+/// A table, with a row for each item of its data, and a header above it.
+///
+/// Each [`Column`] has a header, and a widget in each row that is given the
+/// row's item as its data. The rows scroll in both directions; the header
+/// stays at the top, and scrolls sideways with them.
+///
+/// Clicking the header of a column that can be sorted sorts the rows by it,
+/// and clicking it again reverses them. Sorting only changes the order that
+/// the rows are shown in; the data is left as it is, and each widget keeps
+/// its item. Dragging the divider to the right of a header resizes its
+/// column.
+///
+/// The table should be given a bounded size.
+///
+/// [`Column`]: struct.Column.html
+pub struct Table<T, R> {
+    columns: Vec<ColumnHeader<R>>,
+    body: WidgetPod<T, Scroll<T, TableBody<R>>>,
+    sort: Option<(usize, SortDirection)>,
+    header_height: f64,
+    /// How far the body is scrolled sideways, as the header was laid out.
+    header_offset: f64,
+    /// The column whose header was pressed; it is sorted if the mouse is
+    /// released over it.
+    pressed: Option<usize>,
+    resizing: Option<Resize>,
+}
+
+struct ColumnHeader<R> {
+    label: WidgetPod<(), Label<()>>,
+    width: f64,
+    min_width: f64,
+    compare: Option<Box<Comparator<R>>>,
+}
+
+/// A drag of the divider to the right of `column`.
+#[derive(Clone, Copy)]
+struct Resize {
+    column: usize,
+    start_x: f64,
+    start_width: f64,
+}
+
+/// The cells of the table, in rows.
+struct TableBody<R> {
+    builders: Vec<Box<CellBuilder<R>>>,
+    /// The cells of each row, in the order of the data.
+    rows: Vec<Vec<Cell<R>>>,
+    widths: Vec<f64>,
+    /// The index in the data of each row, in the order they are shown.
+    order: Vec<usize>,
+}
+
+impl<R: Data> Column<R> {
+    /// Create a column with `header` above it, whose cells are made by
+    /// `cell`, which is called once for each row.
+    pub fn new<W: Widget<R> + 'static>(
+        header: impl Into<LabelText<()>>,
+        cell: impl Fn() -> W + 'static,
+    ) -> Self {
+        Column {
+            header: header.into(),
+            cell: Box::new(move || Box::new(cell())),
+            width: DEFAULT_COLUMN_WIDTH,
+            min_width: DEFAULT_MIN_COLUMN_WIDTH,
+            compare: None,
+        }
+    }
+
+    /// Builder-style method to set the width of the column, before it is
+    /// resized.
+    pub fn width(mut self, width: f64) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Builder-style method to set the narrowest that the column can be
+    /// resized to.
+    pub fn min_width(mut self, width: f64) -> Self {
+        self.min_width = width.max(0.0);
+        self
+    }
+
+    /// Builder-style method to let the rows be sorted by this column, in
+    /// the order given by `compare`.
+    pub fn sortable(mut self, compare: impl Fn(&R, &R) -> Ordering + 'static) -> Self {
+        self.compare = Some(Box::new(compare));
+        self
+    }
+}
+
+impl<T: ListIter<R>, R: Data> Table<T, R> {
+    /// Create a table with no columns.
+    pub fn new() -> Self {
+        let body = TableBody {
+            builders: Vec::new(),
+            rows: Vec::new(),
+            widths: Vec::new(),
+            order: Vec::new(),
+        };
+        Table {
+            columns: Vec::new(),
+            body: WidgetPod::new(Scroll::new(body)),
+            sort: None,
+            header_height: 0.0,
+            header_offset: 0.0,
+            pressed: None,
+            resizing: None,
+        }
+    }
+
+    /// Builder-style method to add a column, to the right of the others.
+    pub fn with_column(mut self, column: Column<R>) -> Self {
+        self.columns.push(ColumnHeader {
+            label: WidgetPod::new(Label::new(column.header)),
+            width: column.width.max(column.min_width),
+            min_width: column.min_width,
+            compare: column.compare,
+        });
+        self.body_mut().builders.push(column.cell);
+        self
+    }
+
+    /// Builder-style method to sort the rows by `column` to start with.
+    ///
+    /// Nothing is sorted if that column isn't [`sortable`].
+    ///
+    /// [`sortable`]: struct.Column.html#method.sortable
+    pub fn sorted_by(mut self, column: usize, direction: SortDirection) -> Self {
+        self.sort = Some((column, direction));
+        self
+    }
+
+    fn body_mut(&mut self) -> &mut TableBody<R> {
+        self.body.widget_mut().child_mut()
+    }
+
+    /// Work out the order of the rows, as they are sorted.
+    fn apply_sort(&mut self, data: &T) {
+        let columns = &self.columns;
+        let compare = self
+            .sort
+            .and_then(|(column, direction)| Some((columns.get(column)?, direction)))
+            .and_then(|(column, direction)| Some((column.compare.as_ref()?, direction)));
+        let order = match compare {
+            Some((compare, direction)) => {
+                let mut rows = Vec::with_capacity(data.data_len());
+                data.for_each(|row, _| rows.push(row.clone()));
+                sorted_order(&rows, &**compare, direction)
+            }
+            None => (0..data.data_len()).collect(),
+        };
+        self.body_mut().order = order;
+    }
+
+    /// Sort by `column`, or reverse the sort if it is sorted already.
+    fn toggle_sort(&mut self, column: usize, data: &T) {
+        if self.columns[column].compare.is_none() {
+            return;
+        }
+        self.sort = match self.sort {
+            Some((sorted, SortDirection::Ascending)) if sorted == column => {
+                Some((column, SortDirection::Descending))
+            }
+            _ => Some((column, SortDirection::Ascending)),
+        };
+        self.apply_sort(data);
+    }
+
+    /// The column whose header is at `x`.
+    fn column_at(&self, x: f64) -> Option<usize> {
+        let x = x + self.header_offset;
+        let mut left = 0.0;
+        self.columns.iter().position(|column| {
+            left += column.width;
+            x < left
+        })
+    }
+
+    /// The column whose divider is at `x`.
+    fn divider_at(&self, x: f64) -> Option<usize> {
+        let x = x + self.header_offset;
+        let mut right = 0.0;
+        self.columns.iter().position(|column| {
+            right += column.width;
+            (x - right).abs() <= DIVIDER_SLOP
+        })
+    }
+
+    fn is_in_header(&self, pos: Point) -> bool {
+        pos.y < self.header_height
+    }
+}
+
+impl<T: ListIter<R>, R: Data> Default for Table<T, R> {
+    fn default() -> Self {
+        Table::new()
+    }
+}
+
+impl<T: ListIter<R>, R: Data> Widget<T> for Table<T, R> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() && self.is_in_header(mouse.pos) => {
+                match self.divider_at(mouse.pos.x) {
+                    Some(column) => {
+                        self.resizing = Some(Resize {
+                            column,
+                            start_x: mouse.pos.x,
+                            start_width: self.columns[column].width,
+                        })
+                    }
+                    None => self.pressed = self.column_at(mouse.pos.x),
+                }
+                ctx.set_active(true);
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseMoved(mouse) if ctx.is_active() => {
+                if let Some(resize) = self.resizing {
+                    let column = &mut self.columns[resize.column];
+                    let width = resize.start_width + mouse.pos.x - resize.start_x;
+                    column.width = width.max(column.min_width);
+                    ctx.set_cursor(&Cursor::ResizeLeftRight);
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseUp(mouse) if mouse.button.is_left() && ctx.is_active() => {
+                ctx.set_active(false);
+                self.resizing = None;
+                if let Some(column) = self.pressed.take() {
+                    if self.is_in_header(mouse.pos) && self.column_at(mouse.pos.x) == Some(column) {
+                        self.toggle_sort(column, data);
+                        ctx.request_layout();
+                    }
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseMoved(mouse) => {
+                if self.is_in_header(mouse.pos) && self.divider_at(mouse.pos.x).is_some() {
+                    ctx.set_cursor(&Cursor::ResizeLeftRight);
+                } else {
+                    ctx.clear_cursor();
+                }
+            }
+            // the header scrolls the rows sideways
+            Event::Wheel(wheel) if self.is_in_header(wheel.local_anchor) => {
+                let viewport = self.body.layout_rect().size();
+                let delta = Vec2::new(wheel.delta.x, 0.0);
+                if self.body.widget_mut().scroll(delta, viewport) {
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => (),
+        }
+
+        for column in &mut self.columns {
+            column.label.event(ctx, event, &mut (), env);
+        }
+        self.body.event(ctx, event, data, env);
+
+        // keep the header over the columns as they scroll
+        if (self.body.widget().offset().x - self.header_offset).abs() > 1e-9 {
+            ctx.request_layout();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.apply_sort(data);
+        }
+
+        for column in &mut self.columns {
+            column.label.lifecycle(ctx, event, &(), env);
+        }
+        self.body.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        for column in &mut self.columns {
+            column.label.update(ctx, &(), env);
+        }
+        self.body.update(ctx, data, env);
+
+        if !old_data.same(data) {
+            self.apply_sort(data);
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("Table");
+
+        if !bc.is_width_bounded() || !bc.is_height_bounded() {
+            log::warn!("A Table widget was given unbounded constraints.");
+        }
+        let size = bc.max();
+
+        let mut label_sizes = Vec::with_capacity(self.columns.len());
+        let mut header_height: f64 = 0.0;
+        for column in &mut self.columns {
+            let max_width = column.width - 2.0 * HEADER_PADDING - SORT_ARROW_WIDTH;
+            let label_bc = BoxConstraints::new(
+                Size::ZERO,
+                Size::new(max_width.max(0.0), std::f64::INFINITY),
+            );
+            let label_size = column.label.layout(ctx, &label_bc, &(), env);
+            header_height = header_height.max(label_size.height);
+            label_sizes.push(label_size);
+        }
+        self.header_height = header_height + 2.0 * HEADER_PADDING;
+
+        let widths = self.columns.iter().map(|column| column.width).collect();
+        self.body_mut().widths = widths;
+        let body_height = (size.height - self.header_height).max(0.0);
+        let body_bc = BoxConstraints::tight(Size::new(size.width, body_height));
+        let body_size = self.body.layout(ctx, &body_bc, data, env);
+        let body_origin = Point::new(0.0, self.header_height);
+        self.body.set_layout_rect(
+            ctx,
+            data,
+            env,
+            Rect::from_origin_size(body_origin, body_size),
+        );
+
+        self.header_offset = self.body.widget().offset().x;
+        let mut x = -self.header_offset;
+        for (column, label_size) in self.columns.iter_mut().zip(label_sizes) {
+            let origin = Point::new(x + HEADER_PADDING, HEADER_PADDING);
+            column
+                .label
+                .set_layout_rect(ctx, &(), env, Rect::from_origin_size(origin, label_size));
+            x += column.width;
+        }
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let header_rect = Size::new(ctx.size().width, self.header_height).to_rect();
+        ctx.fill(header_rect, &env.get(theme::BACKGROUND_LIGHT));
+
+        let border = env.get(theme::BORDER_DARK);
+        if let Err(e) = ctx.save() {
+            log::error!("saving render context failed: {:?}", e);
+            return;
+        }
+        ctx.clip(header_rect);
+        let mut right = -self.header_offset;
+        for (i, column) in self.columns.iter_mut().enumerate() {
+            column.label.paint_with_offset(ctx, &(), env);
+            right += column.width;
+            match self.sort {
+                Some((sorted, direction)) if sorted == i && column.compare.is_some() => {
+                    let end = Point::new(right - HEADER_PADDING, self.header_height / 2.0);
+                    let arrow = sort_arrow(end, direction);
+                    ctx.fill(arrow, &env.get(theme::LABEL_COLOR));
+                }
+                _ => (),
+            }
+            let divider = Line::new((right - 0.5, 0.0), (right - 0.5, self.header_height));
+            ctx.stroke(divider, &border, 1.0);
+        }
+        if let Err(e) = ctx.restore() {
+            log::error!("restoring render context failed: {:?}", e);
+        }
+        let bottom = self.header_height - 0.5;
+        ctx.stroke(
+            Line::new((0.0, bottom), (header_rect.x1, bottom)),
+            &border,
+            1.0,
+        );
+
+        self.body.paint_with_offset(ctx, data, env);
+    }
+}
+
+impl<R: Data> TableBody<R> {
+    /// Create or remove rows to match the data.
+    ///
+    /// Returns `true` if rows were added or removed.
+    fn update_row_count(&mut self, len: usize) -> bool {
+        let old_len = self.rows.len();
+        self.rows.truncate(len);
+        while self.rows.len() < len {
+            let row = self.builders.iter().map(|b| WidgetPod::new(b())).collect();
+            self.rows.push(row);
+        }
+        old_len != len
+    }
+}
+
+impl<T: ListIter<R>, R: Data> Widget<T> for TableBody<R> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let rows = &mut self.rows;
+        data.for_each_mut(|item, i| {
+            for cell in rows.get_mut(i).into_iter().flatten() {
+                cell.event(ctx, event, item, env);
+            }
+        });
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.update_row_count(data.data_len()) {
+                ctx.children_changed();
+            }
+        }
+
+        let rows = &mut self.rows;
+        data.for_each(|item, i| {
+            for cell in rows.get_mut(i).into_iter().flatten() {
+                cell.lifecycle(ctx, event, item, env);
+            }
+        });
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        // as with `List`, the rows are updated before any are added, so that
+        // the new ones aren't sent `update` before `WidgetAdded`
+        let rows = &mut self.rows;
+        data.for_each(|item, i| {
+            for cell in rows.get_mut(i).into_iter().flatten() {
+                cell.update(ctx, item, env);
+            }
+        });
+
+        for cell in self.rows.iter_mut().skip(data.data_len()).flatten() {
+            ctx.remove_child(cell);
+        }
+        if self.update_row_count(data.data_len()) {
+            ctx.children_changed();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        // the rows are measured in the order of the data, and then stacked
+        // in the order that they are shown
+        let mut sizes = vec![Vec::new(); self.rows.len()];
+        let (rows, widths) = (&mut self.rows, &self.widths);
+        data.for_each(|item, i| {
+            let cells = match rows.get_mut(i) {
+                Some(cells) => cells,
+                None => return,
+            };
+            for (cell, width) in cells.iter_mut().zip(widths) {
+                let cell_bc = BoxConstraints::new(
+                    Size::new(*width, 0.0),
+                    Size::new(*width, std::f64::INFINITY),
+                );
+                sizes[i].push(cell.layout(ctx, &cell_bc, item, env));
+            }
+        });
+
+        // each cell is placed with the row it shows, so they are placed in
+        // the order of the data too
+        let mut tops = vec![0.0; rows.len()];
+        let mut y = 0.0;
+        for &idx in &self.order {
+            if let (Some(top), Some(sizes)) = (tops.get_mut(idx), sizes.get(idx)) {
+                *top = y;
+                y += sizes.iter().map(|size| size.height).fold(0.0, f64::max);
+            }
+        }
+        data.for_each(|item, i| {
+            let (cells, sizes) = match (rows.get_mut(i), sizes.get(i)) {
+                (Some(cells), Some(sizes)) => (cells, sizes),
+                _ => return,
+            };
+            let mut x = 0.0;
+            for ((cell, size), width) in cells.iter_mut().zip(sizes).zip(widths) {
+                let rect = Rect::from_origin_size(Point::new(x, tops[i]), *size);
+                cell.set_layout_rect(ctx, item, env, rect);
+                x += width;
+            }
+        });
+
+        let width = self.widths.iter().sum();
+        bc.constrain(Size::new(width, y))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let rows = &mut self.rows;
+        data.for_each(|item, i| {
+            for cell in rows.get_mut(i).into_iter().flatten() {
+                cell.paint_with_offset(ctx, item, env);
+            }
+        });
+    }
+}
+
+/// The arrow on the header of the sorted column, whose right end is at the
+/// `right` of its center.
+fn sort_arrow(right: Point, direction: SortDirection) -> BezPath {
+    let half = SORT_ARROW_WIDTH / 2.0;
+    let center = right - Vec2::new(half, 0.0);
+    let (tip, base) = match direction {
+        SortDirection::Ascending => (-half / 2.0, half / 2.0),
+        SortDirection::Descending => (half / 2.0, -half / 2.0),
+    };
+    let mut arrow = BezPath::new();
+    arrow.move_to(center + Vec2::new(0.0, tip));
+    arrow.line_to(center + Vec2::new(half, base));
+    arrow.line_to(center + Vec2::new(-half, base));
+    arrow.close_path();
+    arrow
+}
+
+/// The indices of `rows` in the order that they are shown, when they are
+/// sorted by `compare` in `direction`.
+///
+/// Rows that compare equal keep the order that they have in the data, in
+/// either direction.
+fn sorted_order<R>(rows: &[R], compare: &Comparator<R>, direction: SortDirection) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by(|&a, &b| {
+        let ordering = compare(&rows[a], &rows[b]);
+        match direction {
+            SortDirection::Ascending => ordering,
+            SortDirection::Descending => ordering.reverse(),
+        }
+    });
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Controller, SizedBox, WidgetExt};
+    use crate::{Command, Selector, Target, WidgetId};
+
+    #[test]
+    fn sorting_is_stable_in_both_directions() {
+        let rows = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (3, 'e')];
+        let by_number = |a: &(u32, char), b: &(u32, char)| a.0.cmp(&b.0);
+        assert_eq!(
+            sorted_order(&rows, &by_number, SortDirection::Ascending),
+            vec![1, 3, 0, 2, 4]
+        );
+        // reversed, the equal rows are still in the order of the data
+        assert_eq!(
+            sorted_order(&rows, &by_number, SortDirection::Descending),
+            vec![4, 0, 2, 1, 3]
+        );
+        assert!(sorted_order(&[], &by_number, SortDirection::Ascending).is_empty());
+    }
+
+    /// A key, and the index of the row in the data.
+    type Row = (u32, u32);
+    type Rows = Arc<Vec<Row>>;
+
+    /// The row that each cell widget was last given.
+    type Shown = Rc<RefCell<HashMap<WidgetId, Row>>>;
+
+    fn rows(keys: &[u32]) -> Rows {
+        Arc::new((0..).zip(keys).map(|(i, key)| (*key, i)).collect())
+    }
+
+    /// A cell that is 20 tall, and that records the row it shows.
+    fn make_cell(shown: &Shown) -> impl Widget<Row> {
+        ModularWidget::new(shown.clone())
+            .lifecycle_fn(|shown, ctx, event, data: &Row, _env| {
+                if let LifeCycle::WidgetAdded = event {
+                    shown.borrow_mut().insert(ctx.widget_id(), *data);
+                }
+            })
+            .update_fn(|shown, ctx, _old_data, data, _env| {
+                shown.borrow_mut().insert(ctx.widget_id(), *data);
+            })
+            .layout_fn(|_, _ctx, bc, _data, _env| bc.constrain(Size::new(0., 20.)))
+    }
+
+    /// A table that sorts by the key, with another column that can't be sorted.
+    fn keyed_table(shown: &Shown) -> Table<Rows, Row> {
+        let cells = shown.clone();
+        Table::new()
+            .with_column(
+                Column::new("Key", move || make_cell(&cells)).sortable(|a: &Row, b| a.0.cmp(&b.0)),
+            )
+            .with_column(Column::new("Index", SizedBox::empty))
+    }
+
+    /// The rows that are shown, from the top.
+    fn shown_rows(harness: &mut Harness<Rows>, shown: &Shown) -> Vec<Row> {
+        let shown = shown.borrow();
+        let mut rows: Vec<_> = shown
+            .iter()
+            .map(|(id, row)| (harness.layout_rect_in_window(*id).y0, *row))
+            .collect();
+        rows.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        rows.into_iter().map(|(_, row)| row).collect()
+    }
+
+    /// Replaces the rows with ones with the keys in the argument.
+    const SET_KEYS: Selector = Selector::new("druid-tests.table.set-keys");
+
+    struct SetKeys;
+
+    impl<W: Widget<Rows>> Controller<Rows, W> for SetKeys {
+        fn event(
+            &mut self,
+            child: &mut W,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut Rows,
+            env: &Env,
+        ) {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == SET_KEYS {
+                    *data = rows(cmd.get_object::<Vec<u32>>().unwrap());
+                }
+            }
+            child.event(ctx, event, data, env)
+        }
+    }
+
+    #[test]
+    fn clicking_a_header_sorts_the_rows() {
+        let shown: Shown = Default::default();
+        let data = rows(&[3, 1, 2, 1]);
+        Harness::create(data.clone(), keyed_table(&shown), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            assert_eq!(
+                shown_rows(harness, &shown),
+                vec![(3, 0), (1, 1), (2, 2), (1, 3)]
+            );
+
+            // the rows with the same key stay in the order of the data
+            click(harness, Point::new(50., 5.));
+            harness.just_layout();
+            assert_eq!(
+                shown_rows(harness, &shown),
+                vec![(1, 1), (1, 3), (2, 2), (3, 0)]
+            );
+
+            // clicking again reverses them, still keeping those in order
+            click(harness, Point::new(50., 5.));
+            harness.just_layout();
+            assert_eq!(
+                shown_rows(harness, &shown),
+                vec![(3, 0), (2, 2), (1, 1), (1, 3)]
+            );
+
+            // this column can't be sorted
+            click(harness, Point::new(150., 5.));
+            harness.just_layout();
+            assert_eq!(
+                shown_rows(harness, &shown),
+                vec![(3, 0), (2, 2), (1, 1), (1, 3)]
+            );
+
+            // and the data itself is never reordered
+            assert!(harness.data().same(&data));
+        });
+    }
+
+    #[test]
+    fn sorted_rows_follow_changes_to_the_data() {
+        let shown: Shown = Default::default();
+        let table = keyed_table(&shown)
+            .sorted_by(0, SortDirection::Descending)
+            .controller(SetKeys);
+        Harness::create(rows(&[1, 2]), table, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            assert_eq!(shown_rows(harness, &shown), vec![(2, 1), (1, 0)]);
+
+            // each cell keeps showing the same row of the data, wherever it is
+            harness.submit_command(Command::new(SET_KEYS, vec![1u32, 2, 3]), Target::Auto);
+            harness.just_layout();
+            assert_eq!(shown_rows(harness, &shown), vec![(3, 2), (2, 1), (1, 0)]);
+        });
+    }
+
+    #[test]
+    fn dragging_a_divider_resizes_its_column() {
+        let (key, index) = widget_id2();
+        let table = Table::new()
+            .with_column(
+                Column::new("Key", move || SizedBox::empty().with_id(key))
+                    .width(100.)
+                    .min_width(40.),
+            )
+            .with_column(Column::new("Index", move || {
+                SizedBox::empty().with_id(index)
+            }));
+        Harness::create(rows(&[1]), table, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            assert_eq!(harness.get_state(key).layout_rect.width(), 100.);
+
+            harness.event(Event::MouseMoved(make_mouse(Point::new(101., 5.))));
+            harness.event(Event::MouseDown(make_mouse(Point::new(101., 5.))));
+            harness.event(Event::MouseMoved(make_mouse(Point::new(131., 5.))));
+            harness.just_layout();
+            assert_eq!(harness.get_state(key).layout_rect.width(), 130.);
+            assert_eq!(harness.get_state(index).layout_rect.x0, 130.);
+
+            // it can't be made narrower than its minimum, wherever the mouse goes
+            harness.event(Event::MouseMoved(make_mouse(Point::new(-50., 5.))));
+            harness.just_layout();
+            assert_eq!(harness.get_state(key).layout_rect.width(), 40.);
+            assert_eq!(harness.get_state(index).layout_rect.x0, 40.);
+
+            harness.event(Event::MouseMoved(make_mouse(Point::new(61., 5.))));
+            harness.event(Event::MouseUp(make_mouse(Point::new(61., 5.))));
+            harness.just_layout();
+            assert_eq!(harness.get_state(key).layout_rect.width(), 60.);
+
+            // once it is let go, moving the mouse leaves it alone
+            harness.event(Event::MouseMoved(make_mouse(Point::new(200., 5.))));
+            harness.just_layout();
+            assert_eq!(harness.get_state(key).layout_rect.width(), 60.);
+        });
+    }
+}
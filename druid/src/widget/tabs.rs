@@ -0,0 +1,712 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that shows one of several children, chosen with a bar of tabs.
+
+use std::time::Duration;
+
+use crate::kurbo::{Affine, Line, Point, Rect, Size, Vec2};
+use crate::piet::RenderContext;
+use crate::theme;
+use crate::widget::{Label, LineBreaking};
+use crate::{
+    Animator, BoxConstraints, Curve, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, UpdateCtx, Widget, WidgetPod,
+};
+
+/// The horizontal space between the edges of a tab and its contents.
+const TAB_PADDING: f64 = 8.0;
+/// The narrowest a tab is, unless there isn't room for it.
+const TAB_MIN_WIDTH: f64 = 64.0;
+/// The narrowest tabs are shrunk to when there are too many; past that, the
+/// bar scrolls.
+const TAB_SHRUNK_WIDTH: f64 = 32.0;
+/// The size of the close button of a tab.
+const CLOSE_SIZE: f64 = 8.0;
+/// The height of the line under the selected tab.
+const SELECTED_LINE_HEIGHT: f64 = 2.0;
+/// The name of the horizontal offset of the body in the `Animator`.
+const OFFSET: &str = "offset";
+
+/// Where the tabs of a [`Tabs`] come from.
+///
+/// The tabs are derived from the data, so that they can be added and removed
+/// by changing it. Each tab has a key; the body of a tab is built when its
+/// key first appears, and kept for as long as the key is there.
+///
+/// For a fixed set of tabs, [`Tabs::new`] uses [`StaticTabs`].
+///
+/// [`Tabs`]: struct.Tabs.html
+/// [`Tabs::new`]: struct.Tabs.html#method.new
+/// [`StaticTabs`]: struct.StaticTabs.html
+pub trait TabsPolicy {
+    /// The data of the tabs.
+    type Input: Data;
+    /// What identifies a tab.
+    type Key: Clone + PartialEq;
+
+    /// The keys of the tabs, in the order they appear in the bar.
+    fn tab_keys(&self, data: &Self::Input) -> Vec<Self::Key>;
+
+    /// The text of a tab.
+    fn tab_label(&self, key: &Self::Key, data: &Self::Input) -> String;
+
+    /// Build the body of a tab, when the tab is added.
+    fn tab_body(&mut self, key: &Self::Key, data: &Self::Input) -> Box<dyn Widget<Self::Input>>;
+
+    /// Called when the close button of a tab is clicked.
+    ///
+    /// To close the tab, this should change the data or the policy so that
+    /// the key is no longer in [`tab_keys`]. By default, this does nothing.
+    ///
+    /// [`tab_keys`]: #tymethod.tab_keys
+    #[allow(unused_variables)]
+    fn close_tab(&mut self, key: &Self::Key, data: &mut Self::Input) {}
+}
+
+/// A [`TabsPolicy`] for a fixed set of tabs, given with [`Tabs::with_tab`].
+///
+/// The keys are the indices of the tabs; a closed tab stays closed.
+///
+/// [`TabsPolicy`]: trait.TabsPolicy.html
+/// [`Tabs::with_tab`]: struct.Tabs.html#method.with_tab
+pub struct StaticTabs<T> {
+    tabs: Vec<StaticTab<T>>,
+}
+
+struct StaticTab<T> {
+    label: String,
+    /// The body, until it is taken by `Tabs`.
+    body: Option<Box<dyn Widget<T>>>,
+    closed: bool,
+}
+
+/// A bar of tabs, above the body of the selected tab.
+///
+/// Clicking a tab selects it. Only the body of the selected tab is laid out,
+/// painted, and sent events and updates, and only it is in the focus chain;
+/// but every body is sent its `WidgetAdded` when its tab is added.
+///
+/// When the tabs don't fit in the bar, they are shrunk, and if they still
+/// don't fit, the bar can be scrolled with the mouse wheel.
+///
+/// The tabs are either given up front, with [`new`] and [`with_tab`], or
+/// derived from the data by a [`TabsPolicy`], with [`for_policy`].
+///
+/// [`new`]: #method.new
+/// [`with_tab`]: #method.with_tab
+/// [`for_policy`]: #method.for_policy
+/// [`TabsPolicy`]: trait.TabsPolicy.html
+pub struct Tabs<P: TabsPolicy> {
+    policy: P,
+    tabs: Vec<Tab<P>>,
+    selected: usize,
+    /// The tab under the mouse.
+    hot: Option<usize>,
+    close_buttons: bool,
+    transition: Option<Duration>,
+    animator: Animator,
+    /// How far the bar is scrolled, when the tabs don't fit.
+    bar_offset: f64,
+    /// The width of all the tabs, from the last layout.
+    bar_width: f64,
+    bar_height: f64,
+}
+
+struct Tab<P: TabsPolicy> {
+    key: P::Key,
+    label_text: String,
+    label: WidgetPod<P::Input, Label<P::Input>>,
+    body: WidgetPod<P::Input, Box<dyn Widget<P::Input>>>,
+    /// Where the tab is in the bar, from the last layout, not counting the
+    /// scrolling of the bar.
+    rect: Rect,
+}
+
+impl<T: Data> Tabs<StaticTabs<T>> {
+    /// Create a widget without any tabs; they are added with [`with_tab`].
+    ///
+    /// [`with_tab`]: #method.with_tab
+    pub fn new() -> Self {
+        Tabs::for_policy(StaticTabs { tabs: Vec::new() })
+    }
+
+    /// Builder-style method to add a tab, with the text `label`.
+    pub fn with_tab(mut self, label: impl Into<String>, body: impl Widget<T> + 'static) -> Self {
+        self.policy.tabs.push(StaticTab {
+            label: label.into(),
+            body: Some(Box::new(body)),
+            closed: false,
+        });
+        self
+    }
+}
+
+impl<T: Data> Default for Tabs<StaticTabs<T>> {
+    fn default() -> Self {
+        Tabs::new()
+    }
+}
+
+impl<P: TabsPolicy> Tabs<P> {
+    /// Create a widget whose tabs are derived from the data by `policy`.
+    pub fn for_policy(policy: P) -> Self {
+        Tabs {
+            policy,
+            tabs: Vec::new(),
+            selected: 0,
+            hot: None,
+            close_buttons: false,
+            transition: None,
+            animator: Animator::new(),
+            bar_offset: 0.0,
+            bar_width: 0.0,
+            bar_height: 0.0,
+        }
+    }
+
+    /// Builder-style method to show a close button on each tab, which calls
+    /// [`TabsPolicy::close_tab`].
+    ///
+    /// [`TabsPolicy::close_tab`]: trait.TabsPolicy.html#method.close_tab
+    pub fn with_close_buttons(mut self, close_buttons: bool) -> Self {
+        self.close_buttons = close_buttons;
+        self
+    }
+
+    /// Builder-style method to slide the body of a newly selected tab into
+    /// place, over `duration`.
+    ///
+    /// This doesn't animate if [`theme::ANIMATIONS_ENABLED`] is `false`.
+    ///
+    /// [`theme::ANIMATIONS_ENABLED`]: ../theme/constant.ANIMATIONS_ENABLED.html
+    pub fn with_transition(mut self, duration: Duration) -> Self {
+        self.transition = Some(duration);
+        self
+    }
+
+    /// The index of the selected tab.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The policy that the tabs come from.
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Make the tabs match the keys from the policy, returning whether they
+    /// changed, and the tabs that were removed.
+    ///
+    /// Tabs whose key is still there are kept, and keep their state; the
+    /// selected tab stays selected if it is still there. The caller has to
+    /// remove the removed tabs from the widget tree.
+    fn sync_tabs(&mut self, data: &P::Input) -> (bool, Vec<Tab<P>>) {
+        let keys = self.policy.tab_keys(data);
+        let selected_key = self.tabs.get(self.selected).map(|tab| tab.key.clone());
+        let mut old = std::mem::replace(&mut self.tabs, Vec::with_capacity(keys.len()));
+        let mut changed = false;
+        for key in keys {
+            let label_text = self.policy.tab_label(&key, data);
+            match old.iter().position(|tab| tab.key == key) {
+                Some(idx) => {
+                    // a tab that moved changes the order of the focus chain
+                    changed |= idx != 0;
+                    let mut tab = old.remove(idx);
+                    if tab.label_text != label_text {
+                        tab.label = tab_label(&label_text);
+                        tab.label_text = label_text;
+                        changed = true;
+                    }
+                    self.tabs.push(tab);
+                }
+                None => {
+                    let body = self.policy.tab_body(&key, data);
+                    self.tabs.push(Tab {
+                        key,
+                        label: tab_label(&label_text),
+                        label_text,
+                        body: WidgetPod::new(body),
+                        rect: Rect::ZERO,
+                    });
+                    changed = true;
+                }
+            }
+        }
+        changed |= !old.is_empty();
+
+        let selected = selected_key.and_then(|key| self.tabs.iter().position(|t| t.key == key));
+        self.selected = selected.unwrap_or_else(|| self.selected.min(self.tabs.len().max(1) - 1));
+        self.hot = None;
+        (changed, old)
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, idx: usize, env: &Env) {
+        if idx == self.selected {
+            return;
+        }
+        let direction = if idx > self.selected { 1.0 } else { -1.0 };
+        self.selected = idx;
+        match self.transition {
+            Some(duration) if env.get(theme::ANIMATIONS_ENABLED) => {
+                self.animator.set(OFFSET, direction * ctx.size().width);
+                self.animator
+                    .animate_to(OFFSET, 0.0, duration, Curve::EaseOut);
+                ctx.request_anim_frame();
+            }
+            _ => self.animator.set(OFFSET, 0.0),
+        }
+        // the focus chain has the selected body in it
+        ctx.children_changed();
+        ctx.request_layout();
+    }
+
+    /// The tab at `pos`, in the coordinates of this widget.
+    fn tab_at(&self, pos: Point) -> Option<usize> {
+        if pos.y < 0.0 || pos.y >= self.bar_height {
+            return None;
+        }
+        let pos = pos + Vec2::new(self.bar_offset, 0.0);
+        self.tabs.iter().position(|tab| tab.rect.contains(pos))
+    }
+
+    /// The close button of a tab, in the coordinates of the bar.
+    fn close_rect(tab_rect: Rect) -> Rect {
+        let origin = Point::new(
+            tab_rect.x1 - TAB_PADDING - CLOSE_SIZE,
+            (tab_rect.height() - CLOSE_SIZE) / 2.0,
+        );
+        Rect::from_origin_size(origin, Size::new(CLOSE_SIZE, CLOSE_SIZE))
+    }
+
+    fn set_hot(&mut self, ctx: &mut EventCtx, hot: Option<usize>) {
+        if hot != self.hot {
+            self.hot = hot;
+            ctx.request_paint();
+        }
+    }
+
+    /// How far the bar can be scrolled.
+    fn max_bar_offset(&self, width: f64) -> f64 {
+        (self.bar_width - width).max(0.0)
+    }
+}
+
+impl<P: TabsPolicy> Widget<P::Input> for Tabs<P> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut P::Input, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) => {
+                if let Some(idx) = self.tab_at(mouse.pos) {
+                    let close = Self::close_rect(self.tabs[idx].rect)
+                        .contains(mouse.pos + Vec2::new(self.bar_offset, 0.0));
+                    if self.close_buttons && close {
+                        let key = self.tabs[idx].key.clone();
+                        self.policy.close_tab(&key, data);
+                        let (changed, removed) = self.sync_tabs(data);
+                        for mut tab in removed {
+                            ctx.remove_child(&mut tab.label);
+                            ctx.remove_child(&mut tab.body);
+                        }
+                        if changed {
+                            ctx.children_changed();
+                            ctx.request_layout();
+                        }
+                    } else {
+                        self.select(ctx, idx, env);
+                    }
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            Event::MouseMoved(mouse) => {
+                let hot = if ctx.is_hot() {
+                    self.tab_at(mouse.pos)
+                } else {
+                    None
+                };
+                self.set_hot(ctx, hot);
+            }
+            Event::Wheel(wheel) if self.tab_at(wheel.local_anchor).is_some() => {
+                let max = self.max_bar_offset(ctx.size().width);
+                let delta = if wheel.delta.x != 0.0 {
+                    wheel.delta.x
+                } else {
+                    wheel.delta.y
+                };
+                let offset = (self.bar_offset + delta).max(0.0).min(max);
+                if offset != self.bar_offset {
+                    self.bar_offset = offset;
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => (),
+        }
+        if let Some(tab) = self.tabs.get_mut(self.selected) {
+            tab.body.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &P::Input, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                // no tab has been added to the tree yet, so none is removed
+                self.sync_tabs(data);
+            }
+            LifeCycle::AnimFrame(interval) => {
+                if self.animator.advance(*interval) {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+            LifeCycle::HotChanged(false) => self.hot = None,
+            _ => (),
+        }
+        let selected = self.selected;
+        for (idx, tab) in self.tabs.iter_mut().enumerate() {
+            tab.label.lifecycle(ctx, event, data, env);
+            match event {
+                // the bodies of the other tabs are left out of the focus chain
+                LifeCycle::WidgetAdded | LifeCycle::RouteWidgetAdded if idx != selected => {
+                    let body = &mut tab.body;
+                    ctx.without_focus(|ctx| body.lifecycle(ctx, event, data, env));
+                }
+                _ => tab.body.lifecycle(ctx, event, data, env),
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &P::Input, data: &P::Input, env: &Env) {
+        let (changed, removed) = self.sync_tabs(data);
+        for mut tab in removed {
+            ctx.remove_child(&mut tab.label);
+            ctx.remove_child(&mut tab.body);
+        }
+        if changed {
+            // new tabs get their `WidgetAdded` once the children are rebuilt
+            ctx.children_changed();
+            ctx.request_layout();
+        }
+        // the body of a tab catches up when it is selected
+        for (idx, tab) in self.tabs.iter_mut().enumerate() {
+            tab.label.update(ctx, data, env);
+            if idx == self.selected {
+                tab.body.update(ctx, data, env);
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &P::Input,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("Tabs");
+
+        let bar_height = env.get(theme::BORDERED_WIDGET_HEIGHT);
+        let close_width = if self.close_buttons {
+            CLOSE_SIZE + TAB_PADDING
+        } else {
+            0.0
+        };
+
+        // the natural widths of the tabs
+        let label_bc = BoxConstraints::new(Size::ZERO, Size::new(std::f64::INFINITY, bar_height));
+        let widths: Vec<f64> = self
+            .tabs
+            .iter_mut()
+            .map(|tab| {
+                let label = tab.label.layout(ctx, &label_bc, data, env);
+                (label.width + 2.0 * TAB_PADDING + close_width).max(TAB_MIN_WIDTH)
+            })
+            .collect();
+        let natural: f64 = widths.iter().sum();
+
+        // shrink the tabs if they don't fit; the bar scrolls if they still don't
+        let available = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            natural
+        };
+        let shrink = if natural > available {
+            available / natural
+        } else {
+            1.0
+        };
+
+        let mut x = 0.0;
+        for (tab, width) in self.tabs.iter_mut().zip(widths) {
+            let width = (width * shrink).max(TAB_SHRUNK_WIDTH);
+            let label_width = (width - 2.0 * TAB_PADDING - close_width).max(0.0);
+            let label_size = Size::new(label_width, bar_height);
+            let label =
+                tab.label
+                    .layout(ctx, &BoxConstraints::new(Size::ZERO, label_size), data, env);
+            let label_origin = Point::new(x + TAB_PADDING, (bar_height - label.height) / 2.0);
+            tab.label
+                .set_layout_rect(ctx, data, env, Rect::from_origin_size(label_origin, label));
+            tab.rect = Rect::new(x, 0.0, x + width, bar_height);
+            x += width;
+        }
+        self.bar_width = x;
+        self.bar_height = bar_height;
+
+        let body_bc = bc.shrink((0.0, bar_height));
+        let body_size = match self.tabs.get_mut(self.selected) {
+            Some(tab) => {
+                let size = tab.body.layout(ctx, &body_bc, data, env);
+                let origin = Point::new(0.0, bar_height);
+                tab.body
+                    .set_layout_rect(ctx, data, env, Rect::from_origin_size(origin, size));
+                size
+            }
+            None => body_bc.min(),
+        };
+
+        let size = bc.constrain(Size::new(
+            body_size.width.max(x.min(available)),
+            bar_height + body_size.height,
+        ));
+        self.bar_offset = self.bar_offset.min(self.max_bar_offset(size.width));
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &P::Input, env: &Env) {
+        let size = ctx.size();
+        let bar = Rect::new(0.0, 0.0, size.width, self.bar_height);
+        ctx.fill(bar, &env.get(theme::BACKGROUND_DARK));
+
+        if let Err(e) = ctx.save() {
+            log::error!("saving render context failed: {:?}", e);
+            return;
+        }
+        ctx.clip(bar);
+        let bar_offset = Vec2::new(self.bar_offset, 0.0);
+        ctx.transform(Affine::translate(-bar_offset));
+        let visible = bar + bar_offset;
+        ctx.with_child_ctx(visible, |ctx| self.paint_bar(ctx, data, env));
+        if let Err(e) = ctx.restore() {
+            log::error!("restoring render context failed: {:?}", e);
+        }
+
+        let offset = Vec2::new(self.animator.get(OFFSET).unwrap_or(0.0), 0.0);
+        if let Some(tab) = self.tabs.get_mut(self.selected) {
+            if let Err(e) = ctx.save() {
+                log::error!("saving render context failed: {:?}", e);
+                return;
+            }
+            let body = Rect::new(0.0, self.bar_height, size.width, size.height);
+            ctx.clip(body);
+            ctx.transform(Affine::translate(offset));
+            ctx.with_child_ctx(body - offset, |ctx| {
+                tab.body.paint_with_offset(ctx, data, env)
+            });
+            if let Err(e) = ctx.restore() {
+                log::error!("restoring render context failed: {:?}", e);
+            }
+        }
+    }
+}
+
+impl<P: TabsPolicy> Tabs<P> {
+    /// Paint the tabs, in the coordinates of the bar.
+    fn paint_bar(&mut self, ctx: &mut PaintCtx, data: &P::Input, env: &Env) {
+        for (idx, tab) in self.tabs.iter_mut().enumerate() {
+            if idx == self.selected {
+                ctx.fill(tab.rect, &env.get(theme::BACKGROUND_LIGHT));
+                let line = Rect::new(
+                    tab.rect.x0,
+                    tab.rect.y1 - SELECTED_LINE_HEIGHT,
+                    tab.rect.x1,
+                    tab.rect.y1,
+                );
+                ctx.fill(line, &env.get(theme::PRIMARY_LIGHT));
+            } else if Some(idx) == self.hot {
+                ctx.fill(tab.rect, &env.get(theme::BUTTON_LIGHT));
+            }
+            tab.label.paint_with_offset(ctx, data, env);
+            if self.close_buttons {
+                let close = Self::close_rect(tab.rect);
+                let color = env.get(theme::LABEL_COLOR);
+                let first = Line::new((close.x0, close.y0), (close.x1, close.y1));
+                let second = Line::new((close.x1, close.y0), (close.x0, close.y1));
+                ctx.stroke(first, &color, 1.5);
+                ctx.stroke(second, &color, 1.5);
+            }
+        }
+    }
+}
+
+fn tab_label<T: Data>(text: &str) -> WidgetPod<T, Label<T>> {
+    WidgetPod::new(Label::new(text).with_line_break_mode(LineBreaking::Clip))
+}
+
+impl<T: Data> TabsPolicy for StaticTabs<T> {
+    type Input = T;
+    type Key = usize;
+
+    fn tab_keys(&self, _data: &T) -> Vec<usize> {
+        (0..self.tabs.len())
+            .filter(|&idx| !self.tabs[idx].closed)
+            .collect()
+    }
+
+    fn tab_label(&self, key: &usize, _data: &T) -> String {
+        self.tabs[*key].label.clone()
+    }
+
+    fn tab_body(&mut self, key: &usize, _data: &T) -> Box<dyn Widget<T>> {
+        self.tabs[*key]
+            .body
+            .take()
+            .expect("the body of a static tab is only built once")
+    }
+
+    fn close_tab(&mut self, key: &usize, _data: &mut T) {
+        self.tabs[*key].closed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Controller, WidgetExt};
+    use crate::{Selector, Target, WidgetId};
+
+    /// A widget that registers for focus, and counts the mouse downs it gets.
+    fn focusable_counter<T: Data>(clicks: Rc<Cell<usize>>) -> impl Widget<T> {
+        ModularWidget::new(clicks)
+            .event_fn(|clicks, _ctx, event, _data, _env| {
+                if let Event::MouseDown(_) = event {
+                    clicks.set(clicks.get() + 1);
+                }
+            })
+            .lifecycle_fn(|_, ctx, event, _data, _env| {
+                if let LifeCycle::WidgetAdded = event {
+                    ctx.register_for_focus();
+                }
+            })
+    }
+
+    #[test]
+    fn tabs_follow_the_data() {
+        const ADD_TAB: Selector = Selector::new("druid-tests.add-tab");
+
+        /// A tab for each number in the data, with a body that has the id at
+        /// that index.
+        struct NumberTabs(Vec<WidgetId>);
+
+        impl TabsPolicy for NumberTabs {
+            type Input = Arc<Vec<usize>>;
+            type Key = usize;
+
+            fn tab_keys(&self, data: &Arc<Vec<usize>>) -> Vec<usize> {
+                data.to_vec()
+            }
+
+            fn tab_label(&self, key: &usize, _data: &Arc<Vec<usize>>) -> String {
+                key.to_string()
+            }
+
+            fn tab_body(
+                &mut self,
+                key: &usize,
+                _data: &Arc<Vec<usize>>,
+            ) -> Box<dyn Widget<Arc<Vec<usize>>>> {
+                Box::new(focusable_counter(Default::default()).with_id(self.0[*key]))
+            }
+        }
+
+        /// Adds a tab when sent `ADD_TAB`.
+        struct AddTab;
+
+        impl<W: Widget<Arc<Vec<usize>>>> Controller<Arc<Vec<usize>>, W> for AddTab {
+            fn event(
+                &mut self,
+                child: &mut W,
+                ctx: &mut EventCtx,
+                event: &Event,
+                data: &mut Arc<Vec<usize>>,
+                env: &Env,
+            ) {
+                match event {
+                    Event::Command(cmd) if cmd.selector == ADD_TAB => {
+                        let next = data.len();
+                        Arc::make_mut(data).push(next);
+                    }
+                    _ => child.event(ctx, event, data, env),
+                }
+            }
+        }
+
+        let (tabs, id_0, id_1, id_2) = widget_id4();
+        let widget = Tabs::for_policy(NumberTabs(vec![id_0, id_1, id_2]))
+            .controller(AddTab)
+            .with_id(tabs);
+
+        Harness::create(Arc::new(vec![0, 1]), widget, |harness| {
+            harness.send_initial_events();
+            // a label and a body for each tab
+            assert_eq!(harness.get_state(tabs).children.entry_count(), 4);
+            assert!(harness.get_state(tabs).children.contains(&id_1));
+            // only the body of the selected tab is in the focus chain
+            assert_eq!(harness.window().focus_chain(), &[id_0]);
+
+            harness.submit_command(ADD_TAB, Target::Auto);
+            assert_eq!(harness.get_state(tabs).children.entry_count(), 6);
+            assert!(harness.get_state(tabs).children.contains(&id_2));
+            assert_eq!(harness.window().focus_chain(), &[id_0]);
+
+            // the tabs are as wide as they can be at their narrowest, at 64
+            harness.just_layout();
+            click(harness, Point::new(160., 10.));
+            assert_eq!(harness.window().focus_chain(), &[id_2]);
+        });
+    }
+
+    #[test]
+    fn tabs_route_events_to_the_selected_tab() {
+        let first = Rc::new(Cell::new(0));
+        let second = Rc::new(Cell::new(0));
+        let widget = Tabs::new()
+            .with_tab("A", focusable_counter(first.clone()))
+            .with_tab("B", focusable_counter(second.clone()));
+
+        Harness::create((), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            // the body is below the bar, which is 24 high
+            click(harness, Point::new(50., 60.));
+            assert_eq!((first.get(), second.get()), (1, 0));
+
+            // select the second tab; the click on the bar goes to no body
+            click(harness, Point::new(96., 10.));
+            assert_eq!((first.get(), second.get()), (1, 0));
+
+            harness.just_layout();
+            click(harness, Point::new(50., 60.));
+            assert_eq!((first.get(), second.get()), (1, 1));
+        });
+    }
+}
@@ -14,14 +14,19 @@
 
 //! A textbox widget.
 
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::{
-    Application, BoxConstraints, Cursor, Env, Event, EventCtx, HotKey, KeyCode, LayoutCtx,
-    LifeCycle, LifeCycleCtx, PaintCtx, Selector, SysMods, TimerToken, UpdateCtx, Widget,
+    BoxConstraints, Clipboard, CompositionEvent, Cursor, EditAction, EditBuffer, Env, Event,
+    EventCtx, HotKey, KbKey, KeyBindings, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Role,
+    Selector, SysMods, TimerToken, UpdateCtx, Widget,
 };
 
-use crate::kurbo::{Affine, Line, Point, RoundedRect, Size, Vec2};
+use crate::kurbo::{Affine, Insets, Line, Point, Rect, RoundedRect, Size, Vec2};
 use crate::piet::{
     FontBuilder, PietText, PietTextLayout, RenderContext, Text, TextLayout, TextLayoutBuilder,
 };
@@ -32,19 +37,144 @@ use crate::text::{movement, offset_for_delete_backwards, EditableText, Movement,
 const BORDER_WIDTH: f64 = 1.;
 const PADDING_TOP: f64 = 5.;
 const PADDING_LEFT: f64 = 4.;
+/// The space between the box and its validation message.
+const MESSAGE_SPACING: f64 = 2.;
+/// The space left after the text when sizing to content, for the cursor, and
+/// between the cursor and the edges of the box as the text scrolls.
+const CURSOR_MARGIN: f64 = 2.;
+/// The height of a line of a multiline box, relative to the text size.
+const LINE_HEIGHT_FACTOR: f64 = 1.2;
 
 // we send ourselves this when we want to reset blink, which must be done in event.
 const RESET_BLINK: Selector = Selector::new("druid-builtin.reset-textbox-blink");
 
+type ValidatorFn = dyn Fn(&str) -> Result<(), ValidationError>;
+
 /// A widget that allows user text input.
+///
+/// The text can be checked with a validator, set with [`with_validator`].
+/// It is checked when it is committed, either by pressing enter or by moving
+/// the focus away; if it is not valid, the box is drawn with a border of
+/// [`theme::ERROR_COLOR`], along with the error's message if one is shown.
+/// The error can also be set from outside, with the
+/// [`SET_VALIDATION_ERROR`] command. Either way, it is cleared as soon as
+/// the text is edited to be valid again.
+///
+/// By default the box has a fixed width, and scrolls its text horizontally
+/// when it doesn't fit; with [`with_size_to_content`], it grows and shrinks
+/// with its text instead, between a minimum and maximum width.
+///
+/// A box created with [`multiline`] holds several lines of text instead.
+///
+/// [`with_validator`]: #method.with_validator
+/// [`with_size_to_content`]: #method.with_size_to_content
+/// [`multiline`]: #method.multiline
+/// [`theme::ERROR_COLOR`]: ../theme/constant.ERROR_COLOR.html
+/// [`SET_VALIDATION_ERROR`]: ../commands/constant.SET_VALIDATION_ERROR.html
 #[derive(Debug, Clone)]
 pub struct TextBox {
     placeholder: String,
     width: f64,
+    /// The height of the box, not including a validation message.
+    height: f64,
     hscroll_offset: f64,
+    vscroll_offset: f64,
+    /// The position of the cursor in the line of a single-line box, as of
+    /// the last horizontal scroll.
+    cursor_x: f64,
+    multiline: bool,
     selection: Selection,
+    /// What a mouse drag selects by, and the range the drag started with.
+    drag_unit: DragUnit,
+    drag_anchor: Range<usize>,
     cursor_timer: TimerToken,
     cursor_on: bool,
+    validator: Option<Validator>,
+    error: Option<ValidationError>,
+    message: ValidationMessage,
+    /// The minimum and maximum width, when sizing to content.
+    size_to_content: Option<(f64, f64)>,
+    key_bindings: KeyBindings,
+    /// The text of a composition, and the range of its cursor, while one
+    /// is in progress; it is shown in place of the selection.
+    preedit: Option<(String, Range<usize>)>,
+}
+
+/// The reason that the text in a [`TextBox`] is not valid.
+///
+/// [`TextBox`]: struct.TextBox.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    message: String,
+}
+
+/// How a [`TextBox`] shows the message of its [`ValidationError`].
+///
+/// [`TextBox`]: struct.TextBox.html
+/// [`ValidationError`]: struct.ValidationError.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValidationMessage {
+    /// The message is not shown; only the border marks the error.
+    Hidden,
+    /// The message is painted below the box, over whatever is there,
+    /// without affecting layout.
+    Overlay,
+    /// Space for the message is always reserved below the box, so that the
+    /// layout does not change when an error comes and goes.
+    Reserved,
+}
+
+#[derive(Clone)]
+struct Validator(Arc<ValidatorFn>);
+
+/// What dragging the mouse extends the selection by; a double click drags
+/// by words, and a triple click by lines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DragUnit {
+    Grapheme,
+    Word,
+    Line,
+}
+
+/// A line of the text as it is shown.
+struct VisualLine {
+    /// The range of the text on this line, without a newline that ends it.
+    range: Range<usize>,
+    layout: PietTextLayout,
+}
+
+impl ValidationError {
+    /// Create an error with a message for the user.
+    pub fn new(message: impl Into<String>) -> ValidationError {
+        ValidationError {
+            message: message.into(),
+        }
+    }
+
+    /// The message for the user.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Default for ValidationMessage {
+    fn default() -> Self {
+        ValidationMessage::Hidden
+    }
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Validator")
+    }
 }
 
 impl TextBox {
@@ -52,11 +182,41 @@ impl TextBox {
     pub fn new() -> TextBox {
         Self {
             width: 0.0,
+            height: 0.0,
             hscroll_offset: 0.,
+            vscroll_offset: 0.,
+            cursor_x: 0.,
+            multiline: false,
             selection: Selection::caret(0),
+            drag_unit: DragUnit::Grapheme,
+            drag_anchor: 0..0,
             cursor_timer: TimerToken::INVALID,
             cursor_on: false,
             placeholder: String::new(),
+            validator: None,
+            error: None,
+            message: ValidationMessage::default(),
+            size_to_content: None,
+            key_bindings: KeyBindings::default(),
+            preedit: None,
+        }
+    }
+
+    /// Create a TextBox that holds several lines of text.
+    ///
+    /// Enter inserts a newline, and the text is wrapped at the width of the
+    /// box. The box fills the height it is given, and scrolls to keep the
+    /// cursor in view when the text doesn't fit. The up and down arrows move
+    /// between lines, and home and end go to the ends of the line.
+    ///
+    /// Sizing to content, set with [`with_size_to_content`], only applies to
+    /// single-line boxes.
+    ///
+    /// [`with_size_to_content`]: #method.with_size_to_content
+    pub fn multiline() -> TextBox {
+        TextBox {
+            multiline: true,
+            ..TextBox::new()
         }
     }
 
@@ -66,6 +226,69 @@ impl TextBox {
         self
     }
 
+    /// Builder-style method to set a validator for the `TextBox`'s text.
+    ///
+    /// The validator is run whenever the text is committed, and while the
+    /// text is invalid, whenever it is edited.
+    pub fn with_validator(
+        mut self,
+        validator: impl Fn(&str) -> Result<(), ValidationError> + 'static,
+    ) -> Self {
+        self.validator = Some(Validator(Arc::new(validator)));
+        self
+    }
+
+    /// Builder-style method to set how the message of a validation error is
+    /// shown. By default, it is hidden.
+    pub fn with_validation_message(mut self, message: ValidationMessage) -> Self {
+        self.message = message;
+        self
+    }
+
+    /// Builder-style method to make the width of the box follow its text.
+    ///
+    /// The box is as wide as its text, or its placeholder if it is empty, but
+    /// no narrower than `min_width` and no wider than `max_width`; it is laid
+    /// out again as the text changes. Once the text reaches `max_width`, it
+    /// scrolls as usual. The width is still bound by the constraints.
+    pub fn with_size_to_content(mut self, min_width: f64, max_width: f64) -> Self {
+        self.size_to_content = Some((min_width, max_width.max(min_width)));
+        self
+    }
+
+    /// Builder-style method to set the actions that keys perform.
+    ///
+    /// By default, the box uses the platform's usual bindings, from
+    /// [`KeyBindings::default`]. Tab and shift+tab always move the focus,
+    /// and keys without a binding are typed.
+    ///
+    /// [`KeyBindings::default`]: ../struct.KeyBindings.html#impl-Default
+    pub fn with_key_bindings(mut self, key_bindings: KeyBindings) -> Self {
+        self.key_bindings = key_bindings;
+        self
+    }
+
+    /// The selected range of the text, in bytes; it is empty when there is
+    /// just a caret.
+    pub fn selection_range(&self) -> Range<usize> {
+        self.selection.range()
+    }
+
+    /// The current validation error, if the text is not valid.
+    pub fn validation_error(&self) -> Option<&ValidationError> {
+        self.error.as_ref()
+    }
+
+    /// Show an error, or clear it, as if it came from the validator.
+    pub(crate) fn set_validation_error(&mut self, error: Option<ValidationError>) {
+        self.error = error;
+    }
+
+    /// Select a range of the text, in bytes.
+    pub(crate) fn set_selection_range(&mut self, range: Range<usize>) {
+        self.selection = Selection::new(range.start, range.end);
+    }
+
     #[deprecated(since = "0.5.0", note = "Use TextBox::new instead")]
     #[doc(hidden)]
     pub fn raw() -> TextBox {
@@ -88,6 +311,31 @@ impl TextBox {
             .unwrap()
     }
 
+    /// The lines of the text as it is shown: all of it for a single-line box,
+    /// or the wrapped lines of each paragraph for a multiline one.
+    fn visual_lines(&self, piet_text: &mut PietText, text: &str, env: &Env) -> Vec<VisualLine> {
+        let ranges = if self.multiline {
+            let wrap_width = self.width - PADDING_LEFT * 2.;
+            wrap_ranges(text, |line| {
+                self.get_layout(piet_text, line, env).width() <= wrap_width
+            })
+        } else {
+            vec![0..text.len()]
+        };
+        ranges
+            .into_iter()
+            .map(|range| VisualLine {
+                layout: self.get_layout(piet_text, &text[range.clone()], env),
+                range,
+            })
+            .collect()
+    }
+
+    /// The height of a line of a multiline box.
+    fn line_height(env: &Env) -> f64 {
+        env.get(theme::TEXT_SIZE_NORMAL) * LINE_HEIGHT_FACTOR
+    }
+
     /// Insert text at the cursor position.
     /// Replaces selected text if there's a selection.
     fn insert(&mut self, src: &mut String, new: &str) {
@@ -117,6 +365,36 @@ impl TextBox {
         self.selection.end
     }
 
+    /// The text as it is shown, with the composition in place of the
+    /// selection, and the range of the composition in it.
+    fn shown_text<'a>(&self, text: &'a str) -> (Cow<'a, str>, Range<usize>) {
+        match &self.preedit {
+            Some((preedit, _)) => {
+                let selection = self.selection.constrain_to_len(text.len()).range();
+                let shown = format!(
+                    "{}{}{}",
+                    &text[..selection.start],
+                    preedit,
+                    &text[selection.end..]
+                );
+                (
+                    shown.into(),
+                    selection.start..selection.start + preedit.len(),
+                )
+            }
+            None => (text.into(), self.cursor()..self.cursor()),
+        }
+    }
+
+    /// The active edge of the selection as it is shown, which is in the
+    /// composition while there is one.
+    fn shown_cursor(&self) -> usize {
+        match &self.preedit {
+            Some((_, cursor)) => self.selection.min() + cursor.end,
+            None => self.cursor(),
+        }
+    }
+
     /// Edit a selection using a `Movement`.
     fn move_selection(&mut self, mvmnt: Movement, text: &mut String, modify: bool) {
         // This movement function should ensure all movements are legit.
@@ -138,14 +416,106 @@ impl TextBox {
         }
     }
 
+    /// Put the selected text on the clipboard, deleting it if `cut` is set.
+    fn copy_selection(&mut self, text: &mut String, mut clipboard: Clipboard, cut: bool) {
+        if self.selection.is_caret() {
+            return;
+        }
+        if let Some(selected) = text.slice(self.selection.range()) {
+            clipboard.put_string(selected);
+        }
+        if cut {
+            self.delete_backward(text);
+        }
+    }
+
+    /// Replace the selection with the text on the clipboard, if there is any.
+    fn paste(&mut self, text: &mut String, clipboard: &Clipboard) -> bool {
+        match clipboard.get_string() {
+            Some(pasted) => {
+                self.insert(text, &pasted);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the caret to the start or end of its line.
+    fn move_to_line_edge(&mut self, lines: &[VisualLine], text: &mut String, end: bool) {
+        if !self.multiline {
+            let movement = if end {
+                Movement::RightOfLine
+            } else {
+                Movement::LeftOfLine
+            };
+            self.move_selection(movement, text, false);
+            return;
+        }
+        let idx = line_for_offset(lines, self.cursor());
+        let offset = if end {
+            line_end(lines, idx)
+        } else {
+            lines[idx].range.start
+        };
+        self.selection = Selection::caret(offset);
+    }
+
+    /// Move the cursor to the line above or below, keeping its horizontal
+    /// position; past the first or last line, it goes to the end of the text.
+    fn move_vertically(&mut self, lines: &[VisualLine], down: bool, modify: bool) {
+        let idx = line_for_offset(lines, self.cursor());
+        let offset = if !down && idx == 0 {
+            0
+        } else if down && idx + 1 >= lines.len() {
+            lines[lines.len() - 1].range.end
+        } else {
+            let line = &lines[idx];
+            let x = self.x_for_offset(&line.layout, self.cursor() - line.range.start);
+            let target = if down { idx + 1 } else { idx - 1 };
+            let hit_test = lines[target].layout.hit_test_point(Point::new(x, 0.));
+            let offset = lines[target].range.start + hit_test.metrics.text_position;
+            offset.min(line_end(lines, target))
+        };
+        let start = if modify { self.selection.start } else { offset };
+        self.selection = Selection::new(start, offset);
+    }
+
+    /// The range of the unit of text at `offset`, for selecting by the mouse.
+    fn unit_range(unit: DragUnit, text: &str, lines: &[VisualLine], offset: usize) -> Range<usize> {
+        match unit {
+            DragUnit::Grapheme => offset..offset,
+            DragUnit::Word => word_range(text, offset),
+            DragUnit::Line => lines[line_for_offset(lines, offset)].range.clone(),
+        }
+    }
+
+    /// Select from the anchor of a drag to the unit at `offset`; the
+    /// selection covers both, and its active edge is on the side of `offset`.
+    fn drag_to(&mut self, text: &str, lines: &[VisualLine], offset: usize) {
+        let unit = Self::unit_range(self.drag_unit, text, lines, offset);
+        let anchor = self.drag_anchor.clone();
+        self.selection = if unit.start < anchor.start {
+            Selection::new(anchor.end, unit.start)
+        } else {
+            Selection::new(anchor.start, unit.end.max(anchor.end))
+        };
+    }
+
     /// For a given point, returns the corresponding offset (in bytes) of
     /// the grapheme cluster closest to that point.
-    fn offset_for_point(&self, point: Point, layout: &PietTextLayout) -> usize {
+    fn offset_for_point(&self, point: Point, lines: &[VisualLine], env: &Env) -> usize {
+        let (line, y) = if self.multiline {
+            let y = point.y + self.vscroll_offset - PADDING_TOP;
+            let idx = (y / Self::line_height(env)).floor().max(0.) as usize;
+            (&lines[idx.min(lines.len() - 1)], 0.)
+        } else {
+            (&lines[0], point.y)
+        };
         // Translating from screenspace to Piet's text layout representation.
         // We need to account for hscroll_offset state and TextBox's padding.
-        let translated_point = Point::new(point.x + self.hscroll_offset - PADDING_LEFT, point.y);
-        let hit_test = layout.hit_test_point(translated_point);
-        hit_test.metrics.text_position
+        let translated_point = Point::new(point.x + self.hscroll_offset - PADDING_LEFT, y);
+        let hit_test = line.layout.hit_test_point(translated_point);
+        line.range.start + hit_test.metrics.text_position
     }
 
     /// Given an offset (in bytes) of a valid grapheme cluster, return
@@ -159,30 +529,262 @@ impl TextBox {
         }
     }
 
-    /// Calculate a stateful scroll offset
+    /// The line of the cursor at `offset`, before scrolling.
+    fn cursor_line(&self, lines: &[VisualLine], offset: usize, env: &Env) -> Line {
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let idx = line_for_offset(lines, offset);
+        let line = &lines[idx];
+        let cursor_x = self.x_for_offset(&line.layout, offset - line.range.start);
+        let top = PADDING_TOP + idx as f64 * Self::line_height(env);
+        let text_pos = Point::new(0.0 + PADDING_LEFT, font_size * 0.8 + top);
+        let xy = text_pos + Vec2::new(cursor_x, 2. - font_size);
+        Line::new(xy, xy + Vec2::new(0., font_size + 2.))
+    }
+
+    /// Tell the input method where the cursor is shown, so that it can put
+    /// its candidates beside it.
+    fn set_ime_cursor_area(&self, ctx: &mut EventCtx, lines: &[VisualLine], env: &Env) {
+        let line = self.cursor_line(lines, self.shown_cursor(), env);
+        let scroll = Vec2::new(self.hscroll_offset, self.vscroll_offset);
+        ctx.set_ime_cursor_area(Rect::from_points(line.p0, line.p1) - scroll);
+    }
+
+    /// The width of the part of the box that the text is shown in.
+    fn visible_width(&self) -> f64 {
+        self.width - PADDING_LEFT * 2.
+    }
+
+    /// Scroll horizontally to keep the cursor in view.
     fn update_hscroll(&mut self, layout: &PietTextLayout) {
-        let cursor_x = self.x_for_offset(layout, self.cursor());
-        let overall_text_width = layout.width();
-
-        let padding = PADDING_LEFT * 2.;
-        if overall_text_width < self.width {
-            // There's no offset if text is smaller than text box
-            //
-            // [***I*  ]
-            // ^
-            self.hscroll_offset = 0.;
-        } else if cursor_x > self.width + self.hscroll_offset - padding {
-            // If cursor goes past right side, bump the offset
-            //       ->
-            // **[****I]****
-            //   ^
-            self.hscroll_offset = cursor_x - self.width + padding;
-        } else if cursor_x < self.hscroll_offset {
-            // If cursor goes past left side, match the offset
-            //    <-
-            // **[I****]****
-            //   ^
-            self.hscroll_offset = cursor_x
+        let cursor_x = self.x_for_offset(layout, self.shown_cursor());
+        self.cursor_x = cursor_x;
+        let visible = self.visible_width();
+
+        // If the cursor goes past the right side, bump the offset; if it goes
+        // past the left side, match it
+        //       ->            <-
+        // **[****I]**   **[I****]**
+        //   ^               ^
+        let min_offset = cursor_x + CURSOR_MARGIN - visible;
+        let max_offset = cursor_x - CURSOR_MARGIN;
+        self.hscroll_offset = self.hscroll_offset.max(min_offset).min(max_offset);
+
+        // Don't scroll past the end of the text, such as once it gets shorter;
+        // a cursor after trailing spaces is past the end of the layout
+        //
+        // [***I*  ]
+        // ^
+        let text_width = layout.width().max(cursor_x) + CURSOR_MARGIN;
+        let end_offset = (text_width - visible).max(0.);
+        self.hscroll_offset = self.hscroll_offset.min(end_offset).max(0.);
+    }
+
+    /// Where the cursor is in the visible part of the box, and the width of
+    /// that part, as of the last scroll.
+    #[cfg(test)]
+    pub(crate) fn cursor_in_view(&self) -> (f64, f64) {
+        (self.cursor_x - self.hscroll_offset, self.visible_width())
+    }
+
+    /// Scroll to keep the cursor in view.
+    fn update_scroll(&mut self, lines: &[VisualLine], env: &Env) {
+        if !self.multiline {
+            self.update_hscroll(&lines[0].layout);
+            return;
+        }
+        let line_height = Self::line_height(env);
+        let top = line_for_offset(lines, self.shown_cursor()) as f64 * line_height;
+        let bottom = top + line_height;
+        let viewport = self.height - PADDING_TOP * 2.;
+        if bottom > self.vscroll_offset + viewport {
+            self.vscroll_offset = bottom - viewport;
+        } else if top < self.vscroll_offset {
+            self.vscroll_offset = top;
+        }
+        // don't leave space below the text when it gets shorter
+        let max_offset = (lines.len() as f64 * line_height - viewport).max(0.);
+        self.vscroll_offset = self.vscroll_offset.min(max_offset).max(0.);
+    }
+
+    /// Check the text with the validator, if there is one.
+    fn check(&self, text: &str) -> Result<(), ValidationError> {
+        match &self.validator {
+            Some(validator) => (validator.0)(text),
+            None => Ok(()),
+        }
+    }
+
+    /// Validate committed text, returning whether the error changed.
+    ///
+    /// This only sets an error; it is cleared by editing, so that an error
+    /// set by a command survives a commit.
+    fn commit(&mut self, text: &str) -> bool {
+        match self.check(text) {
+            Err(error) if self.error.as_ref() != Some(&error) => {
+                self.error = Some(error);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The height of the validation message, below the box.
+    fn message_height(&self, env: &Env) -> f64 {
+        MESSAGE_SPACING + env.get(theme::TEXT_SIZE_NORMAL) + PADDING_TOP
+    }
+
+    /// Lay out a multiline box, filling the available height.
+    fn layout_multiline(
+        &mut self,
+        layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &str,
+        env: &Env,
+    ) -> Size {
+        let message_height = match self.message {
+            ValidationMessage::Reserved => self.message_height(env),
+            _ => 0.,
+        };
+        let width = if bc.max().width.is_finite() {
+            bc.max().width
+        } else {
+            env.get(theme::WIDE_WIDGET_WIDTH)
+        };
+        self.width = bc.constrain((width, 0.)).width;
+
+        let content = if data.is_empty() {
+            self.placeholder.as_str()
+        } else {
+            data
+        };
+        let lines = self.visual_lines(layout_ctx.text(), content, env);
+        let height = if bc.max().height.is_finite() {
+            bc.max().height
+        } else {
+            lines.len() as f64 * Self::line_height(env) + PADDING_TOP * 2. + message_height
+        };
+
+        let size = bc.constrain((width, height));
+        if self.message == ValidationMessage::Overlay {
+            layout_ctx.set_paint_insets(Insets::new(0., 0., 0., self.message_height(env)));
+        }
+        self.height = size.height - message_height;
+        self.update_scroll(&lines, env);
+        size
+    }
+
+    /// Perform an action bound to a key.
+    fn perform(
+        &mut self,
+        action: &EditAction,
+        ctx: &mut EventCtx,
+        data: &mut String,
+        lines: &[VisualLine],
+    ) {
+        // whether the action moved the cursor or edited the text
+        let moved = match action {
+            EditAction::MoveLeft => {
+                self.move_selection(Movement::Left, data, false);
+                true
+            }
+            EditAction::MoveRight => {
+                self.move_selection(Movement::Right, data, false);
+                true
+            }
+            EditAction::MoveWordLeft => {
+                self.move_selection(Movement::LeftWord, data, false);
+                true
+            }
+            EditAction::MoveWordRight => {
+                self.move_selection(Movement::RightWord, data, false);
+                true
+            }
+            EditAction::MoveToLineStart => {
+                self.move_to_line_edge(lines, data, false);
+                true
+            }
+            EditAction::MoveToLineEnd => {
+                self.move_to_line_edge(lines, data, true);
+                true
+            }
+            EditAction::MoveUp | EditAction::MoveDown if self.multiline => {
+                let down = matches!(action, EditAction::MoveDown);
+                self.move_vertically(lines, down, false);
+                true
+            }
+            EditAction::SelectLeft => {
+                self.move_selection(Movement::Left, data, true);
+                false
+            }
+            EditAction::SelectRight => {
+                self.move_selection(Movement::Right, data, true);
+                false
+            }
+            EditAction::SelectWordLeft => {
+                self.move_selection(Movement::LeftWord, data, true);
+                false
+            }
+            EditAction::SelectWordRight => {
+                self.move_selection(Movement::RightWord, data, true);
+                false
+            }
+            EditAction::SelectUp | EditAction::SelectDown if self.multiline => {
+                let down = matches!(action, EditAction::SelectDown);
+                self.move_vertically(lines, down, true);
+                false
+            }
+            EditAction::MoveUp
+            | EditAction::MoveDown
+            | EditAction::SelectUp
+            | EditAction::SelectDown => false,
+            EditAction::SelectAll => {
+                self.selection.all(data);
+                false
+            }
+            EditAction::DeleteBackward => {
+                self.delete_backward(data);
+                true
+            }
+            EditAction::DeleteForward => {
+                if self.selection.is_caret() {
+                    // Never touch the characters before the cursor.
+                    if data.next_grapheme_offset(self.cursor()).is_some() {
+                        self.move_selection(Movement::Right, data, false);
+                        self.delete_backward(data);
+                    }
+                } else {
+                    self.delete_backward(data);
+                }
+                true
+            }
+            EditAction::Copy => {
+                self.copy_selection(data, ctx.clipboard(), false);
+                false
+            }
+            EditAction::Cut => {
+                self.copy_selection(data, ctx.clipboard(), true);
+                true
+            }
+            EditAction::Paste => self.paste(data, &ctx.clipboard()),
+            // Enter starts a new line in a multiline box; it is handled,
+            // so that it doesn't also activate a default button
+            EditAction::InsertNewline if self.multiline => {
+                self.insert(data, "\n");
+                ctx.set_handled();
+                true
+            }
+            // Enter commits the text
+            EditAction::InsertNewline => {
+                self.commit(data);
+                false
+            }
+            EditAction::Custom(custom) => {
+                custom.run(&mut EditBuffer::new(data, &mut self.selection));
+                true
+            }
+        };
+        if moved {
+            self.reset_cursor_blink(ctx);
         }
     }
 
@@ -194,21 +796,29 @@ impl TextBox {
 }
 
 impl Widget<String> for TextBox {
-    #[allow(clippy::cognitive_complexity)]
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut String, env: &Env) {
         // Guard against external changes in data?
         self.selection = self.selection.constrain_to(data);
 
-        let mut text_layout = self.get_layout(&mut ctx.text(), &data, env);
+        let mut lines = self.visual_lines(&mut ctx.text(), &data, env);
         match event {
             Event::MouseDown(mouse) => {
                 ctx.request_focus();
                 ctx.set_active(true);
-                let cursor_off = self.offset_for_point(mouse.pos, &text_layout);
+                let cursor_off = self.offset_for_point(mouse.pos, &lines, env);
+                self.drag_unit = match mouse.count {
+                    2 => DragUnit::Word,
+                    count if count >= 3 => DragUnit::Line,
+                    _ => DragUnit::Grapheme,
+                };
                 if mouse.mods.shift {
-                    self.selection.end = cursor_off;
+                    // extend from the existing selection
+                    self.drag_anchor = self.selection.start..self.selection.start;
+                    self.drag_to(data, &lines, cursor_off);
                 } else {
                     self.caret_to(data, cursor_off);
+                    self.drag_anchor = Self::unit_range(self.drag_unit, data, &lines, cursor_off);
+                    self.selection = Selection::new(self.drag_anchor.start, self.drag_anchor.end);
                 }
                 ctx.request_paint();
                 self.reset_cursor_blink(ctx);
@@ -216,7 +826,10 @@ impl Widget<String> for TextBox {
             Event::MouseMoved(mouse) => {
                 ctx.set_cursor(&Cursor::IBeam);
                 if ctx.is_active() {
-                    self.selection.end = self.offset_for_point(mouse.pos, &text_layout);
+                    let offset = self.offset_for_point(mouse.pos, &lines, env);
+                    self.drag_to(data, &lines, offset);
+                    // the text scrolls as the selection is dragged past an edge
+                    self.update_scroll(&lines, env);
                     ctx.request_paint();
                 }
             }
@@ -239,148 +852,224 @@ impl Widget<String> for TextBox {
                     && (cmd.selector == crate::commands::COPY
                         || cmd.selector == crate::commands::CUT) =>
             {
-                if let Some(text) = data.slice(self.selection.range()) {
-                    Application::clipboard().put_string(text);
-                }
-                if !self.selection.is_caret() && cmd.selector == crate::commands::CUT {
-                    self.delete_backward(data);
-                }
+                let cut = cmd.selector == crate::commands::CUT;
+                self.copy_selection(data, ctx.clipboard(), cut);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.selector == RESET_BLINK => self.reset_cursor_blink(ctx),
+            Event::Command(cmd) if cmd.selector == crate::commands::SET_VALIDATION_ERROR => {
+                match cmd.get_object::<Option<ValidationError>>() {
+                    Ok(error) => self.error = error.clone(),
+                    Err(e) => log::error!("SET_VALIDATION_ERROR: {}", e),
+                }
+                ctx.request_paint();
+                ctx.set_handled();
+            }
             Event::Paste(ref item) => {
-                if let Some(string) = item.get_string() {
-                    self.insert(data, &string);
+                if self.paste(data, item) {
                     self.reset_cursor_blink(ctx);
+                    lines = self.visual_lines(&mut ctx.text(), &data, env);
+                    self.update_scroll(&lines, env);
                 }
             }
-            //TODO: move this to a 'handle_key' function, remove the #allow above
+            // the text of a composition is typed when it ends
+            Event::KeyDown(key_event) if key_event.is_composing => ctx.set_handled(),
             Event::KeyDown(key_event) => {
-                match key_event {
-                    // Select all (Ctrl+A || Cmd+A)
-                    k_e if (HotKey::new(SysMods::Cmd, "a")).matches(k_e) => {
-                        self.selection.all(data);
-                    }
-                    // Jump left (Ctrl+ArrowLeft || Cmd+ArrowLeft)
-                    k_e if (HotKey::new(SysMods::Cmd, KeyCode::ArrowLeft)).matches(k_e)
-                        || HotKey::new(None, KeyCode::Home).matches(k_e) =>
-                    {
-                        self.move_selection(Movement::LeftOfLine, data, false);
-                        self.reset_cursor_blink(ctx);
-                    }
-                    // Jump right (Ctrl+ArrowRight || Cmd+ArrowRight)
-                    k_e if (HotKey::new(SysMods::Cmd, KeyCode::ArrowRight)).matches(k_e)
-                        || HotKey::new(None, KeyCode::End).matches(k_e) =>
-                    {
-                        self.move_selection(Movement::RightOfLine, data, false);
-                        self.reset_cursor_blink(ctx);
-                    }
-                    // Select left (Shift+ArrowLeft)
-                    k_e if (HotKey::new(SysMods::Shift, KeyCode::ArrowLeft)).matches(k_e) => {
-                        self.move_selection(Movement::Left, data, true);
-                    }
-                    // Select right (Shift+ArrowRight)
-                    k_e if (HotKey::new(SysMods::Shift, KeyCode::ArrowRight)).matches(k_e) => {
-                        self.move_selection(Movement::Right, data, true);
-                    }
-                    // Move left (ArrowLeft)
-                    k_e if (HotKey::new(None, KeyCode::ArrowLeft)).matches(k_e) => {
-                        self.move_selection(Movement::Left, data, false);
-                        self.reset_cursor_blink(ctx);
-                    }
-                    // Move right (ArrowRight)
-                    k_e if (HotKey::new(None, KeyCode::ArrowRight)).matches(k_e) => {
-                        self.move_selection(Movement::Right, data, false);
-                        self.reset_cursor_blink(ctx);
-                    }
-                    // Backspace
-                    k_e if (HotKey::new(None, KeyCode::Backspace)).matches(k_e) => {
-                        self.delete_backward(data);
-                        self.reset_cursor_blink(ctx);
-                    }
-                    // Delete
-                    k_e if (HotKey::new(None, KeyCode::Delete)).matches(k_e) => {
-                        if self.selection.is_caret() {
-                            // Never touch the characters before the cursor.
-                            if data.next_grapheme_offset(self.cursor()).is_some() {
-                                self.move_selection(Movement::Right, data, false);
-                                self.delete_backward(data);
-                            }
-                        } else {
-                            self.delete_backward(data);
-                        }
-                        self.reset_cursor_blink(ctx);
-                    }
-                    // Tab and shift+tab
-                    k_e if HotKey::new(None, KeyCode::Tab).matches(k_e) => ctx.focus_next(),
-                    k_e if HotKey::new(SysMods::Shift, KeyCode::Tab).matches(k_e) => {
-                        ctx.focus_prev()
-                    }
+                // Tab and shift+tab are left unhandled, so the window
+                // can move focus along the focus chain.
+                if HotKey::new(SysMods::None, KbKey::Tab).matches(key_event)
+                    || HotKey::new(SysMods::Shift, KbKey::Tab).matches(key_event)
+                {
+                    return;
+                }
+                let mods = key_event.mods;
+                match self.key_bindings.action(key_event).cloned() {
+                    Some(action) => self.perform(&action, ctx, data, &lines),
                     // Actual typing
-                    k_e if k_e.key_code.is_printable() => {
-                        let incoming_text = k_e.text().unwrap_or("");
-                        self.insert(data, incoming_text);
-                        self.reset_cursor_blink(ctx);
-                    }
-                    _ => {}
+                    None => match &key_event.key {
+                        KbKey::Character(text) if !(mods.ctrl || mods.meta) => {
+                            self.insert(data, text);
+                            self.reset_cursor_blink(ctx);
+                        }
+                        _ => (),
+                    },
                 }
-                text_layout = self.get_layout(&mut ctx.text(), &data, env);
-                self.update_hscroll(&text_layout);
+                lines = self.visual_lines(&mut ctx.text(), &data, env);
+                self.update_scroll(&lines, env);
+                ctx.request_paint();
+            }
+            Event::Composition(CompositionEvent::Start) => {
+                self.preedit = Some((String::new(), 0..0));
+                self.set_ime_cursor_area(ctx, &lines, env);
+                ctx.set_handled();
+            }
+            // the composition is shown in place of the selection, but it
+            // isn't in the data until it ends
+            Event::Composition(CompositionEvent::Update(text, cursor)) => {
+                let cursor = cursor.start.min(text.len())..cursor.end.min(text.len());
+                self.preedit = Some((text.clone(), cursor));
+                let (shown, _) = self.shown_text(data);
+                lines = self.visual_lines(&mut ctx.text(), &shown, env);
+                self.update_scroll(&lines, env);
+                self.set_ime_cursor_area(ctx, &lines, env);
                 ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::Composition(CompositionEvent::End(text)) => {
+                self.preedit = None;
+                self.insert(data, text);
+                self.reset_cursor_blink(ctx);
+                lines = self.visual_lines(&mut ctx.text(), &data, env);
+                self.update_scroll(&lines, env);
+                ctx.request_paint();
+                ctx.set_handled();
             }
             _ => (),
         }
     }
 
-    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &String, _env: &Env) {
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &String, _env: &Env) {
         match event {
-            LifeCycle::WidgetAdded => ctx.register_for_focus(),
+            LifeCycle::WidgetAdded => {
+                ctx.register_for_focus();
+                ctx.set_role(Role::TextBox);
+                ctx.set_value(data.as_str());
+                if !self.placeholder.is_empty() {
+                    ctx.set_label(self.placeholder.as_str());
+                }
+            }
             // an open question: should we be able to schedule timers here?
             LifeCycle::FocusChanged(true) => ctx.submit_command(RESET_BLINK, ctx.widget_id()),
+            // losing focus commits the text
+            LifeCycle::FocusChanged(false) => {
+                if self.preedit.take().is_some() {
+                    ctx.request_paint();
+                }
+                if self.commit(data) {
+                    ctx.request_paint();
+                }
+            }
             _ => (),
         }
     }
 
-    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &String, _data: &String, _env: &Env) {
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &String, data: &String, env: &Env) {
+        if self.error.is_some() && old_data != data && self.check(data).is_ok() {
+            self.error = None;
+        }
+        if old_data != data {
+            ctx.set_value(data.as_str());
+        }
+        // text that is changed from outside may be shorter; a multiline box
+        // scrolls when it is laid out again
+        if old_data != data && !self.multiline {
+            self.selection = self.selection.constrain_to(data);
+            let (shown, _) = self.shown_text(data);
+            let lines = self.visual_lines(&mut ctx.text(), &shown, env);
+            self.update_scroll(&lines, env);
+        }
+        // the size, or the lines, may change with the text
+        if (self.size_to_content.is_some() || self.multiline) && old_data != data {
+            ctx.request_layout();
+        }
+        if ctx.env_key_changed(theme::BORDERED_WIDGET_HEIGHT)
+            || ctx.env_key_changed(theme::WIDE_WIDGET_WIDTH)
+            || ctx.env_key_changed(theme::TEXT_SIZE_NORMAL)
+            || ctx.env_key_changed(theme::FONT_NAME)
+        {
+            ctx.request_layout();
+        }
         ctx.request_paint();
     }
 
     fn layout(
         &mut self,
-        _layout_ctx: &mut LayoutCtx,
+        layout_ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
-        _data: &String,
+        data: &String,
         env: &Env,
     ) -> Size {
-        let width = env.get(theme::WIDE_WIDGET_WIDTH);
+        if self.multiline {
+            return self.layout_multiline(layout_ctx, bc, data, env);
+        }
+
         let height = env.get(theme::BORDERED_WIDGET_HEIGHT);
+        let content_layout = self.size_to_content.map(|(min_width, max_width)| {
+            let content = if data.is_empty() {
+                &self.placeholder
+            } else {
+                data
+            };
+            let layout = self.get_layout(layout_ctx.text(), content, env);
+            let width = layout.width() + PADDING_LEFT * 2. + CURSOR_MARGIN;
+            (layout, width.max(min_width).min(max_width))
+        });
+        let width = match &content_layout {
+            Some((_, width)) => *width,
+            None => env.get(theme::WIDE_WIDGET_WIDTH),
+        };
 
-        let size = bc.constrain((width, height));
+        let message_height = self.message_height(env);
+        let size = match self.message {
+            ValidationMessage::Reserved => bc.constrain((width, height + message_height)),
+            _ => bc.constrain((width, height)),
+        };
+        if self.message == ValidationMessage::Overlay {
+            layout_ctx.set_paint_insets(Insets::new(0., 0., 0., message_height));
+        }
         self.width = size.width;
+        self.height = height;
+        // the baseline of the text, as painted
+        let baseline = PADDING_TOP + env.get(theme::TEXT_SIZE_NORMAL) * 0.8;
+        layout_ctx.set_baseline_offset(size.height - baseline);
+        // the text only scrolls once the box stops growing
+        let layout = match content_layout {
+            Some((layout, _)) => layout,
+            None => self.get_layout(layout_ctx.text(), &self.shown_text(data).0, env),
+        };
+        self.update_hscroll(&layout);
         size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &String, env: &Env) {
+        let (shown, preedit_range) = self.shown_text(data);
         // Guard against changes in data following `event`
-        let content = if data.is_empty() {
-            &self.placeholder
+        let content = if shown.is_empty() {
+            self.placeholder.as_str()
         } else {
-            data
+            &shown
         };
 
-        self.selection = self.selection.constrain_to(content);
+        // while composing, there is just a caret in the composition
+        let selection = if self.preedit.is_some() {
+            Selection::caret(self.shown_cursor())
+        } else {
+            self.selection = self.selection.constrain_to_len(content.len());
+            self.selection
+        };
 
         let font_size = env.get(theme::TEXT_SIZE_NORMAL);
-        let height = env.get(theme::BORDERED_WIDGET_HEIGHT);
+        let height = if self.multiline {
+            self.height
+        } else {
+            env.get(theme::BORDERED_WIDGET_HEIGHT)
+        };
+        let line_height = Self::line_height(env);
         let background_color = env.get(theme::BACKGROUND_LIGHT);
         let selection_color = env.get(theme::SELECTION_COLOR);
-        let text_color = env.get(theme::LABEL_COLOR);
+        let text_color = if ctx.is_disabled() {
+            env.get(theme::DISABLED_TEXT_COLOR)
+        } else {
+            env.get(theme::LABEL_COLOR)
+        };
         let placeholder_color = env.get(theme::PLACEHOLDER_COLOR);
         let cursor_color = env.get(theme::CURSOR_COLOR);
 
         let has_focus = ctx.has_focus();
 
-        let border_color = if has_focus {
+        let border_color = if self.error.is_some() {
+            env.get(theme::ERROR_COLOR)
+        } else if has_focus {
             env.get(theme::PRIMARY_LIGHT)
         } else {
             env.get(theme::BORDER_DARK)
@@ -400,47 +1089,61 @@ impl Widget<String> for TextBox {
             rc.clip(clip_rect);
 
             // Calculate layout
-            let text_layout = self.get_layout(rc.text(), &content, env);
-
-            // Shift everything inside the clip by the hscroll_offset
-            rc.transform(Affine::translate((-self.hscroll_offset, 0.)));
+            let lines = self.visual_lines(rc.text(), &content, env);
 
-            // Draw selection rect
-            if !self.selection.is_caret() {
-                let (left, right) = (self.selection.min(), self.selection.max());
-                let left_offset = self.x_for_offset(&text_layout, left);
-                let right_offset = self.x_for_offset(&text_layout, right);
+            // Shift everything inside the clip by the scroll offsets
+            rc.transform(Affine::translate((
+                -self.hscroll_offset,
+                -self.vscroll_offset,
+            )));
 
-                let selection_width = right_offset - left_offset;
-
-                let selection_pos = Point::new(left_offset + PADDING_LEFT - 1., PADDING_TOP - 2.);
-
-                let selection_rect = RoundedRect::from_origin_size(
-                    selection_pos,
-                    Size::new(selection_width + 2., font_size + 4.).to_vec2(),
-                    1.,
-                );
-                rc.fill(selection_rect, &selection_color);
-            }
-
-            // Layout, measure, and draw text
             let text_height = font_size * 0.8;
-            let text_pos = Point::new(0.0 + PADDING_LEFT, text_height + PADDING_TOP);
-            let color = if data.is_empty() {
+            let color = if shown.is_empty() {
                 &placeholder_color
             } else {
                 &text_color
             };
+            for (idx, line) in lines.iter().enumerate() {
+                let top = PADDING_TOP + idx as f64 * line_height;
 
-            rc.draw_text(&text_layout, text_pos, color);
+                // Draw selection rect
+                let left = selection.min().max(line.range.start);
+                let right = selection.max().min(line.range.end);
+                if !selection.is_caret() && left < right {
+                    let left_offset = self.x_for_offset(&line.layout, left - line.range.start);
+                    let right_offset = self.x_for_offset(&line.layout, right - line.range.start);
 
-            // Paint the cursor if focused and there's no selection
-            if has_focus && self.cursor_on && self.selection.is_caret() {
-                let cursor_x = self.x_for_offset(&text_layout, self.cursor());
-                let xy = text_pos + Vec2::new(cursor_x, 2. - font_size);
-                let x2y2 = xy + Vec2::new(0., font_size + 2.);
-                let line = Line::new(xy, x2y2);
+                    let selection_width = right_offset - left_offset;
+
+                    let selection_pos = Point::new(left_offset + PADDING_LEFT - 1., top - 2.);
+
+                    let selection_rect = RoundedRect::from_origin_size(
+                        selection_pos,
+                        Size::new(selection_width + 2., font_size + 4.).to_vec2(),
+                        1.,
+                    );
+                    rc.fill(selection_rect, &selection_color);
+                }
 
+                // Draw the text
+                let text_pos = Point::new(0.0 + PADDING_LEFT, text_height + top);
+                rc.draw_text(&line.layout, text_pos, color);
+
+                // Underline the composition
+                let left = preedit_range.start.max(line.range.start);
+                let right = preedit_range.end.min(line.range.end);
+                if self.preedit.is_some() && left < right {
+                    let x0 = self.x_for_offset(&line.layout, left - line.range.start);
+                    let x1 = self.x_for_offset(&line.layout, right - line.range.start);
+                    let y = text_pos.y + 2.;
+                    let underline = Line::new((x0 + PADDING_LEFT, y), (x1 + PADDING_LEFT, y));
+                    rc.stroke(underline, color, 1.);
+                }
+            }
+
+            // Paint the cursor if focused and there's no selection
+            if has_focus && self.cursor_on && selection.is_caret() {
+                let line = self.cursor_line(&lines, selection.end, env);
                 rc.stroke(line, &cursor_color, 1.);
             }
             Ok(())
@@ -449,6 +1152,101 @@ impl Widget<String> for TextBox {
 
         // Paint the border
         ctx.stroke(clip_rect, &border_color, BORDER_WIDTH);
+
+        // Paint the validation message below the box
+        if let Some(error) = &self.error {
+            if self.message != ValidationMessage::Hidden {
+                let layout = self.get_layout(ctx.text(), error.message(), env);
+                let y = height + MESSAGE_SPACING + font_size * 0.8;
+                let pos = Point::new(PADDING_LEFT, y);
+                ctx.draw_text(&layout, pos, &env.get(theme::ERROR_COLOR));
+            }
+        }
+    }
+}
+
+/// Break `text` into the ranges of its lines, at newlines and after spaces, so
+/// that each line `fits` unless it is a single word that doesn't.
+///
+/// The newlines are not in any range; a line that is wrapped ends with the
+/// space it was wrapped at.
+fn wrap_ranges(text: &str, mut fits: impl FnMut(&str) -> bool) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut paragraph_start = 0;
+    for paragraph in text.split('\n') {
+        let end = paragraph_start + paragraph.len();
+        let mut line_start = paragraph_start;
+        // the end of the words that fit on the line so far
+        let mut line_end = paragraph_start;
+        let breaks = paragraph
+            .match_indices(' ')
+            .map(|(idx, _)| paragraph_start + idx + 1)
+            .chain(std::iter::once(end));
+        for brk in breaks {
+            if brk == line_end {
+                continue;
+            }
+            if line_end > line_start && !fits(text[line_start..brk].trim_end()) {
+                lines.push(line_start..line_end);
+                line_start = line_end;
+            }
+            line_end = brk;
+        }
+        lines.push(line_start..end);
+        paragraph_start = end + 1;
+    }
+    lines
+}
+
+/// The range of the word at `offset`, or of the whitespace between words
+/// if that is where `offset` is.
+///
+/// An offset at the end of a word, or at the end of the text, is in the
+/// word before it.
+fn word_range(text: &str, offset: usize) -> Range<usize> {
+    let offset = offset.min(text.len());
+    let class = |c: char| c.is_whitespace();
+    // the character that the offset is in
+    let here = text[offset..]
+        .chars()
+        .next()
+        .filter(|c| !class(*c))
+        .or_else(|| text[..offset].chars().next_back())
+        .or_else(|| text[offset..].chars().next());
+    let here = match here {
+        Some(c) => class(c),
+        None => return offset..offset,
+    };
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| class(*c) != here)
+        .map(|(idx, c)| idx + c.len_utf8())
+        .unwrap_or(0);
+    let end = text[offset..]
+        .char_indices()
+        .find(|(_, c)| class(*c) != here)
+        .map(|(idx, _)| offset + idx)
+        .unwrap_or_else(|| text.len());
+    start..end
+}
+
+/// The index of the line with the caret at `offset`; a caret where a line is
+/// wrapped is at the start of the next line.
+fn line_for_offset(lines: &[VisualLine], offset: usize) -> usize {
+    lines
+        .iter()
+        .rposition(|line| line.range.start <= offset)
+        .unwrap_or(0)
+}
+
+/// The last caret position on a line, before the space that a wrapped line
+/// ends with.
+fn line_end(lines: &[VisualLine], idx: usize) -> usize {
+    let end = lines[idx].range.end;
+    match lines.get(idx + 1) {
+        Some(next) if next.range.start == end => end - 1,
+        _ => end,
     }
 }
 
@@ -460,7 +1258,17 @@ impl Default for TextBox {
 
 #[cfg(test)]
 mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
     use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Align, Controller, Flex, SizedBox, WidgetExt};
+    use crate::{
+        commands, Code, Command, Data, KeyEvent, KeyModifiers, MouseButton, MouseButtons,
+        MouseEvent, RawMods, Target, UnitPoint, WidgetId,
+    };
 
     /// Test that when data is mutated externally widget
     /// can still be used to insert characters.
@@ -485,6 +1293,42 @@ mod tests {
         widget.insert(&mut data, "a");
     }
 
+    #[test]
+    fn word_ranges() {
+        let text = "one  two";
+        assert_eq!(word_range(text, 0), 0..3);
+        assert_eq!(word_range(text, 2), 0..3);
+        // the end of a word is still in it
+        assert_eq!(word_range(text, 3), 0..3);
+        assert_eq!(word_range(text, 4), 3..5);
+        assert_eq!(word_range(text, 5), 5..8);
+        assert_eq!(word_range(text, 8), 5..8);
+        assert_eq!(word_range("", 0), 0..0);
+        assert_eq!(word_range("né", 1), 0..3);
+    }
+
+    #[test]
+    fn wrap_ranges_keep_offsets() {
+        // lines of up to 8 bytes fit
+        let fits = |line: &str| line.len() <= 8;
+        let text = "one two three\n\nfour";
+        let lines: Vec<&str> = wrap_ranges(text, fits)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect();
+        assert_eq!(lines, vec!["one two ", "three", "", "four"]);
+
+        // a word that doesn't fit gets a line of its own
+        let text = "a verylongword b";
+        let lines: Vec<&str> = wrap_ranges(text, fits)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect();
+        assert_eq!(lines, vec!["a ", "verylongword ", "b"]);
+
+        assert_eq!(wrap_ranges("", fits), vec![0..0]);
+    }
+
     /// Test backspace on the combo character o̷
     #[test]
     fn backspace_combining() {
@@ -519,4 +1363,637 @@ mod tests {
         widget.delete_backward(&mut data);
         assert_eq!(data, String::from(""));
     }
+
+    /// Records a `TextBox`'s validation message after every pass.
+    struct ValidationState(Rc<RefCell<Option<String>>>);
+
+    impl ValidationState {
+        fn record(&self, child: &TextBox) {
+            *self.0.borrow_mut() = child.validation_error().map(|e| e.message().to_string());
+        }
+    }
+
+    impl Controller<String, TextBox> for ValidationState {
+        fn event(
+            &mut self,
+            child: &mut TextBox,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut String,
+            env: &Env,
+        ) {
+            child.event(ctx, event, data, env);
+            self.record(child);
+        }
+
+        fn lifecycle(
+            &mut self,
+            child: &mut TextBox,
+            ctx: &mut LifeCycleCtx,
+            event: &LifeCycle,
+            data: &String,
+            env: &Env,
+        ) {
+            child.lifecycle(ctx, event, data, env);
+            self.record(child);
+        }
+
+        fn update(
+            &mut self,
+            child: &mut TextBox,
+            ctx: &mut UpdateCtx,
+            old_data: &String,
+            data: &String,
+            env: &Env,
+        ) {
+            child.update(ctx, old_data, data, env);
+            self.record(child);
+        }
+    }
+
+    fn digits_only(text: &str) -> Result<(), ValidationError> {
+        if text.chars().all(|c| c.is_ascii_digit()) {
+            Ok(())
+        } else {
+            Err(ValidationError::new("digits only"))
+        }
+    }
+
+    #[test]
+    fn textbox_validation_states() {
+        let error = Rc::new(RefCell::new(None));
+        let validated = TextBox::new()
+            .with_validator(digits_only)
+            .controller(ValidationState(error.clone()));
+        let widget = Flex::column()
+            .with_child(validated, 0.0)
+            .with_child(TextBox::new(), 0.0);
+
+        Harness::create(String::new(), widget, |harness| {
+            harness.send_initial_events();
+            harness.event(key_down("\t", Code::Tab));
+
+            // invalid text is only marked once it is committed
+            harness.event(key_down("1", Code::Key1));
+            harness.event(key_down("a", Code::KeyA));
+            assert_eq!(harness.data(), "1a");
+            assert_eq!(*error.borrow(), None);
+            harness.event(key_down("\r", Code::Return));
+            assert_eq!(harness.data(), "1a");
+            assert_eq!(error.borrow().as_deref(), Some("digits only"));
+
+            // and cleared as soon as it is valid again
+            harness.event(key_down("", Code::Backspace));
+            assert_eq!(harness.data(), "1");
+            assert_eq!(*error.borrow(), None);
+
+            // moving the focus away also commits
+            harness.event(key_down("b", Code::KeyB));
+            assert_eq!(*error.borrow(), None);
+            harness.event(key_down("\t", Code::Tab));
+            assert_eq!(error.borrow().as_deref(), Some("digits only"));
+        })
+    }
+
+    #[test]
+    fn textbox_validation_from_command() {
+        let id = WidgetId::next();
+        let error = Rc::new(RefCell::new(None));
+        let widget = TextBox::new()
+            .with_validator(digits_only)
+            .controller(ValidationState(error.clone()))
+            .with_id(id);
+
+        Harness::create("1".to_string(), widget, |harness| {
+            harness.send_initial_events();
+            harness.event(key_down("\t", Code::Tab));
+
+            // the text is valid, but the server says otherwise
+            let taken = Some(ValidationError::new("already taken"));
+            harness.submit_command(Command::new(commands::SET_VALIDATION_ERROR, taken), id);
+            assert_eq!(error.borrow().as_deref(), Some("already taken"));
+
+            // committing doesn't clear it, but editing does
+            harness.event(key_down("\r", Code::Return));
+            assert_eq!(error.borrow().as_deref(), Some("already taken"));
+            harness.event(key_down("", Code::End));
+            harness.event(key_down("2", Code::Key2));
+            assert_eq!(harness.data(), "12");
+            assert_eq!(*error.borrow(), None);
+
+            // and it can be cleared with the command
+            let taken = Some(ValidationError::new("already taken"));
+            harness.submit_command(Command::new(commands::SET_VALIDATION_ERROR, taken), id);
+            assert!(error.borrow().is_some());
+            let cleared: Option<ValidationError> = None;
+            harness.submit_command(Command::new(commands::SET_VALIDATION_ERROR, cleared), id);
+            assert_eq!(*error.borrow(), None);
+        })
+    }
+
+    #[test]
+    fn textbox_clipboard_editing() {
+        fn cmd_key(text: &'static str, code: Code) -> Event {
+            let mods = KeyModifiers {
+                ctrl: cfg!(not(target_os = "macos")),
+                meta: cfg!(target_os = "macos"),
+                ..Default::default()
+            };
+            Event::KeyDown(KeyEvent::for_test(mods, text, code))
+        }
+
+        let id = WidgetId::next();
+        let textbox = TextBox::new().with_id(id);
+        Harness::create("hello world".to_string(), textbox, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let center = harness.get_state(id).layout_rect.center();
+            click(harness, center);
+            let mut clipboard = harness.clipboard();
+
+            // without a selection, there is nothing to copy
+            harness.event(cmd_key("c", Code::KeyC));
+            assert_eq!(clipboard.get_string(), None);
+
+            harness.event(cmd_key("a", Code::KeyA));
+            harness.event(cmd_key("c", Code::KeyC));
+            assert_eq!(clipboard.get_string(), Some("hello world".to_string()));
+            assert_eq!(harness.data(), "hello world");
+
+            harness.event(cmd_key("x", Code::KeyX));
+            assert_eq!(harness.data(), "");
+
+            harness.event(cmd_key("v", Code::KeyV));
+            harness.event(cmd_key("v", Code::KeyV));
+            assert_eq!(harness.data(), "hello worldhello world");
+
+            // the edit menu's commands go to the focused text box
+            harness.event(cmd_key("a", Code::KeyA));
+            harness.submit_command(commands::CUT, Target::Auto);
+            assert_eq!(harness.data(), "");
+            assert_eq!(
+                clipboard.get_string(),
+                Some("hello worldhello world".to_string())
+            );
+
+            // the paste replaces the selection
+            clipboard.put_string("pasted");
+            harness.event(Event::Paste(clipboard.clone()));
+            harness.event(cmd_key("a", Code::KeyA));
+            harness.event(cmd_key("v", Code::KeyV));
+            assert_eq!(harness.data(), "pasted");
+        })
+    }
+
+    #[test]
+    fn textbox_sizes_to_content() {
+        fn width(harness: &mut Harness<String>, id: WidgetId) -> f64 {
+            harness.get_state(id).size().width
+        }
+
+        let (textbox_id, next_id) = widget_id2();
+        let widget = Flex::row()
+            .with_child(
+                TextBox::new()
+                    .with_size_to_content(40., 120.)
+                    .with_id(textbox_id),
+                0.0,
+            )
+            .with_child(SizedBox::empty().width(10.).with_id(next_id), 0.0);
+
+        Harness::create(String::new(), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let empty_width = width(harness, textbox_id);
+            assert_eq!(empty_width, 40.);
+            click(harness, Point::new(10., 10.));
+
+            // typing grows the box, and the row reflows, until the maximum
+            let mut widths = vec![empty_width];
+            for _ in 0..30 {
+                harness.event(key_down("w", Code::KeyW));
+                harness.just_layout();
+                let next_x = harness.get_state(next_id).layout_rect.x0;
+                assert_eq!(next_x, width(harness, textbox_id));
+                widths.push(width(harness, textbox_id));
+            }
+            assert_eq!(harness.data().len(), 30);
+            assert!(widths.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(widths.iter().any(|w| *w > 40. && *w < 120.));
+            assert_eq!(*widths.last().unwrap(), 120.);
+
+            // deleting shrinks it back down
+            for _ in 0..30 {
+                harness.event(key_down("", Code::Backspace));
+            }
+            harness.just_layout();
+            assert_eq!(harness.data(), "");
+            assert_eq!(width(harness, textbox_id), empty_width);
+            assert_eq!(harness.get_state(next_id).layout_rect.x0, empty_width);
+        })
+    }
+
+    /// Records whether the child handled each key press.
+    struct HandledKeys(Rc<RefCell<Vec<bool>>>);
+
+    impl<T, W: Widget<T>> Controller<T, W> for HandledKeys {
+        fn event(
+            &mut self,
+            child: &mut W,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut T,
+            env: &Env,
+        ) {
+            child.event(ctx, event, data, env);
+            if let Event::KeyDown(_) = event {
+                self.0.borrow_mut().push(ctx.is_handled());
+            }
+        }
+    }
+
+    #[test]
+    fn multiline_textbox_newlines() {
+        let handled: Rc<RefCell<Vec<bool>>> = Default::default();
+        let textbox = TextBox::multiline().controller(HandledKeys(handled.clone()));
+        Harness::create(String::new(), textbox, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            click(harness, Point::new(10., 10.));
+
+            harness.event(key_down("a", Code::KeyA));
+            harness.event(key_down("", Code::Return));
+            harness.event(key_down("b", Code::KeyB));
+            assert_eq!(harness.data(), "a\nb");
+            // enter is handled, so that it doesn't reach a default button
+            assert_eq!(*handled.borrow(), vec![false, true, false]);
+
+            // the caret moves between the lines, keeping its position on the line
+            harness.event(key_down("", Code::ArrowUp));
+            harness.event(key_down("c", Code::KeyC));
+            assert_eq!(harness.data(), "ac\nb");
+            harness.event(key_down("", Code::ArrowLeft));
+            harness.event(key_down("", Code::ArrowLeft));
+            harness.event(key_down("", Code::ArrowDown));
+            harness.event(key_down("d", Code::KeyD));
+            assert_eq!(harness.data(), "ac\ndb");
+
+            // home and end go to the ends of the line
+            harness.event(key_down("", Code::End));
+            harness.event(key_down("e", Code::KeyE));
+            harness.event(key_down("", Code::ArrowUp));
+            harness.event(key_down("", Code::Home));
+            harness.event(key_down("f", Code::KeyF));
+            assert_eq!(harness.data(), "fac\ndbe");
+        });
+
+        // a single-line box leaves enter unhandled, and ignores up and down
+        let handled: Rc<RefCell<Vec<bool>>> = Default::default();
+        let textbox = TextBox::new().controller(HandledKeys(handled.clone()));
+        Harness::create("ab".to_string(), textbox, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            click(harness, Point::new(1., 10.));
+            harness.event(key_down("", Code::Return));
+            harness.event(key_down("", Code::ArrowDown));
+            harness.event(key_down("c", Code::KeyC));
+            assert_eq!(harness.data(), "cab");
+            assert!(!handled.borrow()[0]);
+        });
+    }
+
+    #[test]
+    fn multiline_textbox_wrapped_lines() {
+        let id = WidgetId::next();
+        let textbox = TextBox::multiline()
+            .with_id(id)
+            .fix_width(100.)
+            .fix_height(200.);
+        let widget = Flex::column().with_child(textbox, 0.0);
+        let text = "aaaa bbbb cccc dddd eeee ffff".to_string();
+        Harness::create(text, widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            // it fills the height it is given
+            assert_eq!(harness.get_state(id).size(), Size::new(100., 200.));
+            click(harness, Point::new(1., 10.));
+            harness.event(key_down("", Code::ArrowUp));
+
+            // down goes to the start of the next wrapped line
+            harness.event(key_down("", Code::ArrowDown));
+            harness.event(key_down("X", Code::KeyX));
+            let x = harness.data().find('X').unwrap();
+            assert!(x > 0);
+            assert_eq!(&harness.data()[x - 1..x], " ");
+
+            // and up goes back to the first line
+            harness.event(key_down("", Code::ArrowUp));
+            harness.event(key_down("", Code::Home));
+            harness.event(key_down("Y", Code::KeyY));
+            assert!(harness.data().starts_with("Yaaaa "));
+        });
+    }
+
+    #[test]
+    fn textbox_key_bindings() {
+        fn cmd_a() -> Event {
+            Event::KeyDown(KeyEvent::for_test(
+                RawMods::from(SysMods::Cmd),
+                "a",
+                Code::KeyA,
+            ))
+        }
+
+        fn type_after_cmd_a(textbox: TextBox) -> String {
+            let mut result = String::new();
+            Harness::create("abc".to_string(), textbox, |harness| {
+                harness.send_initial_events();
+                harness.just_layout();
+                click(harness, Point::new(100., 10.));
+                harness.event(cmd_a());
+                harness.event(key_down("x", Code::KeyX));
+                result = harness.data().clone();
+            });
+            result
+        }
+
+        // by default, the shortcut selects all, so typing replaces the text
+        assert_eq!(type_after_cmd_a(TextBox::new()), "x");
+
+        // rebound, it moves to the start instead
+        let bindings = KeyBindings::default()
+            .with_binding(HotKey::new(SysMods::Cmd, "a"), EditAction::MoveToLineStart);
+        assert_eq!(
+            type_after_cmd_a(TextBox::new().with_key_bindings(bindings)),
+            "xabc"
+        );
+
+        // a custom action gets the text and its selection
+        let bindings = KeyBindings::default().with_binding(
+            HotKey::new(SysMods::Cmd, "a"),
+            EditAction::custom(|buffer| {
+                let end = buffer.text().len();
+                buffer.set_selection(end - 1, end);
+                buffer.replace_selection("!");
+            }),
+        );
+        assert_eq!(
+            type_after_cmd_a(TextBox::new().with_key_bindings(bindings)),
+            "ab!x"
+        );
+    }
+
+    #[test]
+    fn textbox_unbound_keys_are_typed() {
+        // without bindings, the arrows do nothing, and letters are still typed
+        let textbox = TextBox::new().with_key_bindings(KeyBindings::empty());
+        Harness::create("ab".to_string(), textbox, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            click(harness, Point::new(100., 10.));
+            harness.event(key_down("", Code::ArrowLeft));
+            harness.event(key_down("", Code::Backspace));
+            harness.event(key_down("c", Code::KeyC));
+            assert_eq!(harness.data(), "abc");
+        });
+    }
+
+    /// Records a `TextBox`'s selection after every event.
+    struct SelectionState(Rc<RefCell<std::ops::Range<usize>>>);
+
+    impl Controller<String, TextBox> for SelectionState {
+        fn event(
+            &mut self,
+            child: &mut TextBox,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut String,
+            env: &Env,
+        ) {
+            child.event(ctx, event, data, env);
+            *self.0.borrow_mut() = child.selection_range();
+        }
+    }
+
+    /// Press the left button at `x` in a single-line box, as the `count`th
+    /// click, releasing it at `release_x`.
+    fn text_click<T: Data>(
+        harness: &mut Harness<T>,
+        x: f64,
+        release_x: f64,
+        count: u32,
+        shift: bool,
+    ) {
+        let mouse = |x, buttons| MouseEvent {
+            pos: Point::new(x, 10.),
+            window_pos: Point::new(x, 10.),
+            mods: KeyModifiers {
+                shift,
+                ..KeyModifiers::default()
+            },
+            count,
+            button: MouseButton::Left,
+            buttons,
+            focus: false,
+        };
+        let left = MouseButtons::from(MouseButton::Left);
+        harness.event(Event::MouseMoved(mouse(x, MouseButtons::new())));
+        harness.event(Event::MouseDown(mouse(x, left)));
+        harness.event(Event::MouseMoved(mouse(release_x, left)));
+        harness.event(Event::MouseUp(mouse(release_x, MouseButtons::new())));
+    }
+
+    #[test]
+    fn textbox_mouse_selection() {
+        // far enough to the left and right to hit the ends of the text
+        const START: f64 = 1.;
+        const END: f64 = 390.;
+        let selection: Rc<RefCell<std::ops::Range<usize>>> = Default::default();
+        let textbox = TextBox::new().controller(SelectionState(selection.clone()));
+
+        Harness::create("hello world".to_string(), textbox, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            // a click places the caret, and a drag selects
+            text_click(harness, START, START, 1, false);
+            assert_eq!(*selection.borrow(), 0..0);
+            text_click(harness, START, END, 1, false);
+            assert_eq!(*selection.borrow(), 0..11);
+
+            // shift-click extends from the caret, in either direction
+            text_click(harness, END, END, 1, false);
+            text_click(harness, START, START, 1, true);
+            assert_eq!(*selection.borrow(), 0..11);
+
+            // a double click selects a word, and typing replaces it
+            text_click(harness, START, START, 1, false);
+            text_click(harness, START, START, 2, false);
+            assert_eq!(*selection.borrow(), 0..5);
+            harness.event(key_down("b", Code::KeyB));
+            harness.event(key_down("y", Code::KeyY));
+            harness.event(key_down("e", Code::KeyE));
+            assert_eq!(harness.data(), "bye world");
+            assert_eq!(*selection.borrow(), 3..3);
+
+            // dragging after a double click extends by words
+            text_click(harness, START, START, 1, false);
+            text_click(harness, START, END, 2, false);
+            assert_eq!(*selection.borrow(), 0..9);
+
+            // a triple click selects the line, and backspace deletes it
+            text_click(harness, END, END, 1, false);
+            text_click(harness, END, END, 2, false);
+            assert_eq!(*selection.borrow(), 4..9);
+            text_click(harness, END, END, 3, false);
+            assert_eq!(*selection.borrow(), 0..9);
+            harness.event(key_down("", Code::Backspace));
+            assert_eq!(harness.data(), "");
+        });
+    }
+
+    #[test]
+    fn textbox_types_compositions() {
+        let id = WidgetId::next();
+        Harness::create(String::new(), TextBox::new().with_id(id), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let center = harness.get_state(id).layout_rect.center();
+            click(harness, center);
+
+            // a dead key, then the letter it accents
+            let mut dead = KeyEvent::for_test(KeyModifiers::default(), "", Code::KeyU);
+            dead.key = KbKey::Dead;
+            harness.event(Event::KeyDown(dead));
+            harness.event(Event::Composition(CompositionEvent::Start));
+            harness.event(Event::Composition(CompositionEvent::Update(
+                "¨".into(),
+                2..2,
+            )));
+            let mut letter = KeyEvent::for_test(KeyModifiers::default(), "o", Code::KeyO);
+            letter.is_composing = true;
+            harness.event(Event::KeyDown(letter));
+            assert_eq!(harness.data(), "");
+
+            harness.event(Event::Composition(CompositionEvent::Update(
+                "ö".into(),
+                2..2,
+            )));
+            harness.event(Event::Composition(CompositionEvent::End("ö".into())));
+            assert_eq!(harness.data(), "ö");
+
+            // a held key types each time it repeats
+            let mut held = KeyEvent::for_test(KeyModifiers::default(), "x", Code::KeyX);
+            harness.event(Event::KeyDown(held.clone()));
+            held.repeat = true;
+            harness.event(Event::KeyDown(held.clone()));
+            harness.event(Event::KeyDown(held));
+            assert_eq!(harness.data(), "öxxx");
+        })
+    }
+
+    #[test]
+    fn textbox_shows_input_method_preedit() {
+        let id = WidgetId::next();
+        Harness::create(String::new(), TextBox::new().with_id(id), |harness| {
+            harness.set_initial_size(Size::new(200., 40.));
+            harness.send_initial_events();
+            harness.just_layout();
+            let center = harness.get_state(id).layout_rect.center();
+            click(harness, center);
+            let blank = harness.render_to_image();
+
+            harness.event(Event::Composition(CompositionEvent::Start));
+            harness.event(Event::Composition(CompositionEvent::Update(
+                "k".into(),
+                1..1,
+            )));
+            let first = harness.render_to_image();
+            assert_eq!(harness.data(), "");
+            assert_ne!(first, blank);
+
+            // the preedit is replaced, not appended to
+            let update = CompositionEvent::Update("日本".into(), 6..6);
+            harness.event(Event::Composition(update));
+            let second = harness.render_to_image();
+            assert_eq!(harness.data(), "");
+            assert_ne!(second, first);
+
+            // the committed text replaces it, and isn't underlined
+            harness.event(Event::Composition(CompositionEvent::End("日本".into())));
+            assert_eq!(harness.data(), "日本");
+            assert_ne!(harness.render_to_image(), second);
+
+            harness.event(key_down("a", Code::KeyA));
+            assert_eq!(harness.data(), "日本a");
+        })
+    }
+
+    /// Records where a `TextBox`'s cursor is in the visible part of the box, and
+    /// the width of that part, after every event.
+    struct CursorInView(Rc<Cell<(f64, f64)>>);
+
+    impl Controller<String, TextBox> for CursorInView {
+        fn event(
+            &mut self,
+            child: &mut TextBox,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut String,
+            env: &Env,
+        ) {
+            child.event(ctx, event, data, env);
+            self.0.set(child.cursor_in_view());
+        }
+    }
+
+    #[test]
+    fn textbox_scrolls_to_keep_the_cursor_in_view() {
+        let cursor = Rc::new(Cell::new((0., 0.)));
+        let textbox = TextBox::new()
+            .controller(CursorInView(cursor.clone()))
+            .fix_width(60.);
+        let widget = Align::new(UnitPoint::TOP_LEFT, textbox);
+        let rec = cursor.clone();
+        let assert_in_view = move |what: &str| {
+            let (x, visible) = rec.get();
+            assert!(
+                x >= 0. && x <= visible,
+                "cursor at {} of {} after {}",
+                x,
+                visible,
+                what
+            );
+        };
+
+        Harness::create(String::new(), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            click(harness, Point::new(10., 10.));
+
+            let text = "the quick brown fox jumps over the lazy dog   ";
+            for (i, c) in text.char_indices() {
+                let c = &text[i..i + c.len_utf8()];
+                harness.event(key_down(c, Code::KeyA));
+                assert_in_view(&format!("typing {:?}", &text[..=i]));
+            }
+            assert_eq!(harness.data(), text);
+            // the text is much wider than the box, and has scrolled with the cursor
+            assert!(cursor.get().0 > cursor.get().1 / 2.);
+
+            harness.event(key_down("", Code::Home));
+            assert_in_view("home");
+            assert_eq!(cursor.get().0, 0.);
+            harness.event(key_down("", Code::End));
+            assert_in_view("end");
+            assert!(cursor.get().0 > cursor.get().1 / 2.);
+
+            // it scrolls back as the text gets shorter
+            for _ in 0..text.len() {
+                harness.event(key_down("", Code::Backspace));
+                assert_in_view(&format!("deleting to {:?}", harness.data()));
+            }
+            assert_eq!(cursor.get().0, 0.);
+        });
+    }
 }
@@ -0,0 +1,336 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A toolbar that moves the items that don't fit into an overflow menu.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::widget::Button;
+use crate::{
+    commands, BoxConstraints, Command, ContextMenu, Data, Env, Event, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, MenuDesc, MenuItem, PaintCtx, UpdateCtx, Widget, WidgetPod,
+};
+
+/// The space between the items of a `Toolbar`.
+const ITEM_SPACING: f64 = 4.0;
+/// The label of the button that opens the overflow menu.
+const OVERFLOW_LABEL: &str = "\u{2026}";
+
+/// A row of items that collapses the trailing items that don't fit into
+/// an overflow menu.
+///
+/// Each item has a widget, shown inline, and a [`MenuItem`], shown in the
+/// overflow menu instead when the widget doesn't fit. The menu item should
+/// submit the same command as the widget, so that the item behaves the same
+/// wherever it is; its command is sent to the window.
+///
+/// The items are laid out from left to right at their natural size. If they
+/// don't all fit in the width of the toolbar, a "…" button is shown after the
+/// ones that do, which opens the overflow menu. This is re-evaluated in each
+/// layout, so items move in and out of the menu as the toolbar is resized.
+///
+/// Items in the overflow menu are not painted and don't get mouse events.
+///
+/// [`MenuItem`]: ../struct.MenuItem.html
+pub struct Toolbar<T> {
+    items: Vec<ToolbarItem<T>>,
+    overflow: WidgetPod<T, Button<T>>,
+    /// Set by the overflow button when it is clicked.
+    overflow_clicked: Rc<Cell<bool>>,
+    /// The number of items, from the start, that are shown inline.
+    inline_count: usize,
+}
+
+struct ToolbarItem<T> {
+    widget: WidgetPod<T, Box<dyn Widget<T>>>,
+    menu_item: MenuItem<T>,
+}
+
+impl<T: Data> Toolbar<T> {
+    /// Create an empty toolbar.
+    pub fn new() -> Toolbar<T> {
+        let overflow_clicked = Rc::new(Cell::new(false));
+        let clicked = overflow_clicked.clone();
+        let overflow = Button::new(OVERFLOW_LABEL, move |_, _, _| clicked.set(true));
+        Toolbar {
+            items: Vec::new(),
+            overflow: WidgetPod::new(overflow),
+            overflow_clicked,
+            inline_count: 0,
+        }
+    }
+
+    /// Builder-style variant of `add_item`.
+    pub fn with_item(mut self, widget: impl Widget<T> + 'static, menu_item: MenuItem<T>) -> Self {
+        self.add_item(widget, menu_item);
+        self
+    }
+
+    /// Add an item, with the widget that shows it inline and the menu item
+    /// that shows it in the overflow menu.
+    pub fn add_item(&mut self, widget: impl Widget<T> + 'static, menu_item: MenuItem<T>) {
+        self.items.push(ToolbarItem {
+            widget: WidgetPod::new(widget).boxed(),
+            menu_item,
+        });
+    }
+
+    fn has_overflow(&self) -> bool {
+        self.inline_count < self.items.len()
+    }
+
+    /// Show the overflow menu below the overflow button.
+    fn show_overflow_menu(&self, ctx: &mut EventCtx, window_origin: Point) {
+        let menu = self.items[self.inline_count..]
+            .iter()
+            .fold(MenuDesc::empty(), |menu, item| {
+                menu.append(item.menu_item.clone())
+            });
+        let button_rect = self.overflow.layout_rect();
+        let location = window_origin + Point::new(button_rect.x0, button_rect.y1).to_vec2();
+        // with no target, the items' commands are sent to the window
+        let cmd = Command::new(
+            commands::SHOW_CONTEXT_MENU,
+            ContextMenu::new(menu, location),
+        );
+        ctx.submit_command(cmd, ctx.window_id());
+    }
+}
+
+impl<T: Data> Default for Toolbar<T> {
+    fn default() -> Self {
+        Toolbar::new()
+    }
+}
+
+impl<T: Data> Widget<T> for Toolbar<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        for item in &mut self.items {
+            item.widget.event(ctx, event, data, env);
+        }
+        if self.has_overflow() {
+            self.overflow.event(ctx, event, data, env);
+        }
+        if self.overflow_clicked.replace(false) {
+            if let Event::MouseUp(_) = event {
+                let window_origin = ctx.to_window(Point::ORIGIN);
+                self.show_overflow_menu(ctx, window_origin);
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        for item in &mut self.items {
+            item.widget.lifecycle(ctx, event, data, env);
+        }
+        self.overflow.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        for item in &mut self.items {
+            item.widget.update(ctx, data, env);
+        }
+        self.overflow.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &T,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("Toolbar");
+
+        let max_width = bc.max().width;
+        let child_bc =
+            BoxConstraints::new(Size::ZERO, Size::new(std::f64::INFINITY, bc.max().height));
+
+        // Measure everything.
+        let sizes: Vec<Size> = self
+            .items
+            .iter_mut()
+            .map(|item| item.widget.layout(layout_ctx, &child_bc, data, env))
+            .collect();
+        let overflow_size = self.overflow.layout(layout_ctx, &child_bc, data, env);
+
+        // Find the items that fit: all of them, or as many as fit next to the
+        // overflow button.
+        let total_width = sizes.iter().map(|size| size.width).sum::<f64>()
+            + ITEM_SPACING * sizes.len().saturating_sub(1) as f64;
+        self.inline_count = if total_width <= max_width {
+            sizes.len()
+        } else {
+            let available = max_width - overflow_size.width;
+            let mut end = 0.0;
+            sizes
+                .iter()
+                .take_while(|size| {
+                    end += size.width + ITEM_SPACING;
+                    end <= available
+                })
+                .count()
+        };
+
+        let inline_sizes = &sizes[..self.inline_count];
+        let mut width: f64 = inline_sizes
+            .iter()
+            .map(|size| size.width + ITEM_SPACING)
+            .sum();
+        let mut height = inline_sizes
+            .iter()
+            .map(|size| size.height)
+            .fold(0.0, f64::max);
+        if self.has_overflow() {
+            width += overflow_size.width;
+            height = height.max(overflow_size.height);
+        } else {
+            width = (width - ITEM_SPACING).max(0.0);
+        }
+        let size = bc.constrain(Size::new(width, height));
+
+        // Place the inline items, vertically centered, and the overflow button
+        // after them.
+        let mut x = 0.0;
+        for (item, item_size) in self.items.iter_mut().zip(inline_sizes) {
+            let origin = Point::new(x, (size.height - item_size.height) / 2.0);
+            item.widget.set_layout_rect(
+                layout_ctx,
+                data,
+                env,
+                Rect::from_origin_size(origin, *item_size),
+            );
+            x += item_size.width + ITEM_SPACING;
+        }
+        for item in &mut self.items[self.inline_count..] {
+            item.widget
+                .set_layout_rect(layout_ctx, data, env, Rect::ZERO);
+        }
+        let overflow_rect = if self.has_overflow() {
+            let origin = Point::new(x, (size.height - overflow_size.height) / 2.0);
+            Rect::from_origin_size(origin, overflow_size)
+        } else {
+            Rect::ZERO
+        };
+        self.overflow
+            .set_layout_rect(layout_ctx, data, env, overflow_rect);
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        for item in &mut self.items[..self.inline_count] {
+            item.widget.paint_with_offset(ctx, data, env);
+        }
+        if self.has_overflow() {
+            self.overflow.paint_with_offset(ctx, data, env);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::WidgetExt;
+    use crate::{LocalizedString, Selector, Target, WidgetId};
+
+    #[test]
+    fn toolbar_overflow() {
+        const ACTIONS: [Selector; 5] = [
+            Selector::new("druid-tests.toolbar-0"),
+            Selector::new("druid-tests.toolbar-1"),
+            Selector::new("druid-tests.toolbar-2"),
+            Selector::new("druid-tests.toolbar-3"),
+            Selector::new("druid-tests.toolbar-4"),
+        ];
+        let (id_1, id_2, id_3, id_4, id_5) = widget_id5();
+        let ids = [id_1, id_2, id_3, id_4, id_5];
+
+        fn make_toolbar(ids: [WidgetId; 5], record: &Recording) -> impl Widget<()> {
+            let mut toolbar = Toolbar::new();
+            for (id, action) in ids.iter().zip(&ACTIONS) {
+                let sel = action.clone();
+                let button = Button::new("item", move |ctx, _, _| {
+                    ctx.submit_command(sel.clone(), Target::Auto)
+                });
+                let title = LocalizedString::new("toolbar-item");
+                toolbar.add_item(
+                    button.fix_width(60.).with_id(*id),
+                    MenuItem::new(title, action.clone()),
+                );
+            }
+            toolbar.record(record)
+        }
+
+        /// The commands that reached the toolbar.
+        fn sent(record: &Recording) -> Vec<Selector> {
+            let mut sent = Vec::new();
+            while !record.is_empty() {
+                if let Record::E(Event::Command(cmd)) = record.next() {
+                    sent.push(cmd.selector);
+                }
+            }
+            sent
+        }
+
+        fn inline_items(harness: &mut Harness<()>, ids: &[WidgetId]) -> usize {
+            let shown: Vec<bool> = ids
+                .iter()
+                .map(|id| harness.get_state(*id).layout_rect.size() != Size::ZERO)
+                .collect();
+            let count = shown.iter().take_while(|shown| **shown).count();
+            assert!(shown[count..].iter().all(|shown| !shown), "{:?}", shown);
+            count
+        }
+
+        let record = Recording::default();
+        let expected_inline = [(400., 5), (250., 3), (130., 1), (40., 0)];
+        for &(width, expected) in &expected_inline {
+            let toolbar = make_toolbar(ids, &record);
+            Harness::create((), toolbar, |harness| {
+                harness.set_initial_size(Size::new(width, 100.));
+                harness.send_initial_events();
+                harness.just_layout();
+                assert_eq!(inline_items(harness, &ids), expected, "width {}", width);
+            });
+        }
+
+        Harness::create((), make_toolbar(ids, &record), |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            record.clear();
+            click(harness, Point::new(94., 50.));
+            assert_eq!(sent(&record), &[ACTIONS[1].clone()]);
+
+            // the partition is re-evaluated when the window is resized
+            harness.event(Event::Size(Size::new(130., 100.)));
+            harness.just_layout();
+            assert_eq!(inline_items(harness, &ids), 1);
+
+            // the overflow button comes after the first item, and the menu starts
+            // at the second item
+            click(harness, Point::new(70., 50.));
+            harness.choose_context_menu_item(0);
+            assert_eq!(sent(&record), &[ACTIONS[1].clone()]);
+
+            harness.event(Event::Size(Size::new(400., 100.)));
+            harness.just_layout();
+            assert_eq!(inline_items(harness, &ids), 5);
+        });
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A controller that shows a tooltip while the mouse rests on a widget.
+
+use std::time::{Duration, Instant};
+
+use crate::kurbo::{Point, Size, Vec2};
+use crate::piet::{FontBuilder, Text, TextLayout, TextLayoutBuilder};
+use crate::widget::{Controller, Label, LabelText, WidgetExt};
+use crate::{
+    commands, theme, Data, Env, Event, EventCtx, LifeCycle, LifeCycleCtx, TimerToken, Widget,
+    WindowConfig, WindowId, WindowLevel,
+};
+
+/// How long the mouse must rest on the widget before the tooltip appears.
+const TOOLTIP_DELAY: Duration = Duration::from_millis(600);
+/// Where the tooltip is, relative to the mouse, so that it isn't under it.
+const CURSOR_OFFSET: Vec2 = Vec2::new(0.0, 16.0);
+/// The space between the text and the edges of the tooltip.
+const TOOLTIP_PADDING: f64 = 4.0;
+
+/// A [`Controller`] that shows some text in a tooltip window when the mouse
+/// rests on its widget.
+///
+/// The tooltip appears below the mouse once it has stopped moving for a
+/// moment, and closes when the mouse leaves the widget or is pressed.
+///
+/// This is usually used with [`WidgetExt::tooltip`].
+///
+/// [`Controller`]: trait.Controller.html
+/// [`WidgetExt::tooltip`]: trait.WidgetExt.html#method.tooltip
+pub struct Tooltip<T> {
+    text: LabelText<T>,
+    /// The timer that shows the tooltip, if the mouse is resting.
+    timer: TimerToken,
+    /// Where the mouse last was, in the widget's coordinates.
+    mouse_pos: Point,
+    /// The tooltip's window, while it is open.
+    window: Option<WindowId>,
+}
+
+impl<T: Data> Tooltip<T> {
+    /// Create a controller that shows `text` in a tooltip.
+    pub fn new(text: impl Into<LabelText<T>>) -> Self {
+        Tooltip {
+            text: text.into(),
+            timer: TimerToken::INVALID,
+            mouse_pos: Point::ZERO,
+            window: None,
+        }
+    }
+
+    fn show(&mut self, ctx: &mut EventCtx, data: &T, env: &Env) {
+        self.text.resolve(data, env);
+        let text = self.text.with_display_text(|s| s.to_string());
+        let config = WindowConfig::default()
+            .set_level(WindowLevel::Tooltip)
+            .show_titlebar(false)
+            .resizable(false)
+            .window_size(tooltip_size(ctx, &text, env))
+            .set_position(ctx.to_screen(self.mouse_pos + CURSOR_OFFSET));
+        let content = Label::new(text)
+            .padding(TOOLTIP_PADDING)
+            .background(theme::BACKGROUND_LIGHT)
+            .border(theme::BORDER_LIGHT, 1.0);
+        self.window = Some(ctx.new_sub_window(config, content, (), env.clone()));
+    }
+}
+
+impl<T: Data, W: Widget<T>> Controller<T, W> for Tooltip<T> {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseMoved(mouse) if self.window.is_none() => {
+                self.mouse_pos = mouse.pos;
+                // the tooltip waits until the mouse has been still for a while
+                self.timer = if ctx.is_hot() {
+                    ctx.request_timer(Instant::now() + TOOLTIP_DELAY)
+                } else {
+                    TimerToken::INVALID
+                };
+            }
+            Event::MouseDown(_) | Event::Wheel(_) => {
+                self.timer = TimerToken::INVALID;
+                if let Some(window) = self.window.take() {
+                    ctx.submit_command(commands::CLOSE_WINDOW, window);
+                }
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = TimerToken::INVALID;
+                if ctx.is_hot() {
+                    self.show(ctx, data, env);
+                }
+                return;
+            }
+            _ => (),
+        }
+        child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        if let LifeCycle::HotChanged(false) = event {
+            self.timer = TimerToken::INVALID;
+            if let Some(window) = self.window.take() {
+                ctx.submit_command(commands::CLOSE_WINDOW, window);
+            }
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+}
+
+/// The size of a tooltip window that shows `text`.
+fn tooltip_size(ctx: &mut EventCtx, text: &str, env: &Env) -> Size {
+    let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+    let mut factory = ctx.text();
+    let font = factory
+        .new_font_by_name(env.get(theme::FONT_NAME), font_size)
+        .build();
+    let text_width = font
+        .and_then(|font| factory.new_text_layout(&font, text).build())
+        .map(|layout| layout.width())
+        .unwrap_or_default();
+    // the label pads its text a little, and we add a border
+    let extra = 2.0 * (TOOLTIP_PADDING + 2.0 + 1.0);
+    Size::new(text_width + extra, font_size * 1.2 + extra)
+}
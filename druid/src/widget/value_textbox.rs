@@ -0,0 +1,372 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A text box that edits a value of any type.
+
+use std::mem;
+use std::ops::Range;
+
+use crate::kurbo::Size;
+use crate::text::{Formatter, Validation};
+use crate::widget::{TextBox, ValidationError};
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, HotKey, KbKey, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Selector, SysMods, UpdateCtx, Widget,
+};
+
+/// Sent by a box to itself when it loses focus, since the value can only be
+/// written back to the data in `event`.
+const COMPLETE_EDITING: Selector = Selector::new("druid-builtin.value-textbox-complete-editing");
+
+/// A [`TextBox`] that edits a value of any type, with a [`Formatter`] that
+/// converts it to and from text.
+///
+/// While the box has focus, it keeps the text that is being typed, even when
+/// that isn't how the value would be shown: typing `1.` in a box for an
+/// `f64` keeps the dot. Each edit is checked with the formatter; text that
+/// can't become a value is flagged with the box's validation error, or with
+/// [`reject_invalid_input`], the edit is undone.
+///
+/// The value is written to the data when editing is completed, by pressing
+/// enter or by moving the focus away, and with [`update_data_while_editing`],
+/// after each edit that leaves a complete value. If the text isn't a value
+/// when editing is completed, the data keeps its old value and the error is
+/// shown.
+///
+/// Errors are shown like those of the text box's own validator, so how they
+/// look is set on the `TextBox` that is passed in, as with
+/// [`TextBox::with_validation_message`].
+///
+/// [`TextBox`]: struct.TextBox.html
+/// [`Formatter`]: ../trait.Formatter.html
+/// [`reject_invalid_input`]: #method.reject_invalid_input
+/// [`update_data_while_editing`]: #method.update_data_while_editing
+/// [`TextBox::with_validation_message`]: struct.TextBox.html#method.with_validation_message
+pub struct ValueTextBox<T> {
+    textbox: TextBox,
+    formatter: Box<dyn Formatter<T>>,
+    /// The text in the box: what is being typed while editing, and the
+    /// formatted data otherwise.
+    buffer: String,
+    is_editing: bool,
+    update_data_while_editing: bool,
+    reject_invalid_input: bool,
+}
+
+impl<T: Data> ValueTextBox<T> {
+    /// Create a box that edits its data with `textbox`, as the text given by
+    /// `formatter`.
+    pub fn new(textbox: TextBox, formatter: impl Formatter<T> + 'static) -> Self {
+        ValueTextBox {
+            textbox,
+            formatter: Box::new(formatter),
+            buffer: String::new(),
+            is_editing: false,
+            update_data_while_editing: false,
+            reject_invalid_input: false,
+        }
+    }
+
+    /// Builder-style method to write the value to the data after each edit
+    /// that leaves a complete value, rather than only when editing is
+    /// completed.
+    pub fn update_data_while_editing(mut self, update: bool) -> Self {
+        self.update_data_while_editing = update;
+        self
+    }
+
+    /// Builder-style method to undo edits that make the text invalid, rather
+    /// than flagging them.
+    pub fn reject_invalid_input(mut self, reject: bool) -> Self {
+        self.reject_invalid_input = reject;
+        self
+    }
+
+    /// The current validation error, if the text is not valid.
+    pub fn validation_error(&self) -> Option<&ValidationError> {
+        self.textbox.validation_error()
+    }
+
+    /// Check the text after an edit, writing it to the data if it should be.
+    fn edited(&mut self, old_text: String, old_selection: Range<usize>, data: &mut T) {
+        self.is_editing = true;
+        match self.formatter.validate_partial_input(&self.buffer) {
+            Validation::Invalid(_) if self.reject_invalid_input => {
+                self.buffer = old_text;
+                self.textbox.set_selection_range(old_selection);
+            }
+            Validation::Invalid(error) => self.textbox.set_validation_error(Some(error)),
+            Validation::Partial => self.textbox.set_validation_error(None),
+            Validation::Complete => {
+                self.textbox.set_validation_error(None);
+                if self.update_data_while_editing {
+                    if let Ok(value) = self.formatter.value(&self.buffer) {
+                        *data = value;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Write the value of the text to the data, or show why it isn't one.
+    fn complete(&mut self, ctx: &mut EventCtx, data: &mut T) {
+        match self.formatter.value(&self.buffer) {
+            Ok(value) => {
+                *data = value;
+                self.buffer = if self.is_editing {
+                    self.formatter.format_for_editing(data)
+                } else {
+                    self.formatter.format(data)
+                };
+                self.textbox.set_validation_error(None);
+            }
+            Err(error) => self.textbox.set_validation_error(Some(error)),
+        }
+        ctx.request_layout();
+    }
+}
+
+impl<T: Data> Widget<T> for ValueTextBox<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if cmd.selector == COMPLETE_EDITING {
+                self.is_editing = false;
+                self.complete(ctx, data);
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        let old_text = self.buffer.clone();
+        let old_selection = self.textbox.selection_range();
+        self.textbox.event(ctx, event, &mut self.buffer, env);
+        if self.buffer != old_text {
+            self.edited(old_text, old_selection, data);
+            ctx.set_value(self.buffer.as_str());
+            // a box that sizes to its content follows the text
+            ctx.request_layout();
+        }
+        if let Event::KeyDown(key) = event {
+            if HotKey::new(SysMods::None, KbKey::Enter).matches(key) {
+                self.complete(ctx, data);
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => self.buffer = self.formatter.format(data),
+            LifeCycle::FocusChanged(true) => {
+                self.is_editing = true;
+                self.buffer = self.formatter.format_for_editing(data);
+            }
+            LifeCycle::FocusChanged(false) => ctx.submit_command(COMPLETE_EDITING, ctx.widget_id()),
+            _ => (),
+        }
+        self.textbox.lifecycle(ctx, event, &self.buffer, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        // while editing, the text that is being typed is kept, even when the
+        // data changes because of it
+        if self.is_editing {
+            return;
+        }
+        let text = self.formatter.format(data);
+        if text != self.buffer {
+            let old_text = mem::replace(&mut self.buffer, text);
+            self.textbox.set_validation_error(None);
+            self.textbox.update(ctx, &old_text, &self.buffer, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, env: &Env) -> Size {
+        self.textbox.layout(ctx, bc, &self.buffer, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, env: &Env) {
+        self.textbox.paint(ctx, &self.buffer, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Controller, Flex, WidgetExt};
+    use crate::{Code, ParseFormatter, Point};
+
+    /// Takes digits only, as a `u32`.
+    struct Digits;
+
+    impl Formatter<u32> for Digits {
+        fn format(&self, value: &u32) -> String {
+            value.to_string()
+        }
+
+        fn validate_partial_input(&self, input: &str) -> Validation {
+            if input.is_empty() {
+                Validation::Partial
+            } else if input.chars().all(|c| c.is_ascii_digit()) {
+                Validation::Complete
+            } else {
+                Validation::Invalid(ValidationError::new("digits only"))
+            }
+        }
+
+        fn value(&self, input: &str) -> Result<u32, ValidationError> {
+            input
+                .parse()
+                .map_err(|_| ValidationError::new("not a number"))
+        }
+    }
+
+    /// Keeps the validation error of the box after each event.
+    struct LastError(Rc<RefCell<Option<ValidationError>>>);
+
+    impl<T: Data> Controller<T, ValueTextBox<T>> for LastError {
+        fn event(
+            &mut self,
+            child: &mut ValueTextBox<T>,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut T,
+            env: &Env,
+        ) {
+            child.event(ctx, event, data, env);
+            *self.0.borrow_mut() = child.validation_error().cloned();
+        }
+    }
+
+    /// Focus the box, with the caret after its text.
+    fn focus<T: Data>(harness: &mut Harness<T>) {
+        harness.send_initial_events();
+        harness.just_layout();
+        click(harness, Point::new(100., 10.));
+    }
+
+    fn type_text<T: Data>(harness: &mut Harness<T>, keys: &[(&'static str, Code)]) {
+        for &(text, code) in keys {
+            harness.event(key_down(text, code));
+        }
+    }
+
+    fn backspace<T: Data>(harness: &mut Harness<T>) {
+        harness.event(key_down("", Code::Backspace));
+    }
+
+    #[test]
+    fn partial_input_is_kept_while_editing() {
+        let number = ValueTextBox::new(TextBox::new(), ParseFormatter::new());
+        let widget = number.update_data_while_editing(true);
+        Harness::create(0.0f64, widget, |harness| {
+            focus(harness);
+            backspace(harness);
+            assert_eq!(*harness.data(), 0.0);
+
+            // "1." is already the value 1, but the dot stays for the next digit
+            type_text(harness, &[("1", Code::Key1), (".", Code::Period)]);
+            assert_eq!(*harness.data(), 1.0);
+            type_text(harness, &[("5", Code::Key5)]);
+            assert_eq!(*harness.data(), 1.5);
+
+            // "-" isn't a value yet, so the data keeps its old one
+            for _ in 0..3 {
+                backspace(harness);
+            }
+            type_text(harness, &[("-", Code::Minus)]);
+            assert_eq!(*harness.data(), 1.0);
+            type_text(harness, &[("2", Code::Key2)]);
+            assert_eq!(*harness.data(), -2.0);
+        });
+    }
+
+    #[test]
+    fn data_is_written_when_editing_completes() {
+        let number = || ValueTextBox::new(TextBox::new(), ParseFormatter::new());
+        let widget = Flex::column()
+            .with_child(number(), 0.)
+            .with_child(number(), 0.);
+        Harness::create(0.0f64, widget, |harness| {
+            focus(harness);
+            backspace(harness);
+            type_text(harness, &[("4", Code::Key4), ("2", Code::Key2)]);
+            assert_eq!(*harness.data(), 0.0);
+
+            harness.event(key_down("", Code::Return));
+            assert_eq!(*harness.data(), 42.0);
+
+            // moving focus away completes the editing too
+            type_text(harness, &[(".", Code::Period), ("5", Code::Key5)]);
+            assert_eq!(*harness.data(), 42.0);
+            harness.event(key_down("", Code::Tab));
+            assert_eq!(*harness.data(), 42.5);
+        });
+    }
+
+    #[test]
+    fn incomplete_text_is_flagged_when_editing_completes() {
+        let error = Rc::new(RefCell::new(None));
+        let number = ValueTextBox::new(TextBox::new(), ParseFormatter::new());
+        let widget = number.controller(LastError(error.clone()));
+        Harness::create(3.0f64, widget, |harness| {
+            focus(harness);
+            backspace(harness);
+            type_text(harness, &[("-", Code::Minus)]);
+            assert!(error.borrow().is_none());
+
+            harness.event(key_down("", Code::Return));
+            assert!(error.borrow().is_some());
+            assert_eq!(*harness.data(), 3.0);
+        });
+    }
+
+    #[test]
+    fn invalid_input_is_flagged() {
+        let error = Rc::new(RefCell::new(None));
+        let widget = ValueTextBox::new(TextBox::new(), Digits).controller(LastError(error.clone()));
+        Harness::create(1u32, widget, |harness| {
+            focus(harness);
+            type_text(harness, &[("x", Code::KeyX)]);
+            assert_eq!(*error.borrow(), Some(ValidationError::new("digits only")));
+
+            // the flag goes away with the invalid text
+            backspace(harness);
+            assert!(error.borrow().is_none());
+            type_text(harness, &[("2", Code::Key2)]);
+            harness.event(key_down("", Code::Return));
+            assert_eq!(*harness.data(), 12);
+        });
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        let error = Rc::new(RefCell::new(None));
+        let number = ValueTextBox::new(TextBox::new(), Digits).reject_invalid_input(true);
+        let widget = number.controller(LastError(error.clone()));
+        Harness::create(1u32, widget, |harness| {
+            focus(harness);
+            type_text(harness, &[("x", Code::KeyX)]);
+            assert!(error.borrow().is_none());
+
+            type_text(harness, &[("2", Code::Key2)]);
+            harness.event(key_down("", Code::Return));
+            assert_eq!(*harness.data(), 12);
+        });
+    }
+}
@@ -19,12 +19,18 @@ use crate::{
     Point, Rect, Size, UpdateCtx, Widget, WidgetPod,
 };
 
-/// A widget that can switch dynamically between one of many views depending
-/// on application state.
-
 type ChildPicker<T, U> = dyn Fn(&T, &Env) -> U;
 type ChildBuilder<T, U> = dyn Fn(&U, &T, &Env) -> Box<dyn Widget<T>>;
 
+/// A widget that can switch dynamically between one of many views depending
+/// on application state.
+///
+/// This is like [`Either`], with any number of branches: a key is picked
+/// from the data, and a child is built for each new key. When the key
+/// changes, the old child is dropped and the new one is added in its place,
+/// so it is part of the focus chain, and its layout is requested.
+///
+/// [`Either`]: struct.Either.html
 pub struct ViewSwitcher<T, U> {
     child_picker: Box<ChildPicker<T, U>>,
     child_builder: Box<ChildBuilder<T, U>>,
@@ -32,7 +38,7 @@ pub struct ViewSwitcher<T, U> {
     active_child_id: Option<U>,
 }
 
-impl<T: Data, U: PartialEq> ViewSwitcher<T, U> {
+impl<T: Data, U: Data + PartialEq> ViewSwitcher<T, U> {
     /// Create a new view switcher.
     ///
     /// The `child_picker` closure is called every time the application data changes.
@@ -41,7 +47,7 @@ impl<T: Data, U: PartialEq> ViewSwitcher<T, U> {
     /// `child_builder` closure is called with the new value.
     ///
     /// The `child_builder` closure creates a new child widget based on
-    /// the value passed to it.
+    /// the value passed to it. The previous child is dropped.
     pub fn new(
         child_picker: impl Fn(&T, &Env) -> U + 'static,
         child_builder: impl Fn(&U, &T, &Env) -> Box<dyn Widget<T>> + 'static,
@@ -55,7 +61,7 @@ impl<T: Data, U: PartialEq> ViewSwitcher<T, U> {
     }
 }
 
-impl<T: Data, U: PartialEq> Widget<T> for ViewSwitcher<T, U> {
+impl<T: Data, U: Data + PartialEq> Widget<T> for ViewSwitcher<T, U> {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         if let Some(child) = self.active_child.as_mut() {
             child.event(ctx, event, data, env);
@@ -76,12 +82,16 @@ impl<T: Data, U: PartialEq> Widget<T> for ViewSwitcher<T, U> {
     fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
         let child_id = (self.child_picker)(data, env);
         if Some(&child_id) != self.active_child_id.as_ref() {
+            // the new child has not been added yet, so it doesn't get an update;
+            // it gets `WidgetAdded` once the children have been rebuilt.
+            if let Some(child) = self.active_child.as_mut() {
+                ctx.remove_child(child);
+            }
             self.active_child = Some(WidgetPod::new((self.child_builder)(&child_id, data, env)));
             self.active_child_id = Some(child_id);
             ctx.children_changed();
-        }
-
-        if let Some(child) = self.active_child.as_mut() {
+            ctx.request_layout();
+        } else if let Some(child) = self.active_child.as_mut() {
             child.update(ctx, data, env);
         }
     }
@@ -96,7 +106,12 @@ impl<T: Data, U: PartialEq> Widget<T> for ViewSwitcher<T, U> {
         match self.active_child {
             Some(ref mut child) => {
                 let size = child.layout(layout_ctx, bc, data, env);
-                child.set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+                child.set_layout_rect(
+                    layout_ctx,
+                    data,
+                    env,
+                    Rect::from_origin_size(Point::ORIGIN, size),
+                );
                 size
             }
             None => bc.max(),
@@ -109,3 +124,74 @@ impl<T: Data, U: PartialEq> Widget<T> for ViewSwitcher<T, U> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Controller, Split, WidgetExt};
+    use crate::{Command, Selector, Target};
+
+    #[test]
+    fn view_switcher_replaces_children() {
+        const SWITCH: Selector = Selector::new("druid-tests.switch-view");
+
+        /// Sets the data to the key in a `SWITCH` command.
+        struct Switch;
+
+        impl<W: Widget<u32>> Controller<u32, W> for Switch {
+            fn event(
+                &mut self,
+                child: &mut W,
+                ctx: &mut EventCtx,
+                event: &Event,
+                data: &mut u32,
+                env: &Env,
+            ) {
+                match event {
+                    Event::Command(cmd) if cmd.selector == SWITCH => {
+                        *data = *cmd.get_object::<u32>().unwrap();
+                    }
+                    _ => child.event(ctx, event, data, env),
+                }
+            }
+        }
+
+        let (id_1, id_2, id_3, switcher) = widget_id4();
+        let widget = ViewSwitcher::new(
+            |data: &u32, _env| *data,
+            move |key, _data, _env| match key {
+                0 => Split::vertical(
+                    make_focusable(Default::default()).with_id(id_1),
+                    make_focusable(Default::default()).with_id(id_2),
+                )
+                .boxed(),
+                _ => make_focusable(Default::default()).with_id(id_3).boxed(),
+            },
+        )
+        .with_id(switcher)
+        .controller(Switch);
+
+        Harness::create(0u32, widget, |harness| {
+            harness.send_initial_events();
+            assert_eq!(harness.window().focus_chain(), &[id_1, id_2]);
+            // the split, and its two children
+            assert_eq!(harness.get_state(switcher).children.entry_count(), 3);
+
+            harness.submit_command(Command::new(SWITCH, 1u32), Target::Auto);
+            assert_eq!(harness.window().focus_chain(), &[id_3]);
+            assert!(harness.get_state(switcher).children.contains(&id_3));
+            assert_eq!(harness.get_state(switcher).children.entry_count(), 1);
+            assert!(harness.get_state(switcher).needs_layout);
+
+            // the same key keeps the child
+            harness.submit_command(Command::new(SWITCH, 1u32), Target::Auto);
+            assert_eq!(harness.window().focus_chain(), &[id_3]);
+
+            harness.submit_command(Command::new(SWITCH, 0u32), Target::Auto);
+            assert_eq!(harness.window().focus_chain(), &[id_1, id_2]);
+            assert_eq!(harness.get_state(switcher).children.entry_count(), 3);
+        })
+    }
+}
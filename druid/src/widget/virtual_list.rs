@@ -0,0 +1,639 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A list that only has widgets for the rows that can be seen.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use log::error;
+
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::widget::ListIter;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    RenderContext, UpdateCtx, Widget, WidgetPod,
+};
+
+/// The height of the rows that haven't been measured yet.
+const DEFAULT_ROW_HEIGHT: f64 = 24.0;
+/// The rows on each side of the viewport that have widgets.
+const DEFAULT_OVERSCAN: usize = 4;
+
+type Row<T> = WidgetPod<T, Box<dyn Widget<T>>>;
+
+/// A vertically scrolling list that only has widgets for the rows in and
+/// near its viewport, for collections too long to have a widget per item.
+///
+/// As the list is scrolled with the wheel, the widgets of the rows that
+/// leave the viewport are removed from the widget tree and given to the
+/// rows that come into it, so a widget may be shown for several items over
+/// its lifetime; it is sent `WidgetAdded` with the data of each new item.
+/// The row that has the focus, or a mouse press, keeps its widget while it
+/// is scrolled out.
+///
+/// Rows are measured as they are laid out, and the rows that haven't been
+/// yet are taken to be [`estimated_row_height`] tall; when all the rows are
+/// the same height, [`fixed_row_height`] saves measuring them. The list
+/// keeps the row at the top of the viewport in place as rows are measured,
+/// and as items are added or removed after it.
+///
+/// The list scrolls itself, so it should not be put in a [`Scroll`], and it
+/// should be given a bounded height.
+///
+/// [`estimated_row_height`]: #method.estimated_row_height
+/// [`fixed_row_height`]: #method.fixed_row_height
+/// [`Scroll`]: struct.Scroll.html
+pub struct VirtualList<T> {
+    closure: Box<dyn Fn() -> Box<dyn Widget<T>>>,
+    /// The rows that have widgets in the tree, by index.
+    rows: BTreeMap<usize, Row<T>>,
+    /// The rows that were given widgets, which join the tree with the
+    /// next `RouteWidgetAdded`.
+    incoming: Vec<(usize, Row<T>)>,
+    /// The widgets that were taken from rows, to give to the next ones.
+    spare: Vec<Row<T>>,
+    len: usize,
+    fixed_height: Option<f64>,
+    estimated_height: f64,
+    /// The heights of the rows, when they aren't fixed; the ones that
+    /// haven't been measured are `estimated_height`.
+    heights: Vec<f64>,
+    overscan: usize,
+    /// The row at the top of the viewport, and how far the viewport is
+    /// scrolled into it.
+    anchor: (usize, f64),
+    viewport_height: f64,
+    /// The row that has the focus.
+    focused_row: Option<usize>,
+}
+
+impl<T: Data> VirtualList<T> {
+    /// Create a new virtual list. The closure is called for each widget the
+    /// list needs, which is about as many as there are rows in view.
+    pub fn new<W: Widget<T> + 'static>(closure: impl Fn() -> W + 'static) -> Self {
+        VirtualList {
+            closure: Box::new(move || Box::new(closure())),
+            rows: BTreeMap::new(),
+            incoming: Vec::new(),
+            spare: Vec::new(),
+            len: 0,
+            fixed_height: None,
+            estimated_height: DEFAULT_ROW_HEIGHT,
+            heights: Vec::new(),
+            overscan: DEFAULT_OVERSCAN,
+            anchor: (0, 0.0),
+            viewport_height: 0.0,
+            focused_row: None,
+        }
+    }
+
+    /// Builder-style method to make every row `height` tall, instead of
+    /// measuring them.
+    pub fn fixed_row_height(mut self, height: f64) -> Self {
+        self.fixed_height = Some(height.max(1.0));
+        self
+    }
+
+    /// Builder-style method to set the height of the rows that haven't been
+    /// measured yet, which sets how far the list can be scrolled until they
+    /// are.
+    pub fn estimated_row_height(mut self, height: f64) -> Self {
+        self.estimated_height = height.max(1.0);
+        self
+    }
+
+    /// Builder-style method to set how many rows on each side of the
+    /// viewport have widgets, so that they are ready to be scrolled to.
+    pub fn overscan(mut self, rows: usize) -> Self {
+        self.overscan = rows;
+        self
+    }
+
+    /// How far the list is scrolled, from the top of the first row.
+    pub fn offset(&self) -> f64 {
+        self.row_top(self.anchor.0) + self.anchor.1
+    }
+
+    fn row_top(&self, idx: usize) -> f64 {
+        self.span_height(0..idx)
+    }
+
+    fn span_height(&self, range: Range<usize>) -> f64 {
+        match self.fixed_height {
+            Some(height) => range.len() as f64 * height,
+            None => self.heights[range].iter().sum(),
+        }
+    }
+
+    fn content_height(&self) -> f64 {
+        self.row_top(self.len)
+    }
+
+    /// The row at `y`, in the content, and how far `y` is into it.
+    fn row_at(&self, y: f64) -> (usize, f64) {
+        if self.len == 0 {
+            return (0, 0.0);
+        }
+        if let Some(height) = self.fixed_height {
+            let idx = ((y / height) as usize).min(self.len - 1);
+            return (idx, y - idx as f64 * height);
+        }
+        let mut top = 0.0;
+        for (idx, height) in self.heights.iter().enumerate() {
+            if y < top + height {
+                return (idx, y - top);
+            }
+            top += height;
+        }
+        let last = self.len - 1;
+        (last, y - (top - self.heights[last]))
+    }
+
+    /// Scroll so that `offset` is at the top of the viewport, as near as the
+    /// content allows.
+    fn scroll_to(&mut self, offset: f64) {
+        let max = (self.content_height() - self.viewport_height).max(0.0);
+        self.anchor = self.row_at(offset.min(max).max(0.0));
+    }
+
+    /// Returns `true` if the list moved.
+    fn scroll_by(&mut self, delta: f64) -> bool {
+        let old = self.offset();
+        self.scroll_to(old + delta);
+        (self.offset() - old).abs() > 1e-9
+    }
+
+    fn set_len(&mut self, len: usize) {
+        // the rows before the anchor stay where they are, and so does it
+        let offset = if self.anchor.0 < len {
+            Some(self.offset())
+        } else {
+            None
+        };
+        if self.fixed_height.is_none() {
+            self.heights.resize(len, self.estimated_height);
+        }
+        self.len = len;
+        let offset = offset.unwrap_or_else(|| self.content_height());
+        self.scroll_to(offset);
+    }
+
+    /// The rows that should have widgets: those in the viewport, and
+    /// `overscan` more on each side.
+    fn row_range(&self) -> Range<usize> {
+        if self.len == 0 {
+            return 0..0;
+        }
+        let (last, _) = self.row_at(self.offset() + self.viewport_height);
+        let start = self.anchor.0.saturating_sub(self.overscan);
+        let end = (last + 1 + self.overscan).min(self.len);
+        start..end
+    }
+
+    /// The rows that have widgets, from the first to the last.
+    fn row_span(&self) -> Range<usize> {
+        match (self.rows.keys().next(), self.rows.keys().next_back()) {
+            (Some(first), Some(last)) => *first..*last + 1,
+            _ => 0..0,
+        }
+    }
+
+    /// Take the widgets from the rows that are no longer near the viewport,
+    /// and give widgets to the rows that are, reusing the ones that were
+    /// taken before making new ones.
+    ///
+    /// `remove` is called with each widget that is taken, to remove it from
+    /// the tree. Returns `true` if any widgets were taken or given.
+    fn recycle_rows(&mut self, mut remove: impl FnMut(&mut Row<T>)) -> bool {
+        let range = self.row_range();
+        let len = self.len;
+        let focused_row = self.focused_row;
+        let leaving: Vec<usize> = self
+            .rows
+            .iter()
+            .filter(|(idx, row)| {
+                let keep = range.contains(*idx)
+                    || Some(**idx) == focused_row
+                    || row.is_active()
+                    || row.has_active();
+                **idx >= len || !keep
+            })
+            .map(|(idx, _)| *idx)
+            .collect();
+        let mut changed = !leaving.is_empty();
+        for idx in leaving {
+            let mut row = self.rows.remove(&idx).unwrap();
+            remove(&mut row);
+            self.spare.push(row);
+        }
+        self.incoming.retain(|(idx, _)| range.contains(idx));
+
+        for idx in range {
+            if self.rows.contains_key(&idx) || self.incoming.iter().any(|(i, _)| *i == idx) {
+                continue;
+            }
+            let closure = &self.closure;
+            let row = self
+                .spare
+                .pop()
+                .unwrap_or_else(|| WidgetPod::new(closure()));
+            self.incoming.push((idx, row));
+            changed = true;
+        }
+        // there is no use in keeping more spares than there are widgets
+        self.spare.truncate(self.rows.len());
+        changed
+    }
+}
+
+impl<C: Data, T: ListIter<C>> Widget<T> for VirtualList<C> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let viewport = Rect::from_origin_size(Point::ORIGIN, ctx.size());
+        let force = self
+            .rows
+            .values()
+            .any(|row| row.is_hot() || row.is_active() || row.has_active());
+        // like a scroll, the list doesn't pass the mouse to rows outside of it
+        if let Some(child_event) = event.transform_scroll(Vec2::ZERO, viewport, force) {
+            let span = self.row_span();
+            let rows = &mut self.rows;
+            data.for_each_mut_in(span, |child_data, idx| {
+                if let Some(row) = rows.get_mut(&idx) {
+                    row.event(ctx, &child_event, child_data, env);
+                }
+            });
+        }
+
+        if !ctx.is_handled() {
+            if let Event::Wheel(wheel) = event {
+                if self.scroll_by(wheel.delta.y) {
+                    if self.recycle_rows(|row| ctx.remove_child(row)) {
+                        ctx.children_changed();
+                    }
+                    ctx.request_layout();
+                    ctx.set_handled();
+                }
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                self.set_len(data.data_len());
+                self.recycle_rows(|_| ());
+            }
+            // the viewport is now known, or has changed
+            LifeCycle::Size(_) => {
+                if self.recycle_rows(|row| row.remove(ctx)) {
+                    ctx.children_changed();
+                    ctx.request_layout();
+                }
+            }
+            LifeCycle::RouteFocusChanged { new, .. } => {
+                self.focused_row = new.and_then(|id| {
+                    self.rows
+                        .iter()
+                        .find(|(_, row)| row.id() == id || row.state().children.contains(&id))
+                        .map(|(idx, _)| *idx)
+                });
+            }
+            _ => (),
+        }
+        if let LifeCycle::WidgetAdded | LifeCycle::RouteWidgetAdded = event {
+            self.rows.extend(self.incoming.drain(..));
+        }
+
+        let span = self.row_span();
+        let rows = &mut self.rows;
+        data.for_each_in(span, |child_data, idx| {
+            if let Some(row) = rows.get_mut(&idx) {
+                row.lifecycle(ctx, event, child_data, env);
+            }
+        });
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        let span = self.row_span();
+        let rows = &mut self.rows;
+        data.for_each_in(span, |child_data, idx| {
+            if let Some(row) = rows.get_mut(&idx) {
+                row.update(ctx, child_data, env);
+            }
+        });
+
+        if data.data_len() != self.len {
+            self.set_len(data.data_len());
+            if self.recycle_rows(|row| ctx.remove_child(row)) {
+                ctx.children_changed();
+            }
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("VirtualList");
+
+        let child_bc = match self.fixed_height {
+            Some(height) => BoxConstraints::new(
+                Size::new(bc.min().width, height),
+                Size::new(bc.max().width, height),
+            ),
+            None => BoxConstraints::new(
+                Size::new(bc.min().width, 0.0),
+                Size::new(bc.max().width, std::f64::INFINITY),
+            ),
+        };
+        let mut width = bc.min().width;
+        let mut sizes = Vec::with_capacity(self.rows.len());
+        let span = self.row_span();
+        let rows = &mut self.rows;
+        let heights = &mut self.heights;
+        let measure = self.fixed_height.is_none();
+        data.for_each_in(span.clone(), |child_data, idx| {
+            if let Some(row) = rows.get_mut(&idx) {
+                let size = row.layout(ctx, &child_bc, child_data, env);
+                if measure {
+                    heights[idx] = size.height;
+                }
+                width = width.max(size.width);
+                sizes.push(size);
+            }
+        });
+
+        let height = if bc.is_height_bounded() {
+            bc.max().height
+        } else {
+            self.content_height()
+        };
+        let size = bc.constrain(Size::new(width, height));
+        self.viewport_height = size.height;
+        // the anchor keeps its place as the rows before it are measured,
+        // but the content may now end sooner
+        self.scroll_to(self.offset());
+
+        let offset = self.offset();
+        let mut idx = 0;
+        let mut top = 0.0;
+        let mut tops = Vec::with_capacity(self.rows.len());
+        for row in self.rows.keys() {
+            top += self.span_height(idx..*row);
+            idx = *row;
+            tops.push(top);
+        }
+        // the rows are visited in the same order as they were laid out
+        let rows = &mut self.rows;
+        let mut places = sizes.into_iter().zip(tops);
+        data.for_each_in(span, |child_data, idx| {
+            if let Some(row) = rows.get_mut(&idx) {
+                if let Some((size, top)) = places.next() {
+                    let origin = Point::new(0.0, top - offset);
+                    row.set_layout_rect(ctx, child_data, env, Rect::from_origin_size(origin, size));
+                }
+            }
+        });
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if let Err(e) = ctx.save() {
+            error!("saving render context failed: {:?}", e);
+            return;
+        }
+        let viewport = Rect::from_origin_size(Point::ORIGIN, ctx.size());
+        ctx.clip(viewport);
+        let span = self.row_span();
+        let rows = &mut self.rows;
+        data.for_each_in(span, |child_data, idx| {
+            if let Some(row) = rows.get_mut(&idx) {
+                row.paint_with_offset(ctx, child_data, env);
+            }
+        });
+        if let Err(e) = ctx.restore() {
+            error!("restoring render context failed: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Controller, ControllerHost, IdentityWrapper, List, Scroll, TextBox};
+    use crate::{Code, Command, KeyModifiers, ScrollPhase, Selector, Target, WheelEvent, WidgetId};
+
+    type Items = Arc<Vec<u32>>;
+
+    /// The item that each row widget was last given.
+    type Shown = Rc<RefCell<HashMap<WidgetId, u32>>>;
+
+    /// Resizes the items to the length in the argument.
+    const RESIZE: Selector = Selector::new("druid-tests.resize");
+
+    fn items(len: u32) -> Items {
+        Arc::new((0..len).collect())
+    }
+
+    /// A row that is 20, 30 or 40 tall, and that records what it shows.
+    fn make_row(shown: &Shown) -> impl Widget<u32> {
+        ModularWidget::new(shown.clone())
+            .lifecycle_fn(|shown, ctx, event, data: &u32, _env| {
+                if let LifeCycle::WidgetAdded = event {
+                    shown.borrow_mut().insert(ctx.widget_id(), *data);
+                }
+            })
+            .update_fn(|shown, ctx, _old_data, data, _env| {
+                shown.borrow_mut().insert(ctx.widget_id(), *data);
+            })
+            .layout_fn(|_, _ctx, bc, data, _env| {
+                let height = 20. + (*data % 3) as f64 * 10.;
+                bc.constrain(Size::new(100., height))
+            })
+    }
+
+    /// Handles `RESIZE`, keeping the items that are left.
+    struct Resize;
+
+    impl<W: Widget<Items>> Controller<Items, W> for Resize {
+        fn event(
+            &mut self,
+            child: &mut W,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut Items,
+            env: &Env,
+        ) {
+            if let Event::Command(cmd) = event {
+                if cmd.selector == RESIZE {
+                    let len = *cmd.get_object::<u32>().unwrap();
+                    *data = items(len);
+                }
+            }
+            child.event(ctx, event, data, env)
+        }
+    }
+
+    fn wheel<T: Data>(harness: &mut Harness<T>, delta: f64) {
+        let pos = Point::new(50., 50.);
+        harness.event(Event::MouseMoved(make_mouse(pos)));
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., delta),
+            mods: KeyModifiers::default(),
+            window_anchor: pos,
+            local_anchor: pos,
+            phase: ScrollPhase::Changed,
+            momentum_phase: None,
+        }));
+        harness.just_layout();
+    }
+
+    /// The items that have row widgets.
+    fn shown_items(harness: &mut Harness<Items>, list: WidgetId, shown: &Shown) -> Vec<u32> {
+        let shown = shown.borrow();
+        let mut items: Vec<_> = harness
+            .descendants(list)
+            .iter()
+            .map(|id| shown[id])
+            .collect();
+        items.sort();
+        items
+    }
+
+    /// Where the widget of `item` is, relative to the list.
+    fn item_top(harness: &mut Harness<Items>, list: WidgetId, shown: &Shown, item: u32) -> f64 {
+        let id = harness
+            .descendants(list)
+            .into_iter()
+            .find(|id| shown.borrow()[id] == item)
+            .expect("item has no widget");
+        let list_origin = harness.layout_rect_in_window(list).origin();
+        harness.layout_rect_in_window(id).y0 - list_origin.y
+    }
+
+    #[test]
+    fn rows_in_view_have_widgets() {
+        let list = WidgetId::next();
+        let shown: Shown = Default::default();
+        let rows = shown.clone();
+        // the data type is only known from the harness, so the list can't use
+        // WidgetExt to get its id
+        let widget = VirtualList::new(move || make_row(&rows)).fixed_row_height(20.);
+        let widget = IdentityWrapper::wrap(widget, list);
+        Harness::create(items(50_000), widget, |harness| {
+            harness.set_initial_size(Size::new(200., 200.));
+            harness.send_initial_events();
+            harness.just_layout();
+            // the ten rows in view, the one just below them, and four more
+            assert_eq!(
+                shown_items(harness, list, &shown),
+                (0..15).collect::<Vec<_>>()
+            );
+
+            for _ in 0..50 {
+                wheel(harness, 137.);
+                assert!(harness.descendants(list).len() <= 10 + 1 + 2 * 4);
+            }
+            // 6850 down, the top row is 342
+            let items = shown_items(harness, list, &shown);
+            assert_eq!(items, (338..357).collect::<Vec<_>>());
+            assert_eq!(item_top(harness, list, &shown, 342), -10.);
+
+            // the widgets are reused, so few of them were ever made
+            assert!(shown.borrow().len() < 40);
+        });
+    }
+
+    #[test]
+    fn list_has_a_widget_per_item() {
+        // the same items as above, for comparison
+        let list = WidgetId::next();
+        let shown: Shown = Default::default();
+        let rows = shown.clone();
+        let widget = IdentityWrapper::wrap(List::new(move || make_row(&rows)), list);
+        let widget = Scroll::new(widget).vertical();
+        Harness::create(items(50_000), widget, |harness| {
+            harness.set_initial_size(Size::new(200., 200.));
+            harness.send_initial_events();
+            harness.just_layout();
+            let tree = harness.tree();
+            let rows = tree.iter().filter(|entry| entry.parent == Some(list));
+            assert_eq!(rows.count(), 50_000);
+        });
+    }
+
+    #[test]
+    fn rows_keep_their_place_when_items_change() {
+        let list = WidgetId::next();
+        let shown: Shown = Default::default();
+        let rows = shown.clone();
+        let widget = VirtualList::new(move || make_row(&rows)).estimated_row_height(30.);
+        let widget = ControllerHost::new(IdentityWrapper::wrap(widget, list), Resize);
+        Harness::create(items(1000), widget, |harness| {
+            harness.set_initial_size(Size::new(200., 200.));
+            harness.send_initial_events();
+            harness.just_layout();
+            wheel(harness, 3000.);
+            // measuring the rows in view doesn't move them
+            let top = item_top(harness, list, &shown, 100);
+            harness.just_layout();
+            assert_eq!(item_top(harness, list, &shown, 100), top);
+
+            harness.submit_command(Command::new(RESIZE, 1500u32), Target::Auto);
+            harness.just_layout();
+            assert_eq!(item_top(harness, list, &shown, 100), top);
+
+            harness.submit_command(Command::new(RESIZE, 200u32), Target::Auto);
+            harness.just_layout();
+            assert_eq!(item_top(harness, list, &shown, 100), top);
+            assert!(shown_items(harness, list, &shown)
+                .iter()
+                .all(|item| *item < 200));
+        });
+    }
+
+    #[test]
+    fn focused_row_keeps_its_widget() {
+        let list = WidgetId::next();
+        let widget = VirtualList::new(TextBox::new).fixed_row_height(24.);
+        let widget = IdentityWrapper::wrap(widget, list);
+        let data = Arc::new(vec![String::new(); 1000]);
+        Harness::create(data, widget, |harness| {
+            harness.set_initial_size(Size::new(200., 200.));
+            harness.send_initial_events();
+            harness.just_layout();
+            click(harness, Point::new(50., 36.));
+            let focus = harness.window().focus.expect("row took focus");
+            harness.event(key_down("a", Code::KeyA));
+            assert_eq!(harness.data()[1], "a");
+
+            // the row is far out of view, but it still has the focus
+            wheel(harness, 2400.);
+            assert!(harness.descendants(list).contains(&focus));
+            assert_eq!(harness.window().focus, Some(focus));
+            harness.event(key_down("b", Code::KeyB));
+            assert_eq!(harness.data()[1], "ab");
+
+            wheel(harness, -2400.);
+            assert_eq!(harness.layout_rect_in_window(focus).y0, 24.);
+            assert_eq!(harness.window().focus, Some(focus));
+        });
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that shows or hides its child based on the data.
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, MouseEvent,
+    PaintCtx, UpdateCtx, Widget, WidgetPod,
+};
+
+/// A position that is outside of any widget.
+const OUTSIDE: Point = Point::new(std::f64::NEG_INFINITY, std::f64::NEG_INFINITY);
+
+type PredicateFn<T> = dyn Fn(&T, &Env) -> bool;
+
+/// A widget that shows its child only while a predicate on the data holds.
+///
+/// While the child is hidden it is not painted, and it is left out of the
+/// focus chain. It does not receive keyboard, wheel or zoom events; mouse
+/// events reach it as if the mouse were outside of it, and only until it
+/// stops being hot and any active widget in it is released. Other events,
+/// such as commands and timers, are still delivered.
+///
+/// Created with [`WidgetExt::visible_if`], the hidden child keeps its
+/// layout space; with [`WidgetExt::present_if`], it takes up no space and
+/// is not laid out.
+///
+/// [`WidgetExt::visible_if`]: trait.WidgetExt.html#method.visible_if
+/// [`WidgetExt::present_if`]: trait.WidgetExt.html#method.present_if
+pub struct Visibility<T> {
+    predicate: Box<PredicateFn<T>>,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    /// Whether a hidden child keeps its layout space.
+    keep_space: bool,
+    shown: bool,
+}
+
+impl<T> Visibility<T> {
+    /// Create a widget that hides `child` while `predicate` returns `false`,
+    /// keeping its layout space.
+    pub fn visible_if(
+        predicate: impl Fn(&T, &Env) -> bool + 'static,
+        child: impl Widget<T> + 'static,
+    ) -> Visibility<T> {
+        Visibility::new(predicate, child, true)
+    }
+
+    /// Create a widget that removes `child` from the layout while `predicate`
+    /// returns `false`.
+    pub fn present_if(
+        predicate: impl Fn(&T, &Env) -> bool + 'static,
+        child: impl Widget<T> + 'static,
+    ) -> Visibility<T> {
+        Visibility::new(predicate, child, false)
+    }
+
+    fn new(
+        predicate: impl Fn(&T, &Env) -> bool + 'static,
+        child: impl Widget<T> + 'static,
+        keep_space: bool,
+    ) -> Visibility<T> {
+        Visibility {
+            predicate: Box::new(predicate),
+            child: WidgetPod::new(child).boxed(),
+            keep_space,
+            shown: true,
+        }
+    }
+
+    /// Whether the child is laid out.
+    fn is_laid_out(&self) -> bool {
+        self.shown || self.keep_space
+    }
+}
+
+impl<T: Data> Widget<T> for Visibility<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if self.shown {
+            self.child.event(ctx, event, data, env);
+            return;
+        }
+        // mouse events are only needed to release a child that was hot or active
+        let releasing = self.child.is_hot() || self.child.has_active();
+        match event {
+            Event::MouseDown(_) | Event::MouseUp(_) | Event::MouseMoved(_) if !releasing => (),
+            Event::MouseDown(mouse) => {
+                let event = Event::MouseDown(outside(mouse));
+                self.child.event(ctx, &event, data, env);
+            }
+            Event::MouseUp(mouse) => {
+                let event = Event::MouseUp(outside(mouse));
+                self.child.event(ctx, &event, data, env);
+            }
+            Event::MouseMoved(mouse) => {
+                let event = Event::MouseMoved(outside(mouse));
+                self.child.event(ctx, &event, data, env);
+            }
+            Event::KeyDown(_)
+            | Event::KeyUp(_)
+            | Event::Composition(_)
+            | Event::Paste(_)
+            | Event::Wheel(_)
+            | Event::Zoom(_) => (),
+            // a drag that was over the child has left it
+            Event::DragEnter(_) | Event::DragOver(_) | Event::Drop(_) => {
+                self.child.event(ctx, &Event::DragLeave, data, env);
+            }
+            // we lay out the child again when it is shown
+            Event::Size(_) if !self.keep_space => (),
+            _ => self.child.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.shown = (self.predicate)(data, env);
+        }
+        match event {
+            LifeCycle::WidgetAdded | LifeCycle::RouteWidgetAdded if !self.shown => {
+                let child = &mut self.child;
+                ctx.without_focus(|ctx| child.lifecycle(ctx, event, data, env));
+            }
+            _ => self.child.lifecycle(ctx, event, data, env),
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        let shown = (self.predicate)(data, env);
+        if shown != self.shown {
+            self.shown = shown;
+            // rebuild the focus chain
+            ctx.children_changed();
+            if self.keep_space {
+                ctx.request_paint();
+            } else {
+                ctx.request_layout();
+            }
+        }
+        // a child that is not laid out catches up when it is shown again
+        if self.is_laid_out() {
+            self.child.update(ctx, data, env);
+        }
+    }
+
+    fn layout(
+        &mut self,
+        layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &T,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("Visibility");
+
+        if !self.is_laid_out() {
+            return bc.constrain(Size::ZERO);
+        }
+
+        let size = self.child.layout(layout_ctx, bc, data, env);
+        // a hidden child that keeps its space is never under the mouse
+        let mouse_pos = layout_ctx.mouse_pos;
+        if !self.shown {
+            layout_ctx.mouse_pos = None;
+        }
+        self.child.set_layout_rect(
+            layout_ctx,
+            data,
+            env,
+            Rect::from_origin_size(Point::ORIGIN, size),
+        );
+        layout_ctx.mouse_pos = mouse_pos;
+        let insets = self.child.compute_parent_paint_insets(size);
+        layout_ctx.set_paint_insets(insets);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if self.shown {
+            self.child.paint_with_offset(ctx, data, env);
+        }
+    }
+}
+
+/// The mouse event, moved outside of any widget.
+fn outside(mouse: &MouseEvent) -> MouseEvent {
+    let mut mouse = mouse.clone();
+    mouse.pos = OUTSIDE;
+    mouse
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::tests::harness::*;
+    use crate::tests::helpers::*;
+    use crate::widget::{Align, Controller, WidgetExt};
+    use crate::{Selector, Target, UnitPoint};
+
+    /// Hiding and showing a widget with `visible_if` and `present_if`, starting
+    /// from each state.
+    #[test]
+    fn visibility_transitions() {
+        const TOGGLE: Selector = Selector::new("druid-tests.toggle");
+
+        /// Flips the data when sent `TOGGLE`.
+        struct Toggle;
+
+        impl<W: Widget<bool>> Controller<bool, W> for Toggle {
+            fn event(
+                &mut self,
+                child: &mut W,
+                ctx: &mut EventCtx,
+                event: &Event,
+                data: &mut bool,
+                env: &Env,
+            ) {
+                match event {
+                    Event::Command(cmd) if cmd.selector == TOGGLE => *data = !*data,
+                    _ => child.event(ctx, event, data, env),
+                }
+            }
+        }
+
+        let size = Size::new(100., 50.);
+        for &keep_space in &[true, false] {
+            for &initially_shown in &[true, false] {
+                let (id, child_id) = widget_id2();
+                let clicks = Rc::new(Cell::new(0));
+
+                let child = ModularWidget::new(clicks.clone())
+                    .event_fn(|clicks, _ctx, event, _data: &mut bool, _env| {
+                        if let Event::MouseDown(_) = event {
+                            clicks.set(clicks.get() + 1);
+                        }
+                    })
+                    .lifecycle_fn(|_, ctx, event, _data, _env| {
+                        if let LifeCycle::WidgetAdded = event {
+                            ctx.register_for_focus();
+                        }
+                    })
+                    .layout_fn(move |_, _ctx, bc, _data, _env| bc.constrain(size))
+                    .with_id(child_id);
+                let shown = |data: &bool, _: &Env| *data;
+                let widget = if keep_space {
+                    child.visible_if(shown)
+                } else {
+                    child.present_if(shown)
+                };
+                let widget = Align::new(UnitPoint::TOP_LEFT, widget.with_id(id)).controller(Toggle);
+
+                Harness::create(initially_shown, widget, |harness| {
+                    harness.send_initial_events();
+                    harness.just_layout();
+
+                    let mut expected_clicks = 0;
+                    for &shown in &[initially_shown, !initially_shown] {
+                        if shown != initially_shown {
+                            harness.submit_command(TOGGLE, Target::Auto);
+                            assert_eq!(*harness.data(), shown);
+                            let state = harness.get_state(id);
+                            assert_eq!(state.needs_layout, !keep_space);
+                            // a widget without a size has nothing to paint; the
+                            // whole window is painted again after a layout
+                            assert!(state.size() == Size::ZERO || !state.invalid.is_empty());
+                            harness.just_layout();
+                        }
+
+                        let expected_size = if shown || keep_space {
+                            size
+                        } else {
+                            Size::ZERO
+                        };
+                        assert_eq!(harness.get_state(id).size(), expected_size);
+                        assert_eq!(harness.window().focus_chain().contains(&child_id), shown);
+
+                        click(harness, Point::new(10., 10.));
+                        if shown {
+                            expected_clicks += 1;
+                        }
+                        assert_eq!(clicks.get(), expected_clicks);
+                        assert_eq!(harness.get_state(child_id).is_hot, shown);
+                    }
+                });
+            }
+        }
+    }
+}
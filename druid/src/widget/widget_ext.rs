@@ -14,19 +14,31 @@
 
 //! Convenience methods for widgets.
 
+use std::time::Duration;
+
 use super::{
-    Align, BackgroundBrush, Container, Controller, ControllerHost, EnvScope, IdentityWrapper,
-    Padding, Parse, SizedBox, WidgetId,
+    Align, AnimatedSize, BackgroundBrush, Capture, Click, Clip, Container, Controller,
+    ControllerHost, DisabledIf, DropTarget, EnvScope, IdentityWrapper, LabelFor, LabelText,
+    LabeledBy, Opacity, Padding, Parse, SizedBox, Tooltip, Visibility, WidgetId,
+};
+use crate::kurbo::Shape;
+use crate::{
+    Color, Curve, Data, DragPayload, Env, EnvLens, EventCtx, Insets, KeyOrValue, Lens, LensWrap,
+    Selector, UnitPoint, Widget,
 };
-use crate::{Color, Data, Env, Insets, KeyOrValue, Lens, LensWrap, UnitPoint, Widget};
 
 /// A trait that provides extra methods for combining `Widget`s.
 pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
     /// Wrap this widget in a [`Padding`] widget with the given [`Insets`].
     ///
+    /// The insets can also be a key for insets in the [`Env`]; see
+    /// [`Padding::new`] for the forms they can be given in.
+    ///
     /// [`Padding`]: struct.Padding.html
+    /// [`Padding::new`]: struct.Padding.html#method.new
     /// [`Insets`]: https://docs.rs/kurbo/0.5.4/kurbo/struct.Insets.html
-    fn padding(self, insets: impl Into<Insets>) -> Padding<T> {
+    /// [`Env`]: ../struct.Env.html
+    fn padding(self, insets: impl Into<KeyOrValue<Insets>>) -> Padding<T> {
         Padding::new(insets, self)
     }
 
@@ -51,6 +63,22 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         Align::right(self)
     }
 
+    /// Wrap this widget in an [`Align`] widget, configured to align to the
+    /// leading edge, which is the right when the layout is right to left.
+    ///
+    /// [`Align`]: struct.Align.html
+    fn align_leading(self) -> Align<T> {
+        Align::leading(self)
+    }
+
+    /// Wrap this widget in an [`Align`] widget, configured to align to the
+    /// trailing edge.
+    ///
+    /// [`Align`]: struct.Align.html
+    fn align_trailing(self) -> Align<T> {
+        Align::trailing(self)
+    }
+
     /// Wrap this widget in an [`Align`] widget, configured to align vertically.
     ///
     /// [`Align`]: struct.Align.html
@@ -93,6 +121,20 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         SizedBox::new(self).expand()
     }
 
+    /// Wrap this widget in a [`SizedBox`] with an infinite width.
+    ///
+    /// [`SizedBox`]: struct.SizedBox.html
+    fn expand_width(self) -> SizedBox<T> {
+        SizedBox::new(self).expand_width()
+    }
+
+    /// Wrap this widget in a [`SizedBox`] with an infinite height.
+    ///
+    /// [`SizedBox`]: struct.SizedBox.html
+    fn expand_height(self) -> SizedBox<T> {
+        SizedBox::new(self).expand_height()
+    }
+
     /// Wrap this widget in a [`Container`] with the provided `background`.
     ///
     /// See [`Container::background`] for more information.
@@ -127,6 +169,28 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         EnvScope::new(f, self)
     }
 
+    /// Wrap this widget in an [`AnimatedSize`], which animates the changes
+    /// in its size over `duration`.
+    ///
+    /// [`AnimatedSize`]: struct.AnimatedSize.html
+    fn animate_size(self, duration: Duration) -> AnimatedSize<T> {
+        AnimatedSize::new(self, duration)
+    }
+
+    /// Wrap this widget in an [`Opacity`], which paints it with the opacity
+    /// that the closure returns, fading it in when it is added and
+    /// animating the changes over `duration`.
+    ///
+    /// [`Opacity`]: struct.Opacity.html
+    fn with_opacity_animation(
+        self,
+        opacity: impl Fn(&T, &Env) -> f64 + 'static,
+        duration: Duration,
+        curve: Curve,
+    ) -> Opacity<T> {
+        Opacity::new(self, opacity).animated(duration, curve)
+    }
+
     /// Wrap this widget with the provided [`Controller`].
     ///
     /// [`Controller`]: struct.Controller.html
@@ -134,6 +198,46 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         ControllerHost::new(self, controller)
     }
 
+    /// Call `f` when this widget is clicked.
+    ///
+    /// See [`Click`] for what counts as a click. Unlike a [`Button`], this
+    /// doesn't change how the widget looks.
+    ///
+    /// [`Click`]: struct.Click.html
+    /// [`Button`]: struct.Button.html
+    fn on_click(
+        self,
+        f: impl Fn(&mut EventCtx, &mut T, &Env) + 'static,
+    ) -> ControllerHost<Self, Click<T>> {
+        self.controller(Click::new(f))
+    }
+
+    /// Take things that are dragged onto this widget.
+    ///
+    /// While something is dragged over the widget, `accepts` says whether it
+    /// would be taken; dropping it then calls `on_drop`. See [`DropTarget`].
+    ///
+    /// [`DropTarget`]: struct.DropTarget.html
+    fn on_drop(
+        self,
+        accepts: impl Fn(&DragPayload, &T, &Env) -> bool + 'static,
+        on_drop: impl Fn(&mut EventCtx, &DragPayload, &mut T, &Env) + 'static,
+    ) -> ControllerHost<Self, DropTarget<T>> {
+        self.controller(DropTarget::new(accepts, on_drop))
+    }
+
+    /// Disable this widget while `f` returns `true` for the data.
+    ///
+    /// See [`DisabledIf`] for more information.
+    ///
+    /// [`DisabledIf`]: struct.DisabledIf.html
+    fn disabled_if(
+        self,
+        f: impl Fn(&T, &Env) -> bool + 'static,
+    ) -> ControllerHost<Self, DisabledIf<T>> {
+        self.controller(DisabledIf::new(f))
+    }
+
     /// Draw the [`layout`] `Rect`s of  this widget and its children.
     ///
     /// [`layout`]: trait.Widget.html#tymethod.layout
@@ -141,6 +245,14 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         EnvScope::new(|env, _| env.set(Env::DEBUG_PAINT, true), self)
     }
 
+    /// Paint the [`WidgetId`]s of this widget and its children, each in the
+    /// top right corner of the widget.
+    ///
+    /// [`WidgetId`]: struct.WidgetId.html
+    fn debug_widget_id(self) -> EnvScope<T, Self> {
+        EnvScope::new(|env, _| env.set(Env::DEBUG_WIDGET_ID, true), self)
+    }
+
     /// Set the [`DEBUG_WIDGET`] env variable for this widget (and its descendants).
     ///
     /// This does nothing by default, but you can use this variable while
@@ -151,6 +263,80 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         EnvScope::new(|env, _| env.set(Env::DEBUG_WIDGET, true), self)
     }
 
+    /// Wrap this widget in a [`Clip`] widget that clips it to `shape`,
+    /// which is in this widget's coordinates.
+    ///
+    /// [`Clip`]: struct.Clip.html
+    fn clip(self, shape: impl Shape) -> Clip<T> {
+        Clip::new(shape, self)
+    }
+
+    /// Wrap this widget in a [`Clip`] widget that clips it to its layout
+    /// rect, with the corners rounded off to `radius`.
+    ///
+    /// [`Clip`]: struct.Clip.html
+    fn rounded(self, radius: f64) -> Clip<T> {
+        Clip::rounded(radius, self)
+    }
+
+    /// Wrap this widget in a [`Capture`] widget, which paints it into an
+    /// image when it gets a command with `selector`.
+    ///
+    /// The command's argument must be a [`CaptureRequest`], which says where
+    /// the [`ImageBuf`] is sent.
+    ///
+    /// [`Capture`]: struct.Capture.html
+    /// [`CaptureRequest`]: ../struct.CaptureRequest.html
+    /// [`ImageBuf`]: struct.ImageBuf.html
+    fn capture_on(self, selector: Selector) -> Capture<T> {
+        Capture::new(selector, self)
+    }
+
+    /// Show `text` in a tooltip while the mouse rests on this widget.
+    ///
+    /// See [`Tooltip`] for when the tooltip appears and closes.
+    ///
+    /// [`Tooltip`]: struct.Tooltip.html
+    fn tooltip(self, text: impl Into<LabelText<T>>) -> ControllerHost<Self, Tooltip<T>> {
+        self.controller(Tooltip::new(text))
+    }
+
+    /// Hide this widget while `f` returns `false`, keeping its layout space.
+    ///
+    /// See [`Visibility`] for what it means for a widget to be hidden.
+    ///
+    /// [`Visibility`]: struct.Visibility.html
+    fn visible_if(self, f: impl Fn(&T, &Env) -> bool + 'static) -> Visibility<T> {
+        Visibility::visible_if(f, self)
+    }
+
+    /// Remove this widget from the layout while `f` returns `false`.
+    ///
+    /// See [`Visibility`] for what it means for a widget to be hidden.
+    ///
+    /// [`Visibility`]: struct.Visibility.html
+    fn present_if(self, f: impl Fn(&T, &Env) -> bool + 'static) -> Visibility<T> {
+        Visibility::present_if(f, self)
+    }
+
+    /// Make this widget a label for the widget with the given id.
+    ///
+    /// Clicking this widget will focus the target. See [`LabelFor`].
+    ///
+    /// [`LabelFor`]: struct.LabelFor.html
+    fn labels(self, target: WidgetId) -> ControllerHost<Self, LabelFor> {
+        self.controller(LabelFor::new(target))
+    }
+
+    /// Record that this widget is labeled by the widget with the given id.
+    ///
+    /// See [`LabeledBy`].
+    ///
+    /// [`LabeledBy`]: struct.LabeledBy.html
+    fn labeled_by(self, label: WidgetId) -> ControllerHost<Self, LabeledBy> {
+        self.controller(LabeledBy::new(label))
+    }
+
     /// Wrap this widget in a [`LensWrap`] widget for the provided [`Lens`].
     ///
     ///
@@ -160,6 +346,16 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         LensWrap::new(self, lens)
     }
 
+    /// Wrap this widget in an [`EnvLens`], which shows it the [`Env`] that
+    /// the provided [`Lens`] exposes.
+    ///
+    /// [`EnvLens`]: ../struct.EnvLens.html
+    /// [`Env`]: ../struct.Env.html
+    /// [`Lens`]: ../trait.Lens.html
+    fn env_lens<L: Lens<Env, Env>>(self, lens: L) -> EnvLens<L, Self> {
+        EnvLens::new(self, lens)
+    }
+
     /// Parse a `Widget<String>`'s contents
     fn parse(self) -> Parse<Self>
     where
@@ -220,6 +416,10 @@ impl<T: Data, W> EnvScope<T, W> {
     pub fn debug_paint_layout(self) -> EnvScope<T, W> {
         self.env_scope(|env, _| env.set(Env::DEBUG_PAINT, true))
     }
+
+    pub fn debug_widget_id(self) -> EnvScope<T, W> {
+        self.env_scope(|env, _| env.set(Env::DEBUG_WIDGET_ID, true))
+    }
 }
 
 #[cfg(test)]
@@ -17,22 +17,25 @@
 use std::any::Any;
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
 use std::rc::Rc;
 
-use crate::kurbo::{Size, Vec2};
+use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::piet::Piet;
 use crate::shell::{
-    Application, FileDialogOptions, IdleToken, MouseEvent, WinHandler, WindowHandle,
+    AppIdleHandle, Application, DragEvent, FileDialogOptions, IdleToken, MouseEvent, Scale,
+    ScrollPhase, WinHandler, WindowHandle, WindowState,
 };
 
-use crate::app_delegate::{AppDelegate, DelegateCtx};
+use crate::app_delegate::{AppDelegate, DelegateCtx, Handled};
 use crate::core::CommandQueue;
 use crate::ext_event::ExtEventHost;
 use crate::menu::ContextMenu;
+use crate::sub_window::{SubWindowDesc, NEW_SUB_WINDOW};
 use crate::window::Window;
 use crate::{
-    Command, Data, Env, Event, KeyEvent, KeyModifiers, MenuDesc, Target, TimerToken, WheelEvent,
-    WindowDesc, WindowId,
+    theme, Command, CompositionEvent, Data, Env, Event, KbKey, KeyEvent, KeyModifiers, MenuDesc,
+    Target, TimerToken, WheelEvent, WindowDesc, WindowId, ZoomEvent,
 };
 
 use crate::command::sys as sys_cmd;
@@ -42,6 +45,9 @@ pub(crate) const RUN_COMMANDS_TOKEN: IdleToken = IdleToken::new(1);
 /// A token we are called back with if an external event was submitted.
 pub(crate) const EXT_EVENT_IDLE_TOKEN: IdleToken = IdleToken::new(2);
 
+/// A token we are called back with if widgets have requested idle events.
+pub(crate) const WIDGET_IDLE_TOKEN: IdleToken = IdleToken::new(3);
+
 /// The struct implements the druid-shell `WinHandler` trait.
 ///
 /// One `DruidHandler` exists per window.
@@ -60,7 +66,8 @@ pub struct DruidHandler<T> {
 /// This corresponds to the `AppHandler` trait in druid-shell, which is only
 /// used to handle events that are not associated with a window.
 ///
-/// Currently, this means only menu items on macOS when no window is open.
+/// Currently, this means menu items on macOS when no window is open, and
+/// external events, which can arrive before any window exists.
 pub(crate) struct AppHandler<T> {
     app_state: AppState<T>,
 }
@@ -79,6 +86,9 @@ struct Inner<T> {
     /// the application-level menu, only set on macos and only if there
     /// are no open windows.
     root_menu: Option<MenuDesc<T>>,
+    /// Whether the app was launched without windows and has not opened
+    /// one yet.
+    startup_pending: bool,
     pub(crate) env: Env,
     pub(crate) data: T,
 }
@@ -137,6 +147,7 @@ impl<T> AppState<T> {
             delegate,
             command_queue: VecDeque::new(),
             root_menu: None,
+            startup_pending: false,
             ext_event_host,
             data,
             env,
@@ -148,13 +159,15 @@ impl<T> AppState<T> {
 }
 
 impl<T: Data> Inner<T> {
-    fn get_menu_cmd(&self, window_id: Option<WindowId>, cmd_id: u32) -> Option<Command> {
+    /// The command for a menu item, along with where it should be sent.
+    fn get_menu_cmd(&self, window_id: Option<WindowId>, cmd_id: u32) -> Option<(Target, Command)> {
         match window_id {
             Some(id) => self.windows.get(id).and_then(|w| w.get_menu_cmd(cmd_id)),
             None => self
                 .root_menu
                 .as_ref()
-                .and_then(|m| m.command_for_id(cmd_id)),
+                .and_then(|m| m.command_for_id(cmd_id))
+                .map(|cmd| (Target::Global, cmd)),
         }
     }
 
@@ -193,62 +206,67 @@ impl<T: Data> Inner<T> {
         }
     }
 
-    fn delegate_cmd(&mut self, target: &Target, cmd: &Command) -> bool {
+    fn delegate_cmd(&mut self, target: &Target, cmd: &Command) -> Handled {
         self.with_delegate(|del, data, env, ctx| del.command(ctx, target, cmd, data, env))
-            .unwrap_or(true)
+            .unwrap_or(Handled::No)
     }
 
     fn connect(&mut self, id: WindowId, handle: WindowHandle) {
         self.windows.connect(id, handle);
-
-        // If the external event host has no handle, it cannot wake us
-        // when an event arrives.
-        if self.ext_event_host.handle_window_id.is_none() {
-            self.set_ext_event_idle_handler(id);
-        }
-
+        self.startup_pending = false;
         self.with_delegate(|del, data, env, ctx| del.window_added(id, data, env, ctx));
     }
 
     /// Called after this window has been closed by the platform.
     ///
     /// We clean up resources and notifiy the delegate, if necessary.
-    fn remove_window(&mut self, window_id: WindowId) {
+    ///
+    /// Returns `true` if this was the last window.
+    /// Send `WindowDisconnected` to a window's widgets, if it hasn't been.
+    ///
+    /// Returns `true` if it was sent.
+    fn disconnect_window(&mut self, window_id: WindowId) -> bool {
+        match self.windows.get_mut(window_id) {
+            Some(win) => win.disconnect(&mut self.command_queue, &mut self.data, &self.env),
+            None => false,
+        }
+    }
+
+    /// Send `WindowDisconnected` to the widgets of all windows, for quitting.
+    fn disconnect_all(&mut self) {
+        for win in self.windows.iter_mut() {
+            win.disconnect(&mut self.command_queue, &mut self.data, &self.env);
+        }
+    }
+
+    fn remove_window(&mut self, window_id: WindowId) -> bool {
+        // popups don't outlive the window they were opened from
+        for win in self.windows.iter_mut() {
+            if win.popup_of == Some(window_id) {
+                win.handle.close();
+            }
+        }
         self.with_delegate(|del, data, env, ctx| del.window_removed(window_id, data, env, ctx));
         // when closing the last window:
         if let Some(mut win) = self.windows.remove(window_id) {
             if self.windows.windows.is_empty() {
                 // on mac we need to keep the menu around
                 self.root_menu = win.menu.take();
-                //FIXME: on windows we need to shutdown the app here?
-            }
-        }
-
-        // if we are closing the window that is currently responsible for
-        // waking us when external events arrive, we want to pass that responsibility
-        // to another window.
-        if self.ext_event_host.handle_window_id == Some(window_id) {
-            self.ext_event_host.handle_window_id = None;
-            // find any other live window
-            let win_id = self.windows.windows.keys().find(|k| *k != &window_id);
-            if let Some(any_other_window) = win_id.cloned() {
-                self.set_ext_event_idle_handler(any_other_window);
+                return true;
             }
         }
+        false
     }
 
-    /// Set the idle handle that will be used to wake us when external events arrive.
-    fn set_ext_event_idle_handler(&mut self, id: WindowId) {
-        if let Some(mut idle) = self
-            .windows
-            .get_mut(id)
-            .and_then(|win| win.handle.get_idle_handle())
-        {
-            if self.ext_event_host.has_pending_items() {
-                idle.schedule_idle(EXT_EVENT_IDLE_TOKEN);
-            }
-            self.ext_event_host.set_idle(idle, id);
-        }
+    /// Whether the app should keep running while it has no open windows.
+    ///
+    /// On macOS it always does. Elsewhere, it does while a window is being
+    /// created, or while startup is pending: the app was launched without
+    /// windows, and has not opened one yet.
+    fn keep_alive(&self) -> bool {
+        cfg!(all(target_os = "macos", not(feature = "use_gtk")))
+            || self.startup_pending
+            || !self.windows.pending.is_empty()
     }
 
     /// triggered by a menu item or other command.
@@ -269,9 +287,10 @@ impl<T: Data> Inner<T> {
     }
 
     /// Returns `true` if an animation frame was requested.
-    fn paint(&mut self, window_id: WindowId, piet: &mut Piet) -> bool {
+    fn paint(&mut self, window_id: WindowId, piet: &mut Piet, invalid_rect: Rect) -> bool {
         if let Some(win) = self.windows.get_mut(window_id) {
-            win.do_paint(piet, &mut self.command_queue, &self.data, &self.env);
+            let queue = &mut self.command_queue;
+            win.do_paint(piet, invalid_rect, queue, &self.data, &self.env);
             win.wants_animation_frame()
         } else {
             false
@@ -279,7 +298,7 @@ impl<T: Data> Inner<T> {
     }
 
     fn dispatch_cmd(&mut self, target: Target, cmd: Command) {
-        if !self.delegate_cmd(&target, &cmd) {
+        if self.delegate_cmd(&target, &cmd).is_handled() {
             return;
         }
 
@@ -306,14 +325,9 @@ impl<T: Data> Inner<T> {
                     }
                 }
             }
-            Target::Global => {
-                for w in self.windows.iter_mut() {
-                    let event = Event::Command(cmd.clone());
-                    if w.event(&mut self.command_queue, event, &mut self.data, &self.env) {
-                        break;
-                    }
-                }
-            }
+            // global commands are only for the delegate, which has seen them
+            Target::Global => (),
+            Target::Auto => log::warn!("{:?} was submitted without resolving its target", cmd),
         }
     }
 
@@ -325,6 +339,10 @@ impl<T: Data> Inner<T> {
             _ => (),
         }
 
+        if self.dismiss_popups(source_id, &event) {
+            return true;
+        }
+
         // if the event was swallowed by the delegate we consider it handled?
         let event = match self.delegate_event(source_id, event) {
             Some(event) => event,
@@ -338,6 +356,28 @@ impl<T: Data> Inner<T> {
         }
     }
 
+    /// Close the popups that an event dismisses: a click in the window they
+    /// were opened from, or Escape in it or in one of them.
+    ///
+    /// Returns `true` if Escape closed a popup, in which case it is not sent
+    /// to the widgets.
+    fn dismiss_popups(&mut self, source_id: WindowId, event: &Event) -> bool {
+        let escape = match event {
+            Event::KeyDown(key) => key.key == KbKey::Escape,
+            Event::MouseDown(_) => false,
+            _ => return false,
+        };
+        let mut dismissed = false;
+        for win in self.windows.iter_mut() {
+            let is_popup = win.popup_of.is_some();
+            if win.popup_of == Some(source_id) || escape && is_popup && win.id == source_id {
+                win.handle.close();
+                dismissed = true;
+            }
+        }
+        escape && dismissed
+    }
+
     fn set_menu(&mut self, window_id: WindowId, cmd: &Command) {
         if let Some(win) = self.windows.get_mut(window_id) {
             match cmd.get_object::<MenuDesc<T>>() {
@@ -350,20 +390,37 @@ impl<T: Data> Inner<T> {
     fn show_context_menu(&mut self, window_id: WindowId, cmd: &Command) {
         if let Some(win) = self.windows.get_mut(window_id) {
             match cmd.get_object::<ContextMenu<T>>() {
-                Ok(ContextMenu { menu, location }) => {
-                    win.show_context_menu(menu.to_owned(), *location, &self.data, &self.env)
-                }
+                Ok(menu) => win.show_context_menu(menu.to_owned(), &self.data, &self.env),
                 Err(e) => log::warn!("show-context-menu object error: '{}'", e),
             }
         }
     }
 
+    /// Send the idle events that widgets have requested, in all windows.
+    fn do_idle(&mut self) {
+        for win in self.windows.iter_mut() {
+            win.idle(&mut self.command_queue, &mut self.data, &self.env);
+        }
+    }
+
     fn do_update(&mut self) {
         // we send `update` to all windows, not just the active one:
         for window in self.windows.iter_mut() {
-            window.update(&self.data, &self.env);
+            window.update(&mut self.command_queue, &self.data, &self.env);
         }
         self.invalidate_and_finalize();
+
+        // commands submitted during update, such as those that keep
+        // sub-windows in sync, get their own event/update pass
+        if !self.command_queue.is_empty() {
+            let idle_handle = self
+                .windows
+                .iter_mut()
+                .find_map(|w| w.handle.get_idle_handle());
+            if let Some(mut handle) = idle_handle {
+                handle.schedule_idle(RUN_COMMANDS_TOKEN);
+            }
+        }
     }
 
     /// invalidate any window handles that need it.
@@ -376,6 +433,23 @@ impl<T: Data> Inner<T> {
         }
     }
 
+    /// Update the environment to match the system settings.
+    ///
+    /// This overrides any value that was set in `AppLauncher::configure_env`.
+    fn system_settings_changed(&mut self) {
+        let animations_enabled = !Application::prefers_reduced_motion();
+        if self.env.get(theme::ANIMATIONS_ENABLED) != animations_enabled {
+            self.env.set(theme::ANIMATIONS_ENABLED, animations_enabled);
+            self.do_update();
+        }
+    }
+
+    fn accessibility_activated(&mut self, window_id: WindowId) {
+        if let Some(win) = self.windows.get_mut(window_id) {
+            win.accessibility_activated();
+        }
+    }
+
     #[cfg(target_os = "macos")]
     fn window_got_focus(&mut self, window_id: WindowId) {
         if let Some(win) = self.windows.get_mut(window_id) {
@@ -410,18 +484,52 @@ impl<T: Data> AppState<T> {
         self.inner.borrow_mut().windows.add(id, window);
     }
 
+    /// Keep the app running without windows until the first one is opened.
+    ///
+    /// This is used when the app is launched without windows, so that the
+    /// delegate can decide which window to open.
+    pub(crate) fn set_startup_pending(&self) {
+        self.inner.borrow_mut().startup_pending = true;
+    }
+
+    /// Whether the app should keep running while it has no open windows.
+    pub(crate) fn keep_alive(&self) -> bool {
+        self.inner.borrow().keep_alive()
+    }
+
+    /// Set the handle that wakes us when external events arrive.
+    pub(crate) fn set_ext_event_idle_handle(&self, handle: AppIdleHandle) {
+        self.inner.borrow_mut().ext_event_host.set_idle(handle);
+    }
+
     fn connect_window(&mut self, window_id: WindowId, handle: WindowHandle) {
         self.inner.borrow_mut().connect(window_id, handle)
     }
 
     fn remove_window(&mut self, window_id: WindowId) {
-        self.inner.borrow_mut().remove_window(window_id)
+        // the window is still there while the commands from its teardown run
+        if self.inner.borrow_mut().disconnect_window(window_id) {
+            self.process_commands();
+            self.inner.borrow_mut().do_update();
+        }
+        let last_window = self.inner.borrow_mut().remove_window(window_id);
+        if last_window && !self.keep_alive() {
+            self.quit();
+        }
     }
 
     fn window_got_focus(&mut self, window_id: WindowId) {
         self.inner.borrow_mut().window_got_focus(window_id)
     }
 
+    fn system_settings_changed(&mut self) {
+        self.inner.borrow_mut().system_settings_changed()
+    }
+
+    fn accessibility_activated(&mut self, window_id: WindowId) {
+        self.inner.borrow_mut().accessibility_activated(window_id)
+    }
+
     /// Send an event to the widget hierarchy.
     ///
     /// Returns `true` if the event produced an action.
@@ -435,21 +543,26 @@ impl<T: Data> AppState<T> {
         result
     }
 
-    fn paint_window(&mut self, window_id: WindowId, piet: &mut Piet) -> bool {
-        self.inner.borrow_mut().paint(window_id, piet)
+    fn paint_window(&mut self, window_id: WindowId, piet: &mut Piet, invalid_rect: Rect) -> bool {
+        self.inner.borrow_mut().paint(window_id, piet, invalid_rect)
     }
 
     fn idle(&mut self, token: IdleToken) {
         match token {
             RUN_COMMANDS_TOKEN => {
                 self.process_commands();
-                self.inner.borrow_mut().invalidate_and_finalize();
+                self.inner.borrow_mut().do_update();
             }
             EXT_EVENT_IDLE_TOKEN => {
                 self.process_ext_events();
                 self.process_commands();
                 self.inner.borrow_mut().do_update();
             }
+            WIDGET_IDLE_TOKEN => {
+                self.inner.borrow_mut().do_idle();
+                self.process_commands();
+                self.inner.borrow_mut().do_update();
+            }
             other => log::warn!("unexpected idle token {:?}", other),
         }
     }
@@ -468,7 +581,7 @@ impl<T: Data> AppState<T> {
         loop {
             let ext_cmd = self.inner.borrow_mut().ext_event_host.recv();
             match ext_cmd {
-                Some((targ, cmd)) => self.handle_cmd(targ.unwrap_or(Target::Global), cmd),
+                Some((targ, cmd)) => self.handle_cmd(targ, cmd),
                 None => break,
             }
         }
@@ -482,9 +595,8 @@ impl<T: Data> AppState<T> {
     /// is open but a menu exists, as on macOS) it will be `None`.
     fn handle_system_cmd(&mut self, cmd_id: u32, window_id: Option<WindowId>) {
         let cmd = self.inner.borrow().get_menu_cmd(window_id, cmd_id);
-        let target = window_id.map(Into::into).unwrap_or(Target::Global);
         match cmd {
-            Some(cmd) => self.inner.borrow_mut().append_command(target, cmd),
+            Some((target, cmd)) => self.inner.borrow_mut().append_command(target, cmd),
             None => log::warn!("No command for menu id {}", cmd_id),
         }
         self.process_commands()
@@ -504,12 +616,20 @@ impl<T: Data> AppState<T> {
                     log::error!("failed to create window: '{}'", e);
                 }
             }
+            (_, &NEW_SUB_WINDOW) => {
+                if let Err(e) = self.new_sub_window(cmd) {
+                    log::error!("failed to create sub-window: '{}'", e);
+                }
+            }
             // these should come from a window
             // FIXME: we need to be  able to open a file without a window handle
             (T::Window(id), &sys_cmd::SHOW_OPEN_PANEL) => self.show_open_panel(cmd, id),
             (T::Window(id), &sys_cmd::SHOW_SAVE_PANEL) => self.show_save_panel(cmd, id),
             (T::Window(id), &sys_cmd::CLOSE_WINDOW) => self.request_close_window(cmd, id),
             (T::Window(_), &sys_cmd::SHOW_WINDOW) => self.show_window(cmd),
+            (T::Window(id), &sys_cmd::SET_WINDOW_SIZE) => self.set_window_size(cmd, id),
+            (T::Window(id), &sys_cmd::SET_WINDOW_POSITION) => self.set_window_position(cmd, id),
+            (T::Window(id), &sys_cmd::SET_WINDOW_STATE) => self.set_window_state(cmd, id),
             (T::Window(id), &sys_cmd::PASTE) => self.do_paste(id),
             _sel => self.inner.borrow_mut().dispatch_cmd(target, cmd),
         }
@@ -562,6 +682,13 @@ impl<T: Data> AppState<T> {
         Ok(())
     }
 
+    fn new_sub_window(&mut self, cmd: Command) -> Result<(), Box<dyn std::error::Error>> {
+        let desc = cmd.take_object::<SubWindowDesc>()?;
+        let window = desc.make_window_desc::<T>().build_native(self)?;
+        window.show();
+        Ok(())
+    }
+
     fn request_close_window(&mut self, cmd: Command, window_id: WindowId) {
         let id = cmd.get_object().unwrap_or(&window_id);
         self.inner.borrow_mut().request_close_window(*id);
@@ -574,12 +701,71 @@ impl<T: Data> AppState<T> {
         self.inner.borrow_mut().show_window(id);
     }
 
+    /// Get a clone of a window's handle.
+    ///
+    /// The platform may call back into the handler synchronously when we
+    /// resize or move a window, so we must not be borrowed while we do.
+    fn window_handle(&self, window_id: WindowId) -> Option<WindowHandle> {
+        self.inner
+            .borrow()
+            .windows
+            .get(window_id)
+            .map(|w| w.handle.clone())
+    }
+
+    fn set_window_size(&mut self, cmd: Command, window_id: WindowId) {
+        match cmd.get_object::<Size>() {
+            Ok(size) => {
+                if let Some(handle) = self.window_handle(window_id) {
+                    handle.set_size(*size);
+                }
+            }
+            Err(e) => log::warn!("set-window-size object error: '{}'", e),
+        }
+    }
+
+    fn set_window_position(&mut self, cmd: Command, window_id: WindowId) {
+        match cmd.get_object::<Point>() {
+            Ok(position) => {
+                if let Some(handle) = self.window_handle(window_id) {
+                    handle.set_position(*position);
+                }
+            }
+            Err(e) => log::warn!("set-window-position object error: '{}'", e),
+        }
+    }
+
+    fn set_window_state(&mut self, cmd: Command, window_id: WindowId) {
+        match cmd.get_object::<WindowState>() {
+            Ok(state) => {
+                if let Some(handle) = self.window_handle(window_id) {
+                    handle.set_window_state(*state);
+                }
+            }
+            Err(e) => log::warn!("set-window-state object error: '{}'", e),
+        }
+    }
+
     fn do_paste(&mut self, window_id: WindowId) {
         let event = Event::Paste(Application::clipboard());
         self.inner.borrow_mut().do_window_event(window_id, event);
     }
 
-    fn quit(&self) {
+    /// Disconnect all windows, and quit.
+    ///
+    /// The platform may exit without destroying the windows, so they are
+    /// disconnected here; commands submitted during that are dropped.
+    fn quit(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.disconnect_all();
+        if !inner.command_queue.is_empty() {
+            log::warn!(
+                "dropping {} commands submitted while quitting",
+                inner.command_queue.len()
+            );
+            inner.command_queue.clear();
+        }
+        drop(inner);
         Application::quit()
     }
 
@@ -598,6 +784,10 @@ impl<T: Data> crate::shell::AppHandler for AppHandler<T> {
     fn command(&mut self, id: u32) {
         self.app_state.handle_system_cmd(id, None)
     }
+
+    fn idle(&mut self, token: IdleToken) {
+        self.app_state.idle(token);
+    }
 }
 
 impl<T: Data> WinHandler for DruidHandler<T> {
@@ -609,8 +799,9 @@ impl<T: Data> WinHandler for DruidHandler<T> {
         self.app_state.do_window_event(event, self.window_id);
     }
 
-    fn paint(&mut self, piet: &mut Piet) -> bool {
-        self.app_state.paint_window(self.window_id, piet)
+    fn paint(&mut self, piet: &mut Piet, invalid_rect: Rect) -> bool {
+        self.app_state
+            .paint_window(self.window_id, piet, invalid_rect)
     }
 
     fn size(&mut self, width: u32, height: u32) {
@@ -618,6 +809,11 @@ impl<T: Data> WinHandler for DruidHandler<T> {
         self.app_state.do_window_event(event, self.window_id);
     }
 
+    fn scale(&mut self, scale: Scale) {
+        let event = Event::WindowScale(scale);
+        self.app_state.do_window_event(event, self.window_id);
+    }
+
     fn command(&mut self, id: u32) {
         self.app_state.handle_system_cmd(id, Some(self.window_id));
     }
@@ -638,6 +834,31 @@ impl<T: Data> WinHandler for DruidHandler<T> {
         self.app_state.do_window_event(event, self.window_id);
     }
 
+    fn mouse_capture_lost(&mut self) {
+        self.app_state
+            .do_window_event(Event::MouseCaptureLost, self.window_id);
+    }
+
+    fn drag_enter(&mut self, event: &DragEvent) -> bool {
+        let event = Event::DragEnter(event.clone().into());
+        self.app_state.do_window_event(event, self.window_id)
+    }
+
+    fn drag_over(&mut self, event: &DragEvent) -> bool {
+        let event = Event::DragOver(event.clone().into());
+        self.app_state.do_window_event(event, self.window_id)
+    }
+
+    fn drag_leave(&mut self) {
+        self.app_state
+            .do_window_event(Event::DragLeave, self.window_id);
+    }
+
+    fn drag_drop(&mut self, event: &DragEvent) -> bool {
+        let event = Event::Drop(event.clone().into());
+        self.app_state.do_window_event(event, self.window_id)
+    }
+
     fn key_down(&mut self, event: KeyEvent) -> bool {
         self.app_state
             .do_window_event(Event::KeyDown(event), self.window_id)
@@ -648,13 +869,50 @@ impl<T: Data> WinHandler for DruidHandler<T> {
             .do_window_event(Event::KeyUp(event), self.window_id);
     }
 
+    fn composition_start(&mut self) {
+        let event = Event::Composition(CompositionEvent::Start);
+        self.app_state.do_window_event(event, self.window_id);
+    }
+
+    fn composition_update(&mut self, text: &str, cursor: Range<usize>) {
+        let event = Event::Composition(CompositionEvent::Update(text.to_string(), cursor));
+        self.app_state.do_window_event(event, self.window_id);
+    }
+
+    fn composition_end(&mut self, text: &str) {
+        let event = Event::Composition(CompositionEvent::End(text.to_string()));
+        self.app_state.do_window_event(event, self.window_id);
+    }
+
     fn wheel(&mut self, delta: Vec2, mods: KeyModifiers) {
-        let event = Event::Wheel(WheelEvent { delta, mods });
+        self.wheel_with_phase(delta, mods, ScrollPhase::Changed, None);
+    }
+
+    fn wheel_with_phase(
+        &mut self,
+        delta: Vec2,
+        mods: KeyModifiers,
+        phase: ScrollPhase,
+        momentum_phase: Option<ScrollPhase>,
+    ) {
+        // the anchor is filled in by the window, which knows where the mouse is
+        let event = Event::Wheel(WheelEvent {
+            delta,
+            mods,
+            window_anchor: Point::ZERO,
+            local_anchor: Point::ZERO,
+            phase,
+            momentum_phase,
+        });
         self.app_state.do_window_event(event, self.window_id);
     }
 
     fn zoom(&mut self, delta: f64) {
-        let event = Event::Zoom(delta);
+        let event = Event::Zoom(ZoomEvent {
+            delta,
+            window_anchor: Point::ZERO,
+            local_anchor: Point::ZERO,
+        });
         self.app_state.do_window_event(event, self.window_id);
     }
 
@@ -662,6 +920,14 @@ impl<T: Data> WinHandler for DruidHandler<T> {
         self.app_state.window_got_focus(self.window_id);
     }
 
+    fn system_settings_changed(&mut self) {
+        self.app_state.system_settings_changed();
+    }
+
+    fn accessibility_activated(&mut self) {
+        self.app_state.accessibility_activated(self.window_id);
+    }
+
     fn timer(&mut self, token: TimerToken) {
         self.app_state
             .do_window_event(Event::Timer(token), self.window_id);
@@ -14,20 +14,60 @@
 
 //! Management of multiple windows.
 
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::mem;
-use std::time::Instant;
+use std::rc::Weak;
+use std::time::{Duration, Instant};
 
-use crate::kurbo::{Insets, Point, Rect, Size};
-use crate::piet::{Piet, RenderContext};
-use crate::shell::{Counter, Cursor, WindowHandle};
+use crate::kurbo::{Affine, Insets, Point, Rect, Size};
+use crate::piet::{Color, ImageFormat, Piet, RenderContext};
+use crate::shell::{
+    Application, Clipboard, Code, Counter, Cursor, HotKey, KbKey, KeyEvent, Scale, SysMods,
+    WindowHandle,
+};
 
+use crate::access::{self, AccessNode, AccessTreeBuilder};
 use crate::core::{BaseState, CommandQueue, FocusChange};
-use crate::win_handler::RUN_COMMANDS_TOKEN;
+use crate::event::FocusAnchor;
+use crate::widget::{HostedView, ImageBuf, LabelText};
+use crate::win_handler::{RUN_COMMANDS_TOKEN, WIDGET_IDLE_TOKEN};
 use crate::{
-    BoxConstraints, Command, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
-    LocalizedString, MenuDesc, PaintCtx, UpdateCtx, Widget, WidgetId, WidgetPod, WindowDesc,
+    commands, BoxConstraints, CaptureRequest, Command, ContextMenu, Data, DragEvent, DragPayload,
+    Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, MenuDesc, MouseEvent, PaintCtx,
+    Region, Selector, Target, UpdateCtx, Widget, WidgetId, WidgetPod, WindowDesc,
 };
 
+/// The smallest content scale that can be set with [`SET_USER_SCALE`].
+///
+/// [`SET_USER_SCALE`]: commands/constant.SET_USER_SCALE.html
+const MIN_USER_SCALE: f64 = 0.25;
+/// The largest content scale that can be set with [`SET_USER_SCALE`].
+///
+/// [`SET_USER_SCALE`]: commands/constant.SET_USER_SCALE.html
+const MAX_USER_SCALE: f64 = 5.0;
+/// The factor by which `ZOOM_IN` and `ZOOM_OUT` change the content scale.
+const ZOOM_STEP: f64 = 1.25;
+
+/// Start dragging something inside the window. The argument must be a
+/// [`DragStart`].
+///
+/// [`DragStart`]: struct.DragStart.html
+pub(crate) const START_DRAG: Selector = Selector::new("druid-builtin.start-drag");
+
+/// What a widget started dragging, and which widget it was.
+pub(crate) struct DragStart {
+    pub(crate) payload: DragPayload,
+    pub(crate) source: WidgetId,
+}
+
+/// A drag that a widget in the window started, which follows the mouse
+/// until the button is released.
+struct DragSession {
+    payload: DragPayload,
+    source: WidgetId,
+}
+
 /// A unique identifier for a window.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct WindowId(u64);
@@ -36,28 +76,99 @@ pub struct WindowId(u64);
 pub struct Window<T> {
     pub(crate) id: WindowId,
     pub(crate) root: WidgetPod<T, Box<dyn Widget<T>>>,
-    pub(crate) title: LocalizedString<T>,
+    pub(crate) title: LabelText<T>,
+    /// The title last given to the platform window.
+    pub(crate) platform_title: Option<String>,
+    /// The size of the window, in display points.
     size: Size,
+    /// The smallest size of the window, in display points.
+    pub(crate) min_size: Option<Size>,
+    /// The scale of the content, on top of the platform's DPI scaling.
+    user_scale: f64,
+    /// The number of physical pixels in a display point.
+    scale: Scale,
+    /// The time that each layout pass may take before it defers offscreen
+    /// widgets, if incremental layout is enabled.
+    pub(crate) layout_budget: Option<Duration>,
+    /// The background color given in the `WindowDesc`.
+    pub(crate) desc_background: Option<Color>,
+    /// The background color set with `SET_BACKGROUND_COLOR`, which takes
+    /// precedence over the one in the `WindowDesc`.
+    background: Option<Color>,
+    /// The background color last given to the platform window.
+    pub(crate) platform_background: Option<Color>,
     pub(crate) menu: Option<MenuDesc<T>>,
-    pub(crate) context_menu: Option<MenuDesc<T>>,
+    pub(crate) context_menu: Option<ContextMenu<T>>,
     pub(crate) last_anim: Option<Instant>,
     pub(crate) focus: Option<WidgetId>,
+    /// The last known position of the mouse, if it has been seen, used to
+    /// anchor wheel and zoom events and to find the widgets that layout moves
+    /// under it.
+    last_mouse_pos: Option<Point>,
     pub(crate) handle: WindowHandle,
+    /// The clipboard used by widgets in this window.
+    pub(crate) clipboard: Clipboard,
+    /// The native views that were shown in the last paint.
+    native_views: Vec<Weak<RefCell<HostedView>>>,
+    /// The platform has been asked to call us back on idle, to send the
+    /// idle events that widgets requested.
+    idle_scheduled: bool,
+    /// `WindowDisconnected` has been sent.
+    disconnected: bool,
+    /// The cursor last given to the platform window.
+    cursor: Option<Cursor>,
+    /// The platform window has been asked to capture the mouse.
+    pub(crate) mouse_captured: bool,
+    /// If this is a popup sub-window, the window it was opened from, where a
+    /// click dismisses it.
+    pub(crate) popup_of: Option<WindowId>,
+    /// A screen reader has asked for the accessibility tree, so it is kept
+    /// up to date.
+    access_active: bool,
+    /// The accessibility tree last given to the platform window.
+    access_tree: Option<AccessNode>,
+    /// The drag that a widget started, if one is going on.
+    drag: Option<DragSession>,
     // delegate?
 }
 
 impl<T> Window<T> {
     pub(crate) fn new(id: WindowId, handle: WindowHandle, desc: WindowDesc<T>) -> Window<T> {
+        let mut root = WidgetPod::new(desc.root);
+        // a sub-window's contents have their own data, which can change
+        // when the application's doesn't
+        root.set_always_update(desc.sub_window_of.is_some());
+        let is_popup = desc.config.level.is_popup();
+        let popup_of = desc.sub_window_of.filter(|_| is_popup);
         Window {
             id,
-            root: WidgetPod::new(desc.root),
+            root,
             size: Size::ZERO,
+            min_size: desc.config.min_size,
+            user_scale: 1.0,
+            scale: handle.get_scale(),
+            layout_budget: desc.layout_budget,
+            desc_background: desc.background,
+            background: None,
+            platform_background: None,
             title: desc.title,
+            platform_title: None,
             menu: desc.menu,
             context_menu: None,
             last_anim: None,
             focus: None,
+            last_mouse_pos: None,
             handle,
+            clipboard: Application::clipboard(),
+            native_views: Vec::new(),
+            idle_scheduled: false,
+            disconnected: false,
+            cursor: None,
+            mouse_captured: false,
+            popup_of,
+            access_active: false,
+            access_tree: None,
+            drag: None,
         }
     }
 }
@@ -69,6 +180,9 @@ impl<T: Data> Window<T> {
     }
 
     pub(crate) fn focus_chain(&self) -> &[WidgetId] {
+        if self.root.state().is_disabled() {
+            return &[];
+        }
         &self.root.state().focus_chain
     }
 
@@ -76,24 +190,260 @@ impl<T: Data> Window<T> {
         self.root.state().children.contains(&widget_id)
     }
 
+    /// The scale of the content, as set with [`SET_USER_SCALE`].
+    ///
+    /// [`SET_USER_SCALE`]: commands/constant.SET_USER_SCALE.html
+    #[cfg(test)]
+    pub(crate) fn user_scale(&self) -> f64 {
+        self.user_scale
+    }
+
+    /// The number of physical pixels in a point of content, taking both the
+    /// platform's scale and the content scale into account.
+    fn content_scale(&self) -> Scale {
+        Scale::new(
+            self.scale.x() * self.user_scale,
+            self.scale.y() * self.user_scale,
+        )
+    }
+
+    /// The size of the content, before it is scaled.
+    fn content_size(&self) -> Size {
+        Size::new(
+            self.size.width / self.user_scale,
+            self.size.height / self.user_scale,
+        )
+    }
+
+    /// The new content scale for a zoom command, if this is one.
+    fn scale_for_command(&self, event: &Event) -> Option<f64> {
+        let cmd = match event {
+            Event::Command(cmd) | Event::TargetedCommand(Target::Window(_), cmd) => cmd,
+            _ => return None,
+        };
+        let scale = match cmd.selector {
+            commands::SET_USER_SCALE => match cmd.get_object::<f64>() {
+                Ok(scale) => *scale,
+                Err(e) => {
+                    log::error!("set-user-scale object error: '{}'", e);
+                    return None;
+                }
+            },
+            commands::ZOOM_IN => self.user_scale * ZOOM_STEP,
+            commands::ZOOM_OUT => self.user_scale / ZOOM_STEP,
+            commands::ZOOM_RESET => 1.0,
+            _ => return None,
+        };
+        Some(scale.max(MIN_USER_SCALE).min(MAX_USER_SCALE))
+    }
+
+    /// The color the window is cleared to: the one set by command, or else
+    /// the one in the `WindowDesc`, or else the one in the `Env`.
+    pub(crate) fn background_color(&self, env: &Env) -> Color {
+        self.background
+            .as_ref()
+            .or_else(|| self.desc_background.as_ref())
+            .cloned()
+            .unwrap_or_else(|| env.get(crate::theme::WINDOW_BACKGROUND_COLOR))
+    }
+
+    /// Give the platform window our background color, if it has changed.
+    fn update_background(&mut self, env: &Env) {
+        let color = self.background_color(env);
+        let changed = match &self.platform_background {
+            Some(old) => !old.same(&color),
+            None => true,
+        };
+        if changed {
+            self.handle.set_background_color(color.clone());
+            self.platform_background = Some(color);
+        }
+    }
+
+    /// Handle `SET_BACKGROUND_COLOR`, returning `true` if this is one.
+    fn set_background_for_command(&mut self, event: &Event, env: &Env) -> bool {
+        let cmd = match event {
+            Event::Command(cmd) | Event::TargetedCommand(Target::Window(_), cmd) => cmd,
+            _ => return false,
+        };
+        if cmd.selector != commands::SET_BACKGROUND_COLOR {
+            return false;
+        }
+        match cmd.get_object::<Option<Color>>() {
+            Ok(color) => {
+                self.background = color.clone();
+                self.update_background(env);
+                self.handle.invalidate();
+            }
+            Err(e) => log::error!("set-background-color object error: '{}'", e),
+        }
+        true
+    }
+
+    /// Handle `SET_WINDOW_TITLE`, returning `true` if this is one.
+    fn set_title_for_command(&mut self, event: &Event, data: &T, env: &Env) -> bool {
+        let cmd = match event {
+            Event::Command(cmd) | Event::TargetedCommand(Target::Window(_), cmd) => cmd,
+            _ => return false,
+        };
+        if cmd.selector != commands::SET_WINDOW_TITLE {
+            return false;
+        }
+        match cmd.get_object::<String>() {
+            Ok(title) => {
+                self.title = LabelText::Specific(title.clone());
+                self.update_title(data, env);
+            }
+            Err(e) => log::error!("set-window-title object error: '{}'", e),
+        }
+        true
+    }
+
+    /// Handle a [`CAPTURE_WINDOW`] command, if this is one.
+    ///
+    /// [`CAPTURE_WINDOW`]: commands/constant.CAPTURE_WINDOW.html
+    fn capture_for_command(&self, event: &Event, queue: &mut CommandQueue) -> bool {
+        let cmd = match event {
+            Event::Command(cmd) | Event::TargetedCommand(Target::Window(_), cmd) => cmd,
+            _ => return false,
+        };
+        if cmd.selector != commands::CAPTURE_WINDOW {
+            return false;
+        }
+        let request = match cmd.get_object::<CaptureRequest>() {
+            Ok(request) => request.clone(),
+            Err(e) => {
+                log::error!("capture-window object error: '{}'", e);
+                return true;
+            }
+        };
+        match self.handle.capture_image() {
+            Ok(image) => {
+                let image = ImageBuf::from_raw(
+                    image.pixels,
+                    ImageFormat::RgbaPremul,
+                    image.width,
+                    image.height,
+                );
+                queue.push_back((request.target, Command::new(request.reply, image)));
+            }
+            Err(e) => log::error!("failed to capture the window: {}", e),
+        }
+        true
+    }
+
+    /// Handle a [`START_DRAG`] command, if this is one.
+    ///
+    /// [`START_DRAG`]: constant.START_DRAG.html
+    fn start_drag_for_command(&mut self, event: &Event) -> bool {
+        let cmd = match event {
+            Event::Command(cmd) | Event::TargetedCommand(Target::Window(_), cmd) => cmd,
+            _ => return false,
+        };
+        if cmd.selector != START_DRAG {
+            return false;
+        }
+        match cmd.get_object::<DragStart>() {
+            Ok(start) => {
+                self.drag = Some(DragSession {
+                    payload: start.payload.clone(),
+                    source: start.source,
+                });
+            }
+            Err(e) => log::error!("start-drag object error: '{}'", e),
+        }
+        true
+    }
+
+    /// Move a drag event from window points into content coordinates.
+    fn unscale_drag(&mut self, mut drag: DragEvent) -> DragEvent {
+        drag.pos = (drag.pos.to_vec2() / self.user_scale).to_point();
+        drag.window_pos = (drag.window_pos.to_vec2() / self.user_scale).to_point();
+        self.last_mouse_pos = Some(drag.window_pos);
+        drag
+    }
+
+    /// Move the drag that a widget started along with the mouse, dropping it
+    /// when the button is released and cancelling it on escape.
+    fn continue_drag(&mut self, queue: &mut CommandQueue, event: &Event, data: &mut T, env: &Env) {
+        // the drag events are unscaled again, like those from the platform
+        let scale = self.user_scale;
+        let drag = |session: &DragSession, mouse: &MouseEvent| {
+            let pos = (mouse.window_pos.to_vec2() * scale).to_point();
+            DragEvent {
+                pos,
+                window_pos: pos,
+                mods: mouse.mods,
+                payload: session.payload.clone(),
+            }
+        };
+        match event {
+            Event::MouseMoved(mouse) => {
+                let event = match self.drag.as_ref() {
+                    Some(session) => Event::DragOver(drag(session, mouse)),
+                    None => return,
+                };
+                let accepted = self.event(queue, event, data, env);
+                self.set_cursor(if accepted {
+                    Cursor::Arrow
+                } else {
+                    Cursor::NotAllowed
+                });
+            }
+            Event::MouseUp(mouse) => {
+                let session = match self.drag.take() {
+                    Some(session) => session,
+                    None => return,
+                };
+                let accepted = self.event(queue, Event::Drop(drag(&session, mouse)), data, env);
+                let finished = Command::new(commands::DRAG_FINISHED, accepted);
+                queue.push_back((Target::Widget(session.source), finished));
+            }
+            Event::KeyDown(key) if key.key == KbKey::Escape => {
+                let session = match self.drag.take() {
+                    Some(session) => session,
+                    None => return,
+                };
+                self.event(queue, Event::DragLeave, data, env);
+                let finished = Command::new(commands::DRAG_FINISHED, false);
+                queue.push_back((Target::Widget(session.source), finished));
+            }
+            _ => (),
+        }
+    }
+
+    /// Move a mouse event from window points into content coordinates.
+    fn unscale_mouse(&mut self, mut mouse: MouseEvent) -> MouseEvent {
+        mouse.pos = (mouse.pos.to_vec2() / self.user_scale).to_point();
+        mouse.window_pos = (mouse.window_pos.to_vec2() / self.user_scale).to_point();
+        self.last_mouse_pos = Some(mouse.window_pos);
+        mouse
+    }
+
     pub(crate) fn set_menu(&mut self, mut menu: MenuDesc<T>, data: &T, env: &Env) {
         let platform_menu = menu.build_window_menu(data, env);
         self.handle.set_menu(platform_menu);
         self.menu = Some(menu);
     }
 
-    pub(crate) fn show_context_menu(
-        &mut self,
-        mut menu: MenuDesc<T>,
-        point: Point,
-        data: &T,
-        env: &Env,
-    ) {
-        let platform_menu = menu.build_popup_menu(data, env);
-        self.handle.show_context_menu(platform_menu, point);
+    pub(crate) fn show_context_menu(&mut self, mut menu: ContextMenu<T>, data: &T, env: &Env) {
+        let platform_menu = menu.menu.build_popup_menu(data, env);
+        let location = (menu.location.to_vec2() * self.user_scale).to_point();
+        self.handle.show_context_menu(platform_menu, location);
         self.context_menu = Some(menu);
     }
 
+    /// Build the menu again if the titles or states of its items changed
+    /// with the data.
+    fn update_menu(&mut self, data: &T, env: &Env) {
+        if let Some(menu) = self.menu.as_mut() {
+            if menu.update_state(data, env) {
+                let platform_menu = menu.build_window_menu(data, env);
+                self.handle.set_menu(platform_menu);
+            }
+        }
+    }
+
     /// On macos we need to update the global application menu to be the menu
     /// for the current window.
     #[cfg(target_os = "macos")]
@@ -110,8 +460,9 @@ impl<T: Data> Window<T> {
         data: &mut T,
         env: &Env,
     ) -> bool {
-        let mut cursor = match event {
-            Event::MouseMoved(..) => Some(Cursor::Arrow),
+        // kept so that a release can be replayed as a move, below
+        let released = match &event {
+            Event::MouseUp(mouse) => Some(mouse.clone()),
             _ => None,
         };
 
@@ -119,53 +470,186 @@ impl<T: Data> Window<T> {
             Event::Size(size) => {
                 let dpi = f64::from(self.handle.get_dpi());
                 let scale = 96.0 / dpi;
-                self.size = Size::new(size.width * scale, size.height * scale);
-                Event::Size(self.size)
+                let size = Size::new(size.width * scale, size.height * scale);
+                // the platform should keep the window from getting any smaller,
+                // but we don't rely on it
+                self.size = match self.min_size {
+                    Some(min) => Size::new(size.width.max(min.width), size.height.max(min.height)),
+                    None => size,
+                };
+                Event::Size(self.content_size())
+            }
+            Event::WindowScale(scale) => {
+                self.scale = scale;
+                Event::WindowScale(self.content_scale())
+            }
+            Event::MouseDown(mouse) => Event::MouseDown(self.unscale_mouse(mouse)),
+            Event::MouseUp(mouse) => Event::MouseUp(self.unscale_mouse(mouse)),
+            Event::MouseMoved(mouse) => Event::MouseMoved(self.unscale_mouse(mouse)),
+            Event::DragEnter(drag) => Event::DragEnter(self.unscale_drag(drag)),
+            Event::DragOver(drag) => Event::DragOver(self.unscale_drag(drag)),
+            Event::Drop(drag) => Event::Drop(self.unscale_drag(drag)),
+            Event::Wheel(mut wheel) => {
+                let pos = self.last_mouse_pos.unwrap_or(Point::ZERO);
+                wheel.window_anchor = pos;
+                wheel.local_anchor = pos;
+                Event::Wheel(wheel)
+            }
+            Event::Zoom(mut zoom) => {
+                let pos = self.last_mouse_pos.unwrap_or(Point::ZERO);
+                zoom.window_anchor = pos;
+                zoom.local_anchor = pos;
+                Event::Zoom(zoom)
             }
             other => other,
         };
 
+        // Changing the content scale lays it out again, as if the window had
+        // been resized.
+        let event = match self.scale_for_command(&event) {
+            Some(scale) => {
+                self.user_scale = scale;
+                Event::Size(self.content_size())
+            }
+            None => event,
+        };
+
+        if self.set_background_for_command(&event, env) {
+            return true;
+        }
+
+        if self.set_title_for_command(&event, data, env) {
+            return true;
+        }
+
+        if self.capture_for_command(&event, queue) {
+            return true;
+        }
+
+        if self.start_drag_for_command(&event) {
+            return true;
+        }
+
         if let Event::WindowConnected = event {
             self.lifecycle(queue, &LifeCycle::RouteWidgetAdded, data, env);
         }
 
+        // the platform window no longer has the mouse, so it isn't released
+        // once the active widgets are
+        if let Event::MouseCaptureLost = event {
+            self.mouse_captured = false;
+        }
+
+        let had_active = self.root.has_active();
         let mut base_state = BaseState::new(self.root.id());
+        // notifications that no widget handled end here
+        let mut notifications = VecDeque::new();
         let is_handled = {
             let mut ctx = EventCtx {
-                cursor: &mut cursor,
                 command_queue: queue,
+                notifications: &mut notifications,
                 base_state: &mut base_state,
                 is_handled: false,
                 is_root: true,
-                had_active: self.root.has_active(),
+                had_active,
                 window: &self.handle,
                 window_id: self.id,
+                clipboard: &self.clipboard,
                 focus_widget: self.focus,
+                window_origin: Point::ORIGIN,
+                user_scale: self.user_scale,
             };
 
             self.root.event(&mut ctx, &event, data, env);
             ctx.is_handled
         };
 
+        // If nobody handled a tab key press, move focus to the next
+        // (or, with shift, the previous) widget in the focus chain.
+        if !is_handled && base_state.request_focus.is_none() {
+            if let Event::KeyDown(key) = &event {
+                if HotKey::new(SysMods::None, KbKey::Tab).matches(key) {
+                    base_state.request_focus = Some(FocusChange::Next);
+                } else if HotKey::new(SysMods::Shift, KbKey::Tab).matches(key) {
+                    base_state.request_focus = Some(FocusChange::Previous);
+                }
+            }
+        }
+
+        // Likewise for the zoom shortcuts; any other key is offered to the
+        // whole window, for default buttons and mnemonics.
+        if !is_handled {
+            if let Event::KeyDown(key) = &event {
+                if let Some(zoom) = zoom_for_key(key) {
+                    queue.push_back((self.id.into(), zoom.into()));
+                } else if base_state.request_focus.is_none() {
+                    let cmd = Command::new(commands::UNHANDLED_KEY, key.clone());
+                    queue.push_back((self.id.into(), cmd));
+                }
+            }
+        }
+
         if let Some(focus_req) = base_state.request_focus.take() {
             let old = self.focus;
-            let new = self.widget_for_focus_request(focus_req);
+            let new = self.widget_for_focus_request(focus_req, queue, data, env);
             let event = LifeCycle::RouteFocusChanged { old, new };
             self.lifecycle(queue, &event, data, env);
             self.focus = new;
         }
 
-        if let Some(cursor) = cursor {
-            self.handle.set_cursor(&cursor);
+        // the cursor of the active widget, or else of the innermost hot one
+        // that has one; other events only change it if a widget asks.
+        match base_state.cursor.take() {
+            Some(cursor) => self.set_cursor(cursor),
+            None if event.is_pointer_event() => self.set_cursor(Cursor::Arrow),
+            None => (),
         }
 
+        self.route_disabled_changed(queue, data, env);
+
         // If children are changed during the handling of an event,
         // we need to send RouteWidgetAdded now, so that they are ready for update/layout.
-        if base_state.children_changed {
+        if base_state.children_changed || self.root.state().children_changed {
             self.lifecycle(queue, &LifeCycle::RouteWidgetAdded, data, env);
         }
 
-        is_handled
+        self.continue_drag(queue, &event, data, env);
+
+        // the mouse is captured while a widget is active, so that it sees
+        // the button released even if that is outside of the window
+        let has_active = self.root.has_active();
+        if has_active != self.mouse_captured {
+            self.handle.set_mouse_capture(has_active);
+            self.mouse_captured = has_active;
+        }
+
+        // Releasing the active widget ends its capture of the mouse; the
+        // widgets under the mouse become hot as if it had just moved there.
+        if let Some(mouse) = released {
+            if had_active && !self.root.has_active() {
+                self.event(queue, Event::MouseMoved(mouse), data, env);
+            }
+        }
+
+        // for a drag, what the platform wants to know is whether it can be
+        // dropped where it is
+        match event {
+            Event::DragEnter(_) | Event::DragOver(_) | Event::Drop(_) => base_state.accepts_drop,
+            _ => is_handled,
+        }
+    }
+
+    fn set_cursor(&mut self, cursor: Cursor) {
+        if self.cursor.as_ref() != Some(&cursor) {
+            self.handle.set_cursor(&cursor);
+            self.cursor = Some(cursor);
+        }
+    }
+
+    /// The cursor that the window is showing, if it has set one.
+    #[cfg(test)]
+    pub(crate) fn cursor(&self) -> Option<&Cursor> {
+        self.cursor.as_ref()
     }
 
     pub(crate) fn lifecycle(
@@ -206,38 +690,115 @@ impl<T: Data> Window<T> {
         }
     }
 
-    pub(crate) fn update(&mut self, data: &T, env: &Env) {
+    /// only expose an `AnimFrame` with a given interval for testing; normally
+    /// the interval is measured as part of `do_paint`
+    #[cfg(test)]
+    pub(crate) fn just_anim_frame(
+        &mut self,
+        queue: &mut CommandQueue,
+        interval: u64,
+        data: &T,
+        env: &Env,
+    ) {
+        let mut base_state = BaseState::new(self.root.id());
+        let mut ctx = LifeCycleCtx {
+            command_queue: queue,
+            window_id: self.id,
+            base_state: &mut base_state,
+        };
+        let event = LifeCycle::AnimFrame(interval);
+        self.root.lifecycle(&mut ctx, &event, data, env);
+    }
+
+    pub(crate) fn update(&mut self, queue: &mut CommandQueue, data: &T, env: &Env) {
         self.update_title(data, env);
+        self.update_background(env);
+        self.update_menu(data, env);
 
         let mut base_state = BaseState::new(self.root.id());
         let mut update_ctx = UpdateCtx {
+            command_queue: queue,
             base_state: &mut base_state,
             window: &self.handle,
             window_id: self.id,
+            prev_env: None,
+            env,
+            force_update: false,
         };
 
         self.root.update(&mut update_ctx, data, env);
     }
 
+    /// Send `DisabledChanged` to the widgets whose disabled state changed.
+    ///
+    /// This can change the focus chain, so it comes before children are
+    /// registered again.
+    fn route_disabled_changed(&mut self, queue: &mut CommandQueue, data: &T, env: &Env) {
+        if self.root.state().disabled_changed {
+            self.lifecycle(queue, &LifeCycle::RouteDisabledChanged, data, env);
+        }
+    }
+
     pub(crate) fn invalidate_and_finalize(
         &mut self,
         queue: &mut CommandQueue,
         data: &T,
         env: &Env,
     ) {
+        self.route_disabled_changed(queue, data, env);
         if self.root.state().children_changed {
             self.lifecycle(queue, &LifeCycle::RouteWidgetAdded, data, env);
         }
-        if self.root.state().needs_inval {
+        // layout can move anything, so the whole window is painted again
+        if self.root.state().needs_layout {
             self.handle.invalidate();
+        } else {
+            for rect in self.root.state().invalid.rects() {
+                let rect = Affine::scale(self.user_scale).transform_rect_bbox(*rect);
+                self.handle.invalidate_rect(rect);
+            }
+        }
+        if self.root.state().request_idle && !self.idle_scheduled {
+            if let Some(mut handle) = self.handle.get_idle_handle() {
+                handle.schedule_idle(WIDGET_IDLE_TOKEN);
+                self.idle_scheduled = true;
+            } else {
+                log::error!("failed to get idle handle");
+            }
+        }
+    }
+
+    /// Send `WindowDisconnected` to the widgets, unless it has been sent.
+    ///
+    /// Returns `true` if it was sent.
+    pub(crate) fn disconnect(&mut self, queue: &mut CommandQueue, data: &mut T, env: &Env) -> bool {
+        if self.disconnected {
+            return false;
+        }
+        self.disconnected = true;
+        self.event(queue, Event::WindowDisconnected, data, env);
+        true
+    }
+
+    /// Send the idle events that widgets have requested.
+    ///
+    /// Each requesting widget gets one event, with its own token.
+    pub(crate) fn idle(&mut self, queue: &mut CommandQueue, data: &mut T, env: &Env) {
+        self.idle_scheduled = false;
+        if self.root.state().request_idle {
+            self.event(queue, Event::Idle(WIDGET_IDLE_TOKEN), data, env);
         }
     }
 
     /// Do all the stuff we do in response to a paint call from the system:
     /// layout, send an `AnimFrame` event, and then actually paint.
+    ///
+    /// Only the widgets that intersect `invalid_rect`, which is in display
+    /// points, are painted.
     pub(crate) fn do_paint(
         &mut self,
         piet: &mut Piet,
+        invalid_rect: Rect,
         queue: &mut CommandQueue,
         data: &T,
         env: &Env,
@@ -246,15 +807,32 @@ impl<T: Data> Window<T> {
         self.lifecycle(queue, &LifeCycle::AnimFrame(0), data, env);
 
         if self.root.state().needs_layout {
-            self.layout(piet, data, env);
+            self.layout(piet, queue, data, env);
+        }
+        // an animation that started in layout has its first frame next
+        if self.root.state().request_anim && self.last_anim.is_none() {
+            self.last_anim = Some(Instant::now());
+        }
+        if self.access_active {
+            self.update_access_tree(queue, data, env);
         }
 
-        piet.clear(env.get(crate::theme::WINDOW_BACKGROUND_COLOR));
-        self.paint(piet, data, env);
+        if let Err(e) = piet.save() {
+            log::error!("saving render context failed: {:?}", e);
+            return;
+        }
+        piet.clip(invalid_rect);
+        piet.clear(self.background_color(env));
+        let region = Affine::scale(self.user_scale.recip()).transform_rect_bbox(invalid_rect);
+        self.paint(piet, region.into(), data, env);
+        if let Err(e) = piet.restore() {
+            log::error!("restoring render context failed: {:?}", e);
+        }
 
-        // If commands were submitted during anim frame, ask the handler
-        // to call us back on idle so we can process them in a new event/update pass.
-        if !queue.is_empty() {
+        // If commands were submitted during anim frame, or a widget requested
+        // an update, ask the handler to call us back on idle so we can process
+        // them in a new event/update pass.
+        if !queue.is_empty() || self.root.state().request_update {
             if let Some(mut handle) = self.handle.get_idle_handle() {
                 handle.schedule_idle(RUN_COMMANDS_TOKEN);
             } else {
@@ -263,26 +841,80 @@ impl<T: Data> Window<T> {
         }
     }
 
-    fn layout(&mut self, piet: &mut Piet, data: &T, env: &Env) {
+    fn layout(&mut self, piet: &mut Piet, queue: &mut CommandQueue, data: &T, env: &Env) {
+        self.layout_pass(piet, queue, data, env);
+        // widgets that add children once they know their size, as a
+        // `VirtualList` does, are laid out again with them before painting
+        if self.root.state().children_changed {
+            self.lifecycle(queue, &LifeCycle::RouteWidgetAdded, data, env);
+            self.layout_pass(piet, queue, data, env);
+        }
+    }
+
+    fn layout_pass(&mut self, piet: &mut Piet, queue: &mut CommandQueue, data: &T, env: &Env) {
         let mut layout_ctx = LayoutCtx {
+            command_queue: queue,
             text_factory: piet.text(),
             window_id: self.id,
+            widget_id: self.root.id(),
             paint_insets: Insets::ZERO,
+            baseline_offset: 0.0,
+            deadline: self.layout_budget.map(|budget| Instant::now() + budget),
+            visible: Rect::from_origin_size(Point::ORIGIN, self.content_size()),
+            child_origin: None,
+            window_origin: Point::ORIGIN,
+            mouse_pos: self.last_mouse_pos,
+            has_active: false,
+            deferred: false,
+            request_anim: false,
+            scale: self.content_scale(),
         };
-        let bc = BoxConstraints::tight(self.size);
+        let bc = BoxConstraints::tight(self.content_size());
         let size = self.root.layout(&mut layout_ctx, &bc, data, env);
-        self.root
-            .set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+        self.root.set_layout_rect(
+            &mut layout_ctx,
+            data,
+            env,
+            Rect::from_origin_size(Point::ORIGIN, size),
+        );
+        let deferred = layout_ctx.deferred;
+        self.lifecycle(queue, &LifeCycle::RouteSize, data, env);
+        self.lifecycle(queue, &LifeCycle::RouteWindowOrigin, data, env);
+        // the rest of the deferred widgets are laid out in the next frame
+        if deferred {
+            self.handle.invalidate();
+        }
     }
 
     /// only expose `layout` for testing; normally it is called as part of `do_paint`
     #[cfg(test)]
-    pub(crate) fn just_layout(&mut self, piet: &mut Piet, data: &T, env: &Env) {
-        self.layout(piet, data, env)
+    pub(crate) fn just_layout(
+        &mut self,
+        piet: &mut Piet,
+        queue: &mut CommandQueue,
+        data: &T,
+        env: &Env,
+    ) {
+        self.layout(piet, queue, data, env)
     }
 
-    fn paint(&mut self, piet: &mut Piet, data: &T, env: &Env) {
+    fn paint(&mut self, piet: &mut Piet, region: Region, data: &T, env: &Env) {
+        if let Err(e) = piet.save() {
+            log::error!("saving render context failed: {:?}", e);
+            return;
+        }
+        piet.transform(Affine::scale(self.user_scale));
+        self.paint_content(piet, region, data, env);
+        if let Err(e) = piet.restore() {
+            log::error!("restoring render context failed: {:?}", e);
+        }
+    }
+
+    /// Paint the widgets that intersect `region`, which is in the
+    /// coordinate space of the content.
+    fn paint_content(&mut self, piet: &mut Piet, mut region: Region, data: &T, env: &Env) {
         let base_state = BaseState::new(self.root.id());
+        region.intersect_with(Rect::from_origin_size(Point::ZERO, self.content_size()));
         let mut paint_ctx = PaintCtx {
             render_ctx: piet,
             base_state: &base_state,
@@ -290,15 +922,18 @@ impl<T: Data> Window<T> {
             z_ops: Vec::new(),
             focus_widget: self.focus,
             region: Rect::ZERO.into(),
+            scale: self.content_scale(),
+            window: &self.handle,
+            native_views: Vec::new(),
         };
-        let visible = Rect::from_origin_size(Point::ZERO, self.size);
-        paint_ctx.with_child_ctx(visible, |ctx| self.root.paint(ctx, data, env));
+        let root = &mut self.root;
+        paint_ctx.with_child_ctx(region.clone(), |ctx| root.paint(ctx, data, env));
 
         let mut z_ops = mem::take(&mut paint_ctx.z_ops);
         z_ops.sort_by_key(|k| k.z_index);
 
         for z_op in z_ops.into_iter() {
-            paint_ctx.with_child_ctx(visible, |ctx| {
+            paint_ctx.with_child_ctx(region.clone(), |ctx| {
                 if let Err(e) = ctx.render_ctx.save() {
                     log::error!("saving render context failed: {:?}", e);
                     return;
@@ -312,44 +947,178 @@ impl<T: Data> Window<T> {
                 }
             });
         }
+
+        // the views in the region that were not painted this time are
+        // hidden, and those outside of it are left where they are
+        let mut painted = mem::take(&mut paint_ctx.native_views);
+        let to_content = Affine::scale(self.user_scale.recip());
+        for old in &self.native_views {
+            if !painted.iter().any(|view| view.ptr_eq(old)) {
+                if let Some(view) = old.upgrade() {
+                    let mut view = view.borrow_mut();
+                    match view.shown_frame() {
+                        Some(frame)
+                            if !region.intersects(to_content.transform_rect_bbox(frame)) =>
+                        {
+                            painted.push(old.clone())
+                        }
+                        _ => view.hide(),
+                    }
+                }
+            }
+        }
+        self.native_views = painted;
     }
 
+    /// Resolve the title, and give it to the platform window if its text
+    /// changed.
     pub(crate) fn update_title(&mut self, data: &T, env: &Env) {
-        if self.title.resolve(data, env) {
-            self.handle.set_title(self.title.localized_str());
+        self.title.resolve(data, env);
+        let platform_title = &self.platform_title;
+        let changed = self
+            .title
+            .with_display_text(|title| platform_title.as_deref() != Some(title));
+        if changed {
+            let title = self.title.with_display_text(|s| s.to_string());
+            self.handle.set_title(&title);
+            self.platform_title = Some(title);
+        }
+    }
+
+    /// Start keeping the accessibility tree up to date, as a screen reader
+    /// has asked for it.
+    pub(crate) fn accessibility_activated(&mut self) {
+        self.access_active = true;
+        self.handle.invalidate();
+    }
+
+    /// Build the accessibility tree from the widgets as they are now.
+    pub(crate) fn build_access_tree(
+        &mut self,
+        queue: &mut CommandQueue,
+        data: &T,
+        env: &Env,
+    ) -> AccessNode {
+        let builder = AccessTreeBuilder::new();
+        let event = LifeCycle::RouteAccessTree(builder.clone());
+        self.lifecycle(queue, &event, data, env);
+        let title = self.title.with_display_text(|s| s.to_string());
+        builder.build(Some(title), self.size, self.user_scale, self.focus)
+    }
+
+    /// Give the platform window the accessibility tree, if it changed.
+    fn update_access_tree(&mut self, queue: &mut CommandQueue, data: &T, env: &Env) {
+        let tree = self.build_access_tree(queue, data, env);
+        let events = access::diff(self.access_tree.as_ref(), &tree);
+        if !events.is_empty() {
+            self.handle.update_access_tree(tree.clone(), &events);
         }
+        self.access_tree = Some(tree);
     }
 
-    pub(crate) fn get_menu_cmd(&self, cmd_id: u32) -> Option<Command> {
-        self.context_menu
+    /// The command for a menu item, along with where it should be sent.
+    ///
+    /// Items in a context menu that was shown by a widget are sent to that
+    /// widget; other items are sent to the window.
+    pub(crate) fn get_menu_cmd(&self, cmd_id: u32) -> Option<(Target, Command)> {
+        if let Some(menu) = &self.context_menu {
+            if let Some(cmd) = menu.menu.command_for_id(cmd_id) {
+                let target = menu.target.map(Target::Widget);
+                return Some((target.unwrap_or_else(|| self.id.into()), cmd));
+            }
+        }
+        self.menu
             .as_ref()
             .and_then(|m| m.command_for_id(cmd_id))
-            .or_else(|| self.menu.as_ref().and_then(|m| m.command_for_id(cmd_id)))
+            .map(|cmd| (self.id.into(), cmd))
     }
 
-    fn widget_for_focus_request(&self, focus: FocusChange) -> Option<WidgetId> {
+    /// The position of the currently focused widget in the focus chain.
+    ///
+    /// A focused widget that didn't register for focus isn't in the chain, so
+    /// its position is found from where it is in the widget tree.
+    fn focus_position(&mut self, queue: &mut CommandQueue, data: &T, env: &Env) -> ChainPosition {
+        let focus = match self.focus {
+            Some(focus) => focus,
+            None => return ChainPosition::Outside,
+        };
+        if let Some(idx) = self.focus_chain().iter().position(|id| *id == focus) {
+            return ChainPosition::At(idx);
+        }
+        let search = FocusAnchor::new(focus);
+        let event = LifeCycle::RouteFocusAnchor(search.clone());
+        self.lifecycle(queue, &event, data, env);
+        match search.result() {
+            Some(Some(anchor)) => match self.focus_chain().iter().position(|id| *id == anchor) {
+                Some(idx) => ChainPosition::After(idx),
+                None => ChainPosition::Outside,
+            },
+            // either no registered widget comes before it, or it is gone
+            _ => ChainPosition::Outside,
+        }
+    }
+
+    fn widget_for_focus_request(
+        &mut self,
+        focus: FocusChange,
+        queue: &mut CommandQueue,
+        data: &T,
+        env: &Env,
+    ) -> Option<WidgetId> {
         match focus {
             FocusChange::Resign => None,
             FocusChange::Focus(id) => Some(id),
-            FocusChange::Next => self
-                .focus
-                .and_then(|id| self.focus_chain().iter().position(|i| i == &id))
-                .map(|idx| {
+            FocusChange::Next => match self.focus_position(queue, data, env) {
+                ChainPosition::At(idx) | ChainPosition::After(idx) => {
                     let next_idx = (idx + 1) % self.focus_chain().len();
-                    self.focus_chain()[next_idx]
-                }),
-            FocusChange::Previous => self
-                .focus
-                .and_then(|id| self.focus_chain().iter().position(|i| i == &id))
-                .map(|idx| {
+                    Some(self.focus_chain()[next_idx])
+                }
+                // with nothing focused, start at the beginning of the chain
+                ChainPosition::Outside => self.focus_chain().first().copied(),
+            },
+            FocusChange::Previous => match self.focus_position(queue, data, env) {
+                ChainPosition::At(idx) => {
                     let len = self.focus_chain().len();
                     let prev_idx = (idx + len - 1) % len;
-                    self.focus_chain()[prev_idx]
-                }),
+                    Some(self.focus_chain()[prev_idx])
+                }
+                ChainPosition::After(idx) => Some(self.focus_chain()[idx]),
+                ChainPosition::Outside => self.focus_chain().last().copied(),
+            },
         }
     }
 }
 
+/// Where the focused widget is, relative to the focus chain.
+enum ChainPosition {
+    /// The widget is in the chain, at this index.
+    At(usize),
+    /// The widget isn't in the chain, and comes after the widget at this index.
+    After(usize),
+    /// Nothing is focused, or the focused widget comes before the whole chain.
+    Outside,
+}
+
+/// The zoom command for one of the default zoom shortcuts, if `key` is one.
+fn zoom_for_key(key: &KeyEvent) -> Option<Selector> {
+    let matches = |mods: SysMods, codes: &[Code]| {
+        codes
+            .iter()
+            .any(|code| HotKey::new(mods, *code).matches(key))
+    };
+    if matches(SysMods::Cmd, &[Code::Equals, Code::NumpadAdd])
+        || matches(SysMods::CmdShift, &[Code::Equals])
+    {
+        Some(commands::ZOOM_IN)
+    } else if matches(SysMods::Cmd, &[Code::Minus, Code::NumpadSubtract]) {
+        Some(commands::ZOOM_OUT)
+    } else if matches(SysMods::Cmd, &[Code::Key0, Code::Numpad0]) {
+        Some(commands::ZOOM_RESET)
+    } else {
+        None
+    }
+}
+
 impl WindowId {
     /// Allocate a new, unique window id.
     pub fn next() -> WindowId {